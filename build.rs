@@ -0,0 +1,38 @@
+use std::process::Command;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+/// Stamps the build with the commit it came from and when it was built, for `/version` and
+/// `/health/ready`. A `GIT_COMMIT` set in the environment wins, for builds made outside a
+/// checkout such as in a container; `SOURCE_DATE_EPOCH` pins the build time for reproducible
+/// builds.
+fn main() {
+    // `sqlx::migrate!` embeds `migrations/`, so new files there need a rebuild
+    println!("cargo:rerun-if-changed=migrations");
+    println!("cargo:rerun-if-env-changed=GIT_COMMIT");
+    println!("cargo:rerun-if-changed=.git/HEAD");
+    println!("cargo:rerun-if-changed=.git/refs");
+    // The build time should follow source changes, not only commits
+    println!("cargo:rerun-if-changed=src");
+    println!("cargo:rerun-if-env-changed=SOURCE_DATE_EPOCH");
+
+    let built_at = std::env::var("SOURCE_DATE_EPOCH")
+        .ok()
+        .and_then(|epoch| epoch.parse::<u64>().ok())
+        .or_else(|| SystemTime::now().duration_since(UNIX_EPOCH).ok().map(|elapsed| elapsed.as_secs()));
+    if let Some(built_at) = built_at {
+        println!("cargo:rustc-env=BUILD_TIMESTAMP={}", built_at);
+    }
+
+    if std::env::var_os("GIT_COMMIT").is_some() {
+        return;
+    }
+    let commit = Command::new("git")
+        .args(["rev-parse", "--short", "HEAD"])
+        .output()
+        .ok()
+        .filter(|output| output.status.success())
+        .and_then(|output| String::from_utf8(output.stdout).ok());
+    if let Some(commit) = commit {
+        println!("cargo:rustc-env=GIT_COMMIT={}", commit.trim());
+    }
+}
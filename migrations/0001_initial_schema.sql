@@ -0,0 +1,693 @@
+-- Schema as of the move to versioned migrations. Every statement is idempotent, so it
+-- also runs cleanly against databases created before then.
+
+-- Users first, as nearly every other table references them
+CREATE TABLE IF NOT EXISTS users (
+    id TEXT PRIMARY KEY,
+    name TEXT NOT NULL,
+    email TEXT NOT NULL UNIQUE,
+    password_hash TEXT NOT NULL,
+    created_at DATETIME NOT NULL,
+    updated_at DATETIME NOT NULL,
+    email_verified_at DATETIME
+);
+
+CREATE TABLE IF NOT EXISTS accounts (
+    id TEXT PRIMARY KEY,
+    user_id TEXT NOT NULL,
+    name TEXT NOT NULL,
+    account_type TEXT NOT NULL,
+    balance REAL NOT NULL,
+    currency TEXT NOT NULL DEFAULT 'BDT',
+    credit_limit REAL,
+    created_at DATETIME NOT NULL,
+    updated_at DATETIME NOT NULL,
+    exclude_from_totals BOOLEAN NOT NULL DEFAULT FALSE,
+    statement_day INTEGER,
+    payment_due_day INTEGER,
+    utilization_alert_level INTEGER NOT NULL DEFAULT 0,
+    version INTEGER NOT NULL DEFAULT 1,
+    FOREIGN KEY (user_id) REFERENCES users(id) ON DELETE CASCADE
+);
+
+CREATE TABLE IF NOT EXISTS categories (
+    id TEXT PRIMARY KEY,
+    name TEXT NOT NULL,
+    category_type TEXT NOT NULL,
+    icon TEXT NOT NULL,
+    color TEXT NOT NULL,
+    is_default BOOLEAN NOT NULL DEFAULT FALSE,
+    created_at DATETIME NOT NULL,
+    user_id TEXT NOT NULL DEFAULT '',
+    updated_at DATETIME
+);
+
+CREATE TABLE IF NOT EXISTS transactions (
+    id TEXT PRIMARY KEY,
+    user_id TEXT NOT NULL,
+    account_id TEXT NOT NULL,
+    transaction_type TEXT NOT NULL,
+    amount REAL NOT NULL,
+    currency TEXT NOT NULL DEFAULT 'BDT',
+    category TEXT,
+    description TEXT,
+    date DATETIME NOT NULL,
+    created_at DATETIME NOT NULL,
+    exchange_rate REAL,
+    base_amount REAL,
+    base_currency TEXT,
+    version INTEGER NOT NULL DEFAULT 1,
+    FOREIGN KEY (user_id) REFERENCES users(id) ON DELETE CASCADE,
+    FOREIGN KEY (account_id) REFERENCES accounts(id) ON DELETE CASCADE
+);
+
+CREATE TABLE IF NOT EXISTS liabilities (
+    id TEXT PRIMARY KEY,
+    user_id TEXT NOT NULL,
+    person_name TEXT NOT NULL,
+    amount REAL NOT NULL,
+    currency TEXT NOT NULL DEFAULT 'BDT',
+    due_date DATETIME NOT NULL,
+    is_paid BOOLEAN NOT NULL DEFAULT FALSE,
+    description TEXT,
+    created_at DATETIME NOT NULL,
+    updated_at DATETIME NOT NULL,
+    is_historical_entry BOOLEAN NOT NULL DEFAULT FALSE,
+    account_id TEXT,
+    transaction_id TEXT,
+    FOREIGN KEY (user_id) REFERENCES users(id) ON DELETE CASCADE
+);
+
+CREATE TABLE IF NOT EXISTS loans (
+    id TEXT PRIMARY KEY,
+    user_id TEXT NOT NULL,
+    person_name TEXT NOT NULL,
+    amount REAL NOT NULL,
+    currency TEXT NOT NULL DEFAULT 'BDT',
+    loan_date DATETIME NOT NULL,
+    return_date DATETIME,
+    is_returned BOOLEAN NOT NULL DEFAULT FALSE,
+    description TEXT,
+    created_at DATETIME NOT NULL,
+    updated_at DATETIME NOT NULL,
+    is_historical_entry BOOLEAN NOT NULL DEFAULT FALSE,
+    account_id TEXT,
+    transaction_id TEXT,
+    FOREIGN KEY (user_id) REFERENCES users(id) ON DELETE CASCADE
+);
+
+CREATE TABLE IF NOT EXISTS savings_goals (
+    id TEXT PRIMARY KEY,
+    user_id TEXT NOT NULL,
+    name TEXT NOT NULL,
+    target_amount REAL NOT NULL,
+    current_amount REAL NOT NULL DEFAULT 0.0,
+    currency TEXT NOT NULL DEFAULT 'BDT',
+    target_date DATETIME NOT NULL,
+    description TEXT,
+    account_id TEXT,
+    priority TEXT NOT NULL DEFAULT 'medium',
+    is_completed BOOLEAN NOT NULL DEFAULT FALSE,
+    created_at DATETIME NOT NULL,
+    updated_at DATETIME NOT NULL,
+    version INTEGER NOT NULL DEFAULT 1,
+    FOREIGN KEY (user_id) REFERENCES users(id) ON DELETE CASCADE
+);
+
+CREATE TABLE IF NOT EXISTS budgets (
+    id TEXT PRIMARY KEY,
+    user_id TEXT NOT NULL,
+    category TEXT NOT NULL,
+    amount REAL NOT NULL,
+    currency TEXT NOT NULL DEFAULT 'BDT',
+    period TEXT NOT NULL DEFAULT 'monthly',
+    rollover BOOLEAN NOT NULL DEFAULT FALSE,
+    account_id TEXT,
+    created_at DATETIME NOT NULL,
+    updated_at DATETIME NOT NULL,
+    multi_currency BOOLEAN NOT NULL DEFAULT FALSE,
+    version INTEGER NOT NULL DEFAULT 1,
+    FOREIGN KEY (user_id) REFERENCES users(id) ON DELETE CASCADE
+);
+
+CREATE TABLE IF NOT EXISTS recurring_transactions (
+    id TEXT PRIMARY KEY,
+    user_id TEXT NOT NULL,
+    account_id TEXT NOT NULL,
+    transaction_type TEXT NOT NULL,
+    amount REAL NOT NULL,
+    currency TEXT NOT NULL DEFAULT 'BDT',
+    category TEXT,
+    description TEXT,
+    frequency TEXT NOT NULL DEFAULT 'monthly',
+    start_date DATETIME NOT NULL,
+    end_date DATETIME,
+    next_due_date DATETIME NOT NULL,
+    is_active BOOLEAN NOT NULL DEFAULT TRUE,
+    savings_goal_id TEXT,
+    created_at DATETIME NOT NULL,
+    updated_at DATETIME NOT NULL,
+    recurrence_interval INTEGER NOT NULL DEFAULT 1,
+    day_of_month INTEGER,
+    weekday TEXT,
+    version INTEGER NOT NULL DEFAULT 1,
+    FOREIGN KEY (user_id) REFERENCES users(id) ON DELETE CASCADE,
+    FOREIGN KEY (account_id) REFERENCES accounts(id) ON DELETE CASCADE
+);
+
+-- Indexes for per-user transaction reports and aggregate statistics
+CREATE INDEX IF NOT EXISTS idx_transactions_user_date ON transactions (user_id, date);
+CREATE INDEX IF NOT EXISTS idx_transactions_user_type_currency ON transactions (user_id, transaction_type, currency, amount);
+CREATE INDEX IF NOT EXISTS idx_transactions_user_category ON transactions (user_id, category);
+CREATE INDEX IF NOT EXISTS idx_accounts_user ON accounts (user_id);
+
+CREATE TABLE IF NOT EXISTS user_preferences (
+    user_id TEXT PRIMARY KEY,
+    display_currency TEXT NOT NULL DEFAULT 'BDT',
+    updated_at DATETIME NOT NULL,
+    session_idle_timeout_minutes INTEGER,
+    credit_utilization_thresholds TEXT,
+    FOREIGN KEY (user_id) REFERENCES users(id) ON DELETE CASCADE
+);
+
+-- Per-user keyword -> category frequency model
+CREATE TABLE IF NOT EXISTS category_keywords (
+    user_id TEXT NOT NULL,
+    keyword TEXT NOT NULL,
+    category TEXT NOT NULL,
+    transaction_type TEXT NOT NULL,
+    occurrences INTEGER NOT NULL DEFAULT 0,
+    updated_at DATETIME NOT NULL,
+    PRIMARY KEY (user_id, keyword, category, transaction_type),
+    FOREIGN KEY (user_id) REFERENCES users(id) ON DELETE CASCADE
+);
+
+-- Budgets spanning several categories
+CREATE TABLE IF NOT EXISTS budget_categories (
+    budget_id TEXT NOT NULL,
+    category TEXT NOT NULL,
+    PRIMARY KEY (budget_id, category),
+    FOREIGN KEY (budget_id) REFERENCES budgets(id) ON DELETE CASCADE
+);
+
+-- Materialized per-period budget limits for rollover
+CREATE TABLE IF NOT EXISTS budget_periods (
+    id TEXT PRIMARY KEY,
+    budget_id TEXT NOT NULL,
+    user_id TEXT NOT NULL,
+    period_start DATETIME NOT NULL,
+    period_end DATETIME NOT NULL,
+    base_amount REAL NOT NULL,
+    carried_over REAL NOT NULL DEFAULT 0.0,
+    adjusted_amount REAL NOT NULL,
+    spent REAL NOT NULL DEFAULT 0.0,
+    is_closed BOOLEAN NOT NULL DEFAULT FALSE,
+    closed_at DATETIME,
+    created_at DATETIME NOT NULL,
+    updated_at DATETIME NOT NULL,
+    UNIQUE (budget_id, period_start),
+    FOREIGN KEY (budget_id) REFERENCES budgets(id) ON DELETE CASCADE,
+    FOREIGN KEY (user_id) REFERENCES users(id) ON DELETE CASCADE
+);
+
+-- Repayment plans attached to loans or liabilities
+CREATE TABLE IF NOT EXISTS amortization_schedules (
+    id TEXT PRIMARY KEY,
+    user_id TEXT NOT NULL,
+    loan_id TEXT,
+    liability_id TEXT,
+    principal REAL NOT NULL,
+    annual_rate REAL NOT NULL,
+    term_months INTEGER NOT NULL,
+    payment REAL NOT NULL,
+    total_interest REAL NOT NULL,
+    start_date DATETIME NOT NULL,
+    created_at DATETIME NOT NULL,
+    FOREIGN KEY (user_id) REFERENCES users(id) ON DELETE CASCADE,
+    FOREIGN KEY (loan_id) REFERENCES loans(id) ON DELETE CASCADE,
+    FOREIGN KEY (liability_id) REFERENCES liabilities(id) ON DELETE CASCADE
+);
+
+CREATE TABLE IF NOT EXISTS amortization_entries (
+    schedule_id TEXT NOT NULL,
+    period INTEGER NOT NULL,
+    due_date DATETIME NOT NULL,
+    payment REAL NOT NULL,
+    principal REAL NOT NULL,
+    interest REAL NOT NULL,
+    remaining REAL NOT NULL,
+    actual_payment REAL,
+    paid_date DATETIME,
+    PRIMARY KEY (schedule_id, period),
+    FOREIGN KEY (schedule_id) REFERENCES amortization_schedules(id) ON DELETE CASCADE
+);
+
+-- Physical cash counts with reconciliation results
+CREATE TABLE IF NOT EXISTS cash_counts (
+    id TEXT PRIMARY KEY,
+    user_id TEXT NOT NULL,
+    account_id TEXT NOT NULL,
+    currency TEXT NOT NULL DEFAULT 'BDT',
+    total REAL NOT NULL,
+    previous_balance REAL NOT NULL,
+    difference REAL NOT NULL,
+    adjustment_transaction_id TEXT,
+    note TEXT,
+    counted_at DATETIME NOT NULL,
+    FOREIGN KEY (user_id) REFERENCES users(id) ON DELETE CASCADE,
+    FOREIGN KEY (account_id) REFERENCES accounts(id) ON DELETE CASCADE
+);
+
+-- Per-note breakdown of a cash count
+CREATE TABLE IF NOT EXISTS cash_count_denominations (
+    cash_count_id TEXT NOT NULL,
+    denomination REAL NOT NULL,
+    count INTEGER NOT NULL,
+    subtotal REAL NOT NULL,
+    PRIMARY KEY (cash_count_id, denomination),
+    FOREIGN KEY (cash_count_id) REFERENCES cash_counts(id) ON DELETE CASCADE
+);
+
+CREATE TABLE IF NOT EXISTS savings_goal_contributions (
+    id TEXT PRIMARY KEY,
+    user_id TEXT NOT NULL,
+    goal_id TEXT NOT NULL,
+    amount REAL NOT NULL,
+    contribution_date DATETIME NOT NULL,
+    note TEXT,
+    transaction_id TEXT,
+    source TEXT NOT NULL DEFAULT 'manual',
+    created_at DATETIME NOT NULL,
+    FOREIGN KEY (user_id) REFERENCES users(id) ON DELETE CASCADE,
+    FOREIGN KEY (goal_id) REFERENCES savings_goals(id) ON DELETE CASCADE
+);
+
+-- Append-only log of entity changes
+CREATE TABLE IF NOT EXISTS events (
+    sequence INTEGER PRIMARY KEY AUTOINCREMENT,
+    id TEXT NOT NULL UNIQUE,
+    user_id TEXT NOT NULL,
+    event_type TEXT NOT NULL,
+    entity_type TEXT NOT NULL,
+    entity_id TEXT NOT NULL,
+    data TEXT NOT NULL,
+    created_at DATETIME NOT NULL,
+    FOREIGN KEY (user_id) REFERENCES users(id) ON DELETE CASCADE
+);
+CREATE INDEX IF NOT EXISTS idx_events_user_sequence ON events (user_id, sequence);
+
+-- Per-user request counters, one row per day/endpoint/device
+CREATE TABLE IF NOT EXISTS api_usage (
+    user_id TEXT NOT NULL,
+    day TEXT NOT NULL,
+    method TEXT NOT NULL,
+    route TEXT NOT NULL,
+    device TEXT NOT NULL,
+    request_count INTEGER NOT NULL DEFAULT 0,
+    request_bytes INTEGER NOT NULL DEFAULT 0,
+    response_bytes INTEGER NOT NULL DEFAULT 0,
+    PRIMARY KEY (user_id, day, method, route, device),
+    FOREIGN KEY (user_id) REFERENCES users(id) ON DELETE CASCADE
+);
+
+-- Full snapshot of an entity after each change, for history/restore
+CREATE TABLE IF NOT EXISTS entity_versions (
+    id TEXT PRIMARY KEY,
+    user_id TEXT NOT NULL,
+    entity_type TEXT NOT NULL,
+    entity_id TEXT NOT NULL,
+    version INTEGER NOT NULL,
+    action TEXT NOT NULL,
+    snapshot TEXT,
+    device TEXT NOT NULL,
+    created_at DATETIME NOT NULL,
+    UNIQUE (entity_type, entity_id, version),
+    FOREIGN KEY (user_id) REFERENCES users(id) ON DELETE CASCADE
+);
+
+-- One row per issued token, for idle timeout and revocation
+CREATE TABLE IF NOT EXISTS sessions (
+    id TEXT PRIMARY KEY,
+    user_id TEXT NOT NULL,
+    device TEXT NOT NULL,
+    created_at DATETIME NOT NULL,
+    last_seen_at DATETIME NOT NULL,
+    revoked_at DATETIME,
+    revoked_reason TEXT,
+    reason_reported BOOLEAN NOT NULL DEFAULT FALSE,
+    FOREIGN KEY (user_id) REFERENCES users(id) ON DELETE CASCADE
+);
+CREATE INDEX IF NOT EXISTS idx_sessions_user ON sessions (user_id, revoked_at);
+
+-- Reminders and alerts queued by other modules
+CREATE TABLE IF NOT EXISTS notifications (
+    id TEXT PRIMARY KEY,
+    user_id TEXT NOT NULL,
+    kind TEXT NOT NULL,
+    title TEXT NOT NULL,
+    body TEXT NOT NULL,
+    entity_type TEXT,
+    entity_id TEXT,
+    dedupe_key TEXT,
+    read_at DATETIME,
+    created_at DATETIME NOT NULL,
+    pushed_at DATETIME,
+    UNIQUE (user_id, dedupe_key),
+    FOREIGN KEY (user_id) REFERENCES users(id) ON DELETE CASCADE
+);
+CREATE INDEX IF NOT EXISTS idx_notifications_user_created ON notifications (user_id, created_at);
+
+-- FCM/APNs tokens notifications are pushed to
+CREATE TABLE IF NOT EXISTS push_devices (
+    id TEXT PRIMARY KEY,
+    user_id TEXT NOT NULL,
+    platform TEXT NOT NULL,
+    token TEXT NOT NULL UNIQUE,
+    name TEXT NOT NULL,
+    created_at DATETIME NOT NULL,
+    last_used_at DATETIME NOT NULL,
+    FOREIGN KEY (user_id) REFERENCES users(id) ON DELETE CASCADE
+);
+
+-- Daily rates, 1 base = rate quote
+CREATE TABLE IF NOT EXISTS exchange_rates (
+    base_currency TEXT NOT NULL,
+    quote_currency TEXT NOT NULL,
+    rate_date TEXT NOT NULL,
+    rate REAL NOT NULL,
+    created_at DATETIME NOT NULL,
+    PRIMARY KEY (base_currency, quote_currency, rate_date)
+);
+
+-- Queued emails and their retry state
+CREATE TABLE IF NOT EXISTS email_outbox (
+    id TEXT PRIMARY KEY,
+    user_id TEXT,
+    to_address TEXT NOT NULL,
+    subject TEXT NOT NULL,
+    body TEXT NOT NULL,
+    attempts INTEGER NOT NULL DEFAULT 0,
+    next_attempt_at DATETIME NOT NULL,
+    last_error TEXT,
+    sent_at DATETIME,
+    created_at DATETIME NOT NULL,
+    FOREIGN KEY (user_id) REFERENCES users(id) ON DELETE CASCADE
+);
+CREATE INDEX IF NOT EXISTS idx_email_outbox_pending ON email_outbox (sent_at, next_attempt_at);
+
+-- Single-use email verification and password reset tokens
+CREATE TABLE IF NOT EXISTS email_tokens (
+    token TEXT PRIMARY KEY,
+    user_id TEXT NOT NULL,
+    purpose TEXT NOT NULL,
+    expires_at DATETIME NOT NULL,
+    used_at DATETIME,
+    created_at DATETIME NOT NULL,
+    FOREIGN KEY (user_id) REFERENCES users(id) ON DELETE CASCADE
+);
+
+-- User endpoints that receive signed event payloads
+CREATE TABLE IF NOT EXISTS webhooks (
+    id TEXT PRIMARY KEY,
+    user_id TEXT NOT NULL,
+    url TEXT NOT NULL,
+    secret TEXT NOT NULL,
+    event_types TEXT NOT NULL DEFAULT '[]',
+    schema_version INTEGER NOT NULL,
+    is_active BOOLEAN NOT NULL DEFAULT TRUE,
+    created_at DATETIME NOT NULL,
+    updated_at DATETIME NOT NULL,
+    FOREIGN KEY (user_id) REFERENCES users(id) ON DELETE CASCADE
+);
+
+-- Per-webhook delivery queue with retry state
+CREATE TABLE IF NOT EXISTS webhook_deliveries (
+    id TEXT PRIMARY KEY,
+    webhook_id TEXT NOT NULL,
+    event_id TEXT NOT NULL,
+    event_type TEXT NOT NULL,
+    attempts INTEGER NOT NULL DEFAULT 0,
+    last_status INTEGER,
+    last_error TEXT,
+    next_attempt_at DATETIME NOT NULL,
+    delivered_at DATETIME,
+    created_at DATETIME NOT NULL,
+    FOREIGN KEY (webhook_id) REFERENCES webhooks(id) ON DELETE CASCADE
+);
+CREATE INDEX IF NOT EXISTS idx_webhook_deliveries_pending ON webhook_deliveries (delivered_at, next_attempt_at);
+
+-- A user's own rate per pair, preferred over provider rates
+CREATE TABLE IF NOT EXISTS user_exchange_rates (
+    id TEXT PRIMARY KEY,
+    user_id TEXT NOT NULL,
+    base_currency TEXT NOT NULL,
+    quote_currency TEXT NOT NULL,
+    rate REAL NOT NULL,
+    created_at DATETIME NOT NULL,
+    updated_at DATETIME NOT NULL,
+    UNIQUE (user_id, base_currency, quote_currency),
+    FOREIGN KEY (user_id) REFERENCES users(id) ON DELETE CASCADE
+);
+
+-- Positions in stocks, funds and crypto, valued at market prices
+CREATE TABLE IF NOT EXISTS holdings (
+    id TEXT PRIMARY KEY,
+    user_id TEXT NOT NULL,
+    account_id TEXT,
+    asset_type TEXT NOT NULL CHECK (asset_type IN ('stock', 'mutual_fund', 'crypto')),
+    symbol TEXT NOT NULL,
+    name TEXT,
+    quantity REAL NOT NULL,
+    cost_basis REAL NOT NULL DEFAULT 0,
+    currency TEXT NOT NULL,
+    created_at DATETIME NOT NULL,
+    updated_at DATETIME NOT NULL,
+    FOREIGN KEY (user_id) REFERENCES users(id) ON DELETE CASCADE,
+    FOREIGN KEY (account_id) REFERENCES accounts(id) ON DELETE SET NULL
+);
+CREATE INDEX IF NOT EXISTS idx_holdings_user_id ON holdings (user_id);
+
+-- Latest provider quote per symbol, shared by all users
+CREATE TABLE IF NOT EXISTS market_prices (
+    asset_type TEXT NOT NULL,
+    symbol TEXT NOT NULL,
+    price REAL NOT NULL,
+    currency TEXT NOT NULL,
+    fetched_at DATETIME NOT NULL,
+    PRIMARY KEY (asset_type, symbol)
+);
+
+-- Fixed deposits and monthly deposit schemes
+CREATE TABLE IF NOT EXISTS term_deposits (
+    id TEXT PRIMARY KEY,
+    user_id TEXT NOT NULL,
+    account_id TEXT,
+    name TEXT NOT NULL,
+    deposit_type TEXT NOT NULL CHECK (deposit_type IN ('fixed', 'recurring')),
+    principal REAL NOT NULL,
+    currency TEXT NOT NULL,
+    annual_rate REAL NOT NULL,
+    tenure_months INTEGER NOT NULL,
+    compounding TEXT NOT NULL CHECK (compounding IN ('simple', 'monthly', 'quarterly', 'half_yearly', 'yearly')),
+    start_date DATETIME NOT NULL,
+    maturity_date DATETIME NOT NULL,
+    is_closed BOOLEAN NOT NULL DEFAULT FALSE,
+    notes TEXT,
+    created_at DATETIME NOT NULL,
+    updated_at DATETIME NOT NULL,
+    FOREIGN KEY (user_id) REFERENCES users(id) ON DELETE CASCADE,
+    FOREIGN KEY (account_id) REFERENCES accounts(id) ON DELETE SET NULL
+);
+CREATE INDEX IF NOT EXISTS idx_term_deposits_maturity ON term_deposits (is_closed, maturity_date);
+
+-- One row per closed credit card billing cycle
+CREATE TABLE IF NOT EXISTS card_statements (
+    id TEXT PRIMARY KEY,
+    account_id TEXT NOT NULL,
+    user_id TEXT NOT NULL,
+    period_start DATETIME NOT NULL,
+    period_end DATETIME NOT NULL,
+    due_date DATETIME NOT NULL,
+    currency TEXT NOT NULL,
+    opening_balance REAL NOT NULL,
+    spend REAL NOT NULL,
+    credits REAL NOT NULL,
+    closing_balance REAL NOT NULL,
+    minimum_due REAL NOT NULL,
+    created_at DATETIME NOT NULL,
+    UNIQUE (account_id, period_end),
+    FOREIGN KEY (account_id) REFERENCES accounts(id) ON DELETE CASCADE,
+    FOREIGN KEY (user_id) REFERENCES users(id) ON DELETE CASCADE
+);
+CREATE INDEX IF NOT EXISTS idx_card_statements_due ON card_statements (due_date);
+
+-- Purchases split into monthly installments
+CREATE TABLE IF NOT EXISTS emi_plans (
+    id TEXT PRIMARY KEY,
+    user_id TEXT NOT NULL,
+    account_id TEXT NOT NULL,
+    name TEXT NOT NULL,
+    purchase_amount REAL NOT NULL,
+    currency TEXT NOT NULL,
+    annual_rate REAL NOT NULL DEFAULT 0.0,
+    installments INTEGER NOT NULL,
+    installment_amount REAL NOT NULL,
+    total_interest REAL NOT NULL,
+    purchase_date DATETIME NOT NULL,
+    first_due_date DATETIME NOT NULL,
+    recurring_transaction_id TEXT,
+    category TEXT,
+    notes TEXT,
+    created_at DATETIME NOT NULL,
+    updated_at DATETIME NOT NULL,
+    FOREIGN KEY (user_id) REFERENCES users(id) ON DELETE CASCADE,
+    FOREIGN KEY (account_id) REFERENCES accounts(id) ON DELETE CASCADE,
+    FOREIGN KEY (recurring_transaction_id) REFERENCES recurring_transactions(id) ON DELETE SET NULL
+);
+
+-- Users sharing selected accounts
+CREATE TABLE IF NOT EXISTS households (
+    id TEXT PRIMARY KEY,
+    name TEXT NOT NULL,
+    created_by TEXT NOT NULL,
+    created_at DATETIME NOT NULL,
+    updated_at DATETIME NOT NULL,
+    FOREIGN KEY (created_by) REFERENCES users(id) ON DELETE CASCADE
+);
+
+CREATE TABLE IF NOT EXISTS household_members (
+    household_id TEXT NOT NULL,
+    user_id TEXT NOT NULL,
+    role TEXT NOT NULL,
+    joined_at DATETIME NOT NULL,
+    PRIMARY KEY (household_id, user_id),
+    FOREIGN KEY (household_id) REFERENCES households(id) ON DELETE CASCADE,
+    FOREIGN KEY (user_id) REFERENCES users(id) ON DELETE CASCADE
+);
+
+CREATE TABLE IF NOT EXISTS household_accounts (
+    household_id TEXT NOT NULL,
+    account_id TEXT NOT NULL,
+    shared_by TEXT NOT NULL,
+    shared_at DATETIME NOT NULL,
+    PRIMARY KEY (household_id, account_id),
+    FOREIGN KEY (household_id) REFERENCES households(id) ON DELETE CASCADE,
+    FOREIGN KEY (account_id) REFERENCES accounts(id) ON DELETE CASCADE,
+    FOREIGN KEY (shared_by) REFERENCES users(id) ON DELETE CASCADE
+);
+CREATE INDEX IF NOT EXISTS idx_household_members_user ON household_members (user_id);
+
+CREATE TABLE IF NOT EXISTS household_invites (
+    id TEXT PRIMARY KEY,
+    household_id TEXT NOT NULL,
+    email TEXT NOT NULL,
+    role TEXT NOT NULL,
+    token TEXT NOT NULL UNIQUE,
+    invited_by TEXT NOT NULL,
+    expires_at DATETIME NOT NULL,
+    accepted_at DATETIME,
+    created_at DATETIME NOT NULL,
+    FOREIGN KEY (household_id) REFERENCES households(id) ON DELETE CASCADE,
+    FOREIGN KEY (invited_by) REFERENCES users(id) ON DELETE CASCADE
+);
+
+-- Group expenses between users
+CREATE TABLE IF NOT EXISTS split_expenses (
+    id TEXT PRIMARY KEY,
+    created_by TEXT NOT NULL,
+    paid_by TEXT NOT NULL,
+    description TEXT NOT NULL,
+    amount REAL NOT NULL,
+    currency TEXT NOT NULL,
+    category TEXT,
+    date DATETIME NOT NULL,
+    transaction_id TEXT,
+    created_at DATETIME NOT NULL,
+    FOREIGN KEY (created_by) REFERENCES users(id) ON DELETE CASCADE,
+    FOREIGN KEY (paid_by) REFERENCES users(id) ON DELETE CASCADE,
+    FOREIGN KEY (transaction_id) REFERENCES transactions(id) ON DELETE SET NULL
+);
+
+CREATE TABLE IF NOT EXISTS split_shares (
+    split_id TEXT NOT NULL,
+    user_id TEXT NOT NULL,
+    amount REAL NOT NULL,
+    PRIMARY KEY (split_id, user_id),
+    FOREIGN KEY (split_id) REFERENCES split_expenses(id) ON DELETE CASCADE,
+    FOREIGN KEY (user_id) REFERENCES users(id) ON DELETE CASCADE
+);
+CREATE INDEX IF NOT EXISTS idx_split_shares_user ON split_shares (user_id);
+
+CREATE TABLE IF NOT EXISTS split_settlements (
+    id TEXT PRIMARY KEY,
+    from_user_id TEXT NOT NULL,
+    to_user_id TEXT NOT NULL,
+    amount REAL NOT NULL,
+    currency TEXT NOT NULL,
+    date DATETIME NOT NULL,
+    note TEXT,
+    transaction_id TEXT,
+    created_by TEXT NOT NULL,
+    created_at DATETIME NOT NULL,
+    FOREIGN KEY (from_user_id) REFERENCES users(id) ON DELETE CASCADE,
+    FOREIGN KEY (to_user_id) REFERENCES users(id) ON DELETE CASCADE,
+    FOREIGN KEY (transaction_id) REFERENCES transactions(id) ON DELETE SET NULL,
+    FOREIGN KEY (created_by) REFERENCES users(id) ON DELETE CASCADE
+);
+
+-- User-set lead times for liabilities, card payments and recurring transactions
+CREATE TABLE IF NOT EXISTS bill_reminders (
+    id TEXT PRIMARY KEY,
+    user_id TEXT NOT NULL,
+    target_type TEXT NOT NULL,
+    target_id TEXT NOT NULL,
+    days_before INTEGER NOT NULL,
+    send_email BOOLEAN NOT NULL DEFAULT TRUE,
+    is_active BOOLEAN NOT NULL DEFAULT TRUE,
+    created_at DATETIME NOT NULL,
+    updated_at DATETIME NOT NULL,
+    UNIQUE (user_id, target_type, target_id, days_before),
+    FOREIGN KEY (user_id) REFERENCES users(id) ON DELETE CASCADE
+);
+CREATE INDEX IF NOT EXISTS idx_bill_reminders_target ON bill_reminders (target_type, target_id);
+
+-- Last change to each synced record, kept by triggers
+CREATE TABLE IF NOT EXISTS change_log (
+    entity_type TEXT NOT NULL,
+    entity_id TEXT NOT NULL,
+    user_id TEXT NOT NULL,
+    account_id TEXT,
+    changed_at DATETIME NOT NULL,
+    PRIMARY KEY (entity_type, entity_id)
+);
+CREATE INDEX IF NOT EXISTS idx_change_log_changed ON change_log (changed_at);
+
+-- Tombstones of deleted synced records, kept by triggers
+CREATE TABLE IF NOT EXISTS deleted_records (
+    entity_type TEXT NOT NULL,
+    entity_id TEXT NOT NULL,
+    user_id TEXT NOT NULL,
+    account_id TEXT,
+    deleted_at DATETIME NOT NULL,
+    PRIMARY KEY (entity_type, entity_id)
+);
+CREATE INDEX IF NOT EXISTS idx_deleted_records_deleted ON deleted_records (deleted_at);
+
+-- Each client's delta sync cursor and metadata
+CREATE TABLE IF NOT EXISTS sync_devices (
+    id TEXT PRIMARY KEY,
+    user_id TEXT NOT NULL,
+    device_id TEXT NOT NULL,
+    name TEXT NOT NULL,
+    platform TEXT,
+    app_version TEXT,
+    push_device_id TEXT,
+    last_cursor DATETIME,
+    last_pulled_at DATETIME,
+    last_pushed_at DATETIME,
+    created_at DATETIME NOT NULL,
+    updated_at DATETIME NOT NULL,
+    UNIQUE (user_id, device_id),
+    FOREIGN KEY (user_id) REFERENCES users(id) ON DELETE CASCADE,
+    FOREIGN KEY (push_device_id) REFERENCES push_devices(id) ON DELETE SET NULL
+);
@@ -0,0 +1,4 @@
+-- Accounts created through the API stored their type without underscores ("creditcard"),
+-- unlike imports and the type's own database spelling ("credit_card").
+UPDATE accounts SET account_type = 'credit_card' WHERE account_type = 'creditcard';
+UPDATE accounts SET account_type = 'mobile_banking' WHERE account_type = 'mobilebanking';
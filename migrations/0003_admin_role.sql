@@ -0,0 +1,2 @@
+-- Operators, created with the `create-admin` command; nothing grants this over the API.
+ALTER TABLE users ADD COLUMN is_admin BOOLEAN NOT NULL DEFAULT FALSE;
@@ -0,0 +1,12 @@
+-- One row per database maintenance task run, with how much space it gave back.
+CREATE TABLE IF NOT EXISTS db_maintenance_runs (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    task TEXT NOT NULL,
+    ran_at DATETIME NOT NULL,
+    duration_ms INTEGER NOT NULL,
+    rows_pruned INTEGER NOT NULL DEFAULT 0,
+    size_before_bytes INTEGER NOT NULL,
+    size_after_bytes INTEGER NOT NULL,
+    reclaimed_bytes INTEGER NOT NULL
+);
+CREATE INDEX IF NOT EXISTS idx_db_maintenance_runs_task ON db_maintenance_runs (task, id);
@@ -0,0 +1,9 @@
+-- Per-user overrides of the `quotas` settings; a NULL column keeps the configured default.
+CREATE TABLE IF NOT EXISTS user_quotas (
+    user_id TEXT PRIMARY KEY,
+    requests_per_minute INTEGER,
+    sync_batch_size INTEGER,
+    daily_upload_bytes INTEGER,
+    updated_at DATETIME NOT NULL,
+    FOREIGN KEY (user_id) REFERENCES users(id) ON DELETE CASCADE
+);
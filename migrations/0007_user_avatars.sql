@@ -0,0 +1,2 @@
+-- The storage key of the user's avatar, set by `POST /api/me/avatar`.
+ALTER TABLE users ADD COLUMN avatar_key TEXT;
@@ -0,0 +1,4 @@
+-- Where the user's days begin and how their dates are written; reports bucket by these.
+ALTER TABLE user_preferences ADD COLUMN timezone TEXT NOT NULL DEFAULT 'UTC';
+ALTER TABLE user_preferences ADD COLUMN locale TEXT NOT NULL DEFAULT 'en-US';
+ALTER TABLE user_preferences ADD COLUMN first_day_of_week TEXT NOT NULL DEFAULT 'monday';
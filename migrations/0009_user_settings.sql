@@ -0,0 +1,27 @@
+-- Preferences move from one column each to one row per setting, so new settings and the apps'
+-- own `client` settings need no schema change. Values are JSON; see `services::preferences`.
+CREATE TABLE IF NOT EXISTS user_settings (
+    user_id TEXT NOT NULL,
+    key TEXT NOT NULL,
+    value TEXT NOT NULL,
+    updated_at DATETIME NOT NULL,
+    PRIMARY KEY (user_id, key),
+    FOREIGN KEY (user_id) REFERENCES users(id) ON DELETE CASCADE
+);
+
+INSERT INTO user_settings (user_id, key, value, updated_at)
+SELECT user_id, 'displayCurrency', json_quote(display_currency), updated_at FROM user_preferences
+UNION ALL
+SELECT user_id, 'sessionIdleTimeoutMinutes', session_idle_timeout_minutes, updated_at
+FROM user_preferences WHERE session_idle_timeout_minutes IS NOT NULL
+UNION ALL
+SELECT user_id, 'creditUtilizationThresholds', '[' || credit_utilization_thresholds || ']', updated_at
+FROM user_preferences WHERE credit_utilization_thresholds IS NOT NULL AND credit_utilization_thresholds != ''
+UNION ALL
+SELECT user_id, 'timezone', json_quote(timezone), updated_at FROM user_preferences
+UNION ALL
+SELECT user_id, 'locale', json_quote(locale), updated_at FROM user_preferences
+UNION ALL
+SELECT user_id, 'firstDayOfWeek', json_quote(first_day_of_week), updated_at FROM user_preferences;
+
+DROP TABLE user_preferences;
@@ -0,0 +1,13 @@
+-- Counts every change to a user's preferences, so devices can tell whether their copy is current
+-- and sync pulls can include preferences changed since the last one.
+CREATE TABLE IF NOT EXISTS preference_revisions (
+    user_id TEXT PRIMARY KEY,
+    revision INTEGER NOT NULL,
+    updated_at DATETIME NOT NULL,
+    FOREIGN KEY (user_id) REFERENCES users(id) ON DELETE CASCADE
+);
+
+INSERT INTO preference_revisions (user_id, revision, updated_at)
+SELECT user_id, 1, MAX(updated_at) FROM user_settings GROUP BY user_id;
+
+CREATE INDEX IF NOT EXISTS idx_preference_revisions_updated ON preference_revisions (updated_at);
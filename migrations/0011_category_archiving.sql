@@ -0,0 +1,2 @@
+-- Archived categories stay on the transactions filed under them but drop out of pickers.
+ALTER TABLE categories ADD COLUMN is_archived BOOLEAN NOT NULL DEFAULT FALSE;
@@ -0,0 +1,10 @@
+-- Where each account sits in its owner's list, and whether it is pinned as a favorite.
+ALTER TABLE accounts ADD COLUMN sort_order INTEGER NOT NULL DEFAULT 0;
+ALTER TABLE accounts ADD COLUMN is_favorite BOOLEAN NOT NULL DEFAULT FALSE;
+
+-- Keep the newest-first order lists had before users could arrange them
+UPDATE accounts SET sort_order = (
+    SELECT COUNT(*) FROM accounts AS newer
+    WHERE newer.user_id = accounts.user_id
+      AND (newer.created_at > accounts.created_at OR (newer.created_at = accounts.created_at AND newer.id > accounts.id))
+);
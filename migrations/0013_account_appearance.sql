@@ -0,0 +1,3 @@
+-- How the apps draw an account, kept with it so a reinstall doesn't lose it.
+ALTER TABLE accounts ADD COLUMN icon TEXT;
+ALTER TABLE accounts ADD COLUMN color TEXT;
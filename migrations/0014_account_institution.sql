@@ -0,0 +1,4 @@
+-- Which bank or branch holds an account, and the user's own notes on it.
+ALTER TABLE accounts ADD COLUMN institution_name TEXT;
+ALTER TABLE accounts ADD COLUMN account_number_masked TEXT;
+ALTER TABLE accounts ADD COLUMN notes TEXT;
@@ -0,0 +1,25 @@
+-- Bank statement reconciliations. An account has at most one open at a time.
+CREATE TABLE IF NOT EXISTS reconciliations (
+    id TEXT PRIMARY KEY,
+    account_id TEXT NOT NULL,
+    user_id TEXT NOT NULL,
+    statement_date DATETIME NOT NULL,
+    statement_balance REAL NOT NULL,
+    status TEXT NOT NULL DEFAULT 'open',
+    created_at DATETIME NOT NULL,
+    completed_at DATETIME,
+    FOREIGN KEY (account_id) REFERENCES accounts(id) ON DELETE CASCADE,
+    FOREIGN KEY (user_id) REFERENCES users(id) ON DELETE CASCADE
+);
+CREATE INDEX IF NOT EXISTS idx_reconciliations_account ON reconciliations (account_id, statement_date);
+CREATE UNIQUE INDEX IF NOT EXISTS idx_reconciliations_open ON reconciliations (account_id) WHERE status = 'open';
+
+-- Transactions cleared against a reconciliation. A transaction is cleared at most once.
+CREATE TABLE IF NOT EXISTS reconciliation_transactions (
+    transaction_id TEXT PRIMARY KEY,
+    reconciliation_id TEXT NOT NULL,
+    cleared_at DATETIME NOT NULL,
+    FOREIGN KEY (transaction_id) REFERENCES transactions(id) ON DELETE CASCADE,
+    FOREIGN KEY (reconciliation_id) REFERENCES reconciliations(id) ON DELETE CASCADE
+);
+CREATE INDEX IF NOT EXISTS idx_reconciliation_transactions_reconciliation ON reconciliation_transactions (reconciliation_id);
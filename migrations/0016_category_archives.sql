@@ -0,0 +1,15 @@
+-- Archiving is per user, so a built-in category can leave one user's pickers and stay in everyone else's.
+CREATE TABLE IF NOT EXISTS category_archives (
+    user_id TEXT NOT NULL,
+    category_id TEXT NOT NULL,
+    archived_at DATETIME NOT NULL,
+    PRIMARY KEY (user_id, category_id),
+    FOREIGN KEY (user_id) REFERENCES users(id) ON DELETE CASCADE,
+    FOREIGN KEY (category_id) REFERENCES categories(id) ON DELETE CASCADE
+);
+
+INSERT OR IGNORE INTO category_archives (user_id, category_id, archived_at)
+SELECT user_id, id, COALESCE(updated_at, created_at) FROM categories
+WHERE is_archived AND user_id != '' AND user_id IN (SELECT id FROM users);
+
+ALTER TABLE categories DROP COLUMN is_archived;
@@ -0,0 +1,304 @@
+use std::time::Duration;
+
+use axum::{
+    extract::DefaultBodyLimit,
+    routing::{delete, get, post, put},
+    Router,
+    http::{header, Method},
+};
+use tower_http::cors::{AllowOrigin, Any, CorsLayer};
+use tower_http::timeout::TimeoutLayer;
+use tower_http::trace::TraceLayer;
+
+use crate::config;
+use crate::handlers::{
+    account::{create_account, get_accounts, get_account, update_account, delete_account, get_account_statements, reorder_accounts},
+    // category::{create_category, get_categories, get_category, update_category, delete_category},
+    transaction::{create_transaction, get_transactions, get_transaction, update_transaction, delete_transaction},
+    liability::{create_liability, get_liabilities, get_liability, update_liability, delete_liability},
+    loan::{create_loan, get_loans, get_loan, update_loan, delete_loan},
+    savings_goal::{create_savings_goal, get_savings_goals, get_savings_goal, update_savings_goal, delete_savings_goal, create_contribution, get_contributions},
+    budget::{create_budget, get_budgets, get_budget, update_budget, delete_budget, get_budget_periods, get_budget_progress},
+    recurring_transaction::{create_recurring_transaction, get_recurring_transactions, get_recurring_transaction, update_recurring_transaction, delete_recurring_transaction, post_recurring_transaction, pause_recurring_transaction, resume_recurring_transaction, skip_recurring_transaction},
+    auth::{signup, login, signin, verify_email, resend_verification, forgot_password, reset_password},
+    user_data::{get_user_accounts, get_user_transactions, get_user_loans, get_user_liabilities, get_user_budgets, get_user_savings_goals, get_user_categories, get_user_recurring_transactions},
+    preference::{get_preferences, update_preferences},
+    category_suggestion::suggest_category,
+    category_set::{get_category_presets, apply_category_preset, export_categories, import_categories},
+    category_archive::{archive_category, unarchive_category},
+    cash_count::{create_cash_count, get_cash_counts, get_cash_denominations},
+    reconciliation::{create_reconciliation, get_reconciliations, get_reconciliation, clear_reconciliation_transactions, unclear_reconciliation_transactions, complete_reconciliation},
+    amortization::{generate_amortization, get_amortization_schedules, get_amortization_schedule, record_amortization_payment},
+    event::{get_event_schemas, preview_event_schema},
+    export::{export_all, export_data, export_transactions_csv, get_account_statement},
+    import::{import_all, import_from_app, import_statement, import_transactions_csv},
+    usage::{get_my_usage, get_my_quota},
+    profile::{get_me, update_me, upload_avatar, get_avatar},
+    history::{get_entity_history, restore_entity_version},
+    calendar::get_calendar,
+    net_worth::get_net_worth,
+    notification::{get_notifications, mark_notification_read, mark_all_notifications_read},
+    device::{register_device, get_devices, delete_device},
+    dashboard::get_dashboard,
+    stats::get_stats,
+    currency::{get_currencies, convert, create_exchange_rate, get_exchange_rates, get_exchange_rate, update_exchange_rate, delete_exchange_rate},
+    report::{get_monthly_report, get_cashflow_report, get_forecast_report},
+    webhook::{create_webhook, get_webhooks, get_webhook, update_webhook, delete_webhook, get_webhook_deliveries},
+    investment::{create_holding, get_holdings, get_holding, update_holding, delete_holding, get_market_prices, refresh_market_prices},
+    term_deposit::{create_term_deposit, get_term_deposits, get_term_deposit, update_term_deposit, delete_term_deposit},
+    emi_plan::{create_emi_plan, get_emi_plans, get_emi_plan, update_emi_plan, delete_emi_plan},
+    subscription::{get_subscriptions, track_subscription},
+    household::{
+        create_household, get_households, get_household, update_household, delete_household, share_account, unshare_account,
+        create_household_invite, get_household_invites, delete_household_invite, accept_household_invite,
+        update_household_member, remove_household_member,
+    },
+    split::{
+        create_split_expense, get_split_expenses, get_split_expense, delete_split_expense, get_split_balances,
+        get_friend_balance, create_split_settlement, get_split_settlements, delete_split_settlement,
+    },
+    reminder::{
+        create_bill_reminder, get_bill_reminders, get_bill_reminder, update_bill_reminder, delete_bill_reminder,
+    },
+    sync::{sync_changes, get_sync_changes, register_sync_device, get_sync_devices, delete_sync_device},
+    live::{live_updates, stream_events},
+    health,
+    version,
+    admin::{get_admin_stats, update_user_quota, reload_config},
+};
+use crate::middleware;
+use crate::services::avatars;
+use crate::services::database::DbPool;
+
+/// Every route the server answers, with its middleware, over `pool`.
+pub fn router(pool: DbPool) -> Router {
+    let limits = &config::get().limits;
+
+    // Configure CORS; any origin unless `cors.allowed_origins` narrows it, checked against the
+    // origins in effect so a config reload applies without a restart
+    let allow_origin = AllowOrigin::predicate(|origin, _| {
+        let cors = &config::current().cors;
+        cors.allows_any_origin() || cors.allowed_origins.iter().any(|allowed| allowed.as_bytes() == origin.as_bytes())
+    });
+    let cors = CorsLayer::new()
+        .allow_origin(allow_origin)
+        .allow_methods([Method::GET, Method::POST, Method::PUT, Method::DELETE, Method::OPTIONS])
+        .allow_headers(Any)
+        .expose_headers([header::ETAG]);
+
+    Router::new()
+        // Root route
+        .route("/", get(|| async {
+            serde_json::json!({
+                "message": "Personal Manager Backend API",
+                "version": version::VERSION,
+                "endpoints": {
+                    "auth": {
+                        "signup": "/auth/signup",
+                        "login": "/auth/login",
+                        "signin": "/auth/signin",
+                        "verifyEmail": "/auth/verify-email",
+                        "forgotPassword": "/auth/forgot-password",
+                        "resetPassword": "/auth/reset-password"
+                    },
+                    "userData": {
+                        "accounts": "/api/accounts",
+                        "transactions": "/api/transactions",
+                        "loans": "/api/loans",
+                        "liabilities": "/api/liabilities"
+                    },
+                    "accounts": "/accounts",
+                    "transactions": "/transactions",
+                    "liabilities": "/liabilities",
+                    "loans": "/loans",
+                    "version": "/version",
+                    "health": {
+                        "live": "/health/live",
+                        "ready": "/health/ready"
+                    }
+                }
+            }).to_string()
+        }))
+
+        // Authentication routes
+        .route("/auth/signup", post(signup))
+        .route("/auth/login", post(login))
+        .route("/auth/signin", post(signin))
+        .route("/auth/verify-email", post(verify_email))
+        .route("/auth/resend-verification", post(resend_verification))
+        .route("/auth/forgot-password", post(forgot_password))
+        .route("/auth/reset-password", post(reset_password))
+
+        // User-specific API routes (requires authentication)
+        .route("/api/accounts", get(get_user_accounts))
+        .route("/api/transactions", get(get_user_transactions))
+        .route("/api/loans", get(get_user_loans))
+        .route("/api/liabilities", get(get_user_liabilities))
+        .route("/api/budgets", get(get_user_budgets))
+        .route("/api/savings_goals", get(get_user_savings_goals))
+        .route("/api/categories", get(get_user_categories))
+        .route("/api/categories/presets", get(get_category_presets))
+        .route("/api/categories/presets/:id", post(apply_category_preset))
+        .route("/api/categories/export", get(export_categories))
+        .route("/api/categories/import", post(import_categories))
+        .route("/api/categories/:id/archive", post(archive_category))
+        .route("/api/categories/:id/unarchive", post(unarchive_category))
+        .route("/api/recurring_transactions", get(get_user_recurring_transactions))
+
+        // Account routes (all require authentication)
+        .route("/accounts", post(create_account).get(get_accounts))
+        .route("/accounts/reorder", post(reorder_accounts))
+        .route("/accounts/:id", get(get_account).put(update_account).delete(delete_account))
+        .route("/accounts/:id/cash-counts", post(create_cash_count).get(get_cash_counts))
+        .route("/accounts/:id/statement.pdf", get(get_account_statement))
+        .route("/accounts/:id/statements", get(get_account_statements))
+        .route("/accounts/:id/reconciliations", post(create_reconciliation).get(get_reconciliations))
+        .route("/accounts/:id/reconciliations/:reconciliation_id", get(get_reconciliation))
+        .route("/accounts/:id/reconciliations/:reconciliation_id/clear", post(clear_reconciliation_transactions))
+        .route("/accounts/:id/reconciliations/:reconciliation_id/unclear", post(unclear_reconciliation_transactions))
+        .route("/accounts/:id/reconciliations/:reconciliation_id/complete", post(complete_reconciliation))
+        // Transaction routes (all require authentication)
+        .route("/transactions", post(create_transaction).get(get_transactions))
+        .route("/transactions/:id", get(get_transaction).put(update_transaction).delete(delete_transaction))
+        // Liability routes (all require authentication)
+        .route("/liabilities", post(create_liability).get(get_liabilities))
+        .route("/liabilities/:id", get(get_liability).put(update_liability).delete(delete_liability))
+        // Loan routes (all require authentication)
+        .route("/loans", post(create_loan).get(get_loans))
+        .route("/loans/:id", get(get_loan).put(update_loan).delete(delete_loan))
+        // Savings goal routes (all require authentication)
+        .route("/savings-goals", post(create_savings_goal).get(get_savings_goals))
+        .route("/savings-goals/:id", get(get_savings_goal).put(update_savings_goal).delete(delete_savings_goal))
+        .route("/savings-goals/:id/contributions", post(create_contribution).get(get_contributions))
+        // Budget routes (all require authentication)
+        .route("/budgets", post(create_budget).get(get_budgets))
+        .route("/budgets/:id", get(get_budget).put(update_budget).delete(delete_budget))
+        .route("/budgets/:id/periods", get(get_budget_periods))
+        .route("/budgets/:id/progress", get(get_budget_progress))
+        // Recurring transaction routes (all require authentication)
+        .route("/recurring_transactions", post(create_recurring_transaction).get(get_recurring_transactions))
+        .route("/recurring_transactions/:id", get(get_recurring_transaction).put(update_recurring_transaction).delete(delete_recurring_transaction))
+        .route("/recurring_transactions/:id/post", post(post_recurring_transaction))
+        .route("/recurring-transactions/:id/pause", post(pause_recurring_transaction))
+        .route("/recurring-transactions/:id/resume", post(resume_recurring_transaction))
+        .route("/recurring-transactions/:id/skip", post(skip_recurring_transaction))
+
+        // Preference routes (requires authentication)
+        .route("/api/preferences", get(get_preferences).put(update_preferences))
+
+        // Category suggestion routes (requires authentication)
+        .route("/api/suggest-category", post(suggest_category))
+
+        // Financial tool routes (requires authentication)
+        .route("/api/tools/amortization", post(generate_amortization).get(get_amortization_schedules))
+        .route("/api/tools/amortization/:id", get(get_amortization_schedule))
+        .route("/api/tools/amortization/:id/entries/:period", put(record_amortization_payment))
+        .route("/api/cash-denominations", get(get_cash_denominations))
+        .route("/api/events/schema", get(get_event_schemas))
+        .route("/api/events/schema/preview", get(preview_event_schema))
+        .route("/api/export", get(export_data))
+        .route("/api/export/transactions.csv", get(export_transactions_csv))
+        .route("/api/export/all", get(export_all))
+        .route("/api/import/apps", post(import_from_app))
+        .route("/api/import/statements", post(import_statement))
+        .route("/api/import/transactions", post(import_transactions_csv))
+        .route("/api/import/all", post(import_all).layer(DefaultBodyLimit::max(limits.max_archive_body_bytes)))
+        .route("/api/me", get(get_me).put(update_me))
+        .route("/api/me/avatar", post(upload_avatar).layer(DefaultBodyLimit::max(avatars::MAX_AVATAR_UPLOAD_BYTES)))
+        .route("/avatars/:file", get(get_avatar))
+        .route("/api/me/usage", get(get_my_usage))
+        .route("/api/me/quota", get(get_my_quota))
+        .route("/api/calendar", get(get_calendar))
+        .route("/api/net-worth", get(get_net_worth))
+        .route("/api/dashboard", get(get_dashboard))
+        .route("/api/stats", get(get_stats))
+        .route("/api/currencies", get(get_currencies))
+        .route("/api/convert", get(convert))
+        .route("/api/exchange-rates", post(create_exchange_rate).get(get_exchange_rates))
+        .route("/api/exchange-rates/:id", get(get_exchange_rate).put(update_exchange_rate).delete(delete_exchange_rate))
+        .route("/api/holdings", post(create_holding).get(get_holdings))
+        .route("/api/holdings/:id", get(get_holding).put(update_holding).delete(delete_holding))
+        .route("/api/market-prices", get(get_market_prices))
+        .route("/api/market-prices/refresh", post(refresh_market_prices))
+        .route("/api/term-deposits", post(create_term_deposit).get(get_term_deposits))
+        .route("/api/term-deposits/:id", get(get_term_deposit).put(update_term_deposit).delete(delete_term_deposit))
+        .route("/api/emi-plans", post(create_emi_plan).get(get_emi_plans))
+        .route("/api/emi-plans/:id", get(get_emi_plan).put(update_emi_plan).delete(delete_emi_plan))
+        .route("/api/subscriptions", get(get_subscriptions))
+        .route("/api/subscriptions/:id/track", post(track_subscription))
+        .route("/api/households", post(create_household).get(get_households))
+        .route("/api/households/:id", get(get_household).put(update_household).delete(delete_household))
+        .route("/api/households/:id/accounts", post(share_account))
+        .route("/api/households/:id/accounts/:account_id", delete(unshare_account))
+        .route("/api/households/:id/invites", post(create_household_invite).get(get_household_invites))
+        .route("/api/households/:id/invites/:invite_id", delete(delete_household_invite))
+        .route("/api/households/:id/members/:user_id", put(update_household_member).delete(remove_household_member))
+        .route("/api/household-invites/accept", post(accept_household_invite))
+        .route("/api/splits", post(create_split_expense).get(get_split_expenses))
+        .route("/api/splits/:id", get(get_split_expense).delete(delete_split_expense))
+        .route("/api/splits/balances", get(get_split_balances))
+        .route("/api/splits/balances/:user_id", get(get_friend_balance))
+        .route("/api/splits/settlements", post(create_split_settlement).get(get_split_settlements))
+        .route("/api/splits/settlements/:id", delete(delete_split_settlement))
+        .route("/api/reminders", post(create_bill_reminder).get(get_bill_reminders))
+        .route("/api/reminders/:id", get(get_bill_reminder).put(update_bill_reminder).delete(delete_bill_reminder))
+        .route("/api/sync", post(sync_changes))
+        .route("/api/sync/changes", get(get_sync_changes))
+        .route("/api/sync/devices", post(register_sync_device).get(get_sync_devices))
+        .route("/api/sync/devices/:id", delete(delete_sync_device))
+        .route("/ws", get(live_updates))
+        .route("/api/events", get(stream_events))
+        .route("/api/reports/monthly", get(get_monthly_report))
+        .route("/api/reports/cashflow", get(get_cashflow_report))
+        .route("/api/reports/forecast", get(get_forecast_report))
+        .route("/api/notifications", get(get_notifications))
+        .route("/api/notifications/read-all", post(mark_all_notifications_read))
+        .route("/api/notifications/:id/read", post(mark_notification_read))
+        .route("/api/devices", post(register_device).get(get_devices))
+        .route("/api/devices/:id", delete(delete_device))
+        .route("/api/webhooks", post(create_webhook).get(get_webhooks))
+        .route("/api/webhooks/:id", get(get_webhook).put(update_webhook).delete(delete_webhook))
+        .route("/api/webhooks/:id/deliveries", get(get_webhook_deliveries))
+        // Entity history routes (requires authentication)
+        .route("/api/accounts/:id/history", get(get_entity_history))
+        .route("/api/accounts/:id/history/:version/restore", post(restore_entity_version))
+        .route("/api/transactions/:id/history", get(get_entity_history))
+        .route("/api/transactions/:id/history/:version/restore", post(restore_entity_version))
+        .route("/api/budgets/:id/history", get(get_entity_history))
+        .route("/api/budgets/:id/history/:version/restore", post(restore_entity_version))
+        .route("/api/savings-goals/:id/history", get(get_entity_history))
+        .route("/api/savings-goals/:id/history/:version/restore", post(restore_entity_version))
+        .route("/api/loans/:id/history", get(get_entity_history))
+        .route("/api/loans/:id/history/:version/restore", post(restore_entity_version))
+        .route("/api/liabilities/:id/history", get(get_entity_history))
+        .route("/api/liabilities/:id/history/:version/restore", post(restore_entity_version))
+        .route("/api/recurring-transactions/:id/history", get(get_entity_history))
+        .route("/api/recurring-transactions/:id/history/:version/restore", post(restore_entity_version))
+
+        // Operator endpoints, for admins only
+        .route("/admin/stats", get(get_admin_stats))
+        .route("/admin/users/:id/quota", put(update_user_quota))
+        .route("/admin/config/reload", post(reload_config))
+
+        // Health checks and build info
+        .route("/version", get(version::get_version))
+        .route("/health", get(|| async { "OK" }))
+        .route("/health/live", get(health::live))
+        .route("/health/ready", get(health::ready))
+
+        .route_layer(axum::middleware::from_fn(middleware::usage::track_usage))
+        .route_layer(axum::middleware::from_fn_with_state(pool.clone(), middleware::cache::invalidate_on_write))
+        .route_layer(axum::middleware::from_fn_with_state(pool.clone(), middleware::quota::enforce_quotas))
+        .layer(middleware::load_shed::LoadShedLayer::from_config(limits))
+        .layer(TimeoutLayer::new(Duration::from_secs(limits.request_timeout_secs)))
+        .layer(DefaultBodyLimit::max(limits.max_body_bytes))
+        .layer(axum::middleware::from_fn(middleware::envelope::envelope_errors))
+        .layer(cors)
+        .layer(axum::middleware::from_fn(middleware::access_log::access_log))
+        .layer(
+            TraceLayer::new_for_http()
+                .make_span_with(middleware::request_span::make_span)
+                .on_response(middleware::request_span::on_response),
+        )
+        .with_state(pool)
+}
@@ -0,0 +1,118 @@
+use std::io::Write;
+use std::path::PathBuf;
+
+use anyhow::{anyhow, Result};
+use clap::{Args, Parser, Subcommand};
+
+use crate::services::admin::{self, AdminAccount};
+use crate::services::archive;
+use crate::services::database::DbPool;
+use crate::services::export::{self, ExportFormat};
+
+/// Personal Manager backend server and its administration commands. Settings come from
+/// `config.toml` and the environment, as for the server.
+#[derive(Debug, Parser)]
+#[command(version, args_conflicts_with_subcommands = true)]
+pub struct Cli {
+    #[command(subcommand)]
+    pub command: Option<Command>,
+    /// Options for `serve`, which also runs when no command is given.
+    #[command(flatten)]
+    pub serve: ServeArgs,
+}
+
+#[derive(Debug, Subcommand)]
+pub enum Command {
+    /// Run the HTTP server and background jobs (the default).
+    Serve(ServeArgs),
+    /// Apply pending database migrations and exit.
+    Migrate,
+    /// Create an admin account, or make an existing account an admin.
+    CreateAdmin(CreateAdminArgs),
+    /// Write one user's data to a file or stdout.
+    ExportUser(ExportUserArgs),
+    /// Copy the whole database to a new file; safe while the server is running.
+    Backup(BackupArgs),
+}
+
+#[derive(Debug, Clone, Default, Args)]
+pub struct ServeArgs {
+    /// Create a demo user with sample data on startup, if there isn't one yet.
+    #[arg(long)]
+    pub seed_demo: bool,
+}
+
+#[derive(Debug, Args)]
+pub struct CreateAdminArgs {
+    #[arg(long)]
+    pub email: String,
+    #[arg(long, default_value = "Admin")]
+    pub name: String,
+    /// Prompted for when not given. Leave empty to keep an existing account's password.
+    #[arg(long, env = "ADMIN_PASSWORD", hide_env_values = true)]
+    pub password: Option<String>,
+}
+
+#[derive(Debug, Args)]
+pub struct ExportUserArgs {
+    /// The user's email address.
+    #[arg(long)]
+    pub email: String,
+    /// `archive` (JSON, restorable through the API), `ledger`, `beancount`, `ofx` or `qif`.
+    #[arg(long, default_value = "archive")]
+    pub format: String,
+    /// Where to write; stdout when not given.
+    #[arg(long, short)]
+    pub output: Option<PathBuf>,
+}
+
+#[derive(Debug, Args)]
+pub struct BackupArgs {
+    /// The new database file; must not exist yet.
+    pub output: PathBuf,
+}
+
+pub async fn create_admin(pool: &DbPool, args: CreateAdminArgs) -> Result<()> {
+    let password = match args.password {
+        Some(password) => Some(password),
+        None => {
+            let password = rpassword::prompt_password(format!("Password for {}: ", args.email)).map_err(|e| {
+                anyhow!("can't prompt for a password ({}); pass --password or set ADMIN_PASSWORD", e)
+            })?;
+            Some(password).filter(|password| !password.is_empty())
+        }
+    };
+
+    match admin::create_admin(pool, &args.name, &args.email, password.as_deref()).await? {
+        AdminAccount::Created(id) => println!("✅ Created admin {} ({})", args.email, id),
+        AdminAccount::Promoted(id) => println!("✅ {} ({}) is now an admin", args.email, id),
+    }
+    Ok(())
+}
+
+pub async fn export_user(pool: &DbPool, args: ExportUserArgs) -> Result<()> {
+    let user_id = admin::user_id_by_email(pool, &args.email).await?;
+    let contents = if args.format.eq_ignore_ascii_case("archive") {
+        serde_json::to_string_pretty(&archive::export_archive(pool, &user_id).await?)?
+    } else {
+        let format = ExportFormat::parse(&args.format).ok_or_else(|| {
+            anyhow!("Unknown format '{}'; use archive, ledger, beancount, ofx or qif", args.format)
+        })?;
+        export::export_user(pool, &user_id, format).await?
+    };
+
+    match args.output {
+        Some(path) => {
+            std::fs::write(&path, contents).map_err(|e| anyhow!("can't write {}: {}", path.display(), e))?;
+            eprintln!("✅ Exported {} to {}", args.email, path.display());
+        }
+        None => std::io::stdout().write_all(contents.as_bytes())?,
+    }
+    Ok(())
+}
+
+pub async fn backup(pool: &DbPool, args: BackupArgs) -> Result<()> {
+    admin::backup(pool, &args.output).await?;
+    println!("✅ Backed up the database to {}", args.output.display());
+    Ok(())
+}
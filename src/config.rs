@@ -0,0 +1,648 @@
+use std::net::SocketAddr;
+use std::path::{Path, PathBuf};
+use std::str::FromStr;
+use std::sync::{Arc, OnceLock, RwLock};
+
+use anyhow::{anyhow, Result};
+use serde::Deserialize;
+
+/// Read when `CONFIG_FILE` isn't set, if it exists.
+const DEFAULT_CONFIG_FILE: &str = "config.toml";
+
+/// The secret shipped in the source; fine for development, warned about at startup.
+pub const DEVELOPMENT_JWT_SECRET: &str = "your-secret-key-here-change-in-production";
+
+/// Everything the server can be configured with. Each setting starts at its default, is
+/// replaced by the TOML file (`CONFIG_FILE`, or `config.toml` when present), then by its
+/// environment variable, and the result is checked at startup. A [`reload`] later puts new
+/// log levels, quotas, CORS origins and features in effect; the rest needs a restart.
+#[derive(Debug, Clone, Default, PartialEq, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct Config {
+    pub server: ServerConfig,
+    pub limits: LimitsConfig,
+    pub quotas: QuotaConfig,
+    pub database: DatabaseConfig,
+    pub cache: CacheConfig,
+    pub storage: StorageConfig,
+    pub jwt: JwtConfig,
+    pub cors: CorsConfig,
+    pub log: LogConfig,
+    pub features: Features,
+    pub mail: MailConfig,
+    pub push: PushConfig,
+    pub market_data: MarketDataConfig,
+}
+
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct ServerConfig {
+    /// `SERVER_HOST` and `SERVER_PORT`.
+    pub host: String,
+    pub port: u16,
+}
+
+/// Bounds on what one request may take, so a huge or slow one can't starve the rest.
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct LimitsConfig {
+    /// `MAX_BODY_BYTES`: largest request body most endpoints accept.
+    pub max_body_bytes: usize,
+    /// `MAX_ARCHIVE_BODY_BYTES`: largest full data archive `/api/import/all` accepts.
+    pub max_archive_body_bytes: usize,
+    /// `REQUEST_TIMEOUT_SECS`: how long a request may take before it gets a 408. Streams
+    /// (`/ws`, `/api/events`) only count the time to their first response.
+    pub request_timeout_secs: u64,
+    /// `MAX_CONCURRENT_REQUESTS`: requests handled at once; the rest wait their turn, up to
+    /// `queue_timeout_ms`.
+    pub max_concurrent_requests: usize,
+    /// `MAX_CONCURRENT_WRITES`: requests that change data handled at once. SQLite writes one
+    /// at a time, so more only queue up on the database lock.
+    pub max_concurrent_writes: usize,
+    /// `QUEUE_TIMEOUT_MS`: how long a request waits for its turn before getting a 503.
+    pub queue_timeout_ms: u64,
+}
+
+/// What each user may use, unless an admin set their own in `user_quotas`. A 0 means no limit.
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct QuotaConfig {
+    /// `QUOTA_REQUESTS_PER_MINUTE`: authenticated requests per user per minute.
+    pub requests_per_minute: u64,
+    /// `QUOTA_SYNC_BATCH_SIZE`: changes in one `/api/sync` request.
+    pub sync_batch_size: u64,
+    /// `QUOTA_DAILY_UPLOAD_BYTES`: request body bytes per user per UTC day.
+    pub daily_upload_bytes: u64,
+}
+
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct DatabaseConfig {
+    /// `DATABASE_URL`.
+    pub url: String,
+    /// `DB_MAX_CONNECTIONS` and `DB_MIN_CONNECTIONS`.
+    pub max_connections: u32,
+    pub min_connections: u32,
+    /// `DB_CONNECT_ATTEMPTS`: tries to reach the database at startup before giving up.
+    pub connect_attempts: u32,
+    /// `SLOW_QUERY_MS`: queries taking longer are logged with their SQL; 0 turns this off.
+    pub slow_query_ms: u64,
+}
+
+/// Cached hot reads (dashboard, preferences, exchange rates); see `services::cache`.
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct CacheConfig {
+    /// `CACHE_ENABLED`.
+    pub enabled: bool,
+    /// `CACHE_TTL_SECS`: the longest an entry is served before it is read again.
+    pub ttl_secs: u64,
+    /// `CACHE_MAX_ENTRIES`: entries kept by the in-memory cache.
+    pub max_entries: usize,
+    /// `REDIS_URL`: share the cache between instances through Redis instead of keeping it in
+    /// memory. Needs a build with the `redis` feature.
+    pub redis_url: Option<String>,
+}
+
+/// Uploaded files, such as avatars; see `services::storage`.
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct StorageConfig {
+    /// `STORAGE_DIR`: the directory uploads are kept in, created when missing.
+    pub dir: PathBuf,
+}
+
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct JwtConfig {
+    /// `JWT_SECRET`.
+    pub secret: String,
+    /// `JWT_EXPIRY_HOURS`: how long a token is valid after sign-in.
+    pub expiry_hours: i64,
+}
+
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct CorsConfig {
+    /// `CORS_ORIGINS`, comma-separated. `*` allows any origin.
+    pub allowed_origins: Vec<String>,
+}
+
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct LogConfig {
+    /// `LOG_LEVEL`: a level, or filter directives such as `info,sqlx=warn`. `RUST_LOG` still
+    /// wins when set.
+    pub level: String,
+    /// `LOG_FORMAT`: `text` for people, `json` (one object per line) for log collectors.
+    pub format: String,
+}
+
+/// Background work that can be switched off, e.g. on a staging server that must not email
+/// or push to real users. `FEATURE_<NAME>=false` in the environment.
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct Features {
+    /// All background jobs; when off, none of the others run either.
+    pub background_jobs: bool,
+    pub email: bool,
+    pub push: bool,
+    pub webhooks: bool,
+    pub exchange_rates: bool,
+    pub market_prices: bool,
+}
+
+/// Outgoing email; see `services::mailer`. Delivery is off until `smtp_host` is set.
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct MailConfig {
+    /// `APP_URL`: where links in emails point. Without it, emails carry the bare code.
+    pub app_url: Option<String>,
+    /// `SMTP_HOST`, `SMTP_PORT`, `SMTP_FROM`, `SMTP_USERNAME`, `SMTP_PASSWORD`.
+    pub smtp_host: Option<String>,
+    pub smtp_port: Option<u16>,
+    pub smtp_from: Option<String>,
+    pub smtp_username: Option<String>,
+    pub smtp_password: Option<String>,
+    /// `SMTP_SECURITY`: `starttls`, `tls` or `none`.
+    pub smtp_security: String,
+}
+
+/// Push providers; see `services::push`. Each is off until its key file is set.
+#[derive(Debug, Clone, Default, PartialEq, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct PushConfig {
+    /// `FCM_SERVICE_ACCOUNT_PATH`.
+    pub fcm_service_account_path: Option<PathBuf>,
+    /// `APNS_KEY_PATH`, `APNS_KEY_ID`, `APNS_TEAM_ID`, `APNS_TOPIC` and `APNS_SANDBOX`.
+    pub apns_key_path: Option<PathBuf>,
+    pub apns_key_id: Option<String>,
+    pub apns_team_id: Option<String>,
+    pub apns_topic: Option<String>,
+    pub apns_sandbox: bool,
+}
+
+/// Price and rate providers. Each is off until its URL is set.
+#[derive(Debug, Clone, Default, PartialEq, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct MarketDataConfig {
+    /// `EXCHANGE_RATES_URL`; see `services::currency`.
+    pub exchange_rates_url: Option<String>,
+    /// `EQUITY_QUOTES_URL` and `CRYPTO_QUOTES_URL`, each with a `{symbol}` placeholder.
+    pub equity_quotes_url: Option<String>,
+    pub crypto_quotes_url: Option<String>,
+}
+
+impl Default for ServerConfig {
+    fn default() -> Self {
+        Self {
+            host: "0.0.0.0".to_string(),
+            port: 3000,
+        }
+    }
+}
+
+impl Default for LimitsConfig {
+    fn default() -> Self {
+        Self {
+            max_body_bytes: 2 * 1024 * 1024,
+            max_archive_body_bytes: 64 * 1024 * 1024,
+            request_timeout_secs: 30,
+            max_concurrent_requests: 256,
+            max_concurrent_writes: 16,
+            queue_timeout_ms: 2000,
+        }
+    }
+}
+
+impl Default for QuotaConfig {
+    fn default() -> Self {
+        Self {
+            requests_per_minute: 600,
+            sync_batch_size: 500,
+            daily_upload_bytes: 512 * 1024 * 1024,
+        }
+    }
+}
+
+impl Default for DatabaseConfig {
+    fn default() -> Self {
+        Self {
+            url: "sqlite:./personal_manager.db".to_string(),
+            max_connections: 10,
+            min_connections: 1,
+            connect_attempts: 5,
+            slow_query_ms: 250,
+        }
+    }
+}
+
+impl Default for CacheConfig {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            ttl_secs: 300,
+            max_entries: 10_000,
+            redis_url: None,
+        }
+    }
+}
+
+impl Default for StorageConfig {
+    fn default() -> Self {
+        Self {
+            dir: PathBuf::from("./uploads"),
+        }
+    }
+}
+
+impl Default for JwtConfig {
+    fn default() -> Self {
+        Self {
+            secret: DEVELOPMENT_JWT_SECRET.to_string(),
+            expiry_hours: 24,
+        }
+    }
+}
+
+impl Default for CorsConfig {
+    fn default() -> Self {
+        Self {
+            allowed_origins: vec!["*".to_string()],
+        }
+    }
+}
+
+impl Default for LogConfig {
+    fn default() -> Self {
+        Self {
+            level: "info".to_string(),
+            format: "text".to_string(),
+        }
+    }
+}
+
+impl Default for Features {
+    fn default() -> Self {
+        Self {
+            background_jobs: true,
+            email: true,
+            push: true,
+            webhooks: true,
+            exchange_rates: true,
+            market_prices: true,
+        }
+    }
+}
+
+impl Default for MailConfig {
+    fn default() -> Self {
+        Self {
+            app_url: None,
+            smtp_host: None,
+            smtp_port: None,
+            smtp_from: None,
+            smtp_username: None,
+            smtp_password: None,
+            smtp_security: "starttls".to_string(),
+        }
+    }
+}
+
+impl ServerConfig {
+    pub fn bind_address(&self) -> Result<SocketAddr> {
+        format!("{}:{}", self.host, self.port)
+            .parse()
+            .map_err(|_| anyhow!("server.host '{}' is not an IP address", self.host))
+    }
+}
+
+impl CorsConfig {
+    pub fn allows_any_origin(&self) -> bool {
+        self.allowed_origins.iter().any(|origin| origin == "*")
+    }
+}
+
+impl Features {
+    pub fn enabled(&self, feature: bool) -> bool {
+        self.background_jobs && feature
+    }
+}
+
+/// Environment variables, collecting the ones that don't parse instead of stopping at the first.
+struct Env {
+    errors: Vec<String>,
+}
+
+impl Env {
+    fn string(&mut self, name: &str, target: &mut String) {
+        if let Ok(value) = std::env::var(name) {
+            *target = value;
+        }
+    }
+
+    /// An empty value counts as unset, as `KEY=` lines in compose files produce.
+    fn optional<T: FromStr>(&mut self, name: &str, target: &mut Option<T>) {
+        if let Some(value) = std::env::var(name).ok().filter(|value| !value.is_empty()) {
+            match value.parse() {
+                Ok(parsed) => *target = Some(parsed),
+                Err(_) => self.errors.push(format!("{}='{}' is not valid", name, value)),
+            }
+        }
+    }
+
+    fn parsed<T: FromStr>(&mut self, name: &str, target: &mut T) {
+        let mut value = None;
+        self.optional(name, &mut value);
+        if let Some(value) = value {
+            *target = value;
+        }
+    }
+
+    fn flag(&mut self, name: &str, target: &mut bool) {
+        if let Ok(value) = std::env::var(name) {
+            match value.to_lowercase().as_str() {
+                "1" | "true" | "yes" | "on" => *target = true,
+                "0" | "false" | "no" | "off" => *target = false,
+                _ => self.errors.push(format!("{}='{}' is not true or false", name, value)),
+            }
+        }
+    }
+}
+
+impl Config {
+    /// Defaults, then the config file, then the environment, validated.
+    pub fn load() -> Result<Self> {
+        let mut config = match std::env::var("CONFIG_FILE") {
+            Ok(path) => Self::from_file(Path::new(&path))?,
+            Err(_) if Path::new(DEFAULT_CONFIG_FILE).exists() => Self::from_file(Path::new(DEFAULT_CONFIG_FILE))?,
+            Err(_) => Self::default(),
+        };
+
+        let mut env = Env { errors: Vec::new() };
+        config.apply_env(&mut env);
+        let mut errors = env.errors;
+        errors.extend(config.problems());
+        if errors.is_empty() {
+            Ok(config)
+        } else {
+            Err(anyhow!("invalid configuration:\n  - {}", errors.join("\n  - ")))
+        }
+    }
+
+    pub fn from_file(path: &Path) -> Result<Self> {
+        let text = std::fs::read_to_string(path).map_err(|e| anyhow!("can't read {}: {}", path.display(), e))?;
+        toml::from_str(&text).map_err(|e| anyhow!("invalid {}: {}", path.display(), e))
+    }
+
+    fn apply_env(&mut self, env: &mut Env) {
+        env.string("SERVER_HOST", &mut self.server.host);
+        env.parsed("SERVER_PORT", &mut self.server.port);
+
+        env.parsed("MAX_BODY_BYTES", &mut self.limits.max_body_bytes);
+        env.parsed("MAX_ARCHIVE_BODY_BYTES", &mut self.limits.max_archive_body_bytes);
+        env.parsed("REQUEST_TIMEOUT_SECS", &mut self.limits.request_timeout_secs);
+        env.parsed("MAX_CONCURRENT_REQUESTS", &mut self.limits.max_concurrent_requests);
+        env.parsed("MAX_CONCURRENT_WRITES", &mut self.limits.max_concurrent_writes);
+        env.parsed("QUEUE_TIMEOUT_MS", &mut self.limits.queue_timeout_ms);
+
+        env.parsed("QUOTA_REQUESTS_PER_MINUTE", &mut self.quotas.requests_per_minute);
+        env.parsed("QUOTA_SYNC_BATCH_SIZE", &mut self.quotas.sync_batch_size);
+        env.parsed("QUOTA_DAILY_UPLOAD_BYTES", &mut self.quotas.daily_upload_bytes);
+
+        env.string("DATABASE_URL", &mut self.database.url);
+        env.parsed("DB_MAX_CONNECTIONS", &mut self.database.max_connections);
+        env.parsed("DB_MIN_CONNECTIONS", &mut self.database.min_connections);
+        env.parsed("DB_CONNECT_ATTEMPTS", &mut self.database.connect_attempts);
+        env.parsed("SLOW_QUERY_MS", &mut self.database.slow_query_ms);
+
+        env.flag("CACHE_ENABLED", &mut self.cache.enabled);
+        env.parsed("CACHE_TTL_SECS", &mut self.cache.ttl_secs);
+        env.parsed("CACHE_MAX_ENTRIES", &mut self.cache.max_entries);
+        env.optional("REDIS_URL", &mut self.cache.redis_url);
+
+        env.parsed("STORAGE_DIR", &mut self.storage.dir);
+
+        env.string("JWT_SECRET", &mut self.jwt.secret);
+        env.parsed("JWT_EXPIRY_HOURS", &mut self.jwt.expiry_hours);
+
+        if let Ok(origins) = std::env::var("CORS_ORIGINS") {
+            self.cors.allowed_origins = origins
+                .split(',')
+                .map(str::trim)
+                .filter(|origin| !origin.is_empty())
+                .map(str::to_string)
+                .collect();
+        }
+
+        env.string("LOG_LEVEL", &mut self.log.level);
+        env.string("LOG_FORMAT", &mut self.log.format);
+
+        env.flag("FEATURE_BACKGROUND_JOBS", &mut self.features.background_jobs);
+        env.flag("FEATURE_EMAIL", &mut self.features.email);
+        env.flag("FEATURE_PUSH", &mut self.features.push);
+        env.flag("FEATURE_WEBHOOKS", &mut self.features.webhooks);
+        env.flag("FEATURE_EXCHANGE_RATES", &mut self.features.exchange_rates);
+        env.flag("FEATURE_MARKET_PRICES", &mut self.features.market_prices);
+
+        env.optional("APP_URL", &mut self.mail.app_url);
+        env.optional("SMTP_HOST", &mut self.mail.smtp_host);
+        env.optional("SMTP_PORT", &mut self.mail.smtp_port);
+        env.optional("SMTP_FROM", &mut self.mail.smtp_from);
+        env.optional("SMTP_USERNAME", &mut self.mail.smtp_username);
+        env.optional("SMTP_PASSWORD", &mut self.mail.smtp_password);
+        env.string("SMTP_SECURITY", &mut self.mail.smtp_security);
+
+        env.optional("FCM_SERVICE_ACCOUNT_PATH", &mut self.push.fcm_service_account_path);
+        env.optional("APNS_KEY_PATH", &mut self.push.apns_key_path);
+        env.optional("APNS_KEY_ID", &mut self.push.apns_key_id);
+        env.optional("APNS_TEAM_ID", &mut self.push.apns_team_id);
+        env.optional("APNS_TOPIC", &mut self.push.apns_topic);
+        env.flag("APNS_SANDBOX", &mut self.push.apns_sandbox);
+
+        env.optional("EXCHANGE_RATES_URL", &mut self.market_data.exchange_rates_url);
+        env.optional("EQUITY_QUOTES_URL", &mut self.market_data.equity_quotes_url);
+        env.optional("CRYPTO_QUOTES_URL", &mut self.market_data.crypto_quotes_url);
+    }
+
+    /// What is wrong with the settings, one line each.
+    pub fn problems(&self) -> Vec<String> {
+        let mut problems = Vec::new();
+        let mut check = |valid: bool, problem: String| {
+            if !valid {
+                problems.push(problem);
+            }
+        };
+
+        if let Err(e) = self.server.bind_address() {
+            check(false, e.to_string());
+        }
+
+        check(self.limits.max_body_bytes > 0, "limits.max_body_bytes must be positive".to_string());
+        check(
+            self.limits.max_archive_body_bytes >= self.limits.max_body_bytes,
+            "limits.max_archive_body_bytes must be at least limits.max_body_bytes".to_string(),
+        );
+        check(self.limits.request_timeout_secs > 0, "limits.request_timeout_secs must be positive".to_string());
+        check(self.limits.max_concurrent_requests > 0, "limits.max_concurrent_requests must be positive".to_string());
+        check(
+            (1..=self.limits.max_concurrent_requests).contains(&self.limits.max_concurrent_writes),
+            "limits.max_concurrent_writes must be between 1 and limits.max_concurrent_requests".to_string(),
+        );
+
+        check(!self.database.url.is_empty(), "database.url must be set".to_string());
+        check(self.database.max_connections >= 1, "database.max_connections must be at least 1".to_string());
+        check(
+            self.database.min_connections <= self.database.max_connections,
+            "database.min_connections must not exceed database.max_connections".to_string(),
+        );
+        check(self.database.connect_attempts >= 1, "database.connect_attempts must be at least 1".to_string());
+
+        check(self.cache.ttl_secs > 0, "cache.ttl_secs must be positive".to_string());
+        check(self.cache.max_entries > 0, "cache.max_entries must be positive".to_string());
+        if let Some(url) = &self.cache.redis_url {
+            check(
+                url.starts_with("redis://"),
+                format!("cache.redis_url '{}' must start with redis://", url),
+            );
+            check(
+                cfg!(feature = "redis"),
+                "cache.redis_url is set but this build has no Redis support (build with --features redis)".to_string(),
+            );
+        }
+
+        check(!self.storage.dir.as_os_str().is_empty(), "storage.dir must be set".to_string());
+
+        check(!self.jwt.secret.is_empty(), "jwt.secret must be set".to_string());
+        check(self.jwt.expiry_hours > 0, "jwt.expiry_hours must be positive".to_string());
+
+        check(!self.cors.allowed_origins.is_empty(), "cors.allowed_origins must list at least one origin, or *".to_string());
+        for origin in &self.cors.allowed_origins {
+            check(
+                origin == "*" || origin.starts_with("http://") || origin.starts_with("https://"),
+                format!("cors.allowed_origins: '{}' is not an http(s) origin", origin),
+            );
+        }
+
+        check(
+            tracing_subscriber::filter::LevelFilter::from_str(&self.log.level).is_ok()
+                || (self.log.level.contains('=') && tracing_subscriber::EnvFilter::try_new(&self.log.level).is_ok()),
+            format!("log.level '{}' is not a level (off, error, warn, info, debug, trace) or filter", self.log.level),
+        );
+        check(
+            ["text", "json"].contains(&self.log.format.as_str()),
+            format!("log.format '{}' must be text or json", self.log.format),
+        );
+
+        check(
+            ["starttls", "tls", "none"].contains(&self.mail.smtp_security.as_str()),
+            format!("mail.smtp_security '{}' must be starttls, tls or none", self.mail.smtp_security),
+        );
+        if self.mail.smtp_host.is_some() {
+            match &self.mail.smtp_from {
+                Some(from) => check(
+                    from.parse::<lettre::message::Mailbox>().is_ok(),
+                    format!("mail.smtp_from '{}' is not an email address", from),
+                ),
+                None => check(false, "mail.smtp_from must be set along with mail.smtp_host".to_string()),
+            }
+        }
+
+        if self.push.apns_key_path.is_some() {
+            check(self.push.apns_key_id.is_some(), "push.apns_key_id must be set along with push.apns_key_path".to_string());
+            check(self.push.apns_team_id.is_some(), "push.apns_team_id must be set along with push.apns_key_path".to_string());
+            check(self.push.apns_topic.is_some(), "push.apns_topic must be set along with push.apns_key_path".to_string());
+        }
+
+        for (name, url) in [
+            ("market_data.equity_quotes_url", &self.market_data.equity_quotes_url),
+            ("market_data.crypto_quotes_url", &self.market_data.crypto_quotes_url),
+        ] {
+            if let Some(url) = url {
+                check(url.contains("{symbol}"), format!("{} must contain {{symbol}}", name));
+            }
+        }
+
+        problems
+    }
+}
+
+static CONFIG: OnceLock<Config> = OnceLock::new();
+
+static CURRENT: OnceLock<RwLock<Arc<Config>>> = OnceLock::new();
+
+/// Makes `config` the one [`get`] returns. Call once, before anything reads it.
+pub fn init(config: Config) -> &'static Config {
+    if CONFIG.set(config).is_err() {
+        tracing::warn!("Configuration was already set; keeping the first one");
+    }
+    get()
+}
+
+/// The configuration the server started with, or the defaults when [`init`] hasn't been
+/// called, as in tests. Settings that can be reloaded are read through [`current`] instead.
+pub fn get() -> &'static Config {
+    CONFIG.get_or_init(Config::default)
+}
+
+fn live() -> &'static RwLock<Arc<Config>> {
+    CURRENT.get_or_init(|| RwLock::new(Arc::new(get().clone())))
+}
+
+/// The configuration in effect: [`get`] with the reloadable sections as of the last reload.
+pub fn current() -> Arc<Config> {
+    live().read().expect("configuration lock poisoned").clone()
+}
+
+/// What a reload did, by section (`log.level` and `log.format` apart, as only one reloads).
+#[derive(Debug, Default, PartialEq)]
+pub struct Reloaded {
+    /// Sections whose new values are now in effect.
+    pub applied: Vec<&'static str>,
+    /// Sections that changed but keep their running values until a restart.
+    pub needs_restart: Vec<&'static str>,
+}
+
+/// Loads the configuration again, as at startup, and [`apply`]s it. An invalid one is
+/// rejected whole, leaving the running settings alone.
+pub fn reload() -> Result<Reloaded> {
+    Ok(apply(Config::load()?))
+}
+
+/// Puts the reloadable sections of `loaded` in effect and reports what changed.
+pub fn apply(loaded: Config) -> Reloaded {
+    fn swap<T: PartialEq>(name: &'static str, running: &mut T, loaded: T, applied: &mut Vec<&'static str>) {
+        if *running != loaded {
+            *running = loaded;
+            applied.push(name);
+        }
+    }
+
+    let mut live = live().write().expect("configuration lock poisoned");
+    let mut next = Config::clone(&live);
+    let mut reloaded = Reloaded::default();
+    swap("log.level", &mut next.log.level, loaded.log.level, &mut reloaded.applied);
+    swap("quotas", &mut next.quotas, loaded.quotas, &mut reloaded.applied);
+    swap("cors", &mut next.cors, loaded.cors, &mut reloaded.applied);
+    swap("features", &mut next.features, loaded.features, &mut reloaded.applied);
+
+    for (name, changed) in [
+        ("server", next.server != loaded.server),
+        ("limits", next.limits != loaded.limits),
+        ("database", next.database != loaded.database),
+        ("cache", next.cache != loaded.cache),
+        ("storage", next.storage != loaded.storage),
+        ("jwt", next.jwt != loaded.jwt),
+        ("log.format", next.log.format != loaded.log.format),
+        ("mail", next.mail != loaded.mail),
+        ("push", next.push != loaded.push),
+        ("market_data", next.market_data != loaded.market_data),
+    ] {
+        if changed {
+            reloaded.needs_restart.push(name);
+        }
+    }
+
+    *live = Arc::new(next);
+    reloaded
+}
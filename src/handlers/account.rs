@@ -1,39 +1,61 @@
 use axum::{
-    extract::{Path, State},
-    http::StatusCode,
-    response::Json,
+    extract::{Path, Query, State},
+    http::HeaderMap,
+    response::{Json, Response},
 };
-use serde_json::{json, Value};
+use serde_json::json;
 use chrono::Utc;
-use sqlx::Row;
 
-use crate::models::{Account, CreateAccountRequest, UpdateAccountRequest};
-use crate::services::DbPool;
+use crate::models::{Account, CardStatement, CreateAccountRequest, ReorderAccountsRequest, UpdateAccountRequest};
+use crate::services::{card_statements, concurrency, credit_utilization, etags, history::{self, EntityKind}, households, DbPool};
 use crate::middleware::auth::AuthUser;
+use crate::middleware::device::ClientDevice;
+use crate::utils::response::ApiResponse;
+use crate::utils::error::AppError;
+use crate::utils::pagination::{PageQuery, Paginated};
+use crate::utils::validation::Validate;
 
 pub async fn create_account(
     State(pool): State<DbPool>,
     auth_user: AuthUser,
+    device: ClientDevice,
     Json(request): Json<CreateAccountRequest>,
-) -> Result<Json<Value>, StatusCode> {
-    log::info!("📥 POST /accounts - Creating account for user {}", auth_user.user_id);
-    log::info!("✅ Successfully parsed request: {:?}", request);
+) -> Result<ApiResponse, AppError> {
+    request.validate()?;
 
-    let account = Account::new(request.clone(), auth_user.user_id.clone());
-    let account_type_str = format!("{:?}", account.account_type).to_lowercase();
+    let mut account = Account::new(request.clone(), auth_user.user_id.clone());
+    // New accounts go to the end of the list the user arranged
+    account.sort_order = sqlx::query_scalar("SELECT COALESCE(MAX(sort_order) + 1, 0) FROM accounts WHERE user_id = ?")
+        .bind(&account.user_id)
+        .fetch_one(&pool)
+        .await
+        .map_err(|e| {
+            tracing::error!("Failed to get the next account position: {}", e);
+            AppError::Internal("Failed to create account".into())
+        })?;
     let created_at_str = account.created_at.format("%Y-%m-%d %H:%M:%S").to_string();
     let updated_at_str = account.updated_at.format("%Y-%m-%d %H:%M:%S").to_string();
 
     let result = sqlx::query(
-        "INSERT INTO accounts (id, user_id, name, account_type, balance, currency, credit_limit, created_at, updated_at) VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?)"
+        "INSERT INTO accounts (id, user_id, name, account_type, balance, currency, credit_limit, exclude_from_totals, statement_day, payment_due_day, sort_order, is_favorite, icon, color, institution_name, account_number_masked, notes, created_at, updated_at) VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?)"
     )
     .bind(&account.id)
     .bind(&account.user_id)
     .bind(&account.name)
-    .bind(&account_type_str)
+    .bind(account.account_type)
     .bind(account.balance)
     .bind(&account.currency)
     .bind(account.credit_limit)
+    .bind(account.exclude_from_totals)
+    .bind(account.statement_day)
+    .bind(account.payment_due_day)
+    .bind(account.sort_order)
+    .bind(account.is_favorite)
+    .bind(&account.icon)
+    .bind(&account.color)
+    .bind(&account.institution_name)
+    .bind(&account.account_number_masked)
+    .bind(&account.notes)
     .bind(&created_at_str)
     .bind(&updated_at_str)
     .execute(&pool)
@@ -41,67 +63,73 @@ pub async fn create_account(
 
     match result {
         Ok(_) => {
-            log::info!("✅ Account created successfully: {} ({})", account.name, account.id);
-            Ok(Json(json!({
-                "success": true,
-                "data": account
-            })))
+            history::record(&pool, EntityKind::Account, &account.id, &auth_user.user_id, "created", None, &device).await;
+            Ok(ApiResponse::ok(json!(account)))
         }
         Err(e) => {
-            log::error!("❌ Failed to create account: {}", e);
-            log::error!("Database error details: {:?}", e);
-            log::error!("Raw request data: {:?}", request);
+            tracing::error!("Failed to create account: {}", e);
 
             let error_msg = e.to_string();
             if error_msg.contains("UNIQUE constraint failed: accounts.id") {
-                log::warn!("⚠️  Account with ID {} already exists", account.id);
-                Err(StatusCode::CONFLICT)
+                Err(AppError::Conflict("Account already exists".into()))
             } else {
-                Err(StatusCode::INTERNAL_SERVER_ERROR)
+                Err(AppError::Internal("Failed to create account".into()))
             }
         }
     }
 }
 
+/// One page of the accounts `user_id` can see, in their list order.
+pub async fn accounts_page(pool: &DbPool, user_id: &str, page: &PageQuery) -> Result<Paginated<Account>, sqlx::Error> {
+    let accounts = sqlx::query_as::<_, Account>(&format!(
+        "SELECT * FROM accounts WHERE {} ORDER BY sort_order, created_at DESC, id LIMIT ? OFFSET ?",
+        households::visible_accounts_filter()
+    ))
+    .bind(user_id)
+    .bind(user_id)
+    .bind(page.limit() as i64)
+    .bind(page.offset() as i64)
+    .fetch_all(pool)
+    .await?;
+
+    let total = sqlx::query_scalar::<_, i64>(&format!(
+        "SELECT COUNT(*) FROM accounts WHERE {}",
+        households::visible_accounts_filter()
+    ))
+    .bind(user_id)
+    .bind(user_id)
+    .fetch_one(pool)
+    .await?;
+
+    Ok(Paginated::new(accounts, total as usize, page))
+}
+
+/// Answers 304 when `If-None-Match` holds the list's current `ETag`.
 pub async fn get_accounts(
     State(pool): State<DbPool>,
     auth_user: AuthUser,
-) -> Result<Json<Value>, StatusCode> {
-    log::info!("📥 GET /accounts - Fetching accounts for user {}", auth_user.user_id);
+    headers: HeaderMap,
+    Query(page): Query<PageQuery>,
+) -> Result<Response, AppError> {
+    let etag = etags::list_etag(&pool, EntityKind::Account, &auth_user.user_id).await.map_err(|e| {
+        tracing::error!("Failed to compute the accounts ETag: {}", e);
+        AppError::Internal("Failed to compute the accounts ETag".into())
+    })?;
+    let etag = etags::for_page(&etag, &page);
+    if etags::is_fresh(&headers, &etag) {
+        tracing::info!("Accounts unchanged for user {}", auth_user.user_id);
+        return Ok(etags::not_modified(&etag));
+    }
 
-    let result = sqlx::query(
-        "SELECT id, user_id, name, account_type, balance, currency, credit_limit, created_at, updated_at FROM accounts WHERE user_id = ? ORDER BY created_at DESC"
-    )
-    .bind(&auth_user.user_id)
-    .fetch_all(&pool)
-    .await;
+    let result = accounts_page(&pool, &auth_user.user_id, &page).await;
 
     match result {
-        Ok(rows) => {
-            let accounts: Vec<_> = rows.into_iter().map(|row| {
-                json!({
-                    "id": row.get::<String, _>("id"),
-                    "userId": row.get::<String, _>("user_id"),
-                    "name": row.get::<String, _>("name"),
-                    "type": row.get::<String, _>("account_type"),
-                    "balance": row.get::<f64, _>("balance"),
-                    "currency": row.get::<String, _>("currency"),
-                    "creditLimit": row.get::<Option<f64>, _>("credit_limit"),
-                    "createdAt": row.get::<String, _>("created_at"),
-                    "updatedAt": row.get::<String, _>("updated_at")
-                })
-            }).collect();
-
-            log::info!("✅ Found {} accounts", accounts.len());
-            Ok(Json(json!({
-                "success": true,
-                "data": accounts
-            })))
+        Ok(accounts) => {
+            Ok(etags::tagged(&etag, ApiResponse::ok(json!(accounts))))
         }
         Err(e) => {
-            log::error!("❌ Failed to get accounts: {}", e);
-            log::error!("Database error details: {:?}", e);
-            Err(StatusCode::INTERNAL_SERVER_ERROR)
+            tracing::error!("Failed to get accounts: {}", e);
+            Err(AppError::Internal("Failed to get accounts".into()))
         }
     }
 }
@@ -110,46 +138,25 @@ pub async fn get_account(
     Path(id): Path<String>,
     State(pool): State<DbPool>,
     auth_user: AuthUser,
-) -> Result<Json<Value>, StatusCode> {
-    log::info!("📥 GET /accounts/{} - Fetching account by ID", id);
-
-    let result = sqlx::query(
-        "SELECT id, user_id, name, account_type, balance, currency, credit_limit, created_at, updated_at FROM accounts WHERE id = ? AND user_id = ?"
-    )
+) -> Result<ApiResponse, AppError> {
+    let result = sqlx::query_as::<_, Account>(&format!(
+        "SELECT * FROM accounts WHERE id = ? AND {}",
+        households::visible_accounts_filter()
+    ))
     .bind(&id)
     .bind(&auth_user.user_id)
+    .bind(&auth_user.user_id)
     .fetch_optional(&pool)
     .await;
 
     match result {
-        Ok(Some(row)) => {
-            let account_name = row.get::<String, _>("name");
-            let account = json!({
-                "id": row.get::<String, _>("id"),
-                "userId": row.get::<String, _>("user_id"),
-                "name": account_name,
-                "type": row.get::<String, _>("account_type"),
-                "balance": row.get::<f64, _>("balance"),
-                "currency": row.get::<String, _>("currency"),
-                "creditLimit": row.get::<Option<f64>, _>("credit_limit"),
-                "createdAt": row.get::<String, _>("created_at"),
-                "updatedAt": row.get::<String, _>("updated_at")
-            });
-
-            log::info!("✅ Found account: {}", account_name);
-            Ok(Json(json!({
-                "success": true,
-                "data": account
-            })))
+        Ok(Some(account)) => {
+            Ok(ApiResponse::ok(json!(account)))
         }
-        Ok(None) => {
-            log::warn!("⚠️  Account not found with ID: {}", id);
-            Err(StatusCode::NOT_FOUND)
-        },
+        Ok(None) => Err(AppError::NotFound("Account not found".into())),
         Err(e) => {
-            log::error!("❌ Failed to get account {}: {}", id, e);
-            log::error!("Database error details: {:?}", e);
-            Err(StatusCode::INTERNAL_SERVER_ERROR)
+            tracing::error!("Failed to get account {}: {}", id, e);
+            Err(AppError::Internal("Failed to get account".into()))
         }
     }
 }
@@ -158,45 +165,61 @@ pub async fn update_account(
     Path(id): Path<String>,
     State(pool): State<DbPool>,
     auth_user: AuthUser,
+    device: ClientDevice,
+    headers: HeaderMap,
     Json(request): Json<UpdateAccountRequest>,
-) -> Result<Json<Value>, StatusCode> {
-    log::info!("📥 PUT /accounts/{} - Updating account", id);
-    log::debug!("Update request: {:?}", request);
+) -> Result<Response, AppError> {
+    request.validate()?;
+
+    let before = history::snapshot(&pool, EntityKind::Account, &id, &auth_user.user_id).await;
+    let version = concurrency::expected_version(&headers, request.version)?;
 
     let now = Utc::now().format("%Y-%m-%d %H:%M:%S").to_string();
-    let account_type_str = request.account_type.map(|t| format!("{:?}", t).to_lowercase());
 
     let result = sqlx::query(
-        "UPDATE accounts SET name = COALESCE(?, name), account_type = COALESCE(?, account_type), balance = COALESCE(?, balance), currency = COALESCE(?, currency), credit_limit = COALESCE(?, credit_limit), updated_at = ? WHERE id = ? AND user_id = ?"
+        "UPDATE accounts SET name = COALESCE(?, name), account_type = COALESCE(?, account_type), balance = COALESCE(?, balance), currency = COALESCE(?, currency), credit_limit = COALESCE(?, credit_limit), exclude_from_totals = COALESCE(?, exclude_from_totals), statement_day = COALESCE(?, statement_day), payment_due_day = COALESCE(?, payment_due_day), is_favorite = COALESCE(?, is_favorite), icon = CASE WHEN ? THEN ? ELSE icon END, color = CASE WHEN ? THEN ? ELSE color END, institution_name = CASE WHEN ? THEN ? ELSE institution_name END, account_number_masked = CASE WHEN ? THEN ? ELSE account_number_masked END, notes = CASE WHEN ? THEN ? ELSE notes END, updated_at = ? WHERE id = ? AND user_id = ? AND version = ?"
     )
     .bind(request.name.as_ref())
-    .bind(account_type_str)
+    .bind(request.account_type)
     .bind(request.balance)
     .bind(request.currency.as_ref())
     .bind(request.credit_limit)
+    .bind(request.exclude_from_totals)
+    .bind(request.statement_day)
+    .bind(request.payment_due_day)
+    .bind(request.is_favorite)
+    .bind(request.icon.is_some())
+    .bind(request.icon.flatten())
+    .bind(request.color.is_some())
+    .bind(request.color.flatten())
+    .bind(request.institution_name.is_some())
+    .bind(request.institution_name.flatten())
+    .bind(request.account_number_masked.is_some())
+    .bind(request.account_number_masked.flatten())
+    .bind(request.notes.is_some())
+    .bind(request.notes.flatten())
     .bind(&now)
     .bind(&id)
     .bind(&auth_user.user_id)
+    .bind(version)
     .execute(&pool)
     .await;
 
     match result {
         Ok(result) => {
             if result.rows_affected() == 0 {
-                log::warn!("⚠️  Account not found for update: {}", id);
-                Err(StatusCode::NOT_FOUND)
+                concurrency::rejected_update(&pool, EntityKind::Account, &id, &auth_user.user_id, version).await
             } else {
-                log::info!("✅ Account updated successfully: {}", id);
-                Ok(Json(json!({
-                    "success": true,
-                    "message": "Account updated successfully"
-                })))
+                history::record(&pool, EntityKind::Account, &id, &auth_user.user_id, "updated", before, &device).await;
+                if let Err(e) = credit_utilization::notify_utilization(&pool, &auth_user.user_id, &id).await {
+                    tracing::error!("Failed to check credit utilization for account {}: {}", id, e);
+                }
+                Ok(concurrency::updated(&pool, EntityKind::Account, &id, "Account updated successfully").await)
             }
         }
         Err(e) => {
-            log::error!("❌ Failed to update account {}: {}", id, e);
-            log::error!("Database error details: {:?}", e);
-            Err(StatusCode::INTERNAL_SERVER_ERROR)
+            tracing::error!("Failed to update account {}: {}", id, e);
+            Err(AppError::Internal("Failed to update account".into()))
         }
     }
 }
@@ -205,8 +228,9 @@ pub async fn delete_account(
     Path(id): Path<String>,
     State(pool): State<DbPool>,
     auth_user: AuthUser,
-) -> Result<Json<Value>, StatusCode> {
-    log::info!("📥 DELETE /accounts/{} - Deleting account", id);
+    device: ClientDevice,
+) -> Result<ApiResponse, AppError> {
+    let before = history::snapshot(&pool, EntityKind::Account, &id, &auth_user.user_id).await;
 
     let result = sqlx::query("DELETE FROM accounts WHERE id = ? AND user_id = ?")
         .bind(&id)
@@ -217,20 +241,128 @@ pub async fn delete_account(
     match result {
         Ok(result) => {
             if result.rows_affected() == 0 {
-                log::warn!("⚠️  Account not found for deletion: {}", id);
-                Err(StatusCode::NOT_FOUND)
+                Err(AppError::NotFound("Account not found".into()))
             } else {
-                log::info!("✅ Account deleted successfully: {}", id);
-                Ok(Json(json!({
-                    "success": true,
-                    "message": "Account deleted successfully"
-                })))
+                history::record(&pool, EntityKind::Account, &id, &auth_user.user_id, "deleted", before, &device).await;
+                Ok(ApiResponse::message("Account deleted successfully"))
             }
         }
         Err(e) => {
-            log::error!("❌ Failed to delete account {}: {}", id, e);
-            log::error!("Database error details: {:?}", e);
-            Err(StatusCode::INTERNAL_SERVER_ERROR)
+            tracing::error!("Failed to delete account {}: {}", id, e);
+            Err(AppError::Internal("Failed to delete account".into()))
         }
     }
 }
+
+/// Arranges the user's own accounts in the order given. Only accounts whose position changes
+/// are touched, so other devices pull just those.
+pub async fn reorder_accounts(
+    State(pool): State<DbPool>,
+    auth_user: AuthUser,
+    Json(request): Json<ReorderAccountsRequest>,
+) -> Result<ApiResponse, AppError> {
+    request.validate()?;
+
+    let internal = |e: sqlx::Error| {
+        tracing::error!("Failed to reorder accounts for user {}: {}", auth_user.user_id, e);
+        AppError::Internal("Failed to reorder accounts".into())
+    };
+    let mut tx = pool.begin().await.map_err(internal)?;
+    let current = sqlx::query_as::<_, (String, i64)>(
+        "SELECT id, sort_order FROM accounts WHERE user_id = ? ORDER BY sort_order, created_at DESC"
+    )
+    .bind(&auth_user.user_id)
+    .fetch_all(&mut tx)
+    .await
+    .map_err(internal)?;
+
+    if let Some(unknown) = request.account_ids.iter().find(|id| !current.iter().any(|(own, _)| own == *id)) {
+        return Err(AppError::NotFound(format!("Account {} not found", unknown)));
+    }
+    let rest = current.iter().filter(|(id, _)| !request.account_ids.contains(id));
+    let positions: Vec<&String> = request.account_ids.iter().chain(rest.map(|(id, _)| id)).collect();
+
+    let now = Utc::now().format("%Y-%m-%d %H:%M:%S").to_string();
+    for (position, id) in positions.into_iter().enumerate() {
+        let position = position as i64;
+        if current.iter().any(|(own, order)| own == id && *order == position) {
+            continue;
+        }
+        sqlx::query("UPDATE accounts SET sort_order = ?, updated_at = ? WHERE id = ? AND user_id = ?")
+            .bind(position)
+            .bind(&now)
+            .bind(id)
+            .bind(&auth_user.user_id)
+            .execute(&mut tx)
+            .await
+            .map_err(internal)?;
+    }
+
+    let accounts = sqlx::query_as::<_, Account>("SELECT * FROM accounts WHERE user_id = ? ORDER BY sort_order")
+        .bind(&auth_user.user_id)
+        .fetch_all(&mut tx)
+        .await
+        .map_err(internal)?;
+    tx.commit().await.map_err(internal)?;
+
+    Ok(ApiResponse::ok(json!(accounts)))
+}
+
+/// Closed billing cycles of a credit card, newest first. Empty until the card has statement
+/// and payment due days. Household members sharing the card see the owner's statements.
+pub async fn get_account_statements(
+    Path(id): Path<String>,
+    State(pool): State<DbPool>,
+    auth_user: AuthUser,
+    Query(page): Query<PageQuery>,
+) -> Result<ApiResponse, AppError> {
+    let access = households::account_access(&pool, &id, &auth_user.user_id)
+        .await
+        .map_err(|e| {
+            tracing::error!("Failed to get account {}: {}", id, e);
+            AppError::Internal("Failed to get account".into())
+        })?
+        .ok_or_else(|| AppError::NotFound("Account not found".into()))?;
+
+    let card = card_statements::billing_cycle(&pool, &id, &access.owner_id)
+        .await
+        .map_err(|e| {
+            tracing::error!("Failed to get billing cycle for {}: {}", id, e);
+            AppError::Internal("Failed to get billing cycle".into())
+        })?;
+    if let Some(card) = &card {
+        if let Err(e) = card_statements::sync_statements(&pool, card).await {
+            tracing::error!("Failed to sync statements for {}: {}", id, e);
+            return Err(AppError::Internal("Failed to sync statements".into()));
+        }
+    }
+
+    let statements = sqlx::query_as::<_, CardStatement>(
+        "SELECT * FROM card_statements WHERE account_id = ? AND user_id = ? ORDER BY period_end DESC, id LIMIT ? OFFSET ?"
+    )
+    .bind(&id)
+    .bind(&access.owner_id)
+    .bind(page.limit() as i64)
+    .bind(page.offset() as i64)
+    .fetch_all(&pool)
+    .await
+    .map_err(|e| {
+        tracing::error!("Failed to get statements for {}: {}", id, e);
+        AppError::Internal("Failed to get statements".into())
+    })?;
+    let total = sqlx::query_scalar::<_, i64>("SELECT COUNT(*) FROM card_statements WHERE account_id = ? AND user_id = ?")
+        .bind(&id)
+        .bind(&access.owner_id)
+        .fetch_one(&pool)
+        .await
+        .map_err(|e| {
+            tracing::error!("Failed to count statements for {}: {}", id, e);
+            AppError::Internal("Failed to get statements".into())
+        })?;
+
+    let data = card_statements::statuses(&pool, &id, statements).await.map_err(|e| {
+        tracing::error!("Failed to get statement payments for {}: {}", id, e);
+        AppError::Internal("Failed to get statement payments".into())
+    })?;
+    Ok(ApiResponse::ok(json!(Paginated::new(data, total as usize, &page))))
+}
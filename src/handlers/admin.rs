@@ -0,0 +1,65 @@
+use axum::{
+    extract::{Path, State},
+    response::Json,
+};
+use serde_json::json;
+
+use crate::models::UpdateUserQuotaRequest;
+use crate::services::{admin, quotas, reload, DbPool};
+use crate::middleware::auth::AdminUser;
+use crate::utils::response::ApiResponse;
+use crate::utils::error::AppError;
+
+pub async fn get_admin_stats(
+    State(pool): State<DbPool>,
+    _admin: AdminUser,
+) -> Result<ApiResponse, AppError> {
+    let stats = admin::stats(&pool).await.map_err(|e| {
+        tracing::error!("Failed to compute admin stats: {}", e);
+        AppError::Internal("Failed to compute admin stats".into())
+    })?;
+
+    Ok(ApiResponse::ok(json!(stats)))
+}
+
+/// Sets one user's quotas, replacing earlier overrides.
+pub async fn update_user_quota(
+    Path(id): Path<String>,
+    State(pool): State<DbPool>,
+    admin_user: AdminUser,
+    Json(request): Json<UpdateUserQuotaRequest>,
+) -> Result<ApiResponse, AppError> {
+    let internal_error = |e: anyhow::Error| {
+        tracing::error!("Failed to update quotas of user {}: {}", id, e);
+        AppError::Internal("Failed to update quotas".into())
+    };
+    let exists: bool = sqlx::query_scalar("SELECT EXISTS (SELECT 1 FROM users WHERE id = ?)")
+        .bind(&id)
+        .fetch_one(&pool)
+        .await
+        .map_err(|e| internal_error(e.into()))?;
+    if !exists {
+        return Err(AppError::NotFound("User not found".into()));
+    }
+
+    quotas::set_for_user(&pool, &id, &request).await.map_err(internal_error)?;
+    let quotas = quotas::for_user(&pool, &id).await.map_err(internal_error)?;
+    tracing::info!("Admin {} set the quotas of user {}: {:?}", admin_user.user_id, id, quotas);
+
+    Ok(ApiResponse::ok(json!(quotas)))
+}
+
+/// Reloads the configuration like a SIGHUP does, listing what took effect and what waits for
+/// a restart. An invalid configuration is refused with its problems, and nothing changes.
+pub async fn reload_config(admin_user: AdminUser) -> Result<ApiResponse, AppError> {
+    let reloaded = reload::reload().map_err(|e| {
+        tracing::warn!("Admin {} tried to reload an invalid configuration: {:#}", admin_user.user_id, e);
+        AppError::Unprocessable(format!("{:#}", e))
+    })?;
+    tracing::info!("Admin {} reloaded the configuration", admin_user.user_id);
+
+    Ok(ApiResponse::ok(json!({
+        "applied": reloaded.applied,
+        "needsRestart": reloaded.needs_restart
+    })))
+}
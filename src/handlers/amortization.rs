@@ -0,0 +1,185 @@
+use axum::{
+    extract::{Path, Query, State},
+    response::Json,
+};
+use chrono::Utc;
+use serde::Deserialize;
+use serde_json::json;
+
+use crate::models::{AmortizationRequest, AmortizationSchedule, RecordAmortizationPaymentRequest};
+use crate::services::{amortization, DbPool};
+use crate::middleware::auth::AuthUser;
+use crate::utils::response::ApiResponse;
+use crate::utils::error::AppError;
+use crate::utils::pagination::{PageQuery, Paginated};
+
+const MAX_TERM_MONTHS: u32 = 600;
+
+#[derive(Debug, Deserialize)]
+pub struct AmortizationListQuery {
+    #[serde(alias = "loanId")]
+    pub loan_id: Option<String>,
+    #[serde(alias = "liabilityId")]
+    pub liability_id: Option<String>,
+}
+
+async fn owns_row(pool: &DbPool, table: &str, id: &str, user_id: &str) -> Result<bool, AppError> {
+    let sql = format!("SELECT id FROM {} WHERE id = ? AND user_id = ?", table);
+    sqlx::query(&sql)
+        .bind(id)
+        .bind(user_id)
+        .fetch_optional(pool)
+        .await
+        .map(|row| row.is_some())
+        .map_err(|e| {
+            tracing::error!("Failed to look up {} {}: {}", table, id, e);
+            AppError::Internal("Failed to look up".into())
+        })
+}
+
+pub async fn generate_amortization(
+    State(pool): State<DbPool>,
+    auth_user: AuthUser,
+    Json(request): Json<AmortizationRequest>,
+) -> Result<ApiResponse, AppError> {
+    if !request.principal.is_positive()
+        || request.annual_rate < 0.0
+        || request.term_months == 0
+        || request.term_months > MAX_TERM_MONTHS
+        || (request.loan_id.is_some() && request.liability_id.is_some())
+    {
+        tracing::warn!("Invalid amortization request from user {}", auth_user.user_id);
+        return Err(AppError::BadRequest("Invalid amortization request".into()));
+    }
+
+    if let Some(loan_id) = &request.loan_id {
+        if !owns_row(&pool, "loans", loan_id, &auth_user.user_id).await? {
+            return Err(AppError::NotFound("Loan not found".into()));
+        }
+    }
+    if let Some(liability_id) = &request.liability_id {
+        if !owns_row(&pool, "liabilities", liability_id, &auth_user.user_id).await? {
+            return Err(AppError::NotFound("Liability not found".into()));
+        }
+    }
+
+    let (mut schedule, entries) = amortization::build_schedule(
+        &auth_user.user_id,
+        request.principal,
+        request.annual_rate,
+        request.term_months,
+        request.start_date.unwrap_or_else(Utc::now),
+    );
+    schedule.loan_id = request.loan_id;
+    schedule.liability_id = request.liability_id;
+
+    let is_saved = schedule.loan_id.is_some() || schedule.liability_id.is_some();
+    if is_saved {
+        if let Err(e) = amortization::save_schedule(&pool, &schedule, &entries).await {
+            tracing::error!("Failed to save amortization schedule: {}", e);
+            return Err(AppError::Internal("Failed to save amortization schedule".into()));
+        }
+        tracing::info!("Amortization schedule saved: {}", schedule.id);
+    }
+
+    Ok(ApiResponse::ok(json!({
+        "schedule": schedule,
+        "entries": entries,
+        "isSaved": is_saved
+    })))
+}
+
+pub async fn get_amortization_schedules(
+    State(pool): State<DbPool>,
+    auth_user: AuthUser,
+    Query(query): Query<AmortizationListQuery>,
+    Query(page): Query<PageQuery>,
+) -> Result<ApiResponse, AppError> {
+    let schedules = sqlx::query_as::<_, AmortizationSchedule>(
+        "SELECT * FROM amortization_schedules WHERE user_id = ?1 AND (?2 IS NULL OR loan_id = ?2) AND (?3 IS NULL OR liability_id = ?3) ORDER BY created_at DESC, id LIMIT ?4 OFFSET ?5"
+    )
+    .bind(&auth_user.user_id)
+    .bind(&query.loan_id)
+    .bind(&query.liability_id)
+    .bind(page.limit() as i64)
+    .bind(page.offset() as i64)
+    .fetch_all(&pool)
+    .await;
+    let total = sqlx::query_scalar::<_, i64>(
+        "SELECT COUNT(*) FROM amortization_schedules WHERE user_id = ?1 AND (?2 IS NULL OR loan_id = ?2) AND (?3 IS NULL OR liability_id = ?3)"
+    )
+    .bind(&auth_user.user_id)
+    .bind(&query.loan_id)
+    .bind(&query.liability_id)
+    .fetch_one(&pool)
+    .await;
+
+    match (schedules, total) {
+        (Ok(schedules), Ok(total)) => Ok(ApiResponse::ok(json!(Paginated::new(schedules, total as usize, &page)))),
+        (Err(e), _) | (_, Err(e)) => {
+            tracing::error!("Failed to get amortization schedules: {}", e);
+            Err(AppError::Internal("Failed to get amortization schedules".into()))
+        }
+    }
+}
+
+pub async fn get_amortization_schedule(
+    Path(id): Path<String>,
+    State(pool): State<DbPool>,
+    auth_user: AuthUser,
+) -> Result<ApiResponse, AppError> {
+    match amortization::load_schedule(&pool, &auth_user.user_id, &id).await {
+        Ok(Some((schedule, entries))) => {
+            let tracking = amortization::tracking(&entries, Utc::now());
+            Ok(ApiResponse::ok(json!({
+                "schedule": schedule,
+                "entries": entries,
+                "tracking": tracking
+            })))
+        }
+        Ok(None) => Err(AppError::NotFound("Amortization schedule not found".into())),
+        Err(e) => {
+            tracing::error!("Failed to get amortization schedule {}: {}", id, e);
+            Err(AppError::Internal("Failed to get amortization schedule".into()))
+        }
+    }
+}
+
+pub async fn record_amortization_payment(
+    Path((id, period)): Path<(String, i64)>,
+    State(pool): State<DbPool>,
+    auth_user: AuthUser,
+    Json(request): Json<RecordAmortizationPaymentRequest>,
+) -> Result<ApiResponse, AppError> {
+    if request.actual_payment.is_negative() {
+        return Err(AppError::BadRequest("Payment can't be negative".into()));
+    }
+
+    let paid_date = request.paid_date.unwrap_or_else(Utc::now).format("%Y-%m-%d %H:%M:%S").to_string();
+
+    let result = sqlx::query(
+        "UPDATE amortization_entries SET actual_payment = ?, paid_date = ? WHERE schedule_id = ? AND period = ? AND schedule_id IN (SELECT id FROM amortization_schedules WHERE user_id = ?)"
+    )
+    .bind(request.actual_payment)
+    .bind(&paid_date)
+    .bind(&id)
+    .bind(period)
+    .bind(&auth_user.user_id)
+    .execute(&pool)
+    .await;
+
+    match result {
+        Ok(result) => {
+            if result.rows_affected() == 0 {
+                Err(AppError::NotFound("Amortization schedule entry not found".into()))
+            } else {
+                tracing::info!("Amortization payment recorded: {} period {}", id, period);
+                Ok(ApiResponse::message("Payment recorded successfully"))
+            }
+        }
+        Err(e) => {
+            tracing::error!("Failed to record amortization payment: {}", e);
+            Err(AppError::Internal("Failed to record amortization payment".into()))
+        }
+    }
+}
@@ -1,14 +1,13 @@
 use axum::{
     extract::State,
-    http::StatusCode,
     response::Json,
 };
-use serde::{Deserialize, Serialize};
-use serde_json::{json, Value};
+use serde::Deserialize;
+use serde_json::json;
 use bcrypt::{hash, verify, DEFAULT_COST};
 use anyhow::Result;
 
-use crate::models::{User, CreateUserRequest, LoginRequest, AuthResponse, UserResponse};
+use crate::models::{User, CreateUserRequest, LoginRequest, AuthResponse, UserResponse, VerifyEmailRequest, ForgotPasswordRequest, ResetPasswordRequest};
 
 #[derive(Debug, Deserialize)]
 pub struct SigninRequest {
@@ -18,7 +17,7 @@ pub struct SigninRequest {
 }
 
 impl SigninRequest {
-    pub fn to_create_user_request(self) -> CreateUserRequest {
+    pub fn into_create_user_request(self) -> CreateUserRequest {
         CreateUserRequest {
             name: self.name.unwrap_or_else(|| "User".to_string()),
             email: self.email,
@@ -26,7 +25,7 @@ impl SigninRequest {
         }
     }
     
-    pub fn to_login_request(self) -> LoginRequest {
+    pub fn into_login_request(self) -> LoginRequest {
         LoginRequest {
             email: self.email,
             password: self.password,
@@ -34,12 +33,37 @@ impl SigninRequest {
     }
 }
 use crate::services::database::DbPool;
-use crate::utils::jwt::create_jwt;
+use crate::middleware::auth::AuthUser;
+use crate::middleware::device::ClientDevice;
+use crate::services::email_tokens::{self, TokenPurpose};
+use crate::services::mailer::{self, EmailTemplate};
+use crate::services::sessions;
+use crate::utils::datetime::format_db_datetime;
+use crate::utils::response::ApiResponse;
+use crate::utils::error::AppError;
+use crate::utils::validation::Validate;
+
+/// Emails the user a link to confirm their address. Failures are logged, not returned.
+pub(crate) async fn send_verification_email(pool: &DbPool, user: &User) {
+    match email_tokens::issue(pool, &user.id, TokenPurpose::VerifyEmail).await {
+        Ok(token) => {
+            mailer::send(pool, Some(&user.id), &user.email, EmailTemplate::Verification {
+                name: &user.name,
+                token: &token,
+            })
+            .await;
+        }
+        Err(e) => tracing::error!("Failed to issue verification token for user {}: {}", user.id, e),
+    }
+}
 
 pub async fn signup(
     State(pool): State<DbPool>,
+    device: ClientDevice,
     Json(payload): Json<CreateUserRequest>,
-) -> Result<Json<Value>, (StatusCode, Json<Value>)> {
+) -> Result<ApiResponse, AppError> {
+    payload.validate()?;
+
     // Check if user already exists
     let existing_user = sqlx::query_as::<_, User>(
         "SELECT * FROM users WHERE email = ?",
@@ -50,21 +74,11 @@ pub async fn signup(
 
     match existing_user {
         Ok(Some(_)) => {
-            return Err((
-                StatusCode::CONFLICT,
-                Json(json!({
-                    "error": "User with this email already exists"
-                })),
-            ));
+            return Err(AppError::Conflict("User with this email already exists".into()));
         }
         Ok(None) => {}
         Err(_) => {
-            return Err((
-                StatusCode::INTERNAL_SERVER_ERROR,
-                Json(json!({
-                    "error": "Database error"
-                })),
-            ));
+            return Err(AppError::Internal("Database error".into()));
         }
     }
 
@@ -72,12 +86,7 @@ pub async fn signup(
     let password_hash = match hash(&payload.password, DEFAULT_COST) {
         Ok(hash) => hash,
         Err(_) => {
-            return Err((
-                StatusCode::INTERNAL_SERVER_ERROR,
-                Json(json!({
-                    "error": "Failed to hash password"
-                })),
-            ));
+            return Err(AppError::Internal("Failed to hash password".into()));
         }
     };
 
@@ -92,46 +101,40 @@ pub async fn signup(
     .bind(&user.name)
     .bind(&user.email)
     .bind(&user.password_hash)
-    .bind(&user.created_at)
-    .bind(&user.updated_at)
+    .bind(user.created_at)
+    .bind(user.updated_at)
     .execute(&pool)
     .await;
 
     match result {
         Ok(_) => {
+            send_verification_email(&pool, &user).await;
+
             // Generate JWT token
-            let token = match create_jwt(&user.id) {
-                Ok(token) => token,
+            let (token, reauth_reason) = match sessions::start_session(&pool, &user.id, &device.0).await {
+                Ok(session) => session,
                 Err(_) => {
-                    return Err((
-                        StatusCode::INTERNAL_SERVER_ERROR,
-                        Json(json!({
-                            "error": "Failed to create token"
-                        })),
-                    ));
+                    return Err(AppError::Internal("Failed to create token".into()));
                 }
             };
 
             let response = AuthResponse {
                 token,
                 user: UserResponse::from(user),
+                reauth_reason,
             };
 
-            Ok(Json(json!(response)))
+            Ok(ApiResponse::ok(json!(response)))
         }
-        Err(_) => Err((
-            StatusCode::INTERNAL_SERVER_ERROR,
-            Json(json!({
-                "error": "Failed to create user"
-            })),
-        )),
+        Err(_) => Err(AppError::Internal("Failed to create user".into())),
     }
 }
 
 pub async fn login(
     State(pool): State<DbPool>,
+    device: ClientDevice,
     Json(payload): Json<LoginRequest>,
-) -> Result<Json<Value>, (StatusCode, Json<Value>)> {
+) -> Result<ApiResponse, AppError> {
     // Find user by email
     let user = sqlx::query_as::<_, User>(
         "SELECT * FROM users WHERE email = ?",
@@ -143,20 +146,10 @@ pub async fn login(
     let user = match user {
         Ok(Some(user)) => user,
         Ok(None) => {
-            return Err((
-                StatusCode::UNAUTHORIZED,
-                Json(json!({
-                    "error": "Invalid email or password"
-                })),
-            ));
+            return Err(AppError::Unauthorized("Invalid email or password".into()));
         }
         Err(_) => {
-            return Err((
-                StatusCode::INTERNAL_SERVER_ERROR,
-                Json(json!({
-                    "error": "Database error"
-                })),
-            ));
+            return Err(AppError::Internal("Database error".into()));
         }
     };
 
@@ -164,49 +157,36 @@ pub async fn login(
     let is_valid = match verify(&payload.password, &user.password_hash) {
         Ok(valid) => valid,
         Err(_) => {
-            return Err((
-                StatusCode::INTERNAL_SERVER_ERROR,
-                Json(json!({
-                    "error": "Failed to verify password"
-                })),
-            ));
+            return Err(AppError::Internal("Failed to verify password".into()));
         }
     };
 
     if !is_valid {
-        return Err((
-            StatusCode::UNAUTHORIZED,
-            Json(json!({
-                "error": "Invalid email or password"
-            })),
-        ));
+        return Err(AppError::Unauthorized("Invalid email or password".into()));
     }
 
     // Generate JWT token
-    let token = match create_jwt(&user.id) {
-        Ok(token) => token,
+    let (token, reauth_reason) = match sessions::start_session(&pool, &user.id, &device.0).await {
+        Ok(session) => session,
         Err(_) => {
-            return Err((
-                StatusCode::INTERNAL_SERVER_ERROR,
-                Json(json!({
-                    "error": "Failed to create token"
-                })),
-            ));
+            return Err(AppError::Internal("Failed to create token".into()));
         }
     };
 
     let response = AuthResponse {
         token,
         user: UserResponse::from(user),
+        reauth_reason,
     };
 
-    Ok(Json(json!(response)))
+    Ok(ApiResponse::ok(json!(response)))
 }
 
 pub async fn signin(
     State(pool): State<DbPool>,
+    device: ClientDevice,
     Json(payload): Json<SigninRequest>,
-) -> Result<Json<Value>, (StatusCode, Json<Value>)> {
+) -> Result<ApiResponse, AppError> {
     let email = payload.email.trim().to_lowercase();
     
     // First try to find existing user
@@ -223,72 +203,51 @@ pub async fn signin(
             let is_valid = match verify(&payload.password, &user.password_hash) {
                 Ok(valid) => valid,
                 Err(_) => {
-                    return Err((
-                        StatusCode::INTERNAL_SERVER_ERROR,
-                        Json(json!({
-                            "error": "Failed to verify password"
-                        })),
-                    ));
+                    return Err(AppError::Internal("Failed to verify password".into()));
                 }
             };
 
             if !is_valid {
-                return Err((
-                    StatusCode::UNAUTHORIZED,
-                    Json(json!({
-                        "error": "Invalid email or password"
-                    })),
-                ));
+                return Err(AppError::Unauthorized("Invalid email or password".into()));
             }
 
             // Generate JWT token
-            let token = match create_jwt(&user.id) {
-                Ok(token) => token,
+            let (token, reauth_reason) = match sessions::start_session(&pool, &user.id, &device.0).await {
+                Ok(session) => session,
                 Err(_) => {
-                    return Err((
-                        StatusCode::INTERNAL_SERVER_ERROR,
-                        Json(json!({
-                            "error": "Failed to create token"
-                        })),
-                    ));
+                    return Err(AppError::Internal("Failed to create token".into()));
                 }
             };
 
             let response = AuthResponse {
                 token,
                 user: UserResponse::from(user),
+                reauth_reason,
             };
 
-            Ok(Json(json!(response)))
+            Ok(ApiResponse::ok(json!(response)))
         }
         Ok(None) => {
             // User doesn't exist, create new account
             if payload.name.is_none() {
-                return Err((
-                    StatusCode::BAD_REQUEST,
-                    Json(json!({
-                        "error": "Name is required for new user registration"
-                    })),
-                ));
+                return Err(AppError::BadRequest("Name is required for new user registration".into()));
             }
+            let mut request = payload.into_create_user_request();
+            request.email = email;
+            request.validate()?;
 
             // Hash password
-            let password_hash = match hash(&payload.password, DEFAULT_COST) {
+            let password_hash = match hash(&request.password, DEFAULT_COST) {
                 Ok(hash) => hash,
                 Err(_) => {
-                    return Err((
-                        StatusCode::INTERNAL_SERVER_ERROR,
-                        Json(json!({
-                            "error": "Failed to hash password"
-                        })),
-                    ));
+                    return Err(AppError::Internal("Failed to hash password".into()));
                 }
             };
 
             // Create new user
             let user = User::new(
-                payload.name.unwrap(),
-                email,
+                request.name,
+                request.email,
                 password_hash,
             );
 
@@ -300,46 +259,173 @@ pub async fn signin(
             .bind(&user.name)
             .bind(&user.email)
             .bind(&user.password_hash)
-            .bind(&user.created_at)
-            .bind(&user.updated_at)
+            .bind(user.created_at)
+            .bind(user.updated_at)
             .execute(&pool)
             .await;
 
             match result {
                 Ok(_) => {
+                    send_verification_email(&pool, &user).await;
+
                     // Generate JWT token
-                    let token = match create_jwt(&user.id) {
-                        Ok(token) => token,
+                    let (token, reauth_reason) = match sessions::start_session(&pool, &user.id, &device.0).await {
+                        Ok(session) => session,
                         Err(_) => {
-                            return Err((
-                                StatusCode::INTERNAL_SERVER_ERROR,
-                                Json(json!({
-                                    "error": "Failed to create token"
-                                })),
-                            ));
+                            return Err(AppError::Internal("Failed to create token".into()));
                         }
                     };
 
                     let response = AuthResponse {
                         token,
                         user: UserResponse::from(user),
+                        reauth_reason,
                     };
 
-                    Ok(Json(json!(response)))
+                    Ok(ApiResponse::ok(json!(response)))
                 }
-                Err(_) => Err((
-                    StatusCode::INTERNAL_SERVER_ERROR,
-                    Json(json!({
-                        "error": "Failed to create user"
-                    })),
-                )),
+                Err(_) => Err(AppError::Internal("Failed to create user".into())),
+            }
+        }
+        Err(_) => Err(AppError::Internal("Database error".into())),
+    }
+}
+
+pub async fn verify_email(
+    State(pool): State<DbPool>,
+    Json(payload): Json<VerifyEmailRequest>,
+) -> Result<ApiResponse, AppError> {
+    let user_id = match email_tokens::consume(&pool, payload.token.trim(), TokenPurpose::VerifyEmail).await {
+        Ok(Some(user_id)) => user_id,
+        Ok(None) => {
+            return Err(AppError::BadRequest("Invalid or expired verification token".into()));
+        }
+        Err(e) => {
+            tracing::error!("Failed to check verification token: {}", e);
+            return Err(AppError::Internal("Database error".into()));
+        }
+    };
+
+    let result = sqlx::query("UPDATE users SET email_verified_at = COALESCE(email_verified_at, ?) WHERE id = ?")
+        .bind(format_db_datetime(chrono::Utc::now()))
+        .bind(&user_id)
+        .execute(&pool)
+        .await;
+
+    match result {
+        Ok(_) => {
+            tracing::info!("Email verified for user {}", user_id);
+            Ok(ApiResponse::message("Email verified"))
+        }
+        Err(e) => {
+            tracing::error!("Failed to mark email verified for user {}: {}", user_id, e);
+            Err(AppError::Internal("Database error".into()))
+        }
+    }
+}
+
+pub async fn resend_verification(
+    State(pool): State<DbPool>,
+    auth_user: AuthUser,
+) -> Result<ApiResponse, AppError> {
+    let user = match sqlx::query_as::<_, User>("SELECT * FROM users WHERE id = ?")
+        .bind(&auth_user.user_id)
+        .fetch_optional(&pool)
+        .await
+    {
+        Ok(Some(user)) => user,
+        Ok(None) => {
+            return Err(AppError::NotFound("User not found".into()));
+        }
+        Err(_) => {
+            return Err(AppError::Internal("Database error".into()));
+        }
+    };
+
+    if user.email_verified_at.is_some() {
+        return Err(AppError::Conflict("Email is already verified".into()));
+    }
+
+    send_verification_email(&pool, &user).await;
+    Ok(ApiResponse::message("Verification email sent"))
+}
+
+pub async fn forgot_password(
+    State(pool): State<DbPool>,
+    Json(payload): Json<ForgotPasswordRequest>,
+) -> Result<ApiResponse, AppError> {
+    let email = payload.email.trim().to_lowercase();
+    let user = sqlx::query_as::<_, User>("SELECT * FROM users WHERE lower(email) = ?")
+        .bind(&email)
+        .fetch_optional(&pool)
+        .await;
+
+    match user {
+        Ok(Some(user)) => match email_tokens::issue(&pool, &user.id, TokenPurpose::ResetPassword).await {
+            Ok(token) => {
+                mailer::send(&pool, Some(&user.id), &user.email, EmailTemplate::PasswordReset {
+                    name: &user.name,
+                    token: &token,
+                })
+                .await;
             }
+            Err(e) => tracing::error!("Failed to issue password reset token for user {}: {}", user.id, e),
+        },
+        Ok(None) => {}
+        Err(_) => {
+            return Err(AppError::Internal("Database error".into()));
         }
-        Err(_) => Err((
-            StatusCode::INTERNAL_SERVER_ERROR,
-            Json(json!({
-                "error": "Database error"
-            })),
-        )),
     }
-}
\ No newline at end of file
+
+    // Same response whether or not the account exists, so emails can't be probed
+    Ok(ApiResponse::message("If an account exists for that email, a password reset link has been sent"))
+}
+
+pub async fn reset_password(
+    State(pool): State<DbPool>,
+    Json(payload): Json<ResetPasswordRequest>,
+) -> Result<ApiResponse, AppError> {
+    payload.validate()?;
+
+    let user_id = match email_tokens::consume(&pool, payload.token.trim(), TokenPurpose::ResetPassword).await {
+        Ok(Some(user_id)) => user_id,
+        Ok(None) => {
+            return Err(AppError::BadRequest("Invalid or expired reset token".into()));
+        }
+        Err(e) => {
+            tracing::error!("Failed to check reset token: {}", e);
+            return Err(AppError::Internal("Database error".into()));
+        }
+    };
+
+    let password_hash = match hash(&payload.password, DEFAULT_COST) {
+        Ok(hash) => hash,
+        Err(_) => {
+            return Err(AppError::Internal("Failed to hash password".into()));
+        }
+    };
+
+    // The link proves the user controls the address, so it also counts as verification
+    let now = format_db_datetime(chrono::Utc::now());
+    let result = sqlx::query(
+        "UPDATE users SET password_hash = ?, email_verified_at = COALESCE(email_verified_at, ?), updated_at = ? WHERE id = ?",
+    )
+    .bind(&password_hash)
+    .bind(&now)
+    .bind(&now)
+    .bind(&user_id)
+    .execute(&pool)
+    .await;
+
+    if let Err(e) = result {
+        tracing::error!("Failed to reset password for user {}: {}", user_id, e);
+        return Err(AppError::Internal("Failed to reset password".into()));
+    }
+
+    if let Err(e) = sessions::revoke_user_sessions(&pool, &user_id, sessions::PASSWORD_RESET_REASON).await {
+        tracing::error!("Failed to sign out sessions after password reset for user {}: {}", user_id, e);
+    }
+
+    tracing::info!("Password reset for user {}", user_id);
+    Ok(ApiResponse::message("Password has been reset"))
+}
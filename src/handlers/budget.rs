@@ -1,29 +1,41 @@
 use axum::{
-    extract::{Path, State},
-    http::StatusCode,
-    response::Json,
+    extract::{Path, Query, State},
+    http::HeaderMap,
+    response::{Json, Response},
 };
-use serde_json::{json, Value};
+use serde_json::json;
 use chrono::Utc;
-use sqlx::Row;
 
-use crate::models::{Budget, CreateBudgetRequest, UpdateBudgetRequest};
-use crate::services::DbPool;
+use crate::models::{Budget, BudgetPeriod, CreateBudgetRequest, UpdateBudgetRequest};
+use crate::services::{budget_progress, budget_rollover, concurrency, etags, history::{self, EntityKind}, DbPool};
 use crate::middleware::auth::AuthUser;
+use crate::middleware::owned::{self, Owned};
+use crate::middleware::device::ClientDevice;
+use crate::utils::response::ApiResponse;
+use crate::utils::error::AppError;
+use crate::utils::pagination::{PageQuery, Paginated};
+use crate::utils::validation::Validate;
 
 pub async fn create_budget(
     State(pool): State<DbPool>,
     auth_user: AuthUser,
+    device: ClientDevice,
     Json(request): Json<CreateBudgetRequest>,
-) -> Result<Json<Value>, StatusCode> {
-    log::info!("POST /budgets - Creating budget for user {}", auth_user.user_id);
+) -> Result<ApiResponse, AppError> {
+    request.validate()?;
 
+    let categories = request.categories.clone().unwrap_or_default();
     let budget = Budget::new(request, auth_user.user_id.clone());
+    if budget.category.is_empty() && budget.account_id.is_none() {
+        tracing::warn!("Budget must target at least one category or an account");
+        return Err(AppError::BadRequest("Budget must target at least one category or an account".into()));
+    }
+
     let created_at_str = budget.created_at.format("%Y-%m-%d %H:%M:%S").to_string();
     let updated_at_str = budget.updated_at.format("%Y-%m-%d %H:%M:%S").to_string();
 
     let result = sqlx::query(
-        "INSERT INTO budgets (id, user_id, category, amount, currency, period, created_at, updated_at) VALUES (?, ?, ?, ?, ?, ?, ?, ?)"
+        "INSERT INTO budgets (id, user_id, category, amount, currency, period, rollover, account_id, multi_currency, created_at, updated_at) VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?)"
     )
     .bind(&budget.id)
     .bind(&budget.user_id)
@@ -31,6 +43,9 @@ pub async fn create_budget(
     .bind(budget.amount)
     .bind(&budget.currency)
     .bind(&budget.period)
+    .bind(budget.rollover)
+    .bind(&budget.account_id)
+    .bind(budget.multi_currency)
     .bind(&created_at_str)
     .bind(&updated_at_str)
     .execute(&pool)
@@ -38,145 +53,143 @@ pub async fn create_budget(
 
     match result {
         Ok(_) => {
-            log::info!("Budget created successfully: {} ({})", budget.category, budget.id);
-            Ok(Json(json!({
-                "success": true,
-                "data": budget
-            })))
+            if let Err(e) = budget_progress::set_linked_categories(&pool, &budget.id, &categories).await {
+                tracing::error!("Failed to link budget categories: {}", e);
+                return Err(AppError::Internal("Failed to link budget categories".into()));
+            }
+
+            tracing::info!("Budget created successfully: {} ({})", budget.category, budget.id);
+            history::record(&pool, EntityKind::Budget, &budget.id, &auth_user.user_id, "created", None, &device).await;
+            let mut data = json!(budget);
+            data["categories"] = json!(budget.target_categories(&categories));
+            Ok(ApiResponse::ok(json!(data)))
         }
         Err(e) => {
-            log::error!("Failed to create budget: {}", e);
+            tracing::error!("Failed to create budget: {}", e);
             let error_msg = e.to_string();
             if error_msg.contains("UNIQUE constraint failed: budgets.id") {
-                log::warn!("Budget with ID {} already exists", budget.id);
-                Err(StatusCode::CONFLICT)
+                tracing::warn!("Budget with ID {} already exists", budget.id);
+                Err(AppError::Conflict("Budget already exists".into()))
             } else {
-                Err(StatusCode::INTERNAL_SERVER_ERROR)
+                Err(AppError::Internal("Failed to create budget".into()))
             }
         }
     }
 }
 
+/// Answers 304 when `If-None-Match` holds the list's current `ETag`.
 pub async fn get_budgets(
     State(pool): State<DbPool>,
     auth_user: AuthUser,
-) -> Result<Json<Value>, StatusCode> {
-    log::info!("GET /budgets - Fetching budgets for user {}", auth_user.user_id);
+    headers: HeaderMap,
+    Query(page): Query<PageQuery>,
+) -> Result<Response, AppError> {
+    let etag = etags::list_etag(&pool, EntityKind::Budget, &auth_user.user_id).await.map_err(|e| {
+        tracing::error!("Failed to compute the budgets ETag: {}", e);
+        AppError::Internal("Failed to compute the budgets ETag".into())
+    })?;
+    let etag = etags::for_page(&etag, &page);
+    if etags::is_fresh(&headers, &etag) {
+        tracing::info!("Budgets unchanged for user {}", auth_user.user_id);
+        return Ok(etags::not_modified(&etag));
+    }
 
-    let result = sqlx::query(
-        "SELECT id, user_id, category, amount, currency, period, created_at, updated_at FROM budgets WHERE user_id = ? ORDER BY created_at DESC"
-    )
-    .bind(&auth_user.user_id)
-    .fetch_all(&pool)
-    .await;
+    let result = owned::page::<Budget>(&pool, &auth_user.user_id, "created_at DESC", &page).await;
+
+    let linked = budget_progress::linked_categories_by_budget(&pool, &auth_user.user_id)
+        .await
+        .map_err(|e| {
+            tracing::error!("Failed to get budget categories: {}", e);
+            AppError::Internal("Failed to get budget categories".into())
+        })?;
 
     match result {
-        Ok(rows) => {
-            let budgets: Vec<_> = rows.into_iter().map(|row| {
-                json!({
-                    "id": row.get::<String, _>("id"),
-                    "userId": row.get::<String, _>("user_id"),
-                    "category": row.get::<String, _>("category"),
-                    "amount": row.get::<f64, _>("amount"),
-                    "currency": row.get::<String, _>("currency"),
-                    "period": row.get::<String, _>("period"),
-                    "createdAt": row.get::<String, _>("created_at"),
-                    "updatedAt": row.get::<String, _>("updated_at")
-                })
-            }).collect();
-
-            log::info!("Found {} budgets", budgets.len());
-            Ok(Json(json!({
-                "success": true,
-                "data": budgets
-            })))
+        Ok(budgets) => {
+            let budgets = budgets.map(|budget| {
+                let categories = budget.target_categories(linked.get(&budget.id).map(Vec::as_slice).unwrap_or_default());
+                let mut value = json!(budget);
+                value["categories"] = json!(categories);
+                value
+            });
+            Ok(etags::tagged(&etag, ApiResponse::ok(json!(budgets))))
         }
         Err(e) => {
-            log::error!("Failed to get budgets: {}", e);
-            Err(StatusCode::INTERNAL_SERVER_ERROR)
+            tracing::error!("Failed to get budgets: {}", e);
+            Err(AppError::Internal("Failed to get budgets".into()))
         }
     }
 }
 
 pub async fn get_budget(
-    Path(id): Path<String>,
     State(pool): State<DbPool>,
-    auth_user: AuthUser,
-) -> Result<Json<Value>, StatusCode> {
-    log::info!("GET /budgets/{} - Fetching budget by ID", id);
+    Owned { resource: budget, .. }: Owned<Budget>,
+) -> Result<ApiResponse, AppError> {
+    let linked = budget_progress::linked_categories(&pool, &budget.id).await.map_err(|e| {
+        tracing::error!("Failed to get budget categories: {}", e);
+        AppError::Internal("Failed to get budget categories".into())
+    })?;
+    let categories = budget.target_categories(&linked);
+    let mut budget = json!(budget);
+    budget["categories"] = json!(categories);
 
-    let result = sqlx::query(
-        "SELECT id, user_id, category, amount, currency, period, created_at, updated_at FROM budgets WHERE id = ? AND user_id = ?"
-    )
-    .bind(&id)
-    .bind(&auth_user.user_id)
-    .fetch_optional(&pool)
-    .await;
-
-    match result {
-        Ok(Some(row)) => {
-            let budget = json!({
-                "id": row.get::<String, _>("id"),
-                "userId": row.get::<String, _>("user_id"),
-                "category": row.get::<String, _>("category"),
-                "amount": row.get::<f64, _>("amount"),
-                "currency": row.get::<String, _>("currency"),
-                "period": row.get::<String, _>("period"),
-                "createdAt": row.get::<String, _>("created_at"),
-                "updatedAt": row.get::<String, _>("updated_at")
-            });
-
-            Ok(Json(json!({
-                "success": true,
-                "data": budget
-            })))
-        }
-        Ok(None) => Err(StatusCode::NOT_FOUND),
-        Err(e) => {
-            log::error!("Failed to get budget: {}", e);
-            Err(StatusCode::INTERNAL_SERVER_ERROR)
-        }
-    }
+    Ok(ApiResponse::ok(json!(budget)))
 }
 
 pub async fn update_budget(
     Path(id): Path<String>,
     State(pool): State<DbPool>,
     auth_user: AuthUser,
+    device: ClientDevice,
+    headers: HeaderMap,
     Json(request): Json<UpdateBudgetRequest>,
-) -> Result<Json<Value>, StatusCode> {
-    log::info!("PUT /budgets/{} - Updating budget", id);
+) -> Result<Response, AppError> {
+    request.validate()?;
+
+    let before = history::snapshot(&pool, EntityKind::Budget, &id, &auth_user.user_id).await;
+    let version = concurrency::expected_version(&headers, request.version)?;
 
     let now = Utc::now().format("%Y-%m-%d %H:%M:%S").to_string();
+    let category = request
+        .category
+        .clone()
+        .or_else(|| request.categories.as_ref().and_then(|c| c.first().cloned()));
 
     let result = sqlx::query(
-        "UPDATE budgets SET category = COALESCE(?, category), amount = COALESCE(?, amount), currency = COALESCE(?, currency), period = COALESCE(?, period), updated_at = ? WHERE id = ? AND user_id = ?"
+        "UPDATE budgets SET category = COALESCE(?, category), account_id = COALESCE(?, account_id), amount = COALESCE(?, amount), currency = COALESCE(?, currency), period = COALESCE(?, period), rollover = COALESCE(?, rollover), multi_currency = COALESCE(?, multi_currency), updated_at = ? WHERE id = ? AND user_id = ? AND version = ?"
     )
-    .bind(request.category)
+    .bind(category)
+    .bind(request.account_id)
     .bind(request.amount)
     .bind(request.currency)
     .bind(request.period)
+    .bind(request.rollover)
+    .bind(request.multi_currency)
     .bind(&now)
     .bind(&id)
     .bind(&auth_user.user_id)
+    .bind(version)
     .execute(&pool)
     .await;
 
     match result {
         Ok(result) => {
             if result.rows_affected() == 0 {
-                Err(StatusCode::NOT_FOUND)
+                concurrency::rejected_update(&pool, EntityKind::Budget, &id, &auth_user.user_id, version).await
             } else {
-                log::info!("Budget updated successfully: {}", id);
-                Ok(Json(json!({
-                    "success": true,
-                    "message": "Budget updated successfully"
-                })))
+                if let Some(categories) = request.categories.as_ref() {
+                    if let Err(e) = budget_progress::set_linked_categories(&pool, &id, categories).await {
+                        tracing::error!("Failed to link budget categories: {}", e);
+                        return Err(AppError::Internal("Failed to link budget categories".into()));
+                    }
+                }
+                tracing::info!("Budget updated successfully: {}", id);
+                history::record(&pool, EntityKind::Budget, &id, &auth_user.user_id, "updated", before, &device).await;
+                Ok(concurrency::updated(&pool, EntityKind::Budget, &id, "Budget updated successfully").await)
             }
         }
         Err(e) => {
-            log::error!("Failed to update budget: {}", e);
-            Err(StatusCode::INTERNAL_SERVER_ERROR)
+            tracing::error!("Failed to update budget: {}", e);
+            Err(AppError::Internal("Failed to update budget".into()))
         }
     }
 }
@@ -185,8 +198,9 @@ pub async fn delete_budget(
     Path(id): Path<String>,
     State(pool): State<DbPool>,
     auth_user: AuthUser,
-) -> Result<Json<Value>, StatusCode> {
-    log::info!("DELETE /budgets/{} - Deleting budget", id);
+    device: ClientDevice,
+) -> Result<ApiResponse, AppError> {
+    let before = history::snapshot(&pool, EntityKind::Budget, &id, &auth_user.user_id).await;
 
     let result = sqlx::query("DELETE FROM budgets WHERE id = ? AND user_id = ?")
         .bind(&id)
@@ -197,18 +211,81 @@ pub async fn delete_budget(
     match result {
         Ok(result) => {
             if result.rows_affected() == 0 {
-                Err(StatusCode::NOT_FOUND)
+                Err(AppError::NotFound("Budget not found".into()))
             } else {
-                log::info!("Budget deleted successfully: {}", id);
-                Ok(Json(json!({
-                    "success": true,
-                    "message": "Budget deleted successfully"
-                })))
+                tracing::info!("Budget deleted successfully: {}", id);
+                history::record(&pool, EntityKind::Budget, &id, &auth_user.user_id, "deleted", before, &device).await;
+                Ok(ApiResponse::message("Budget deleted successfully"))
             }
         }
         Err(e) => {
-            log::error!("Failed to delete budget: {}", e);
-            Err(StatusCode::INTERNAL_SERVER_ERROR)
+            tracing::error!("Failed to delete budget: {}", e);
+            Err(AppError::Internal("Failed to delete budget".into()))
+        }
+    }
+}
+
+/// One page of the budget's periods, latest first.
+async fn periods_page(pool: &DbPool, budget_id: &str, user_id: &str, page: &PageQuery) -> Result<Paginated<BudgetPeriod>, sqlx::Error> {
+    let periods = sqlx::query_as::<_, BudgetPeriod>(
+        "SELECT * FROM budget_periods WHERE budget_id = ? AND user_id = ? ORDER BY period_start DESC, id LIMIT ? OFFSET ?"
+    )
+    .bind(budget_id)
+    .bind(user_id)
+    .bind(page.limit() as i64)
+    .bind(page.offset() as i64)
+    .fetch_all(pool)
+    .await?;
+
+    let total = sqlx::query_scalar::<_, i64>("SELECT COUNT(*) FROM budget_periods WHERE budget_id = ? AND user_id = ?")
+        .bind(budget_id)
+        .bind(user_id)
+        .fetch_one(pool)
+        .await?;
+
+    Ok(Paginated::new(periods, total as usize, page))
+}
+
+pub async fn get_budget_periods(
+    Path(id): Path<String>,
+    State(pool): State<DbPool>,
+    auth_user: AuthUser,
+    Query(page): Query<PageQuery>,
+) -> Result<ApiResponse, AppError> {
+    let budget = owned::load::<Budget>(&pool, &id, &auth_user.user_id).await?;
+
+    if let Err(e) = budget_rollover::sync_budget_periods(&pool, &budget).await {
+        tracing::error!("Failed to sync budget periods for {}: {}", id, e);
+        return Err(AppError::Internal("Failed to sync budget periods".into()));
+    }
+
+    let result = periods_page(&pool, &id, &auth_user.user_id, &page).await;
+
+    match result {
+        Ok(periods) => {
+            let periods = periods.map(|period| {
+                let mut value = json!(period);
+                value["remaining"] = json!(period.remaining());
+                value
+            });
+            Ok(ApiResponse::ok(json!(periods)))
+        }
+        Err(e) => {
+            tracing::error!("Failed to get budget periods: {}", e);
+            Err(AppError::Internal("Failed to get budget periods".into()))
+        }
+    }
+}
+
+pub async fn get_budget_progress(
+    State(pool): State<DbPool>,
+    Owned { resource: budget, .. }: Owned<Budget>,
+) -> Result<ApiResponse, AppError> {
+    match budget_progress::budget_progress(&pool, &budget).await {
+        Ok(progress) => Ok(ApiResponse::ok(json!(progress))),
+        Err(e) => {
+            tracing::error!("Failed to compute budget progress for {}: {}", budget.id, e);
+            Err(AppError::Internal("Failed to compute budget progress".into()))
         }
     }
 }
@@ -0,0 +1,36 @@
+use axum::extract::{Query, State};
+use serde_json::json;
+
+use crate::models::CalendarQuery;
+use crate::services::calendar::{self, current_month, parse_month};
+use crate::services::DbPool;
+use crate::middleware::auth::AuthUser;
+use crate::utils::response::ApiResponse;
+use crate::utils::error::AppError;
+
+pub async fn get_calendar(
+    State(pool): State<DbPool>,
+    auth_user: AuthUser,
+    Query(query): Query<CalendarQuery>,
+) -> Result<ApiResponse, AppError> {
+    let first_day = match query.month.as_deref() {
+        Some(month) => parse_month(month).ok_or_else(|| {
+            tracing::warn!("Invalid calendar month '{}', expected YYYY-MM", month);
+            AppError::BadRequest("Invalid calendar month, expected YYYY-MM".into())
+        })?,
+        None => current_month(),
+    };
+
+    let (days, totals) = calendar::month_calendar(&pool, &auth_user.user_id, first_day)
+        .await
+        .map_err(|e| {
+            tracing::error!("Failed to build calendar: {}", e);
+            AppError::Internal("Failed to build calendar".into())
+        })?;
+
+    Ok(ApiResponse::ok(json!({
+        "month": first_day.format("%Y-%m").to_string(),
+        "days": days,
+        "totals": totals
+    })))
+}
@@ -0,0 +1,271 @@
+use std::collections::{BTreeMap, HashMap};
+
+use axum::{
+    extract::{Path, Query, State},
+    response::Json,
+};
+use rust_decimal::Decimal;
+use serde::Deserialize;
+use serde_json::json;
+
+use crate::models::{
+    Account, AccountType, CashCount, CashCountDenomination, CashDenominations, CreateCashCountRequest,
+    CreateTransactionRequest, DenominationCount, Transaction, TransactionType,
+};
+use crate::services::{households, DbPool};
+use crate::middleware::auth::AuthUser;
+use crate::utils::money::Money;
+use crate::utils::response::ApiResponse;
+use crate::utils::error::AppError;
+use crate::utils::pagination::{PageQuery, Paginated};
+use crate::utils::validation::Validate;
+
+#[derive(Debug, Deserialize)]
+pub struct DenominationQuery {
+    pub currency: Option<String>,
+}
+
+/// Merges repeated denominations and drops empty rows so each note value appears once.
+fn merge_denominations(denominations: &[DenominationCount]) -> Vec<DenominationCount> {
+    let mut merged: BTreeMap<Money, DenominationCount> = BTreeMap::new();
+    for d in denominations.iter().filter(|d| d.count > 0) {
+        // Negated so the largest note comes first
+        merged
+            .entry(-d.denomination)
+            .and_modify(|existing| existing.count += d.count)
+            .or_insert_with(|| d.clone());
+    }
+    merged.into_values().collect()
+}
+
+pub async fn create_cash_count(
+    Path(account_id): Path<String>,
+    State(pool): State<DbPool>,
+    auth_user: AuthUser,
+    Json(request): Json<CreateCashCountRequest>,
+) -> Result<ApiResponse, AppError> {
+    request.validate()?;
+
+    let access = households::account_access(&pool, &account_id, &auth_user.user_id)
+        .await
+        .map_err(|e| {
+            tracing::error!("Failed to get account {}: {}", account_id, e);
+            AppError::Internal("Failed to get account".into())
+        })?
+        .ok_or_else(|| AppError::NotFound("Account not found".into()))?;
+    if !access.can_write() {
+        tracing::warn!("User {} can only view shared account {}", auth_user.user_id, account_id);
+        return Err(AppError::Forbidden("You can only view this shared account".into()));
+    }
+
+    let account = sqlx::query_as::<_, Account>("SELECT * FROM accounts WHERE id = ?")
+        .bind(&account_id)
+        .fetch_optional(&pool)
+        .await
+        .map_err(|e| {
+            tracing::error!("Failed to get account {}: {}", account_id, e);
+            AppError::Internal("Failed to get account".into())
+        })?
+        .ok_or_else(|| AppError::NotFound("Account not found".into()))?;
+
+    if !matches!(account.account_type, AccountType::Cash | AccountType::Wallet) {
+        tracing::warn!("Cash counts are only supported for cash and wallet accounts");
+        return Err(AppError::BadRequest("Cash counts are only supported for cash and wallet accounts".into()));
+    }
+
+    let denominations = merge_denominations(&request.denominations);
+    let mut cash_count = CashCount::new(
+        auth_user.user_id.clone(),
+        account.id.clone(),
+        account.currency.clone(),
+        &denominations,
+        account.balance,
+        request.note,
+    );
+
+    let reconcile = request.reconcile.unwrap_or(true);
+    let adjustment = if reconcile && !cash_count.difference.is_zero() {
+        let transaction_type = if cash_count.difference.is_positive() {
+            TransactionType::Income
+        } else {
+            TransactionType::Expense
+        };
+        Some(Transaction::new(
+            CreateTransactionRequest {
+                id: None,
+                account_id: account.id.clone(),
+                transaction_type,
+                amount: cash_count.difference.abs(),
+                currency: Some(account.currency.clone()),
+                category: Some("Adjustment".to_string()),
+                description: Some("Cash count reconciliation".to_string()),
+                date: Some(cash_count.counted_at),
+                created_at: None,
+                exchange_rate: None,
+                base_amount: None,
+            },
+            auth_user.user_id.clone(),
+        ))
+    } else {
+        None
+    };
+    cash_count.adjustment_transaction_id = adjustment.as_ref().map(|t| t.id.clone());
+
+    let counted_at_str = cash_count.counted_at.format("%Y-%m-%d %H:%M:%S").to_string();
+
+    let result: Result<(), sqlx::Error> = async {
+        let mut tx = pool.begin().await?;
+
+        if let Some(transaction) = &adjustment {
+            sqlx::query(
+                "INSERT INTO transactions (id, user_id, account_id, transaction_type, amount, currency, category, description, date, created_at) VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?, ?)"
+            )
+            .bind(&transaction.id)
+            .bind(&transaction.user_id)
+            .bind(&transaction.account_id)
+            .bind(format!("{:?}", transaction.transaction_type).to_lowercase())
+            .bind(transaction.amount)
+            .bind(&transaction.currency)
+            .bind(&transaction.category)
+            .bind(&transaction.description)
+            .bind(&counted_at_str)
+            .bind(&counted_at_str)
+            .execute(&mut tx)
+            .await?;
+
+            sqlx::query("UPDATE accounts SET balance = ?, updated_at = ? WHERE id = ?")
+                .bind(cash_count.total)
+                .bind(&counted_at_str)
+                .bind(&account.id)
+                .execute(&mut tx)
+                .await?;
+        }
+
+        sqlx::query(
+            "INSERT INTO cash_counts (id, user_id, account_id, currency, total, previous_balance, difference, adjustment_transaction_id, note, counted_at) VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?, ?)"
+        )
+        .bind(&cash_count.id)
+        .bind(&cash_count.user_id)
+        .bind(&cash_count.account_id)
+        .bind(&cash_count.currency)
+        .bind(cash_count.total)
+        .bind(cash_count.previous_balance)
+        .bind(cash_count.difference)
+        .bind(&cash_count.adjustment_transaction_id)
+        .bind(&cash_count.note)
+        .bind(&counted_at_str)
+        .execute(&mut tx)
+        .await?;
+
+        for d in &denominations {
+            sqlx::query(
+                "INSERT INTO cash_count_denominations (cash_count_id, denomination, count, subtotal) VALUES (?, ?, ?, ?)"
+            )
+            .bind(&cash_count.id)
+            .bind(d.denomination)
+            .bind(d.count)
+            .bind(d.denomination * Decimal::from(d.count))
+            .execute(&mut tx)
+            .await?;
+        }
+
+        tx.commit().await
+    }
+    .await;
+
+    match result {
+        Ok(_) => {
+            tracing::info!("Cash count recorded: {} {} (difference {})", cash_count.total, cash_count.currency, cash_count.difference);
+            let breakdown: Vec<_> = denominations.iter().map(|d| json!({
+                "denomination": d.denomination,
+                "count": d.count,
+                "subtotal": d.denomination * Decimal::from(d.count)
+            })).collect();
+            Ok(ApiResponse::ok(json!({
+                "cashCount": cash_count,
+                "denominations": breakdown,
+                "adjustmentTransaction": adjustment
+            })))
+        }
+        Err(e) => {
+            tracing::error!("Failed to record cash count: {}", e);
+            Err(AppError::Internal("Failed to record cash count".into()))
+        }
+    }
+}
+
+pub async fn get_cash_counts(
+    Path(account_id): Path<String>,
+    State(pool): State<DbPool>,
+    auth_user: AuthUser,
+    Query(page): Query<PageQuery>,
+) -> Result<ApiResponse, AppError> {
+    let counts = sqlx::query_as::<_, CashCount>(&format!(
+        "SELECT * FROM cash_counts WHERE account_id = ? AND {} ORDER BY counted_at DESC, id LIMIT ? OFFSET ?",
+        households::visible_rows_filter()
+    ))
+    .bind(&account_id)
+    .bind(&auth_user.user_id)
+    .bind(&auth_user.user_id)
+    .bind(page.limit() as i64)
+    .bind(page.offset() as i64)
+    .fetch_all(&pool)
+    .await
+    .map_err(|e| {
+        tracing::error!("Failed to get cash counts: {}", e);
+        AppError::Internal("Failed to get cash counts".into())
+    })?;
+
+    let total = sqlx::query_scalar::<_, i64>(&format!(
+        "SELECT COUNT(*) FROM cash_counts WHERE account_id = ? AND {}",
+        households::visible_rows_filter()
+    ))
+    .bind(&account_id)
+    .bind(&auth_user.user_id)
+    .bind(&auth_user.user_id)
+    .fetch_one(&pool)
+    .await
+    .map_err(|e| {
+        tracing::error!("Failed to count cash counts: {}", e);
+        AppError::Internal("Failed to get cash counts".into())
+    })?;
+
+    // Only the breakdowns of the counts on this page
+    let mut by_count: HashMap<String, Vec<CashCountDenomination>> = HashMap::new();
+    if !counts.is_empty() {
+        let sql = format!(
+            "SELECT * FROM cash_count_denominations WHERE cash_count_id IN ({}) ORDER BY denomination DESC",
+            vec!["?"; counts.len()].join(", ")
+        );
+        let mut query = sqlx::query_as::<_, CashCountDenomination>(&sql);
+        for count in &counts {
+            query = query.bind(&count.id);
+        }
+        let denominations = query.fetch_all(&pool).await.map_err(|e| {
+            tracing::error!("Failed to get cash count denominations: {}", e);
+            AppError::Internal("Failed to get cash counts".into())
+        })?;
+        for d in denominations {
+            by_count.entry(d.cash_count_id.clone()).or_default().push(d);
+        }
+    }
+
+    let history: Vec<_> = counts.into_iter().map(|count| {
+        let breakdown = by_count.remove(&count.id).unwrap_or_default();
+        let mut value = json!(count);
+        value["denominations"] = json!(breakdown);
+        value
+    }).collect();
+    Ok(ApiResponse::ok(json!(Paginated::new(history, total as usize, &page))))
+}
+
+pub async fn get_cash_denominations(
+    _auth_user: AuthUser,
+    Query(query): Query<DenominationQuery>,
+) -> ApiResponse {
+    let currency = query.currency.unwrap_or_else(|| "BDT".to_string()).to_uppercase();
+    ApiResponse::ok(json!({
+        "currency": currency,
+        "denominations": CashDenominations::for_currency(&currency)
+    }))
+}
@@ -8,11 +8,17 @@ use sqlx::Row;
 
 use crate::models::{Category, CreateCategoryRequest, UpdateCategoryRequest};
 use crate::services::DbPool;
+use crate::utils::response::ApiResponse;
+use crate::utils::error::AppError;
+use crate::utils::validation::Validate;
 
 pub async fn create_category(
     State(pool): State<DbPool>,
     Json(request): Json<CreateCategoryRequest>,
-) -> Result<Json<Value>, StatusCode> {
+) -> Result<ApiResponse, AppError> {
+
+    request.validate()?;
+
     let category = Category::new(request);
     let category_type_str = format!("{:?}", category.category_type).to_lowercase();
     let created_at_str = category.created_at.format("%Y-%m-%d %H:%M:%S").to_string();
@@ -31,48 +37,30 @@ pub async fn create_category(
     .await;
 
     match result {
-        Ok(_) => Ok(Json(json!({
-            "success": true,
-            "data": category
-        }))),
+        Ok(_) => Ok(ApiResponse::ok(json!(category))),
         Err(e) => {
-            log::error!("Failed to create category: {}", e);
-            Err(StatusCode::INTERNAL_SERVER_ERROR)
+            tracing::error!("Failed to create category: {}", e);
+            Err(AppError::Internal("Failed to create category".into()))
         }
     }
 }
 
 pub async fn get_categories(
     State(pool): State<DbPool>,
-) -> Result<Json<Value>, StatusCode> {
-    let result = sqlx::query(
-        "SELECT id, name, category_type, icon, color, is_default, created_at FROM categories ORDER BY is_default DESC, created_at ASC"
+) -> Result<ApiResponse, AppError> {
+    let result = sqlx::query_as::<_, Category>(
+        "SELECT * FROM categories ORDER BY is_default DESC, created_at ASC"
     )
     .fetch_all(&pool)
     .await;
 
     match result {
-        Ok(rows) => {
-            let categories: Vec<_> = rows.into_iter().map(|row| {
-                json!({
-                    "id": row.get::<String, _>("id"),
-                    "name": row.get::<String, _>("name"),
-                    "category_type": row.get::<String, _>("category_type"),
-                    "icon": row.get::<String, _>("icon"),
-                    "color": row.get::<String, _>("color"),
-                    "is_default": row.get::<bool, _>("is_default"),
-                    "created_at": row.get::<String, _>("created_at")
-                })
-            }).collect();
-            
-            Ok(Json(json!({
-                "success": true,
-                "data": categories
-            })))
+        Ok(categories) => {
+            Ok(ApiResponse::ok(json!(categories)))
         }
         Err(e) => {
-            log::error!("Failed to get categories: {}", e);
-            Err(StatusCode::INTERNAL_SERVER_ERROR)
+            tracing::error!("Failed to get categories: {}", e);
+            Err(AppError::Internal("Failed to get categories".into()))
         }
     }
 }
@@ -80,35 +68,22 @@ pub async fn get_categories(
 pub async fn get_category(
     Path(id): Path<String>,
     State(pool): State<DbPool>,
-) -> Result<Json<Value>, StatusCode> {
-    let result = sqlx::query(
-        "SELECT id, name, category_type, icon, color, is_default, created_at FROM categories WHERE id = ?"
+) -> Result<ApiResponse, AppError> {
+    let result = sqlx::query_as::<_, Category>(
+        "SELECT * FROM categories WHERE id = ?"
     )
     .bind(&id)
     .fetch_optional(&pool)
     .await;
 
     match result {
-        Ok(Some(row)) => {
-            let category = json!({
-                "id": row.get::<String, _>("id"),
-                "name": row.get::<String, _>("name"),
-                "category_type": row.get::<String, _>("category_type"),
-                "icon": row.get::<String, _>("icon"),
-                "color": row.get::<String, _>("color"),
-                "is_default": row.get::<bool, _>("is_default"),
-                "created_at": row.get::<String, _>("created_at")
-            });
-            
-            Ok(Json(json!({
-                "success": true,
-                "data": category
-            })))
+        Ok(Some(category)) => {
+            Ok(ApiResponse::ok(json!(category)))
         }
-        Ok(None) => Err(StatusCode::NOT_FOUND),
+        Ok(None) => Err(AppError::NotFound("Category not found".into())),
         Err(e) => {
-            log::error!("Failed to get category: {}", e);
-            Err(StatusCode::INTERNAL_SERVER_ERROR)
+            tracing::error!("Failed to get category: {}", e);
+            Err(AppError::Internal("Failed to get category".into()))
         }
     }
 }
@@ -117,7 +92,10 @@ pub async fn update_category(
     Path(id): Path<String>,
     State(pool): State<DbPool>,
     Json(request): Json<UpdateCategoryRequest>,
-) -> Result<Json<Value>, StatusCode> {
+) -> Result<ApiResponse, AppError> {
+
+    request.validate()?;
+
     let category_type_str = request.category_type.map(|t| format!("{:?}", t).to_lowercase());
     
     let result = sqlx::query(
@@ -135,17 +113,14 @@ pub async fn update_category(
     match result {
         Ok(result) => {
             if result.rows_affected() == 0 {
-                Err(StatusCode::NOT_FOUND)
+                Err(AppError::NotFound("Category not found".into()))
             } else {
-                Ok(Json(json!({
-                    "success": true,
-                    "message": "Category updated successfully"
-                })))
+                Ok(ApiResponse::message("Category updated successfully"))
             }
         }
         Err(e) => {
-            log::error!("Failed to update category: {}", e);
-            Err(StatusCode::INTERNAL_SERVER_ERROR)
+            tracing::error!("Failed to update category: {}", e);
+            Err(AppError::Internal("Failed to update category".into()))
         }
     }
 }
@@ -153,7 +128,7 @@ pub async fn update_category(
 pub async fn delete_category(
     Path(id): Path<String>,
     State(pool): State<DbPool>,
-) -> Result<Json<Value>, StatusCode> {
+) -> Result<ApiResponse, AppError> {
     let result = sqlx::query("DELETE FROM categories WHERE id = ?")
         .bind(&id)
         .execute(&pool)
@@ -162,17 +137,14 @@ pub async fn delete_category(
     match result {
         Ok(result) => {
             if result.rows_affected() == 0 {
-                Err(StatusCode::NOT_FOUND)
+                Err(AppError::NotFound("Category not found".into()))
             } else {
-                Ok(Json(json!({
-                    "success": true,
-                    "message": "Category deleted successfully"
-                })))
+                Ok(ApiResponse::message("Category deleted successfully"))
             }
         }
         Err(e) => {
-            log::error!("Failed to delete category: {}", e);
-            Err(StatusCode::INTERNAL_SERVER_ERROR)
+            tracing::error!("Failed to delete category: {}", e);
+            Err(AppError::Internal("Failed to delete category".into()))
         }
     }
 }
\ No newline at end of file
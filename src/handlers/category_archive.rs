@@ -0,0 +1,77 @@
+use axum::extract::{Path, State};
+use chrono::Utc;
+use serde_json::json;
+
+use crate::models::Category;
+use crate::services::DbPool;
+use crate::middleware::auth::AuthUser;
+use crate::utils::datetime::format_db_datetime;
+use crate::utils::response::ApiResponse;
+use crate::utils::error::AppError;
+
+/// Archives or restores a category for this user only. Built-in categories are shared by every
+/// user, so the mark is kept per user rather than on the category.
+async fn set_archived(pool: &DbPool, user_id: &str, id: &str, archived: bool) -> Result<ApiResponse, AppError> {
+    let exists = sqlx::query_scalar::<_, String>("SELECT id FROM categories WHERE id = ? AND (user_id = ? OR user_id = '')")
+        .bind(id)
+        .bind(user_id)
+        .fetch_optional(pool)
+        .await
+        .map_err(|e| {
+            tracing::error!("Failed to get category {}: {}", id, e);
+            AppError::Internal("Failed to get category".into())
+        })?;
+    if exists.is_none() {
+        return Err(AppError::NotFound("Category not found".into()));
+    }
+
+    let result = if archived {
+        sqlx::query("INSERT OR IGNORE INTO category_archives (user_id, category_id, archived_at) VALUES (?, ?, ?)")
+            .bind(user_id)
+            .bind(id)
+            .bind(format_db_datetime(Utc::now()))
+            .execute(pool)
+            .await
+    } else {
+        sqlx::query("DELETE FROM category_archives WHERE user_id = ? AND category_id = ?")
+            .bind(user_id)
+            .bind(id)
+            .execute(pool)
+            .await
+    };
+    result.map_err(|e| {
+        tracing::error!("Failed to update category {}: {}", id, e);
+        AppError::Internal("Failed to update category".into())
+    })?;
+
+    let category = sqlx::query_as::<_, Category>(
+        "SELECT *, EXISTS (SELECT 1 FROM category_archives a WHERE a.user_id = ? AND a.category_id = categories.id) AS is_archived FROM categories WHERE id = ?",
+    )
+    .bind(user_id)
+    .bind(id)
+    .fetch_one(pool)
+    .await
+    .map_err(|e| {
+        tracing::error!("Failed to get category {}: {}", id, e);
+        AppError::Internal("Failed to get category".into())
+    })?;
+
+    tracing::info!("{} category {} for user {}", if archived { "Archived" } else { "Unarchived" }, id, user_id);
+    Ok(ApiResponse::ok(json!(category)))
+}
+
+pub async fn archive_category(
+    Path(id): Path<String>,
+    State(pool): State<DbPool>,
+    auth_user: AuthUser,
+) -> Result<ApiResponse, AppError> {
+    set_archived(&pool, &auth_user.user_id, &id, true).await
+}
+
+pub async fn unarchive_category(
+    Path(id): Path<String>,
+    State(pool): State<DbPool>,
+    auth_user: AuthUser,
+) -> Result<ApiResponse, AppError> {
+    set_archived(&pool, &auth_user.user_id, &id, false).await
+}
@@ -0,0 +1,94 @@
+use axum::{
+    extract::{Path, State},
+    http::StatusCode,
+    response::Json,
+};
+use chrono::Utc;
+use serde_json::json;
+
+use crate::models::{CategoryImportSummary, CategorySet};
+use crate::services::category_presets::{self, PresetPack, CATEGORY_SET_FORMAT, CATEGORY_SET_VERSION, MAX_IMPORTED_CATEGORIES, PRESET_PACKS};
+use crate::services::DbPool;
+use crate::middleware::auth::AuthUser;
+use crate::utils::response::ApiResponse;
+use crate::utils::error::AppError;
+use crate::utils::validation::Validate;
+
+fn created(summary: CategoryImportSummary) -> (StatusCode, ApiResponse) {
+    let status = if summary.created.is_empty() { StatusCode::OK } else { StatusCode::CREATED };
+    (status, ApiResponse::ok(json!(summary)))
+}
+
+pub async fn get_category_presets(_auth_user: AuthUser) -> ApiResponse {
+    let packs: Vec<_> = PRESET_PACKS.iter().map(PresetPack::summary).collect();
+    ApiResponse::ok(json!(packs))
+}
+
+pub async fn apply_category_preset(
+    Path(pack_id): Path<String>,
+    State(pool): State<DbPool>,
+    auth_user: AuthUser,
+) -> Result<(StatusCode, ApiResponse), AppError> {
+    let pack = PresetPack::find(&pack_id).ok_or_else(|| AppError::NotFound("Category preset not found".into()))?;
+
+    let summary = category_presets::add_categories(&pool, &auth_user.user_id, &pack.templates())
+        .await
+        .map_err(|e| {
+            tracing::error!("Failed to apply category preset {} for user {}: {}", pack.id, auth_user.user_id, e);
+            AppError::Internal("Failed to apply category preset".into())
+        })?;
+
+    tracing::info!(
+        "Applied category preset {} for user {}: {} created, {} unarchived, {} skipped",
+        pack.id, auth_user.user_id, summary.created.len(), summary.unarchived.len(), summary.skipped.len()
+    );
+    Ok(created(summary))
+}
+
+pub async fn export_categories(
+    State(pool): State<DbPool>,
+    auth_user: AuthUser,
+) -> Result<ApiResponse, AppError> {
+    let categories = category_presets::export(&pool, &auth_user.user_id).await.map_err(|e| {
+        tracing::error!("Failed to export categories for user {}: {}", auth_user.user_id, e);
+        AppError::Internal("Failed to export categories".into())
+    })?;
+
+    let set = CategorySet {
+        format: CATEGORY_SET_FORMAT.to_string(),
+        version: CATEGORY_SET_VERSION,
+        exported_at: Some(Utc::now()),
+        categories,
+    };
+    Ok(ApiResponse::ok(json!(set)))
+}
+
+pub async fn import_categories(
+    State(pool): State<DbPool>,
+    auth_user: AuthUser,
+    Json(set): Json<CategorySet>,
+) -> Result<(StatusCode, ApiResponse), AppError> {
+    if !set.format.is_empty() && set.format != CATEGORY_SET_FORMAT {
+        return Err(AppError::BadRequest(format!("Expected a {} document", CATEGORY_SET_FORMAT)));
+    }
+    if set.version > CATEGORY_SET_VERSION {
+        return Err(AppError::BadRequest(format!("Category set version {} is newer than this server reads", set.version)));
+    }
+    if set.categories.len() > MAX_IMPORTED_CATEGORIES {
+        return Err(AppError::BadRequest(format!("At most {} categories can be imported at once", MAX_IMPORTED_CATEGORIES)));
+    }
+    set.validate()?;
+
+    let summary = category_presets::add_categories(&pool, &auth_user.user_id, &set.categories)
+        .await
+        .map_err(|e| {
+            tracing::error!("Failed to import categories for user {}: {}", auth_user.user_id, e);
+            AppError::Internal("Failed to import categories".into())
+        })?;
+
+    tracing::info!(
+        "Imported categories for user {}: {} created, {} unarchived, {} skipped",
+        auth_user.user_id, summary.created.len(), summary.unarchived.len(), summary.skipped.len()
+    );
+    Ok(created(summary))
+}
@@ -0,0 +1,44 @@
+use axum::{
+    extract::State,
+    response::Json,
+};
+use serde_json::json;
+
+use crate::models::SuggestCategoryRequest;
+use crate::services::{category_model, DbPool};
+use crate::middleware::auth::AuthUser;
+use crate::utils::response::ApiResponse;
+use crate::utils::error::AppError;
+
+const DEFAULT_SUGGESTION_LIMIT: usize = 3;
+const MAX_SUGGESTION_LIMIT: usize = 10;
+
+pub async fn suggest_category(
+    State(pool): State<DbPool>,
+    auth_user: AuthUser,
+    Json(request): Json<SuggestCategoryRequest>,
+) -> Result<ApiResponse, AppError> {
+    let limit = request
+        .limit
+        .unwrap_or(DEFAULT_SUGGESTION_LIMIT)
+        .clamp(1, MAX_SUGGESTION_LIMIT);
+
+    let result = category_model::suggest(
+        &pool,
+        &auth_user.user_id,
+        &request.description,
+        request.transaction_type,
+        limit,
+    )
+    .await;
+
+    match result {
+        Ok(suggestions) => {
+            Ok(ApiResponse::ok(json!(suggestions)))
+        }
+        Err(e) => {
+            tracing::error!("Failed to suggest category: {}", e);
+            Err(AppError::Internal("Failed to suggest category".into()))
+        }
+    }
+}
@@ -0,0 +1,209 @@
+use axum::{
+    extract::{Path, Query, State},
+    response::Json,
+};
+use chrono::Utc;
+use serde_json::json;
+use uuid::Uuid;
+
+use crate::models::{
+    Conversion, ConvertQuery, CreateExchangeRateRequest, UpdateExchangeRateRequest, UserExchangeRate,
+    SUPPORTED_CURRENCIES,
+};
+use crate::services::currency;
+use crate::services::DbPool;
+use crate::middleware::auth::AuthUser;
+use crate::middleware::owned::{self, Owned};
+use crate::utils::datetime::format_db_datetime;
+use crate::utils::response::ApiResponse;
+use crate::utils::error::AppError;
+use crate::utils::pagination::PageQuery;
+use crate::utils::validation::Validate;
+
+pub async fn get_currencies(
+    State(pool): State<DbPool>,
+    auth_user: AuthUser,
+) -> Result<ApiResponse, AppError> {
+    let display_currency = currency::display_currency(&pool, &auth_user.user_id)
+        .await
+        .map_err(|e| {
+            tracing::error!("Failed to load display currency: {}", e);
+            AppError::Internal("Failed to load display currency".into())
+        })?;
+
+    Ok(ApiResponse::ok(json!({
+        "displayCurrency": display_currency,
+        "currencies": SUPPORTED_CURRENCIES
+    })))
+}
+
+pub async fn convert(
+    State(pool): State<DbPool>,
+    auth_user: AuthUser,
+    Query(query): Query<ConvertQuery>,
+) -> Result<ApiResponse, AppError> {
+    let to = match query.to {
+        Some(to) => to,
+        None => currency::display_currency(&pool, &auth_user.user_id)
+            .await
+            .map_err(|e| {
+                tracing::error!("Failed to load display currency: {}", e);
+                AppError::Internal("Failed to load display currency".into())
+            })?,
+    };
+    if !currency::is_currency_code(&query.from) || !currency::is_currency_code(&to) {
+        tracing::warn!("Invalid conversion {} {} to {}", query.amount, query.from, to);
+        return Err(AppError::BadRequest("Invalid conversion".into()));
+    }
+
+    let (from, to) = (query.from.to_uppercase(), to.to_uppercase());
+    let date = query.date.unwrap_or_else(|| Utc::now().date_naive());
+    let rate = currency::rate_on(&pool, &auth_user.user_id, &from, &to, date)
+        .await
+        .map_err(|e| {
+            tracing::error!("Failed to look up exchange rate: {}", e);
+            AppError::Internal("Failed to look up exchange rate".into())
+        })?
+        .ok_or_else(|| {
+            tracing::warn!("No exchange rate from {} to {} on {}", from, to, date);
+            AppError::NotFound("No exchange rate found for that date".into())
+        })?;
+
+    let converted = currency::round(query.amount * rate, &to);
+    Ok(ApiResponse::ok(json!(Conversion {
+        from,
+        to,
+        amount: query.amount,
+        rate,
+        converted,
+        date,
+    })))
+}
+
+pub async fn create_exchange_rate(
+    State(pool): State<DbPool>,
+    auth_user: AuthUser,
+    Json(request): Json<CreateExchangeRateRequest>,
+) -> Result<ApiResponse, AppError> {
+    request.validate()?;
+
+    let (base, quote) = (request.base_currency.trim().to_uppercase(), request.quote_currency.trim().to_uppercase());
+
+    // One rate per pair: a rate for the opposite direction already covers this one.
+    let existing = sqlx::query_scalar::<_, String>(
+        r#"
+        SELECT id FROM user_exchange_rates
+        WHERE user_id = ? AND ((base_currency = ? AND quote_currency = ?) OR (base_currency = ? AND quote_currency = ?))
+        "#,
+    )
+    .bind(&auth_user.user_id)
+    .bind(&base)
+    .bind(&quote)
+    .bind(&quote)
+    .bind(&base)
+    .fetch_optional(&pool)
+    .await
+    .map_err(|e| {
+        tracing::error!("Failed to check existing exchange rates: {}", e);
+        AppError::Internal("Failed to check existing exchange rates".into())
+    })?;
+    if let Some(id) = existing {
+        tracing::warn!("Exchange rate for {}/{} already exists: {}", base, quote, id);
+        return Err(AppError::Conflict("An exchange rate for this currency pair already exists".into()));
+    }
+
+    let id = Uuid::new_v4().to_string();
+    let now = format_db_datetime(Utc::now());
+    sqlx::query(
+        r#"
+        INSERT INTO user_exchange_rates (id, user_id, base_currency, quote_currency, rate, created_at, updated_at)
+        VALUES (?, ?, ?, ?, ?, ?, ?)
+        "#,
+    )
+    .bind(&id)
+    .bind(&auth_user.user_id)
+    .bind(&base)
+    .bind(&quote)
+    .bind(request.rate)
+    .bind(&now)
+    .bind(&now)
+    .execute(&pool)
+    .await
+    .map_err(|e| {
+        tracing::error!("Failed to create exchange rate: {}", e);
+        AppError::Internal("Failed to create exchange rate".into())
+    })?;
+    // Read back rather than `RETURNING *`, which hands whole-number rates back as integers
+    // that don't decode as `f64`
+    let rate = owned::find::<UserExchangeRate>(&pool, &id, &auth_user.user_id).await?.ok_or_else(|| AppError::Internal("Failed to create exchange rate".into()))?;
+
+    tracing::info!("Exchange rate created: {} ({}/{})", rate.id, rate.base_currency, rate.quote_currency);
+    Ok(ApiResponse::ok(json!(rate)))
+}
+
+pub async fn get_exchange_rates(
+    State(pool): State<DbPool>,
+    auth_user: AuthUser,
+    Query(page): Query<PageQuery>,
+) -> Result<ApiResponse, AppError> {
+    let rates = owned::page::<UserExchangeRate>(&pool, &auth_user.user_id, "base_currency, quote_currency", &page)
+        .await
+        .map_err(|e| {
+            tracing::error!("Failed to get exchange rates: {}", e);
+            AppError::Internal("Failed to get exchange rates".into())
+        })?;
+
+    Ok(ApiResponse::ok(json!(rates)))
+}
+
+pub async fn get_exchange_rate(Owned { resource: rate, .. }: Owned<UserExchangeRate>) -> ApiResponse {
+    ApiResponse::ok(json!(rate))
+}
+
+pub async fn update_exchange_rate(
+    Path(id): Path<String>,
+    State(pool): State<DbPool>,
+    auth_user: AuthUser,
+    Json(request): Json<UpdateExchangeRateRequest>,
+) -> Result<ApiResponse, AppError> {
+    request.validate()?;
+
+    sqlx::query("UPDATE user_exchange_rates SET rate = ?, updated_at = ? WHERE id = ? AND user_id = ?")
+        .bind(request.rate)
+        .bind(format_db_datetime(Utc::now()))
+        .bind(&id)
+        .bind(&auth_user.user_id)
+        .execute(&pool)
+        .await
+        .map_err(|e| {
+            tracing::error!("Failed to update exchange rate {}: {}", id, e);
+            AppError::Internal("Failed to update exchange rate".into())
+        })?;
+    let rate = owned::load::<UserExchangeRate>(&pool, &id, &auth_user.user_id).await?;
+
+    tracing::info!("Exchange rate updated successfully: {}", id);
+    Ok(ApiResponse::ok(json!(rate)))
+}
+
+pub async fn delete_exchange_rate(
+    Path(id): Path<String>,
+    State(pool): State<DbPool>,
+    auth_user: AuthUser,
+) -> Result<ApiResponse, AppError> {
+    let result = sqlx::query("DELETE FROM user_exchange_rates WHERE id = ? AND user_id = ?")
+        .bind(&id)
+        .bind(&auth_user.user_id)
+        .execute(&pool)
+        .await
+        .map_err(|e| {
+            tracing::error!("Failed to delete exchange rate {}: {}", id, e);
+            AppError::Internal("Failed to delete exchange rate".into())
+        })?;
+
+    if result.rows_affected() == 0 {
+        tracing::warn!("Exchange rate not found: {}", id);
+        return Err(AppError::NotFound("Exchange rate not found".into()));
+    }
+
+    Ok(ApiResponse::message("Exchange rate deleted successfully"))
+}
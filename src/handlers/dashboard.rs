@@ -0,0 +1,25 @@
+use axum::extract::State;
+use serde_json::json;
+
+use crate::services::{cache, dashboard};
+use crate::services::DbPool;
+use crate::middleware::auth::AuthUser;
+use crate::utils::response::ApiResponse;
+use crate::utils::error::AppError;
+
+pub async fn get_dashboard(
+    State(pool): State<DbPool>,
+    auth_user: AuthUser,
+) -> Result<ApiResponse, AppError> {
+    let dashboard = cache::get()
+        .cached(&auth_user.user_id, "dashboard", async {
+            Ok(serde_json::to_value(dashboard::dashboard(&pool, &auth_user.user_id).await?)?)
+        })
+        .await
+        .map_err(|e| {
+            tracing::error!("Failed to build dashboard: {}", e);
+            AppError::Internal("Failed to build dashboard".into())
+        })?;
+
+    Ok(ApiResponse::ok(json!(dashboard)))
+}
@@ -0,0 +1,114 @@
+use axum::{
+    extract::{Path, Query, State},
+    response::Json,
+};
+use chrono::Utc;
+use serde_json::json;
+use uuid::Uuid;
+
+use crate::models::{PushDevice, RegisterDeviceRequest};
+use crate::services::DbPool;
+use crate::middleware::auth::AuthUser;
+use crate::middleware::device::ClientDevice;
+use crate::utils::datetime::format_db_datetime;
+use crate::utils::response::ApiResponse;
+use crate::utils::error::AppError;
+use crate::utils::pagination::{PageQuery, Paginated};
+
+/// Registers (or re-registers) a push token. A token moves to the latest user that registers it,
+/// since a shared device may switch accounts.
+pub async fn register_device(
+    State(pool): State<DbPool>,
+    auth_user: AuthUser,
+    client: ClientDevice,
+    Json(request): Json<RegisterDeviceRequest>,
+) -> Result<ApiResponse, AppError> {
+    let token = request.token.trim();
+    if token.is_empty() || token.len() > 4096 {
+        tracing::warn!("Rejected push token with length {}", token.len());
+        return Err(AppError::BadRequest("Invalid push token".into()));
+    }
+    let name = request.name.filter(|n| !n.trim().is_empty()).unwrap_or(client.0);
+    let now = format_db_datetime(Utc::now());
+
+    let device = sqlx::query_as::<_, PushDevice>(
+        r#"
+        INSERT INTO push_devices (id, user_id, platform, token, name, created_at, last_used_at)
+        VALUES (?, ?, ?, ?, ?, ?, ?)
+        ON CONFLICT (token) DO UPDATE SET
+            user_id = excluded.user_id,
+            platform = excluded.platform,
+            name = excluded.name,
+            last_used_at = excluded.last_used_at
+        RETURNING *
+        "#,
+    )
+    .bind(Uuid::new_v4().to_string())
+    .bind(&auth_user.user_id)
+    .bind(request.platform.as_str())
+    .bind(token)
+    .bind(&name)
+    .bind(&now)
+    .bind(&now)
+    .fetch_one(&pool)
+    .await
+    .map_err(|e| {
+        tracing::error!("Failed to register push device: {}", e);
+        AppError::Internal("Failed to register push device".into())
+    })?;
+
+    tracing::info!("Push device registered: {} ({})", device.id, device.name);
+    Ok(ApiResponse::ok(json!(device)))
+}
+
+pub async fn get_devices(
+    State(pool): State<DbPool>,
+    auth_user: AuthUser,
+    Query(page): Query<PageQuery>,
+) -> Result<ApiResponse, AppError> {
+    let devices = sqlx::query_as::<_, PushDevice>(
+        "SELECT * FROM push_devices WHERE user_id = ? ORDER BY last_used_at DESC, id LIMIT ? OFFSET ?"
+    )
+    .bind(&auth_user.user_id)
+    .bind(page.limit() as i64)
+    .bind(page.offset() as i64)
+    .fetch_all(&pool)
+    .await
+    .map_err(|e| {
+        tracing::error!("Failed to get push devices: {}", e);
+        AppError::Internal("Failed to get push devices".into())
+    })?;
+    let total = sqlx::query_scalar::<_, i64>("SELECT COUNT(*) FROM push_devices WHERE user_id = ?")
+        .bind(&auth_user.user_id)
+        .fetch_one(&pool)
+        .await
+        .map_err(|e| {
+            tracing::error!("Failed to count push devices: {}", e);
+            AppError::Internal("Failed to get push devices".into())
+        })?;
+
+    Ok(ApiResponse::ok(json!(Paginated::new(devices, total as usize, &page))))
+}
+
+pub async fn delete_device(
+    Path(id): Path<String>,
+    State(pool): State<DbPool>,
+    auth_user: AuthUser,
+) -> Result<ApiResponse, AppError> {
+    let result = sqlx::query("DELETE FROM push_devices WHERE id = ? AND user_id = ?")
+        .bind(&id)
+        .bind(&auth_user.user_id)
+        .execute(&pool)
+        .await
+        .map_err(|e| {
+            tracing::error!("Failed to delete push device {}: {}", id, e);
+            AppError::Internal("Failed to delete push device".into())
+        })?;
+
+    if result.rows_affected() == 0 {
+        tracing::warn!("Push device not found: {}", id);
+        return Err(AppError::NotFound("Push device not found".into()));
+    }
+
+    Ok(ApiResponse::message("Device unregistered successfully"))
+}
@@ -0,0 +1,197 @@
+use axum::{
+    extract::{Path, Query, State},
+    response::Json,
+};
+use chrono::{Months, Utc};
+use serde_json::{json, Value};
+use uuid::Uuid;
+
+use crate::models::{CreateEmiPlanRequest, EmiPlan, UpdateEmiPlanRequest};
+use crate::services::{emi_plans, households, DbPool};
+use crate::middleware::auth::AuthUser;
+use crate::middleware::owned::{self, Owned};
+use crate::utils::datetime::format_db_datetime;
+use crate::utils::response::ApiResponse;
+use crate::utils::error::AppError;
+use crate::utils::pagination::{PageQuery, Paginated};
+use crate::utils::validation::{FieldErrors, Validate};
+
+async fn with_status(pool: &DbPool, plan: EmiPlan) -> Result<Value, AppError> {
+    let id = plan.id.clone();
+    let status = emi_plans::status(pool, plan).await.map_err(|e| {
+        tracing::error!("Failed to work out EMI plan status for {}: {}", id, e);
+        AppError::Internal("Failed to work out EMI plan status".into())
+    })?;
+    Ok(json!(status))
+}
+
+pub async fn create_emi_plan(
+    State(pool): State<DbPool>,
+    auth_user: AuthUser,
+    Json(request): Json<CreateEmiPlanRequest>,
+) -> Result<ApiResponse, AppError> {
+    request.validate()?;
+
+    let writable = households::can_write_account(&pool, &request.account_id, &auth_user.user_id)
+        .await
+        .map_err(|e| {
+            tracing::error!("Failed to look up account {}: {}", request.account_id, e);
+            AppError::Internal("Failed to look up account".into())
+        })?;
+    if !writable {
+        return Err(AppError::NotFound("Account not found".into()));
+    }
+    let account_currency = sqlx::query_scalar::<_, String>("SELECT currency FROM accounts WHERE id = ?")
+        .bind(&request.account_id)
+        .fetch_one(&pool)
+        .await
+        .map_err(|e| {
+            tracing::error!("Failed to look up account {}: {}", request.account_id, e);
+            AppError::Internal("Failed to look up account".into())
+        })?;
+
+    let code = request.currency.as_deref().map(str::to_uppercase).unwrap_or(account_currency);
+    let annual_rate = request.annual_rate.unwrap_or(0.0);
+    let purchase_date = request.purchase_date.unwrap_or_else(Utc::now);
+    let first_due_date = request.first_due_date.unwrap_or(purchase_date + Months::new(1));
+    let mut errors = FieldErrors::default();
+    errors.fits_currency("purchaseAmount", request.purchase_amount, &code);
+    errors.not_before("firstDueDate", first_due_date, purchase_date, "purchaseDate");
+    errors.into_result()?;
+
+    let (installment_amount, total_interest) =
+        emi_plans::installment_terms(request.purchase_amount, annual_rate, request.installments, &code);
+    let now = Utc::now();
+    let plan = EmiPlan {
+        id: Uuid::new_v4().to_string(),
+        user_id: auth_user.user_id.clone(),
+        account_id: request.account_id,
+        name: request.name.trim().to_string(),
+        purchase_amount: request.purchase_amount,
+        currency: code,
+        annual_rate,
+        installments: i64::from(request.installments),
+        installment_amount,
+        total_interest,
+        purchase_date,
+        first_due_date,
+        recurring_transaction_id: None,
+        category: request.category,
+        notes: request.notes,
+        created_at: now,
+        updated_at: now,
+    };
+    let plan = emi_plans::create_plan(&pool, plan).await.map_err(|e| {
+        tracing::error!("Failed to create EMI plan: {}", e);
+        AppError::Internal("Failed to create EMI plan".into())
+    })?;
+
+    tracing::info!("EMI plan created: {} ({} x {})", plan.id, plan.installments, plan.installment_amount);
+    Ok(ApiResponse::ok(json!(with_status(&pool, plan).await?)))
+}
+
+pub async fn get_emi_plans(
+    State(pool): State<DbPool>,
+    auth_user: AuthUser,
+    Query(page): Query<PageQuery>,
+) -> Result<ApiResponse, AppError> {
+    let plans = owned::page::<EmiPlan>(&pool, &auth_user.user_id, "purchase_date DESC", &page)
+        .await
+        .map_err(|e| {
+            tracing::error!("Failed to get EMI plans: {}", e);
+            AppError::Internal("Failed to get EMI plans".into())
+        })?;
+
+    let mut data = Vec::with_capacity(plans.items.len());
+    for plan in plans.items {
+        data.push(with_status(&pool, plan).await?);
+    }
+    Ok(ApiResponse::ok(json!(Paginated::new(data, plans.total, &page))))
+}
+
+pub async fn get_emi_plan(
+    State(pool): State<DbPool>,
+    Owned { resource: plan, .. }: Owned<EmiPlan>,
+) -> Result<ApiResponse, AppError> {
+    Ok(ApiResponse::ok(json!(with_status(&pool, plan).await?)))
+}
+
+/// Renames or recategorizes a plan; its installments follow. The terms are fixed once created.
+pub async fn update_emi_plan(
+    Path(id): Path<String>,
+    State(pool): State<DbPool>,
+    auth_user: AuthUser,
+    Json(request): Json<UpdateEmiPlanRequest>,
+) -> Result<ApiResponse, AppError> {
+    request.validate()?;
+
+    let current = owned::load::<EmiPlan>(&pool, &id, &auth_user.user_id).await?;
+    let name = request.name.as_deref().map(str::trim).unwrap_or(&current.name).to_string();
+    let now = format_db_datetime(Utc::now());
+
+    let result: Result<(), sqlx::Error> = async {
+        let mut tx = pool.begin().await?;
+        sqlx::query("UPDATE emi_plans SET name = ?, category = COALESCE(?, category), notes = COALESCE(?, notes), updated_at = ? WHERE id = ? AND user_id = ?")
+            .bind(&name)
+            .bind(&request.category)
+            .bind(&request.notes)
+            .bind(&now)
+            .bind(&id)
+            .bind(&auth_user.user_id)
+            .execute(&mut tx)
+            .await?;
+        if let Some(schedule_id) = &current.recurring_transaction_id {
+            sqlx::query("UPDATE recurring_transactions SET description = ?, category = COALESCE(?, category), updated_at = ? WHERE id = ? AND user_id = ?")
+                .bind(emi_plans::installment_description(&name))
+                .bind(&request.category)
+                .bind(&now)
+                .bind(schedule_id)
+                .bind(&auth_user.user_id)
+                .execute(&mut tx)
+                .await?;
+        }
+        tx.commit().await
+    }
+    .await;
+    result.map_err(|e| {
+        tracing::error!("Failed to update EMI plan {}: {}", id, e);
+        AppError::Internal("Failed to update EMI plan".into())
+    })?;
+
+    let plan = owned::load::<EmiPlan>(&pool, &id, &auth_user.user_id).await?;
+    tracing::info!("EMI plan updated successfully: {}", id);
+    Ok(ApiResponse::ok(json!(with_status(&pool, plan).await?)))
+}
+
+/// Deletes a plan and stops its remaining installments. Installments already posted stay.
+pub async fn delete_emi_plan(
+    Path(id): Path<String>,
+    State(pool): State<DbPool>,
+    auth_user: AuthUser,
+) -> Result<ApiResponse, AppError> {
+    let plan = owned::load::<EmiPlan>(&pool, &id, &auth_user.user_id).await?;
+    let result: Result<(), sqlx::Error> = async {
+        let mut tx = pool.begin().await?;
+        sqlx::query("DELETE FROM emi_plans WHERE id = ? AND user_id = ?")
+            .bind(&id)
+            .bind(&auth_user.user_id)
+            .execute(&mut tx)
+            .await?;
+        if let Some(schedule_id) = &plan.recurring_transaction_id {
+            sqlx::query("DELETE FROM recurring_transactions WHERE id = ? AND user_id = ?")
+                .bind(schedule_id)
+                .bind(&auth_user.user_id)
+                .execute(&mut tx)
+                .await?;
+        }
+        tx.commit().await
+    }
+    .await;
+    result.map_err(|e| {
+        tracing::error!("Failed to delete EMI plan {}: {}", id, e);
+        AppError::Internal("Failed to delete EMI plan".into())
+    })?;
+
+    tracing::info!("EMI plan deleted successfully: {}", id);
+    Ok(ApiResponse::message("EMI plan deleted successfully"))
+}
@@ -0,0 +1,57 @@
+use axum::extract::{Query, State};
+use serde_json::{json, Value};
+
+use crate::models::SchemaPreviewQuery;
+use crate::services::events::{self, CURRENT_SCHEMA_VERSION, SUPPORTED_SCHEMA_VERSIONS};
+use crate::services::DbPool;
+use crate::middleware::auth::AuthUser;
+use crate::utils::response::ApiResponse;
+use crate::utils::error::AppError;
+
+pub async fn get_event_schemas(
+    _auth_user: AuthUser,
+) -> Result<ApiResponse, AppError> {
+    Ok(ApiResponse::ok(json!({
+        "currentVersion": CURRENT_SCHEMA_VERSION,
+        "supportedVersions": SUPPORTED_SCHEMA_VERSIONS,
+        "versions": events::schema_descriptions()
+    })))
+}
+
+/// Renders the user's most recent real events side by side in the current and target schema
+/// versions so a consumer can check the new shape before upgrading.
+pub async fn preview_event_schema(
+    State(pool): State<DbPool>,
+    auth_user: AuthUser,
+    Query(query): Query<SchemaPreviewQuery>,
+) -> Result<ApiResponse, AppError> {
+    if !events::is_supported_version(query.version) {
+        tracing::warn!("Unsupported schema version requested: {}", query.version);
+        return Err(AppError::BadRequest("Unsupported schema version requested".into()));
+    }
+
+    let limit = query.limit.unwrap_or(5).clamp(1, 50);
+    let recent = events::recent_events(&pool, &auth_user.user_id, limit)
+        .await
+        .map_err(|e| {
+            tracing::error!("Failed to load recent events: {}", e);
+            AppError::Internal("Failed to load recent events".into())
+        })?;
+
+    let samples: Vec<Value> = recent
+        .iter()
+        .map(|event| {
+            json!({
+                "eventType": event.event_type,
+                "current": events::render_payload(event, CURRENT_SCHEMA_VERSION),
+                "preview": events::render_payload(event, query.version)
+            })
+        })
+        .collect();
+
+    Ok(ApiResponse::ok(json!({
+        "currentVersion": CURRENT_SCHEMA_VERSION,
+        "targetVersion": query.version,
+        "samples": samples
+    })))
+}
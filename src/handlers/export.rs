@@ -0,0 +1,173 @@
+use axum::{
+    body::boxed,
+    extract::{Path, Query, State},
+    http::header,
+    response::{IntoResponse, Response},
+};
+use chrono::NaiveDate;
+use serde::Deserialize;
+
+use crate::services::export::{self, ExportFormat};
+use crate::services::{archive, calendar, statement};
+use crate::services::DbPool;
+use crate::middleware::auth::AuthUser;
+use crate::utils::error::AppError;
+
+#[derive(Debug, Deserialize)]
+pub struct ExportQuery {
+    pub format: String,
+    /// Limits an OFX or QIF export to one account.
+    #[serde(alias = "accountId")]
+    pub account_id: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct CsvExportQuery {
+    /// `YYYY-MM-DD`; only transactions on or after this day.
+    pub from: Option<NaiveDate>,
+    /// `YYYY-MM-DD`, inclusive.
+    pub to: Option<NaiveDate>,
+    /// Comma-separated column names; defaults to date, type, amount, currency, category,
+    /// description and account.
+    pub columns: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct StatementQuery {
+    /// `YYYY-MM-DD`; defaults to the first of the current month.
+    pub from: Option<NaiveDate>,
+    /// `YYYY-MM-DD`, inclusive; defaults to today.
+    pub to: Option<NaiveDate>,
+}
+
+pub async fn export_data(
+    State(pool): State<DbPool>,
+    auth_user: AuthUser,
+    Query(query): Query<ExportQuery>,
+) -> Result<Response, AppError> {
+    let format = ExportFormat::parse(&query.format).ok_or_else(|| {
+        tracing::warn!("Unsupported export format: {}", query.format);
+        AppError::BadRequest("Unsupported export format".into())
+    })?;
+
+    let body = match query.account_id.as_deref() {
+        Some(account_id) if format.is_statement() => {
+            export::export_statements(&pool, &auth_user.user_id, format, Some(account_id)).await
+        }
+        Some(_) => {
+            tracing::warn!("Account filter is not supported for {} exports", query.format);
+            return Err(AppError::BadRequest("Account filter is not supported for this export format".into()));
+        }
+        None => export::export_user(&pool, &auth_user.user_id, format).await.map(Some),
+    }
+    .map_err(|e| {
+        tracing::error!("Failed to export data: {}", e);
+        AppError::Internal("Failed to export data".into())
+    })?
+    .ok_or_else(|| {
+        tracing::warn!("Account not found for export: {:?}", query.account_id);
+        AppError::NotFound("Account not found".into())
+    })?;
+
+    Ok((
+        [
+            (header::CONTENT_TYPE, format.content_type().to_string()),
+            (
+                header::CONTENT_DISPOSITION,
+                format!("attachment; filename=\"personal-manager.{}\"", format.file_extension()),
+            ),
+        ],
+        body,
+    )
+        .into_response())
+}
+
+pub async fn export_transactions_csv(
+    State(pool): State<DbPool>,
+    auth_user: AuthUser,
+    Query(query): Query<CsvExportQuery>,
+) -> Result<Response, AppError> {
+    if let (Some(from), Some(to)) = (query.from, query.to) {
+        if from > to {
+            tracing::warn!("Invalid export range {} to {}", from, to);
+            return Err(AppError::BadRequest("Invalid export range".into()));
+        }
+    }
+
+    let columns = export::parse_columns(query.columns.as_deref().unwrap_or_default()).map_err(|column| {
+        tracing::warn!("Unknown CSV export column: {}", column);
+        AppError::BadRequest("Unknown CSV export column".into())
+    })?;
+
+    let body = export::transactions_csv(pool, auth_user.user_id, query.from, query.to, columns);
+
+    Ok((
+        [
+            (header::CONTENT_TYPE, "text/csv; charset=utf-8"),
+            (header::CONTENT_DISPOSITION, "attachment; filename=\"transactions.csv\""),
+        ],
+        boxed(body),
+    )
+        .into_response())
+}
+
+pub async fn get_account_statement(
+    Path(id): Path<String>,
+    State(pool): State<DbPool>,
+    auth_user: AuthUser,
+    Query(query): Query<StatementQuery>,
+) -> Result<Response, AppError> {
+    let from = query.from.unwrap_or_else(calendar::current_month);
+    let to = query.to.unwrap_or_else(|| chrono::Utc::now().date_naive());
+    if from > to {
+        tracing::warn!("Invalid statement range {} to {}", from, to);
+        return Err(AppError::BadRequest("Invalid statement range".into()));
+    }
+
+    let statement = statement::load(&pool, &auth_user.user_id, &id, from, to)
+        .await
+        .map_err(|e| {
+            tracing::error!("Failed to load statement for account {}: {}", id, e);
+            AppError::Internal("Failed to load statement".into())
+        })?
+        .ok_or_else(|| {
+            tracing::warn!("Account not found for statement: {}", id);
+            AppError::NotFound("Account not found".into())
+        })?;
+
+    let body = statement::render_pdf(&statement);
+
+    Ok((
+        [
+            (header::CONTENT_TYPE, "application/pdf".to_string()),
+            (
+                header::CONTENT_DISPOSITION,
+                format!("attachment; filename=\"statement-{}-{}.pdf\"", from.format("%Y%m%d"), to.format("%Y%m%d")),
+            ),
+        ],
+        body,
+    )
+        .into_response())
+}
+
+pub async fn export_all(
+    State(pool): State<DbPool>,
+    auth_user: AuthUser,
+) -> Result<Response, AppError> {
+    let body = archive::stream_archive(pool, auth_user.user_id).await.map_err(|e| {
+        tracing::error!("Failed to export archive: {}", e);
+        AppError::Internal("Failed to export archive".into())
+    })?;
+
+    Ok((
+        [
+            (header::CONTENT_TYPE, "application/json".to_string()),
+            (
+                header::CONTENT_DISPOSITION,
+                format!("attachment; filename=\"personal-manager-{}.json\"", chrono::Utc::now().format("%Y%m%d")),
+            ),
+        ],
+        boxed(body),
+    )
+        .into_response())
+}
@@ -0,0 +1,73 @@
+use std::time::Duration;
+
+use axum::{
+    extract::State,
+    http::StatusCode,
+    response::{IntoResponse, Response},
+};
+use serde_json::json;
+
+use crate::handlers::version;
+use crate::services::database::{self, DbPool};
+use crate::utils::response::ApiResponse;
+
+/// How long readiness waits for the database before calling it down.
+const DB_PING_TIMEOUT: Duration = Duration::from_secs(2);
+
+fn build() -> serde_json::Value {
+    json!({
+        "version": version::VERSION,
+        "commit": version::commit()
+    })
+}
+
+/// The process is up and serving. Touches nothing else, so a slow database never gets a
+/// healthy instance restarted.
+pub async fn live() -> ApiResponse {
+    ApiResponse::ok(json!({ "status": "ok", "build": build() }))
+}
+
+/// Whether this instance should be sent traffic: the database answers and every migration has
+/// run. Answers 503 with the same report otherwise.
+pub async fn ready(State(pool): State<DbPool>) -> Response {
+    let database = match tokio::time::timeout(DB_PING_TIMEOUT, sqlx::query("SELECT 1").execute(&pool)).await {
+        Ok(Ok(_)) => Ok(()),
+        Ok(Err(e)) => Err(e.to_string()),
+        Err(_) => Err(format!("no answer within {}s", DB_PING_TIMEOUT.as_secs())),
+    };
+    let migrations = match &database {
+        Ok(()) => database::pending_migrations(&pool).await.map_err(|e| e.to_string()),
+        Err(_) => Err("database unavailable".to_string()),
+    };
+
+    let is_ready = database.is_ok() && migrations.as_ref().is_ok_and(|pending| pending.is_empty());
+    let report = json!({
+        "status": if is_ready { "ready" } else { "not_ready" },
+        "database": match &database {
+            Ok(()) => json!({ "status": "up" }),
+            Err(e) => json!({ "status": "down", "error": e }),
+        },
+        "migrations": match &migrations {
+            Ok(pending) => json!({
+                "status": if pending.is_empty() { "applied" } else { "pending" },
+                "known": database::MIGRATOR.iter().count(),
+                "pending": pending
+            }),
+            Err(e) => json!({ "status": "unknown", "error": e }),
+        },
+        "build": build()
+    });
+
+    if is_ready {
+        return ApiResponse::ok(report).into_response();
+    }
+    if let Err(e) = &database {
+        tracing::error!("Readiness check failed, database is down: {}", e);
+    } else {
+        tracing::warn!("Readiness check failed: {}", report["migrations"]);
+    }
+    ApiResponse::error(StatusCode::SERVICE_UNAVAILABLE, "Not ready to serve requests")
+        .with_code("not_ready")
+        .with_data(report)
+        .into_response()
+}
@@ -0,0 +1,74 @@
+use axum::extract::{MatchedPath, Path, State};
+use serde_json::json;
+
+use crate::services::history::{self, EntityKind};
+use crate::services::DbPool;
+use crate::middleware::auth::AuthUser;
+use crate::middleware::device::ClientDevice;
+use crate::utils::response::ApiResponse;
+use crate::utils::error::AppError;
+
+/// The history routes are registered per entity (`/api/accounts/:id/history`, ...); the
+/// collection segment of the matched route says which table to look at.
+fn entity_kind(matched: &MatchedPath) -> Result<EntityKind, AppError> {
+    matched
+        .as_str()
+        .split('/')
+        .nth(2)
+        .and_then(EntityKind::from_path_segment)
+        .ok_or_else(|| AppError::NotFound("No history for this kind of record".into()))
+}
+
+pub async fn get_entity_history(
+    matched: MatchedPath,
+    Path(id): Path<String>,
+    State(pool): State<DbPool>,
+    auth_user: AuthUser,
+) -> Result<ApiResponse, AppError> {
+    let kind = entity_kind(&matched)?;
+    tracing::info!("GET {} - Fetching history of {} {}", matched.as_str(), kind.name(), id);
+
+    let versions = history::versions(&pool, kind, &id, &auth_user.user_id)
+        .await
+        .map_err(|e| {
+            tracing::error!("Failed to load history for {} {}: {}", kind.name(), id, e);
+            AppError::Internal("Failed to load history".into())
+        })?;
+
+    if versions.is_empty() && history::snapshot(&pool, kind, &id, &auth_user.user_id).await.is_none() {
+        tracing::warn!("No {} {} or history found", kind.name(), id);
+        return Err(AppError::NotFound("Record not found".into()));
+    }
+
+    Ok(ApiResponse::ok(json!({
+        "entityType": kind.name(),
+        "entityId": id,
+        "versions": versions
+    })))
+}
+
+pub async fn restore_entity_version(
+    matched: MatchedPath,
+    Path((id, version)): Path<(String, i64)>,
+    State(pool): State<DbPool>,
+    auth_user: AuthUser,
+    device: ClientDevice,
+) -> Result<ApiResponse, AppError> {
+    let kind = entity_kind(&matched)?;
+    tracing::info!("POST {} - Restoring {} {} to version {}", matched.as_str(), kind.name(), id, version);
+
+    match history::restore(&pool, kind, &id, &auth_user.user_id, version, &device).await {
+        Ok(Some(restored)) => {
+            tracing::info!("Restored {} {} to version {}", kind.name(), id, version);
+            Ok(ApiResponse::ok(json!(restored)))
+        }
+        Ok(None) => {
+            tracing::warn!("Version {} of {} {} not found", version, kind.name(), id);
+            Err(AppError::NotFound("Version not found".into()))
+        }
+        Err(e) => {
+            tracing::error!("Failed to restore {} {} to version {}: {}", kind.name(), id, version, e);
+            Err(AppError::Unprocessable("This version can't be restored".into()))
+        }
+    }
+}
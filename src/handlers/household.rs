@@ -0,0 +1,485 @@
+use axum::{
+    extract::{Path, Query, State},
+    response::Json,
+};
+use chrono::Utc;
+use serde_json::{json, Value};
+use uuid::Uuid;
+
+use crate::models::{
+    AcceptHouseholdInviteRequest, CreateHouseholdInviteRequest, CreateHouseholdRequest, Household, HouseholdInvite,
+    HouseholdRole, ShareAccountRequest, UpdateHouseholdMemberRequest, UpdateHouseholdRequest,
+};
+use crate::services::households::{self, AcceptOutcome};
+use crate::services::mailer::{self, EmailTemplate};
+use crate::services::DbPool;
+use crate::middleware::auth::AuthUser;
+use crate::utils::datetime::format_db_datetime;
+use crate::utils::response::ApiResponse;
+use crate::utils::error::AppError;
+use crate::utils::pagination::{PageQuery, Paginated};
+use crate::utils::validation::Validate;
+
+/// The household and the user's role in it; 404 unless they are a member.
+async fn find_household(pool: &DbPool, id: &str, user_id: &str) -> Result<(Household, HouseholdRole), AppError> {
+    let role = households::membership(pool, id, user_id)
+        .await
+        .map_err(|e| {
+            tracing::error!("Failed to get membership of household {}: {}", id, e);
+            AppError::Internal("Failed to get membership of household".into())
+        })?
+        .ok_or_else(|| AppError::NotFound("Household not found".into()))?;
+    let household = sqlx::query_as::<_, Household>("SELECT * FROM households WHERE id = ?")
+        .bind(id)
+        .fetch_optional(pool)
+        .await
+        .map_err(|e| {
+            tracing::error!("Failed to get household {}: {}", id, e);
+            AppError::Internal("Failed to get household".into())
+        })?
+        .ok_or_else(|| AppError::NotFound("Household not found".into()))?;
+    Ok((household, role))
+}
+
+/// The household for one of its owners; 403 for other members.
+async fn find_managed_household(pool: &DbPool, id: &str, user_id: &str) -> Result<Household, AppError> {
+    let (household, role) = find_household(pool, id, user_id).await?;
+    if !role.can_manage() {
+        return Err(AppError::Forbidden("Only household owners can do this".into()));
+    }
+    Ok(household)
+}
+
+async fn owner_count(pool: &DbPool, id: &str) -> Result<i64, AppError> {
+    households::owner_count(pool, id).await.map_err(|e| {
+        tracing::error!("Failed to count owners of household {}: {}", id, e);
+        AppError::Internal("Failed to count owners of household".into())
+    })
+}
+
+async fn with_detail(pool: &DbPool, household: Household, role: HouseholdRole) -> Result<Value, AppError> {
+    let id = household.id.clone();
+    let detail = households::detail(pool, household, role).await.map_err(|e| {
+        tracing::error!("Failed to get members of household {}: {}", id, e);
+        AppError::Internal("Failed to get members of household".into())
+    })?;
+    Ok(json!(detail))
+}
+
+/// Creates a household with the user as its owner.
+pub async fn create_household(
+    State(pool): State<DbPool>,
+    auth_user: AuthUser,
+    Json(request): Json<CreateHouseholdRequest>,
+) -> Result<ApiResponse, AppError> {
+    request.validate()?;
+
+    let id = Uuid::new_v4().to_string();
+    let now = format_db_datetime(Utc::now());
+    let result: Result<(), sqlx::Error> = async {
+        let mut tx = pool.begin().await?;
+        sqlx::query("INSERT INTO households (id, name, created_by, created_at, updated_at) VALUES (?, ?, ?, ?, ?)")
+            .bind(&id)
+            .bind(request.name.trim())
+            .bind(&auth_user.user_id)
+            .bind(&now)
+            .bind(&now)
+            .execute(&mut tx)
+            .await?;
+        sqlx::query("INSERT INTO household_members (household_id, user_id, role, joined_at) VALUES (?, ?, ?, ?)")
+            .bind(&id)
+            .bind(&auth_user.user_id)
+            .bind(HouseholdRole::Owner)
+            .bind(&now)
+            .execute(&mut tx)
+            .await?;
+        tx.commit().await
+    }
+    .await;
+    result.map_err(|e| {
+        tracing::error!("Failed to create household: {}", e);
+        AppError::Internal("Failed to create household".into())
+    })?;
+
+    let (household, role) = find_household(&pool, &id, &auth_user.user_id).await?;
+    tracing::info!("Household created: {}", id);
+    Ok(ApiResponse::ok(json!(with_detail(&pool, household, role).await?)))
+}
+
+pub async fn get_households(
+    State(pool): State<DbPool>,
+    auth_user: AuthUser,
+    Query(page): Query<PageQuery>,
+) -> Result<ApiResponse, AppError> {
+    let memberships = households::for_user(&pool, &auth_user.user_id, &page).await.map_err(|e| {
+        tracing::error!("Failed to get households: {}", e);
+        AppError::Internal("Failed to get households".into())
+    })?;
+
+    let mut data = Vec::with_capacity(memberships.items.len());
+    for (household, role) in memberships.items {
+        data.push(with_detail(&pool, household, role).await?);
+    }
+    Ok(ApiResponse::ok(json!(Paginated::new(data, memberships.total, &page))))
+}
+
+pub async fn get_household(
+    Path(id): Path<String>,
+    State(pool): State<DbPool>,
+    auth_user: AuthUser,
+) -> Result<ApiResponse, AppError> {
+    let (household, role) = find_household(&pool, &id, &auth_user.user_id).await?;
+    Ok(ApiResponse::ok(json!(with_detail(&pool, household, role).await?)))
+}
+
+/// Renames the household. Owners only.
+pub async fn update_household(
+    Path(id): Path<String>,
+    State(pool): State<DbPool>,
+    auth_user: AuthUser,
+    Json(request): Json<UpdateHouseholdRequest>,
+) -> Result<ApiResponse, AppError> {
+    request.validate()?;
+    find_managed_household(&pool, &id, &auth_user.user_id).await?;
+
+    sqlx::query("UPDATE households SET name = ?, updated_at = ? WHERE id = ?")
+        .bind(request.name.trim())
+        .bind(format_db_datetime(Utc::now()))
+        .bind(&id)
+        .execute(&pool)
+        .await
+        .map_err(|e| {
+            tracing::error!("Failed to update household {}: {}", id, e);
+            AppError::Internal("Failed to update household".into())
+        })?;
+
+    let (household, role) = find_household(&pool, &id, &auth_user.user_id).await?;
+    tracing::info!("Household updated successfully: {}", id);
+    Ok(ApiResponse::ok(json!(with_detail(&pool, household, role).await?)))
+}
+
+/// Deletes the household. Owners only; the shared accounts stay with their owners.
+pub async fn delete_household(
+    Path(id): Path<String>,
+    State(pool): State<DbPool>,
+    auth_user: AuthUser,
+) -> Result<ApiResponse, AppError> {
+    find_managed_household(&pool, &id, &auth_user.user_id).await?;
+
+    sqlx::query("DELETE FROM households WHERE id = ?")
+        .bind(&id)
+        .execute(&pool)
+        .await
+        .map_err(|e| {
+            tracing::error!("Failed to delete household {}: {}", id, e);
+            AppError::Internal("Failed to delete household".into())
+        })?;
+
+    tracing::info!("Household deleted successfully: {}", id);
+    Ok(ApiResponse::message("Household deleted successfully"))
+}
+
+/// Shares one of the user's own accounts with the household. Viewers can't share.
+pub async fn share_account(
+    Path(id): Path<String>,
+    State(pool): State<DbPool>,
+    auth_user: AuthUser,
+    Json(request): Json<ShareAccountRequest>,
+) -> Result<ApiResponse, AppError> {
+    let (_, role) = find_household(&pool, &id, &auth_user.user_id).await?;
+    if !role.can_write() {
+        return Err(AppError::Forbidden("Viewers can't share accounts".into()));
+    }
+    let access = households::account_access(&pool, &request.account_id, &auth_user.user_id)
+        .await
+        .map_err(|e| {
+            tracing::error!("Failed to look up account {}: {}", request.account_id, e);
+            AppError::Internal("Failed to look up account".into())
+        })?;
+    match access {
+        Some(access) if access.is_owner() => {}
+        Some(_) => return Err(AppError::Forbidden("Only the account's owner can share it".into())),
+        None => return Err(AppError::NotFound("Account not found".into())),
+    }
+
+    let result = sqlx::query("INSERT INTO household_accounts (household_id, account_id, shared_by, shared_at) VALUES (?, ?, ?, ?)")
+        .bind(&id)
+        .bind(&request.account_id)
+        .bind(&auth_user.user_id)
+        .bind(format_db_datetime(Utc::now()))
+        .execute(&pool)
+        .await;
+    match result {
+        Ok(_) => {}
+        Err(e) if e.to_string().contains("UNIQUE constraint failed") => {
+            tracing::warn!("Account {} is already shared with household {}", request.account_id, id);
+            return Err(AppError::Conflict("Account is already shared with household".into()));
+        }
+        Err(e) => {
+            tracing::error!("Failed to share account {}: {}", request.account_id, e);
+            return Err(AppError::Internal("Failed to share account".into()));
+        }
+    }
+
+    let (household, role) = find_household(&pool, &id, &auth_user.user_id).await?;
+    tracing::info!("Account {} shared with household {}", request.account_id, id);
+    Ok(ApiResponse::ok(json!(with_detail(&pool, household, role).await?)))
+}
+
+/// Stops sharing an account. Its owner or a household owner may do this.
+pub async fn unshare_account(
+    Path((id, account_id)): Path<(String, String)>,
+    State(pool): State<DbPool>,
+    auth_user: AuthUser,
+) -> Result<ApiResponse, AppError> {
+    let (_, role) = find_household(&pool, &id, &auth_user.user_id).await?;
+    let shared_by = sqlx::query_scalar::<_, String>("SELECT shared_by FROM household_accounts WHERE household_id = ? AND account_id = ?")
+        .bind(&id)
+        .bind(&account_id)
+        .fetch_optional(&pool)
+        .await
+        .map_err(|e| {
+            tracing::error!("Failed to look up shared account {}: {}", account_id, e);
+            AppError::Internal("Failed to look up shared account".into())
+        })?
+        .ok_or_else(|| AppError::NotFound("Shared account not found".into()))?;
+    if shared_by != auth_user.user_id && !role.can_manage() {
+        return Err(AppError::Forbidden("Only whoever shared the account or a household owner can unshare it".into()));
+    }
+
+    sqlx::query("DELETE FROM household_accounts WHERE household_id = ? AND account_id = ?")
+        .bind(&id)
+        .bind(&account_id)
+        .execute(&pool)
+        .await
+        .map_err(|e| {
+            tracing::error!("Failed to unshare account {}: {}", account_id, e);
+            AppError::Internal("Failed to unshare account".into())
+        })?;
+
+    tracing::info!("Account {} no longer shared with household {}", account_id, id);
+    Ok(ApiResponse::message("Account unshared successfully"))
+}
+
+/// Emails an invitation to join the household. Owners only. The token is also returned so it
+/// can be passed on another way.
+pub async fn create_household_invite(
+    Path(id): Path<String>,
+    State(pool): State<DbPool>,
+    auth_user: AuthUser,
+    Json(request): Json<CreateHouseholdInviteRequest>,
+) -> Result<ApiResponse, AppError> {
+    request.validate()?;
+
+    let email = request.email.trim().to_lowercase();
+    let household = find_managed_household(&pool, &id, &auth_user.user_id).await?;
+
+    let existing = sqlx::query_scalar::<_, String>(
+        "SELECT hm.user_id FROM household_members hm JOIN users u ON u.id = hm.user_id WHERE hm.household_id = ? AND lower(u.email) = ?"
+    )
+    .bind(&id)
+    .bind(&email)
+    .fetch_optional(&pool)
+    .await
+    .map_err(|e| {
+        tracing::error!("Failed to look up members of household {}: {}", id, e);
+        AppError::Internal("Failed to look up members of household".into())
+    })?;
+    if existing.is_some() {
+        tracing::warn!("{} is already a member of household {}", email, id);
+        return Err(AppError::Conflict("That user is already a member of the household".into()));
+    }
+
+    let role = request.role.unwrap_or(HouseholdRole::Member);
+    let invite = households::create_invite(&pool, &id, &email, role, &auth_user.user_id)
+        .await
+        .map_err(|e| {
+            tracing::error!("Failed to create invite for household {}: {}", id, e);
+            AppError::Internal("Failed to create invite for household".into())
+        })?;
+
+    let inviter = sqlx::query_scalar::<_, String>("SELECT name FROM users WHERE id = ?")
+        .bind(&auth_user.user_id)
+        .fetch_one(&pool)
+        .await
+        .map_err(|e| {
+            tracing::error!("Failed to get user {}: {}", auth_user.user_id, e);
+            AppError::Internal("Failed to get user".into())
+        })?;
+    mailer::send(&pool, None, &email, EmailTemplate::HouseholdInvite {
+        inviter: &inviter,
+        household: &household.name,
+        token: &invite.token,
+    })
+    .await;
+
+    tracing::info!("Invite {} sent for household {}", invite.id, id);
+    let token = invite.token.clone();
+    let mut data = json!(invite);
+    data["token"] = json!(token);
+    Ok(ApiResponse::ok(json!(data)))
+}
+
+/// Invitations not yet accepted. Owners only.
+pub async fn get_household_invites(
+    Path(id): Path<String>,
+    State(pool): State<DbPool>,
+    auth_user: AuthUser,
+    Query(page): Query<PageQuery>,
+) -> Result<ApiResponse, AppError> {
+    find_managed_household(&pool, &id, &auth_user.user_id).await?;
+    let now = format_db_datetime(Utc::now());
+    let invites = sqlx::query_as::<_, HouseholdInvite>(
+        "SELECT * FROM household_invites WHERE household_id = ?1 AND accepted_at IS NULL AND expires_at > ?2 ORDER BY created_at DESC, id LIMIT ?3 OFFSET ?4"
+    )
+    .bind(&id)
+    .bind(&now)
+    .bind(page.limit() as i64)
+    .bind(page.offset() as i64)
+    .fetch_all(&pool)
+    .await
+    .map_err(|e| {
+        tracing::error!("Failed to get invites for household {}: {}", id, e);
+        AppError::Internal("Failed to get invites for household".into())
+    })?;
+    let total = sqlx::query_scalar::<_, i64>(
+        "SELECT COUNT(*) FROM household_invites WHERE household_id = ? AND accepted_at IS NULL AND expires_at > ?"
+    )
+    .bind(&id)
+    .bind(&now)
+    .fetch_one(&pool)
+    .await
+    .map_err(|e| {
+        tracing::error!("Failed to count invites for household {}: {}", id, e);
+        AppError::Internal("Failed to get invites for household".into())
+    })?;
+
+    Ok(ApiResponse::ok(json!(Paginated::new(invites, total as usize, &page))))
+}
+
+/// Withdraws a pending invitation. Owners only.
+pub async fn delete_household_invite(
+    Path((id, invite_id)): Path<(String, String)>,
+    State(pool): State<DbPool>,
+    auth_user: AuthUser,
+) -> Result<ApiResponse, AppError> {
+    find_managed_household(&pool, &id, &auth_user.user_id).await?;
+    let result = sqlx::query("DELETE FROM household_invites WHERE id = ? AND household_id = ? AND accepted_at IS NULL")
+        .bind(&invite_id)
+        .bind(&id)
+        .execute(&pool)
+        .await
+        .map_err(|e| {
+            tracing::error!("Failed to revoke invite {}: {}", invite_id, e);
+            AppError::Internal("Failed to revoke invite".into())
+        })?;
+    if result.rows_affected() == 0 {
+        return Err(AppError::NotFound("Invite not found".into()));
+    }
+
+    tracing::info!("Invite revoked: {}", invite_id);
+    Ok(ApiResponse::message("Invite revoked successfully"))
+}
+
+/// Joins the household an invitation is for. It must be addressed to the user's email.
+pub async fn accept_household_invite(
+    State(pool): State<DbPool>,
+    auth_user: AuthUser,
+    Json(request): Json<AcceptHouseholdInviteRequest>,
+) -> Result<ApiResponse, AppError> {
+    let email = sqlx::query_scalar::<_, String>("SELECT email FROM users WHERE id = ?")
+        .bind(&auth_user.user_id)
+        .fetch_one(&pool)
+        .await
+        .map_err(|e| {
+            tracing::error!("Failed to get user {}: {}", auth_user.user_id, e);
+            AppError::Internal("Failed to get user".into())
+        })?;
+    let outcome = households::accept_invite(&pool, request.token.trim(), &auth_user.user_id, &email)
+        .await
+        .map_err(|e| {
+            tracing::error!("Failed to accept invite: {}", e);
+            AppError::Internal("Failed to accept invite".into())
+        })?;
+    let household_id = match outcome {
+        AcceptOutcome::Joined(household_id) => household_id,
+        AcceptOutcome::NotFound => return Err(AppError::NotFound("Invite not found or expired".into())),
+        AcceptOutcome::WrongEmail => {
+            tracing::warn!("User {} tried to accept an invite for another email", auth_user.user_id);
+            return Err(AppError::Forbidden("This invite was sent to another email address".into()));
+        }
+        AcceptOutcome::AlreadyMember => return Err(AppError::Conflict("You are already a member of this household".into())),
+    };
+
+    let (household, role) = find_household(&pool, &household_id, &auth_user.user_id).await?;
+    tracing::info!("User {} joined household {}", auth_user.user_id, household_id);
+    Ok(ApiResponse::ok(json!(with_detail(&pool, household, role).await?)))
+}
+
+/// Changes a member's role. Owners only; the last owner can't step down.
+pub async fn update_household_member(
+    Path((id, user_id)): Path<(String, String)>,
+    State(pool): State<DbPool>,
+    auth_user: AuthUser,
+    Json(request): Json<UpdateHouseholdMemberRequest>,
+) -> Result<ApiResponse, AppError> {
+    find_managed_household(&pool, &id, &auth_user.user_id).await?;
+    let current = households::membership(&pool, &id, &user_id)
+        .await
+        .map_err(|e| {
+            tracing::error!("Failed to get membership of household {}: {}", id, e);
+            AppError::Internal("Failed to get membership of household".into())
+        })?
+        .ok_or_else(|| AppError::NotFound("Member not found".into()))?;
+    if current.can_manage() && !request.role.can_manage() && owner_count(&pool, &id).await? <= 1 {
+        tracing::warn!("Household {} would be left without an owner", id);
+        return Err(AppError::Conflict("Household would be left without an owner".into()));
+    }
+
+    sqlx::query("UPDATE household_members SET role = ? WHERE household_id = ? AND user_id = ?")
+        .bind(request.role)
+        .bind(&id)
+        .bind(&user_id)
+        .execute(&pool)
+        .await
+        .map_err(|e| {
+            tracing::error!("Failed to update member {} of household {}: {}", user_id, id, e);
+            AppError::Internal("Failed to update member of household".into())
+        })?;
+
+    let (household, role) = find_household(&pool, &id, &auth_user.user_id).await?;
+    tracing::info!("Member {} of household {} is now {:?}", user_id, id, request.role);
+    Ok(ApiResponse::ok(json!(with_detail(&pool, household, role).await?)))
+}
+
+/// Removes a member, or lets a member leave. Accounts they shared stop being shared there.
+/// The last owner can't leave; they delete the household instead.
+pub async fn remove_household_member(
+    Path((id, user_id)): Path<(String, String)>,
+    State(pool): State<DbPool>,
+    auth_user: AuthUser,
+) -> Result<ApiResponse, AppError> {
+    let (_, role) = find_household(&pool, &id, &auth_user.user_id).await?;
+    if user_id != auth_user.user_id && !role.can_manage() {
+        return Err(AppError::Forbidden("Only household owners can remove other members".into()));
+    }
+    let current = households::membership(&pool, &id, &user_id)
+        .await
+        .map_err(|e| {
+            tracing::error!("Failed to get membership of household {}: {}", id, e);
+            AppError::Internal("Failed to get membership of household".into())
+        })?
+        .ok_or_else(|| AppError::NotFound("Member not found".into()))?;
+    if current.can_manage() && owner_count(&pool, &id).await? <= 1 {
+        tracing::warn!("Household {} would be left without an owner", id);
+        return Err(AppError::Conflict("Household would be left without an owner".into()));
+    }
+
+    households::remove_member(&pool, &id, &user_id).await.map_err(|e| {
+        tracing::error!("Failed to remove member {} from household {}: {}", user_id, id, e);
+        AppError::Internal("Failed to remove member from household".into())
+    })?;
+
+    tracing::info!("Member {} removed from household {}", user_id, id);
+    Ok(ApiResponse::message("Member removed successfully"))
+}
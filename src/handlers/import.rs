@@ -0,0 +1,162 @@
+use axum::{
+    extract::{Query, State},
+    response::Json,
+};
+use serde_json::json;
+
+use crate::models::{AppImportRequest, CsvImportRequest, DataArchive, RestoreQuery, StatementImportRequest};
+use crate::services::archive;
+use crate::services::import::{self, mapped_csv, ImportSource, ParsedImport};
+use crate::services::DbPool;
+use crate::middleware::auth::AuthUser;
+use crate::utils::response::ApiResponse;
+use crate::utils::error::AppError;
+
+type ImportError = AppError;
+
+fn bad_request(message: String) -> ImportError {
+    AppError::BadRequest(message)
+}
+
+fn internal_error(e: anyhow::Error) -> ImportError {
+    tracing::error!("Import failed: {}", e);
+    AppError::Internal("Import failed".into())
+}
+
+/// Shared preview/commit flow: dry runs (the default) only report what would happen.
+async fn run_import(
+    pool: &DbPool,
+    user_id: &str,
+    parsed: ParsedImport,
+    dry_run: Option<bool>,
+    currency: Option<String>,
+) -> Result<ApiResponse, ImportError> {
+    let preview = import::preview(pool, user_id, parsed)
+        .await
+        .map_err(internal_error)?;
+
+    if dry_run.unwrap_or(true) {
+        tracing::info!(
+            "Import preview: {} transactions, {} duplicates, {} errors",
+            preview.transactions.len(),
+            preview.duplicates.len(),
+            preview.errors.len()
+        );
+        return Ok(ApiResponse::ok(json!(preview)).with_meta(json!({ "dryRun": true })));
+    }
+
+    let currency = currency.unwrap_or_else(|| "BDT".to_string());
+    let summary = import::apply(pool, user_id, preview, &currency)
+        .await
+        .map_err(internal_error)?;
+
+    tracing::info!("Imported {} transactions for user {}", summary.transactions_imported, user_id);
+    Ok(ApiResponse::ok(json!(summary)).with_meta(json!({ "dryRun": false })))
+}
+
+pub async fn import_from_app(
+    State(pool): State<DbPool>,
+    auth_user: AuthUser,
+    Json(request): Json<AppImportRequest>,
+) -> Result<ApiResponse, ImportError> {
+    let source = ImportSource::parse(&request.app)
+        .filter(|s| matches!(s, ImportSource::MoneyManager | ImportSource::Wallet | ImportSource::Ynab | ImportSource::Mint))
+        .ok_or_else(|| {
+            bad_request(format!(
+                "Unsupported app '{}'; expected money_manager, wallet, ynab or mint",
+                request.app
+            ))
+        })?;
+
+    let parsed = source.parse_content(&request.content).map_err(|e| {
+        tracing::warn!("Failed to parse {} export: {}", request.app, e);
+        bad_request(format!("Could not read export file: {}", e))
+    })?;
+
+    run_import(&pool, &auth_user.user_id, parsed, request.dry_run, request.currency).await
+}
+
+async fn account_name(pool: &DbPool, user_id: &str, account_id: &str) -> Result<String, ImportError> {
+    sqlx::query_scalar::<_, String>("SELECT name FROM accounts WHERE id = ? AND user_id = ?")
+        .bind(account_id)
+        .bind(user_id)
+        .fetch_optional(pool)
+        .await
+        .map_err(|e| internal_error(e.into()))?
+        .ok_or_else(|| AppError::NotFound("Account not found".into()))
+}
+
+pub async fn import_statement(
+    State(pool): State<DbPool>,
+    auth_user: AuthUser,
+    Json(request): Json<StatementImportRequest>,
+) -> Result<ApiResponse, ImportError> {
+    let source = match &request.format {
+        Some(format) => ImportSource::parse(format),
+        None => ImportSource::detect_statement(&request.content),
+    }
+    .filter(|s| matches!(s, ImportSource::Qif | ImportSource::Ofx))
+    .ok_or_else(|| bad_request("Unsupported statement format; expected qif or ofx".to_string()))?;
+
+    let mut parsed = source.parse_content(&request.content).map_err(|e| {
+        tracing::warn!("Failed to parse statement: {}", e);
+        bad_request(format!("Could not read statement file: {}", e))
+    })?;
+
+    if let Some(account_id) = &request.account_id {
+        let account_name = account_name(&pool, &auth_user.user_id, account_id).await?;
+        for transaction in &mut parsed.transactions {
+            transaction.account = account_name.clone();
+        }
+    }
+
+    run_import(&pool, &auth_user.user_id, parsed, request.dry_run, request.currency).await
+}
+
+pub async fn import_transactions_csv(
+    State(pool): State<DbPool>,
+    auth_user: AuthUser,
+    Json(request): Json<CsvImportRequest>,
+) -> Result<ApiResponse, ImportError> {
+    let delimiter = match request.delimiter {
+        Some(delimiter) if delimiter.is_ascii() => delimiter as u8,
+        Some(delimiter) => return Err(bad_request(format!("Unsupported delimiter '{}'", delimiter))),
+        None => mapped_csv::detect_delimiter(&request.content),
+    };
+
+    let account = match &request.account_id {
+        Some(account_id) => Some(account_name(&pool, &auth_user.user_id, account_id).await?),
+        None => None,
+    };
+
+    let parsed = mapped_csv::parse(&request.content, delimiter, &request.mapping, account.as_deref()).map_err(|e| {
+        tracing::warn!("Failed to parse mapped CSV: {}", e);
+        bad_request(format!("Could not read CSV file: {}", e))
+    })?;
+
+    run_import(&pool, &auth_user.user_id, parsed, request.dry_run, request.currency).await
+}
+
+pub async fn import_all(
+    State(pool): State<DbPool>,
+    auth_user: AuthUser,
+    Query(query): Query<RestoreQuery>,
+    Json(archive): Json<DataArchive>,
+) -> Result<ApiResponse, ImportError> {
+    archive::check_header(&archive).map_err(bad_request)?;
+
+    let summary = archive::restore_archive(&pool, &auth_user.user_id, archive, query.conflict, query.validate_only)
+        .await
+        .map_err(internal_error)?;
+
+    if !summary.validate_only && !summary.committed {
+        tracing::warn!("Archive restore rolled back with {} errors", summary.errors.len());
+        return Err(AppError::Invalid {
+            message: "Archive contains invalid rows; nothing was restored".to_string(),
+            details: json!(summary),
+        });
+    }
+
+    tracing::info!("Archive restore for user {}: {:?} inserted", auth_user.user_id, summary.inserted);
+    Ok(ApiResponse::ok(json!(summary)))
+}
@@ -0,0 +1,244 @@
+use axum::{
+    extract::{Path, Query, State},
+    response::Json,
+};
+use chrono::Utc;
+use serde_json::json;
+use uuid::Uuid;
+
+use crate::config;
+use crate::models::{CreateHoldingRequest, Holding, MarketQuote, UpdateHoldingRequest};
+use crate::services::market_prices::{self, PriceProviders};
+use crate::services::{currency, households, investments, DbPool};
+use crate::middleware::auth::AuthUser;
+use crate::middleware::owned::{self, Owned};
+use crate::utils::datetime::format_db_datetime;
+use crate::utils::response::ApiResponse;
+use crate::utils::error::AppError;
+use crate::utils::pagination::{PageQuery, Paginated};
+use crate::utils::validation::{FieldErrors, Validate};
+
+/// The account is the user's own or shared with them by a household they may write to.
+async fn can_use_account(pool: &DbPool, account_id: &str, user_id: &str) -> Result<bool, AppError> {
+    households::can_write_account(pool, account_id, user_id)
+        .await
+        .map_err(|e| {
+            tracing::error!("Failed to look up account {}: {}", account_id, e);
+            AppError::Internal("Failed to look up account".into())
+        })
+}
+
+async fn valued(pool: &DbPool, holding: Holding) -> Result<ApiResponse, AppError> {
+    let valuation = investments::value(pool, holding).await.map_err(|e| {
+        tracing::error!("Failed to value holding: {}", e);
+        AppError::Internal("Failed to value holding".into())
+    })?;
+    Ok(ApiResponse::ok(json!(valuation)))
+}
+
+pub async fn create_holding(
+    State(pool): State<DbPool>,
+    auth_user: AuthUser,
+    Json(request): Json<CreateHoldingRequest>,
+) -> Result<ApiResponse, AppError> {
+    request.validate()?;
+
+    let symbol = request.symbol.trim().to_string();
+    let code = request
+        .currency
+        .as_deref()
+        .map(str::to_uppercase)
+        .unwrap_or_else(|| currency::DEFAULT_DISPLAY_CURRENCY.to_string());
+    let cost_basis = request.cost_basis.unwrap_or_default();
+    let mut errors = FieldErrors::default();
+    errors.fits_currency("costBasis", cost_basis, &code);
+    errors.into_result()?;
+    if let Some(account_id) = &request.account_id {
+        if !can_use_account(&pool, account_id, &auth_user.user_id).await? {
+            return Err(AppError::NotFound("Account not found".into()));
+        }
+    }
+
+    let id = Uuid::new_v4().to_string();
+    let now = format_db_datetime(Utc::now());
+    sqlx::query(
+        r#"
+        INSERT INTO holdings (id, user_id, account_id, asset_type, symbol, name, quantity, cost_basis, currency, created_at, updated_at)
+        VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?)
+        "#,
+    )
+    .bind(&id)
+    .bind(&auth_user.user_id)
+    .bind(&request.account_id)
+    .bind(request.asset_type)
+    .bind(&symbol)
+    .bind(&request.name)
+    .bind(request.quantity)
+    .bind(cost_basis)
+    .bind(&code)
+    .bind(&now)
+    .bind(&now)
+    .execute(&pool)
+    .await
+    .map_err(|e| {
+        tracing::error!("Failed to create holding: {}", e);
+        AppError::Internal("Failed to create holding".into())
+    })?;
+    // Read back rather than `RETURNING *`, which hands whole-number quantities back as integers
+    // that don't decode as `f64`
+    let holding = owned::find::<Holding>(&pool, &id, &auth_user.user_id).await?.ok_or_else(|| AppError::Internal("Failed to create holding".into()))?;
+
+    tracing::info!("Holding created: {} ({} {})", holding.id, holding.asset_type.as_str(), holding.symbol);
+    valued(&pool, holding).await
+}
+
+pub async fn get_holdings(
+    State(pool): State<DbPool>,
+    auth_user: AuthUser,
+    Query(page): Query<PageQuery>,
+) -> Result<ApiResponse, AppError> {
+    let holdings = investments::valuations(&pool, &auth_user.user_id, &page)
+        .await
+        .map_err(|e| {
+            tracing::error!("Failed to get holdings: {}", e);
+            AppError::Internal("Failed to get holdings".into())
+        })?;
+
+    Ok(ApiResponse::ok(json!(holdings)))
+}
+
+pub async fn get_holding(
+    State(pool): State<DbPool>,
+    Owned { resource: holding, .. }: Owned<Holding>,
+) -> Result<ApiResponse, AppError> {
+    valued(&pool, holding).await
+}
+
+pub async fn update_holding(
+    Path(id): Path<String>,
+    State(pool): State<DbPool>,
+    auth_user: AuthUser,
+    Json(request): Json<UpdateHoldingRequest>,
+) -> Result<ApiResponse, AppError> {
+    request.validate()?;
+
+    let current = owned::load::<Holding>(&pool, &id, &auth_user.user_id).await?;
+
+    let code = request.currency.as_deref().map(str::to_uppercase).unwrap_or(current.currency);
+    let cost_basis = request.cost_basis.unwrap_or(current.cost_basis);
+    let mut errors = FieldErrors::default();
+    errors.fits_currency("costBasis", cost_basis, &code);
+    errors.into_result()?;
+    if let Some(account_id) = &request.account_id {
+        if !can_use_account(&pool, account_id, &auth_user.user_id).await? {
+            return Err(AppError::NotFound("Account not found".into()));
+        }
+    }
+
+    sqlx::query(
+        r#"
+        UPDATE holdings SET account_id = COALESCE(?, account_id), name = COALESCE(?, name), quantity = COALESCE(?, quantity),
+            cost_basis = ?, currency = ?, updated_at = ?
+        WHERE id = ? AND user_id = ?
+        "#,
+    )
+    .bind(&request.account_id)
+    .bind(&request.name)
+    .bind(request.quantity)
+    .bind(cost_basis)
+    .bind(&code)
+    .bind(format_db_datetime(Utc::now()))
+    .bind(&id)
+    .bind(&auth_user.user_id)
+    .execute(&pool)
+    .await
+    .map_err(|e| {
+        tracing::error!("Failed to update holding {}: {}", id, e);
+        AppError::Internal("Failed to update holding".into())
+    })?;
+    let holding = owned::load::<Holding>(&pool, &id, &auth_user.user_id).await?;
+
+    tracing::info!("Holding updated successfully: {}", id);
+    valued(&pool, holding).await
+}
+
+pub async fn delete_holding(
+    Path(id): Path<String>,
+    State(pool): State<DbPool>,
+    auth_user: AuthUser,
+) -> Result<ApiResponse, AppError> {
+    let result = sqlx::query("DELETE FROM holdings WHERE id = ? AND user_id = ?")
+        .bind(&id)
+        .bind(&auth_user.user_id)
+        .execute(&pool)
+        .await
+        .map_err(|e| {
+            tracing::error!("Failed to delete holding {}: {}", id, e);
+            AppError::Internal("Failed to delete holding".into())
+        })?;
+    if result.rows_affected() == 0 {
+        return Err(AppError::NotFound("Holding not found".into()));
+    }
+
+    tracing::info!("Holding deleted successfully: {}", id);
+    Ok(ApiResponse::message("Holding deleted successfully"))
+}
+
+/// Cached quotes for the symbols the user holds.
+pub async fn get_market_prices(
+    State(pool): State<DbPool>,
+    auth_user: AuthUser,
+    Query(page): Query<PageQuery>,
+) -> Result<ApiResponse, AppError> {
+    let quotes = sqlx::query_as::<_, MarketQuote>(
+        r#"
+        SELECT p.* FROM market_prices p
+        WHERE EXISTS (SELECT 1 FROM holdings h WHERE h.user_id = ? AND h.asset_type = p.asset_type AND h.symbol = p.symbol)
+        ORDER BY p.asset_type, p.symbol
+        LIMIT ? OFFSET ?
+        "#,
+    )
+    .bind(&auth_user.user_id)
+    .bind(page.limit() as i64)
+    .bind(page.offset() as i64)
+    .fetch_all(&pool)
+    .await
+    .map_err(|e| {
+        tracing::error!("Failed to get market prices: {}", e);
+        AppError::Internal("Failed to get market prices".into())
+    })?;
+    let total = sqlx::query_scalar::<_, i64>(
+        "SELECT COUNT(*) FROM market_prices p WHERE EXISTS (SELECT 1 FROM holdings h WHERE h.user_id = ? AND h.asset_type = p.asset_type AND h.symbol = p.symbol)"
+    )
+    .bind(&auth_user.user_id)
+    .fetch_one(&pool)
+    .await
+    .map_err(|e| {
+        tracing::error!("Failed to count market prices: {}", e);
+        AppError::Internal("Failed to get market prices".into())
+    })?;
+
+    Ok(ApiResponse::ok(json!(Paginated::new(quotes, total as usize, &page))))
+}
+
+/// Fetches quotes for the user's holdings now rather than waiting for the hourly refresh.
+pub async fn refresh_market_prices(
+    State(pool): State<DbPool>,
+    auth_user: AuthUser,
+) -> Result<ApiResponse, AppError> {
+    let providers = PriceProviders::from_config(&config::get().market_data);
+    if providers.is_empty() {
+        tracing::warn!("Market price refresh requested but no provider is configured");
+        return Err(AppError::Unavailable("Market price refresh requested but no provider is configured".into()));
+    }
+    let refreshed = market_prices::refresh_prices(&pool, &providers, Some(&auth_user.user_id))
+        .await
+        .map_err(|e| {
+            tracing::error!("Failed to refresh market prices: {}", e);
+            AppError::Internal("Failed to refresh market prices".into())
+        })?;
+
+    Ok(ApiResponse::ok(json!({
+        "refreshed": refreshed
+    })))
+}
@@ -1,22 +1,27 @@
 use axum::{
-    extract::{Path, State},
-    http::StatusCode,
+    extract::{Path, Query, State},
     response::Json,
 };
-use serde_json::{json, Value};
+use serde_json::json;
 use chrono::Utc;
-use sqlx::Row;
 
 use crate::models::{Liability, CreateLiabilityRequest, UpdateLiabilityRequest};
-use crate::services::DbPool;
+use crate::services::{history::{self, EntityKind}, DbPool};
 use crate::middleware::auth::AuthUser;
+use crate::middleware::owned::{self, Owned};
+use crate::middleware::device::ClientDevice;
+use crate::utils::response::ApiResponse;
+use crate::utils::error::AppError;
+use crate::utils::pagination::PageQuery;
+use crate::utils::validation::Validate;
 
 pub async fn create_liability(
     State(pool): State<DbPool>,
     auth_user: AuthUser,
+    device: ClientDevice,
     Json(request): Json<CreateLiabilityRequest>,
-) -> Result<Json<Value>, StatusCode> {
-    log::info!("📥 POST /liabilities - Creating liability for user {}", auth_user.user_id);
+) -> Result<ApiResponse, AppError> {
+    request.validate()?;
 
     let liability = Liability::new(request, auth_user.user_id.clone());
     let due_date_str = liability.due_date.format("%Y-%m-%d %H:%M:%S").to_string();
@@ -44,20 +49,16 @@ pub async fn create_liability(
 
     match result {
         Ok(_) => {
-            log::info!("✅ Liability created successfully: {} ({})", liability.person_name, liability.id);
-            Ok(Json(json!({
-                "success": true,
-                "data": liability
-            })))
+            history::record(&pool, EntityKind::Liability, &liability.id, &auth_user.user_id, "created", None, &device).await;
+            Ok(ApiResponse::ok(json!(liability)))
         }
         Err(e) => {
-            log::error!("❌ Failed to create liability: {}", e);
+            tracing::error!("Failed to create liability: {}", e);
             let error_msg = e.to_string();
             if error_msg.contains("UNIQUE constraint failed: liabilities.id") {
-                log::warn!("⚠️  Liability with ID {} already exists", liability.id);
-                Err(StatusCode::CONFLICT)
+                Err(AppError::Conflict("Liability already exists".into()))
             } else {
-                Err(StatusCode::INTERNAL_SERVER_ERROR)
+                Err(AppError::Internal("Failed to create liability".into()))
             }
         }
     }
@@ -66,102 +67,35 @@ pub async fn create_liability(
 pub async fn get_liabilities(
     State(pool): State<DbPool>,
     auth_user: AuthUser,
-) -> Result<Json<Value>, StatusCode> {
-    log::info!("📥 GET /liabilities - Fetching liabilities for user {}", auth_user.user_id);
-
-    let result = sqlx::query(
-        "SELECT id, user_id, person_name, amount, currency, due_date, is_paid, description, created_at, updated_at, is_historical_entry, account_id, transaction_id FROM liabilities WHERE user_id = ? ORDER BY due_date ASC"
-    )
-    .bind(&auth_user.user_id)
-    .fetch_all(&pool)
-    .await;
+    Query(page): Query<PageQuery>,
+) -> Result<ApiResponse, AppError> {
+    let result = owned::page::<Liability>(&pool, &auth_user.user_id, "due_date ASC", &page).await;
 
     match result {
-        Ok(rows) => {
-            let liabilities: Vec<_> = rows.into_iter().map(|row| {
-                json!({
-                    "id": row.get::<String, _>("id"),
-                    "user_id": row.get::<String, _>("user_id"),
-                    "person_name": row.get::<String, _>("person_name"),
-                    "amount": row.get::<f64, _>("amount"),
-                    "currency": row.get::<String, _>("currency"),
-                    "due_date": row.get::<String, _>("due_date"),
-                    "is_paid": row.get::<bool, _>("is_paid"),
-                    "description": row.get::<Option<String>, _>("description"),
-                    "created_at": row.get::<String, _>("created_at"),
-                    "updated_at": row.get::<String, _>("updated_at"),
-                    "is_historical_entry": row.get::<bool, _>("is_historical_entry"),
-                    "account_id": row.get::<Option<String>, _>("account_id"),
-                    "transaction_id": row.get::<Option<String>, _>("transaction_id")
-                })
-            }).collect();
-
-            log::info!("✅ Found {} liabilities", liabilities.len());
-            Ok(Json(json!({
-                "success": true,
-                "data": liabilities
-            })))
+        Ok(liabilities) => {
+            Ok(ApiResponse::ok(json!(liabilities)))
         }
         Err(e) => {
-            log::error!("Failed to get liabilities: {}", e);
-            Err(StatusCode::INTERNAL_SERVER_ERROR)
+            tracing::error!("Failed to get liabilities: {}", e);
+            Err(AppError::Internal("Failed to get liabilities".into()))
         }
     }
 }
 
-pub async fn get_liability(
-    Path(id): Path<String>,
-    State(pool): State<DbPool>,
-    auth_user: AuthUser,
-) -> Result<Json<Value>, StatusCode> {
-    log::info!("📥 GET /liabilities/{} - Fetching liability by ID", id);
-
-    let result = sqlx::query(
-        "SELECT id, user_id, person_name, amount, currency, due_date, is_paid, description, created_at, updated_at, is_historical_entry, account_id, transaction_id FROM liabilities WHERE id = ? AND user_id = ?"
-    )
-    .bind(&id)
-    .bind(&auth_user.user_id)
-    .fetch_optional(&pool)
-    .await;
-
-    match result {
-        Ok(Some(row)) => {
-            let liability = json!({
-                "id": row.get::<String, _>("id"),
-                "user_id": row.get::<String, _>("user_id"),
-                "person_name": row.get::<String, _>("person_name"),
-                "amount": row.get::<f64, _>("amount"),
-                "currency": row.get::<String, _>("currency"),
-                "due_date": row.get::<String, _>("due_date"),
-                "is_paid": row.get::<bool, _>("is_paid"),
-                "description": row.get::<Option<String>, _>("description"),
-                "created_at": row.get::<String, _>("created_at"),
-                "updated_at": row.get::<String, _>("updated_at"),
-                "is_historical_entry": row.get::<bool, _>("is_historical_entry"),
-                "account_id": row.get::<Option<String>, _>("account_id"),
-                "transaction_id": row.get::<Option<String>, _>("transaction_id")
-            });
-
-            Ok(Json(json!({
-                "success": true,
-                "data": liability
-            })))
-        }
-        Ok(None) => Err(StatusCode::NOT_FOUND),
-        Err(e) => {
-            log::error!("Failed to get liability: {}", e);
-            Err(StatusCode::INTERNAL_SERVER_ERROR)
-        }
-    }
+pub async fn get_liability(Owned { resource: liability, .. }: Owned<Liability>) -> ApiResponse {
+    ApiResponse::ok(json!(liability))
 }
 
 pub async fn update_liability(
     Path(id): Path<String>,
     State(pool): State<DbPool>,
     auth_user: AuthUser,
+    device: ClientDevice,
     Json(request): Json<UpdateLiabilityRequest>,
-) -> Result<Json<Value>, StatusCode> {
-    log::info!("📥 PUT /liabilities/{} - Updating liability", id);
+) -> Result<ApiResponse, AppError> {
+    request.validate()?;
+
+    let before = history::snapshot(&pool, EntityKind::Liability, &id, &auth_user.user_id).await;
 
     let now = Utc::now().format("%Y-%m-%d %H:%M:%S").to_string();
     let due_date_str = request.due_date.map(|d| d.format("%Y-%m-%d %H:%M:%S").to_string());
@@ -187,18 +121,15 @@ pub async fn update_liability(
     match result {
         Ok(result) => {
             if result.rows_affected() == 0 {
-                Err(StatusCode::NOT_FOUND)
+                Err(AppError::NotFound("Liability not found".into()))
             } else {
-                log::info!("✅ Liability updated successfully: {}", id);
-                Ok(Json(json!({
-                    "success": true,
-                    "message": "Liability updated successfully"
-                })))
+                history::record(&pool, EntityKind::Liability, &id, &auth_user.user_id, "updated", before, &device).await;
+                Ok(ApiResponse::message("Liability updated successfully"))
             }
         }
         Err(e) => {
-            log::error!("Failed to update liability: {}", e);
-            Err(StatusCode::INTERNAL_SERVER_ERROR)
+            tracing::error!("Failed to update liability: {}", e);
+            Err(AppError::Internal("Failed to update liability".into()))
         }
     }
 }
@@ -207,8 +138,9 @@ pub async fn delete_liability(
     Path(id): Path<String>,
     State(pool): State<DbPool>,
     auth_user: AuthUser,
-) -> Result<Json<Value>, StatusCode> {
-    log::info!("📥 DELETE /liabilities/{} - Deleting liability", id);
+    device: ClientDevice,
+) -> Result<ApiResponse, AppError> {
+    let before = history::snapshot(&pool, EntityKind::Liability, &id, &auth_user.user_id).await;
 
     let result = sqlx::query("DELETE FROM liabilities WHERE id = ? AND user_id = ?")
         .bind(&id)
@@ -219,18 +151,15 @@ pub async fn delete_liability(
     match result {
         Ok(result) => {
             if result.rows_affected() == 0 {
-                Err(StatusCode::NOT_FOUND)
+                Err(AppError::NotFound("Liability not found".into()))
             } else {
-                log::info!("✅ Liability deleted successfully: {}", id);
-                Ok(Json(json!({
-                    "success": true,
-                    "message": "Liability deleted successfully"
-                })))
+                history::record(&pool, EntityKind::Liability, &id, &auth_user.user_id, "deleted", before, &device).await;
+                Ok(ApiResponse::message("Liability deleted successfully"))
             }
         }
         Err(e) => {
-            log::error!("Failed to delete liability: {}", e);
-            Err(StatusCode::INTERNAL_SERVER_ERROR)
+            tracing::error!("Failed to delete liability: {}", e);
+            Err(AppError::Internal("Failed to delete liability".into()))
         }
     }
 }
@@ -0,0 +1,163 @@
+use std::convert::Infallible;
+use std::time::Duration;
+
+use axum::{
+    extract::{
+        ws::{Message, WebSocket, WebSocketUpgrade},
+        Query, State,
+    },
+    http::{header, HeaderMap},
+    response::{
+        sse::{Event, KeepAlive, Sse},
+        IntoResponse, Response,
+    },
+};
+use serde::Deserialize;
+use serde_json::json;
+use tokio::sync::broadcast::error::RecvError;
+use tokio_stream::wrappers::{errors::BroadcastStreamRecvError, BroadcastStream, WatchStream};
+use tokio_stream::StreamExt;
+
+use crate::middleware::auth::{authenticate_token, AuthUser};
+use crate::services::live::{self, LiveChange};
+use crate::services::DbPool;
+use crate::utils::error::AppError;
+
+/// How often idle connections are pinged, so proxies don't close them.
+const PING_INTERVAL: Duration = Duration::from_secs(30);
+
+#[derive(Debug, Deserialize)]
+pub struct LiveQuery {
+    /// For clients that can't set `Authorization`, e.g. browser WebSockets and `EventSource`.
+    pub token: Option<String>,
+}
+
+/// Authenticates with `Authorization: Bearer <token>` or, failing that, `?token=`.
+async fn authenticate(pool: &DbPool, headers: &HeaderMap, query: LiveQuery) -> Result<AuthUser, AppError> {
+    let token = headers
+        .get(header::AUTHORIZATION)
+        .and_then(|value| value.to_str().ok())
+        .and_then(|value| value.strip_prefix("Bearer "))
+        .map(str::to_string)
+        .or(query.token)
+        .ok_or_else(|| AppError::Unauthorized("Missing token".into()))?;
+    authenticate_token(pool, &token).await
+}
+
+/// Sent instead of changes that can't be delivered; the client should pull
+/// `/api/sync/changes` to catch up.
+fn resync_message() -> String {
+    json!({ "type": "resync" }).to_string()
+}
+
+/// Opens a WebSocket that pushes a message for every account, transaction, budget, savings
+/// goal, recurring transaction, loan or liability change the user can see, from any device.
+pub async fn live_updates(
+    ws: WebSocketUpgrade,
+    State(pool): State<DbPool>,
+    Query(query): Query<LiveQuery>,
+    headers: HeaderMap,
+) -> Result<Response, AppError> {
+    let auth_user = authenticate(&pool, &headers, query).await?;
+    Ok(ws.on_upgrade(move |socket| stream_changes(socket, auth_user.user_id)))
+}
+
+async fn stream_changes(mut socket: WebSocket, user_id: String) {
+    let mut changes = live::subscribe();
+    let closing = async {
+        let _ = live::closing().wait_for(|closing| *closing).await;
+    };
+    tokio::pin!(closing);
+    let mut ping = tokio::time::interval(PING_INTERVAL);
+
+    let hello = json!({ "type": "connected" });
+    if socket.send(Message::Text(hello.to_string())).await.is_err() {
+        return;
+    }
+
+    loop {
+        tokio::select! {
+            change = changes.recv() => {
+                let message = match change {
+                    Ok(change) if change.recipients.contains(&user_id) => change.payload.to_string(),
+                    Ok(_) => continue,
+                    Err(RecvError::Lagged(skipped)) => {
+                        tracing::warn!("Live updates for user {} fell {} changes behind", user_id, skipped);
+                        resync_message()
+                    }
+                    Err(RecvError::Closed) => break,
+                };
+                if socket.send(Message::Text(message)).await.is_err() {
+                    break;
+                }
+            }
+            incoming = socket.recv() => {
+                match incoming {
+                    Some(Ok(Message::Close(_))) | Some(Err(_)) | None => break,
+                    // Pings are answered by the socket; clients have nothing else to say
+                    Some(Ok(_)) => {}
+                }
+            }
+            _ = ping.tick() => {
+                if socket.send(Message::Ping(Vec::new())).await.is_err() {
+                    break;
+                }
+            }
+            _ = &mut closing => {
+                let _ = socket.send(Message::Close(None)).await;
+                break;
+            }
+        }
+    }
+
+    tracing::info!("Live updates closed for user {}", user_id);
+}
+
+fn change_event(change: &LiveChange) -> Event {
+    Event::default().id(change.event_id()).data(change.payload.to_string())
+}
+
+/// The same changes as `/ws`, as Server-Sent Events for clients that can't hold a WebSocket.
+/// Each event's `id` can be sent back as `Last-Event-ID` on reconnect to receive what was
+/// missed; when that is no longer possible, the stream starts with a `resync` message.
+pub async fn stream_events(
+    State(pool): State<DbPool>,
+    Query(query): Query<LiveQuery>,
+    headers: HeaderMap,
+) -> Result<Response, AppError> {
+    let auth_user = authenticate(&pool, &headers, query).await?;
+    let last_event_id = headers.get("Last-Event-ID").and_then(|value| value.to_str().ok());
+    // Subscribe before reading the replay so nothing falls between the two
+    let changes = BroadcastStream::new(live::subscribe());
+    let (replay, resync) = match last_event_id.map(live::changes_after) {
+        None => (Vec::new(), false),
+        Some(Some(replay)) => (replay, false),
+        Some(None) => (Vec::new(), true),
+    };
+    let replayed_up_to = replay.last().map(|change| change.sequence).unwrap_or(0);
+
+    let user_id = auth_user.user_id;
+    let head: Vec<Event> = resync
+        .then(|| Event::default().data(resync_message()))
+        .into_iter()
+        .chain(replay.iter().filter(|change| change.recipients.contains(&user_id)).map(|change| change_event(change)))
+        .collect();
+    let live = changes.filter_map(move |change| match change {
+        Ok(change) if change.sequence > replayed_up_to && change.recipients.contains(&user_id) => Some(change_event(&change)),
+        Ok(_) => None,
+        Err(BroadcastStreamRecvError::Lagged(skipped)) => {
+            tracing::warn!("Event stream for user {} fell {} changes behind", user_id, skipped);
+            Some(Event::default().data(resync_message()))
+        }
+    });
+    // Ends the stream at shutdown, so it doesn't hold the server open
+    let closing = WatchStream::new(live::closing()).filter(|closing| *closing).map(|_| None);
+    let events = tokio_stream::iter(head)
+        .chain(live)
+        .map(Some)
+        .merge(closing)
+        .map_while(|event| event)
+        .map(Ok::<_, Infallible>);
+
+    Ok(Sse::new(events).keep_alive(KeepAlive::default()).into_response())
+}
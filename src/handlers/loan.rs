@@ -1,22 +1,27 @@
 use axum::{
-    extract::{Path, State},
-    http::StatusCode,
+    extract::{Path, Query, State},
     response::Json,
 };
-use serde_json::{json, Value};
+use serde_json::json;
 use chrono::Utc;
-use sqlx::Row;
 
 use crate::models::{Loan, CreateLoanRequest, UpdateLoanRequest};
-use crate::services::DbPool;
+use crate::services::{history::{self, EntityKind}, DbPool};
 use crate::middleware::auth::AuthUser;
+use crate::middleware::device::ClientDevice;
+use crate::middleware::owned::{self, Owned};
+use crate::utils::response::ApiResponse;
+use crate::utils::error::AppError;
+use crate::utils::pagination::PageQuery;
+use crate::utils::validation::Validate;
 
 pub async fn create_loan(
     State(pool): State<DbPool>,
     auth_user: AuthUser,
+    device: ClientDevice,
     Json(request): Json<CreateLoanRequest>,
-) -> Result<Json<Value>, StatusCode> {
-    log::info!("📥 POST /loans - Creating loan for user {}", auth_user.user_id);
+) -> Result<ApiResponse, AppError> {
+    request.validate()?;
 
     let loan = Loan::new(request, auth_user.user_id.clone());
     let loan_date_str = loan.loan_date.format("%Y-%m-%d %H:%M:%S").to_string();
@@ -46,20 +51,16 @@ pub async fn create_loan(
 
     match result {
         Ok(_) => {
-            log::info!("✅ Loan created successfully: {} ({})", loan.person_name, loan.id);
-            Ok(Json(json!({
-                "success": true,
-                "data": loan
-            })))
+            history::record(&pool, EntityKind::Loan, &loan.id, &auth_user.user_id, "created", None, &device).await;
+            Ok(ApiResponse::ok(json!(loan)))
         }
         Err(e) => {
-            log::error!("❌ Failed to create loan: {}", e);
+            tracing::error!("Failed to create loan: {}", e);
             let error_msg = e.to_string();
             if error_msg.contains("UNIQUE constraint failed: loans.id") {
-                log::warn!("⚠️  Loan with ID {} already exists", loan.id);
-                Err(StatusCode::CONFLICT)
+                Err(AppError::Conflict("Loan already exists".into()))
             } else {
-                Err(StatusCode::INTERNAL_SERVER_ERROR)
+                Err(AppError::Internal("Failed to create loan".into()))
             }
         }
     }
@@ -68,104 +69,35 @@ pub async fn create_loan(
 pub async fn get_loans(
     State(pool): State<DbPool>,
     auth_user: AuthUser,
-) -> Result<Json<Value>, StatusCode> {
-    log::info!("📥 GET /loans - Fetching loans for user {}", auth_user.user_id);
-
-    let result = sqlx::query(
-        "SELECT id, user_id, person_name, amount, currency, loan_date, return_date, is_returned, description, created_at, updated_at, is_historical_entry, account_id, transaction_id FROM loans WHERE user_id = ? ORDER BY loan_date DESC"
-    )
-    .bind(&auth_user.user_id)
-    .fetch_all(&pool)
-    .await;
+    Query(page): Query<PageQuery>,
+) -> Result<ApiResponse, AppError> {
+    let result = owned::page::<Loan>(&pool, &auth_user.user_id, "loan_date DESC", &page).await;
 
     match result {
-        Ok(rows) => {
-            let loans: Vec<_> = rows.into_iter().map(|row| {
-                json!({
-                    "id": row.get::<String, _>("id"),
-                    "user_id": row.get::<String, _>("user_id"),
-                    "person_name": row.get::<String, _>("person_name"),
-                    "amount": row.get::<f64, _>("amount"),
-                    "currency": row.get::<String, _>("currency"),
-                    "loan_date": row.get::<String, _>("loan_date"),
-                    "return_date": row.get::<Option<String>, _>("return_date"),
-                    "is_returned": row.get::<bool, _>("is_returned"),
-                    "description": row.get::<Option<String>, _>("description"),
-                    "created_at": row.get::<String, _>("created_at"),
-                    "updated_at": row.get::<String, _>("updated_at"),
-                    "is_historical_entry": row.get::<bool, _>("is_historical_entry"),
-                    "account_id": row.get::<Option<String>, _>("account_id"),
-                    "transaction_id": row.get::<Option<String>, _>("transaction_id")
-                })
-            }).collect();
-
-            log::info!("✅ Found {} loans", loans.len());
-            Ok(Json(json!({
-                "success": true,
-                "data": loans
-            })))
+        Ok(loans) => {
+            Ok(ApiResponse::ok(json!(loans)))
         }
         Err(e) => {
-            log::error!("Failed to get loans: {}", e);
-            Err(StatusCode::INTERNAL_SERVER_ERROR)
+            tracing::error!("Failed to get loans: {}", e);
+            Err(AppError::Internal("Failed to get loans".into()))
         }
     }
 }
 
-pub async fn get_loan(
-    Path(id): Path<String>,
-    State(pool): State<DbPool>,
-    auth_user: AuthUser,
-) -> Result<Json<Value>, StatusCode> {
-    log::info!("📥 GET /loans/{} - Fetching loan by ID", id);
-
-    let result = sqlx::query(
-        "SELECT id, user_id, person_name, amount, currency, loan_date, return_date, is_returned, description, created_at, updated_at, is_historical_entry, account_id, transaction_id FROM loans WHERE id = ? AND user_id = ?"
-    )
-    .bind(&id)
-    .bind(&auth_user.user_id)
-    .fetch_optional(&pool)
-    .await;
-
-    match result {
-        Ok(Some(row)) => {
-            let loan = json!({
-                "id": row.get::<String, _>("id"),
-                "user_id": row.get::<String, _>("user_id"),
-                "person_name": row.get::<String, _>("person_name"),
-                "amount": row.get::<f64, _>("amount"),
-                "currency": row.get::<String, _>("currency"),
-                "loan_date": row.get::<String, _>("loan_date"),
-                "return_date": row.get::<Option<String>, _>("return_date"),
-                "is_returned": row.get::<bool, _>("is_returned"),
-                "description": row.get::<Option<String>, _>("description"),
-                "created_at": row.get::<String, _>("created_at"),
-                "updated_at": row.get::<String, _>("updated_at"),
-                "is_historical_entry": row.get::<bool, _>("is_historical_entry"),
-                "account_id": row.get::<Option<String>, _>("account_id"),
-                "transaction_id": row.get::<Option<String>, _>("transaction_id")
-            });
-
-            Ok(Json(json!({
-                "success": true,
-                "data": loan
-            })))
-        }
-        Ok(None) => Err(StatusCode::NOT_FOUND),
-        Err(e) => {
-            log::error!("Failed to get loan: {}", e);
-            Err(StatusCode::INTERNAL_SERVER_ERROR)
-        }
-    }
+pub async fn get_loan(Owned { resource: loan, .. }: Owned<Loan>) -> ApiResponse {
+    ApiResponse::ok(json!(loan))
 }
 
 pub async fn update_loan(
     Path(id): Path<String>,
     State(pool): State<DbPool>,
     auth_user: AuthUser,
+    device: ClientDevice,
     Json(request): Json<UpdateLoanRequest>,
-) -> Result<Json<Value>, StatusCode> {
-    log::info!("📥 PUT /loans/{} - Updating loan", id);
+) -> Result<ApiResponse, AppError> {
+    request.validate()?;
+
+    let before = history::snapshot(&pool, EntityKind::Loan, &id, &auth_user.user_id).await;
 
     let now = Utc::now().format("%Y-%m-%d %H:%M:%S").to_string();
     let loan_date_str = request.loan_date.map(|d| d.format("%Y-%m-%d %H:%M:%S").to_string());
@@ -193,18 +125,15 @@ pub async fn update_loan(
     match result {
         Ok(result) => {
             if result.rows_affected() == 0 {
-                Err(StatusCode::NOT_FOUND)
+                Err(AppError::NotFound("Loan not found".into()))
             } else {
-                log::info!("✅ Loan updated successfully: {}", id);
-                Ok(Json(json!({
-                    "success": true,
-                    "message": "Loan updated successfully"
-                })))
+                history::record(&pool, EntityKind::Loan, &id, &auth_user.user_id, "updated", before, &device).await;
+                Ok(ApiResponse::message("Loan updated successfully"))
             }
         }
         Err(e) => {
-            log::error!("Failed to update loan: {}", e);
-            Err(StatusCode::INTERNAL_SERVER_ERROR)
+            tracing::error!("Failed to update loan: {}", e);
+            Err(AppError::Internal("Failed to update loan".into()))
         }
     }
 }
@@ -213,8 +142,9 @@ pub async fn delete_loan(
     Path(id): Path<String>,
     State(pool): State<DbPool>,
     auth_user: AuthUser,
-) -> Result<Json<Value>, StatusCode> {
-    log::info!("📥 DELETE /loans/{} - Deleting loan", id);
+    device: ClientDevice,
+) -> Result<ApiResponse, AppError> {
+    let before = history::snapshot(&pool, EntityKind::Loan, &id, &auth_user.user_id).await;
 
     let result = sqlx::query("DELETE FROM loans WHERE id = ? AND user_id = ?")
         .bind(&id)
@@ -225,18 +155,15 @@ pub async fn delete_loan(
     match result {
         Ok(result) => {
             if result.rows_affected() == 0 {
-                Err(StatusCode::NOT_FOUND)
+                Err(AppError::NotFound("Loan not found".into()))
             } else {
-                log::info!("✅ Loan deleted successfully: {}", id);
-                Ok(Json(json!({
-                    "success": true,
-                    "message": "Loan deleted successfully"
-                })))
+                history::record(&pool, EntityKind::Loan, &id, &auth_user.user_id, "deleted", before, &device).await;
+                Ok(ApiResponse::message("Loan deleted successfully"))
             }
         }
         Err(e) => {
-            log::error!("Failed to delete loan: {}", e);
-            Err(StatusCode::INTERNAL_SERVER_ERROR)
+            tracing::error!("Failed to delete loan: {}", e);
+            Err(AppError::Internal("Failed to delete loan".into()))
         }
     }
 }
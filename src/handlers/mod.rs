@@ -5,7 +5,40 @@ pub mod liability;
 pub mod loan;
 pub mod auth;
 pub mod user_data;
+pub mod profile;
 pub mod preference;
 pub mod savings_goal;
 pub mod budget;
 pub mod recurring_transaction;
+pub mod category_suggestion;
+pub mod category_set;
+pub mod category_archive;
+pub mod amortization;
+pub mod cash_count;
+pub mod reconciliation;
+pub mod event;
+pub mod export;
+pub mod import;
+pub mod usage;
+pub mod history;
+pub mod calendar;
+pub mod net_worth;
+pub mod notification;
+pub mod device;
+pub mod webhook;
+pub mod report;
+pub mod dashboard;
+pub mod stats;
+pub mod currency;
+pub mod investment;
+pub mod term_deposit;
+pub mod emi_plan;
+pub mod subscription;
+pub mod household;
+pub mod split;
+pub mod reminder;
+pub mod sync;
+pub mod live;
+pub mod health;
+pub mod version;
+pub mod admin;
@@ -0,0 +1,39 @@
+use axum::extract::State;
+use serde_json::json;
+
+use crate::services::{currency, net_worth};
+use crate::services::DbPool;
+use crate::middleware::auth::AuthUser;
+use crate::utils::response::ApiResponse;
+use crate::utils::error::AppError;
+
+pub async fn get_net_worth(
+    State(pool): State<DbPool>,
+    auth_user: AuthUser,
+) -> Result<ApiResponse, AppError> {
+    let (totals, excluded_accounts) = net_worth::net_worth(&pool, &auth_user.user_id)
+        .await
+        .map_err(|e| {
+            tracing::error!("Failed to compute net worth: {}", e);
+            AppError::Internal("Failed to compute net worth".into())
+        })?;
+
+    let display_currency = currency::display_currency(&pool, &auth_user.user_id)
+        .await
+        .map_err(|e| {
+            tracing::error!("Failed to load display currency: {}", e);
+            AppError::Internal("Failed to load display currency".into())
+        })?;
+    let consolidated = net_worth::consolidate(&pool, &auth_user.user_id, &totals, &display_currency)
+        .await
+        .map_err(|e| {
+            tracing::error!("Failed to consolidate net worth: {}", e);
+            AppError::Internal("Failed to consolidate net worth".into())
+        })?;
+
+    Ok(ApiResponse::ok(json!({
+        "totals": totals,
+        "consolidated": consolidated,
+        "excludedAccounts": excluded_accounts
+    })))
+}
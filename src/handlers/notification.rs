@@ -0,0 +1,53 @@
+use axum::extract::{Path, Query, State};
+use serde_json::json;
+
+use crate::models::NotificationQuery;
+use crate::services::notifications;
+use crate::services::DbPool;
+use crate::middleware::auth::AuthUser;
+use crate::utils::response::ApiResponse;
+use crate::utils::error::AppError;
+use crate::utils::pagination::PageQuery;
+
+fn internal_error(e: anyhow::Error) -> AppError {
+    tracing::error!("Notification query failed: {}", e);
+    AppError::Internal("Notification query failed".into())
+}
+
+pub async fn get_notifications(
+    State(pool): State<DbPool>,
+    auth_user: AuthUser,
+    Query(query): Query<NotificationQuery>,
+    Query(page): Query<PageQuery>,
+) -> Result<ApiResponse, AppError> {
+    let items = notifications::list(&pool, &auth_user.user_id, query.unread.unwrap_or(false), &page)
+        .await
+        .map_err(internal_error)?;
+    let unread = notifications::unread_count(&pool, &auth_user.user_id).await.map_err(internal_error)?;
+
+    Ok(ApiResponse::ok(json!(items)).with_meta(json!({ "unreadCount": unread })))
+}
+
+pub async fn mark_notification_read(
+    Path(id): Path<String>,
+    State(pool): State<DbPool>,
+    auth_user: AuthUser,
+) -> Result<ApiResponse, AppError> {
+    if !notifications::mark_read(&pool, &auth_user.user_id, &id).await.map_err(internal_error)? {
+        tracing::warn!("Notification not found: {}", id);
+        return Err(AppError::NotFound("Notification not found".into()));
+    }
+
+    Ok(ApiResponse::message("Notification marked as read"))
+}
+
+pub async fn mark_all_notifications_read(
+    State(pool): State<DbPool>,
+    auth_user: AuthUser,
+) -> Result<ApiResponse, AppError> {
+    let updated = notifications::mark_all_read(&pool, &auth_user.user_id).await.map_err(internal_error)?;
+
+    Ok(ApiResponse::ok(json!({
+        "marked": updated
+    })))
+}
@@ -0,0 +1,166 @@
+use axum::{
+    extract::{Multipart, Path, State},
+    http::header,
+    response::{IntoResponse, Json, Response},
+};
+use bcrypt::verify;
+use chrono::Utc;
+use serde_json::json;
+
+use crate::handlers::auth::send_verification_email;
+use crate::models::{UpdateProfileRequest, User, UserResponse};
+use crate::services::{avatars, storage, DbPool};
+use crate::middleware::auth::AuthUser;
+use crate::utils::error::AppError;
+use crate::utils::response::ApiResponse;
+use crate::utils::validation::{FieldErrors, Validate};
+
+async fn find_user(pool: &DbPool, user_id: &str) -> Result<User, AppError> {
+    sqlx::query_as::<_, User>("SELECT * FROM users WHERE id = ?")
+        .bind(user_id)
+        .fetch_optional(pool)
+        .await
+        .map_err(|e| {
+            tracing::error!("Failed to load user {}: {}", user_id, e);
+            AppError::Internal("Failed to load profile".into())
+        })?
+        .ok_or_else(|| AppError::NotFound("User not found".into()))
+}
+
+/// The signed-in user's profile.
+pub async fn get_me(
+    State(pool): State<DbPool>,
+    auth_user: AuthUser,
+) -> Result<ApiResponse, AppError> {
+    let user = find_user(&pool, &auth_user.user_id).await?;
+    Ok(ApiResponse::ok(json!(UserResponse::from(user))))
+}
+
+/// Changes the signed-in user's name or email. A new email takes the current password, must not
+/// belong to another account, and is unverified until the link sent to it is followed.
+pub async fn update_me(
+    State(pool): State<DbPool>,
+    auth_user: AuthUser,
+    Json(payload): Json<UpdateProfileRequest>,
+) -> Result<ApiResponse, AppError> {
+    payload.validate()?;
+    let mut user = find_user(&pool, &auth_user.user_id).await?;
+    let internal_error = |e: sqlx::Error| {
+        tracing::error!("Failed to update profile of user {}: {}", auth_user.user_id, e);
+        AppError::Internal("Failed to update profile".into())
+    };
+
+    // Compared and stored like signin reads them
+    let new_email = payload.email.map(|email| email.trim().to_lowercase()).filter(|email| *email != user.email);
+    if let Some(email) = &new_email {
+        let mut errors = FieldErrors::default();
+        errors.check(payload.current_password.is_some(), "currentPassword", "is required to change the email address");
+        errors.into_result()?;
+        let password = payload.current_password.as_deref().unwrap_or_default();
+        let is_valid = verify(password, &user.password_hash).map_err(|e| {
+            tracing::error!("Failed to verify password of user {}: {}", user.id, e);
+            AppError::Internal("Failed to verify password".into())
+        })?;
+        if !is_valid {
+            tracing::warn!("Wrong password for email change of user {}", user.id);
+            return Err(AppError::Forbidden("Current password is incorrect".into()));
+        }
+
+        let taken: bool = sqlx::query_scalar("SELECT EXISTS (SELECT 1 FROM users WHERE email = ? AND id != ?)")
+            .bind(email)
+            .bind(&user.id)
+            .fetch_one(&pool)
+            .await
+            .map_err(internal_error)?;
+        if taken {
+            return Err(AppError::Conflict("User with this email already exists".into()));
+        }
+        user.email = email.clone();
+        user.email_verified_at = None;
+    }
+    if let Some(name) = payload.name {
+        user.name = name.trim().to_string();
+    }
+    user.updated_at = Utc::now();
+
+    sqlx::query("UPDATE users SET name = ?, email = ?, email_verified_at = ?, updated_at = ? WHERE id = ?")
+        .bind(&user.name)
+        .bind(&user.email)
+        .bind(user.email_verified_at)
+        .bind(user.updated_at)
+        .bind(&user.id)
+        .execute(&pool)
+        .await
+        .map_err(internal_error)?;
+
+    if new_email.is_some() {
+        tracing::info!("User {} changed their email address", user.id);
+        send_verification_email(&pool, &user).await;
+    }
+    Ok(ApiResponse::ok(json!(UserResponse::from(user))))
+}
+
+/// Sets the signed-in user's avatar from the multipart field `avatar` (or `file`): a PNG, JPEG
+/// or WebP, cropped square and scaled down. Answers with the profile, whose `avatarUrl` points
+/// at the new image.
+pub async fn upload_avatar(
+    State(pool): State<DbPool>,
+    auth_user: AuthUser,
+    mut multipart: Multipart,
+) -> Result<ApiResponse, AppError> {
+    let mut upload = None;
+    while let Some(field) = multipart.next_field().await.map_err(|e| {
+        tracing::warn!("Unreadable avatar upload from user {}: {}", auth_user.user_id, e);
+        AppError::BadRequest("Unreadable multipart body".into())
+    })? {
+        if matches!(field.name(), Some("avatar" | "file")) {
+            upload = Some(field.bytes().await.map_err(|e| {
+                tracing::warn!("Unreadable avatar upload from user {}: {}", auth_user.user_id, e);
+                AppError::BadRequest("Unreadable avatar upload".into())
+            })?);
+            break;
+        }
+    }
+    let upload = upload.ok_or_else(|| AppError::BadRequest("Missing the avatar field".into()))?;
+
+    // Decoding and scaling are CPU-bound, so they stay off the request threads
+    let png = tokio::task::spawn_blocking(move || avatars::process(&upload))
+        .await
+        .map_err(|e| {
+            tracing::error!("Avatar processing panicked: {}", e);
+            AppError::Internal("Failed to process avatar".into())
+        })?
+        .map_err(|message| {
+            tracing::warn!("Rejected avatar from user {}: {}", auth_user.user_id, message);
+            AppError::Unprocessable(message)
+        })?;
+
+    avatars::replace(&pool, &auth_user.user_id, png).await.map_err(|e| {
+        tracing::error!("Failed to store avatar of user {}: {}", auth_user.user_id, e);
+        AppError::Internal("Failed to store avatar".into())
+    })?;
+
+    let user = find_user(&pool, &auth_user.user_id).await?;
+    Ok(ApiResponse::ok(json!(UserResponse::from(user))))
+}
+
+/// An avatar, by the file name in its URL. Each upload gets a new name, so it is cached for good.
+pub async fn get_avatar(Path(file): Path<String>) -> Result<Response, AppError> {
+    let bytes = storage::get()
+        .get(&avatars::key(&file))
+        .await
+        .map_err(|e| {
+            tracing::warn!("Failed to read avatar {}: {}", file, e);
+            AppError::NotFound("Avatar not found".into())
+        })?
+        .ok_or_else(|| AppError::NotFound("Avatar not found".into()))?;
+
+    Ok((
+        [
+            (header::CONTENT_TYPE, "image/png"),
+            (header::CACHE_CONTROL, "public, max-age=31536000, immutable"),
+        ],
+        bytes,
+    )
+        .into_response())
+}
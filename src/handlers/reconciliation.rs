@@ -0,0 +1,235 @@
+use axum::{
+    extract::{Path, Query, State},
+    http::StatusCode,
+    response::Json,
+};
+use chrono::Utc;
+use serde_json::json;
+use uuid::Uuid;
+
+use crate::models::{ClearTransactionsRequest, CreateReconciliationRequest, Reconciliation, ReconciliationStatus};
+use crate::services::households::{self, AccountAccess};
+use crate::services::user_time::{self, UserTime};
+use crate::services::{reconciliations, DbPool};
+use crate::middleware::auth::AuthUser;
+use crate::utils::datetime::format_db_datetime;
+use crate::utils::response::ApiResponse;
+use crate::utils::error::AppError;
+use crate::utils::pagination::{PageQuery, Paginated};
+use crate::utils::validation::{FieldErrors, Validate};
+
+fn internal_error(e: anyhow::Error) -> AppError {
+    tracing::error!("Reconciliation query failed: {}", e);
+    AppError::Internal("Reconciliation query failed".into())
+}
+
+/// The user's access to the account, refusing view-only members when `write` is set.
+async fn account_access(pool: &DbPool, account_id: &str, user_id: &str, write: bool) -> Result<AccountAccess, AppError> {
+    let access = households::account_access(pool, account_id, user_id)
+        .await
+        .map_err(internal_error)?
+        .ok_or_else(|| AppError::NotFound("Account not found".into()))?;
+    if write && !access.can_write() {
+        tracing::warn!("User {} can only view shared account {}", user_id, account_id);
+        return Err(AppError::Forbidden("You can only view this shared account".into()));
+    }
+    Ok(access)
+}
+
+/// Statement days end at midnight on the account owner's clock.
+async fn owner_time(pool: &DbPool, access: &AccountAccess) -> Result<UserTime, AppError> {
+    user_time::for_user(pool, &access.owner_id).await.map_err(internal_error)
+}
+
+async fn find_reconciliation(pool: &DbPool, account_id: &str, id: &str) -> Result<Reconciliation, AppError> {
+    reconciliations::find(pool, account_id, id)
+        .await
+        .map_err(internal_error)?
+        .ok_or_else(|| AppError::NotFound("Reconciliation not found".into()))
+}
+
+fn require_open(reconciliation: &Reconciliation) -> Result<(), AppError> {
+    if reconciliation.status != ReconciliationStatus::Open {
+        return Err(AppError::Conflict("Reconciliation is already completed".into()));
+    }
+    Ok(())
+}
+
+async fn summary_response(pool: &DbPool, reconciliation: Reconciliation, time: &UserTime) -> Result<ApiResponse, AppError> {
+    let summary = reconciliations::summary(pool, reconciliation, time).await.map_err(internal_error)?;
+    Ok(ApiResponse::ok(json!(summary)))
+}
+
+pub async fn create_reconciliation(
+    Path(account_id): Path<String>,
+    State(pool): State<DbPool>,
+    auth_user: AuthUser,
+    Json(request): Json<CreateReconciliationRequest>,
+) -> Result<(StatusCode, ApiResponse), AppError> {
+    request.validate()?;
+    let access = account_access(&pool, &account_id, &auth_user.user_id, true).await?;
+    let currency = sqlx::query_scalar::<_, String>("SELECT currency FROM accounts WHERE id = ?")
+        .bind(&account_id)
+        .fetch_one(&pool)
+        .await
+        .map_err(|e| internal_error(e.into()))?;
+    let mut errors = FieldErrors::default();
+    errors.fits_currency("statementBalance", request.statement_balance, &currency);
+    errors.into_result()?;
+
+    if let Some(open) = reconciliations::open_for_account(&pool, &account_id).await.map_err(internal_error)? {
+        tracing::warn!("Account {} already has open reconciliation {}", account_id, open.id);
+        return Err(AppError::Conflict("Finish or complete the open reconciliation first".into()));
+    }
+
+    let now = Utc::now();
+    let reconciliation = Reconciliation {
+        id: Uuid::new_v4().to_string(),
+        account_id: account_id.clone(),
+        user_id: access.owner_id.clone(),
+        statement_date: request.statement_date,
+        statement_balance: request.statement_balance,
+        status: ReconciliationStatus::Open,
+        created_at: now,
+        completed_at: None,
+    };
+    sqlx::query(
+        "INSERT INTO reconciliations (id, account_id, user_id, statement_date, statement_balance, status, created_at) VALUES (?, ?, ?, ?, ?, ?, ?)"
+    )
+    .bind(&reconciliation.id)
+    .bind(&reconciliation.account_id)
+    .bind(&reconciliation.user_id)
+    .bind(format_db_datetime(reconciliation.statement_date))
+    .bind(reconciliation.statement_balance)
+    .bind(reconciliation.status)
+    .bind(format_db_datetime(now))
+    .execute(&pool)
+    .await
+    .map_err(|e| internal_error(e.into()))?;
+
+    tracing::info!("Started reconciliation {} for account {}", reconciliation.id, account_id);
+    let time = owner_time(&pool, &access).await?;
+    Ok((StatusCode::CREATED, summary_response(&pool, reconciliation, &time).await?))
+}
+
+/// The account's reconciliations, newest statement first.
+pub async fn get_reconciliations(
+    Path(account_id): Path<String>,
+    State(pool): State<DbPool>,
+    auth_user: AuthUser,
+    Query(page): Query<PageQuery>,
+) -> Result<ApiResponse, AppError> {
+    account_access(&pool, &account_id, &auth_user.user_id, false).await?;
+
+    let items = sqlx::query_as::<_, Reconciliation>(
+        "SELECT * FROM reconciliations WHERE account_id = ? ORDER BY statement_date DESC, created_at DESC, id LIMIT ? OFFSET ?"
+    )
+    .bind(&account_id)
+    .bind(page.limit() as i64)
+    .bind(page.offset() as i64)
+    .fetch_all(&pool)
+    .await
+    .map_err(|e| internal_error(e.into()))?;
+    let total = sqlx::query_scalar::<_, i64>("SELECT COUNT(*) FROM reconciliations WHERE account_id = ?")
+        .bind(&account_id)
+        .fetch_one(&pool)
+        .await
+        .map_err(|e| internal_error(e.into()))?;
+
+    Ok(ApiResponse::ok(json!(Paginated::new(items, total as usize, &page))))
+}
+
+pub async fn get_reconciliation(
+    Path((account_id, id)): Path<(String, String)>,
+    State(pool): State<DbPool>,
+    auth_user: AuthUser,
+) -> Result<ApiResponse, AppError> {
+    let access = account_access(&pool, &account_id, &auth_user.user_id, false).await?;
+    let reconciliation = find_reconciliation(&pool, &account_id, &id).await?;
+    let time = owner_time(&pool, &access).await?;
+    summary_response(&pool, reconciliation, &time).await
+}
+
+/// Marks transactions as cleared: the statement shows them. Only the account's transactions up
+/// to the statement day can be cleared.
+pub async fn clear_reconciliation_transactions(
+    Path((account_id, id)): Path<(String, String)>,
+    State(pool): State<DbPool>,
+    auth_user: AuthUser,
+    Json(request): Json<ClearTransactionsRequest>,
+) -> Result<ApiResponse, AppError> {
+    request.validate()?;
+    let access = account_access(&pool, &account_id, &auth_user.user_id, true).await?;
+    let reconciliation = find_reconciliation(&pool, &account_id, &id).await?;
+    require_open(&reconciliation)?;
+    let time = owner_time(&pool, &access).await?;
+
+    let rejected = reconciliations::not_clearable(&pool, &reconciliation, &request.transaction_ids, &time)
+        .await
+        .map_err(internal_error)?;
+    if !rejected.is_empty() {
+        return Err(AppError::Unprocessable(format!(
+            "Not transactions of this account up to the statement date: {}",
+            rejected.join(", ")
+        )));
+    }
+
+    reconciliations::clear(&pool, &id, &request.transaction_ids).await.map_err(internal_error)?;
+
+    tracing::info!("Cleared {} transactions against reconciliation {}", request.transaction_ids.len(), id);
+    summary_response(&pool, reconciliation, &time).await
+}
+
+/// Takes back transactions cleared by mistake.
+pub async fn unclear_reconciliation_transactions(
+    Path((account_id, id)): Path<(String, String)>,
+    State(pool): State<DbPool>,
+    auth_user: AuthUser,
+    Json(request): Json<ClearTransactionsRequest>,
+) -> Result<ApiResponse, AppError> {
+    request.validate()?;
+    let access = account_access(&pool, &account_id, &auth_user.user_id, true).await?;
+    let reconciliation = find_reconciliation(&pool, &account_id, &id).await?;
+    require_open(&reconciliation)?;
+
+    reconciliations::unclear(&pool, &id, &request.transaction_ids).await.map_err(internal_error)?;
+
+    tracing::info!("Uncleared {} transactions of reconciliation {}", request.transaction_ids.len(), id);
+    let time = owner_time(&pool, &access).await?;
+    summary_response(&pool, reconciliation, &time).await
+}
+
+/// Closes a reconciliation once nothing is left unexplained.
+pub async fn complete_reconciliation(
+    Path((account_id, id)): Path<(String, String)>,
+    State(pool): State<DbPool>,
+    auth_user: AuthUser,
+) -> Result<ApiResponse, AppError> {
+    let access = account_access(&pool, &account_id, &auth_user.user_id, true).await?;
+    let reconciliation = find_reconciliation(&pool, &account_id, &id).await?;
+    require_open(&reconciliation)?;
+    let time = owner_time(&pool, &access).await?;
+
+    let summary = reconciliations::summary(&pool, reconciliation, &time).await.map_err(internal_error)?;
+    if !summary.difference.is_zero() {
+        return Err(AppError::Unprocessable(format!(
+            "The cleared balance is {} {} off the statement",
+            summary.difference, summary.currency
+        )));
+    }
+
+    // Another request may have completed it since it was read
+    let reconciliation = sqlx::query_as::<_, Reconciliation>(
+        "UPDATE reconciliations SET status = ?, completed_at = ? WHERE id = ? AND status = 'open' RETURNING *"
+    )
+    .bind(ReconciliationStatus::Completed)
+    .bind(format_db_datetime(Utc::now()))
+    .bind(&id)
+    .fetch_optional(&pool)
+    .await
+    .map_err(|e| internal_error(e.into()))?
+    .ok_or_else(|| AppError::Conflict("Reconciliation is already completed".into()))?;
+
+    tracing::info!("Completed reconciliation {} for account {}", id, account_id);
+    summary_response(&pool, reconciliation, &time).await
+}
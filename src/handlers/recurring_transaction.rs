@@ -1,68 +1,52 @@
 use axum::{
-    extract::{Path, State},
-    http::StatusCode,
-    response::Json,
+    extract::{Path, Query, State},
+    http::HeaderMap,
+    response::{Json, Response},
 };
-use serde_json::{json, Value};
+use serde_json::json;
 use chrono::Utc;
-use sqlx::Row;
 
-use crate::models::{RecurringTransaction, CreateRecurringTransactionRequest, UpdateRecurringTransactionRequest};
-use crate::services::DbPool;
+use crate::models::{RecurringTransaction, CreateRecurringTransactionRequest, UpdateRecurringTransactionRequest, RecurrenceRule, weekday_name};
+use crate::services::{concurrency, recurring, history::{self, EntityKind}, DbPool};
 use crate::middleware::auth::AuthUser;
+use crate::middleware::owned::{self, Owned};
+use crate::middleware::device::ClientDevice;
+use crate::utils::response::ApiResponse;
+use crate::utils::error::AppError;
+use crate::utils::pagination::PageQuery;
+use crate::utils::validation::{FieldErrors, Validate};
 
 pub async fn create_recurring_transaction(
     State(pool): State<DbPool>,
     auth_user: AuthUser,
+    device: ClientDevice,
     Json(request): Json<CreateRecurringTransactionRequest>,
-) -> Result<Json<Value>, StatusCode> {
-    log::info!("POST /recurring_transactions - Creating recurring transaction for user {}", auth_user.user_id);
+) -> Result<ApiResponse, AppError> {
+    request.validate()?;
 
-    let rt = RecurringTransaction::new(request, auth_user.user_id.clone());
-    let start_date_str = rt.start_date.format("%Y-%m-%d %H:%M:%S").to_string();
-    let end_date_str = rt.end_date.map(|d| d.format("%Y-%m-%d %H:%M:%S").to_string());
-    let next_due_date_str = rt.next_due_date.format("%Y-%m-%d %H:%M:%S").to_string();
-    let created_at_str = rt.created_at.format("%Y-%m-%d %H:%M:%S").to_string();
-    let updated_at_str = rt.updated_at.format("%Y-%m-%d %H:%M:%S").to_string();
-
-    let result = sqlx::query(
-        "INSERT INTO recurring_transactions (id, user_id, account_id, transaction_type, amount, currency, category, description, frequency, start_date, end_date, next_due_date, is_active, savings_goal_id, created_at, updated_at) VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?)"
-    )
-    .bind(&rt.id)
-    .bind(&rt.user_id)
-    .bind(&rt.account_id)
-    .bind(&rt.transaction_type)
-    .bind(rt.amount)
-    .bind(&rt.currency)
-    .bind(&rt.category)
-    .bind(&rt.description)
-    .bind(&rt.frequency)
-    .bind(&start_date_str)
-    .bind(&end_date_str)
-    .bind(&next_due_date_str)
-    .bind(rt.is_active)
-    .bind(&rt.savings_goal_id)
-    .bind(&created_at_str)
-    .bind(&updated_at_str)
-    .execute(&pool)
-    .await;
+    let rt = RecurringTransaction::new(request, auth_user.user_id.clone()).map_err(|e| {
+        tracing::warn!("Invalid recurrence rule: {}", e);
+        AppError::BadRequest("Invalid recurrence rule".into())
+    })?;
+    let result = match pool.acquire().await {
+        Ok(mut conn) => recurring::insert_recurring(&mut conn, &rt).await,
+        Err(e) => Err(e),
+    };
 
     match result {
         Ok(_) => {
-            log::info!("Recurring transaction created successfully: {}", rt.id);
-            Ok(Json(json!({
-                "success": true,
-                "data": rt
-            })))
+            tracing::info!("Recurring transaction created successfully: {}", rt.id);
+            history::record(&pool, EntityKind::RecurringTransaction, &rt.id, &auth_user.user_id, "created", None, &device).await;
+            Ok(ApiResponse::ok(json!(rt)))
         }
         Err(e) => {
-            log::error!("Failed to create recurring transaction: {}", e);
+            tracing::error!("Failed to create recurring transaction: {}", e);
             let error_msg = e.to_string();
             if error_msg.contains("UNIQUE constraint failed: recurring_transactions.id") {
-                log::warn!("Recurring transaction with ID {} already exists", rt.id);
-                Err(StatusCode::CONFLICT)
+                tracing::warn!("Recurring transaction with ID {} already exists", rt.id);
+                Err(AppError::Conflict("Recurring transaction already exists".into()))
             } else {
-                Err(StatusCode::INTERNAL_SERVER_ERROR)
+                Err(AppError::Internal("Failed to create recurring transaction".into()))
             }
         }
     }
@@ -71,116 +55,89 @@ pub async fn create_recurring_transaction(
 pub async fn get_recurring_transactions(
     State(pool): State<DbPool>,
     auth_user: AuthUser,
-) -> Result<Json<Value>, StatusCode> {
-    log::info!("GET /recurring_transactions - Fetching recurring transactions for user {}", auth_user.user_id);
-
-    let result = sqlx::query(
-        "SELECT id, user_id, account_id, transaction_type, amount, currency, category, description, frequency, start_date, end_date, next_due_date, is_active, savings_goal_id, created_at, updated_at FROM recurring_transactions WHERE user_id = ? ORDER BY created_at DESC"
-    )
-    .bind(&auth_user.user_id)
-    .fetch_all(&pool)
-    .await;
+    Query(page): Query<PageQuery>,
+) -> Result<ApiResponse, AppError> {
+    let result = owned::page::<RecurringTransaction>(&pool, &auth_user.user_id, "created_at DESC", &page).await;
 
     match result {
-        Ok(rows) => {
-            let transactions: Vec<_> = rows.into_iter().map(|row| {
-                json!({
-                    "id": row.get::<String, _>("id"),
-                    "userId": row.get::<String, _>("user_id"),
-                    "accountId": row.get::<String, _>("account_id"),
-                    "transactionType": row.get::<String, _>("transaction_type"),
-                    "amount": row.get::<f64, _>("amount"),
-                    "currency": row.get::<String, _>("currency"),
-                    "category": row.get::<Option<String>, _>("category"),
-                    "description": row.get::<Option<String>, _>("description"),
-                    "frequency": row.get::<String, _>("frequency"),
-                    "startDate": row.get::<String, _>("start_date"),
-                    "endDate": row.get::<Option<String>, _>("end_date"),
-                    "nextDueDate": row.get::<String, _>("next_due_date"),
-                    "isActive": row.get::<bool, _>("is_active"),
-                    "savingsGoalId": row.get::<Option<String>, _>("savings_goal_id"),
-                    "createdAt": row.get::<String, _>("created_at"),
-                    "updatedAt": row.get::<String, _>("updated_at")
-                })
-            }).collect();
-
-            log::info!("Found {} recurring transactions", transactions.len());
-            Ok(Json(json!({
-                "success": true,
-                "data": transactions
-            })))
+        Ok(transactions) => {
+            Ok(ApiResponse::ok(json!(transactions)))
         }
         Err(e) => {
-            log::error!("Failed to get recurring transactions: {}", e);
-            Err(StatusCode::INTERNAL_SERVER_ERROR)
+            tracing::error!("Failed to get recurring transactions: {}", e);
+            Err(AppError::Internal("Failed to get recurring transactions".into()))
         }
     }
 }
 
-pub async fn get_recurring_transaction(
-    Path(id): Path<String>,
-    State(pool): State<DbPool>,
-    auth_user: AuthUser,
-) -> Result<Json<Value>, StatusCode> {
-    log::info!("GET /recurring_transactions/{} - Fetching recurring transaction by ID", id);
-
-    let result = sqlx::query(
-        "SELECT id, user_id, account_id, transaction_type, amount, currency, category, description, frequency, start_date, end_date, next_due_date, is_active, savings_goal_id, created_at, updated_at FROM recurring_transactions WHERE id = ? AND user_id = ?"
-    )
-    .bind(&id)
-    .bind(&auth_user.user_id)
-    .fetch_optional(&pool)
-    .await;
-
-    match result {
-        Ok(Some(row)) => {
-            let transaction = json!({
-                "id": row.get::<String, _>("id"),
-                "userId": row.get::<String, _>("user_id"),
-                "accountId": row.get::<String, _>("account_id"),
-                "transactionType": row.get::<String, _>("transaction_type"),
-                "amount": row.get::<f64, _>("amount"),
-                "currency": row.get::<String, _>("currency"),
-                "category": row.get::<Option<String>, _>("category"),
-                "description": row.get::<Option<String>, _>("description"),
-                "frequency": row.get::<String, _>("frequency"),
-                "startDate": row.get::<String, _>("start_date"),
-                "endDate": row.get::<Option<String>, _>("end_date"),
-                "nextDueDate": row.get::<String, _>("next_due_date"),
-                "isActive": row.get::<bool, _>("is_active"),
-                "savingsGoalId": row.get::<Option<String>, _>("savings_goal_id"),
-                "createdAt": row.get::<String, _>("created_at"),
-                "updatedAt": row.get::<String, _>("updated_at")
-            });
-
-            Ok(Json(json!({
-                "success": true,
-                "data": transaction
-            })))
-        }
-        Ok(None) => Err(StatusCode::NOT_FOUND),
-        Err(e) => {
-            log::error!("Failed to get recurring transaction: {}", e);
-            Err(StatusCode::INTERNAL_SERVER_ERROR)
-        }
-    }
+pub async fn get_recurring_transaction(Owned { resource: transaction, .. }: Owned<RecurringTransaction>) -> ApiResponse {
+    ApiResponse::ok(json!(transaction))
 }
 
 pub async fn update_recurring_transaction(
     Path(id): Path<String>,
     State(pool): State<DbPool>,
     auth_user: AuthUser,
+    device: ClientDevice,
+    headers: HeaderMap,
     Json(request): Json<UpdateRecurringTransactionRequest>,
-) -> Result<Json<Value>, StatusCode> {
-    log::info!("PUT /recurring_transactions/{} - Updating recurring transaction", id);
+) -> Result<Response, AppError> {
+    request.validate()?;
+
+    let before = history::snapshot(&pool, EntityKind::RecurringTransaction, &id, &auth_user.user_id).await;
+    let version = concurrency::expected_version(&headers, request.version)?;
+
+    let existing = owned::load::<RecurringTransaction>(&pool, &id, &auth_user.user_id).await?;
+
+    // A new frequency starts a fresh rule; otherwise merge the fields that were sent.
+    let schedule_changed = request.frequency.is_some()
+        || request.interval.is_some()
+        || request.day_of_month.is_some()
+        || request.weekday.is_some()
+        || request.start_date.is_some();
+    let rule = if request.frequency.is_some() {
+        RecurrenceRule::parse(
+            request.frequency.as_deref().unwrap_or_default(),
+            request.interval,
+            request.day_of_month,
+            request.weekday.as_deref(),
+        )
+    } else {
+        RecurrenceRule::parse(
+            &existing.frequency,
+            request.interval.or(Some(existing.recurrence_interval.max(1) as u32)),
+            request.day_of_month.or(existing.day_of_month.map(|d| d as u32)),
+            request.weekday.as_deref().or(existing.weekday.as_deref()),
+        )
+    }
+    .map_err(|e| {
+        tracing::warn!("Invalid recurrence rule: {}", e);
+        AppError::BadRequest("Invalid recurrence rule".into())
+    })?;
+
+    let start_date = request.start_date.unwrap_or(existing.start_date);
+    let end_date = request.end_date.or(existing.end_date);
+    if let Some(end_date) = end_date {
+        let mut errors = FieldErrors::default();
+        errors.not_before("endDate", end_date, start_date, "startDate");
+        errors.into_result()?;
+    }
+
+    // Pending occurrences keep their place; otherwise the new schedule applies from now on.
+    let next_due_date = if schedule_changed {
+        rule.next_on_or_after(start_date, existing.next_due_date.min(Utc::now()))
+            .ok_or_else(|| AppError::BadRequest("The schedule has no upcoming occurrence".into()))?
+    } else {
+        existing.next_due_date
+    };
 
     let now = Utc::now().format("%Y-%m-%d %H:%M:%S").to_string();
-    let start_date_str = request.start_date.map(|d| d.format("%Y-%m-%d %H:%M:%S").to_string());
+    let start_date_str = start_date.format("%Y-%m-%d %H:%M:%S").to_string();
     let end_date_str = request.end_date.map(|d| d.format("%Y-%m-%d %H:%M:%S").to_string());
-    let next_due_date_str = request.next_due_date.map(|d| d.format("%Y-%m-%d %H:%M:%S").to_string());
+    let next_due_date_str = next_due_date.format("%Y-%m-%d %H:%M:%S").to_string();
 
     let result = sqlx::query(
-        "UPDATE recurring_transactions SET account_id = COALESCE(?, account_id), transaction_type = COALESCE(?, transaction_type), amount = COALESCE(?, amount), currency = COALESCE(?, currency), category = COALESCE(?, category), description = COALESCE(?, description), frequency = COALESCE(?, frequency), start_date = COALESCE(?, start_date), end_date = COALESCE(?, end_date), next_due_date = COALESCE(?, next_due_date), is_active = COALESCE(?, is_active), savings_goal_id = COALESCE(?, savings_goal_id), updated_at = ? WHERE id = ? AND user_id = ?"
+        "UPDATE recurring_transactions SET account_id = COALESCE(?, account_id), transaction_type = COALESCE(?, transaction_type), amount = COALESCE(?, amount), currency = COALESCE(?, currency), category = COALESCE(?, category), description = COALESCE(?, description), frequency = ?, recurrence_interval = ?, day_of_month = ?, weekday = ?, start_date = ?, end_date = COALESCE(?, end_date), next_due_date = ?, is_active = COALESCE(?, is_active), savings_goal_id = COALESCE(?, savings_goal_id), updated_at = ? WHERE id = ? AND user_id = ? AND version = ?"
     )
     .bind(request.account_id)
     .bind(request.transaction_type)
@@ -188,7 +145,10 @@ pub async fn update_recurring_transaction(
     .bind(request.currency)
     .bind(request.category)
     .bind(request.description)
-    .bind(request.frequency)
+    .bind(rule.frequency.as_str())
+    .bind(rule.interval as i64)
+    .bind(rule.day_of_month.map(i64::from))
+    .bind(rule.weekday.map(weekday_name))
     .bind(start_date_str)
     .bind(end_date_str)
     .bind(next_due_date_str)
@@ -197,24 +157,23 @@ pub async fn update_recurring_transaction(
     .bind(&now)
     .bind(&id)
     .bind(&auth_user.user_id)
+    .bind(version)
     .execute(&pool)
     .await;
 
     match result {
         Ok(result) => {
             if result.rows_affected() == 0 {
-                Err(StatusCode::NOT_FOUND)
+                concurrency::rejected_update(&pool, EntityKind::RecurringTransaction, &id, &auth_user.user_id, version).await
             } else {
-                log::info!("Recurring transaction updated successfully: {}", id);
-                Ok(Json(json!({
-                    "success": true,
-                    "message": "Recurring transaction updated successfully"
-                })))
+                tracing::info!("Recurring transaction updated successfully: {}", id);
+                history::record(&pool, EntityKind::RecurringTransaction, &id, &auth_user.user_id, "updated", before, &device).await;
+                Ok(concurrency::updated(&pool, EntityKind::RecurringTransaction, &id, "Recurring transaction updated successfully").await)
             }
         }
         Err(e) => {
-            log::error!("Failed to update recurring transaction: {}", e);
-            Err(StatusCode::INTERNAL_SERVER_ERROR)
+            tracing::error!("Failed to update recurring transaction: {}", e);
+            Err(AppError::Internal("Failed to update recurring transaction".into()))
         }
     }
 }
@@ -223,8 +182,9 @@ pub async fn delete_recurring_transaction(
     Path(id): Path<String>,
     State(pool): State<DbPool>,
     auth_user: AuthUser,
-) -> Result<Json<Value>, StatusCode> {
-    log::info!("DELETE /recurring_transactions/{} - Deleting recurring transaction", id);
+    device: ClientDevice,
+) -> Result<ApiResponse, AppError> {
+    let before = history::snapshot(&pool, EntityKind::RecurringTransaction, &id, &auth_user.user_id).await;
 
     let result = sqlx::query("DELETE FROM recurring_transactions WHERE id = ? AND user_id = ?")
         .bind(&id)
@@ -235,18 +195,121 @@ pub async fn delete_recurring_transaction(
     match result {
         Ok(result) => {
             if result.rows_affected() == 0 {
-                Err(StatusCode::NOT_FOUND)
+                Err(AppError::NotFound("Recurring transaction not found".into()))
             } else {
-                log::info!("Recurring transaction deleted successfully: {}", id);
-                Ok(Json(json!({
-                    "success": true,
-                    "message": "Recurring transaction deleted successfully"
-                })))
+                tracing::info!("Recurring transaction deleted successfully: {}", id);
+                history::record(&pool, EntityKind::RecurringTransaction, &id, &auth_user.user_id, "deleted", before, &device).await;
+                Ok(ApiResponse::message("Recurring transaction deleted successfully"))
             }
         }
         Err(e) => {
-            log::error!("Failed to delete recurring transaction: {}", e);
-            Err(StatusCode::INTERNAL_SERVER_ERROR)
+            tracing::error!("Failed to delete recurring transaction: {}", e);
+            Err(AppError::Internal("Failed to delete recurring transaction".into()))
+        }
+    }
+}
+
+pub async fn post_recurring_transaction(
+    Path(id): Path<String>,
+    State(pool): State<DbPool>,
+    auth_user: AuthUser,
+) -> Result<ApiResponse, AppError> {
+    let rt = owned::load::<RecurringTransaction>(&pool, &id, &auth_user.user_id).await?;
+
+    match recurring::post_occurrence(&pool, &rt, Utc::now()).await {
+        Ok(posted) => {
+            tracing::info!("Posted occurrence {} for recurring transaction {}", posted.transaction.id, id);
+            Ok(ApiResponse::ok(json!(posted)))
+        }
+        Err(e) => {
+            tracing::error!("Failed to post recurring transaction {}: {}", id, e);
+            Err(AppError::Internal("Failed to post recurring transaction".into()))
         }
     }
 }
+
+async fn save_schedule_state(pool: &DbPool, rt: &RecurringTransaction) -> Result<(), AppError> {
+    sqlx::query("UPDATE recurring_transactions SET next_due_date = ?, is_active = ?, updated_at = ? WHERE id = ? AND user_id = ?")
+        .bind(rt.next_due_date.format("%Y-%m-%d %H:%M:%S").to_string())
+        .bind(rt.is_active)
+        .bind(rt.updated_at.format("%Y-%m-%d %H:%M:%S").to_string())
+        .bind(&rt.id)
+        .bind(&rt.user_id)
+        .execute(pool)
+        .await
+        .map_err(|e| {
+            tracing::error!("Failed to update recurring transaction {}: {}", rt.id, e);
+            AppError::Internal("Failed to update recurring transaction".into())
+        })?;
+    Ok(())
+}
+
+pub async fn pause_recurring_transaction(
+    Path(id): Path<String>,
+    State(pool): State<DbPool>,
+    auth_user: AuthUser,
+) -> Result<ApiResponse, AppError> {
+    let mut rt = owned::load::<RecurringTransaction>(&pool, &id, &auth_user.user_id).await?;
+    rt.is_active = false;
+    rt.updated_at = Utc::now();
+    save_schedule_state(&pool, &rt).await?;
+
+    Ok(ApiResponse::ok(json!(rt)))
+}
+
+/// Reactivates a paused schedule. Occurrences that fell due while paused are not posted.
+pub async fn resume_recurring_transaction(
+    Path(id): Path<String>,
+    State(pool): State<DbPool>,
+    auth_user: AuthUser,
+) -> Result<ApiResponse, AppError> {
+    let mut rt = owned::load::<RecurringTransaction>(&pool, &id, &auth_user.user_id).await?;
+    let now = Utc::now();
+    if rt.next_due_date < now {
+        let rule = rt.rule().map_err(|e| {
+            tracing::warn!("Recurring transaction {} has an invalid schedule: {}", id, e);
+            AppError::Unprocessable("Recurring transaction has an invalid schedule".into())
+        })?;
+        rt.next_due_date = rule
+            .next_on_or_after(rt.start_date, now)
+            .ok_or_else(|| AppError::Unprocessable("The schedule has no upcoming occurrence".into()))?;
+    }
+    if rt.end_date.is_some_and(|end| rt.next_due_date > end) {
+        tracing::warn!("Recurring transaction {} has already ended; cannot resume", id);
+        return Err(AppError::Conflict("Recurring transaction has already ended; cannot resume".into()));
+    }
+
+    rt.is_active = true;
+    rt.updated_at = now;
+    save_schedule_state(&pool, &rt).await?;
+
+    Ok(ApiResponse::ok(json!(rt)))
+}
+
+/// Moves past the next occurrence without posting it.
+pub async fn skip_recurring_transaction(
+    Path(id): Path<String>,
+    State(pool): State<DbPool>,
+    auth_user: AuthUser,
+) -> Result<ApiResponse, AppError> {
+    let mut rt = owned::load::<RecurringTransaction>(&pool, &id, &auth_user.user_id).await?;
+    let rule = rt.rule().map_err(|e| {
+        tracing::warn!("Recurring transaction {} has an invalid schedule: {}", id, e);
+        AppError::Unprocessable("Recurring transaction has an invalid schedule".into())
+    })?;
+    let skipped = rt.next_due_date;
+    rt.next_due_date = rule
+        .next_after(rt.start_date, skipped)
+        .ok_or_else(|| AppError::Unprocessable("The schedule has no occurrence after this one".into()))?;
+    if rt.end_date.is_some_and(|end| rt.next_due_date > end) {
+        rt.is_active = false;
+    }
+    rt.updated_at = Utc::now();
+    save_schedule_state(&pool, &rt).await?;
+
+    tracing::info!("Skipped occurrence {} of recurring transaction {}", skipped, id);
+    Ok(ApiResponse::ok(json!({
+        "skippedDate": skipped,
+        "recurringTransaction": rt
+    })))
+}
@@ -0,0 +1,234 @@
+use axum::{
+    extract::{Path, Query, State},
+    response::Json,
+};
+use chrono::Utc;
+use serde_json::{json, Value};
+use uuid::Uuid;
+
+use crate::models::{BillReminder, BillReminderQuery, CreateBillReminderRequest, ReminderTarget, UpdateBillReminderRequest};
+use crate::services::{households, reminders, DbPool};
+use crate::middleware::auth::AuthUser;
+use crate::middleware::owned::{self, Owned};
+use crate::utils::datetime::format_db_datetime;
+use crate::utils::response::ApiResponse;
+use crate::utils::error::AppError;
+use crate::utils::pagination::{PageQuery, Paginated};
+use crate::utils::validation::Validate;
+
+/// The bill must be the user's own liability or recurring transaction, or a credit card they
+/// can see, including one shared through a household.
+async fn check_target(pool: &DbPool, target_type: ReminderTarget, target_id: &str, user_id: &str) -> Result<(), AppError> {
+    let lookup = match target_type {
+        ReminderTarget::Liability => "SELECT COUNT(*) FROM liabilities WHERE id = ? AND user_id = ?",
+        ReminderTarget::RecurringTransaction => "SELECT COUNT(*) FROM recurring_transactions WHERE id = ? AND user_id = ?",
+        ReminderTarget::CreditCard => return check_card(pool, target_id, user_id).await,
+    };
+    let count = sqlx::query_scalar::<_, i64>(lookup)
+        .bind(target_id)
+        .bind(user_id)
+        .fetch_one(pool)
+        .await
+        .map_err(|e| {
+            tracing::error!("Failed to look up {} {}: {}", target_type.as_str(), target_id, e);
+            AppError::Internal("Failed to look up".into())
+        })?;
+    if count == 0 {
+        tracing::warn!("No {} {} for user {}", target_type.as_str(), target_id, user_id);
+        return Err(AppError::NotFound("Reminder target not found".into()));
+    }
+    Ok(())
+}
+
+async fn check_card(pool: &DbPool, account_id: &str, user_id: &str) -> Result<(), AppError> {
+    let access = households::account_access(pool, account_id, user_id)
+        .await
+        .map_err(|e| {
+            tracing::error!("Failed to look up account {}: {}", account_id, e);
+            AppError::Internal("Failed to look up account".into())
+        })?;
+    if access.is_none() {
+        return Err(AppError::NotFound("Account not found".into()));
+    }
+
+    let is_card = sqlx::query_scalar::<_, i64>("SELECT COUNT(*) FROM accounts WHERE id = ? AND account_type = 'credit_card'")
+        .bind(account_id)
+        .fetch_one(pool)
+        .await
+        .map_err(|e| {
+            tracing::error!("Failed to look up account {}: {}", account_id, e);
+            AppError::Internal("Failed to look up account".into())
+        })?;
+    if is_card == 0 {
+        tracing::warn!("Account {} is not a credit card", account_id);
+        return Err(AppError::BadRequest("Account is not a credit card".into()));
+    }
+    Ok(())
+}
+
+async fn with_schedule(pool: &DbPool, reminder: BillReminder) -> Result<Value, AppError> {
+    let id = reminder.id.clone();
+    let schedule = reminders::schedule(pool, reminder).await.map_err(|e| {
+        tracing::error!("Failed to work out the schedule of bill reminder {}: {}", id, e);
+        AppError::Internal("Failed to work out the schedule of bill reminder".into())
+    })?;
+    Ok(json!(schedule))
+}
+
+/// Adds a reminder sent `daysBefore` days ahead of a liability, credit card payment or
+/// recurring transaction falling due. A bill with its own reminders no longer gets the
+/// default one.
+pub async fn create_bill_reminder(
+    State(pool): State<DbPool>,
+    auth_user: AuthUser,
+    Json(request): Json<CreateBillReminderRequest>,
+) -> Result<ApiResponse, AppError> {
+    request.validate()?;
+    check_target(&pool, request.target_type, &request.target_id, &auth_user.user_id).await?;
+
+    let now = Utc::now();
+    let reminder = BillReminder {
+        id: Uuid::new_v4().to_string(),
+        user_id: auth_user.user_id.clone(),
+        target_type: request.target_type,
+        target_id: request.target_id,
+        days_before: request.days_before,
+        send_email: request.send_email.unwrap_or(true),
+        is_active: true,
+        created_at: now,
+        updated_at: now,
+    };
+
+    let result = sqlx::query(
+        "INSERT INTO bill_reminders (id, user_id, target_type, target_id, days_before, send_email, is_active, created_at, updated_at) VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?)"
+    )
+    .bind(&reminder.id)
+    .bind(&reminder.user_id)
+    .bind(reminder.target_type)
+    .bind(&reminder.target_id)
+    .bind(reminder.days_before)
+    .bind(reminder.send_email)
+    .bind(reminder.is_active)
+    .bind(format_db_datetime(reminder.created_at))
+    .bind(format_db_datetime(reminder.updated_at))
+    .execute(&pool)
+    .await;
+
+    if let Err(e) = result {
+        if e.to_string().contains("UNIQUE constraint failed") {
+            tracing::warn!("A {}-day reminder already exists for {} {}", reminder.days_before, reminder.target_type.as_str(), reminder.target_id);
+            return Err(AppError::Conflict("A reminder for that many days before already exists".into()));
+        }
+        tracing::error!("Failed to create bill reminder: {}", e);
+        return Err(AppError::Internal("Failed to create bill reminder".into()));
+    }
+
+    tracing::info!("Bill reminder created: {} ({} days before {} {})", reminder.id, reminder.days_before, reminder.target_type.as_str(), reminder.target_id);
+    Ok(ApiResponse::ok(json!(with_schedule(&pool, reminder).await?)))
+}
+
+pub async fn get_bill_reminders(
+    State(pool): State<DbPool>,
+    auth_user: AuthUser,
+    Query(query): Query<BillReminderQuery>,
+    Query(page): Query<PageQuery>,
+) -> Result<ApiResponse, AppError> {
+    let reminders = sqlx::query_as::<_, BillReminder>(
+        "SELECT * FROM bill_reminders WHERE user_id = ?1 AND (?2 IS NULL OR target_type = ?2) AND (?3 IS NULL OR target_id = ?3) ORDER BY target_type, target_id, days_before DESC, id LIMIT ?4 OFFSET ?5"
+    )
+    .bind(&auth_user.user_id)
+    .bind(query.target_type)
+    .bind(&query.target_id)
+    .bind(page.limit() as i64)
+    .bind(page.offset() as i64)
+    .fetch_all(&pool)
+    .await
+    .map_err(|e| {
+        tracing::error!("Failed to get bill reminders: {}", e);
+        AppError::Internal("Failed to get bill reminders".into())
+    })?;
+    let total = sqlx::query_scalar::<_, i64>(
+        "SELECT COUNT(*) FROM bill_reminders WHERE user_id = ?1 AND (?2 IS NULL OR target_type = ?2) AND (?3 IS NULL OR target_id = ?3)"
+    )
+    .bind(&auth_user.user_id)
+    .bind(query.target_type)
+    .bind(&query.target_id)
+    .fetch_one(&pool)
+    .await
+    .map_err(|e| {
+        tracing::error!("Failed to count bill reminders: {}", e);
+        AppError::Internal("Failed to get bill reminders".into())
+    })?;
+
+    let mut data = Vec::with_capacity(reminders.len());
+    for reminder in reminders {
+        data.push(with_schedule(&pool, reminder).await?);
+    }
+    Ok(ApiResponse::ok(json!(Paginated::new(data, total as usize, &page))))
+}
+
+pub async fn get_bill_reminder(
+    State(pool): State<DbPool>,
+    Owned { resource: reminder, .. }: Owned<BillReminder>,
+) -> Result<ApiResponse, AppError> {
+    Ok(ApiResponse::ok(json!(with_schedule(&pool, reminder).await?)))
+}
+
+/// Changes a reminder's lead time or email setting, or pauses it.
+pub async fn update_bill_reminder(
+    Path(id): Path<String>,
+    State(pool): State<DbPool>,
+    auth_user: AuthUser,
+    Json(request): Json<UpdateBillReminderRequest>,
+) -> Result<ApiResponse, AppError> {
+    request.validate()?;
+    let mut reminder = owned::load::<BillReminder>(&pool, &id, &auth_user.user_id).await?;
+    reminder.days_before = request.days_before.unwrap_or(reminder.days_before);
+    reminder.send_email = request.send_email.unwrap_or(reminder.send_email);
+    reminder.is_active = request.is_active.unwrap_or(reminder.is_active);
+    reminder.updated_at = Utc::now();
+
+    let result = sqlx::query("UPDATE bill_reminders SET days_before = ?, send_email = ?, is_active = ?, updated_at = ? WHERE id = ?")
+        .bind(reminder.days_before)
+        .bind(reminder.send_email)
+        .bind(reminder.is_active)
+        .bind(format_db_datetime(reminder.updated_at))
+        .bind(&id)
+        .execute(&pool)
+        .await;
+
+    if let Err(e) = result {
+        if e.to_string().contains("UNIQUE constraint failed") {
+            tracing::warn!("A {}-day reminder already exists for {} {}", reminder.days_before, reminder.target_type.as_str(), reminder.target_id);
+            return Err(AppError::Conflict("A reminder for that many days before already exists".into()));
+        }
+        tracing::error!("Failed to update bill reminder {}: {}", id, e);
+        return Err(AppError::Internal("Failed to update bill reminder".into()));
+    }
+
+    tracing::info!("Bill reminder updated successfully: {}", id);
+    Ok(ApiResponse::ok(json!(with_schedule(&pool, reminder).await?)))
+}
+
+pub async fn delete_bill_reminder(
+    Path(id): Path<String>,
+    State(pool): State<DbPool>,
+    auth_user: AuthUser,
+) -> Result<ApiResponse, AppError> {
+    let result = sqlx::query("DELETE FROM bill_reminders WHERE id = ? AND user_id = ?")
+        .bind(&id)
+        .bind(&auth_user.user_id)
+        .execute(&pool)
+        .await
+        .map_err(|e| {
+            tracing::error!("Failed to delete bill reminder {}: {}", id, e);
+            AppError::Internal("Failed to delete bill reminder".into())
+        })?;
+
+    if result.rows_affected() == 0 {
+        return Err(AppError::NotFound("Bill reminder not found".into()));
+    }
+
+    tracing::info!("Bill reminder deleted successfully: {}", id);
+    Ok(ApiResponse::message("Reminder deleted successfully"))
+}
@@ -0,0 +1,103 @@
+use axum::extract::{Query, State};
+use chrono::Datelike;
+use serde_json::json;
+
+use crate::models::{CashflowQuery, ForecastQuery, Granularity, MonthlyReportQuery};
+use crate::services::user_time::{self, UserTime};
+use crate::services::{reports, DbPool};
+use crate::middleware::auth::AuthUser;
+use crate::utils::response::ApiResponse;
+use crate::utils::error::AppError;
+
+/// The user's calendar, which reports bucket by.
+async fn user_time(pool: &DbPool, user_id: &str) -> Result<UserTime, AppError> {
+    user_time::for_user(pool, user_id).await.map_err(|e| {
+        tracing::error!("Failed to load the timezone of user {}: {}", user_id, e);
+        AppError::Internal("Failed to build report".into())
+    })
+}
+
+pub async fn get_monthly_report(
+    State(pool): State<DbPool>,
+    auth_user: AuthUser,
+    Query(query): Query<MonthlyReportQuery>,
+) -> Result<ApiResponse, AppError> {
+    let time = user_time(&pool, &auth_user.user_id).await?;
+    let today = time.today();
+    let (year, month) = (query.year.unwrap_or(today.year()), query.month.unwrap_or(today.month()));
+    let first_day = reports::month_start(year, month).ok_or_else(|| {
+        tracing::warn!("Invalid report month {}-{}", year, month);
+        AppError::BadRequest("Invalid report month".into())
+    })?;
+
+    let summary = reports::monthly_summary(&pool, &auth_user.user_id, first_day, &time)
+        .await
+        .map_err(|e| {
+            tracing::error!("Failed to build monthly summary: {}", e);
+            AppError::Internal("Failed to build monthly summary".into())
+        })?;
+
+    Ok(ApiResponse::ok(json!(summary)))
+}
+
+pub async fn get_cashflow_report(
+    State(pool): State<DbPool>,
+    auth_user: AuthUser,
+    Query(query): Query<CashflowQuery>,
+) -> Result<ApiResponse, AppError> {
+    let time = user_time(&pool, &auth_user.user_id).await?;
+    let to = query.to.unwrap_or_else(|| time.today());
+    let from = query.from.unwrap_or_else(|| reports::default_cashflow_start(query.granularity, to, &time));
+    let starts = reports::bucket_starts(query.granularity, from, to, &time);
+    if from > to || starts.len() > reports::MAX_CASHFLOW_BUCKETS {
+        tracing::warn!("Invalid cash flow range {} to {} ({} buckets)", from, to, starts.len());
+        return Err(AppError::BadRequest("Invalid cash flow range".into()));
+    }
+
+    let series = reports::cashflow(&pool, &auth_user.user_id, &starts, to, &time)
+        .await
+        .map_err(|e| {
+            tracing::error!("Failed to build cash flow report: {}", e);
+            AppError::Internal("Failed to build cash flow report".into())
+        })?;
+
+    Ok(ApiResponse::ok(json!({
+        "granularity": query.granularity,
+        "from": starts[0],
+        "to": to,
+        "series": series
+    })))
+}
+
+pub async fn get_forecast_report(
+    State(pool): State<DbPool>,
+    auth_user: AuthUser,
+    Query(query): Query<ForecastQuery>,
+) -> Result<ApiResponse, AppError> {
+    let months = query.months.unwrap_or(reports::MAX_FORECAST_MONTHS);
+    if !(1..=reports::MAX_FORECAST_MONTHS).contains(&months) {
+        tracing::warn!("Invalid forecast horizon: {} months", months);
+        return Err(AppError::BadRequest("Invalid forecast horizon".into()));
+    }
+
+    let time = user_time(&pool, &auth_user.user_id).await?;
+    let this_month = reports::bucket_start(Granularity::Month, time.today(), &time);
+    let (categories, totals) = reports::forecast(&pool, &auth_user.user_id, this_month, months, &time)
+        .await
+        .map_err(|e| {
+            tracing::error!("Failed to build forecast: {}", e);
+            AppError::Internal("Failed to build forecast".into())
+        })?;
+
+    let trending_up: Vec<&str> = categories
+        .iter()
+        .filter(|f| f.trending_up)
+        .map(|f| f.category.as_str())
+        .collect();
+
+    Ok(ApiResponse::ok(json!({
+        "categories": categories,
+        "totals": totals,
+        "trendingUp": trending_up
+    })))
+}
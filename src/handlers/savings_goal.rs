@@ -1,22 +1,32 @@
 use axum::{
-    extract::{Path, State},
-    http::StatusCode,
-    response::Json,
+    extract::{Path, Query, State},
+    http::HeaderMap,
+    response::{Json, Response},
 };
-use serde_json::{json, Value};
+use serde_json::json;
 use chrono::Utc;
-use sqlx::Row;
 
-use crate::models::{SavingsGoal, CreateSavingsGoalRequest, UpdateSavingsGoalRequest};
-use crate::services::DbPool;
+use crate::models::{
+    SavingsGoal, CreateSavingsGoalRequest, UpdateSavingsGoalRequest, SavingsGoalContribution, CreateContributionRequest,
+    Transaction, CreateTransactionRequest, TransactionType,
+};
+use crate::services::goal_contributions::{self, ContributionOutcome};
+use crate::services::{concurrency, events, history::{self, EntityKind}, DbPool};
 use crate::middleware::auth::AuthUser;
+use crate::middleware::owned::{self, Owned};
+use crate::middleware::device::ClientDevice;
+use crate::utils::response::ApiResponse;
+use crate::utils::error::AppError;
+use crate::utils::pagination::{PageQuery, Paginated};
+use crate::utils::validation::Validate;
 
 pub async fn create_savings_goal(
     State(pool): State<DbPool>,
     auth_user: AuthUser,
+    device: ClientDevice,
     Json(request): Json<CreateSavingsGoalRequest>,
-) -> Result<Json<Value>, StatusCode> {
-    log::info!("POST /savings-goals - Creating savings goal for user {}", auth_user.user_id);
+) -> Result<ApiResponse, AppError> {
+    request.validate()?;
 
     let goal = SavingsGoal::new(request, auth_user.user_id.clone());
     let target_date_str = goal.target_date.format("%Y-%m-%d %H:%M:%S").to_string();
@@ -44,20 +54,18 @@ pub async fn create_savings_goal(
 
     match result {
         Ok(_) => {
-            log::info!("Savings goal created successfully: {} ({})", goal.name, goal.id);
-            Ok(Json(json!({
-                "success": true,
-                "data": goal
-            })))
+            tracing::info!("Savings goal created successfully: {} ({})", goal.name, goal.id);
+            history::record(&pool, EntityKind::SavingsGoal, &goal.id, &auth_user.user_id, "created", None, &device).await;
+            Ok(ApiResponse::ok(json!(goal)))
         }
         Err(e) => {
-            log::error!("Failed to create savings goal: {}", e);
+            tracing::error!("Failed to create savings goal: {}", e);
             let error_msg = e.to_string();
             if error_msg.contains("UNIQUE constraint failed: savings_goals.id") {
-                log::warn!("Savings goal with ID {} already exists", goal.id);
-                Err(StatusCode::CONFLICT)
+                tracing::warn!("Savings goal with ID {} already exists", goal.id);
+                Err(AppError::Conflict("Savings goal already exists".into()))
             } else {
-                Err(StatusCode::INTERNAL_SERVER_ERROR)
+                Err(AppError::Internal("Failed to create savings goal".into()))
             }
         }
     }
@@ -66,108 +74,43 @@ pub async fn create_savings_goal(
 pub async fn get_savings_goals(
     State(pool): State<DbPool>,
     auth_user: AuthUser,
-) -> Result<Json<Value>, StatusCode> {
-    log::info!("GET /savings-goals - Fetching savings goals for user {}", auth_user.user_id);
-
-    let result = sqlx::query(
-        "SELECT id, user_id, name, target_amount, current_amount, currency, target_date, description, account_id, priority, is_completed, created_at, updated_at FROM savings_goals WHERE user_id = ? ORDER BY target_date ASC"
-    )
-    .bind(&auth_user.user_id)
-    .fetch_all(&pool)
-    .await;
+    Query(page): Query<PageQuery>,
+) -> Result<ApiResponse, AppError> {
+    let result = owned::page::<SavingsGoal>(&pool, &auth_user.user_id, "target_date ASC", &page).await;
 
     match result {
-        Ok(rows) => {
-            let goals: Vec<_> = rows.into_iter().map(|row| {
-                json!({
-                    "id": row.get::<String, _>("id"),
-                    "user_id": row.get::<String, _>("user_id"),
-                    "name": row.get::<String, _>("name"),
-                    "target_amount": row.get::<f64, _>("target_amount"),
-                    "current_amount": row.get::<f64, _>("current_amount"),
-                    "currency": row.get::<String, _>("currency"),
-                    "target_date": row.get::<String, _>("target_date"),
-                    "description": row.get::<Option<String>, _>("description"),
-                    "account_id": row.get::<Option<String>, _>("account_id"),
-                    "priority": row.get::<String, _>("priority"),
-                    "is_completed": row.get::<bool, _>("is_completed"),
-                    "created_at": row.get::<String, _>("created_at"),
-                    "updated_at": row.get::<String, _>("updated_at")
-                })
-            }).collect();
-
-            log::info!("Found {} savings goals", goals.len());
-            Ok(Json(json!({
-                "success": true,
-                "data": goals
-            })))
+        Ok(goals) => {
+            Ok(ApiResponse::ok(json!(goals)))
         }
         Err(e) => {
-            log::error!("Failed to get savings goals: {}", e);
-            Err(StatusCode::INTERNAL_SERVER_ERROR)
+            tracing::error!("Failed to get savings goals: {}", e);
+            Err(AppError::Internal("Failed to get savings goals".into()))
         }
     }
 }
 
-pub async fn get_savings_goal(
-    Path(id): Path<String>,
-    State(pool): State<DbPool>,
-    auth_user: AuthUser,
-) -> Result<Json<Value>, StatusCode> {
-    log::info!("GET /savings-goals/{} - Fetching savings goal by ID", id);
-
-    let result = sqlx::query(
-        "SELECT id, user_id, name, target_amount, current_amount, currency, target_date, description, account_id, priority, is_completed, created_at, updated_at FROM savings_goals WHERE id = ? AND user_id = ?"
-    )
-    .bind(&id)
-    .bind(&auth_user.user_id)
-    .fetch_optional(&pool)
-    .await;
-
-    match result {
-        Ok(Some(row)) => {
-            let goal = json!({
-                "id": row.get::<String, _>("id"),
-                "user_id": row.get::<String, _>("user_id"),
-                "name": row.get::<String, _>("name"),
-                "target_amount": row.get::<f64, _>("target_amount"),
-                "current_amount": row.get::<f64, _>("current_amount"),
-                "currency": row.get::<String, _>("currency"),
-                "target_date": row.get::<String, _>("target_date"),
-                "description": row.get::<Option<String>, _>("description"),
-                "account_id": row.get::<Option<String>, _>("account_id"),
-                "priority": row.get::<String, _>("priority"),
-                "is_completed": row.get::<bool, _>("is_completed"),
-                "created_at": row.get::<String, _>("created_at"),
-                "updated_at": row.get::<String, _>("updated_at")
-            });
-
-            Ok(Json(json!({
-                "success": true,
-                "data": goal
-            })))
-        }
-        Ok(None) => Err(StatusCode::NOT_FOUND),
-        Err(e) => {
-            log::error!("Failed to get savings goal: {}", e);
-            Err(StatusCode::INTERNAL_SERVER_ERROR)
-        }
-    }
+pub async fn get_savings_goal(Owned { resource: goal, .. }: Owned<SavingsGoal>) -> ApiResponse {
+    ApiResponse::ok(json!(goal))
 }
 
 pub async fn update_savings_goal(
     Path(id): Path<String>,
     State(pool): State<DbPool>,
     auth_user: AuthUser,
+    device: ClientDevice,
+    headers: HeaderMap,
     Json(request): Json<UpdateSavingsGoalRequest>,
-) -> Result<Json<Value>, StatusCode> {
-    log::info!("PUT /savings-goals/{} - Updating savings goal", id);
+) -> Result<Response, AppError> {
+    request.validate()?;
+
+    let before = history::snapshot(&pool, EntityKind::SavingsGoal, &id, &auth_user.user_id).await;
+    let version = concurrency::expected_version(&headers, request.version)?;
 
     let now = Utc::now().format("%Y-%m-%d %H:%M:%S").to_string();
     let target_date_str = request.target_date.map(|d| d.format("%Y-%m-%d %H:%M:%S").to_string());
 
     let result = sqlx::query(
-        "UPDATE savings_goals SET name = COALESCE(?, name), target_amount = COALESCE(?, target_amount), current_amount = COALESCE(?, current_amount), currency = COALESCE(?, currency), target_date = COALESCE(?, target_date), description = COALESCE(?, description), account_id = COALESCE(?, account_id), priority = COALESCE(?, priority), is_completed = COALESCE(?, is_completed), updated_at = ? WHERE id = ? AND user_id = ?"
+        "UPDATE savings_goals SET name = COALESCE(?, name), target_amount = COALESCE(?, target_amount), current_amount = COALESCE(?, current_amount), currency = COALESCE(?, currency), target_date = COALESCE(?, target_date), description = COALESCE(?, description), account_id = COALESCE(?, account_id), priority = COALESCE(?, priority), is_completed = COALESCE(?, is_completed), updated_at = ? WHERE id = ? AND user_id = ? AND version = ?"
     )
     .bind(request.name)
     .bind(request.target_amount)
@@ -181,24 +124,23 @@ pub async fn update_savings_goal(
     .bind(&now)
     .bind(&id)
     .bind(&auth_user.user_id)
+    .bind(version)
     .execute(&pool)
     .await;
 
     match result {
         Ok(result) => {
             if result.rows_affected() == 0 {
-                Err(StatusCode::NOT_FOUND)
+                concurrency::rejected_update(&pool, EntityKind::SavingsGoal, &id, &auth_user.user_id, version).await
             } else {
-                log::info!("Savings goal updated successfully: {}", id);
-                Ok(Json(json!({
-                    "success": true,
-                    "message": "Savings goal updated successfully"
-                })))
+                tracing::info!("Savings goal updated successfully: {}", id);
+                history::record(&pool, EntityKind::SavingsGoal, &id, &auth_user.user_id, "updated", before, &device).await;
+                Ok(concurrency::updated(&pool, EntityKind::SavingsGoal, &id, "Savings goal updated successfully").await)
             }
         }
         Err(e) => {
-            log::error!("Failed to update savings goal: {}", e);
-            Err(StatusCode::INTERNAL_SERVER_ERROR)
+            tracing::error!("Failed to update savings goal: {}", e);
+            Err(AppError::Internal("Failed to update savings goal".into()))
         }
     }
 }
@@ -207,8 +149,9 @@ pub async fn delete_savings_goal(
     Path(id): Path<String>,
     State(pool): State<DbPool>,
     auth_user: AuthUser,
-) -> Result<Json<Value>, StatusCode> {
-    log::info!("DELETE /savings-goals/{} - Deleting savings goal", id);
+    device: ClientDevice,
+) -> Result<ApiResponse, AppError> {
+    let before = history::snapshot(&pool, EntityKind::SavingsGoal, &id, &auth_user.user_id).await;
 
     let result = sqlx::query("DELETE FROM savings_goals WHERE id = ? AND user_id = ?")
         .bind(&id)
@@ -219,18 +162,171 @@ pub async fn delete_savings_goal(
     match result {
         Ok(result) => {
             if result.rows_affected() == 0 {
-                Err(StatusCode::NOT_FOUND)
+                Err(AppError::NotFound("Savings goal not found".into()))
             } else {
-                log::info!("Savings goal deleted successfully: {}", id);
-                Ok(Json(json!({
-                    "success": true,
-                    "message": "Savings goal deleted successfully"
-                })))
+                tracing::info!("Savings goal deleted successfully: {}", id);
+                history::record(&pool, EntityKind::SavingsGoal, &id, &auth_user.user_id, "deleted", before, &device).await;
+                Ok(ApiResponse::message("Savings goal deleted successfully"))
+            }
+        }
+        Err(e) => {
+            tracing::error!("Failed to delete savings goal: {}", e);
+            Err(AppError::Internal("Failed to delete savings goal".into()))
+        }
+    }
+}
+
+pub async fn create_contribution(
+    Path(id): Path<String>,
+    State(pool): State<DbPool>,
+    auth_user: AuthUser,
+    Json(request): Json<CreateContributionRequest>,
+) -> Result<ApiResponse, AppError> {
+    request.validate()?;
+
+    let goal = owned::load::<SavingsGoal>(&pool, &id, &auth_user.user_id).await?;
+
+    let date = request.date.unwrap_or_else(Utc::now);
+    let transaction = if request.create_transaction.unwrap_or(false) {
+        let account_id = request
+            .account_id
+            .clone()
+            .or_else(|| goal.account_id.clone())
+            .ok_or_else(|| AppError::BadRequest("A contribution needs an account".into()))?;
+        Some(Transaction::new(
+            CreateTransactionRequest {
+                id: None,
+                account_id,
+                transaction_type: TransactionType::Transfer,
+                amount: request.amount.abs(),
+                currency: Some(goal.currency.clone()),
+                category: Some("Savings".to_string()),
+                description: Some(format!("Contribution to {}", goal.name)),
+                date: Some(date),
+                created_at: None,
+                exchange_rate: None,
+                base_amount: None,
+            },
+            auth_user.user_id.clone(),
+        ))
+    } else {
+        None
+    };
+
+    let contribution = SavingsGoalContribution::new(
+        auth_user.user_id.clone(),
+        goal.id.clone(),
+        request.amount,
+        date,
+        request.note,
+        transaction.as_ref().map(|t| t.id.clone()),
+        "manual",
+    );
+
+    let result: anyhow::Result<ContributionOutcome> = async {
+        let mut tx = pool.begin().await?;
+
+        if let Some(transaction) = &transaction {
+            let date_str = transaction.date.format("%Y-%m-%d %H:%M:%S").to_string();
+            let created_at_str = transaction.created_at.format("%Y-%m-%d %H:%M:%S").to_string();
+            sqlx::query(
+                "INSERT INTO transactions (id, user_id, account_id, transaction_type, amount, currency, category, description, date, created_at) VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?, ?)"
+            )
+            .bind(&transaction.id)
+            .bind(&transaction.user_id)
+            .bind(&transaction.account_id)
+            .bind("transfer")
+            .bind(transaction.amount)
+            .bind(&transaction.currency)
+            .bind(&transaction.category)
+            .bind(&transaction.description)
+            .bind(&date_str)
+            .bind(&created_at_str)
+            .execute(&mut tx)
+            .await?;
+        }
+
+        let outcome = goal_contributions::apply_contribution(&mut tx, &contribution).await?;
+        if matches!(outcome, ContributionOutcome::Applied { .. }) {
+            tx.commit().await?;
+        }
+        Ok(outcome)
+    }
+    .await;
+
+    match result {
+        Ok(ContributionOutcome::Applied { current_amount, is_completed }) => {
+            tracing::info!("Contribution recorded for goal {}: {} (now {})", id, contribution.amount, current_amount);
+            if let Some(transaction) = &transaction {
+                events::emit(&pool, &auth_user.user_id, "transaction.created", "transaction", &transaction.id, transaction).await;
+            }
+            if is_completed && !goal.is_completed {
+                events::emit(&pool, &auth_user.user_id, "goal.completed", "savings_goal", &goal.id, &json!({
+                    "id": goal.id,
+                    "name": goal.name,
+                    "currentAmount": current_amount,
+                    "targetAmount": goal.target_amount
+                })).await;
             }
+            Ok(ApiResponse::ok(json!({
+                "contribution": contribution,
+                "transaction": transaction,
+                "goal": {
+                    "id": goal.id,
+                    "currentAmount": current_amount,
+                    "targetAmount": goal.target_amount,
+                    "isCompleted": is_completed
+                }
+            })))
+        }
+        Ok(ContributionOutcome::GoalNotFound) => Err(AppError::NotFound("Savings goal not found".into())),
+        Ok(ContributionOutcome::InsufficientSavings) => {
+            tracing::warn!("Withdrawal exceeds saved amount for goal {}", id);
+            Err(AppError::BadRequest("Withdrawal exceeds saved amount for goal".into()))
+        }
+        Err(e) => {
+            tracing::error!("Failed to record contribution: {}", e);
+            Err(AppError::Internal("Failed to record contribution".into()))
+        }
+    }
+}
+
+/// One page of the goal's contributions, newest first.
+async fn contributions_page(pool: &DbPool, goal_id: &str, user_id: &str, page: &PageQuery) -> Result<Paginated<SavingsGoalContribution>, sqlx::Error> {
+    let contributions = sqlx::query_as::<_, SavingsGoalContribution>(
+        "SELECT * FROM savings_goal_contributions WHERE goal_id = ? AND user_id = ? ORDER BY contribution_date DESC, id LIMIT ? OFFSET ?"
+    )
+    .bind(goal_id)
+    .bind(user_id)
+    .bind(page.limit() as i64)
+    .bind(page.offset() as i64)
+    .fetch_all(pool)
+    .await?;
+
+    let total = sqlx::query_scalar::<_, i64>("SELECT COUNT(*) FROM savings_goal_contributions WHERE goal_id = ? AND user_id = ?")
+        .bind(goal_id)
+        .bind(user_id)
+        .fetch_one(pool)
+        .await?;
+
+    Ok(Paginated::new(contributions, total as usize, page))
+}
+
+pub async fn get_contributions(
+    Path(id): Path<String>,
+    State(pool): State<DbPool>,
+    auth_user: AuthUser,
+    Query(page): Query<PageQuery>,
+) -> Result<ApiResponse, AppError> {
+    let result = contributions_page(&pool, &id, &auth_user.user_id, &page).await;
+
+    match result {
+        Ok(contributions) => {
+            Ok(ApiResponse::ok(json!(contributions)))
         }
         Err(e) => {
-            log::error!("Failed to delete savings goal: {}", e);
-            Err(StatusCode::INTERNAL_SERVER_ERROR)
+            tracing::error!("Failed to get contributions: {}", e);
+            Err(AppError::Internal("Failed to get contributions".into()))
         }
     }
 }
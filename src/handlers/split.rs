@@ -0,0 +1,472 @@
+use std::collections::HashSet;
+
+use axum::{
+    extract::{Path, Query, State},
+    response::Json,
+};
+use chrono::Utc;
+use serde_json::{json, Value};
+use uuid::Uuid;
+
+use crate::models::{
+    CreateSplitExpenseRequest, CreateSplitSettlementRequest, CreateTransactionRequest, SettlementDirection, SplitExpense,
+    SplitExpenseDetail, SplitSettlement, Transaction, TransactionType,
+};
+use crate::services::{currency, households, splits, DbPool};
+use crate::middleware::auth::AuthUser;
+use crate::utils::money::Money;
+use crate::utils::response::ApiResponse;
+use crate::utils::error::AppError;
+use crate::utils::pagination::{PageQuery, Paginated};
+use crate::utils::validation::Validate;
+
+const SPLIT_CATEGORY: &str = "Split";
+const SETTLEMENT_CATEGORY: &str = "Settlement";
+
+/// Someone the user shares a household with, for a split expense. Unknown and unrelated
+/// people get the same 404, so it doesn't tell whether an email is registered.
+async fn resolve_member(pool: &DbPool, user_id: &str, reference: &str) -> Result<String, AppError> {
+    splits::resolve_member(pool, user_id, reference)
+        .await
+        .map_err(|e| {
+            tracing::error!("Failed to look up user {}: {}", reference, e);
+            AppError::Internal("Failed to look up user".into())
+        })?
+        .ok_or_else(|| {
+            tracing::warn!("No household member of {} matches {}", user_id, reference);
+            AppError::NotFound("No one in your households matches that email or ID".into())
+        })
+}
+
+/// Someone the user shares a household or a split expense with, for a settlement.
+async fn resolve_friend(pool: &DbPool, user_id: &str, reference: &str) -> Result<String, AppError> {
+    splits::resolve_friend(pool, user_id, reference)
+        .await
+        .map_err(|e| {
+            tracing::error!("Failed to look up user {}: {}", reference, e);
+            AppError::Internal("Failed to look up user".into())
+        })?
+        .ok_or_else(|| {
+            tracing::warn!("No split friend of {} matches {}", user_id, reference);
+            AppError::NotFound("No one you share a household or split with matches that email or ID".into())
+        })
+}
+
+/// Fixes the transaction's amount in the user's display currency, as other transactions are.
+async fn capture_base_amount(pool: &DbPool, transaction: &mut Transaction) -> Result<(), AppError> {
+    let base = currency::capture_base_amount(
+        pool,
+        &transaction.user_id,
+        transaction.amount,
+        &transaction.currency,
+        transaction.date.date_naive(),
+        None,
+        None,
+    )
+    .await
+    .map_err(|e| {
+        tracing::error!("Failed to capture exchange rate: {}", e);
+        AppError::Internal("Failed to capture exchange rate".into())
+    })?;
+    if let Some(base) = base {
+        transaction.exchange_rate = Some(base.exchange_rate);
+        transaction.base_amount = Some(base.amount);
+        transaction.base_currency = Some(base.currency);
+    }
+    Ok(())
+}
+
+/// The account must be the user's own or shared with them by a household they may write to.
+async fn check_account_writable(pool: &DbPool, account_id: &str, user_id: &str) -> Result<(), AppError> {
+    let writable = households::can_write_account(pool, account_id, user_id)
+        .await
+        .map_err(|e| {
+            tracing::error!("Failed to look up account {}: {}", account_id, e);
+            AppError::Internal("Failed to look up account".into())
+        })?;
+    if writable {
+        Ok(())
+    } else {
+        Err(AppError::NotFound("Account not found".into()))
+    }
+}
+
+async fn find_split(pool: &DbPool, id: &str, user_id: &str) -> Result<Option<SplitExpense>, AppError> {
+    sqlx::query_as::<_, SplitExpense>(&format!("SELECT * FROM split_expenses WHERE id = ? AND {}", splits::INVOLVED_SPLITS))
+        .bind(id)
+        .bind(user_id)
+        .bind(user_id)
+        .fetch_optional(pool)
+        .await
+        .map_err(|e| {
+            tracing::error!("Failed to get split expense {}: {}", id, e);
+            AppError::Internal("Failed to get split expense".into())
+        })
+}
+
+async fn with_shares(pool: &DbPool, split: SplitExpense) -> Result<Value, AppError> {
+    let id = split.id.clone();
+    let detail = splits::detail(pool, split).await.map_err(|e| {
+        tracing::error!("Failed to get shares of split expense {}: {}", id, e);
+        AppError::Internal("Failed to get shares of split expense".into())
+    })?;
+    Ok(json!(detail))
+}
+
+async fn with_all_shares(pool: &DbPool, expenses: Vec<SplitExpense>) -> Result<Vec<SplitExpenseDetail>, AppError> {
+    splits::details(pool, expenses).await.map_err(|e| {
+        tracing::error!("Failed to get shares of split expenses: {}", e);
+        AppError::Internal("Failed to get shares of split expenses".into())
+    })
+}
+
+async fn friend_balances(pool: &DbPool, user_id: &str, friend_id: Option<&str>) -> Result<Value, AppError> {
+    let balances = splits::balances(pool, user_id, friend_id).await.map_err(|e| {
+        tracing::error!("Failed to work out split balances for {}: {}", user_id, e);
+        AppError::Internal("Failed to work out split balances".into())
+    })?;
+    Ok(json!(balances))
+}
+
+/// Records a group expense. Shares are given for every participant or for none, in which case
+/// the amount is split evenly. When the creator paid and names an account, their payment is
+/// recorded there as an expense for the full amount.
+pub async fn create_split_expense(
+    State(pool): State<DbPool>,
+    auth_user: AuthUser,
+    Json(request): Json<CreateSplitExpenseRequest>,
+) -> Result<ApiResponse, AppError> {
+    request.validate()?;
+
+    let code = request
+        .currency
+        .as_deref()
+        .map(str::to_uppercase)
+        .unwrap_or_else(|| currency::DEFAULT_DISPLAY_CURRENCY.to_string());
+
+    let paid_by = match &request.paid_by {
+        Some(reference) => resolve_member(&pool, &auth_user.user_id, reference).await?,
+        None => auth_user.user_id.clone(),
+    };
+    let mut participants = Vec::with_capacity(request.participants.len());
+    for participant in &request.participants {
+        participants.push(resolve_member(&pool, &auth_user.user_id, &participant.user).await?);
+    }
+    let unique: HashSet<_> = participants.iter().collect();
+    if unique.len() != participants.len() || (paid_by != auth_user.user_id && !unique.contains(&auth_user.user_id)) {
+        tracing::warn!("Split participants must be distinct and include the creator");
+        return Err(AppError::BadRequest("Split participants must be distinct and include the creator".into()));
+    }
+
+    let given = request.participants.iter().filter(|p| p.amount.is_some()).count();
+    let amounts = if given == 0 {
+        splits::even_shares(request.amount, participants.len(), &code)
+    } else {
+        let amounts: Vec<Money> = request.participants.iter().filter_map(|p| p.amount).collect();
+        let valid = amounts.iter().all(|a| !a.is_negative() && currency::fits_minor_unit(*a, &code))
+            && amounts.iter().copied().sum::<Money>() == request.amount;
+        if !valid {
+            tracing::warn!("Split shares must be non-negative and add up to {}", request.amount);
+            return Err(AppError::BadRequest("Split shares must be non-negative and add up to the total".into()));
+        }
+        amounts
+    };
+    let shares: Vec<(String, Money)> = participants.into_iter().zip(amounts).collect();
+
+    let date = request.date.unwrap_or_else(Utc::now);
+    let description = request.description.trim().to_string();
+    let transaction = match &request.account_id {
+        Some(_) if paid_by != auth_user.user_id => {
+            tracing::warn!("Only the payer can record a split expense in an account");
+            return Err(AppError::BadRequest("Only the payer can record a split expense in an account".into()));
+        }
+        Some(account_id) => {
+            check_account_writable(&pool, account_id, &auth_user.user_id).await?;
+            let mut transaction = Transaction::new(
+                CreateTransactionRequest {
+                    id: None,
+                    account_id: account_id.clone(),
+                    transaction_type: TransactionType::Expense,
+                    amount: request.amount,
+                    currency: Some(code.clone()),
+                    category: Some(request.category.clone().unwrap_or_else(|| SPLIT_CATEGORY.to_string())),
+                    description: Some(description.clone()),
+                    date: Some(date),
+                    created_at: None,
+                    exchange_rate: None,
+                    base_amount: None,
+                },
+                auth_user.user_id.clone(),
+            );
+            capture_base_amount(&pool, &mut transaction).await?;
+            Some(transaction)
+        }
+        None => None,
+    };
+
+    let split = SplitExpense {
+        id: Uuid::new_v4().to_string(),
+        created_by: auth_user.user_id.clone(),
+        paid_by,
+        description,
+        amount: request.amount,
+        currency: code,
+        category: request.category,
+        date,
+        transaction_id: transaction.as_ref().map(|t| t.id.clone()),
+        created_at: Utc::now(),
+    };
+    splits::create_split(&pool, &split, &shares, transaction.as_ref())
+        .await
+        .map_err(|e| {
+            tracing::error!("Failed to create split expense: {}", e);
+            AppError::Internal("Failed to create split expense".into())
+        })?;
+
+    tracing::info!("Split expense created: {} ({} {} between {})", split.id, split.amount, split.currency, shares.len());
+    Ok(ApiResponse::ok(json!(with_shares(&pool, split).await?)))
+}
+
+pub async fn get_split_expenses(
+    State(pool): State<DbPool>,
+    auth_user: AuthUser,
+    Query(page): Query<PageQuery>,
+) -> Result<ApiResponse, AppError> {
+    let expenses = sqlx::query_as::<_, SplitExpense>(&format!(
+        "SELECT * FROM split_expenses WHERE {} ORDER BY date DESC, id LIMIT ? OFFSET ?",
+        splits::INVOLVED_SPLITS
+    ))
+    .bind(&auth_user.user_id)
+    .bind(&auth_user.user_id)
+    .bind(page.limit() as i64)
+    .bind(page.offset() as i64)
+    .fetch_all(&pool)
+    .await
+    .map_err(|e| {
+        tracing::error!("Failed to get split expenses: {}", e);
+        AppError::Internal("Failed to get split expenses".into())
+    })?;
+    let total = sqlx::query_scalar::<_, i64>(&format!("SELECT COUNT(*) FROM split_expenses WHERE {}", splits::INVOLVED_SPLITS))
+        .bind(&auth_user.user_id)
+        .bind(&auth_user.user_id)
+        .fetch_one(&pool)
+        .await
+        .map_err(|e| {
+            tracing::error!("Failed to count split expenses: {}", e);
+            AppError::Internal("Failed to get split expenses".into())
+        })?;
+
+    let data = with_all_shares(&pool, expenses).await?;
+    Ok(ApiResponse::ok(json!(Paginated::new(data, total as usize, &page))))
+}
+
+pub async fn get_split_expense(
+    Path(id): Path<String>,
+    State(pool): State<DbPool>,
+    auth_user: AuthUser,
+) -> Result<ApiResponse, AppError> {
+    let split = find_split(&pool, &id, &auth_user.user_id).await?.ok_or_else(|| AppError::NotFound("Split expense not found".into()))?;
+    Ok(ApiResponse::ok(json!(with_shares(&pool, split).await?)))
+}
+
+/// Deletes a split expense. Only its creator can; the payer's transaction, if any, stays.
+pub async fn delete_split_expense(
+    Path(id): Path<String>,
+    State(pool): State<DbPool>,
+    auth_user: AuthUser,
+) -> Result<ApiResponse, AppError> {
+    let split = find_split(&pool, &id, &auth_user.user_id).await?.ok_or_else(|| AppError::NotFound("Split expense not found".into()))?;
+    if split.created_by != auth_user.user_id {
+        return Err(AppError::Forbidden("Only whoever recorded a split expense can delete it".into()));
+    }
+    sqlx::query("DELETE FROM split_expenses WHERE id = ?")
+        .bind(&id)
+        .execute(&pool)
+        .await
+        .map_err(|e| {
+            tracing::error!("Failed to delete split expense {}: {}", id, e);
+            AppError::Internal("Failed to delete split expense".into())
+        })?;
+
+    tracing::info!("Split expense deleted successfully: {}", id);
+    Ok(ApiResponse::message("Split expense deleted successfully"))
+}
+
+/// What every friend owes the user or is owed, by currency.
+pub async fn get_split_balances(
+    State(pool): State<DbPool>,
+    auth_user: AuthUser,
+) -> Result<ApiResponse, AppError> {
+    Ok(ApiResponse::ok(json!(friend_balances(&pool, &auth_user.user_id, None).await?)))
+}
+
+/// The balance with one friend along with the shared expenses and settlements behind it.
+pub async fn get_friend_balance(
+    Path(friend_id): Path<String>,
+    State(pool): State<DbPool>,
+    auth_user: AuthUser,
+) -> Result<ApiResponse, AppError> {
+    let expenses = sqlx::query_as::<_, SplitExpense>(
+        r#"
+        SELECT * FROM split_expenses
+        WHERE (paid_by = ?1 AND id IN (SELECT split_id FROM split_shares WHERE user_id = ?2))
+           OR (paid_by = ?2 AND id IN (SELECT split_id FROM split_shares WHERE user_id = ?1))
+        ORDER BY date DESC
+        "#,
+    )
+    .bind(&auth_user.user_id)
+    .bind(&friend_id)
+    .fetch_all(&pool)
+    .await
+    .map_err(|e| {
+        tracing::error!("Failed to get split expenses with {}: {}", friend_id, e);
+        AppError::Internal("Failed to get split expenses".into())
+    })?;
+    let settlements = sqlx::query_as::<_, SplitSettlement>(
+        "SELECT * FROM split_settlements WHERE (from_user_id = ?1 AND to_user_id = ?2) OR (from_user_id = ?2 AND to_user_id = ?1) ORDER BY date DESC"
+    )
+    .bind(&auth_user.user_id)
+    .bind(&friend_id)
+    .fetch_all(&pool)
+    .await
+    .map_err(|e| {
+        tracing::error!("Failed to get settlements with {}: {}", friend_id, e);
+        AppError::Internal("Failed to get settlements".into())
+    })?;
+    if expenses.is_empty() && settlements.is_empty() {
+        return Err(AppError::NotFound("No shared expenses with that user".into()));
+    }
+
+    let details = with_all_shares(&pool, expenses).await?;
+    Ok(ApiResponse::ok(json!({
+        "balances": friend_balances(&pool, &auth_user.user_id, Some(&friend_id)).await?,
+        "expenses": details,
+        "settlements": settlements
+    })))
+}
+
+/// Records a payment to or from a friend. With an account, the user's side is recorded there
+/// as an expense (paid) or income (received).
+pub async fn create_split_settlement(
+    State(pool): State<DbPool>,
+    auth_user: AuthUser,
+    Json(request): Json<CreateSplitSettlementRequest>,
+) -> Result<ApiResponse, AppError> {
+    request.validate()?;
+
+    let code = request
+        .currency
+        .as_deref()
+        .map(str::to_uppercase)
+        .unwrap_or_else(|| currency::DEFAULT_DISPLAY_CURRENCY.to_string());
+    let friend = resolve_friend(&pool, &auth_user.user_id, &request.friend).await?;
+    if friend == auth_user.user_id {
+        return Err(AppError::BadRequest("You can't settle up with yourself".into()));
+    }
+
+    let paid = request.direction == SettlementDirection::Paid;
+    let (from_user_id, to_user_id) = if paid {
+        (auth_user.user_id.clone(), friend)
+    } else {
+        (friend, auth_user.user_id.clone())
+    };
+    let date = request.date.unwrap_or_else(Utc::now);
+    let transaction = match &request.account_id {
+        Some(account_id) => {
+            check_account_writable(&pool, account_id, &auth_user.user_id).await?;
+            let mut transaction = Transaction::new(
+                CreateTransactionRequest {
+                    id: None,
+                    account_id: account_id.clone(),
+                    transaction_type: if paid { TransactionType::Expense } else { TransactionType::Income },
+                    amount: request.amount,
+                    currency: Some(code.clone()),
+                    category: Some(SETTLEMENT_CATEGORY.to_string()),
+                    description: request.note.clone().or_else(|| Some("Split settlement".to_string())),
+                    date: Some(date),
+                    created_at: None,
+                    exchange_rate: None,
+                    base_amount: None,
+                },
+                auth_user.user_id.clone(),
+            );
+            capture_base_amount(&pool, &mut transaction).await?;
+            Some(transaction)
+        }
+        None => None,
+    };
+
+    let settlement = SplitSettlement {
+        id: Uuid::new_v4().to_string(),
+        from_user_id,
+        to_user_id,
+        amount: request.amount,
+        currency: code,
+        date,
+        note: request.note,
+        transaction_id: transaction.as_ref().map(|t| t.id.clone()),
+        created_by: auth_user.user_id.clone(),
+        created_at: Utc::now(),
+    };
+    splits::create_settlement(&pool, &settlement, transaction.as_ref())
+        .await
+        .map_err(|e| {
+            tracing::error!("Failed to record settlement: {}", e);
+            AppError::Internal("Failed to record settlement".into())
+        })?;
+
+    tracing::info!("Settlement recorded: {} ({} {})", settlement.id, settlement.amount, settlement.currency);
+    Ok(ApiResponse::ok(json!(settlement)))
+}
+
+pub async fn get_split_settlements(
+    State(pool): State<DbPool>,
+    auth_user: AuthUser,
+    Query(page): Query<PageQuery>,
+) -> Result<ApiResponse, AppError> {
+    let settlements = sqlx::query_as::<_, SplitSettlement>(
+        "SELECT * FROM split_settlements WHERE from_user_id = ?1 OR to_user_id = ?1 ORDER BY date DESC, id LIMIT ?2 OFFSET ?3"
+    )
+    .bind(&auth_user.user_id)
+    .bind(page.limit() as i64)
+    .bind(page.offset() as i64)
+    .fetch_all(&pool)
+    .await
+    .map_err(|e| {
+        tracing::error!("Failed to get settlements: {}", e);
+        AppError::Internal("Failed to get settlements".into())
+    })?;
+    let total = sqlx::query_scalar::<_, i64>("SELECT COUNT(*) FROM split_settlements WHERE from_user_id = ?1 OR to_user_id = ?1")
+        .bind(&auth_user.user_id)
+        .fetch_one(&pool)
+        .await
+        .map_err(|e| {
+            tracing::error!("Failed to count settlements: {}", e);
+            AppError::Internal("Failed to get settlements".into())
+        })?;
+
+    Ok(ApiResponse::ok(json!(Paginated::new(settlements, total as usize, &page))))
+}
+
+/// Deletes a settlement. Only its creator can; their transaction, if any, stays.
+pub async fn delete_split_settlement(
+    Path(id): Path<String>,
+    State(pool): State<DbPool>,
+    auth_user: AuthUser,
+) -> Result<ApiResponse, AppError> {
+    let result = sqlx::query("DELETE FROM split_settlements WHERE id = ? AND created_by = ?")
+        .bind(&id)
+        .bind(&auth_user.user_id)
+        .execute(&pool)
+        .await
+        .map_err(|e| {
+            tracing::error!("Failed to delete settlement {}: {}", id, e);
+            AppError::Internal("Failed to delete settlement".into())
+        })?;
+    if result.rows_affected() == 0 {
+        return Err(AppError::NotFound("Settlement not found".into()));
+    }
+
+    tracing::info!("Settlement deleted successfully: {}", id);
+    Ok(ApiResponse::message("Settlement deleted successfully"))
+}
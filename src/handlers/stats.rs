@@ -0,0 +1,35 @@
+use axum::extract::{Query, State};
+use serde_json::json;
+
+use crate::models::StatsQuery;
+use crate::services::{stats, user_time};
+use crate::services::DbPool;
+use crate::middleware::auth::AuthUser;
+use crate::utils::response::ApiResponse;
+use crate::utils::error::AppError;
+
+pub async fn get_stats(
+    State(pool): State<DbPool>,
+    auth_user: AuthUser,
+    Query(query): Query<StatsQuery>,
+) -> Result<ApiResponse, AppError> {
+    if let (Some(from), Some(to)) = (query.from, query.to) {
+        if from > to {
+            tracing::warn!("Invalid stats range {} to {}", from, to);
+            return Err(AppError::BadRequest("Invalid stats range".into()));
+        }
+    }
+
+    let time = user_time::for_user(&pool, &auth_user.user_id).await.map_err(|e| {
+        tracing::error!("Failed to load the timezone of user {}: {}", auth_user.user_id, e);
+        AppError::Internal("Failed to compute statistics".into())
+    })?;
+    let stats = stats::stats(&pool, &auth_user.user_id, query.from, query.to, &time)
+        .await
+        .map_err(|e| {
+            tracing::error!("Failed to compute statistics: {}", e);
+            AppError::Internal("Failed to compute statistics".into())
+        })?;
+
+    Ok(ApiResponse::ok(json!(stats)))
+}
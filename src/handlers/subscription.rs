@@ -0,0 +1,59 @@
+use axum::extract::{Path, Query, State};
+use serde_json::json;
+
+use crate::services::{history::{self, EntityKind}, subscriptions, DbPool};
+use crate::middleware::auth::AuthUser;
+use crate::middleware::device::ClientDevice;
+use crate::utils::response::ApiResponse;
+use crate::utils::error::AppError;
+use crate::utils::pagination::{PageQuery, Paginated};
+
+/// Subscriptions detected in the user's transaction history.
+pub async fn get_subscriptions(
+    State(pool): State<DbPool>,
+    auth_user: AuthUser,
+    Query(page): Query<PageQuery>,
+) -> Result<ApiResponse, AppError> {
+    let detected = subscriptions::detect(&pool, &auth_user.user_id)
+        .await
+        .map_err(|e| {
+            tracing::error!("Failed to detect subscriptions: {}", e);
+            AppError::Internal("Failed to detect subscriptions".into())
+        })?;
+
+    Ok(ApiResponse::ok(json!(Paginated::slice(detected, &page))))
+}
+
+/// Turns a detected subscription into a recurring transaction.
+pub async fn track_subscription(
+    Path(id): Path<String>,
+    State(pool): State<DbPool>,
+    auth_user: AuthUser,
+    device: ClientDevice,
+) -> Result<ApiResponse, AppError> {
+    let detected = subscriptions::detect(&pool, &auth_user.user_id)
+        .await
+        .map_err(|e| {
+            tracing::error!("Failed to detect subscriptions: {}", e);
+            AppError::Internal("Failed to detect subscriptions".into())
+        })?;
+    let subscription = detected
+        .into_iter()
+        .find(|subscription| subscription.id == id)
+        .ok_or_else(|| AppError::NotFound("Subscription not found".into()))?;
+    if let Some(existing) = &subscription.recurring_transaction_id {
+        tracing::warn!("Subscription {} is already tracked by recurring transaction {}", id, existing);
+        return Err(AppError::Conflict("Subscription is already tracked by recurring transaction".into()));
+    }
+
+    let rt = subscriptions::track(&pool, &auth_user.user_id, &subscription)
+        .await
+        .map_err(|e| {
+            tracing::error!("Failed to track subscription {}: {}", id, e);
+            AppError::Internal("Failed to track subscription".into())
+        })?;
+    history::record(&pool, EntityKind::RecurringTransaction, &rt.id, &auth_user.user_id, "created", None, &device).await;
+
+    tracing::info!("Subscription {} now tracked by recurring transaction {}", id, rt.id);
+    Ok(ApiResponse::ok(json!(rt)))
+}
@@ -0,0 +1,317 @@
+use axum::{
+    body::boxed,
+    extract::{Path, Query, State},
+    http::{header, HeaderMap, StatusCode},
+    response::{IntoResponse, Json, Response},
+};
+use serde::de::DeserializeOwned;
+use serde_json::{json, Value};
+use uuid::Uuid;
+
+use crate::handlers::{account, budget, recurring_transaction, savings_goal, transaction};
+use crate::models::{RegisterSyncDeviceRequest, SyncChange, SyncChangesQuery, SyncEntity, SyncOperation, SyncRequest, SyncResult};
+use crate::services::{quotas, sync, DbPool};
+use crate::middleware::auth::AuthUser;
+use crate::middleware::device::{ClientDevice, DEVICE_HEADER};
+use crate::utils::response::ApiResponse;
+use crate::utils::error::AppError;
+use crate::utils::pagination::PageQuery;
+
+/// The `X-Device-Id` a client sent. Unlike `ClientDevice`, never falls back to the User-Agent,
+/// which several devices may share.
+fn sync_device_id(headers: &HeaderMap) -> Option<String> {
+    headers
+        .get(DEVICE_HEADER)
+        .and_then(|value| value.to_str().ok())
+        .map(str::trim)
+        .filter(|id| !id.is_empty() && id.len() <= 120)
+        .map(str::to_string)
+}
+
+type ChangeError = AppError;
+
+fn bad_request(message: impl Into<String>) -> ChangeError {
+    AppError::BadRequest(message.into())
+}
+
+/// The change's body for a create, carrying the client's ID.
+fn create_body<T: DeserializeOwned>(change: &SyncChange) -> Result<T, ChangeError> {
+    if Uuid::parse_str(&change.id).is_err() {
+        return Err(bad_request("id must be a UUID"));
+    }
+    let mut data = match &change.data {
+        Some(Value::Object(data)) => data.clone(),
+        _ => return Err(bad_request("data must be an object")),
+    };
+    data.insert("id".to_string(), json!(change.id));
+    serde_json::from_value(Value::Object(data)).map_err(|e| bad_request(e.to_string()))
+}
+
+fn update_body<T: DeserializeOwned>(change: &SyncChange) -> Result<T, ChangeError> {
+    match &change.data {
+        Some(data @ Value::Object(_)) => serde_json::from_value(data.clone()).map_err(|e| bad_request(e.to_string())),
+        _ => Err(bad_request("data must be an object")),
+    }
+}
+
+/// Applies one change through the entity's own endpoint, so it is validated, access-checked
+/// and recorded in history exactly as if sent on its own. Updates carry their base `version`
+/// in `data`.
+async fn apply_change(pool: &DbPool, user_id: &str, device: &str, change: &SyncChange) -> Result<Response, ChangeError> {
+    let state = || State(pool.clone());
+    let user = || AuthUser { user_id: user_id.to_string() };
+    let device = || ClientDevice(device.to_string());
+    let path = || Path(change.id.clone());
+    let headers = HeaderMap::new;
+
+    let response = match (change.entity, change.operation) {
+        (SyncEntity::Account, SyncOperation::Create) => {
+            account::create_account(state(), user(), device(), Json(create_body(change)?)).await.into_response()
+        }
+        (SyncEntity::Account, SyncOperation::Update) => {
+            account::update_account(path(), state(), user(), device(), headers(), Json(update_body(change)?)).await.into_response()
+        }
+        (SyncEntity::Account, SyncOperation::Delete) => account::delete_account(path(), state(), user(), device()).await.into_response(),
+        (SyncEntity::Transaction, SyncOperation::Create) => {
+            transaction::create_transaction(state(), user(), device(), Json(create_body(change)?)).await.into_response()
+        }
+        (SyncEntity::Transaction, SyncOperation::Update) => {
+            transaction::update_transaction(path(), state(), user(), device(), headers(), Json(update_body(change)?)).await.into_response()
+        }
+        (SyncEntity::Transaction, SyncOperation::Delete) => {
+            transaction::delete_transaction(path(), state(), user(), device()).await.into_response()
+        }
+        (SyncEntity::Budget, SyncOperation::Create) => {
+            budget::create_budget(state(), user(), device(), Json(create_body(change)?)).await.into_response()
+        }
+        (SyncEntity::Budget, SyncOperation::Update) => {
+            budget::update_budget(path(), state(), user(), device(), headers(), Json(update_body(change)?)).await.into_response()
+        }
+        (SyncEntity::Budget, SyncOperation::Delete) => budget::delete_budget(path(), state(), user(), device()).await.into_response(),
+        (SyncEntity::SavingsGoal, SyncOperation::Create) => {
+            savings_goal::create_savings_goal(state(), user(), device(), Json(create_body(change)?)).await.into_response()
+        }
+        (SyncEntity::SavingsGoal, SyncOperation::Update) => {
+            savings_goal::update_savings_goal(path(), state(), user(), device(), headers(), Json(update_body(change)?)).await.into_response()
+        }
+        (SyncEntity::SavingsGoal, SyncOperation::Delete) => {
+            savings_goal::delete_savings_goal(path(), state(), user(), device()).await.into_response()
+        }
+        (SyncEntity::RecurringTransaction, SyncOperation::Create) => {
+            recurring_transaction::create_recurring_transaction(state(), user(), device(), Json(create_body(change)?)).await.into_response()
+        }
+        (SyncEntity::RecurringTransaction, SyncOperation::Update) => {
+            recurring_transaction::update_recurring_transaction(path(), state(), user(), device(), headers(), Json(update_body(change)?)).await.into_response()
+        }
+        (SyncEntity::RecurringTransaction, SyncOperation::Delete) => {
+            recurring_transaction::delete_recurring_transaction(path(), state(), user(), device()).await.into_response()
+        }
+    };
+
+    Ok(response)
+}
+
+/// The status of an endpoint's response, its `data` and, for failures, its error. A conflict's
+/// `data` is the server's copy of the record.
+async fn outcome(response: Response) -> (StatusCode, Option<Value>, Option<String>) {
+    let status = response.status();
+    let body = match hyper::body::to_bytes(response.into_body()).await {
+        Ok(bytes) => serde_json::from_slice::<Value>(&bytes).unwrap_or(Value::Null),
+        Err(e) => {
+            tracing::error!("Failed to read the response to a sync change: {}", e);
+            Value::Null
+        }
+    };
+
+    let data = body.get("data").cloned();
+    if status.is_success() {
+        return (status, data, None);
+    }
+    let error = body
+        .pointer("/error/message")
+        .and_then(Value::as_str)
+        .or(status.canonical_reason())
+        .unwrap_or("Failed")
+        .to_string();
+    (status, data, Some(error))
+}
+
+/// Applies a batch of changes made offline, in order. Each change stands alone: one failing
+/// doesn't stop or undo the others, and every change gets its own result. Clients should send
+/// parents first, e.g. an account before its transactions.
+pub async fn sync_changes(
+    State(pool): State<DbPool>,
+    auth_user: AuthUser,
+    device: ClientDevice,
+    headers: HeaderMap,
+    Json(request): Json<SyncRequest>,
+) -> Result<ApiResponse, AppError> {
+    let batch_limit = quotas::for_user(&pool, &auth_user.user_id)
+        .await
+        .map_err(|e| {
+            tracing::error!("Failed to look up quotas of user {}: {}", auth_user.user_id, e);
+            AppError::Internal("Failed to look up quotas".into())
+        })?
+        .sync_batch_size;
+    if let Some(limit) = batch_limit.filter(|&limit| request.changes.len() as u64 > limit) {
+        tracing::warn!("Sync batch of {} changes is over the limit of {}", request.changes.len(), limit);
+        return Err(AppError::PayloadTooLarge(format!("Too many changes in one sync batch; the limit is {}", limit)));
+    }
+
+    let mut results = Vec::with_capacity(request.changes.len());
+    for change in &request.changes {
+        let result = apply_change(&pool, &auth_user.user_id, &device.0, change).await;
+        let (status, data, error) = match result {
+            Ok(response) => outcome(response).await,
+            Err(error) => (error.status(), None, Some(error.message().to_string())),
+        };
+        results.push(SyncResult {
+            entity: change.entity,
+            operation: change.operation,
+            id: change.id.clone(),
+            success: status.is_success(),
+            status: status.as_u16(),
+            data,
+            error,
+        });
+    }
+
+    if let Some(device_id) = sync_device_id(&headers) {
+        if let Err(e) = sync::record_push(&pool, &auth_user.user_id, &device_id).await {
+            tracing::error!("Failed to record sync push from device {}: {}", device_id, e);
+        }
+    }
+
+    let failed = results.iter().filter(|r| !r.success).count();
+    tracing::info!("Sync for user {} applied {} changes, {} failed", auth_user.user_id, results.len() - failed, failed);
+    Ok(ApiResponse::ok(json!({
+        "applied": results.len() - failed,
+        "failed": failed,
+        "results": results
+    })))
+}
+
+/// Everything created, changed or deleted since the client's last pull. Without `since`, returns
+/// every record, for a first download. A `since` older than the change log's retention gets
+/// 410 Gone; the client should then download everything again. Clients sending `X-Device-Id`
+/// have their cursor recorded, for `GET /api/sync/devices`. Transactions are streamed, so
+/// large histories don't have to fit in memory.
+pub async fn get_sync_changes(
+    State(pool): State<DbPool>,
+    auth_user: AuthUser,
+    headers: HeaderMap,
+    Query(query): Query<SyncChangesQuery>,
+) -> Result<Response, AppError> {
+    if query.since.is_some_and(|since| !sync::within_retention(since)) {
+        tracing::warn!("Sync cursor {:?} is older than the change log keeps", query.since);
+        return Err(AppError::Gone("Sync cursor is older than the change log keeps".into()));
+    }
+
+    let changes = sync::changes_since(&pool, &auth_user.user_id, query.since)
+        .await
+        .map_err(|e| {
+            tracing::error!("Failed to get sync changes for user {}: {}", auth_user.user_id, e);
+            AppError::Internal("Failed to get sync changes".into())
+        })?;
+
+    if let Some(device_id) = sync_device_id(&headers) {
+        if let Err(e) = sync::record_pull(&pool, &auth_user.user_id, &device_id, changes.server_time).await {
+            tracing::error!("Failed to record sync pull from device {}: {}", device_id, e);
+        }
+    }
+
+    let body = sync::pull_body(pool, auth_user.user_id.clone(), query.since, changes).map_err(|e| {
+        tracing::error!("Failed to write sync changes for user {}: {}", auth_user.user_id, e);
+        AppError::Internal("Failed to get sync changes".into())
+    })?;
+    Ok(([(header::CONTENT_TYPE, "application/json")], boxed(body)).into_response())
+}
+
+/// Registers the calling device for sync, or updates its name, platform, app version or push
+/// registration. Devices are also registered by their first pull or push.
+pub async fn register_sync_device(
+    State(pool): State<DbPool>,
+    auth_user: AuthUser,
+    client: ClientDevice,
+    headers: HeaderMap,
+    Json(request): Json<RegisterSyncDeviceRequest>,
+) -> Result<ApiResponse, AppError> {
+    let device_id = request
+        .device_id
+        .as_deref()
+        .map(str::trim)
+        .filter(|id| !id.is_empty() && id.len() <= 120)
+        .map(str::to_string)
+        .or_else(|| sync_device_id(&headers))
+        .ok_or_else(|| {
+            tracing::warn!("Sync device registration without a device ID");
+            AppError::BadRequest("Sync device registration without a device ID".into())
+        })?;
+
+    if let Some(push_device_id) = &request.push_device_id {
+        let owned = sqlx::query_scalar::<_, i64>("SELECT COUNT(*) FROM push_devices WHERE id = ? AND user_id = ?")
+            .bind(push_device_id)
+            .bind(&auth_user.user_id)
+            .fetch_one(&pool)
+            .await
+            .map_err(|e| {
+                tracing::error!("Failed to look up push device {}: {}", push_device_id, e);
+                AppError::Internal("Failed to look up push device".into())
+            })?;
+        if owned == 0 {
+            tracing::warn!("Push device not found: {}", push_device_id);
+            return Err(AppError::NotFound("Push device not found".into()));
+        }
+    }
+
+    let name = request.name.clone().filter(|n| !n.trim().is_empty()).unwrap_or(client.0);
+    let device = sync::register_device(&pool, &auth_user.user_id, &device_id, &name, &request)
+        .await
+        .map_err(|e| {
+            tracing::error!("Failed to register sync device {}: {}", device_id, e);
+            AppError::Internal("Failed to register sync device".into())
+        })?;
+
+    tracing::info!("Sync device registered: {} ({})", device.id, device.name);
+    Ok(ApiResponse::ok(json!(device)))
+}
+
+/// The user's devices with their sync lag: time since each one's cursor and how many changes
+/// it hasn't pulled yet.
+pub async fn get_sync_devices(
+    State(pool): State<DbPool>,
+    auth_user: AuthUser,
+    Query(page): Query<PageQuery>,
+) -> Result<ApiResponse, AppError> {
+    let devices = sync::device_statuses(&pool, &auth_user.user_id, &page)
+        .await
+        .map_err(|e| {
+            tracing::error!("Failed to get sync devices for user {}: {}", auth_user.user_id, e);
+            AppError::Internal("Failed to get sync devices".into())
+        })?;
+
+    Ok(ApiResponse::ok(json!(devices)))
+}
+
+pub async fn delete_sync_device(
+    Path(id): Path<String>,
+    State(pool): State<DbPool>,
+    auth_user: AuthUser,
+) -> Result<ApiResponse, AppError> {
+    let result = sqlx::query("DELETE FROM sync_devices WHERE id = ? AND user_id = ?")
+        .bind(&id)
+        .bind(&auth_user.user_id)
+        .execute(&pool)
+        .await
+        .map_err(|e| {
+            tracing::error!("Failed to delete sync device {}: {}", id, e);
+            AppError::Internal("Failed to delete sync device".into())
+        })?;
+
+    if result.rows_affected() == 0 {
+        tracing::warn!("Sync device not found: {}", id);
+        return Err(AppError::NotFound("Sync device not found".into()));
+    }
+
+    Ok(ApiResponse::message("Sync device removed successfully"))
+}
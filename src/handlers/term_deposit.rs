@@ -0,0 +1,178 @@
+use axum::{
+    extract::{Path, Query, State},
+    response::Json,
+};
+use chrono::Utc;
+use serde_json::json;
+use uuid::Uuid;
+
+use crate::models::{Compounding, CreateTermDepositRequest, DepositType, TermDeposit, UpdateTermDepositRequest};
+use crate::services::{currency, households, term_deposits, DbPool};
+use crate::middleware::auth::AuthUser;
+use crate::middleware::owned::{self, Owned};
+use crate::utils::datetime::format_db_datetime;
+use crate::utils::response::ApiResponse;
+use crate::utils::error::AppError;
+use crate::utils::pagination::PageQuery;
+use crate::utils::validation::{FieldErrors, Validate};
+
+/// The account is the user's own or shared with them by a household they may write to.
+async fn can_use_account(pool: &DbPool, account_id: &str, user_id: &str) -> Result<bool, AppError> {
+    households::can_write_account(pool, account_id, user_id)
+        .await
+        .map_err(|e| {
+            tracing::error!("Failed to look up account {}: {}", account_id, e);
+            AppError::Internal("Failed to look up account".into())
+        })
+}
+
+pub async fn create_term_deposit(
+    State(pool): State<DbPool>,
+    auth_user: AuthUser,
+    Json(request): Json<CreateTermDepositRequest>,
+) -> Result<ApiResponse, AppError> {
+    request.validate()?;
+
+    let code = request
+        .currency
+        .as_deref()
+        .map(str::to_uppercase)
+        .unwrap_or_else(|| currency::DEFAULT_DISPLAY_CURRENCY.to_string());
+    if let Some(account_id) = &request.account_id {
+        if !can_use_account(&pool, account_id, &auth_user.user_id).await? {
+            return Err(AppError::NotFound("Account not found".into()));
+        }
+    }
+
+    let id = Uuid::new_v4().to_string();
+    let now = format_db_datetime(Utc::now());
+    let start_date = request.start_date.unwrap_or_else(Utc::now);
+    sqlx::query(
+        r#"
+        INSERT INTO term_deposits (id, user_id, account_id, name, deposit_type, principal, currency, annual_rate, tenure_months,
+            compounding, start_date, maturity_date, is_closed, notes, created_at, updated_at)
+        VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, FALSE, ?, ?, ?)
+        "#,
+    )
+    .bind(&id)
+    .bind(&auth_user.user_id)
+    .bind(&request.account_id)
+    .bind(request.name.trim())
+    .bind(request.deposit_type.unwrap_or(DepositType::Fixed))
+    .bind(request.principal)
+    .bind(&code)
+    .bind(request.annual_rate)
+    .bind(request.tenure_months)
+    .bind(request.compounding.unwrap_or(Compounding::Quarterly))
+    .bind(format_db_datetime(start_date))
+    .bind(format_db_datetime(term_deposits::maturity_date(start_date, request.tenure_months)))
+    .bind(&request.notes)
+    .bind(&now)
+    .bind(&now)
+    .execute(&pool)
+    .await
+    .map_err(|e| {
+        tracing::error!("Failed to create term deposit: {}", e);
+        AppError::Internal("Failed to create term deposit".into())
+    })?;
+    let deposit = owned::find::<TermDeposit>(&pool, &id, &auth_user.user_id).await?.ok_or_else(|| AppError::Internal("Failed to create term deposit".into()))?;
+
+    tracing::info!("Term deposit created: {} ({})", deposit.name, deposit.id);
+    Ok(ApiResponse::ok(json!(term_deposits::summarize(deposit))))
+}
+
+pub async fn get_term_deposits(
+    State(pool): State<DbPool>,
+    auth_user: AuthUser,
+    Query(page): Query<PageQuery>,
+) -> Result<ApiResponse, AppError> {
+    let deposits = owned::page::<TermDeposit>(&pool, &auth_user.user_id, "is_closed ASC, maturity_date ASC", &page)
+        .await
+        .map_err(|e| {
+        tracing::error!("Failed to get term deposits: {}", e);
+        AppError::Internal("Failed to get term deposits".into())
+    })?;
+
+    Ok(ApiResponse::ok(json!(deposits.map(term_deposits::summarize))))
+}
+
+pub async fn get_term_deposit(Owned { resource: deposit, .. }: Owned<TermDeposit>) -> ApiResponse {
+    ApiResponse::ok(json!(term_deposits::summarize(deposit)))
+}
+
+pub async fn update_term_deposit(
+    Path(id): Path<String>,
+    State(pool): State<DbPool>,
+    auth_user: AuthUser,
+    Json(request): Json<UpdateTermDepositRequest>,
+) -> Result<ApiResponse, AppError> {
+    request.validate()?;
+
+    let current = owned::load::<TermDeposit>(&pool, &id, &auth_user.user_id).await?;
+    let principal = request.principal.unwrap_or(current.principal);
+    let annual_rate = request.annual_rate.unwrap_or(current.annual_rate);
+    let tenure_months = request.tenure_months.unwrap_or(current.tenure_months as u32);
+    let start_date = request.start_date.unwrap_or(current.start_date);
+    let mut errors = FieldErrors::default();
+    errors.fits_currency("principal", principal, &current.currency);
+    errors.into_result()?;
+    if let Some(account_id) = &request.account_id {
+        if !can_use_account(&pool, account_id, &auth_user.user_id).await? {
+            return Err(AppError::NotFound("Account not found".into()));
+        }
+    }
+
+    sqlx::query(
+        r#"
+        UPDATE term_deposits SET account_id = COALESCE(?, account_id), name = COALESCE(?, name), principal = ?, annual_rate = ?,
+            tenure_months = ?, compounding = COALESCE(?, compounding), start_date = ?, maturity_date = ?,
+            is_closed = COALESCE(?, is_closed), notes = COALESCE(?, notes), updated_at = ?
+        WHERE id = ? AND user_id = ?
+        "#,
+    )
+    .bind(&request.account_id)
+    .bind(request.name.as_deref().map(str::trim))
+    .bind(principal)
+    .bind(annual_rate)
+    .bind(tenure_months)
+    .bind(request.compounding)
+    .bind(format_db_datetime(start_date))
+    .bind(format_db_datetime(term_deposits::maturity_date(start_date, tenure_months)))
+    .bind(request.is_closed)
+    .bind(&request.notes)
+    .bind(format_db_datetime(Utc::now()))
+    .bind(&id)
+    .bind(&auth_user.user_id)
+    .execute(&pool)
+    .await
+    .map_err(|e| {
+        tracing::error!("Failed to update term deposit {}: {}", id, e);
+        AppError::Internal("Failed to update term deposit".into())
+    })?;
+    let deposit = owned::load::<TermDeposit>(&pool, &id, &auth_user.user_id).await?;
+
+    tracing::info!("Term deposit updated successfully: {}", id);
+    Ok(ApiResponse::ok(json!(term_deposits::summarize(deposit))))
+}
+
+pub async fn delete_term_deposit(
+    Path(id): Path<String>,
+    State(pool): State<DbPool>,
+    auth_user: AuthUser,
+) -> Result<ApiResponse, AppError> {
+    let result = sqlx::query("DELETE FROM term_deposits WHERE id = ? AND user_id = ?")
+        .bind(&id)
+        .bind(&auth_user.user_id)
+        .execute(&pool)
+        .await
+        .map_err(|e| {
+            tracing::error!("Failed to delete term deposit {}: {}", id, e);
+            AppError::Internal("Failed to delete term deposit".into())
+        })?;
+    if result.rows_affected() == 0 {
+        return Err(AppError::NotFound("Term deposit not found".into()));
+    }
+
+    tracing::info!("Term deposit deleted successfully: {}", id);
+    Ok(ApiResponse::message("Term deposit deleted successfully"))
+}
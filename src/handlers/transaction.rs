@@ -1,30 +1,118 @@
 use axum::{
-    extract::{Path, State},
-    http::StatusCode,
-    response::Json,
+    extract::{Path, Query, State},
+    http::HeaderMap,
+    response::{Json, Response},
 };
-use serde_json::{json, Value};
-use sqlx::Row;
+use serde_json::json;
 
 use crate::models::{Transaction, CreateTransactionRequest, UpdateTransactionRequest};
-use crate::services::DbPool;
+use crate::services::{budget_progress, concurrency, credit_utilization, currency, etags, events, history::{self, EntityKind}, households, DbPool};
 use crate::middleware::auth::AuthUser;
+use crate::middleware::device::ClientDevice;
+use crate::utils::money::Money;
+use crate::utils::response::ApiResponse;
+use crate::utils::error::AppError;
+use crate::utils::pagination::{PageQuery, Paginated};
+use crate::utils::validation::{FieldErrors, Validate};
+
+/// The account must be the user's own or shared with them by a household they may write to.
+async fn check_account_writable(pool: &DbPool, account_id: &str, user_id: &str) -> Result<(), AppError> {
+    let access = households::account_access(pool, account_id, user_id)
+        .await
+        .map_err(|e| {
+            tracing::error!("Failed to look up account {}: {}", account_id, e);
+            AppError::Internal("Failed to look up account".into())
+        })?;
+    match access {
+        Some(access) if access.can_write() => Ok(()),
+        Some(_) => {
+            tracing::warn!("User {} can only view shared account {}", user_id, account_id);
+            Err(AppError::Forbidden("You can only view this shared account".into()))
+        }
+        None => {
+            tracing::warn!("Account not found for transaction: {}", account_id);
+            Err(AppError::NotFound("Account not found".into()))
+        }
+    }
+}
+
+/// Fixes the base amount again after an edit. A new amount alone keeps the recorded rate;
+/// a new currency or date looks the rate up afresh unless the client gives one.
+async fn recapture_base_amount(
+    pool: &DbPool,
+    request: &UpdateTransactionRequest,
+    transaction: &Transaction,
+) -> anyhow::Result<()> {
+    let keep_rate = request.currency.is_none()
+        && request.date.is_none()
+        && request.exchange_rate.is_none()
+        && request.base_amount.is_none();
+    let base = match (keep_rate, transaction.exchange_rate, &transaction.base_currency) {
+        (true, Some(rate), Some(base_currency)) => Some(currency::BaseAmount {
+            currency: base_currency.clone(),
+            exchange_rate: rate,
+            amount: currency::round(transaction.amount * rate, base_currency),
+        }),
+        _ => {
+            currency::capture_base_amount(
+                pool,
+                &transaction.user_id,
+                transaction.amount,
+                &transaction.currency,
+                transaction.date.date_naive(),
+                request.exchange_rate,
+                request.base_amount,
+            )
+            .await?
+        }
+    };
+
+    sqlx::query("UPDATE transactions SET exchange_rate = ?, base_amount = ?, base_currency = ? WHERE id = ? AND user_id = ?")
+        .bind(base.as_ref().map(|b| b.exchange_rate))
+        .bind(base.as_ref().map(|b| b.amount))
+        .bind(base.map(|b| b.currency))
+        .bind(&transaction.id)
+        .bind(&transaction.user_id)
+        .execute(pool)
+        .await?;
+    Ok(())
+}
 
 pub async fn create_transaction(
     State(pool): State<DbPool>,
     auth_user: AuthUser,
+    device: ClientDevice,
     Json(request): Json<CreateTransactionRequest>,
-) -> Result<Json<Value>, StatusCode> {
-    log::info!("📥 POST /transactions - Creating transaction for user {}", auth_user.user_id);
-    log::info!("✅ Successfully parsed request: {:?}", request);
+) -> Result<ApiResponse, AppError> {
+    request.validate()?;
 
-    let transaction = Transaction::new(request.clone(), auth_user.user_id.clone());
+    let mut transaction = Transaction::new(request.clone(), auth_user.user_id.clone());
+    check_account_writable(&pool, &transaction.account_id, &auth_user.user_id).await?;
+    let base = currency::capture_base_amount(
+        &pool,
+        &auth_user.user_id,
+        transaction.amount,
+        &transaction.currency,
+        transaction.date.date_naive(),
+        request.exchange_rate,
+        request.base_amount,
+    )
+    .await
+    .map_err(|e| {
+        tracing::error!("Failed to capture exchange rate: {}", e);
+        AppError::Internal("Failed to capture exchange rate".into())
+    })?;
+    if let Some(base) = base {
+        transaction.exchange_rate = Some(base.exchange_rate);
+        transaction.base_amount = Some(base.amount);
+        transaction.base_currency = Some(base.currency);
+    }
     let transaction_type_str = format!("{:?}", transaction.transaction_type).to_lowercase();
     let date_str = transaction.date.format("%Y-%m-%d %H:%M:%S").to_string();
     let created_at_str = transaction.created_at.format("%Y-%m-%d %H:%M:%S").to_string();
 
     let result = sqlx::query(
-        "INSERT INTO transactions (id, user_id, account_id, transaction_type, amount, currency, category, description, date, created_at) VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?, ?)"
+        "INSERT INTO transactions (id, user_id, account_id, transaction_type, amount, currency, category, description, date, created_at, exchange_rate, base_amount, base_currency) VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?)"
     )
     .bind(&transaction.id)
     .bind(&transaction.user_id)
@@ -36,73 +124,88 @@ pub async fn create_transaction(
     .bind(&transaction.description)
     .bind(&date_str)
     .bind(&created_at_str)
+    .bind(transaction.exchange_rate)
+    .bind(transaction.base_amount)
+    .bind(&transaction.base_currency)
     .execute(&pool)
     .await;
 
     match result {
         Ok(_) => {
-            log::info!("✅ Transaction created successfully: {} {} ({})", transaction.amount, transaction.currency, transaction.id);
-            Ok(Json(json!({
-                "success": true,
-                "data": transaction
-            })))
+            history::record(&pool, EntityKind::Transaction, &transaction.id, &auth_user.user_id, "created", None, &device).await;
+            events::emit(&pool, &auth_user.user_id, "transaction.created", "transaction", &transaction.id, &transaction).await;
+            if let Err(e) = budget_progress::notify_exceeded_budgets(&pool, &auth_user.user_id).await {
+                tracing::error!("Failed to check budgets after transaction {}: {}", transaction.id, e);
+            }
+            if let Err(e) = credit_utilization::notify_utilization(&pool, &auth_user.user_id, &transaction.account_id).await {
+                tracing::error!("Failed to check credit utilization after transaction {}: {}", transaction.id, e);
+            }
+            Ok(ApiResponse::ok(json!(transaction)))
         }
         Err(e) => {
-            log::error!("❌ Failed to create transaction: {}", e);
-            log::error!("Database error details: {:?}", e);
-            log::error!("Raw request data: {:?}", request);
+            tracing::error!("Failed to create transaction: {}", e);
 
             let error_msg = e.to_string();
             if error_msg.contains("UNIQUE constraint failed: transactions.id") {
-                log::warn!("⚠️  Transaction with ID {} already exists", transaction.id);
-                Err(StatusCode::CONFLICT)
+                Err(AppError::Conflict("Transaction already exists".into()))
             } else {
-                Err(StatusCode::INTERNAL_SERVER_ERROR)
+                Err(AppError::Internal("Failed to create transaction".into()))
             }
         }
     }
 }
 
+/// One page of the transactions `user_id` can see, newest first.
+pub async fn transactions_page(pool: &DbPool, user_id: &str, page: &PageQuery) -> Result<Paginated<Transaction>, sqlx::Error> {
+    let transactions = sqlx::query_as::<_, Transaction>(&format!(
+        "SELECT * FROM transactions WHERE {} ORDER BY date DESC, id LIMIT ? OFFSET ?",
+        households::visible_rows_filter()
+    ))
+    .bind(user_id)
+    .bind(user_id)
+    .bind(page.limit() as i64)
+    .bind(page.offset() as i64)
+    .fetch_all(pool)
+    .await?;
+
+    let total = sqlx::query_scalar::<_, i64>(&format!(
+        "SELECT COUNT(*) FROM transactions WHERE {}",
+        households::visible_rows_filter()
+    ))
+    .bind(user_id)
+    .bind(user_id)
+    .fetch_one(pool)
+    .await?;
+
+    Ok(Paginated::new(transactions, total as usize, page))
+}
+
+/// Answers 304 when `If-None-Match` holds the list's current `ETag`.
 pub async fn get_transactions(
     State(pool): State<DbPool>,
     auth_user: AuthUser,
-) -> Result<Json<Value>, StatusCode> {
-    log::info!("📥 GET /transactions - Fetching transactions for user {}", auth_user.user_id);
+    headers: HeaderMap,
+    Query(page): Query<PageQuery>,
+) -> Result<Response, AppError> {
+    let etag = etags::list_etag(&pool, EntityKind::Transaction, &auth_user.user_id).await.map_err(|e| {
+        tracing::error!("Failed to compute the transactions ETag: {}", e);
+        AppError::Internal("Failed to compute the transactions ETag".into())
+    })?;
+    let etag = etags::for_page(&etag, &page);
+    if etags::is_fresh(&headers, &etag) {
+        tracing::info!("Transactions unchanged for user {}", auth_user.user_id);
+        return Ok(etags::not_modified(&etag));
+    }
 
-    let result = sqlx::query(
-        "SELECT id, user_id, account_id, transaction_type, amount, currency, category, description, date, created_at FROM transactions WHERE user_id = ? ORDER BY date DESC"
-    )
-    .bind(&auth_user.user_id)
-    .fetch_all(&pool)
-    .await;
+    let result = transactions_page(&pool, &auth_user.user_id, &page).await;
 
     match result {
-        Ok(rows) => {
-            let transactions: Vec<_> = rows.into_iter().map(|row| {
-                json!({
-                    "id": row.get::<String, _>("id"),
-                    "userId": row.get::<String, _>("user_id"),
-                    "account_id": row.get::<String, _>("account_id"),
-                    "transaction_type": row.get::<String, _>("transaction_type"),
-                    "amount": row.get::<f64, _>("amount"),
-                    "currency": row.get::<String, _>("currency"),
-                    "category": row.get::<Option<String>, _>("category"),
-                    "description": row.get::<Option<String>, _>("description"),
-                    "date": row.get::<String, _>("date"),
-                    "created_at": row.get::<String, _>("created_at")
-                })
-            }).collect();
-
-            log::info!("✅ Found {} transactions", transactions.len());
-            Ok(Json(json!({
-                "success": true,
-                "data": transactions
-            })))
+        Ok(transactions) => {
+            Ok(etags::tagged(&etag, ApiResponse::ok(json!(transactions))))
         }
         Err(e) => {
-            log::error!("❌ Failed to get transactions: {}", e);
-            log::error!("Database error details: {:?}", e);
-            Err(StatusCode::INTERNAL_SERVER_ERROR)
+            tracing::error!("Failed to get transactions: {}", e);
+            Err(AppError::Internal("Failed to get transactions".into()))
         }
     }
 }
@@ -111,48 +214,25 @@ pub async fn get_transaction(
     Path(id): Path<String>,
     State(pool): State<DbPool>,
     auth_user: AuthUser,
-) -> Result<Json<Value>, StatusCode> {
-    log::info!("📥 GET /transactions/{} - Fetching transaction by ID", id);
-
-    let result = sqlx::query(
-        "SELECT id, user_id, account_id, transaction_type, amount, currency, category, description, date, created_at FROM transactions WHERE id = ? AND user_id = ?"
-    )
+) -> Result<ApiResponse, AppError> {
+    let result = sqlx::query_as::<_, Transaction>(&format!(
+        "SELECT * FROM transactions WHERE id = ? AND {}",
+        households::visible_rows_filter()
+    ))
     .bind(&id)
     .bind(&auth_user.user_id)
+    .bind(&auth_user.user_id)
     .fetch_optional(&pool)
     .await;
 
     match result {
-        Ok(Some(row)) => {
-            let amount = row.get::<f64, _>("amount");
-            let currency = row.get::<String, _>("currency");
-            let transaction = json!({
-                "id": row.get::<String, _>("id"),
-                "userId": row.get::<String, _>("user_id"),
-                "account_id": row.get::<String, _>("account_id"),
-                "transaction_type": row.get::<String, _>("transaction_type"),
-                "amount": amount,
-                "currency": currency,
-                "category": row.get::<Option<String>, _>("category"),
-                "description": row.get::<Option<String>, _>("description"),
-                "date": row.get::<String, _>("date"),
-                "created_at": row.get::<String, _>("created_at")
-            });
-
-            log::info!("✅ Found transaction: {} {}", amount, currency);
-            Ok(Json(json!({
-                "success": true,
-                "data": transaction
-            })))
+        Ok(Some(transaction)) => {
+            Ok(ApiResponse::ok(json!(transaction)))
         }
-        Ok(None) => {
-            log::warn!("⚠️  Transaction not found with ID: {}", id);
-            Err(StatusCode::NOT_FOUND)
-        },
+        Ok(None) => Err(AppError::NotFound("Transaction not found".into())),
         Err(e) => {
-            log::error!("❌ Failed to get transaction {}: {}", id, e);
-            log::error!("Database error details: {:?}", e);
-            Err(StatusCode::INTERNAL_SERVER_ERROR)
+            tracing::error!("Failed to get transaction {}: {}", id, e);
+            Err(AppError::Internal("Failed to get transaction".into()))
         }
     }
 }
@@ -161,46 +241,99 @@ pub async fn update_transaction(
     Path(id): Path<String>,
     State(pool): State<DbPool>,
     auth_user: AuthUser,
+    device: ClientDevice,
+    headers: HeaderMap,
     Json(request): Json<UpdateTransactionRequest>,
-) -> Result<Json<Value>, StatusCode> {
-    log::info!("📥 PUT /transactions/{} - Updating transaction", id);
-    log::debug!("Update request: {:?}", request);
+) -> Result<Response, AppError> {
+    request.validate()?;
+
+    let before = history::snapshot(&pool, EntityKind::Transaction, &id, &auth_user.user_id).await;
+    let version = concurrency::expected_version(&headers, request.version)?;
+
+    if let Some(account_id) = &request.account_id {
+        check_account_writable(&pool, account_id, &auth_user.user_id).await?;
+    }
+    if request.amount.is_some() != request.currency.is_some() {
+        let current = sqlx::query_as::<_, (Money, String)>("SELECT amount, currency FROM transactions WHERE id = ? AND user_id = ?")
+            .bind(&id)
+            .bind(&auth_user.user_id)
+            .fetch_optional(&pool)
+            .await
+            .map_err(|e| {
+                tracing::error!("Failed to load transaction {}: {}", id, e);
+                AppError::Internal("Failed to load transaction".into())
+            })?;
+        if let Some((amount, code)) = current {
+            let mut errors = FieldErrors::default();
+            errors.fits_currency("amount", request.amount.unwrap_or(amount), request.currency.as_deref().unwrap_or(&code));
+            errors.into_result()?;
+        }
+    }
+    let rate_changed = request.amount.is_some()
+        || request.currency.is_some()
+        || request.date.is_some()
+        || request.exchange_rate.is_some()
+        || request.base_amount.is_some();
 
     let transaction_type_str = request.transaction_type.map(|t| format!("{:?}", t).to_lowercase());
     let date_str = request.date.map(|d| d.format("%Y-%m-%d %H:%M:%S").to_string());
 
     let result = sqlx::query(
-        "UPDATE transactions SET account_id = COALESCE(?, account_id), transaction_type = COALESCE(?, transaction_type), amount = COALESCE(?, amount), currency = COALESCE(?, currency), category = COALESCE(?, category), description = COALESCE(?, description), date = COALESCE(?, date) WHERE id = ? AND user_id = ?"
+        "UPDATE transactions SET account_id = COALESCE(?, account_id), transaction_type = COALESCE(?, transaction_type), amount = COALESCE(?, amount), currency = COALESCE(?, currency), category = COALESCE(?, category), description = COALESCE(?, description), date = COALESCE(?, date) WHERE id = ? AND user_id = ? AND version = ?"
     )
-    .bind(request.account_id)
+    .bind(&request.account_id)
     .bind(transaction_type_str)
     .bind(request.amount)
-    .bind(request.currency)
-    .bind(request.category)
-    .bind(request.description)
+    .bind(&request.currency)
+    .bind(&request.category)
+    .bind(&request.description)
     .bind(date_str)
     .bind(&id)
     .bind(&auth_user.user_id)
+    .bind(version)
     .execute(&pool)
     .await;
 
     match result {
         Ok(result) => {
             if result.rows_affected() == 0 {
-                log::warn!("⚠️  Transaction not found for update: {}", id);
-                Err(StatusCode::NOT_FOUND)
+                concurrency::rejected_update(&pool, EntityKind::Transaction, &id, &auth_user.user_id, version).await
             } else {
-                log::info!("✅ Transaction updated successfully: {}", id);
-                Ok(Json(json!({
-                    "success": true,
-                    "message": "Transaction updated successfully"
-                })))
+                if rate_changed {
+                    let updated = sqlx::query_as::<_, Transaction>("SELECT * FROM transactions WHERE id = ? AND user_id = ?")
+                        .bind(&id)
+                        .bind(&auth_user.user_id)
+                        .fetch_one(&pool)
+                        .await;
+                    let recaptured = match updated {
+                        Ok(updated) => recapture_base_amount(&pool, &request, &updated).await,
+                        Err(e) => Err(e.into()),
+                    };
+                    if let Err(e) = recaptured {
+                        tracing::error!("Failed to capture exchange rate for transaction {}: {}", id, e);
+                    }
+                }
+                history::record(&pool, EntityKind::Transaction, &id, &auth_user.user_id, "updated", before, &device).await;
+                let updated = sqlx::query_as::<_, Transaction>("SELECT * FROM transactions WHERE id = ? AND user_id = ?")
+                    .bind(&id)
+                    .bind(&auth_user.user_id)
+                    .fetch_optional(&pool)
+                    .await;
+                if let Ok(Some(updated)) = updated {
+                    events::emit(&pool, &auth_user.user_id, "transaction.updated", "transaction", &id, &updated).await;
+                    if let Err(e) = credit_utilization::notify_utilization(&pool, &auth_user.user_id, &updated.account_id).await {
+                        tracing::error!("Failed to check credit utilization after transaction {}: {}", id, e);
+                    }
+                }
+                if let Err(e) = budget_progress::notify_exceeded_budgets(&pool, &auth_user.user_id).await {
+                    tracing::error!("Failed to check budgets after transaction {}: {}", id, e);
+                }
+                Ok(concurrency::updated(&pool, EntityKind::Transaction, &id, "Transaction updated successfully").await)
             }
         }
         Err(e) => {
-            log::error!("❌ Failed to update transaction {}: {}", id, e);
-            log::error!("Database error details: {:?}", e);
-            Err(StatusCode::INTERNAL_SERVER_ERROR)
+            tracing::error!("Failed to update transaction {}: {}", id, e);
+            Err(AppError::Internal("Failed to update transaction".into()))
         }
     }
 }
@@ -209,8 +342,9 @@ pub async fn delete_transaction(
     Path(id): Path<String>,
     State(pool): State<DbPool>,
     auth_user: AuthUser,
-) -> Result<Json<Value>, StatusCode> {
-    log::info!("📥 DELETE /transactions/{} - Deleting transaction", id);
+    device: ClientDevice,
+) -> Result<ApiResponse, AppError> {
+    let before = history::snapshot(&pool, EntityKind::Transaction, &id, &auth_user.user_id).await;
 
     let result = sqlx::query("DELETE FROM transactions WHERE id = ? AND user_id = ?")
         .bind(&id)
@@ -221,20 +355,16 @@ pub async fn delete_transaction(
     match result {
         Ok(result) => {
             if result.rows_affected() == 0 {
-                log::warn!("⚠️  Transaction not found for deletion: {}", id);
-                Err(StatusCode::NOT_FOUND)
+                Err(AppError::NotFound("Transaction not found".into()))
             } else {
-                log::info!("✅ Transaction deleted successfully: {}", id);
-                Ok(Json(json!({
-                    "success": true,
-                    "message": "Transaction deleted successfully"
-                })))
+                history::record(&pool, EntityKind::Transaction, &id, &auth_user.user_id, "deleted", before, &device).await;
+                events::emit(&pool, &auth_user.user_id, "transaction.deleted", "transaction", &id, &json!({ "id": id })).await;
+                Ok(ApiResponse::message("Transaction deleted successfully"))
             }
         }
         Err(e) => {
-            log::error!("❌ Failed to delete transaction {}: {}", id, e);
-            log::error!("Database error details: {:?}", e);
-            Err(StatusCode::INTERNAL_SERVER_ERROR)
+            tracing::error!("Failed to delete transaction {}: {}", id, e);
+            Err(AppError::Internal("Failed to delete transaction".into()))
         }
     }
 }
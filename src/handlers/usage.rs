@@ -0,0 +1,50 @@
+use axum::extract::State;
+use serde_json::json;
+
+use crate::services::quotas;
+use crate::services::usage::{self, USAGE_WINDOW_DAYS};
+use crate::services::DbPool;
+use crate::middleware::auth::AuthUser;
+use crate::utils::response::ApiResponse;
+use crate::utils::error::AppError;
+
+pub async fn get_my_usage(
+    State(pool): State<DbPool>,
+    auth_user: AuthUser,
+) -> Result<ApiResponse, AppError> {
+    let internal_error = |e: anyhow::Error| {
+        tracing::error!("Failed to load API usage: {}", e);
+        AppError::Internal("Failed to load API usage".into())
+    };
+    let by_endpoint = usage::usage_by_endpoint(&pool, &auth_user.user_id).await.map_err(internal_error)?;
+    let by_device = usage::usage_by_device(&pool, &auth_user.user_id).await.map_err(internal_error)?;
+    let daily = usage::usage_by_day(&pool, &auth_user.user_id).await.map_err(internal_error)?;
+
+    let requests: i64 = daily.iter().map(|d| d.requests).sum();
+    let request_bytes: i64 = daily.iter().map(|d| d.request_bytes).sum();
+    let response_bytes: i64 = daily.iter().map(|d| d.response_bytes).sum();
+
+    Ok(ApiResponse::ok(json!({
+        "periodDays": USAGE_WINDOW_DAYS,
+        "totals": {
+            "requests": requests,
+            "requestBytes": request_bytes,
+            "responseBytes": response_bytes
+        },
+        "byEndpoint": by_endpoint,
+        "byDevice": by_device,
+        "daily": daily
+    })))
+}
+
+pub async fn get_my_quota(
+    State(pool): State<DbPool>,
+    auth_user: AuthUser,
+) -> Result<ApiResponse, AppError> {
+    let status = quotas::status(&pool, &auth_user.user_id).await.map_err(|e| {
+        tracing::error!("Failed to load quota status: {}", e);
+        AppError::Internal("Failed to load quota status".into())
+    })?;
+
+    Ok(ApiResponse::ok(json!(status)))
+}
@@ -1,234 +1,169 @@
 use axum::{
-    extract::State,
-    http::StatusCode,
-    response::Json,
+    extract::{Query, State},
+    http::HeaderMap,
+    response::Response,
 };
-use serde_json::{json, Value};
-use sqlx::Row;
-use crate::models::{Account, Transaction, Loan, Liability, Budget, RecurringTransaction};
+use serde_json::json;
+use crate::models::{Loan, Liability, Budget, RecurringTransaction, SavingsGoal, Category, CategoryQuery};
+use crate::services::{budget_progress, etags};
+use crate::services::history::EntityKind;
 use crate::services::database::DbPool;
 use crate::middleware::AuthUser;
+use crate::middleware::owned;
+use crate::utils::response::ApiResponse;
+use crate::utils::error::AppError;
+use crate::utils::pagination::{PageQuery, Paginated};
+use crate::handlers::account::accounts_page;
+use crate::handlers::transaction::transactions_page;
 
 pub async fn get_user_accounts(
     State(pool): State<DbPool>,
     auth_user: AuthUser,
-) -> Result<Json<Value>, (StatusCode, Json<Value>)> {
-    let accounts = sqlx::query_as::<_, Account>(
-        "SELECT * FROM accounts WHERE user_id = ? ORDER BY created_at DESC",
-    )
-    .bind(&auth_user.user_id)
-    .fetch_all(&pool)
-    .await
-    .map_err(|_| {
-        (
-            StatusCode::INTERNAL_SERVER_ERROR,
-            Json(json!({
-                "error": "Failed to fetch accounts"
-            })),
-        )
-    })?;
-
-    Ok(Json(json!({
-        "accounts": accounts
-    })))
+    headers: HeaderMap,
+    Query(page): Query<PageQuery>,
+) -> Result<Response, AppError> {
+    let etag = etags::list_etag(&pool, EntityKind::Account, &auth_user.user_id).await.map_err(|_| AppError::Internal("Failed to fetch accounts".into()))?;
+    let etag = etags::for_page(&etag, &page);
+    if etags::is_fresh(&headers, &etag) {
+        return Ok(etags::not_modified(&etag));
+    }
+
+    let accounts = accounts_page(&pool, &auth_user.user_id, &page)
+        .await
+        .map_err(|_| AppError::Internal("Failed to fetch accounts".into()))?;
+
+    Ok(etags::tagged(&etag, ApiResponse::ok(json!(accounts))))
 }
 
 pub async fn get_user_transactions(
     State(pool): State<DbPool>,
     auth_user: AuthUser,
-) -> Result<Json<Value>, (StatusCode, Json<Value>)> {
-    let transactions = sqlx::query_as::<_, Transaction>(
-        "SELECT * FROM transactions WHERE user_id = ? ORDER BY date DESC",
-    )
-    .bind(&auth_user.user_id)
-    .fetch_all(&pool)
-    .await
-    .map_err(|_| {
-        (
-            StatusCode::INTERNAL_SERVER_ERROR,
-            Json(json!({
-                "error": "Failed to fetch transactions"
-            })),
-        )
-    })?;
-
-    Ok(Json(json!({
-        "transactions": transactions
-    })))
+    headers: HeaderMap,
+    Query(page): Query<PageQuery>,
+) -> Result<Response, AppError> {
+    let etag = etags::list_etag(&pool, EntityKind::Transaction, &auth_user.user_id).await.map_err(|_| AppError::Internal("Failed to fetch transactions".into()))?;
+    let etag = etags::for_page(&etag, &page);
+    if etags::is_fresh(&headers, &etag) {
+        return Ok(etags::not_modified(&etag));
+    }
+
+    let transactions = transactions_page(&pool, &auth_user.user_id, &page)
+        .await
+        .map_err(|_| AppError::Internal("Failed to fetch transactions".into()))?;
+
+    Ok(etags::tagged(&etag, ApiResponse::ok(json!(transactions))))
 }
 
 pub async fn get_user_loans(
     State(pool): State<DbPool>,
     auth_user: AuthUser,
-) -> Result<Json<Value>, (StatusCode, Json<Value>)> {
-    let loans = sqlx::query_as::<_, Loan>(
-        "SELECT * FROM loans WHERE user_id = ? ORDER BY loan_date DESC",
-    )
-    .bind(&auth_user.user_id)
-    .fetch_all(&pool)
-    .await
-    .map_err(|_| {
-        (
-            StatusCode::INTERNAL_SERVER_ERROR,
-            Json(json!({
-                "error": "Failed to fetch loans"
-            })),
-        )
-    })?;
-
-    Ok(Json(json!({
-        "loans": loans
-    })))
+    Query(page): Query<PageQuery>,
+) -> Result<ApiResponse, AppError> {
+    let loans = owned::page::<Loan>(&pool, &auth_user.user_id, "loan_date DESC", &page)
+        .await
+        .map_err(|_| AppError::Internal("Failed to fetch loans".into()))?;
+
+    Ok(ApiResponse::ok(json!(loans)))
 }
 
 pub async fn get_user_liabilities(
     State(pool): State<DbPool>,
     auth_user: AuthUser,
-) -> Result<Json<Value>, (StatusCode, Json<Value>)> {
-    let liabilities = sqlx::query_as::<_, Liability>(
-        "SELECT * FROM liabilities WHERE user_id = ? ORDER BY due_date ASC",
-    )
-    .bind(&auth_user.user_id)
-    .fetch_all(&pool)
-    .await
-    .map_err(|_| {
-        (
-            StatusCode::INTERNAL_SERVER_ERROR,
-            Json(json!({
-                "error": "Failed to fetch liabilities"
-            })),
-        )
-    })?;
-
-    Ok(Json(json!({
-        "liabilities": liabilities
-    })))
+    Query(page): Query<PageQuery>,
+) -> Result<ApiResponse, AppError> {
+    let liabilities = owned::page::<Liability>(&pool, &auth_user.user_id, "due_date ASC", &page)
+        .await
+        .map_err(|_| AppError::Internal("Failed to fetch liabilities".into()))?;
+
+    Ok(ApiResponse::ok(json!(liabilities)))
 }
 
 pub async fn get_user_budgets(
     State(pool): State<DbPool>,
     auth_user: AuthUser,
-) -> Result<Json<Value>, (StatusCode, Json<Value>)> {
-    let budgets = sqlx::query_as::<_, Budget>(
-        "SELECT * FROM budgets WHERE user_id = ? ORDER BY created_at DESC",
-    )
-    .bind(&auth_user.user_id)
-    .fetch_all(&pool)
-    .await
-    .map_err(|_| {
-        (
-            StatusCode::INTERNAL_SERVER_ERROR,
-            Json(json!({
-                "error": "Failed to fetch budgets"
-            })),
-        )
-    })?;
-
-    Ok(Json(json!({
-        "budgets": budgets
-    })))
+    headers: HeaderMap,
+    Query(page): Query<PageQuery>,
+) -> Result<Response, AppError> {
+    let etag = etags::list_etag(&pool, EntityKind::Budget, &auth_user.user_id).await.map_err(|_| AppError::Internal("Failed to fetch budgets".into()))?;
+    let etag = etags::for_page(&etag, &page);
+    if etags::is_fresh(&headers, &etag) {
+        return Ok(etags::not_modified(&etag));
+    }
+
+    let budgets = owned::page::<Budget>(&pool, &auth_user.user_id, "created_at DESC", &page)
+        .await
+        .map_err(|_| AppError::Internal("Failed to fetch budgets".into()))?;
+
+    let linked = budget_progress::linked_categories_by_budget(&pool, &auth_user.user_id)
+        .await
+        .map_err(|_| AppError::Internal("Failed to fetch budgets".into()))?;
+
+    let budgets = budgets.map(|budget| {
+        let categories = budget.target_categories(linked.get(&budget.id).map(Vec::as_slice).unwrap_or_default());
+        let mut value = json!(budget);
+        value["categories"] = json!(categories);
+        value
+    });
+
+    Ok(etags::tagged(&etag, ApiResponse::ok(json!(budgets))))
 }
 
 pub async fn get_user_savings_goals(
     State(pool): State<DbPool>,
     auth_user: AuthUser,
-) -> Result<Json<Value>, (StatusCode, Json<Value>)> {
-    let rows = sqlx::query(
-        "SELECT id, user_id, name, target_amount, current_amount, currency, target_date, description, account_id, priority, is_completed, created_at, updated_at FROM savings_goals WHERE user_id = ? ORDER BY created_at DESC",
-    )
-    .bind(&auth_user.user_id)
-    .fetch_all(&pool)
-    .await
-    .map_err(|_| {
-        (
-            StatusCode::INTERNAL_SERVER_ERROR,
-            Json(json!({
-                "error": "Failed to fetch savings goals"
-            })),
-        )
-    })?;
-
-    let savings_goals: Vec<_> = rows.into_iter().map(|row| {
-        json!({
-            "id": row.get::<String, _>("id"),
-            "userId": row.get::<String, _>("user_id"),
-            "name": row.get::<String, _>("name"),
-            "targetAmount": row.get::<f64, _>("target_amount"),
-            "currentAmount": row.get::<f64, _>("current_amount"),
-            "currency": row.get::<String, _>("currency"),
-            "targetDate": row.get::<String, _>("target_date"),
-            "description": row.get::<Option<String>, _>("description"),
-            "accountId": row.get::<Option<String>, _>("account_id"),
-            "priority": row.get::<String, _>("priority"),
-            "isCompleted": row.get::<bool, _>("is_completed"),
-            "createdAt": row.get::<String, _>("created_at"),
-            "updatedAt": row.get::<String, _>("updated_at")
-        })
-    }).collect();
-
-    Ok(Json(json!({
-        "savings_goals": savings_goals
-    })))
+    Query(page): Query<PageQuery>,
+) -> Result<ApiResponse, AppError> {
+    let savings_goals = owned::page::<SavingsGoal>(&pool, &auth_user.user_id, "created_at DESC", &page)
+        .await
+        .map_err(|_| AppError::Internal("Failed to fetch savings goals".into()))?;
+
+    Ok(ApiResponse::ok(json!(savings_goals)))
 }
 
 pub async fn get_user_categories(
     State(pool): State<DbPool>,
     auth_user: AuthUser,
-) -> Result<Json<Value>, (StatusCode, Json<Value>)> {
-    let rows = sqlx::query(
-        "SELECT id, name, category_type, icon, color, is_default, created_at, user_id, updated_at FROM categories WHERE user_id = ? OR user_id = '' ORDER BY created_at DESC",
+    Query(query): Query<CategoryQuery>,
+    Query(page): Query<PageQuery>,
+) -> Result<ApiResponse, AppError> {
+    let include_archived = query.include_archived.unwrap_or(false);
+    let categories = sqlx::query_as::<_, Category>(
+        "SELECT * FROM (
+             SELECT *, EXISTS (SELECT 1 FROM category_archives a WHERE a.user_id = ? AND a.category_id = categories.id) AS is_archived
+             FROM categories WHERE user_id = ? OR user_id = ''
+         ) WHERE ? OR NOT is_archived ORDER BY created_at DESC, id LIMIT ? OFFSET ?",
     )
     .bind(&auth_user.user_id)
+    .bind(&auth_user.user_id)
+    .bind(include_archived)
+    .bind(page.limit() as i64)
+    .bind(page.offset() as i64)
     .fetch_all(&pool)
     .await
-    .map_err(|_| {
-        (
-            StatusCode::INTERNAL_SERVER_ERROR,
-            Json(json!({
-                "error": "Failed to fetch categories"
-            })),
-        )
-    })?;
-
-    let categories: Vec<_> = rows.into_iter().map(|row| {
-        json!({
-            "id": row.get::<String, _>("id"),
-            "name": row.get::<String, _>("name"),
-            "categoryType": row.get::<String, _>("category_type"),
-            "icon": row.get::<String, _>("icon"),
-            "color": row.get::<String, _>("color"),
-            "isDefault": row.get::<bool, _>("is_default"),
-            "createdAt": row.get::<String, _>("created_at"),
-            "userId": row.get::<String, _>("user_id"),
-            "updatedAt": row.get::<Option<String>, _>("updated_at")
-        })
-    }).collect();
-
-    Ok(Json(json!({
-        "categories": categories
-    })))
+    .map_err(|_| AppError::Internal("Failed to fetch categories".into()))?;
+
+    let total = sqlx::query_scalar::<_, i64>(
+        "SELECT COUNT(*) FROM categories WHERE (user_id = ? OR user_id = '')
+         AND (? OR NOT EXISTS (SELECT 1 FROM category_archives a WHERE a.user_id = ? AND a.category_id = categories.id))",
+    )
+    .bind(&auth_user.user_id)
+    .bind(include_archived)
+    .bind(&auth_user.user_id)
+    .fetch_one(&pool)
+    .await
+    .map_err(|_| AppError::Internal("Failed to fetch categories".into()))?;
+
+    Ok(ApiResponse::ok(json!(Paginated::new(categories, total as usize, &page))))
 }
 
 pub async fn get_user_recurring_transactions(
     State(pool): State<DbPool>,
     auth_user: AuthUser,
-) -> Result<Json<Value>, (StatusCode, Json<Value>)> {
-    let recurring_transactions = sqlx::query_as::<_, RecurringTransaction>(
-        "SELECT * FROM recurring_transactions WHERE user_id = ? ORDER BY created_at DESC",
-    )
-    .bind(&auth_user.user_id)
-    .fetch_all(&pool)
-    .await
-    .map_err(|_| {
-        (
-            StatusCode::INTERNAL_SERVER_ERROR,
-            Json(json!({
-                "error": "Failed to fetch recurring transactions"
-            })),
-        )
-    })?;
-
-    Ok(Json(json!({
-        "recurring_transactions": recurring_transactions
-    })))
+    Query(page): Query<PageQuery>,
+) -> Result<ApiResponse, AppError> {
+    let recurring_transactions = owned::page::<RecurringTransaction>(&pool, &auth_user.user_id, "created_at DESC", &page)
+        .await
+        .map_err(|_| AppError::Internal("Failed to fetch recurring transactions".into()))?;
+
+    Ok(ApiResponse::ok(json!(recurring_transactions)))
 }
\ No newline at end of file
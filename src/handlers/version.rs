@@ -0,0 +1,86 @@
+use axum::extract::State;
+use chrono::{DateTime, SecondsFormat, Utc};
+use serde_json::json;
+
+use crate::config;
+use crate::services::database::{self, DbPool};
+use crate::utils::error::AppError;
+use crate::utils::response::ApiResponse;
+
+/// This build's semver; clients compare it with the oldest backend they work with.
+pub const VERSION: &str = env!("CARGO_PKG_VERSION");
+
+/// The commit this build came from, set by `build.rs`.
+pub fn commit() -> &'static str {
+    option_env!("GIT_COMMIT").unwrap_or("unknown")
+}
+
+/// When this build was made, set by `build.rs`.
+pub fn built_at() -> Option<DateTime<Utc>> {
+    option_env!("BUILD_TIMESTAMP")
+        .and_then(|timestamp| timestamp.parse().ok())
+        .and_then(|timestamp| DateTime::from_timestamp(timestamp, 0))
+}
+
+/// Optional cargo features compiled into this build.
+fn compiled_features() -> Vec<&'static str> {
+    let mut features = Vec::new();
+    if cfg!(feature = "redis") {
+        features.push("redis");
+    }
+    features
+}
+
+/// Background features switched on in the configuration.
+fn enabled_features() -> Vec<&'static str> {
+    let features = &config::current().features;
+    let mut enabled = Vec::new();
+    if features.background_jobs {
+        enabled.push("background_jobs");
+    }
+    for (name, on) in [
+        ("email", features.email),
+        ("push", features.push),
+        ("webhooks", features.webhooks),
+        ("exchange_rates", features.exchange_rates),
+        ("market_prices", features.market_prices),
+    ] {
+        if features.enabled(on) {
+            enabled.push(name);
+        }
+    }
+    enabled
+}
+
+async fn migration_state(pool: &DbPool) -> anyhow::Result<(Option<i64>, Vec<String>)> {
+    Ok((database::migration_level(pool).await?, database::pending_migrations(pool).await?))
+}
+
+/// What this server is: its version split into parts for comparing, the commit and time it
+/// was built, its features, and how far the database schema has been migrated.
+pub async fn get_version(State(pool): State<DbPool>) -> Result<ApiResponse, AppError> {
+    let (applied, pending) = migration_state(&pool).await.map_err(|e| {
+        tracing::error!("Failed to read the migration level: {}", e);
+        AppError::Internal("Failed to read the migration level".into())
+    })?;
+    let latest = database::MIGRATOR.iter().map(|migration| migration.version).max();
+
+    let mut parts = VERSION.split('.').map(|part| part.parse::<u64>().ok());
+    Ok(ApiResponse::ok(json!({
+        "version": VERSION,
+        "major": parts.next().flatten(),
+        "minor": parts.next().flatten(),
+        "patch": parts.next().flatten(),
+        "commit": commit(),
+        "builtAt": built_at().map(|time| time.to_rfc3339_opts(SecondsFormat::Secs, true)),
+        "features": {
+            "compiled": compiled_features(),
+            "enabled": enabled_features()
+        },
+        "migrations": {
+            "applied": applied,
+            "latest": latest,
+            "pending": pending
+        }
+    })))
+}
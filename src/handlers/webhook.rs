@@ -0,0 +1,216 @@
+use axum::{
+    extract::{Path, Query, State},
+    response::Json,
+};
+use chrono::Utc;
+use serde_json::{json, Value};
+use uuid::Uuid;
+
+use crate::models::{CreateWebhookRequest, UpdateWebhookRequest, Webhook, WebhookDelivery};
+use crate::services::{events, webhooks, DbPool};
+use crate::middleware::auth::AuthUser;
+use crate::middleware::owned::{self, Owned};
+use crate::utils::datetime::format_db_datetime;
+use crate::utils::response::ApiResponse;
+use crate::utils::error::AppError;
+use crate::utils::pagination::{PageQuery, Paginated};
+use crate::utils::validation::{FieldErrors, Validate};
+
+/// Requested event types as the stored JSON array.
+fn event_types_json(events: &[String]) -> String {
+    json!(events).to_string()
+}
+
+/// Turns away URLs whose host resolves to a loopback, private or link-local address.
+async fn check_destination(url: &str) -> Result<(), AppError> {
+    let mut errors = FieldErrors::default();
+    errors.check(webhooks::is_public_url(url).await, "url", "must resolve to a public address");
+    errors.into_result()
+}
+
+fn generate_secret() -> String {
+    format!("whsec_{}", Uuid::new_v4().simple())
+}
+
+fn webhook_json(webhook: &Webhook) -> Value {
+    let mut data = json!(webhook);
+    data["events"] = json!(webhook.events());
+    data
+}
+
+pub async fn create_webhook(
+    State(pool): State<DbPool>,
+    auth_user: AuthUser,
+    Json(request): Json<CreateWebhookRequest>,
+) -> Result<ApiResponse, AppError> {
+    request.validate()?;
+    check_destination(request.url.trim()).await?;
+
+    let url = request.url.trim();
+    let schema_version = request.schema_version.unwrap_or(events::CURRENT_SCHEMA_VERSION);
+    let event_types = event_types_json(&request.events.unwrap_or_default());
+    let secret = request.secret.filter(|s| !s.is_empty()).unwrap_or_else(generate_secret);
+    let now = format_db_datetime(Utc::now());
+
+    let webhook = sqlx::query_as::<_, Webhook>(
+        r#"
+        INSERT INTO webhooks (id, user_id, url, secret, event_types, schema_version, is_active, created_at, updated_at)
+        VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?)
+        RETURNING *
+        "#,
+    )
+    .bind(Uuid::new_v4().to_string())
+    .bind(&auth_user.user_id)
+    .bind(url)
+    .bind(&secret)
+    .bind(&event_types)
+    .bind(schema_version as i64)
+    .bind(request.is_active.unwrap_or(true))
+    .bind(&now)
+    .bind(&now)
+    .fetch_one(&pool)
+    .await
+    .map_err(|e| {
+        tracing::error!("Failed to create webhook: {}", e);
+        AppError::Internal("Failed to create webhook".into())
+    })?;
+
+    tracing::info!("Webhook created: {} -> {}", webhook.id, webhook.url);
+    let mut data = webhook_json(&webhook);
+    data["secret"] = json!(webhook.secret);
+    Ok(ApiResponse::ok(json!(data)))
+}
+
+pub async fn get_webhooks(
+    State(pool): State<DbPool>,
+    auth_user: AuthUser,
+    Query(page): Query<PageQuery>,
+) -> Result<ApiResponse, AppError> {
+    let webhooks = owned::page::<Webhook>(&pool, &auth_user.user_id, "created_at DESC", &page)
+        .await
+        .map_err(|e| {
+            tracing::error!("Failed to get webhooks: {}", e);
+            AppError::Internal("Failed to get webhooks".into())
+        })?;
+
+    Ok(ApiResponse::ok(json!(webhooks.map(|webhook| webhook_json(&webhook)))))
+}
+
+pub async fn get_webhook(Owned { resource: webhook, .. }: Owned<Webhook>) -> ApiResponse {
+    ApiResponse::ok(json!(webhook_json(&webhook)))
+}
+
+pub async fn update_webhook(
+    Path(id): Path<String>,
+    State(pool): State<DbPool>,
+    auth_user: AuthUser,
+    Json(request): Json<UpdateWebhookRequest>,
+) -> Result<ApiResponse, AppError> {
+    request.validate()?;
+    if let Some(url) = &request.url {
+        check_destination(url.trim()).await?;
+    }
+
+    let url = request.url.as_deref().map(str::trim);
+    let event_types = request.events.as_deref().map(event_types_json);
+    let secret = request.secret.filter(|s| !s.is_empty());
+
+    let webhook = sqlx::query_as::<_, Webhook>(
+        r#"
+        UPDATE webhooks SET
+            url = COALESCE(?, url),
+            secret = COALESCE(?, secret),
+            event_types = COALESCE(?, event_types),
+            schema_version = COALESCE(?, schema_version),
+            is_active = COALESCE(?, is_active),
+            updated_at = ?
+        WHERE id = ? AND user_id = ?
+        RETURNING *
+        "#,
+    )
+    .bind(url)
+    .bind(&secret)
+    .bind(event_types)
+    .bind(request.schema_version.map(|v| v as i64))
+    .bind(request.is_active)
+    .bind(format_db_datetime(Utc::now()))
+    .bind(&id)
+    .bind(&auth_user.user_id)
+    .fetch_optional(&pool)
+    .await
+    .map_err(|e| {
+        tracing::error!("Failed to update webhook {}: {}", id, e);
+        AppError::Internal("Failed to update webhook".into())
+    })?
+    .ok_or_else(|| AppError::NotFound("Webhook not found".into()))?;
+
+    tracing::info!("Webhook updated successfully: {}", id);
+    let mut data = webhook_json(&webhook);
+    if secret.is_some() {
+        data["secret"] = json!(webhook.secret);
+    }
+    Ok(ApiResponse::ok(json!(data)))
+}
+
+pub async fn delete_webhook(
+    Path(id): Path<String>,
+    State(pool): State<DbPool>,
+    auth_user: AuthUser,
+) -> Result<ApiResponse, AppError> {
+    let result = sqlx::query("DELETE FROM webhooks WHERE id = ? AND user_id = ?")
+        .bind(&id)
+        .bind(&auth_user.user_id)
+        .execute(&pool)
+        .await
+        .map_err(|e| {
+            tracing::error!("Failed to delete webhook {}: {}", id, e);
+            AppError::Internal("Failed to delete webhook".into())
+        })?;
+
+    if result.rows_affected() == 0 {
+        tracing::warn!("Webhook not found: {}", id);
+        return Err(AppError::NotFound("Webhook not found".into()));
+    }
+
+    Ok(ApiResponse::message("Webhook deleted successfully"))
+}
+
+pub async fn get_webhook_deliveries(
+    Path(id): Path<String>,
+    State(pool): State<DbPool>,
+    auth_user: AuthUser,
+    Query(page): Query<PageQuery>,
+) -> Result<ApiResponse, AppError> {
+    let deliveries = sqlx::query_as::<_, WebhookDelivery>(
+        r#"
+        SELECT d.* FROM webhook_deliveries d
+        JOIN webhooks w ON w.id = d.webhook_id
+        WHERE d.webhook_id = ? AND w.user_id = ?
+        ORDER BY d.created_at DESC, d.rowid DESC
+        LIMIT ? OFFSET ?
+        "#,
+    )
+    .bind(&id)
+    .bind(&auth_user.user_id)
+    .bind(page.limit() as i64)
+    .bind(page.offset() as i64)
+    .fetch_all(&pool)
+    .await
+    .map_err(|e| {
+        tracing::error!("Failed to get deliveries for webhook {}: {}", id, e);
+        AppError::Internal("Failed to get deliveries for webhook".into())
+    })?;
+    let total = sqlx::query_scalar::<_, i64>(
+        "SELECT COUNT(*) FROM webhook_deliveries d JOIN webhooks w ON w.id = d.webhook_id WHERE d.webhook_id = ? AND w.user_id = ?"
+    )
+    .bind(&id)
+    .bind(&auth_user.user_id)
+    .fetch_one(&pool)
+    .await
+    .map_err(|e| {
+        tracing::error!("Failed to count deliveries for webhook {}: {}", id, e);
+        AppError::Internal("Failed to get deliveries for webhook".into())
+    })?;
+
+    Ok(ApiResponse::ok(json!(Paginated::new(deliveries, total as usize, &page))))
+}
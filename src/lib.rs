@@ -0,0 +1,8 @@
+pub mod app;
+pub mod cli;
+pub mod config;
+pub mod models;
+pub mod handlers;
+pub mod services;
+pub mod middleware;
+pub mod utils;
@@ -1,139 +1,138 @@
-use axum::{
-    routing::{get, post, put, delete},
-    Router,
-    http::{Method, HeaderValue},
-};
-use tower_http::cors::{CorsLayer, Any};
-use tower_http::trace::TraceLayer;
-use std::net::SocketAddr;
-
-mod models;
-mod handlers;
-mod services;
-mod middleware;
-mod utils;
-
-use handlers::{
-    account::{create_account, get_accounts, get_account, update_account, delete_account},
-    // category::{create_category, get_categories, get_category, update_category, delete_category},
-    transaction::{create_transaction, get_transactions, get_transaction, update_transaction, delete_transaction},
-    liability::{create_liability, get_liabilities, get_liability, update_liability, delete_liability},
-    loan::{create_loan, get_loans, get_loan, update_loan, delete_loan},
-    savings_goal::{create_savings_goal, get_savings_goals, get_savings_goal, update_savings_goal, delete_savings_goal},
-    budget::{create_budget, get_budgets, get_budget, update_budget, delete_budget},
-    recurring_transaction::{create_recurring_transaction, get_recurring_transactions, get_recurring_transaction, update_recurring_transaction, delete_recurring_transaction},
-    auth::{signup, login, signin},
-    user_data::{get_user_accounts, get_user_transactions, get_user_loans, get_user_liabilities, get_user_budgets, get_user_savings_goals, get_user_categories, get_user_recurring_transactions},
-    preference::{get_preferences, update_preferences},
-};
+use std::time::Duration;
+
+use clap::Parser;
+
+use personal_manager_backend::cli::{self, Cli, Command, ServeArgs};
+use personal_manager_backend::config::{self, Config, DEVELOPMENT_JWT_SECRET};
+use personal_manager_backend::services::scheduler::Scheduler;
+use personal_manager_backend::services::DbPool;
+use personal_manager_backend::utils::logging;
+use personal_manager_backend::{app, services};
+
+/// How long in-flight requests may take to finish once shutdown starts.
+const REQUEST_DRAIN_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// How long background jobs may take to finish the run they are in.
+const JOB_DRAIN_TIMEOUT: Duration = Duration::from_secs(20);
 
 #[tokio::main]
 async fn main() {
-    // Initialize logger with different levels
-    env_logger::Builder::from_env(env_logger::Env::default().default_filter_or("info"))
-        .format_timestamp_secs()
-        .init();
+    let cli = Cli::parse();
+
+    // Settings come first: they pick the log level
+    let config = match Config::load() {
+        Ok(config) => config,
+        Err(e) => {
+            eprintln!("❌ {:#}", e);
+            std::process::exit(1);
+        }
+    };
 
-    log::info!("🚀 Starting Personal Manager Backend Server...");
-    log::info!("📊 Log level: {}", log::max_level());
+    // Initialize logging
+    logging::init(&config.log);
+    let config = config::init(config);
 
     // Initialize database
-    let database_url = std::env::var("DATABASE_URL").unwrap_or_else(|_| "sqlite:./personal_manager.db".to_string());
-    log::info!("🗄️  Initializing database: {}", database_url);
-
-    let pool = services::database::init_db(&database_url).await.expect("Failed to initialize database");
-
-    // Create tables
-    log::info!("🔧 Creating database tables...");
-    services::database::create_tables(&pool).await.expect("Failed to create tables");
-
-    // Configure CORS for Flutter development
-    let cors = CorsLayer::new()
-        .allow_origin(Any)
-        .allow_methods([Method::GET, Method::POST, Method::PUT, Method::DELETE, Method::OPTIONS])
-        .allow_headers(Any);
-
-    let app = Router::new()
-        // Root route
-        .route("/", get(|| async {
-            serde_json::json!({
-                "message": "Personal Manager Backend API",
-                "version": "1.0.0",
-                "endpoints": {
-                    "auth": {
-                        "signup": "/auth/signup",
-                        "login": "/auth/login",
-                        "signin": "/auth/signin"
-                    },
-                    "user_data": {
-                        "accounts": "/api/accounts",
-                        "transactions": "/api/transactions",
-                        "loans": "/api/loans",
-                        "liabilities": "/api/liabilities"
-                    },
-                    "accounts": "/accounts",
-                    "transactions": "/transactions",
-                    "liabilities": "/liabilities",
-                    "loans": "/loans",
-                    "health": "/health"
-                }
-            }).to_string()
-        }))
-
-        // Authentication routes
-        .route("/auth/signup", post(signup))
-        .route("/auth/login", post(login))
-        .route("/auth/signin", post(signin))
-
-        // User-specific API routes (requires authentication)
-        .route("/api/accounts", get(get_user_accounts))
-        .route("/api/transactions", get(get_user_transactions))
-        .route("/api/loans", get(get_user_loans))
-        .route("/api/liabilities", get(get_user_liabilities))
-        .route("/api/budgets", get(get_user_budgets))
-        .route("/api/savings_goals", get(get_user_savings_goals))
-        .route("/api/categories", get(get_user_categories))
-        .route("/api/recurring_transactions", get(get_user_recurring_transactions))
-
-        // Account routes (all require authentication)
-        .route("/accounts", post(create_account).get(get_accounts))
-        .route("/accounts/:id", get(get_account).put(update_account).delete(delete_account))
-        // Transaction routes (all require authentication)
-        .route("/transactions", post(create_transaction).get(get_transactions))
-        .route("/transactions/:id", get(get_transaction).put(update_transaction).delete(delete_transaction))
-        // Liability routes (all require authentication)
-        .route("/liabilities", post(create_liability).get(get_liabilities))
-        .route("/liabilities/:id", get(get_liability).put(update_liability).delete(delete_liability))
-        // Loan routes (all require authentication)
-        .route("/loans", post(create_loan).get(get_loans))
-        .route("/loans/:id", get(get_loan).put(update_loan).delete(delete_loan))
-        // Savings goal routes (all require authentication)
-        .route("/savings-goals", post(create_savings_goal).get(get_savings_goals))
-        .route("/savings-goals/:id", get(get_savings_goal).put(update_savings_goal).delete(delete_savings_goal))
-        // Budget routes (all require authentication)
-        .route("/budgets", post(create_budget).get(get_budgets))
-        .route("/budgets/:id", get(get_budget).put(update_budget).delete(delete_budget))
-        // Recurring transaction routes (all require authentication)
-        .route("/recurring_transactions", post(create_recurring_transaction).get(get_recurring_transactions))
-        .route("/recurring_transactions/:id", get(get_recurring_transaction).put(update_recurring_transaction).delete(delete_recurring_transaction))
-
-        // Preference routes (requires authentication)
-        .route("/api/preferences", get(get_preferences).put(update_preferences))
-
-        // Health check
-        .route("/health", get(|| async { "OK" }))
-
-        .layer(cors)
-        .layer(TraceLayer::new_for_http())
-        .with_state(pool);
-
-    let addr = SocketAddr::from(([0, 0, 0, 0], 3000));
+    tracing::info!("🗄️  Initializing database: {}", config.database.url);
+    let pool = match services::database::init_db(&config.database).await {
+        Ok(pool) => pool,
+        Err(e) => {
+            eprintln!("❌ Failed to open the database: {:#}", e);
+            std::process::exit(1);
+        }
+    };
+
+    let command = cli.command.unwrap_or(Command::Serve(cli.serve));
+    let result = run(&pool, config, command).await;
+    pool.close().await;
+
+    if let Err(e) = result {
+        eprintln!("❌ {:#}", e);
+        std::process::exit(1);
+    }
+}
+
+async fn run(pool: &DbPool, config: &Config, command: Command) -> anyhow::Result<()> {
+    // Backups skip migrating, so one can be taken before a risky upgrade
+    if !matches!(command, Command::Backup(_)) {
+        services::database::migrate(pool).await?;
+    }
+
+    match command {
+        Command::Serve(args) => serve(pool.clone(), config, args).await?,
+        Command::Migrate => {}
+        Command::CreateAdmin(args) => cli::create_admin(pool, args).await?,
+        Command::ExportUser(args) => cli::export_user(pool, args).await?,
+        Command::Backup(args) => cli::backup(pool, args).await?,
+    }
+    Ok(())
+}
+
+/// Runs the HTTP server and background jobs until a shutdown signal, then drains both.
+async fn serve(pool: DbPool, config: &Config, args: ServeArgs) -> anyhow::Result<()> {
+    tracing::info!("🚀 Starting Personal Manager Backend Server...");
+    tracing::info!("📊 Log level: {}", config.log.level);
+    if config.jwt.secret == DEVELOPMENT_JWT_SECRET {
+        tracing::warn!("🔑 JWT_SECRET is not set; tokens are signed with the development secret");
+    }
+
+    let cache = services::cache::init(&config.cache)
+        .await
+        .map_err(|e| anyhow::anyhow!("can't connect to the cache at cache.redis_url: {}", e))?;
+    tracing::info!("🧠 Cache: {}", cache.backend_name());
+    let storage = services::storage::init(&config.storage);
+    tracing::info!("📁 Storage: {} ({})", storage.backend_name(), config.storage.dir.display());
+
+    // Demo data for client development
+    if args.seed_demo {
+        match services::seed::seed_demo(&pool).await {
+            Ok(true) => tracing::info!(
+                "🌱 Seeded demo user {} (password: {})",
+                services::seed::DEMO_EMAIL,
+                services::seed::DEMO_PASSWORD
+            ),
+            Ok(false) => tracing::info!("🌱 Demo user {} already exists, not seeding", services::seed::DEMO_EMAIL),
+            Err(e) => tracing::error!("Failed to seed demo data: {}", e),
+        }
+    }
+
+    // Start background jobs
+    let shutdown = services::shutdown::Coordinator::new();
+    let mut scheduler = Scheduler::new(pool.clone());
+    // Every job is scheduled; the features in effect decide on each tick whether it runs
+    services::category_model::schedule_training_job(&mut scheduler);
+    services::budget_rollover::schedule_period_close_job(&mut scheduler);
+    services::recurring::schedule_recurring_job(&mut scheduler);
+    services::usage::schedule_usage_flush_job(&mut scheduler);
+    services::usage::schedule_usage_prune_job(&mut scheduler);
+    services::sessions::schedule_session_sweep_job(&mut scheduler);
+    services::card_statements::schedule_statement_job(&mut scheduler);
+    services::reminders::schedule_reminder_jobs(&mut scheduler);
+    services::notifications::schedule_notification_prune_job(&mut scheduler);
+    services::maintenance::schedule_maintenance_jobs(&mut scheduler);
+    services::push::schedule_push_delivery_job(&mut scheduler, &config.push);
+    services::mailer::schedule_mail_delivery_job(&mut scheduler, &config.mail);
+    services::webhooks::schedule_webhook_delivery_job(&mut scheduler);
+    services::currency::schedule_exchange_rate_job(&mut scheduler, &config.market_data);
+    services::market_prices::schedule_price_refresh_job(&mut scheduler, &config.market_data);
+    if !config.features.background_jobs {
+        tracing::warn!("⏸️  Background jobs paused by FEATURE_BACKGROUND_JOBS until it is turned on and reloaded");
+    }
+    scheduler.start(&shutdown);
+
+    // SIGHUP reloads log level, quotas, CORS origins and features without dropping connections
+    tokio::spawn(services::reload::reload_on_hangup());
+
+    let app = app::router(pool.clone());
+
+    let addr = config.server.bind_address().expect("server address was validated on load");
     println!("🚀 Server starting...");
-    println!("📡 Server running on http://localhost:3000");
-    println!("📡 Server running on http://0.0.0.0:3000");
+    println!("📡 Server running on http://{}", addr);
     println!("📋 Available endpoints:");
     println!("   GET  /              - API info");
     println!("   GET  /health        - Health check");
+    println!("   GET  /health/live   - Liveness probe");
+    println!("   GET  /health/ready  - Readiness probe (database, migrations, build)");
     println!("   POST /auth/signup   - Sign up");
     println!("   POST /auth/login    - Login");
     println!("   POST /auth/signin   - Sign in/up");
@@ -142,12 +141,46 @@ async fn main() {
     println!("   CRUD /loans         - Loan management");
     println!("   CRUD /liabilities   - Liability management");
     println!("   GET  /api/*         - User data download");
+    println!("   WS   /ws            - Live updates");
+    println!("   SSE  /api/events    - Live updates as Server-Sent Events");
     println!("   🔒 All CRUD endpoints require authentication");
-    println!("   🌐 CORS enabled for all origins");
+    if config.cors.allows_any_origin() {
+        println!("   🌐 CORS enabled for all origins");
+    } else {
+        println!("   🌐 CORS enabled for {}", config.cors.allowed_origins.join(", "));
+    }
     println!("✅ Ready to accept connections!");
 
-    hyper::Server::bind(&addr)
+    let server = hyper::Server::bind(&addr)
         .serve(app.into_make_service())
-        .await
-        .unwrap();
+        .with_graceful_shutdown(async {
+            services::shutdown::signal().await;
+            tracing::info!("🛑 Shutting down: no longer accepting connections");
+            shutdown.begin();
+            services::live::close();
+        });
+
+    // Open requests get a grace period, in case one hangs
+    let grace_period = async {
+        shutdown.stopping().await;
+        tokio::time::sleep(REQUEST_DRAIN_TIMEOUT).await;
+    };
+    tokio::select! {
+        result = server => {
+            if let Err(e) = result {
+                tracing::error!("Server error: {}", e);
+            }
+        }
+        _ = grace_period => tracing::warn!("⏱️  Requests still open after {:?}, closing them", REQUEST_DRAIN_TIMEOUT),
+    }
+
+    if !shutdown.drain(JOB_DRAIN_TIMEOUT).await {
+        tracing::warn!("⏱️  Background jobs still running after {:?}, stopping anyway", JOB_DRAIN_TIMEOUT);
+    }
+    // Requests counted since the last flush would otherwise be lost
+    if let Err(e) = services::usage::flush(&pool).await {
+        tracing::error!("Failed to write API usage on shutdown: {}", e);
+    }
+    tracing::info!("👋 Stopped, goodbye");
+    Ok(())
 }
@@ -0,0 +1,45 @@
+use std::cell::RefCell;
+use std::time::Instant;
+
+use axum::{body::HttpBody, http::Request, middleware::Next, response::Response};
+
+tokio::task_local! {
+    /// The user the request authenticated as, once the auth extractor has run.
+    static SIGNED_IN_USER: RefCell<Option<String>>;
+}
+
+/// Logs one line per request once it is answered: method, path, status, user, response size
+/// and duration. Streaming responses, whose size isn't known up front, leave `bytes` out.
+pub async fn access_log<B>(request: Request<B>, next: Next<B>) -> Response {
+    let started = Instant::now();
+    let method = request.method().clone();
+    let path = request.uri().path().to_string();
+
+    let (response, user_id) = SIGNED_IN_USER
+        .scope(RefCell::new(None), async {
+            let response = next.run(request).await;
+            (response, SIGNED_IN_USER.with(|user| user.borrow().clone()))
+        })
+        .await;
+
+    let status = response.status().as_u16();
+    let bytes = response.body().size_hint().exact();
+    let user_id = user_id.as_deref().unwrap_or("-");
+    let duration_ms = (started.elapsed().as_secs_f64() * 10_000.0).round() / 10.0;
+    tracing::info!(
+        target: "access_log",
+        %method, path, status, user_id, bytes, duration_ms,
+        "{} {} {} {:.1}ms", method, path, status, duration_ms,
+    );
+    response
+}
+
+/// Names the signed-in user in the current request's access log line.
+pub fn record_user(user_id: &str) {
+    let _ = SIGNED_IN_USER.try_with(|user| *user.borrow_mut() = Some(user_id.to_string()));
+}
+
+/// The user the current request signed in as, if any.
+pub fn current_user() -> Option<String> {
+    SIGNED_IN_USER.try_with(|user| user.borrow().clone()).ok().flatten()
+}
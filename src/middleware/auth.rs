@@ -1,10 +1,12 @@
 use axum::{
-    extract::FromRequestParts,
-    http::{request::Parts, StatusCode},
-    response::Json,
+    extract::{FromRef, FromRequestParts},
+    http::{request::Parts},
 };
-use serde_json::json;
+use crate::middleware::{access_log, request_span};
+use crate::services::database::DbPool;
+use crate::services::sessions::{self, SessionStatus, IDLE_TIMEOUT_REASON};
 use crate::utils::jwt::verify_jwt;
+use crate::utils::error::AppError;
 
 pub struct AuthUser {
     pub user_id: String,
@@ -13,50 +15,89 @@ pub struct AuthUser {
 #[axum::async_trait]
 impl<S> FromRequestParts<S> for AuthUser
 where
+    DbPool: FromRef<S>,
     S: Send + Sync,
 {
-    type Rejection = (StatusCode, Json<serde_json::Value>);
+    type Rejection = AppError;
 
-    async fn from_request_parts(parts: &mut Parts, _state: &S) -> Result<Self, Self::Rejection> {
+    async fn from_request_parts(parts: &mut Parts, state: &S) -> Result<Self, Self::Rejection> {
         // Get Authorization header
         let auth_header = parts
             .headers
             .get("Authorization")
             .and_then(|header| header.to_str().ok())
-            .ok_or_else(|| {
-                (
-                    StatusCode::UNAUTHORIZED,
-                    Json(json!({
-                        "error": "Missing Authorization header"
-                    })),
-                )
-            })?;
+            .ok_or_else(|| AppError::Unauthorized("Missing Authorization header".into()))?;
 
         // Check if it starts with "Bearer "
         if !auth_header.starts_with("Bearer ") {
-            return Err((
-                StatusCode::UNAUTHORIZED,
-                Json(json!({
-                    "error": "Invalid Authorization header format. Expected: Bearer <token>"
-                })),
-            ));
+            return Err(AppError::Unauthorized("Invalid Authorization header format. Expected: Bearer <token>".into()));
         }
 
         // Extract token
         let token = &auth_header[7..]; // Remove "Bearer " prefix
 
-        // Verify JWT token
-        let claims = verify_jwt(token).map_err(|_| {
-            (
-                StatusCode::UNAUTHORIZED,
-                Json(json!({
-                    "error": "Invalid or expired token"
-                })),
-            )
+        authenticate_token(&DbPool::from_ref(state), token).await
+    }
+}
+
+/// A signed-in user with `users.is_admin` set; anyone else gets a 403.
+pub struct AdminUser {
+    pub user_id: String,
+}
+
+#[axum::async_trait]
+impl<S> FromRequestParts<S> for AdminUser
+where
+    DbPool: FromRef<S>,
+    S: Send + Sync,
+{
+    type Rejection = AppError;
+
+    async fn from_request_parts(parts: &mut Parts, state: &S) -> Result<Self, Self::Rejection> {
+        let AuthUser { user_id } = AuthUser::from_request_parts(parts, state).await?;
+
+        let is_admin: Option<bool> = sqlx::query_scalar("SELECT is_admin FROM users WHERE id = ?")
+            .bind(&user_id)
+            .fetch_optional(&DbPool::from_ref(state))
+            .await
+            .map_err(|e| {
+                tracing::error!("Failed to check admin role of {}: {}", user_id, e);
+                AppError::Internal("Failed to verify admin role".into())
+            })?;
+        if is_admin != Some(true) {
+            tracing::warn!("User {} was refused an admin endpoint", user_id);
+            return Err(AppError::Forbidden("Admin access required".into()));
+        }
+        Ok(AdminUser { user_id })
+    }
+}
+
+/// Checks a bearer token and its session, for requests that can't send an `Authorization`
+/// header, such as WebSocket upgrades from a browser.
+pub async fn authenticate_token(pool: &DbPool, token: &str) -> Result<AuthUser, AppError> {
+    // Verify JWT token
+    let claims = verify_jwt(token).map_err(|_| AppError::Unauthorized("Invalid or expired token".into()))?;
+
+    // Tokens carry a session id that can be ended by idle timeout
+    if let Some(session_id) = &claims.sid {
+        let status = sessions::touch_session(pool, session_id, &claims.sub).await.map_err(|e| {
+            tracing::error!("Failed to check session {}: {}", session_id, e);
+            AppError::Internal("Failed to verify session".into())
         })?;
 
-        Ok(AuthUser {
-            user_id: claims.sub,
-        })
+        if let SessionStatus::Ended(reason) = status {
+            let message = if reason == IDLE_TIMEOUT_REASON {
+                "Session expired due to inactivity"
+            } else {
+                "Session has been signed out"
+            };
+            return Err(AppError::SessionEnded { reason, message: message.to_string() });
+        }
     }
-}
\ No newline at end of file
+
+    request_span::record_user(&claims.sub);
+    access_log::record_user(&claims.sub);
+    Ok(AuthUser {
+        user_id: claims.sub,
+    })
+}
@@ -0,0 +1,49 @@
+use axum::{
+    extract::State,
+    http::{header, Request},
+    middleware::Next,
+    response::Response,
+};
+
+use crate::services::cache;
+use crate::services::database::DbPool;
+use crate::services::households;
+use crate::utils::jwt::verify_jwt;
+
+/// Drops the cached reads of a user whose write succeeded, and of everyone sharing a household
+/// with them, since shared accounts show up in their totals too. Runs after the response so a
+/// read can't cache the data from before the write.
+pub async fn invalidate_on_write<B>(
+    State(pool): State<DbPool>,
+    request: Request<B>,
+    next: Next<B>,
+) -> Response {
+    if request.method().is_safe() {
+        return next.run(request).await;
+    }
+
+    let user_id = request
+        .headers()
+        .get(header::AUTHORIZATION)
+        .and_then(|value| value.to_str().ok())
+        .and_then(|value| value.strip_prefix("Bearer "))
+        .and_then(|token| verify_jwt(token).ok())
+        .map(|claims| claims.sub);
+
+    let response = next.run(request).await;
+    let Some(user_id) = user_id.filter(|_| response.status().is_success()) else {
+        return response;
+    };
+
+    let cache = cache::get();
+    cache.invalidate_user(&user_id).await;
+    match households::co_member_ids(&pool, &user_id).await {
+        Ok(members) => {
+            for member in members {
+                cache.invalidate_user(&member).await;
+            }
+        }
+        Err(e) => tracing::error!("Failed to find the household members of {}: {}", user_id, e),
+    }
+    response
+}
@@ -0,0 +1,35 @@
+use std::convert::Infallible;
+
+use axum::{
+    extract::FromRequestParts,
+    http::{header, request::Parts, HeaderMap},
+};
+
+/// Header clients can set to tell their devices/integrations apart.
+pub const DEVICE_HEADER: &str = "x-device-id";
+
+/// Which device or integration made a request: `X-Device-Id` if sent, else the User-Agent.
+pub struct ClientDevice(pub String);
+
+pub fn device_label(headers: &HeaderMap) -> String {
+    headers
+        .get(DEVICE_HEADER)
+        .or_else(|| headers.get(header::USER_AGENT))
+        .and_then(|value| value.to_str().ok())
+        .unwrap_or("unknown")
+        .chars()
+        .take(120)
+        .collect()
+}
+
+#[axum::async_trait]
+impl<S> FromRequestParts<S> for ClientDevice
+where
+    S: Send + Sync,
+{
+    type Rejection = Infallible;
+
+    async fn from_request_parts(parts: &mut Parts, _state: &S) -> Result<Self, Self::Rejection> {
+        Ok(ClientDevice(device_label(&parts.headers)))
+    }
+}
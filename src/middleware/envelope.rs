@@ -0,0 +1,49 @@
+use axum::{
+    body::HttpBody,
+    http::{header, Request},
+    middleware::Next,
+    response::{IntoResponse, Response},
+};
+
+use crate::utils::response::ApiResponse;
+
+/// Puts failures that weren't answered with the JSON envelope into one: unmatched routes and
+/// methods, and axum's plain-text rejections such as an unreadable JSON body. The rejection's
+/// text, when there is one, becomes the error message.
+pub async fn envelope_errors<B>(request: Request<B>, next: Next<B>) -> Response {
+    let response = next.run(request).await;
+    let status = response.status();
+    if !(status.is_client_error() || status.is_server_error()) {
+        return response;
+    }
+    let is_json = response
+        .headers()
+        .get(header::CONTENT_TYPE)
+        .and_then(|value| value.to_str().ok())
+        .is_some_and(|value| value.starts_with("application/json"));
+    if is_json {
+        return response;
+    }
+
+    let (parts, body) = response.into_parts();
+    let text = match body.size_hint().exact() {
+        Some(0) => String::new(),
+        _ => hyper::body::to_bytes(body)
+            .await
+            .map(|bytes| String::from_utf8_lossy(&bytes).trim().to_string())
+            .unwrap_or_default(),
+    };
+    let message = if text.is_empty() {
+        status.canonical_reason().unwrap_or("Request failed").to_string()
+    } else {
+        text
+    };
+
+    let mut enveloped = ApiResponse::error(status, message).into_response();
+    for (name, value) in parts.headers.iter() {
+        if name != header::CONTENT_TYPE && name != header::CONTENT_LENGTH {
+            enveloped.headers_mut().insert(name.clone(), value.clone());
+        }
+    }
+    enveloped
+}
@@ -0,0 +1,120 @@
+use std::sync::Arc;
+use std::task::{Context, Poll};
+use std::time::Duration;
+
+use axum::{
+    http::{header, Request},
+    response::{IntoResponse, Response},
+};
+use futures_util::future::BoxFuture;
+use tokio::sync::{OwnedSemaphorePermit, Semaphore};
+use tower::{Layer, Service};
+
+use crate::config::LimitsConfig;
+use crate::utils::error::AppError;
+
+/// What shed requests are told to wait before trying again.
+const RETRY_AFTER_SECS: u64 = 2;
+
+/// Caps the requests in flight, and the writes among them, since SQLite takes one writer at a
+/// time and a pile of queued writes only ends in timeouts. A request that can't get its turn
+/// within the queue timeout gets a 503 with `Retry-After` instead of waiting on. Health checks
+/// are never held back, so a busy instance isn't mistaken for a dead one.
+#[derive(Clone)]
+pub struct LoadShedLayer {
+    requests: Arc<Semaphore>,
+    writes: Arc<Semaphore>,
+    queue_timeout: Duration,
+}
+
+impl LoadShedLayer {
+    pub fn new(max_requests: usize, max_writes: usize, queue_timeout: Duration) -> Self {
+        Self {
+            requests: Arc::new(Semaphore::new(max_requests)),
+            writes: Arc::new(Semaphore::new(max_writes)),
+            queue_timeout,
+        }
+    }
+
+    pub fn from_config(limits: &LimitsConfig) -> Self {
+        Self::new(
+            limits.max_concurrent_requests,
+            limits.max_concurrent_writes,
+            Duration::from_millis(limits.queue_timeout_ms),
+        )
+    }
+
+    /// A permit from `semaphore`, or `None` when none freed up within the queue timeout.
+    async fn acquire(&self, semaphore: &Arc<Semaphore>) -> Option<OwnedSemaphorePermit> {
+        tokio::time::timeout(self.queue_timeout, semaphore.clone().acquire_owned())
+            .await
+            .ok()
+            .and_then(Result::ok)
+    }
+}
+
+impl<S> Layer<S> for LoadShedLayer {
+    type Service = LoadShed<S>;
+
+    fn layer(&self, inner: S) -> Self::Service {
+        LoadShed { inner, limits: self.clone() }
+    }
+}
+
+#[derive(Clone)]
+pub struct LoadShed<S> {
+    inner: S,
+    limits: LoadShedLayer,
+}
+
+impl<S, B> Service<Request<B>> for LoadShed<S>
+where
+    S: Service<Request<B>, Response = Response> + Clone + Send + 'static,
+    S::Future: Send,
+    B: Send + 'static,
+{
+    type Response = Response;
+    type Error = S::Error;
+    type Future = BoxFuture<'static, Result<Response, S::Error>>;
+
+    fn poll_ready(&mut self, cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        self.inner.poll_ready(cx)
+    }
+
+    fn call(&mut self, request: Request<B>) -> Self::Future {
+        // The clone may not be ready; keep the one that was polled
+        let clone = self.inner.clone();
+        let mut inner = std::mem::replace(&mut self.inner, clone);
+        let limits = self.limits.clone();
+
+        Box::pin(async move {
+            if request.uri().path().starts_with("/health") {
+                return inner.call(request).await;
+            }
+
+            let Some(_request_permit) = limits.acquire(&limits.requests).await else {
+                tracing::warn!("Shedding {} {}: too many requests in flight", request.method(), request.uri().path());
+                return Ok(shed());
+            };
+            let _write_permit = if request.method().is_safe() {
+                None
+            } else {
+                let Some(permit) = limits.acquire(&limits.writes).await else {
+                    tracing::warn!("Shedding {} {}: too many writes in flight", request.method(), request.uri().path());
+                    return Ok(shed());
+                };
+                Some(permit)
+            };
+
+            inner.call(request).await
+        })
+    }
+}
+
+fn shed() -> Response {
+    (
+        [(header::RETRY_AFTER, RETRY_AFTER_SECS.to_string())],
+        AppError::Unavailable("The server is busy; try again shortly".into()),
+    )
+        .into_response()
+}
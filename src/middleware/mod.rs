@@ -1,3 +1,12 @@
 pub mod auth;
+pub mod device;
+pub mod usage;
+pub mod envelope;
+pub mod request_span;
+pub mod access_log;
+pub mod cache;
+pub mod owned;
+pub mod quota;
+pub mod load_shed;
 
 pub use auth::*;
\ No newline at end of file
@@ -0,0 +1,125 @@
+use std::collections::HashMap;
+
+use axum::{
+    extract::{FromRef, FromRequestParts, Path},
+    http::request::Parts,
+};
+use sqlx::{sqlite::SqliteRow, FromRow};
+
+use crate::middleware::auth::AuthUser;
+use crate::models::{
+    BillReminder, Budget, EmiPlan, Holding, Liability, Loan, RecurringTransaction, SavingsGoal, TermDeposit,
+    UserExchangeRate, Webhook,
+};
+use crate::services::database::DbPool;
+use crate::utils::error::AppError;
+use crate::utils::pagination::{PageQuery, Paginated};
+
+/// An entity that belongs to a single user through a `user_id` column.
+pub trait OwnedResource: for<'r> FromRow<'r, SqliteRow> + Send + Unpin {
+    /// Table the entity is read from; it must have `id` and `user_id` columns.
+    const TABLE: &'static str;
+    /// What the entity is called in error messages, in lower case: "savings goal".
+    const NAME: &'static str;
+}
+
+macro_rules! owned_resources {
+    ($($model:ty => $table:literal, $name:literal;)*) => {
+        $(impl OwnedResource for $model {
+            const TABLE: &'static str = $table;
+            const NAME: &'static str = $name;
+        })*
+    };
+}
+
+owned_resources! {
+    Loan => "loans", "loan";
+    Liability => "liabilities", "liability";
+    SavingsGoal => "savings_goals", "savings goal";
+    Budget => "budgets", "budget";
+    RecurringTransaction => "recurring_transactions", "recurring transaction";
+    TermDeposit => "term_deposits", "term deposit";
+    EmiPlan => "emi_plans", "EMI plan";
+    Holding => "holdings", "holding";
+    UserExchangeRate => "user_exchange_rates", "exchange rate";
+    Webhook => "webhooks", "webhook";
+    BillReminder => "bill_reminders", "bill reminder";
+}
+
+/// The `T` named by the route's `:id`, loaded only if it belongs to the signed-in user. Anyone
+/// else's gets the same 404 as an id that doesn't exist.
+pub struct Owned<T> {
+    pub user: AuthUser,
+    pub resource: T,
+}
+
+#[axum::async_trait]
+impl<S, T> FromRequestParts<S> for Owned<T>
+where
+    DbPool: FromRef<S>,
+    S: Send + Sync,
+    T: OwnedResource,
+{
+    type Rejection = AppError;
+
+    async fn from_request_parts(parts: &mut Parts, state: &S) -> Result<Self, Self::Rejection> {
+        let user = AuthUser::from_request_parts(parts, state).await?;
+
+        let Path(params) = Path::<HashMap<String, String>>::from_request_parts(parts, state)
+            .await
+            .map_err(|e| AppError::BadRequest(e.body_text()))?;
+        let id = params.get("id").ok_or_else(|| {
+            tracing::error!("Route {} has no :id for {}", parts.uri.path(), T::NAME);
+            AppError::Internal(format!("Failed to get {}", T::NAME))
+        })?;
+
+        let resource = load(&DbPool::from_ref(state), id, &user.user_id).await?;
+        Ok(Self { user, resource })
+    }
+}
+
+/// `user_id`'s `T` with this `id`, or `None` when there is none or it's someone else's.
+pub async fn find<T: OwnedResource>(pool: &DbPool, id: &str, user_id: &str) -> Result<Option<T>, AppError> {
+    let sql = format!("SELECT * FROM {} WHERE id = ? AND user_id = ?", T::TABLE);
+    sqlx::query_as::<_, T>(&sql)
+        .bind(id)
+        .bind(user_id)
+        .fetch_optional(pool)
+        .await
+        .map_err(|e| {
+            tracing::error!("Failed to get {} {}: {}", T::NAME, id, e);
+            AppError::Internal(format!("Failed to get {}", T::NAME))
+        })
+}
+
+/// One page of `user_id`'s `T`s sorted by `order`, such as `"created_at DESC"`, cut and
+/// counted by the database.
+pub async fn page<T: OwnedResource>(pool: &DbPool, user_id: &str, order: &str, page: &PageQuery) -> Result<Paginated<T>, sqlx::Error> {
+    let sql = format!("SELECT * FROM {} WHERE user_id = ? ORDER BY {}, id LIMIT ? OFFSET ?", T::TABLE, order);
+    let items = sqlx::query_as::<_, T>(&sql)
+        .bind(user_id)
+        .bind(page.limit() as i64)
+        .bind(page.offset() as i64)
+        .fetch_all(pool)
+        .await?;
+
+    let total = sqlx::query_scalar::<_, i64>(&format!("SELECT COUNT(*) FROM {} WHERE user_id = ?", T::TABLE))
+        .bind(user_id)
+        .fetch_one(pool)
+        .await?;
+
+    Ok(Paginated::new(items, total as usize, page))
+}
+
+/// Like [`find`], but a missing entity is a 404.
+pub async fn load<T: OwnedResource>(pool: &DbPool, id: &str, user_id: &str) -> Result<T, AppError> {
+    find(pool, id, user_id).await?.ok_or_else(|| AppError::NotFound(not_found_message(T::NAME)))
+}
+
+fn not_found_message(name: &str) -> String {
+    let mut chars = name.chars();
+    match chars.next() {
+        Some(first) => format!("{}{} not found", first.to_uppercase(), chars.as_str()),
+        None => "Not found".to_string(),
+    }
+}
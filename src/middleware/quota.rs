@@ -0,0 +1,82 @@
+use axum::{
+    extract::State,
+    http::{header, HeaderValue, Request},
+    middleware::Next,
+    response::{IntoResponse, Response},
+};
+use chrono::Utc;
+
+use crate::services::database::DbPool;
+use crate::services::quotas;
+use crate::utils::error::AppError;
+use crate::utils::jwt::verify_jwt;
+
+/// Holds signed-in users to their request rate and daily upload quotas, answering 429 with a
+/// `Retry-After` once either is used up. Successful responses carry `X-RateLimit-Limit` and
+/// `X-RateLimit-Remaining`. A quota lookup that fails lets the request through.
+pub async fn enforce_quotas<B>(
+    State(pool): State<DbPool>,
+    request: Request<B>,
+    next: Next<B>,
+) -> Response {
+    let user_id = request
+        .headers()
+        .get(header::AUTHORIZATION)
+        .and_then(|value| value.to_str().ok())
+        .and_then(|value| value.strip_prefix("Bearer "))
+        .and_then(|token| verify_jwt(token).ok())
+        .map(|claims| claims.sub);
+    let Some(user_id) = user_id else {
+        return next.run(request).await;
+    };
+
+    let limits = match quotas::for_user(&pool, &user_id).await {
+        Ok(limits) => limits,
+        Err(e) => {
+            tracing::error!("Failed to look up quotas of user {}: {}", user_id, e);
+            return next.run(request).await;
+        }
+    };
+    let now = Utc::now();
+
+    let used = match quotas::take_request(&user_id, limits.requests_per_minute, now) {
+        Ok(used) => used,
+        Err(retry_after_secs) => {
+            tracing::warn!("User {} is over their limit of {:?} requests per minute", user_id, limits.requests_per_minute);
+            return AppError::QuotaExceeded {
+                quota: "requestsPerMinute".to_string(),
+                message: "Too many requests; try again shortly".to_string(),
+                retry_after_secs,
+            }
+            .into_response();
+        }
+    };
+
+    let incoming = request
+        .headers()
+        .get(header::CONTENT_LENGTH)
+        .and_then(|value| value.to_str().ok())
+        .and_then(|value| value.parse::<u64>().ok())
+        .unwrap_or(0);
+    match quotas::check_upload(&pool, &user_id, limits.daily_upload_bytes, incoming, now).await {
+        Ok(None) => {}
+        Ok(Some(retry_after_secs)) => {
+            tracing::warn!("User {} is over their daily upload quota of {:?} bytes", user_id, limits.daily_upload_bytes);
+            return AppError::QuotaExceeded {
+                quota: "dailyUploadBytes".to_string(),
+                message: "Daily upload quota used up".to_string(),
+                retry_after_secs,
+            }
+            .into_response();
+        }
+        Err(e) => tracing::error!("Failed to check the upload quota of user {}: {}", user_id, e),
+    }
+
+    let mut response = next.run(request).await;
+    if let Some(limit) = limits.requests_per_minute {
+        let headers = response.headers_mut();
+        headers.insert("x-ratelimit-limit", HeaderValue::from(limit));
+        headers.insert("x-ratelimit-remaining", HeaderValue::from(limit.saturating_sub(used)));
+    }
+    response
+}
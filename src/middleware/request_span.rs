@@ -0,0 +1,37 @@
+use std::time::Duration;
+
+use axum::{
+    extract::MatchedPath,
+    http::{Request, Response},
+};
+use tracing::{field, Span};
+
+/// Opens the span each request's logs are recorded under. `user_id` is filled in once the
+/// request authenticates, `status` and `latency_ms` once it is answered.
+pub fn make_span<B>(request: &Request<B>) -> Span {
+    let route = request
+        .extensions()
+        .get::<MatchedPath>()
+        .map(MatchedPath::as_str)
+        .unwrap_or("unmatched");
+    tracing::info_span!(
+        "request",
+        method = %request.method(),
+        route,
+        path = %request.uri().path(),
+        user_id = field::Empty,
+        status = field::Empty,
+        latency_ms = field::Empty,
+    )
+}
+
+pub fn on_response<B>(response: &Response<B>, latency: Duration, span: &Span) {
+    span.record("status", response.status().as_u16());
+    span.record("latency_ms", latency.as_millis() as u64);
+    tracing::debug!("finished processing request");
+}
+
+/// Tags the current request's span with the signed-in user.
+pub fn record_user(user_id: &str) {
+    Span::current().record("user_id", user_id);
+}
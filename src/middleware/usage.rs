@@ -0,0 +1,56 @@
+use axum::{
+    body::HttpBody,
+    extract::MatchedPath,
+    http::{header, Request},
+    middleware::Next,
+    response::Response,
+};
+
+use crate::middleware::device::device_label;
+use crate::services::usage::{self, UsageRecord};
+use crate::utils::jwt::verify_jwt;
+
+fn header_str<B>(request: &Request<B>, name: impl header::AsHeaderName) -> Option<&str> {
+    request.headers().get(name).and_then(|value| value.to_str().ok())
+}
+
+/// Counts requests and bytes per user, endpoint and device. Unauthenticated requests are not tracked.
+pub async fn track_usage<B>(
+    request: Request<B>,
+    next: Next<B>,
+) -> Response {
+    let user_id = header_str(&request, header::AUTHORIZATION)
+        .and_then(|value| value.strip_prefix("Bearer "))
+        .and_then(|token| verify_jwt(token).ok())
+        .map(|claims| claims.sub);
+
+    let Some(user_id) = user_id else {
+        return next.run(request).await;
+    };
+
+    let method = request.method().to_string();
+    let route = request
+        .extensions()
+        .get::<MatchedPath>()
+        .map(|path| path.as_str().to_string())
+        .unwrap_or_else(|| request.uri().path().to_string());
+    let device = device_label(request.headers());
+    let request_bytes = header_str(&request, header::CONTENT_LENGTH)
+        .and_then(|value| value.parse::<i64>().ok())
+        .unwrap_or(0);
+
+    let response = next.run(request).await;
+    let response_bytes = response.body().size_hint().exact().unwrap_or(0) as i64;
+
+    // Counted in memory and written out by the usage flush job
+    usage::record(UsageRecord {
+        user_id,
+        method,
+        route,
+        device,
+        request_bytes,
+        response_bytes,
+    });
+
+    response
+}
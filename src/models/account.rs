@@ -1,8 +1,13 @@
+use std::collections::HashSet;
+
 use chrono::{DateTime, Utc};
-use serde::{Deserialize, Serialize};
+use serde::{Deserialize, Deserializer, Serialize};
 use sqlx::FromRow;
 use uuid::Uuid;
 
+use crate::utils::money::Money;
+use crate::utils::validation::{FieldErrors, Validate};
+
 #[derive(Debug, Clone, Serialize, Deserialize, FromRow)]
 pub struct Account {
     pub id: String,
@@ -11,14 +16,43 @@ pub struct Account {
     pub name: String,
     #[serde(rename = "type")]
     pub account_type: AccountType,
-    pub balance: f64,
+    pub balance: Money,
     pub currency: String,
     #[serde(rename = "creditLimit")]
-    pub credit_limit: Option<f64>,
+    pub credit_limit: Option<Money>,
+    /// Tracked, but left out of net worth and other totals (e.g. a business account).
+    #[serde(rename = "excludeFromTotals")]
+    pub exclude_from_totals: bool,
+    /// Day of the month a credit card statement closes (clamped to short months).
+    #[serde(rename = "statementDay")]
+    pub statement_day: Option<u32>,
+    /// Day of the month a credit card payment is due, after the statement closes.
+    #[serde(rename = "paymentDueDay")]
+    pub payment_due_day: Option<u32>,
+    /// Position in the owner's account list, from 0; set by `POST /accounts/reorder`.
+    #[serde(rename = "sortOrder")]
+    pub sort_order: i64,
+    #[serde(rename = "isFavorite")]
+    pub is_favorite: bool,
+    /// Chosen by the apps, such as an emoji or an icon name. None uses the type's default.
+    pub icon: Option<String>,
+    /// A color such as `#4CAF50`. None uses the type's default.
+    pub color: Option<String>,
+    /// The bank or branch holding the account, such as `BRAC Bank, Gulshan`.
+    #[serde(rename = "institutionName")]
+    pub institution_name: Option<String>,
+    /// Enough of the account number to tell it apart, such as `****1234`; never the whole number.
+    #[serde(rename = "accountNumberMasked")]
+    pub account_number_masked: Option<String>,
+    pub notes: Option<String>,
     #[serde(rename = "createdAt")]
     pub created_at: DateTime<Utc>,
     #[serde(rename = "updatedAt")]
     pub updated_at: DateTime<Utc>,
+    /// Increases with every change; see `services::concurrency`.
+    #[sqlx(default)]
+    #[serde(default)]
+    pub version: i64,
 }
 
 #[derive(Debug, Clone, Copy, Serialize, Deserialize, sqlx::Type)]
@@ -30,7 +64,7 @@ pub enum AccountType {
     #[sqlx(rename = "bank")]
     Bank,
     #[sqlx(rename = "mobile_banking")]
-    #[serde(alias = "mobileBanking")]
+    #[serde(alias = "mobileBanking", alias = "mobile_banking")]
     MobileBanking,
     #[sqlx(rename = "cash")]
     Cash,
@@ -39,7 +73,7 @@ pub enum AccountType {
     #[sqlx(rename = "savings")]
     Savings,
     #[sqlx(rename = "credit_card")]
-    #[serde(alias = "creditCard")]
+    #[serde(alias = "creditCard", alias = "credit_card")]
     CreditCard,
 }
 
@@ -49,10 +83,25 @@ pub struct CreateAccountRequest {
     pub name: String,
     #[serde(alias = "type")]
     pub account_type: AccountType,
-    pub balance: f64,
+    pub balance: Money,
     pub currency: Option<String>,
     #[serde(alias = "creditLimit")]
-    pub credit_limit: Option<f64>,
+    pub credit_limit: Option<Money>,
+    #[serde(alias = "excludeFromTotals")]
+    pub exclude_from_totals: Option<bool>,
+    #[serde(alias = "statementDay")]
+    pub statement_day: Option<u32>,
+    #[serde(alias = "paymentDueDay")]
+    pub payment_due_day: Option<u32>,
+    #[serde(alias = "isFavorite")]
+    pub is_favorite: Option<bool>,
+    pub icon: Option<String>,
+    pub color: Option<String>,
+    #[serde(alias = "institutionName")]
+    pub institution_name: Option<String>,
+    #[serde(alias = "accountNumberMasked")]
+    pub account_number_masked: Option<String>,
+    pub notes: Option<String>,
     // Accept but ignore these fields sent by Flutter
     #[serde(alias = "createdAt")]
     pub created_at: Option<DateTime<Utc>>,
@@ -64,9 +113,30 @@ pub struct CreateAccountRequest {
 pub struct UpdateAccountRequest {
     pub name: Option<String>,
     pub account_type: Option<AccountType>,
-    pub balance: Option<f64>,
+    pub balance: Option<Money>,
     pub currency: Option<String>,
-    pub credit_limit: Option<f64>,
+    pub credit_limit: Option<Money>,
+    #[serde(alias = "excludeFromTotals")]
+    pub exclude_from_totals: Option<bool>,
+    #[serde(alias = "statementDay")]
+    pub statement_day: Option<u32>,
+    #[serde(alias = "paymentDueDay")]
+    pub payment_due_day: Option<u32>,
+    #[serde(alias = "isFavorite")]
+    pub is_favorite: Option<bool>,
+    // Sent as null these are cleared; left out they are kept
+    #[serde(default, deserialize_with = "nullable")]
+    pub icon: Option<Option<String>>,
+    #[serde(default, deserialize_with = "nullable")]
+    pub color: Option<Option<String>>,
+    #[serde(alias = "institutionName", default, deserialize_with = "nullable")]
+    pub institution_name: Option<Option<String>>,
+    #[serde(alias = "accountNumberMasked", default, deserialize_with = "nullable")]
+    pub account_number_masked: Option<Option<String>>,
+    #[serde(default, deserialize_with = "nullable")]
+    pub notes: Option<Option<String>>,
+    /// The version the update is based on, unless sent as `If-Match`.
+    pub version: Option<i64>,
 }
 
 impl Account {
@@ -80,39 +150,144 @@ impl Account {
             balance: request.balance,
             currency: request.currency.unwrap_or_else(|| "BDT".to_string()),
             credit_limit: request.credit_limit,
+            exclude_from_totals: request.exclude_from_totals.unwrap_or(false),
+            statement_day: request.statement_day,
+            payment_due_day: request.payment_due_day,
+            sort_order: 0,
+            is_favorite: request.is_favorite.unwrap_or(false),
+            icon: request.icon,
+            color: request.color,
+            institution_name: request.institution_name,
+            account_number_masked: request.account_number_masked,
+            notes: request.notes,
             created_at: now,
             updated_at: now,
+            version: 1,
         }
     }
+}
 
-    pub fn available_credit(&self) -> f64 {
-        match self.account_type {
-            AccountType::CreditCard => {
-                if let Some(limit) = self.credit_limit {
-                    limit + self.balance
-                } else {
-                    0.0
-                }
-            }
-            _ => 0.0,
+/// The user's accounts in the order they should be listed. Accounts left out keep their
+/// relative order after the listed ones.
+#[derive(Debug, Deserialize)]
+pub struct ReorderAccountsRequest {
+    #[serde(alias = "accountIds")]
+    pub account_ids: Vec<String>,
+}
+
+/// How much of a credit card's limit is in use.
+#[derive(Debug, Clone, Serialize)]
+pub struct CreditUtilization {
+    #[serde(rename = "accountId")]
+    pub account_id: String,
+    pub name: String,
+    pub currency: String,
+    #[serde(rename = "creditLimit")]
+    pub credit_limit: Money,
+    pub used: Money,
+    #[serde(rename = "utilizationPercent")]
+    pub utilization_percent: f64,
+    /// The highest alert threshold the card is at or over, if any.
+    pub threshold: Option<u32>,
+}
+
+/// Tells a field sent as `null`, `Some(None)`, from one left out, `None`.
+fn nullable<'de, D, T>(deserializer: D) -> Result<Option<Option<T>>, D::Error>
+where
+    D: Deserializer<'de>,
+    T: Deserialize<'de>,
+{
+    Option::<T>::deserialize(deserializer).map(Some)
+}
+
+/// Whether `color` is written `#RRGGBB`, as the apps store it.
+fn valid_color(color: &str) -> bool {
+    color.len() == 7 && color.starts_with('#') && color[1..].chars().all(|c| c.is_ascii_hexdigit())
+}
+
+/// How many digits of an account number may be stored.
+pub const MAX_VISIBLE_ACCOUNT_DIGITS: usize = 4;
+
+/// Statement and due days run 1–31; later days than a month has fall on its last day.
+pub fn valid_billing_day(day: Option<u32>) -> bool {
+    day.is_none_or(|day| (1..=31).contains(&day))
+}
+
+impl Validate for CreateAccountRequest {
+    fn check_fields(&self, errors: &mut FieldErrors) {
+        errors.name("name", &self.name);
+        let code = self.currency.as_deref().unwrap_or("BDT");
+        errors.currency("currency", code);
+        errors.fits_currency("balance", self.balance, code);
+        if let Some(limit) = self.credit_limit {
+            errors.not_negative("creditLimit", limit);
         }
+        check_billing_days(errors, self.statement_day, self.payment_due_day);
+        check_appearance(errors, self.icon.as_deref(), self.color.as_deref());
+        check_institution(errors, self.institution_name.as_deref(), self.account_number_masked.as_deref(), self.notes.as_deref());
     }
+}
 
-    pub fn used_amount(&self) -> f64 {
-        match self.account_type {
-            AccountType::CreditCard => -self.balance,
-            _ => 0.0,
+impl Validate for UpdateAccountRequest {
+    fn check_fields(&self, errors: &mut FieldErrors) {
+        if let Some(name) = &self.name {
+            errors.name("name", name);
+        }
+        if let Some(code) = &self.currency {
+            errors.currency("currency", code);
+            if let Some(balance) = self.balance {
+                errors.fits_currency("balance", balance, code);
+            }
         }
+        if let Some(limit) = self.credit_limit {
+            errors.not_negative("creditLimit", limit);
+        }
+        check_billing_days(errors, self.statement_day, self.payment_due_day);
+        check_appearance(errors, self.icon.as_ref().and_then(Option::as_deref), self.color.as_ref().and_then(Option::as_deref));
+        check_institution(
+            errors,
+            self.institution_name.as_ref().and_then(Option::as_deref),
+            self.account_number_masked.as_ref().and_then(Option::as_deref),
+            self.notes.as_ref().and_then(Option::as_deref),
+        );
     }
+}
 
-    pub fn display_balance(&self) -> f64 {
-        match self.account_type {
-            AccountType::CreditCard => self.available_credit(),
-            _ => self.balance,
-        }
+impl Validate for ReorderAccountsRequest {
+    fn check_fields(&self, errors: &mut FieldErrors) {
+        let mut seen = HashSet::new();
+        errors.check(!self.account_ids.is_empty(), "accountIds", "must list at least one account");
+        errors.check(self.account_ids.iter().all(|id| seen.insert(id)), "accountIds", "must not repeat an account");
     }
+}
 
-    pub fn is_credit_card(&self) -> bool {
-        matches!(self.account_type, AccountType::CreditCard)
+fn check_billing_days(errors: &mut FieldErrors, statement_day: Option<u32>, payment_due_day: Option<u32>) {
+    errors.check(valid_billing_day(statement_day), "statementDay", "must be a day of the month (1-31)");
+    errors.check(valid_billing_day(payment_due_day), "paymentDueDay", "must be a day of the month (1-31)");
+}
+
+fn check_appearance(errors: &mut FieldErrors, icon: Option<&str>, color: Option<&str>) {
+    if let Some(icon) = icon {
+        errors.text("icon", icon);
+    }
+    if let Some(color) = color {
+        errors.check(valid_color(color), "color", "must be a color written #RRGGBB");
+    }
+}
+
+fn check_institution(errors: &mut FieldErrors, institution_name: Option<&str>, account_number_masked: Option<&str>, notes: Option<&str>) {
+    if let Some(name) = institution_name {
+        errors.text("institutionName", name);
+    }
+    if let Some(number) = account_number_masked {
+        errors.text("accountNumberMasked", number);
+        errors.check(
+            number.chars().filter(char::is_ascii_digit).count() <= MAX_VISIBLE_ACCOUNT_DIGITS,
+            "accountNumberMasked",
+            &format!("must show at most {} digits of the account number", MAX_VISIBLE_ACCOUNT_DIGITS),
+        );
+    }
+    if let Some(notes) = notes {
+        errors.text("notes", notes);
     }
 }
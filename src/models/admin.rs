@@ -0,0 +1,51 @@
+use serde::Serialize;
+use sqlx::FromRow;
+
+#[derive(Debug, Clone, Serialize, FromRow)]
+pub struct UserCounts {
+    pub total: i64,
+    pub verified: i64,
+    pub admins: i64,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct TableRows {
+    pub table: String,
+    pub rows: i64,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct DatabaseFileStats {
+    #[serde(rename = "sizeBytes")]
+    pub size_bytes: i64,
+    #[serde(rename = "freeBytes")]
+    pub free_bytes: i64,
+    #[serde(rename = "pageSize")]
+    pub page_size: i64,
+}
+
+#[derive(Debug, Clone, Serialize, FromRow)]
+pub struct DailyCount {
+    pub day: String,
+    pub count: i64,
+}
+
+/// Users who made at least one API request in the last day, week and month.
+#[derive(Debug, Clone, Serialize, FromRow)]
+pub struct ActiveUsers {
+    pub daily: i64,
+    pub weekly: i64,
+    pub monthly: i64,
+}
+
+/// Capacity and engagement numbers for operators, from `GET /admin/stats`.
+#[derive(Debug, Clone, Serialize)]
+pub struct AdminStats {
+    pub users: UserCounts,
+    #[serde(rename = "activeUsers")]
+    pub active_users: ActiveUsers,
+    #[serde(rename = "signupsByDay")]
+    pub signups_by_day: Vec<DailyCount>,
+    pub database: DatabaseFileStats,
+    pub tables: Vec<TableRows>,
+}
@@ -0,0 +1,83 @@
+use serde::{Deserialize, Serialize};
+use sqlx::FromRow;
+use chrono::{DateTime, Utc};
+
+use crate::utils::money::Money;
+
+#[derive(Debug, Deserialize)]
+pub struct AmortizationRequest {
+    pub principal: Money,
+    /// Nominal annual interest rate as a percentage, e.g. `9.5` for 9.5%.
+    #[serde(alias = "annualRate")]
+    pub annual_rate: f64,
+    #[serde(alias = "termMonths")]
+    pub term_months: u32,
+    #[serde(alias = "startDate")]
+    pub start_date: Option<DateTime<Utc>>,
+    #[serde(alias = "loanId")]
+    pub loan_id: Option<String>,
+    #[serde(alias = "liabilityId")]
+    pub liability_id: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, FromRow)]
+pub struct AmortizationSchedule {
+    pub id: String,
+    #[serde(rename = "userId")]
+    pub user_id: String,
+    #[serde(rename = "loanId")]
+    pub loan_id: Option<String>,
+    #[serde(rename = "liabilityId")]
+    pub liability_id: Option<String>,
+    pub principal: Money,
+    #[serde(rename = "annualRate")]
+    pub annual_rate: f64,
+    #[serde(rename = "termMonths")]
+    pub term_months: i64,
+    pub payment: Money,
+    #[serde(rename = "totalInterest")]
+    pub total_interest: Money,
+    #[serde(rename = "startDate")]
+    pub start_date: DateTime<Utc>,
+    #[serde(rename = "createdAt")]
+    pub created_at: DateTime<Utc>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, FromRow)]
+pub struct AmortizationEntry {
+    #[serde(skip_serializing)]
+    pub schedule_id: String,
+    pub period: i64,
+    #[serde(rename = "dueDate")]
+    pub due_date: DateTime<Utc>,
+    pub payment: Money,
+    pub principal: Money,
+    pub interest: Money,
+    pub remaining: Money,
+    #[serde(rename = "actualPayment")]
+    pub actual_payment: Option<Money>,
+    #[serde(rename = "paidDate")]
+    pub paid_date: Option<DateTime<Utc>>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct RecordAmortizationPaymentRequest {
+    #[serde(alias = "actualPayment")]
+    pub actual_payment: Money,
+    #[serde(alias = "paidDate")]
+    pub paid_date: Option<DateTime<Utc>>,
+}
+
+/// Planned vs. actual repayment up to today.
+#[derive(Debug, Clone, Serialize)]
+pub struct AmortizationTracking {
+    #[serde(rename = "plannedToDate")]
+    pub planned_to_date: Money,
+    #[serde(rename = "paidToDate")]
+    pub paid_to_date: Money,
+    pub variance: Money,
+    #[serde(rename = "periodsDue")]
+    pub periods_due: i64,
+    #[serde(rename = "periodsPaid")]
+    pub periods_paid: i64,
+}
@@ -0,0 +1,69 @@
+use std::collections::BTreeMap;
+
+use serde::{Deserialize, Serialize};
+use serde_json::{Map, Value};
+
+pub const ARCHIVE_FORMAT: &str = "personal-manager-archive";
+pub const ARCHIVE_VERSION: u32 = 3;
+
+/// The account holder, for reference; restoring never changes the profile or credentials.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ArchiveUser {
+    pub id: String,
+    pub name: String,
+    pub email: String,
+    #[serde(rename = "createdAt", alias = "created_at")]
+    pub created_at: String,
+}
+
+/// Everything a user owns, as raw table rows keyed by column name.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DataArchive {
+    pub format: String,
+    pub version: u32,
+    #[serde(rename = "exportedAt", alias = "exported_at")]
+    pub exported_at: String,
+    pub user: ArchiveUser,
+    pub tables: BTreeMap<String, Vec<Map<String, Value>>>,
+}
+
+/// What to do with an archived row whose ID is already taken.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ConflictPolicy {
+    /// Insert it under a fresh ID and point references at the new one.
+    #[default]
+    Remap,
+    /// Keep the stored row if it is the caller's own; rows held by anyone else are still remapped.
+    Skip,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct RestoreQuery {
+    /// Runs the whole restore and rolls it back, reporting what would happen.
+    #[serde(default, alias = "validateOnly", alias = "dry_run", alias = "dryRun")]
+    pub validate_only: bool,
+    #[serde(default)]
+    pub conflict: ConflictPolicy,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct RestoreRowError {
+    pub table: String,
+    /// Position of the row within the table's array in the archive.
+    pub index: usize,
+    pub message: String,
+}
+
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct RestoreSummary {
+    #[serde(rename = "validateOnly")]
+    pub validate_only: bool,
+    /// Whether the rows were written; false for validation runs and whenever any row failed.
+    pub committed: bool,
+    pub inserted: BTreeMap<String, u64>,
+    pub skipped: BTreeMap<String, u64>,
+    /// Rows restored under a new ID because theirs was already taken.
+    pub remapped: u64,
+    pub errors: Vec<RestoreRowError>,
+}
@@ -1,7 +1,10 @@
 use serde::{Deserialize, Serialize};
 use sqlx::FromRow;
 use uuid::Uuid;
-use chrono::{DateTime, Utc};
+use chrono::{DateTime, Datelike, Duration, Months, NaiveDate, Utc};
+
+use crate::utils::money::Money;
+use crate::utils::validation::{FieldErrors, Validate};
 
 #[derive(Debug, Clone, Serialize, Deserialize, FromRow)]
 pub struct Budget {
@@ -9,30 +12,131 @@ pub struct Budget {
     #[serde(rename = "userId")]
     pub user_id: String,
     pub category: String,
-    pub amount: f64,
+    pub amount: Money,
     pub currency: String,
     pub period: String,
+    pub rollover: bool,
+    #[serde(rename = "accountId")]
+    pub account_id: Option<String>,
+    /// Counts expenses in every currency, converted into `currency` at transaction-date rates.
+    #[serde(rename = "multiCurrency")]
+    pub multi_currency: bool,
     #[serde(rename = "createdAt")]
     pub created_at: DateTime<Utc>,
     #[serde(rename = "updatedAt")]
     pub updated_at: DateTime<Utc>,
+    /// Increases with every change; see `services::concurrency`.
+    #[sqlx(default)]
+    #[serde(default)]
+    pub version: i64,
 }
 
 #[derive(Debug, Deserialize)]
 pub struct CreateBudgetRequest {
     pub id: Option<String>,
-    pub category: String,
-    pub amount: f64,
+    pub category: Option<String>,
+    pub categories: Option<Vec<String>>,
+    #[serde(alias = "accountId")]
+    pub account_id: Option<String>,
+    pub amount: Money,
     pub currency: Option<String>,
     pub period: Option<String>,
+    pub rollover: Option<bool>,
+    #[serde(alias = "multiCurrency")]
+    pub multi_currency: Option<bool>,
 }
 
 #[derive(Debug, Deserialize)]
 pub struct UpdateBudgetRequest {
     pub category: Option<String>,
-    pub amount: Option<f64>,
+    pub categories: Option<Vec<String>>,
+    #[serde(alias = "accountId")]
+    pub account_id: Option<String>,
+    pub amount: Option<Money>,
     pub currency: Option<String>,
     pub period: Option<String>,
+    pub rollover: Option<bool>,
+    #[serde(alias = "multiCurrency")]
+    pub multi_currency: Option<bool>,
+    /// The version the update is based on, unless sent as `If-Match`.
+    pub version: Option<i64>,
+}
+
+/// Spending against a budget's current period, broken down by category.
+#[derive(Debug, Clone, Serialize)]
+pub struct BudgetProgress {
+    #[serde(rename = "budgetId")]
+    pub budget_id: String,
+    pub categories: Vec<String>,
+    #[serde(rename = "accountId")]
+    pub account_id: Option<String>,
+    pub currency: String,
+    #[serde(rename = "periodStart")]
+    pub period_start: DateTime<Utc>,
+    #[serde(rename = "periodEnd")]
+    pub period_end: DateTime<Utc>,
+    pub limit: Money,
+    pub spent: Money,
+    pub remaining: Money,
+    #[serde(rename = "percentUsed")]
+    pub percent_used: f64,
+    #[serde(rename = "isExceeded")]
+    pub is_exceeded: bool,
+    #[serde(rename = "byCategory")]
+    pub by_category: Vec<CategorySpending>,
+    #[serde(rename = "multiCurrency")]
+    pub multi_currency: bool,
+    /// Native-currency detail behind `spent`, which is always in the budget's currency.
+    #[serde(rename = "byCurrency")]
+    pub by_currency: Vec<CurrencySpending>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct CategorySpending {
+    pub category: String,
+    pub spent: Money,
+}
+
+/// Spending in one currency against a budget.
+#[derive(Debug, Clone, Serialize)]
+pub struct CurrencySpending {
+    pub currency: String,
+    /// Total in this currency.
+    pub spent: Money,
+    /// The part of `spent` that could be converted, in the budget's currency.
+    pub converted: Money,
+    /// The part of `spent`, in this currency, with no exchange rate on its date; it is
+    /// left out of the budget's total.
+    pub unconverted: Money,
+}
+
+/// One materialized period of a budget, with the limit adjusted by any rollover.
+#[derive(Debug, Clone, Serialize, Deserialize, FromRow)]
+pub struct BudgetPeriod {
+    pub id: String,
+    #[serde(rename = "budgetId")]
+    pub budget_id: String,
+    #[serde(rename = "userId")]
+    pub user_id: String,
+    #[serde(rename = "periodStart")]
+    pub period_start: DateTime<Utc>,
+    #[serde(rename = "periodEnd")]
+    pub period_end: DateTime<Utc>,
+    #[serde(rename = "baseAmount")]
+    pub base_amount: Money,
+    #[serde(rename = "carriedOver")]
+    pub carried_over: Money,
+    #[serde(rename = "adjustedAmount")]
+    pub adjusted_amount: Money,
+    pub spent: Money,
+    #[serde(rename = "isClosed")]
+    pub is_closed: bool,
+    #[serde(rename = "closedAt")]
+    pub closed_at: Option<DateTime<Utc>>,
+    #[serde(rename = "createdAt")]
+    pub created_at: DateTime<Utc>,
+    #[serde(rename = "updatedAt")]
+    pub updated_at: DateTime<Utc>,
 }
 
 impl Budget {
@@ -41,12 +145,115 @@ impl Budget {
         Self {
             id: request.id.unwrap_or_else(|| Uuid::new_v4().to_string()),
             user_id,
-            category: request.category,
+            category: request
+                .category
+                .or_else(|| request.categories.as_ref().and_then(|c| c.first().cloned()))
+                .unwrap_or_default(),
             amount: request.amount,
             currency: request.currency.unwrap_or_else(|| "BDT".to_string()),
             period: request.period.unwrap_or_else(|| "monthly".to_string()),
+            rollover: request.rollover.unwrap_or(false),
+            account_id: request.account_id,
+            multi_currency: request.multi_currency.unwrap_or(false),
             created_at: now,
             updated_at: now,
+            version: 1,
+        }
+    }
+
+    /// Resolves the categories a budget covers: the join-table entries when present,
+    /// otherwise the single `category`. An empty list means every category.
+    pub fn target_categories(&self, linked: &[String]) -> Vec<String> {
+        if !linked.is_empty() {
+            linked.to_vec()
+        } else if self.category.is_empty() {
+            Vec::new()
+        } else {
+            vec![self.category.clone()]
         }
     }
+
+    /// Returns the `[start, end)` bounds of the budget period containing `at`.
+    /// Weeks start on Monday; unknown period names fall back to monthly.
+    pub fn period_bounds(&self, at: DateTime<Utc>) -> (DateTime<Utc>, DateTime<Utc>) {
+        let date = at.date_naive();
+        let (start, end) = match self.period.as_str() {
+            "daily" => (date, date + Duration::days(1)),
+            "weekly" => {
+                let start = date - Duration::days(date.weekday().num_days_from_monday() as i64);
+                (start, start + Duration::days(7))
+            }
+            "yearly" => {
+                let start = NaiveDate::from_ymd_opt(date.year(), 1, 1).unwrap_or(date);
+                (start, start + Months::new(12))
+            }
+            _ => {
+                let start = date.with_day(1).unwrap_or(date);
+                (start, start + Months::new(1))
+            }
+        };
+        (start_of_day(start), start_of_day(end))
+    }
+}
+
+impl BudgetPeriod {
+    pub fn remaining(&self) -> Money {
+        self.adjusted_amount - self.spent
+    }
+}
+
+fn start_of_day(date: NaiveDate) -> DateTime<Utc> {
+    date.and_hms_opt(0, 0, 0).unwrap_or_default().and_utc()
+}
+
+/// Periods a budget can repeat over; see [`Budget::period_bounds`].
+pub const BUDGET_PERIODS: &[&str] = &["daily", "weekly", "monthly", "yearly"];
+
+impl Validate for CreateBudgetRequest {
+    fn check_fields(&self, errors: &mut FieldErrors) {
+        if let Some(category) = &self.category {
+            errors.text("category", category);
+        }
+        if let Some(categories) = &self.categories {
+            check_categories(errors, categories);
+        }
+        errors.positive("amount", self.amount);
+        let code = self.currency.as_deref().unwrap_or("BDT");
+        errors.currency("currency", code);
+        errors.fits_currency("amount", self.amount, code);
+        if let Some(period) = &self.period {
+            errors.one_of("period", period, BUDGET_PERIODS);
+        }
+    }
+}
+
+impl Validate for UpdateBudgetRequest {
+    fn check_fields(&self, errors: &mut FieldErrors) {
+        if let Some(category) = &self.category {
+            errors.text("category", category);
+        }
+        if let Some(categories) = &self.categories {
+            check_categories(errors, categories);
+        }
+        if let Some(amount) = self.amount {
+            errors.positive("amount", amount);
+        }
+        if let Some(code) = &self.currency {
+            errors.currency("currency", code);
+            if let Some(amount) = self.amount {
+                errors.fits_currency("amount", amount, code);
+            }
+        }
+        if let Some(period) = &self.period {
+            errors.one_of("period", period, BUDGET_PERIODS);
+        }
+    }
+}
+
+fn check_categories(errors: &mut FieldErrors, categories: &[String]) {
+    errors.check(
+        categories.iter().all(|category| !category.trim().is_empty()),
+        "categories",
+        "must not contain empty names",
+    );
 }
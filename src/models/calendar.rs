@@ -0,0 +1,34 @@
+use serde::{Deserialize, Serialize};
+
+use crate::utils::money::Money;
+
+#[derive(Debug, Deserialize)]
+pub struct CalendarQuery {
+    /// `YYYY-MM`; defaults to the current month.
+    pub month: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct CalendarItem {
+    /// `liability_due`, `loan_expected`, `recurring`, `goal_deadline` or `deposit_maturity`.
+    pub kind: &'static str,
+    pub id: String,
+    pub title: String,
+    pub amount: Money,
+    pub currency: String,
+    /// `in` or `out` for money movements; `null` for goal deadlines.
+    pub direction: Option<&'static str>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct CalendarDay {
+    pub date: String,
+    pub items: Vec<CalendarItem>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct CalendarTotals {
+    pub currency: String,
+    pub inflow: Money,
+    pub outflow: Money,
+}
@@ -0,0 +1,56 @@
+use chrono::{DateTime, Utc};
+use serde::Serialize;
+use sqlx::FromRow;
+
+use crate::utils::money::Money;
+
+/// Billing settings of a credit card account with statement and due days set.
+#[derive(Debug, Clone, FromRow)]
+pub struct CardBillingCycle {
+    pub account_id: String,
+    pub user_id: String,
+    pub currency: String,
+    pub statement_day: u32,
+    pub payment_due_day: u32,
+    pub created_at: DateTime<Utc>,
+}
+
+/// One closed credit card billing cycle. Balances are amounts owed; spend and credits are the
+/// cycle's expense and income transactions on the card.
+#[derive(Debug, Clone, Serialize, FromRow)]
+pub struct CardStatement {
+    pub id: String,
+    #[serde(rename = "accountId")]
+    pub account_id: String,
+    #[serde(rename = "userId")]
+    pub user_id: String,
+    #[serde(rename = "periodStart")]
+    pub period_start: DateTime<Utc>,
+    /// Exclusive: the start of the day after the statement day.
+    #[serde(rename = "periodEnd")]
+    pub period_end: DateTime<Utc>,
+    #[serde(rename = "dueDate")]
+    pub due_date: DateTime<Utc>,
+    pub currency: String,
+    #[serde(rename = "openingBalance")]
+    pub opening_balance: Money,
+    pub spend: Money,
+    pub credits: Money,
+    #[serde(rename = "closingBalance")]
+    pub closing_balance: Money,
+    #[serde(rename = "minimumDue")]
+    pub minimum_due: Money,
+    #[serde(rename = "createdAt")]
+    pub created_at: DateTime<Utc>,
+}
+
+/// A statement with what has been paid towards it since it closed.
+#[derive(Debug, Clone, Serialize)]
+pub struct CardStatementStatus {
+    #[serde(flatten)]
+    pub statement: CardStatement,
+    #[serde(rename = "amountPaid")]
+    pub amount_paid: Money,
+    #[serde(rename = "isPaid")]
+    pub is_paid: bool,
+}
@@ -0,0 +1,109 @@
+use serde::{Deserialize, Serialize};
+use sqlx::FromRow;
+use uuid::Uuid;
+use chrono::{DateTime, Utc};
+use rust_decimal::Decimal;
+
+use crate::utils::money::Money;
+use crate::utils::validation::{FieldErrors, Validate};
+
+#[derive(Debug, Clone, Serialize, Deserialize, FromRow)]
+pub struct CashCount {
+    pub id: String,
+    #[serde(rename = "userId")]
+    pub user_id: String,
+    #[serde(rename = "accountId")]
+    pub account_id: String,
+    pub currency: String,
+    pub total: Money,
+    #[serde(rename = "previousBalance")]
+    pub previous_balance: Money,
+    pub difference: Money,
+    #[serde(rename = "adjustmentTransactionId")]
+    pub adjustment_transaction_id: Option<String>,
+    pub note: Option<String>,
+    #[serde(rename = "countedAt")]
+    pub counted_at: DateTime<Utc>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, FromRow)]
+pub struct CashCountDenomination {
+    #[serde(skip_serializing)]
+    pub cash_count_id: String,
+    pub denomination: Money,
+    pub count: i64,
+    pub subtotal: Money,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct DenominationCount {
+    #[serde(alias = "value")]
+    pub denomination: Money,
+    pub count: i64,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct CreateCashCountRequest {
+    pub denominations: Vec<DenominationCount>,
+    pub note: Option<String>,
+    /// Post an adjustment transaction and correct the balance when the count differs.
+    #[serde(alias = "reconcile")]
+    pub reconcile: Option<bool>,
+}
+
+impl CashCount {
+    pub fn new(
+        user_id: String,
+        account_id: String,
+        currency: String,
+        denominations: &[DenominationCount],
+        previous_balance: Money,
+        note: Option<String>,
+    ) -> Self {
+        let total: Money = denominations
+            .iter()
+            .map(|d| d.denomination * Decimal::from(d.count))
+            .sum();
+        Self {
+            id: Uuid::new_v4().to_string(),
+            user_id,
+            account_id,
+            currency,
+            total,
+            previous_balance,
+            difference: total - previous_balance,
+            adjustment_transaction_id: None,
+            note,
+            counted_at: Utc::now(),
+        }
+    }
+}
+
+pub struct CashDenominations;
+
+impl CashDenominations {
+    /// Common note and coin values for a currency, largest first.
+    pub fn for_currency(currency: &str) -> Vec<f64> {
+        match currency {
+            "BDT" => vec![1000.0, 500.0, 200.0, 100.0, 50.0, 20.0, 10.0, 5.0, 2.0, 1.0],
+            "INR" => vec![500.0, 200.0, 100.0, 50.0, 20.0, 10.0, 5.0, 2.0, 1.0],
+            "USD" => vec![100.0, 50.0, 20.0, 10.0, 5.0, 2.0, 1.0, 0.25, 0.10, 0.05, 0.01],
+            "EUR" => vec![500.0, 200.0, 100.0, 50.0, 20.0, 10.0, 5.0, 2.0, 1.0, 0.50, 0.20, 0.10, 0.05, 0.02, 0.01],
+            "GBP" => vec![50.0, 20.0, 10.0, 5.0, 2.0, 1.0, 0.50, 0.20, 0.10, 0.05, 0.02, 0.01],
+            _ => vec![1000.0, 500.0, 100.0, 50.0, 20.0, 10.0, 5.0, 2.0, 1.0],
+        }
+    }
+}
+
+impl Validate for CreateCashCountRequest {
+    fn check_fields(&self, errors: &mut FieldErrors) {
+        errors.check(
+            self.denominations.iter().all(|d| d.denomination.is_positive() && d.count >= 0),
+            "denominations",
+            "must have positive note values and counts that aren't negative",
+        );
+        if let Some(note) = &self.note {
+            errors.text("note", note);
+        }
+    }
+}
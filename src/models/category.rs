@@ -3,41 +3,63 @@ use sqlx::FromRow;
 use uuid::Uuid;
 use chrono::{DateTime, Utc};
 
+use crate::utils::validation::{FieldErrors, Validate};
+
 #[derive(Debug, Clone, Serialize, Deserialize, FromRow)]
 pub struct Category {
     pub id: String,
     pub name: String,
+    #[serde(rename = "categoryType")]
     pub category_type: CategoryType,
     pub icon: String,
     pub color: String,
+    #[serde(rename = "isDefault")]
     pub is_default: bool,
+    /// Archived categories are left out of pickers but still name the transactions filed under them.
+    /// Archiving is per user, so this is only filled in by queries made for one.
+    #[serde(rename = "isArchived")]
+    #[sqlx(default)]
+    pub is_archived: bool,
+    #[serde(rename = "createdAt")]
     pub created_at: DateTime<Utc>,
+    /// Empty for the built-in categories every user sees.
+    #[serde(rename = "userId")]
+    pub user_id: String,
+    #[serde(rename = "updatedAt")]
+    pub updated_at: Option<DateTime<Utc>>,
 }
 
 #[derive(Debug, Clone, Copy, Serialize, Deserialize, sqlx::Type)]
 #[sqlx(type_name = "TEXT", rename_all = "lowercase")]
+#[serde(rename_all = "lowercase")]
 pub enum CategoryType {
     #[sqlx(rename = "income")]
+    #[serde(alias = "Income")]
     Income,
     #[sqlx(rename = "expense")]
+    #[serde(alias = "Expense")]
     Expense,
 }
 
 #[derive(Debug, Deserialize)]
 pub struct CreateCategoryRequest {
     pub name: String,
+    #[serde(alias = "categoryType")]
     pub category_type: CategoryType,
     pub icon: String,
     pub color: String,
+    #[serde(alias = "isDefault")]
     pub is_default: Option<bool>,
 }
 
 #[derive(Debug, Deserialize)]
 pub struct UpdateCategoryRequest {
     pub name: Option<String>,
+    #[serde(alias = "categoryType")]
     pub category_type: Option<CategoryType>,
     pub icon: Option<String>,
     pub color: Option<String>,
+    #[serde(alias = "isDefault")]
     pub is_default: Option<bool>,
 }
 
@@ -50,117 +72,81 @@ impl Category {
             icon: request.icon,
             color: request.color,
             is_default: request.is_default.unwrap_or(false),
+            is_archived: false,
             created_at: Utc::now(),
+            user_id: String::new(),
+            updated_at: None,
         }
     }
 }
 
-pub struct DefaultCategories;
+/// `?includeArchived=true` on `GET /api/categories` lists archived categories too.
+#[derive(Debug, Default, Deserialize)]
+pub struct CategoryQuery {
+    #[serde(rename = "includeArchived", alias = "include_archived")]
+    pub include_archived: Option<bool>,
+}
+
+/// A category without identity, as preset packs list them and category exports carry them.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CategoryTemplate {
+    pub name: String,
+    #[serde(rename = "categoryType", alias = "category_type")]
+    pub category_type: CategoryType,
+    pub icon: String,
+    pub color: String,
+}
+
+/// A user's own categories, as `GET /api/categories/export` writes them and
+/// `POST /api/categories/import` reads them back.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CategorySet {
+    #[serde(default)]
+    pub format: String,
+    #[serde(default)]
+    pub version: u32,
+    #[serde(rename = "exportedAt", default)]
+    pub exported_at: Option<DateTime<Utc>>,
+    pub categories: Vec<CategoryTemplate>,
+}
 
-impl DefaultCategories {
-    pub fn get_income_categories() -> Vec<Category> {
-        vec![
-            Category {
-                id: Uuid::new_v4().to_string(),
-                name: "Salary".to_string(),
-                category_type: CategoryType::Income,
-                icon: "💰".to_string(),
-                color: "#4CAF50".to_string(),
-                is_default: true,
-                created_at: Utc::now(),
-            },
-            Category {
-                id: Uuid::new_v4().to_string(),
-                name: "Business".to_string(),
-                category_type: CategoryType::Income,
-                icon: "💼".to_string(),
-                color: "#2196F3".to_string(),
-                is_default: true,
-                created_at: Utc::now(),
-            },
-            Category {
-                id: Uuid::new_v4().to_string(),
-                name: "Investment".to_string(),
-                category_type: CategoryType::Income,
-                icon: "📈".to_string(),
-                color: "#FF9800".to_string(),
-                is_default: true,
-                created_at: Utc::now(),
-            },
-            Category {
-                id: Uuid::new_v4().to_string(),
-                name: "Gift".to_string(),
-                category_type: CategoryType::Income,
-                icon: "🎁".to_string(),
-                color: "#E91E63".to_string(),
-                is_default: true,
-                created_at: Utc::now(),
-            },
-        ]
+/// What adding a batch of categories did. Names the user can already pick, their own or
+/// built in, are skipped rather than duplicated; ones the user archived are unarchived.
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct CategoryImportSummary {
+    pub created: Vec<Category>,
+    pub unarchived: Vec<Category>,
+    pub skipped: Vec<String>,
+}
+
+impl Validate for CreateCategoryRequest {
+    fn check_fields(&self, errors: &mut FieldErrors) {
+        errors.name("name", &self.name);
+        errors.text("icon", &self.icon);
+        errors.text("color", &self.color);
     }
+}
 
-    pub fn get_expense_categories() -> Vec<Category> {
-        vec![
-            Category {
-                id: Uuid::new_v4().to_string(),
-                name: "Food".to_string(),
-                category_type: CategoryType::Expense,
-                icon: "🍔".to_string(),
-                color: "#FF5722".to_string(),
-                is_default: true,
-                created_at: Utc::now(),
-            },
-            Category {
-                id: Uuid::new_v4().to_string(),
-                name: "Transportation".to_string(),
-                category_type: CategoryType::Expense,
-                icon: "🚗".to_string(),
-                color: "#607D8B".to_string(),
-                is_default: true,
-                created_at: Utc::now(),
-            },
-            Category {
-                id: Uuid::new_v4().to_string(),
-                name: "Shopping".to_string(),
-                category_type: CategoryType::Expense,
-                icon: "🛍️".to_string(),
-                color: "#9C27B0".to_string(),
-                is_default: true,
-                created_at: Utc::now(),
-            },
-            Category {
-                id: Uuid::new_v4().to_string(),
-                name: "Entertainment".to_string(),
-                category_type: CategoryType::Expense,
-                icon: "🎬".to_string(),
-                color: "#673AB7".to_string(),
-                is_default: true,
-                created_at: Utc::now(),
-            },
-            Category {
-                id: Uuid::new_v4().to_string(),
-                name: "Bills".to_string(),
-                category_type: CategoryType::Expense,
-                icon: "💡".to_string(),
-                color: "#795548".to_string(),
-                is_default: true,
-                created_at: Utc::now(),
-            },
-            Category {
-                id: Uuid::new_v4().to_string(),
-                name: "Medical".to_string(),
-                category_type: CategoryType::Expense,
-                icon: "⚕️".to_string(),
-                color: "#F44336".to_string(),
-                is_default: true,
-                created_at: Utc::now(),
-            },
-        ]
+impl Validate for UpdateCategoryRequest {
+    fn check_fields(&self, errors: &mut FieldErrors) {
+        if let Some(name) = &self.name {
+            errors.name("name", name);
+        }
+        if let Some(icon) = &self.icon {
+            errors.text("icon", icon);
+        }
+        if let Some(color) = &self.color {
+            errors.text("color", color);
+        }
     }
+}
 
-    pub fn get_all_default_categories() -> Vec<Category> {
-        let mut categories = Self::get_income_categories();
-        categories.extend(Self::get_expense_categories());
-        categories
+impl Validate for CategorySet {
+    fn check_fields(&self, errors: &mut FieldErrors) {
+        for (index, category) in self.categories.iter().enumerate() {
+            errors.name(&format!("categories[{}].name", index), &category.name);
+            errors.text(&format!("categories[{}].icon", index), &category.icon);
+            errors.text(&format!("categories[{}].color", index), &category.color);
+        }
     }
-}
\ No newline at end of file
+}
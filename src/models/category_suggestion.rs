@@ -0,0 +1,19 @@
+use serde::{Deserialize, Serialize};
+
+use crate::models::TransactionType;
+
+#[derive(Debug, Deserialize)]
+pub struct SuggestCategoryRequest {
+    pub description: String,
+    #[serde(alias = "type", alias = "transactionType")]
+    pub transaction_type: Option<TransactionType>,
+    pub limit: Option<usize>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct CategorySuggestion {
+    pub category: String,
+    pub score: f64,
+    #[serde(rename = "matchedKeywords")]
+    pub matched_keywords: Vec<String>,
+}
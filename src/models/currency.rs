@@ -0,0 +1,168 @@
+use chrono::NaiveDate;
+use serde::{Deserialize, Serialize};
+use sqlx::FromRow;
+
+use crate::utils::money::Money;
+use crate::utils::validation::{FieldErrors, Validate};
+
+#[derive(Debug, Deserialize)]
+pub struct ConvertQuery {
+    pub from: String,
+    /// Defaults to the user's display currency.
+    pub to: Option<String>,
+    pub amount: Money,
+    /// `YYYY-MM-DD`; converts at the rate in effect that day. Defaults to today.
+    pub date: Option<NaiveDate>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct Conversion {
+    pub from: String,
+    pub to: String,
+    pub amount: Money,
+    pub rate: f64,
+    pub converted: Money,
+    pub date: NaiveDate,
+}
+
+/// A user's own rate for a currency pair: `1 base = rate quote`. Used in place of provider
+/// rates for that pair, in either direction, on every date.
+#[derive(Debug, Clone, Serialize, FromRow)]
+pub struct UserExchangeRate {
+    pub id: String,
+    #[serde(rename = "userId")]
+    pub user_id: String,
+    #[serde(rename = "baseCurrency")]
+    pub base_currency: String,
+    #[serde(rename = "quoteCurrency")]
+    pub quote_currency: String,
+    pub rate: f64,
+    #[serde(rename = "createdAt")]
+    pub created_at: String,
+    #[serde(rename = "updatedAt")]
+    pub updated_at: String,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct CreateExchangeRateRequest {
+    #[serde(alias = "baseCurrency")]
+    pub base_currency: String,
+    #[serde(alias = "quoteCurrency")]
+    pub quote_currency: String,
+    pub rate: f64,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct UpdateExchangeRateRequest {
+    pub rate: f64,
+}
+
+#[derive(Debug, Clone, Copy, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum SymbolPosition {
+    Before,
+    After,
+}
+
+/// How amounts in a currency are written, shared with clients so both sides format alike.
+#[derive(Debug, Clone, Copy, Serialize)]
+pub struct CurrencyInfo {
+    pub code: &'static str,
+    pub name: &'static str,
+    pub symbol: &'static str,
+    /// Digits after the decimal separator.
+    pub decimals: u8,
+    #[serde(rename = "symbolPosition")]
+    pub symbol_position: SymbolPosition,
+    #[serde(rename = "thousandsSeparator")]
+    pub thousands_separator: &'static str,
+    #[serde(rename = "decimalSeparator")]
+    pub decimal_separator: &'static str,
+    /// Digit group sizes from the right, the last repeating: `[3]` gives 1,000,000 and
+    /// `[3, 2]` gives the South Asian 10,00,000.
+    pub grouping: &'static [u8],
+}
+
+const fn currency(
+    code: &'static str,
+    name: &'static str,
+    symbol: &'static str,
+    decimals: u8,
+    grouping: &'static [u8],
+) -> CurrencyInfo {
+    CurrencyInfo {
+        code,
+        name,
+        symbol,
+        decimals,
+        symbol_position: SymbolPosition::Before,
+        thousands_separator: ",",
+        decimal_separator: ".",
+        grouping,
+    }
+}
+
+const WESTERN: &[u8] = &[3];
+const SOUTH_ASIAN: &[u8] = &[3, 2];
+
+pub const SUPPORTED_CURRENCIES: &[CurrencyInfo] = &[
+    currency("BDT", "Bangladeshi Taka", "৳", 2, SOUTH_ASIAN),
+    currency("INR", "Indian Rupee", "₹", 2, SOUTH_ASIAN),
+    currency("PKR", "Pakistani Rupee", "Rs", 2, WESTERN),
+    currency("NPR", "Nepalese Rupee", "Rs", 2, SOUTH_ASIAN),
+    currency("LKR", "Sri Lankan Rupee", "Rs", 2, WESTERN),
+    currency("USD", "US Dollar", "$", 2, WESTERN),
+    CurrencyInfo {
+        symbol_position: SymbolPosition::After,
+        thousands_separator: ".",
+        decimal_separator: ",",
+        ..currency("EUR", "Euro", "€", 2, WESTERN)
+    },
+    currency("GBP", "British Pound", "£", 2, WESTERN),
+    currency("CAD", "Canadian Dollar", "$", 2, WESTERN),
+    currency("AUD", "Australian Dollar", "$", 2, WESTERN),
+    currency("SGD", "Singapore Dollar", "$", 2, WESTERN),
+    currency("MYR", "Malaysian Ringgit", "RM", 2, WESTERN),
+    currency("JPY", "Japanese Yen", "¥", 0, WESTERN),
+    currency("CNY", "Chinese Yuan", "¥", 2, WESTERN),
+    CurrencyInfo { symbol_position: SymbolPosition::After, ..currency("AED", "UAE Dirham", "AED", 2, WESTERN) },
+    CurrencyInfo { symbol_position: SymbolPosition::After, ..currency("SAR", "Saudi Riyal", "SAR", 2, WESTERN) },
+    CurrencyInfo { symbol_position: SymbolPosition::After, ..currency("QAR", "Qatari Riyal", "QAR", 2, WESTERN) },
+    CurrencyInfo { symbol_position: SymbolPosition::After, ..currency("KWD", "Kuwaiti Dinar", "KWD", 3, WESTERN) },
+    CurrencyInfo { symbol_position: SymbolPosition::After, ..currency("BHD", "Bahraini Dinar", "BHD", 3, WESTERN) },
+    CurrencyInfo { symbol_position: SymbolPosition::After, ..currency("OMR", "Omani Rial", "OMR", 3, WESTERN) },
+];
+
+/// Decimals assumed for currencies not in [`SUPPORTED_CURRENCIES`], as for most ISO 4217 codes.
+const DEFAULT_DECIMALS: u8 = 2;
+
+impl CurrencyInfo {
+    pub fn find(code: &str) -> Option<&'static CurrencyInfo> {
+        SUPPORTED_CURRENCIES.iter().find(|c| c.code.eq_ignore_ascii_case(code))
+    }
+
+    /// Digits after the decimal separator for `code`, e.g. 0 for JPY and 3 for BHD.
+    pub fn decimals_for(code: &str) -> u32 {
+        Self::find(code).map_or(DEFAULT_DECIMALS, |c| c.decimals) as u32
+    }
+}
+
+fn valid_rate(rate: f64) -> bool {
+    rate.is_finite() && rate > 0.0
+}
+
+impl Validate for CreateExchangeRateRequest {
+    fn check_fields(&self, errors: &mut FieldErrors) {
+        let (base, quote) = (self.base_currency.trim(), self.quote_currency.trim());
+        errors.currency("baseCurrency", base);
+        errors.currency("quoteCurrency", quote);
+        errors.check(!base.eq_ignore_ascii_case(quote), "quoteCurrency", "must differ from baseCurrency");
+        errors.check(valid_rate(self.rate), "rate", "must be greater than zero");
+    }
+}
+
+impl Validate for UpdateExchangeRateRequest {
+    fn check_fields(&self, errors: &mut FieldErrors) {
+        errors.check(valid_rate(self.rate), "rate", "must be greater than zero");
+    }
+}
@@ -0,0 +1,62 @@
+use chrono::{DateTime, Utc};
+use serde::Serialize;
+
+use crate::models::{BudgetProgress, CalendarItem, CreditUtilization, NetWorthTotals, ReportTransaction};
+
+use crate::utils::money::Money;
+
+#[derive(Debug, Clone, Serialize)]
+pub struct AccountBalanceTotal {
+    pub currency: String,
+    pub balance: Money,
+    #[serde(rename = "accountCount")]
+    pub account_count: i64,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct UpcomingBill {
+    #[serde(rename = "dueDate")]
+    pub due_date: DateTime<Utc>,
+    #[serde(flatten)]
+    pub item: CalendarItem,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct GoalProgress {
+    pub id: String,
+    pub name: String,
+    #[serde(rename = "targetAmount")]
+    pub target_amount: Money,
+    #[serde(rename = "currentAmount")]
+    pub current_amount: Money,
+    pub currency: String,
+    #[serde(rename = "targetDate")]
+    pub target_date: DateTime<Utc>,
+    #[serde(rename = "percentComplete")]
+    pub percent_complete: f64,
+}
+
+/// Everything the app's home screen needs, in one response.
+#[derive(Debug, Clone, Serialize)]
+pub struct Dashboard {
+    /// Balances per currency, leaving out accounts flagged `exclude_from_totals`.
+    #[serde(rename = "accountTotals")]
+    pub account_totals: Vec<AccountBalanceTotal>,
+    #[serde(rename = "excludedAccounts")]
+    pub excluded_accounts: i64,
+    #[serde(rename = "netWorth")]
+    pub net_worth: Vec<NetWorthTotals>,
+    #[serde(rename = "recentTransactions")]
+    pub recent_transactions: Vec<ReportTransaction>,
+    pub budgets: Vec<BudgetProgress>,
+    /// Unpaid liabilities and recurring expenses due soon, soonest first.
+    #[serde(rename = "upcomingBills")]
+    pub upcoming_bills: Vec<UpcomingBill>,
+    /// Savings goals not yet completed.
+    pub goals: Vec<GoalProgress>,
+    /// Credit cards with a limit, and how much of it is in use.
+    #[serde(rename = "creditUtilization")]
+    pub credit_utilization: Vec<CreditUtilization>,
+    #[serde(rename = "unreadNotifications")]
+    pub unread_notifications: i64,
+}
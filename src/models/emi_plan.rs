@@ -0,0 +1,126 @@
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use sqlx::FromRow;
+
+use crate::utils::money::Money;
+use crate::utils::validation::{FieldErrors, Validate};
+
+/// A purchase paid off in equal monthly installments, posted as expenses on the account by
+/// the linked recurring transaction.
+#[derive(Debug, Clone, Serialize, FromRow)]
+pub struct EmiPlan {
+    pub id: String,
+    #[serde(rename = "userId")]
+    pub user_id: String,
+    #[serde(rename = "accountId")]
+    pub account_id: String,
+    pub name: String,
+    #[serde(rename = "purchaseAmount")]
+    pub purchase_amount: Money,
+    pub currency: String,
+    /// Percent per year on the reducing balance; `0` for a no-cost EMI.
+    #[serde(rename = "annualRate")]
+    pub annual_rate: f64,
+    pub installments: i64,
+    #[serde(rename = "installmentAmount")]
+    pub installment_amount: Money,
+    #[serde(rename = "totalInterest")]
+    pub total_interest: Money,
+    #[serde(rename = "purchaseDate")]
+    pub purchase_date: DateTime<Utc>,
+    #[serde(rename = "firstDueDate")]
+    pub first_due_date: DateTime<Utc>,
+    /// The schedule posting the installments; unset once it has been deleted.
+    #[serde(rename = "recurringTransactionId")]
+    pub recurring_transaction_id: Option<String>,
+    pub category: Option<String>,
+    pub notes: Option<String>,
+    #[serde(rename = "createdAt")]
+    pub created_at: DateTime<Utc>,
+    #[serde(rename = "updatedAt")]
+    pub updated_at: DateTime<Utc>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct CreateEmiPlanRequest {
+    #[serde(alias = "accountId")]
+    pub account_id: String,
+    pub name: String,
+    #[serde(alias = "purchaseAmount")]
+    pub purchase_amount: Money,
+    pub currency: Option<String>,
+    #[serde(alias = "annualRate")]
+    pub annual_rate: Option<f64>,
+    pub installments: u32,
+    #[serde(alias = "purchaseDate")]
+    pub purchase_date: Option<DateTime<Utc>>,
+    /// Defaults to a month after the purchase.
+    #[serde(alias = "firstDueDate")]
+    pub first_due_date: Option<DateTime<Utc>>,
+    pub category: Option<String>,
+    pub notes: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct UpdateEmiPlanRequest {
+    pub name: Option<String>,
+    pub category: Option<String>,
+    pub notes: Option<String>,
+}
+
+/// A plan with how far through its installments it is.
+#[derive(Debug, Clone, Serialize)]
+pub struct EmiPlanStatus {
+    #[serde(flatten)]
+    pub plan: EmiPlan,
+    #[serde(rename = "installmentsPosted")]
+    pub installments_posted: i64,
+    #[serde(rename = "remainingAmount")]
+    pub remaining_amount: Money,
+    #[serde(rename = "nextDueDate")]
+    pub next_due_date: Option<DateTime<Utc>>,
+    #[serde(rename = "lastDueDate")]
+    pub last_due_date: DateTime<Utc>,
+}
+
+/// Longest plan, in monthly installments.
+pub const MAX_INSTALLMENTS: u32 = 120;
+
+impl Validate for CreateEmiPlanRequest {
+    fn check_fields(&self, errors: &mut FieldErrors) {
+        errors.name("accountId", &self.account_id);
+        errors.name("name", &self.name);
+        errors.positive("purchaseAmount", self.purchase_amount);
+        if let Some(code) = &self.currency {
+            errors.currency("currency", code);
+        }
+        if let Some(annual_rate) = self.annual_rate {
+            errors.percentage("annualRate", annual_rate);
+        }
+        errors.check(
+            (1..=MAX_INSTALLMENTS).contains(&self.installments),
+            "installments",
+            &format!("must be between 1 and {}", MAX_INSTALLMENTS),
+        );
+        if let Some(purchase_date) = self.purchase_date {
+            errors.date("purchaseDate", purchase_date);
+        }
+        if let Some(first_due_date) = self.first_due_date {
+            errors.date("firstDueDate", first_due_date);
+        }
+        if let Some(notes) = &self.notes {
+            errors.text("notes", notes);
+        }
+    }
+}
+
+impl Validate for UpdateEmiPlanRequest {
+    fn check_fields(&self, errors: &mut FieldErrors) {
+        if let Some(name) = &self.name {
+            errors.name("name", name);
+        }
+        if let Some(notes) = &self.notes {
+            errors.text("notes", notes);
+        }
+    }
+}
@@ -0,0 +1,163 @@
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use sqlx::FromRow;
+
+use crate::utils::validation::{FieldErrors, Validate};
+
+/// What a member may do in a household. Owners manage it; members and owners add
+/// transactions to its shared accounts; viewers only see them.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, sqlx::Type)]
+#[sqlx(type_name = "TEXT", rename_all = "lowercase")]
+#[serde(rename_all = "lowercase")]
+pub enum HouseholdRole {
+    Owner,
+    Member,
+    Viewer,
+}
+
+impl HouseholdRole {
+    pub fn can_manage(&self) -> bool {
+        matches!(self, Self::Owner)
+    }
+
+    pub fn can_write(&self) -> bool {
+        matches!(self, Self::Owner | Self::Member)
+    }
+}
+
+/// A group of users, e.g. a couple, sharing selected accounts.
+#[derive(Debug, Clone, Serialize, FromRow)]
+pub struct Household {
+    pub id: String,
+    pub name: String,
+    #[serde(rename = "createdBy")]
+    pub created_by: String,
+    #[serde(rename = "createdAt")]
+    pub created_at: DateTime<Utc>,
+    #[serde(rename = "updatedAt")]
+    pub updated_at: DateTime<Utc>,
+}
+
+#[derive(Debug, Clone, Serialize, FromRow)]
+pub struct HouseholdMember {
+    #[serde(rename = "userId")]
+    pub user_id: String,
+    pub name: String,
+    pub email: String,
+    pub role: HouseholdRole,
+    #[serde(rename = "joinedAt")]
+    pub joined_at: DateTime<Utc>,
+}
+
+#[derive(Debug, Clone, Serialize, FromRow)]
+pub struct SharedAccount {
+    #[serde(rename = "accountId")]
+    pub account_id: String,
+    pub name: String,
+    /// The account's owner, who shared it.
+    #[serde(rename = "sharedBy")]
+    pub shared_by: String,
+    #[serde(rename = "sharedAt")]
+    pub shared_at: DateTime<Utc>,
+}
+
+/// A household as one of its members sees it.
+#[derive(Debug, Clone, Serialize)]
+pub struct HouseholdDetail {
+    #[serde(flatten)]
+    pub household: Household,
+    /// The requesting user's role.
+    pub role: HouseholdRole,
+    pub members: Vec<HouseholdMember>,
+    pub accounts: Vec<SharedAccount>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct CreateHouseholdRequest {
+    pub name: String,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct UpdateHouseholdRequest {
+    pub name: String,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct ShareAccountRequest {
+    #[serde(alias = "accountId")]
+    pub account_id: String,
+}
+
+/// An emailed invitation to join a household, accepted with its token by the user signed up
+/// under that email.
+#[derive(Debug, Clone, Serialize, FromRow)]
+pub struct HouseholdInvite {
+    pub id: String,
+    #[serde(rename = "householdId")]
+    pub household_id: String,
+    pub email: String,
+    pub role: HouseholdRole,
+    #[serde(skip_serializing)]
+    pub token: String,
+    #[serde(rename = "invitedBy")]
+    pub invited_by: String,
+    #[serde(rename = "expiresAt")]
+    pub expires_at: DateTime<Utc>,
+    #[serde(rename = "acceptedAt")]
+    pub accepted_at: Option<DateTime<Utc>>,
+    #[serde(rename = "createdAt")]
+    pub created_at: DateTime<Utc>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct CreateHouseholdInviteRequest {
+    pub email: String,
+    /// Defaults to `member`.
+    pub role: Option<HouseholdRole>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct AcceptHouseholdInviteRequest {
+    pub token: String,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct UpdateHouseholdMemberRequest {
+    pub role: HouseholdRole,
+}
+
+const MAX_NAME_LENGTH: usize = 100;
+
+fn check_name(errors: &mut FieldErrors, name: &str) {
+    errors.name("name", name);
+    errors.check(
+        name.trim().chars().count() <= MAX_NAME_LENGTH,
+        "name",
+        &format!("must be at most {} characters", MAX_NAME_LENGTH),
+    );
+}
+
+impl Validate for CreateHouseholdRequest {
+    fn check_fields(&self, errors: &mut FieldErrors) {
+        check_name(errors, &self.name);
+    }
+}
+
+impl Validate for UpdateHouseholdRequest {
+    fn check_fields(&self, errors: &mut FieldErrors) {
+        check_name(errors, &self.name);
+    }
+}
+
+impl Validate for CreateHouseholdInviteRequest {
+    fn check_fields(&self, errors: &mut FieldErrors) {
+        let email = self.email.trim();
+        errors.check(
+            email
+                .split_once('@')
+                .is_some_and(|(local, domain)| !local.is_empty() && domain.contains('.') && !email.contains(char::is_whitespace)),
+            "email",
+            "must be an email address",
+        );
+    }
+}
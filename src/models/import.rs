@@ -0,0 +1,121 @@
+use serde::{Deserialize, Serialize};
+use chrono::{DateTime, Utc};
+
+use super::TransactionType;
+
+use crate::utils::money::Money;
+
+#[derive(Debug, Deserialize)]
+pub struct StatementImportRequest {
+    /// `qif` or `ofx`; detected from the content when omitted.
+    pub format: Option<String>,
+    pub content: String,
+    /// Account the statement belongs to; otherwise matched from the statement's account info.
+    #[serde(alias = "accountId")]
+    pub account_id: Option<String>,
+    #[serde(alias = "dryRun")]
+    pub dry_run: Option<bool>,
+    pub currency: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct AppImportRequest {
+    /// Source app: `money_manager`, `wallet`, `ynab` or `mint`.
+    pub app: String,
+    /// Raw export file contents.
+    pub content: String,
+    #[serde(alias = "dryRun")]
+    pub dry_run: Option<bool>,
+    /// Currency used when a row does not carry one.
+    pub currency: Option<String>,
+}
+
+/// Which CSV header holds each transaction field, for files from banks and spreadsheets without
+/// a dedicated adapter. Header names are matched case-insensitively.
+#[derive(Debug, Clone, Deserialize)]
+pub struct CsvColumnMapping {
+    pub date: String,
+    /// Signed amount; negative values are expenses unless a type column says otherwise.
+    pub amount: Option<String>,
+    /// Separate outflow and inflow columns, used instead of `amount`.
+    pub debit: Option<String>,
+    pub credit: Option<String>,
+    /// Column holding `income`, `expense` or `transfer` (or `credit` / `debit`).
+    #[serde(rename = "type", alias = "transactionType")]
+    pub transaction_type: Option<String>,
+    pub account: Option<String>,
+    pub category: Option<String>,
+    pub description: Option<String>,
+    pub currency: Option<String>,
+    /// chrono format such as `%d/%m/%Y`; common layouts are recognised when omitted.
+    #[serde(alias = "dateFormat")]
+    pub date_format: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct CsvImportRequest {
+    pub content: String,
+    pub mapping: CsvColumnMapping,
+    /// Field separator; detected from the header line when omitted.
+    pub delimiter: Option<char>,
+    /// Account every row is imported into; required when the mapping has no account column.
+    #[serde(alias = "accountId")]
+    pub account_id: Option<String>,
+    #[serde(alias = "dryRun")]
+    pub dry_run: Option<bool>,
+    pub currency: Option<String>,
+}
+
+/// A transaction row parsed out of an external export, before it is mapped onto this user's data.
+#[derive(Debug, Clone, Serialize)]
+pub struct ImportedTransaction {
+    pub row: usize,
+    pub account: String,
+    pub currency: Option<String>,
+    #[serde(rename = "type")]
+    pub transaction_type: TransactionType,
+    pub amount: Money,
+    pub category: Option<String>,
+    pub description: Option<String>,
+    pub date: DateTime<Utc>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct ImportRowError {
+    pub row: usize,
+    pub message: String,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct ImportedCategory {
+    pub name: String,
+    #[serde(rename = "type")]
+    pub category_type: String,
+}
+
+/// What an import would do; returned as-is for dry runs.
+#[derive(Debug, Clone, Serialize)]
+pub struct ImportPreview {
+    #[serde(rename = "accountsToCreate")]
+    pub accounts_to_create: Vec<String>,
+    #[serde(rename = "categoriesToCreate")]
+    pub categories_to_create: Vec<ImportedCategory>,
+    pub transactions: Vec<ImportedTransaction>,
+    /// Rows matching a transaction already stored for the same account; never imported.
+    pub duplicates: Vec<ImportedTransaction>,
+    pub skipped: usize,
+    pub errors: Vec<ImportRowError>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct ImportSummary {
+    #[serde(rename = "accountsCreated")]
+    pub accounts_created: usize,
+    #[serde(rename = "categoriesCreated")]
+    pub categories_created: usize,
+    #[serde(rename = "transactionsImported")]
+    pub transactions_imported: usize,
+    pub duplicates: usize,
+    pub skipped: usize,
+    pub errors: Vec<ImportRowError>,
+}
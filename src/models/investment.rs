@@ -0,0 +1,149 @@
+use serde::{Deserialize, Serialize};
+use sqlx::FromRow;
+
+use crate::utils::money::Money;
+use crate::utils::validation::{FieldErrors, Validate};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, sqlx::Type)]
+#[sqlx(type_name = "TEXT", rename_all = "snake_case")]
+#[serde(rename_all = "snake_case")]
+pub enum AssetType {
+    Stock,
+    #[serde(alias = "mutualFund")]
+    MutualFund,
+    Crypto,
+}
+
+impl AssetType {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Stock => "stock",
+            Self::MutualFund => "mutual_fund",
+            Self::Crypto => "crypto",
+        }
+    }
+}
+
+/// A position in a market-priced asset. `cost_basis` is the total paid for `quantity`, in
+/// `currency`, which is also the currency the holding is valued in.
+#[derive(Debug, Clone, Serialize, FromRow)]
+pub struct Holding {
+    pub id: String,
+    #[serde(rename = "userId")]
+    pub user_id: String,
+    #[serde(rename = "accountId")]
+    pub account_id: Option<String>,
+    #[serde(rename = "assetType")]
+    pub asset_type: AssetType,
+    /// Ticker as the price provider knows it, e.g. `AAPL`, `VFIAX`, or `bitcoin` for crypto.
+    pub symbol: String,
+    pub name: Option<String>,
+    pub quantity: f64,
+    #[serde(rename = "costBasis")]
+    pub cost_basis: Money,
+    pub currency: String,
+    #[serde(rename = "createdAt")]
+    pub created_at: String,
+    #[serde(rename = "updatedAt")]
+    pub updated_at: String,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct CreateHoldingRequest {
+    #[serde(alias = "accountId")]
+    pub account_id: Option<String>,
+    #[serde(alias = "assetType")]
+    pub asset_type: AssetType,
+    pub symbol: String,
+    pub name: Option<String>,
+    pub quantity: f64,
+    #[serde(alias = "costBasis")]
+    pub cost_basis: Option<Money>,
+    pub currency: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct UpdateHoldingRequest {
+    #[serde(alias = "accountId")]
+    pub account_id: Option<String>,
+    pub name: Option<String>,
+    pub quantity: Option<f64>,
+    #[serde(alias = "costBasis")]
+    pub cost_basis: Option<Money>,
+    pub currency: Option<String>,
+}
+
+/// The last price fetched for a symbol, shared by every user holding it.
+#[derive(Debug, Clone, Serialize, FromRow)]
+pub struct MarketQuote {
+    #[serde(rename = "assetType")]
+    pub asset_type: AssetType,
+    pub symbol: String,
+    /// Price of one unit in `currency`. Kept as a plain number since it can be far smaller
+    /// than a money amount's precision, e.g. for low-priced tokens.
+    pub price: f64,
+    pub currency: String,
+    #[serde(rename = "fetchedAt")]
+    pub fetched_at: String,
+}
+
+/// A holding valued at its cached quote, converted into the holding's currency.
+#[derive(Debug, Clone, Serialize)]
+pub struct HoldingValuation {
+    #[serde(flatten)]
+    pub holding: Holding,
+    pub quote: Option<MarketQuote>,
+    /// `None` without a quote, or without a rate from the quote's currency.
+    #[serde(rename = "marketValue")]
+    pub market_value: Option<Money>,
+    pub gain: Option<Money>,
+}
+
+impl HoldingValuation {
+    /// Market value when known, otherwise what was paid, so totals never drop a holding.
+    pub fn value_or_cost(&self) -> Money {
+        self.market_value.unwrap_or(self.holding.cost_basis)
+    }
+}
+
+/// Symbols go into provider URLs as-is, so only ticker characters are allowed.
+fn valid_symbol(symbol: &str) -> bool {
+    !symbol.is_empty()
+        && symbol.len() <= 32
+        && symbol.chars().all(|c| c.is_ascii_alphanumeric() || matches!(c, '.' | '-' | '_' | '^' | '='))
+}
+
+fn valid_quantity(quantity: f64) -> bool {
+    quantity.is_finite() && quantity >= 0.0
+}
+
+impl Validate for CreateHoldingRequest {
+    fn check_fields(&self, errors: &mut FieldErrors) {
+        errors.check(valid_symbol(self.symbol.trim()), "symbol", "must be a ticker symbol");
+        if let Some(name) = &self.name {
+            errors.text("name", name);
+        }
+        errors.check(valid_quantity(self.quantity), "quantity", "must not be negative");
+        if let Some(cost_basis) = self.cost_basis {
+            errors.not_negative("costBasis", cost_basis);
+        }
+        if let Some(code) = &self.currency {
+            errors.currency("currency", code);
+        }
+    }
+}
+
+impl Validate for UpdateHoldingRequest {
+    fn check_fields(&self, errors: &mut FieldErrors) {
+        if let Some(name) = &self.name {
+            errors.text("name", name);
+        }
+        errors.check(self.quantity.is_none_or(valid_quantity), "quantity", "must not be negative");
+        if let Some(cost_basis) = self.cost_basis {
+            errors.not_negative("costBasis", cost_basis);
+        }
+        if let Some(code) = &self.currency {
+            errors.currency("currency", code);
+        }
+    }
+}
@@ -3,6 +3,9 @@ use sqlx::FromRow;
 use uuid::Uuid;
 use chrono::{DateTime, Utc};
 
+use crate::utils::money::Money;
+use crate::utils::validation::{FieldErrors, Validate};
+
 #[derive(Debug, Clone, Serialize, Deserialize, FromRow)]
 pub struct Liability {
     pub id: String,
@@ -10,7 +13,7 @@ pub struct Liability {
     pub user_id: String,
     #[serde(rename = "personName")]
     pub person_name: String,
-    pub amount: f64,
+    pub amount: Money,
     pub currency: String,
     #[serde(rename = "dueDate")]
     pub due_date: DateTime<Utc>,
@@ -32,27 +35,39 @@ pub struct Liability {
 #[derive(Debug, Deserialize)]
 pub struct CreateLiabilityRequest {
     pub id: Option<String>,
+    #[serde(alias = "personName")]
     pub person_name: String,
-    pub amount: f64,
+    pub amount: Money,
     pub currency: Option<String>,
+    #[serde(alias = "dueDate")]
     pub due_date: DateTime<Utc>,
+    #[serde(alias = "isPaid")]
     pub is_paid: Option<bool>,
     pub description: Option<String>,
+    #[serde(alias = "isHistoricalEntry")]
     pub is_historical_entry: Option<bool>,
+    #[serde(alias = "accountId")]
     pub account_id: Option<String>,
+    #[serde(alias = "transactionId")]
     pub transaction_id: Option<String>,
 }
 
 #[derive(Debug, Deserialize)]
 pub struct UpdateLiabilityRequest {
+    #[serde(alias = "personName")]
     pub person_name: Option<String>,
-    pub amount: Option<f64>,
+    pub amount: Option<Money>,
     pub currency: Option<String>,
+    #[serde(alias = "dueDate")]
     pub due_date: Option<DateTime<Utc>>,
+    #[serde(alias = "isPaid")]
     pub is_paid: Option<bool>,
     pub description: Option<String>,
+    #[serde(alias = "isHistoricalEntry")]
     pub is_historical_entry: Option<bool>,
+    #[serde(alias = "accountId")]
     pub account_id: Option<String>,
+    #[serde(alias = "transactionId")]
     pub transaction_id: Option<String>,
 }
 
@@ -75,16 +90,38 @@ impl Liability {
             transaction_id: request.transaction_id,
         }
     }
+}
 
-    pub fn is_overdue(&self) -> bool {
-        !self.is_paid && self.due_date < Utc::now()
+impl Validate for CreateLiabilityRequest {
+    fn check_fields(&self, errors: &mut FieldErrors) {
+        errors.name("personName", &self.person_name);
+        errors.positive("amount", self.amount);
+        let code = self.currency.as_deref().unwrap_or("BDT");
+        errors.currency("currency", code);
+        errors.fits_currency("amount", self.amount, code);
+        errors.date("dueDate", self.due_date);
+        if let Some(description) = &self.description {
+            errors.text("description", description);
+        }
     }
+}
 
-    pub fn days_until_due(&self) -> i64 {
-        if self.is_paid {
-            0
-        } else {
-            (self.due_date - Utc::now()).num_days()
+impl Validate for UpdateLiabilityRequest {
+    fn check_fields(&self, errors: &mut FieldErrors) {
+        if let Some(person_name) = &self.person_name {
+            errors.name("personName", person_name);
+        }
+        if let Some(amount) = self.amount {
+            errors.positive("amount", amount);
+        }
+        if let Some(code) = &self.currency {
+            errors.currency("currency", code);
+        }
+        if let Some(due_date) = self.due_date {
+            errors.date("dueDate", due_date);
+        }
+        if let Some(description) = &self.description {
+            errors.text("description", description);
         }
     }
 }
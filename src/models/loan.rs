@@ -3,6 +3,9 @@ use sqlx::FromRow;
 use uuid::Uuid;
 use chrono::{DateTime, Utc};
 
+use crate::utils::money::Money;
+use crate::utils::validation::{FieldErrors, Validate};
+
 #[derive(Debug, Clone, Serialize, Deserialize, FromRow)]
 pub struct Loan {
     pub id: String,
@@ -10,7 +13,7 @@ pub struct Loan {
     pub user_id: String,
     #[serde(rename = "personName")]
     pub person_name: String,
-    pub amount: f64,
+    pub amount: Money,
     pub currency: String,
     #[serde(rename = "loanDate")]
     pub loan_date: DateTime<Utc>,
@@ -34,29 +37,43 @@ pub struct Loan {
 #[derive(Debug, Deserialize)]
 pub struct CreateLoanRequest {
     pub id: Option<String>,
+    #[serde(alias = "personName")]
     pub person_name: String,
-    pub amount: f64,
+    pub amount: Money,
     pub currency: Option<String>,
+    #[serde(alias = "loanDate")]
     pub loan_date: DateTime<Utc>,
+    #[serde(alias = "returnDate")]
     pub return_date: Option<DateTime<Utc>>,
+    #[serde(alias = "isReturned")]
     pub is_returned: Option<bool>,
     pub description: Option<String>,
+    #[serde(alias = "isHistoricalEntry")]
     pub is_historical_entry: Option<bool>,
+    #[serde(alias = "accountId")]
     pub account_id: Option<String>,
+    #[serde(alias = "transactionId")]
     pub transaction_id: Option<String>,
 }
 
 #[derive(Debug, Deserialize)]
 pub struct UpdateLoanRequest {
+    #[serde(alias = "personName")]
     pub person_name: Option<String>,
-    pub amount: Option<f64>,
+    pub amount: Option<Money>,
     pub currency: Option<String>,
+    #[serde(alias = "loanDate")]
     pub loan_date: Option<DateTime<Utc>>,
+    #[serde(alias = "returnDate")]
     pub return_date: Option<DateTime<Utc>>,
+    #[serde(alias = "isReturned")]
     pub is_returned: Option<bool>,
     pub description: Option<String>,
+    #[serde(alias = "isHistoricalEntry")]
     pub is_historical_entry: Option<bool>,
+    #[serde(alias = "accountId")]
     pub account_id: Option<String>,
+    #[serde(alias = "transactionId")]
     pub transaction_id: Option<String>,
 }
 
@@ -81,3 +98,47 @@ impl Loan {
         }
     }
 }
+
+impl Validate for CreateLoanRequest {
+    fn check_fields(&self, errors: &mut FieldErrors) {
+        errors.name("personName", &self.person_name);
+        errors.positive("amount", self.amount);
+        let code = self.currency.as_deref().unwrap_or("BDT");
+        errors.currency("currency", code);
+        errors.fits_currency("amount", self.amount, code);
+        errors.date("loanDate", self.loan_date);
+        if let Some(return_date) = self.return_date {
+            errors.date("returnDate", return_date);
+            errors.not_before("returnDate", return_date, self.loan_date, "loanDate");
+        }
+        if let Some(description) = &self.description {
+            errors.text("description", description);
+        }
+    }
+}
+
+impl Validate for UpdateLoanRequest {
+    fn check_fields(&self, errors: &mut FieldErrors) {
+        if let Some(person_name) = &self.person_name {
+            errors.name("personName", person_name);
+        }
+        if let Some(amount) = self.amount {
+            errors.positive("amount", amount);
+        }
+        if let Some(code) = &self.currency {
+            errors.currency("currency", code);
+        }
+        if let Some(loan_date) = self.loan_date {
+            errors.date("loanDate", loan_date);
+        }
+        if let Some(return_date) = self.return_date {
+            errors.date("returnDate", return_date);
+            if let Some(loan_date) = self.loan_date {
+                errors.not_before("returnDate", return_date, loan_date, "loanDate");
+            }
+        }
+        if let Some(description) = &self.description {
+            errors.text("description", description);
+        }
+    }
+}
@@ -8,6 +8,34 @@ pub mod user_preference;
 pub mod savings_goal;
 pub mod budget;
 pub mod recurring_transaction;
+pub mod recurrence;
+pub mod category_suggestion;
+pub mod amortization;
+pub mod cash_count;
+pub mod event;
+pub mod import;
+pub mod usage;
+pub mod calendar;
+pub mod net_worth;
+pub mod notification;
+pub mod device;
+pub mod webhook;
+pub mod report;
+pub mod dashboard;
+pub mod archive;
+pub mod currency;
+pub mod investment;
+pub mod term_deposit;
+pub mod card_statement;
+pub mod emi_plan;
+pub mod subscription;
+pub mod household;
+pub mod split;
+pub mod reminder;
+pub mod sync;
+pub mod admin;
+pub mod quota;
+pub mod reconciliation;
 
 pub use account::*;
 pub use category::*;
@@ -18,4 +46,32 @@ pub use user::*;
 pub use user_preference::*;
 pub use savings_goal::*;
 pub use budget::*;
-pub use recurring_transaction::*;
\ No newline at end of file
+pub use recurring_transaction::*;
+pub use recurrence::*;
+pub use category_suggestion::*;
+pub use amortization::*;
+pub use cash_count::*;
+pub use event::*;
+pub use import::*;
+pub use usage::*;
+pub use calendar::*;
+pub use net_worth::*;
+pub use notification::*;
+pub use device::*;
+pub use webhook::*;
+pub use report::*;
+pub use dashboard::*;
+pub use archive::*;
+pub use currency::*;
+pub use investment::*;
+pub use term_deposit::*;
+pub use card_statement::*;
+pub use emi_plan::*;
+pub use subscription::*;
+pub use household::*;
+pub use split::*;
+pub use reminder::*;
+pub use sync::*;
+pub use admin::*;
+pub use quota::*;
+pub use reconciliation::*;
@@ -0,0 +1,29 @@
+use serde::Serialize;
+
+use crate::utils::money::Money;
+
+#[derive(Debug, Clone, Serialize)]
+pub struct NetWorthTotals {
+    pub currency: String,
+    /// Positive account balances, loans still owed back to the user, and investments.
+    pub assets: Money,
+    /// Holdings at market value (at cost where no price is known); part of `assets`.
+    pub investments: Money,
+    /// Overdrawn/credit card balances plus unpaid liabilities.
+    pub debts: Money,
+    #[serde(rename = "netWorth")]
+    pub net_worth: Money,
+}
+
+/// Net worth with every currency converted into one, at today's rates.
+#[derive(Debug, Clone, Serialize)]
+pub struct ConsolidatedNetWorth {
+    pub currency: String,
+    pub assets: Money,
+    pub debts: Money,
+    #[serde(rename = "netWorth")]
+    pub net_worth: Money,
+    /// Currencies with no known rate into `currency`; their amounts are not included.
+    #[serde(rename = "unconvertedCurrencies")]
+    pub unconverted_currencies: Vec<String>,
+}
@@ -0,0 +1,25 @@
+use serde::{Deserialize, Serialize};
+use sqlx::FromRow;
+
+#[derive(Debug, Clone, Serialize, FromRow)]
+pub struct Notification {
+    pub id: String,
+    #[serde(rename = "userId")]
+    pub user_id: String,
+    pub kind: String,
+    pub title: String,
+    pub body: String,
+    #[serde(rename = "entityType")]
+    pub entity_type: Option<String>,
+    #[serde(rename = "entityId")]
+    pub entity_id: Option<String>,
+    #[serde(rename = "readAt")]
+    pub read_at: Option<String>,
+    #[serde(rename = "createdAt")]
+    pub created_at: String,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct NotificationQuery {
+    pub unread: Option<bool>,
+}
@@ -0,0 +1,66 @@
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use sqlx::FromRow;
+
+/// What a user may use: the `quotas` settings with their `user_quotas` row on top. `None`
+/// means no limit.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+pub struct Quotas {
+    #[serde(rename = "requestsPerMinute")]
+    pub requests_per_minute: Option<u64>,
+    #[serde(rename = "syncBatchSize")]
+    pub sync_batch_size: Option<u64>,
+    #[serde(rename = "dailyUploadBytes")]
+    pub daily_upload_bytes: Option<u64>,
+}
+
+/// A `user_quotas` row; a NULL column keeps the configured default.
+#[derive(Debug, Clone, FromRow)]
+pub struct UserQuotaOverride {
+    pub requests_per_minute: Option<i64>,
+    pub sync_batch_size: Option<i64>,
+    pub daily_upload_bytes: Option<i64>,
+}
+
+/// How much of a quota that refills over time has been used.
+#[derive(Debug, Clone, Serialize)]
+pub struct QuotaUsage {
+    pub limit: Option<u64>,
+    pub used: u64,
+    pub remaining: Option<u64>,
+    #[serde(rename = "resetsAt")]
+    pub resets_at: DateTime<Utc>,
+}
+
+impl QuotaUsage {
+    pub fn new(limit: Option<u64>, used: u64, resets_at: DateTime<Utc>) -> Self {
+        Self {
+            limit,
+            used,
+            remaining: limit.map(|limit| limit.saturating_sub(used)),
+            resets_at,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct QuotaStatus {
+    #[serde(rename = "requestsPerMinute")]
+    pub requests_per_minute: QuotaUsage,
+    #[serde(rename = "dailyUploadBytes")]
+    pub daily_upload_bytes: QuotaUsage,
+    #[serde(rename = "syncBatchSize")]
+    pub sync_batch_size: Option<u64>,
+}
+
+/// An admin's overrides for one user, replacing any earlier ones. A missing field goes back
+/// to the configured default; 0 lifts the limit.
+#[derive(Debug, Clone, Default, Deserialize)]
+pub struct UpdateUserQuotaRequest {
+    #[serde(alias = "requestsPerMinute")]
+    pub requests_per_minute: Option<u64>,
+    #[serde(alias = "syncBatchSize")]
+    pub sync_batch_size: Option<u64>,
+    #[serde(alias = "dailyUploadBytes")]
+    pub daily_upload_bytes: Option<u64>,
+}
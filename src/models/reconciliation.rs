@@ -0,0 +1,87 @@
+use std::collections::HashSet;
+
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use sqlx::FromRow;
+
+use crate::models::Transaction;
+use crate::utils::money::Money;
+use crate::utils::validation::{FieldErrors, Validate};
+
+/// Checking an account against a bank statement: the user marks the transactions the statement
+/// shows as cleared until the cleared balance matches the statement's closing balance.
+#[derive(Debug, Clone, Serialize, FromRow)]
+pub struct Reconciliation {
+    pub id: String,
+    #[serde(rename = "accountId")]
+    pub account_id: String,
+    #[serde(rename = "userId")]
+    pub user_id: String,
+    /// The day the statement closes; transactions up to the end of it, in the user's timezone,
+    /// can be cleared.
+    #[serde(rename = "statementDate")]
+    pub statement_date: DateTime<Utc>,
+    #[serde(rename = "statementBalance")]
+    pub statement_balance: Money,
+    pub status: ReconciliationStatus,
+    #[serde(rename = "createdAt")]
+    pub created_at: DateTime<Utc>,
+    #[serde(rename = "completedAt")]
+    pub completed_at: Option<DateTime<Utc>>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, sqlx::Type)]
+#[sqlx(type_name = "TEXT", rename_all = "lowercase")]
+#[serde(rename_all = "lowercase")]
+pub enum ReconciliationStatus {
+    Open,
+    Completed,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct CreateReconciliationRequest {
+    #[serde(alias = "statementDate")]
+    pub statement_date: DateTime<Utc>,
+    #[serde(alias = "statementBalance")]
+    pub statement_balance: Money,
+}
+
+/// Transactions to mark as cleared against a reconciliation, or to take back.
+#[derive(Debug, Deserialize)]
+pub struct ClearTransactionsRequest {
+    #[serde(alias = "transactionIds")]
+    pub transaction_ids: Vec<String>,
+}
+
+/// Where a reconciliation stands. The cleared balance is the account's balance without the
+/// transactions no reconciliation has cleared yet; a completed reconciliation has no difference.
+#[derive(Debug, Clone, Serialize)]
+pub struct ReconciliationSummary {
+    #[serde(flatten)]
+    pub reconciliation: Reconciliation,
+    pub currency: String,
+    #[serde(rename = "clearedBalance")]
+    pub cleared_balance: Money,
+    /// The statement balance less the cleared balance: what is still unexplained.
+    pub difference: Money,
+    /// Transactions cleared by this reconciliation.
+    #[serde(rename = "clearedTransactionIds")]
+    pub cleared_transaction_ids: Vec<String>,
+    /// Transactions up to the statement date that no reconciliation has cleared, oldest first.
+    pub uncleared: Vec<Transaction>,
+}
+
+impl Validate for CreateReconciliationRequest {
+    fn check_fields(&self, errors: &mut FieldErrors) {
+        errors.date("statementDate", self.statement_date);
+        errors.check(self.statement_date <= Utc::now(), "statementDate", "must not be in the future");
+    }
+}
+
+impl Validate for ClearTransactionsRequest {
+    fn check_fields(&self, errors: &mut FieldErrors) {
+        let mut seen = HashSet::new();
+        errors.check(!self.transaction_ids.is_empty(), "transactionIds", "must list at least one transaction");
+        errors.check(self.transaction_ids.iter().all(|id| seen.insert(id)), "transactionIds", "must not repeat a transaction");
+    }
+}
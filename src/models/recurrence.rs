@@ -0,0 +1,173 @@
+use std::fmt;
+
+use chrono::{DateTime, Datelike, Duration, Months, Utc, Weekday};
+use serde::{Deserialize, Serialize};
+
+use crate::utils::datetime::days_in_month;
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Frequency {
+    Daily,
+    Weekly,
+    Monthly,
+    Yearly,
+}
+
+impl Frequency {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Daily => "daily",
+            Self::Weekly => "weekly",
+            Self::Monthly => "monthly",
+            Self::Yearly => "yearly",
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct RecurrenceError(pub String);
+
+impl fmt::Display for RecurrenceError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.0)
+    }
+}
+
+impl std::error::Error for RecurrenceError {}
+
+/// When a recurring transaction repeats: every `interval` days/weeks/months/years, optionally
+/// pinned to a weekday (weekly) or day of month (monthly/yearly). Occurrences are counted from
+/// the schedule's start date.
+#[derive(Debug, Clone, PartialEq)]
+pub struct RecurrenceRule {
+    pub frequency: Frequency,
+    pub interval: u32,
+    pub day_of_month: Option<u32>,
+    pub weekday: Option<Weekday>,
+}
+
+pub const MAX_RECURRENCE_INTERVAL: u32 = 366;
+
+/// Parses weekday names (`monday`, `mon`) and ISO numbers (1 = Monday .. 7 = Sunday).
+pub fn parse_weekday(value: &str) -> Option<Weekday> {
+    if let Ok(number) = value.trim().parse::<u32>() {
+        return match number {
+            1..=7 => Weekday::try_from((number - 1) as u8).ok(),
+            _ => None,
+        };
+    }
+    value.trim().parse::<Weekday>().ok()
+}
+
+pub fn weekday_name(weekday: Weekday) -> &'static str {
+    match weekday {
+        Weekday::Mon => "monday",
+        Weekday::Tue => "tuesday",
+        Weekday::Wed => "wednesday",
+        Weekday::Thu => "thursday",
+        Weekday::Fri => "friday",
+        Weekday::Sat => "saturday",
+        Weekday::Sun => "sunday",
+    }
+}
+
+impl RecurrenceRule {
+    /// Builds a validated rule. Legacy frequency strings (`biweekly`, `quarterly`, `annually`)
+    /// are mapped onto a base frequency and multiplied into the interval.
+    pub fn parse(
+        frequency: &str,
+        interval: Option<u32>,
+        day_of_month: Option<u32>,
+        weekday: Option<&str>,
+    ) -> Result<Self, RecurrenceError> {
+        let (frequency, multiplier) = match frequency.trim().to_lowercase().as_str() {
+            "daily" => (Frequency::Daily, 1),
+            "weekly" => (Frequency::Weekly, 1),
+            "biweekly" => (Frequency::Weekly, 2),
+            "monthly" => (Frequency::Monthly, 1),
+            "quarterly" => (Frequency::Monthly, 3),
+            "yearly" | "annually" => (Frequency::Yearly, 1),
+            other => return Err(RecurrenceError(format!("Unknown frequency '{}'", other))),
+        };
+
+        let interval = interval.unwrap_or(1);
+        if interval == 0 || interval > MAX_RECURRENCE_INTERVAL {
+            return Err(RecurrenceError(format!(
+                "interval must be between 1 and {}",
+                MAX_RECURRENCE_INTERVAL
+            )));
+        }
+
+        let weekday = match weekday {
+            Some(value) => Some(
+                parse_weekday(value).ok_or_else(|| RecurrenceError(format!("Unknown weekday '{}'", value)))?,
+            ),
+            None => None,
+        };
+
+        if weekday.is_some() && frequency != Frequency::Weekly {
+            return Err(RecurrenceError("weekday is only valid for weekly schedules".to_string()));
+        }
+        if let Some(day) = day_of_month {
+            if !matches!(frequency, Frequency::Monthly | Frequency::Yearly) {
+                return Err(RecurrenceError(
+                    "dayOfMonth is only valid for monthly or yearly schedules".to_string(),
+                ));
+            }
+            if !(1..=31).contains(&day) {
+                return Err(RecurrenceError("dayOfMonth must be between 1 and 31".to_string()));
+            }
+        }
+
+        Ok(Self {
+            frequency,
+            interval: interval * multiplier,
+            day_of_month,
+            weekday,
+        })
+    }
+
+    /// The `k`-th candidate occurrence counted from `start` (candidates before `start` are possible
+    /// for weekday/day-of-month rules and are filtered out by the callers).
+    fn candidate(&self, start: DateTime<Utc>, k: u32) -> Option<DateTime<Utc>> {
+        let step = k.checked_mul(self.interval)?;
+        match self.frequency {
+            Frequency::Daily => Some(start + Duration::days(step as i64)),
+            Frequency::Weekly => {
+                let week_start = start - Duration::days(start.weekday().num_days_from_monday() as i64);
+                let weekday = self.weekday.unwrap_or(start.weekday());
+                Some(week_start + Duration::weeks(step as i64) + Duration::days(weekday.num_days_from_monday() as i64))
+            }
+            Frequency::Monthly | Frequency::Yearly => {
+                let months = if self.frequency == Frequency::Yearly { step.checked_mul(12)? } else { step };
+                let first_of_month = start.with_day(1)?.checked_add_months(Months::new(months))?;
+                let wanted = self.day_of_month.unwrap_or(start.day());
+                let day = wanted.min(days_in_month(first_of_month.year(), first_of_month.month()));
+                first_of_month.with_day(day)
+            }
+        }
+    }
+
+    /// First occurrence at or after `at`, for a schedule that began on `start`.
+    pub fn next_on_or_after(&self, start: DateTime<Utc>, at: DateTime<Utc>) -> Option<DateTime<Utc>> {
+        let at = at.max(start);
+        let elapsed = match self.frequency {
+            Frequency::Daily => (at - start).num_days(),
+            Frequency::Weekly => (at - start).num_weeks(),
+            Frequency::Monthly => ((at.year() - start.year()) * 12 + at.month() as i32 - start.month() as i32) as i64,
+            Frequency::Yearly => (at.year() - start.year()) as i64,
+        };
+        let estimate = (elapsed.max(0) as u32 / self.interval).saturating_sub(1);
+
+        // The estimate lands at most a couple of steps early; walk forward from it.
+        (estimate..estimate + 8)
+            .filter_map(|k| self.candidate(start, k))
+            .find(|candidate| *candidate >= at)
+    }
+
+    /// Occurrence following `previous`.
+    pub fn next_after(&self, start: DateTime<Utc>, previous: DateTime<Utc>) -> Option<DateTime<Utc>> {
+        self.next_on_or_after(start, previous + Duration::seconds(1))
+    }
+}
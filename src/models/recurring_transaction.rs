@@ -3,6 +3,11 @@ use sqlx::FromRow;
 use uuid::Uuid;
 use chrono::{DateTime, Utc};
 
+use super::recurrence::{weekday_name, RecurrenceError, RecurrenceRule};
+
+use crate::utils::money::Money;
+use crate::utils::validation::{FieldErrors, Validate};
+
 #[derive(Debug, Clone, Serialize, Deserialize, FromRow)]
 pub struct RecurringTransaction {
     pub id: String,
@@ -12,11 +17,16 @@ pub struct RecurringTransaction {
     pub account_id: String,
     #[serde(rename = "transactionType")]
     pub transaction_type: String,
-    pub amount: f64,
+    pub amount: Money,
     pub currency: String,
     pub category: Option<String>,
     pub description: Option<String>,
     pub frequency: String,
+    #[serde(rename = "interval")]
+    pub recurrence_interval: i64,
+    #[serde(rename = "dayOfMonth")]
+    pub day_of_month: Option<i64>,
+    pub weekday: Option<String>,
     #[serde(rename = "startDate")]
     pub start_date: DateTime<Utc>,
     #[serde(rename = "endDate")]
@@ -31,45 +41,90 @@ pub struct RecurringTransaction {
     pub created_at: DateTime<Utc>,
     #[serde(rename = "updatedAt")]
     pub updated_at: DateTime<Utc>,
+    /// Increases with every change; see `services::concurrency`.
+    #[sqlx(default)]
+    #[serde(default)]
+    pub version: i64,
 }
 
 #[derive(Debug, Deserialize)]
 pub struct CreateRecurringTransactionRequest {
     pub id: Option<String>,
+    #[serde(alias = "accountId")]
     pub account_id: String,
+    #[serde(alias = "transactionType")]
     pub transaction_type: String,
-    pub amount: f64,
+    pub amount: Money,
     pub currency: Option<String>,
     pub category: Option<String>,
     pub description: Option<String>,
     pub frequency: Option<String>,
+    pub interval: Option<u32>,
+    #[serde(alias = "dayOfMonth")]
+    pub day_of_month: Option<u32>,
+    pub weekday: Option<String>,
+    #[serde(alias = "startDate")]
     pub start_date: DateTime<Utc>,
+    #[serde(alias = "endDate")]
     pub end_date: Option<DateTime<Utc>>,
-    pub next_due_date: DateTime<Utc>,
+    /// Ignored: the next due date is computed from the recurrence rule.
+    #[serde(alias = "nextDueDate")]
+    pub next_due_date: Option<DateTime<Utc>>,
+    #[serde(alias = "isActive")]
     pub is_active: Option<bool>,
+    #[serde(alias = "savingsGoalId")]
     pub savings_goal_id: Option<String>,
 }
 
 #[derive(Debug, Deserialize)]
 pub struct UpdateRecurringTransactionRequest {
+    #[serde(alias = "accountId")]
     pub account_id: Option<String>,
+    #[serde(alias = "transactionType")]
     pub transaction_type: Option<String>,
-    pub amount: Option<f64>,
+    pub amount: Option<Money>,
     pub currency: Option<String>,
     pub category: Option<String>,
     pub description: Option<String>,
     pub frequency: Option<String>,
+    pub interval: Option<u32>,
+    #[serde(alias = "dayOfMonth")]
+    pub day_of_month: Option<u32>,
+    pub weekday: Option<String>,
+    #[serde(alias = "startDate")]
     pub start_date: Option<DateTime<Utc>>,
+    #[serde(alias = "endDate")]
     pub end_date: Option<DateTime<Utc>>,
+    /// Ignored: the next due date is recomputed when the schedule changes.
+    #[serde(alias = "nextDueDate")]
     pub next_due_date: Option<DateTime<Utc>>,
+    #[serde(alias = "isActive")]
     pub is_active: Option<bool>,
+    #[serde(alias = "savingsGoalId")]
     pub savings_goal_id: Option<String>,
+    /// The version the update is based on, unless sent as `If-Match`.
+    pub version: Option<i64>,
 }
 
 impl RecurringTransaction {
-    pub fn new(request: CreateRecurringTransactionRequest, user_id: String) -> Self {
+    pub fn new(request: CreateRecurringTransactionRequest, user_id: String) -> Result<Self, RecurrenceError> {
+        let rule = RecurrenceRule::parse(
+            request.frequency.as_deref().unwrap_or("monthly"),
+            request.interval,
+            request.day_of_month,
+            request.weekday.as_deref(),
+        )?;
+        let next_due_date = rule
+            .next_on_or_after(request.start_date, request.start_date)
+            .ok_or_else(|| RecurrenceError("Could not compute the next due date".to_string()))?;
+        if let Some(end_date) = request.end_date {
+            if end_date < request.start_date {
+                return Err(RecurrenceError("endDate must not be before startDate".to_string()));
+            }
+        }
+
         let now = Utc::now();
-        Self {
+        Ok(Self {
             id: request.id.unwrap_or_else(|| Uuid::new_v4().to_string()),
             user_id,
             account_id: request.account_id,
@@ -78,14 +133,77 @@ impl RecurringTransaction {
             currency: request.currency.unwrap_or_else(|| "BDT".to_string()),
             category: request.category,
             description: request.description,
-            frequency: request.frequency.unwrap_or_else(|| "monthly".to_string()),
+            frequency: rule.frequency.as_str().to_string(),
+            recurrence_interval: rule.interval as i64,
+            day_of_month: rule.day_of_month.map(i64::from),
+            weekday: rule.weekday.map(|w| weekday_name(w).to_string()),
             start_date: request.start_date,
             end_date: request.end_date,
-            next_due_date: request.next_due_date,
+            next_due_date,
             is_active: request.is_active.unwrap_or(true),
             savings_goal_id: request.savings_goal_id,
             created_at: now,
             updated_at: now,
+            version: 1,
+        })
+    }
+
+    /// The stored schedule as a rule; the columns were validated on write.
+    pub fn rule(&self) -> Result<RecurrenceRule, RecurrenceError> {
+        RecurrenceRule::parse(
+            &self.frequency,
+            Some(self.recurrence_interval.max(1) as u32),
+            self.day_of_month.map(|d| d as u32),
+            self.weekday.as_deref(),
+        )
+    }
+}
+
+const TRANSACTION_TYPES: &[&str] = &["income", "expense", "transfer"];
+
+impl Validate for CreateRecurringTransactionRequest {
+    fn check_fields(&self, errors: &mut FieldErrors) {
+        errors.name("accountId", &self.account_id);
+        errors.one_of("transactionType", &self.transaction_type.to_lowercase(), TRANSACTION_TYPES);
+        errors.positive("amount", self.amount);
+        let code = self.currency.as_deref().unwrap_or("BDT");
+        errors.currency("currency", code);
+        errors.fits_currency("amount", self.amount, code);
+        if let Some(description) = &self.description {
+            errors.text("description", description);
+        }
+        errors.date("startDate", self.start_date);
+        if let Some(end_date) = self.end_date {
+            errors.date("endDate", end_date);
+            errors.not_before("endDate", end_date, self.start_date, "startDate");
+        }
+        if let Some(next_due_date) = self.next_due_date {
+            errors.date("nextDueDate", next_due_date);
+        }
+    }
+}
+
+impl Validate for UpdateRecurringTransactionRequest {
+    fn check_fields(&self, errors: &mut FieldErrors) {
+        if let Some(account_id) = &self.account_id {
+            errors.name("accountId", account_id);
+        }
+        if let Some(transaction_type) = &self.transaction_type {
+            errors.one_of("transactionType", &transaction_type.to_lowercase(), TRANSACTION_TYPES);
+        }
+        if let Some(amount) = self.amount {
+            errors.positive("amount", amount);
+        }
+        if let Some(code) = &self.currency {
+            errors.currency("currency", code);
+        }
+        if let Some(description) = &self.description {
+            errors.text("description", description);
+        }
+        for (field, date) in [("startDate", self.start_date), ("endDate", self.end_date), ("nextDueDate", self.next_due_date)] {
+            if let Some(date) = date {
+                errors.date(field, date);
+            }
         }
     }
 }
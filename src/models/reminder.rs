@@ -0,0 +1,119 @@
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use sqlx::FromRow;
+
+use crate::services::reminders::MAX_REMINDER_DAYS_BEFORE;
+use crate::utils::validation::{FieldErrors, Validate};
+
+/// What a bill reminder watches for a due date.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, sqlx::Type)]
+#[sqlx(type_name = "TEXT", rename_all = "snake_case")]
+#[serde(rename_all = "snake_case")]
+pub enum ReminderTarget {
+    /// An unpaid liability's due date.
+    Liability,
+    /// The payment due date of a credit card account's closed statements.
+    CreditCard,
+    /// A recurring transaction's next occurrence.
+    RecurringTransaction,
+}
+
+impl ReminderTarget {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Liability => "liability",
+            Self::CreditCard => "credit_card",
+            Self::RecurringTransaction => "recurring_transaction",
+        }
+    }
+}
+
+/// A user-configured reminder sent a number of days before a bill falls due. Several
+/// reminders may watch the same bill with different lead times.
+#[derive(Debug, Clone, Serialize, FromRow)]
+pub struct BillReminder {
+    pub id: String,
+    #[serde(rename = "userId")]
+    pub user_id: String,
+    #[serde(rename = "targetType")]
+    pub target_type: ReminderTarget,
+    #[serde(rename = "targetId")]
+    pub target_id: String,
+    /// 0 reminds on the due date itself.
+    #[serde(rename = "daysBefore")]
+    pub days_before: i64,
+    /// Also email the reminder; notifications are always queued and pushed.
+    #[serde(rename = "sendEmail")]
+    pub send_email: bool,
+    #[serde(rename = "isActive")]
+    pub is_active: bool,
+    #[serde(rename = "createdAt")]
+    pub created_at: DateTime<Utc>,
+    #[serde(rename = "updatedAt")]
+    pub updated_at: DateTime<Utc>,
+}
+
+/// A reminder with the next due date of its bill and when it will go out.
+#[derive(Debug, Clone, Serialize)]
+pub struct BillReminderSchedule {
+    #[serde(flatten)]
+    pub reminder: BillReminder,
+    /// `None` when nothing is coming up, e.g. the liability is paid.
+    #[serde(rename = "nextDueDate")]
+    pub next_due_date: Option<DateTime<Utc>>,
+    #[serde(rename = "remindAt")]
+    pub remind_at: Option<DateTime<Utc>>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct CreateBillReminderRequest {
+    #[serde(alias = "targetType")]
+    pub target_type: ReminderTarget,
+    #[serde(alias = "targetId")]
+    pub target_id: String,
+    #[serde(alias = "daysBefore")]
+    pub days_before: i64,
+    #[serde(alias = "sendEmail")]
+    pub send_email: Option<bool>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct UpdateBillReminderRequest {
+    #[serde(alias = "daysBefore")]
+    pub days_before: Option<i64>,
+    #[serde(alias = "sendEmail")]
+    pub send_email: Option<bool>,
+    #[serde(alias = "isActive")]
+    pub is_active: Option<bool>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct BillReminderQuery {
+    #[serde(alias = "targetType")]
+    pub target_type: Option<ReminderTarget>,
+    #[serde(alias = "targetId")]
+    pub target_id: Option<String>,
+}
+
+fn check_days_before(errors: &mut FieldErrors, days: i64) {
+    errors.check(
+        (0..=MAX_REMINDER_DAYS_BEFORE).contains(&days),
+        "daysBefore",
+        &format!("must be between 0 and {}", MAX_REMINDER_DAYS_BEFORE),
+    );
+}
+
+impl Validate for CreateBillReminderRequest {
+    fn check_fields(&self, errors: &mut FieldErrors) {
+        errors.name("targetId", &self.target_id);
+        check_days_before(errors, self.days_before);
+    }
+}
+
+impl Validate for UpdateBillReminderRequest {
+    fn check_fields(&self, errors: &mut FieldErrors) {
+        if let Some(days) = self.days_before {
+            check_days_before(errors, days);
+        }
+    }
+}
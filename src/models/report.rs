@@ -0,0 +1,221 @@
+use chrono::{DateTime, FixedOffset, NaiveDate};
+use serde::{Deserialize, Serialize};
+
+use crate::utils::money::Money;
+
+#[derive(Debug, Deserialize)]
+pub struct MonthlyReportQuery {
+    /// Defaults to the current year.
+    pub year: Option<i32>,
+    /// 1-12; defaults to the current month.
+    pub month: Option<u32>,
+}
+
+/// Income and expense totals in one currency.
+#[derive(Debug, Clone, Serialize)]
+pub struct CurrencyTotals {
+    pub currency: String,
+    pub income: Money,
+    pub expenses: Money,
+    pub net: Money,
+    #[serde(rename = "transactionCount")]
+    pub transaction_count: i64,
+}
+
+/// Income and expenses converted into one currency at each transaction day's rate.
+#[derive(Debug, Clone, Serialize)]
+pub struct ConsolidatedTotals {
+    pub currency: String,
+    pub income: Money,
+    pub expenses: Money,
+    pub net: Money,
+    /// Currencies with no known rate into `currency`; their amounts are not included.
+    #[serde(rename = "unconvertedCurrencies")]
+    pub unconverted_currencies: Vec<String>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct CategoryTotal {
+    pub category: String,
+    pub currency: String,
+    pub total: Money,
+    #[serde(rename = "transactionCount")]
+    pub transaction_count: i64,
+    /// Share of the month's expenses in the same currency, as a percentage.
+    #[serde(rename = "percentOfExpenses")]
+    pub percent_of_expenses: f64,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct ReportTransaction {
+    pub id: String,
+    #[serde(rename = "accountId")]
+    pub account_id: String,
+    #[serde(rename = "type")]
+    pub transaction_type: String,
+    pub amount: Money,
+    pub currency: String,
+    pub category: Option<String>,
+    pub description: Option<String>,
+    /// With the UTC offset of the user's timezone.
+    pub date: DateTime<FixedOffset>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct MonthlySummary {
+    /// `YYYY-MM`.
+    pub month: String,
+    pub totals: Vec<CurrencyTotals>,
+    /// `totals` in the user's display currency.
+    pub consolidated: ConsolidatedTotals,
+    /// Highest-spending expense categories.
+    #[serde(rename = "topCategories")]
+    pub top_categories: Vec<CategoryTotal>,
+    #[serde(rename = "largestTransactions")]
+    pub largest_transactions: Vec<ReportTransaction>,
+    /// Accounts flagged `exclude_from_totals`, whose transactions are left out.
+    #[serde(rename = "excludedAccounts")]
+    pub excluded_accounts: i64,
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Granularity {
+    Week,
+    #[default]
+    Month,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct CashflowQuery {
+    #[serde(default)]
+    pub granularity: Granularity,
+    /// `YYYY-MM-DD`; defaults to 12 buckets before `to`.
+    pub from: Option<NaiveDate>,
+    /// `YYYY-MM-DD`, inclusive; defaults to today.
+    pub to: Option<NaiveDate>,
+}
+
+/// Income and expenses in one week or month. Weeks start on the user's first day of the week.
+#[derive(Debug, Clone, Serialize)]
+pub struct CashflowBucket {
+    pub start: NaiveDate,
+    pub income: Money,
+    pub expenses: Money,
+    pub net: Money,
+}
+
+/// One currency's cash flow, with a bucket for every period in the range (zero when empty).
+#[derive(Debug, Clone, Serialize)]
+pub struct CashflowSeries {
+    pub currency: String,
+    pub buckets: Vec<CashflowBucket>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct ForecastQuery {
+    /// How many months to project, 1-3; defaults to 3.
+    pub months: Option<u32>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct MonthAmount {
+    /// `YYYY-MM`.
+    pub month: String,
+    pub amount: Money,
+}
+
+/// Trend and projection for one category's income or spending in one currency.
+#[derive(Debug, Clone, Serialize)]
+pub struct CategoryForecast {
+    #[serde(rename = "type")]
+    pub transaction_type: String,
+    pub category: String,
+    pub currency: String,
+    /// Monthly totals for the complete months the forecast is fitted on, oldest first.
+    pub history: Vec<MonthAmount>,
+    /// Average of the most recent months of `history`.
+    #[serde(rename = "movingAverage")]
+    pub moving_average: Money,
+    /// Change of the recent average over the one before it; `null` when there was nothing before.
+    #[serde(rename = "changePercent")]
+    pub change_percent: Option<f64>,
+    /// Spending categories whose recent average rose significantly.
+    #[serde(rename = "trendingUp")]
+    pub trending_up: bool,
+    pub projections: Vec<MonthAmount>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct ForecastTotals {
+    pub month: String,
+    pub currency: String,
+    pub income: Money,
+    pub expenses: Money,
+    pub net: Money,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct StatsQuery {
+    /// `YYYY-MM-DD`; transaction statistics start here when given.
+    pub from: Option<NaiveDate>,
+    /// `YYYY-MM-DD`, inclusive.
+    pub to: Option<NaiveDate>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct EntityCounts {
+    pub transactions: i64,
+    pub accounts: i64,
+    pub budgets: i64,
+    #[serde(rename = "savingsGoals")]
+    pub savings_goals: i64,
+    #[serde(rename = "activeRecurring")]
+    pub active_recurring: i64,
+    #[serde(rename = "unpaidLiabilities")]
+    pub unpaid_liabilities: i64,
+    #[serde(rename = "outstandingLoans")]
+    pub outstanding_loans: i64,
+}
+
+/// Aggregates over one transaction type in one currency.
+#[derive(Debug, Clone, Serialize)]
+pub struct TransactionAggregate {
+    pub currency: String,
+    #[serde(rename = "type")]
+    pub transaction_type: String,
+    pub count: i64,
+    pub total: Money,
+    pub average: Money,
+    pub min: Money,
+    pub max: Money,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct BusiestCategory {
+    pub category: String,
+    #[serde(rename = "transactionCount")]
+    pub transaction_count: i64,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct Stats {
+    pub counts: EntityCounts,
+    #[serde(rename = "byCurrency")]
+    pub by_currency: Vec<TransactionAggregate>,
+    /// Largest and smallest single income or expense, in its own currency.
+    #[serde(rename = "largestTransaction")]
+    pub largest_transaction: Option<ReportTransaction>,
+    #[serde(rename = "smallestTransaction")]
+    pub smallest_transaction: Option<ReportTransaction>,
+    /// The expense category with the most transactions.
+    #[serde(rename = "busiestCategory")]
+    pub busiest_category: Option<BusiestCategory>,
+    #[serde(rename = "firstTransactionDate")]
+    pub first_transaction_date: Option<DateTime<FixedOffset>>,
+    #[serde(rename = "lastTransactionDate")]
+    pub last_transaction_date: Option<DateTime<FixedOffset>>,
+    /// Accounts flagged `exclude_from_totals`, whose transactions are left out.
+    #[serde(rename = "excludedAccounts")]
+    pub excluded_accounts: i64,
+}
@@ -3,6 +3,9 @@ use sqlx::FromRow;
 use uuid::Uuid;
 use chrono::{DateTime, Utc};
 
+use crate::utils::money::Money;
+use crate::utils::validation::{FieldErrors, Validate};
+
 #[derive(Debug, Clone, Serialize, Deserialize, FromRow)]
 pub struct SavingsGoal {
     pub id: String,
@@ -10,9 +13,9 @@ pub struct SavingsGoal {
     pub user_id: String,
     pub name: String,
     #[serde(rename = "targetAmount")]
-    pub target_amount: f64,
+    pub target_amount: Money,
     #[serde(rename = "currentAmount")]
-    pub current_amount: f64,
+    pub current_amount: Money,
     pub currency: String,
     #[serde(rename = "targetDate")]
     pub target_date: DateTime<Utc>,
@@ -26,16 +29,23 @@ pub struct SavingsGoal {
     pub created_at: DateTime<Utc>,
     #[serde(rename = "updatedAt")]
     pub updated_at: DateTime<Utc>,
+    /// Increases with every change; see `services::concurrency`.
+    #[sqlx(default)]
+    #[serde(default)]
+    pub version: i64,
 }
 
 #[derive(Debug, Deserialize)]
 pub struct CreateSavingsGoalRequest {
     pub id: Option<String>,
     pub name: String,
-    pub target_amount: f64,
+    #[serde(alias = "targetAmount")]
+    pub target_amount: Money,
     pub currency: Option<String>,
+    #[serde(alias = "targetDate")]
     pub target_date: DateTime<Utc>,
     pub description: Option<String>,
+    #[serde(alias = "accountId")]
     pub account_id: Option<String>,
     pub priority: Option<String>,
 }
@@ -43,14 +53,21 @@ pub struct CreateSavingsGoalRequest {
 #[derive(Debug, Deserialize)]
 pub struct UpdateSavingsGoalRequest {
     pub name: Option<String>,
-    pub target_amount: Option<f64>,
-    pub current_amount: Option<f64>,
+    #[serde(alias = "targetAmount")]
+    pub target_amount: Option<Money>,
+    #[serde(alias = "currentAmount")]
+    pub current_amount: Option<Money>,
     pub currency: Option<String>,
+    #[serde(alias = "targetDate")]
     pub target_date: Option<DateTime<Utc>>,
     pub description: Option<String>,
+    #[serde(alias = "accountId")]
     pub account_id: Option<String>,
     pub priority: Option<String>,
+    #[serde(alias = "isCompleted")]
     pub is_completed: Option<bool>,
+    /// The version the update is based on, unless sent as `If-Match`.
+    pub version: Option<i64>,
 }
 
 impl SavingsGoal {
@@ -61,7 +78,7 @@ impl SavingsGoal {
             user_id,
             name: request.name,
             target_amount: request.target_amount,
-            current_amount: 0.0,
+            current_amount: Money::ZERO,
             currency: request.currency.unwrap_or_else(|| "BDT".to_string()),
             target_date: request.target_date,
             description: request.description,
@@ -70,6 +87,121 @@ impl SavingsGoal {
             is_completed: false,
             created_at: now,
             updated_at: now,
+            version: 1,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, FromRow)]
+pub struct SavingsGoalContribution {
+    pub id: String,
+    #[serde(rename = "userId")]
+    pub user_id: String,
+    #[serde(rename = "goalId")]
+    pub goal_id: String,
+    pub amount: Money,
+    #[serde(rename = "contributionDate")]
+    pub contribution_date: DateTime<Utc>,
+    pub note: Option<String>,
+    #[serde(rename = "transactionId")]
+    pub transaction_id: Option<String>,
+    /// `manual` for API contributions, `recurring` for ones posted by the recurring engine.
+    pub source: String,
+    #[serde(rename = "createdAt")]
+    pub created_at: DateTime<Utc>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct CreateContributionRequest {
+    pub amount: Money,
+    #[serde(alias = "contributionDate")]
+    pub date: Option<DateTime<Utc>>,
+    pub note: Option<String>,
+    /// Source account for the transfer transaction; defaults to the goal's linked account.
+    #[serde(alias = "accountId")]
+    pub account_id: Option<String>,
+    #[serde(alias = "createTransaction")]
+    pub create_transaction: Option<bool>,
+}
+
+impl SavingsGoalContribution {
+    pub fn new(
+        user_id: String,
+        goal_id: String,
+        amount: Money,
+        contribution_date: DateTime<Utc>,
+        note: Option<String>,
+        transaction_id: Option<String>,
+        source: &str,
+    ) -> Self {
+        Self {
+            id: Uuid::new_v4().to_string(),
+            user_id,
+            goal_id,
+            amount,
+            contribution_date,
+            note,
+            transaction_id,
+            source: source.to_string(),
+            created_at: Utc::now(),
+        }
+    }
+}
+
+/// How urgent a goal is, for ordering.
+pub const GOAL_PRIORITIES: &[&str] = &["low", "medium", "high"];
+
+impl Validate for CreateSavingsGoalRequest {
+    fn check_fields(&self, errors: &mut FieldErrors) {
+        errors.name("name", &self.name);
+        errors.positive("targetAmount", self.target_amount);
+        let code = self.currency.as_deref().unwrap_or("BDT");
+        errors.currency("currency", code);
+        errors.fits_currency("targetAmount", self.target_amount, code);
+        errors.date("targetDate", self.target_date);
+        if let Some(description) = &self.description {
+            errors.text("description", description);
+        }
+        if let Some(priority) = &self.priority {
+            errors.one_of("priority", priority, GOAL_PRIORITIES);
+        }
+    }
+}
+
+impl Validate for UpdateSavingsGoalRequest {
+    fn check_fields(&self, errors: &mut FieldErrors) {
+        if let Some(name) = &self.name {
+            errors.name("name", name);
+        }
+        if let Some(target_amount) = self.target_amount {
+            errors.positive("targetAmount", target_amount);
+        }
+        if let Some(current_amount) = self.current_amount {
+            errors.not_negative("currentAmount", current_amount);
+        }
+        if let Some(code) = &self.currency {
+            errors.currency("currency", code);
+        }
+        if let Some(target_date) = self.target_date {
+            errors.date("targetDate", target_date);
+        }
+        if let Some(description) = &self.description {
+            errors.text("description", description);
+        }
+        if let Some(priority) = &self.priority {
+            errors.one_of("priority", priority, GOAL_PRIORITIES);
+        }
+    }
+}
+
+impl Validate for CreateContributionRequest {
+    fn check_fields(&self, errors: &mut FieldErrors) {
+        errors.check(!self.amount.is_zero(), "amount", "must not be zero");
+        if let Some(date) = self.date {
+            errors.date("date", date);
+        }
+        if let Some(note) = &self.note {
+            errors.text("note", note);
         }
     }
 }
@@ -0,0 +1,160 @@
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use sqlx::FromRow;
+
+use crate::utils::money::Money;
+use crate::services::currency;
+use crate::utils::validation::{FieldErrors, Validate};
+
+/// A group expense paid by one user and owed in shares by the participants.
+#[derive(Debug, Clone, Serialize, FromRow)]
+pub struct SplitExpense {
+    pub id: String,
+    #[serde(rename = "createdBy")]
+    pub created_by: String,
+    #[serde(rename = "paidBy")]
+    pub paid_by: String,
+    pub description: String,
+    pub amount: Money,
+    pub currency: String,
+    pub category: Option<String>,
+    pub date: DateTime<Utc>,
+    /// The payer's expense for the full amount, when the creator paid and gave an account.
+    #[serde(rename = "transactionId")]
+    pub transaction_id: Option<String>,
+    #[serde(rename = "createdAt")]
+    pub created_at: DateTime<Utc>,
+}
+
+/// What one participant owes towards a split expense, including the payer's own part.
+#[derive(Debug, Clone, Serialize, FromRow)]
+pub struct SplitShare {
+    #[serde(rename = "userId")]
+    pub user_id: String,
+    pub name: String,
+    pub email: String,
+    pub amount: Money,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct SplitExpenseDetail {
+    #[serde(flatten)]
+    pub split: SplitExpense,
+    pub shares: Vec<SplitShare>,
+}
+
+/// A payment from one user to another that pays down what they owe.
+#[derive(Debug, Clone, Serialize, FromRow)]
+pub struct SplitSettlement {
+    pub id: String,
+    #[serde(rename = "fromUserId")]
+    pub from_user_id: String,
+    #[serde(rename = "toUserId")]
+    pub to_user_id: String,
+    pub amount: Money,
+    pub currency: String,
+    pub date: DateTime<Utc>,
+    pub note: Option<String>,
+    /// The creator's side of the payment in one of their accounts, if recorded.
+    #[serde(rename = "transactionId")]
+    pub transaction_id: Option<String>,
+    #[serde(rename = "createdBy")]
+    pub created_by: String,
+    #[serde(rename = "createdAt")]
+    pub created_at: DateTime<Utc>,
+}
+
+/// Where the user stands with a friend in one currency. Positive when the friend owes the
+/// user, negative when the user owes the friend.
+#[derive(Debug, Clone, Serialize)]
+pub struct FriendBalance {
+    #[serde(rename = "userId")]
+    pub user_id: String,
+    pub name: String,
+    pub email: String,
+    pub currency: String,
+    pub balance: Money,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct SplitParticipantRequest {
+    /// A user ID or email address.
+    pub user: String,
+    /// Leave out for every participant to split evenly.
+    pub amount: Option<Money>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct CreateSplitExpenseRequest {
+    pub description: String,
+    pub amount: Money,
+    pub currency: Option<String>,
+    pub category: Option<String>,
+    pub date: Option<DateTime<Utc>>,
+    /// A user ID or email address; defaults to the creator.
+    #[serde(alias = "paidBy")]
+    pub paid_by: Option<String>,
+    /// The creator's account to record their payment in, when they paid.
+    #[serde(alias = "accountId")]
+    pub account_id: Option<String>,
+    pub participants: Vec<SplitParticipantRequest>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum SettlementDirection {
+    /// The user paid the friend.
+    Paid,
+    /// The friend paid the user.
+    Received,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct CreateSplitSettlementRequest {
+    /// A user ID or email address.
+    pub friend: String,
+    pub direction: SettlementDirection,
+    pub amount: Money,
+    pub currency: Option<String>,
+    pub date: Option<DateTime<Utc>>,
+    pub note: Option<String>,
+    /// The user's account the money left or arrived in.
+    #[serde(alias = "accountId")]
+    pub account_id: Option<String>,
+}
+
+impl Validate for CreateSplitExpenseRequest {
+    fn check_fields(&self, errors: &mut FieldErrors) {
+        errors.name("description", &self.description);
+        errors.positive("amount", self.amount);
+        let code = self.currency.as_deref().unwrap_or(currency::DEFAULT_DISPLAY_CURRENCY);
+        errors.currency("currency", code);
+        errors.fits_currency("amount", self.amount, code);
+        if let Some(date) = self.date {
+            errors.date("date", date);
+        }
+        errors.check(!self.participants.is_empty(), "participants", "must not be empty");
+        let given = self.participants.iter().filter(|p| p.amount.is_some()).count();
+        errors.check(
+            given == 0 || given == self.participants.len(),
+            "participants",
+            "must all have an amount, or none to split evenly",
+        );
+    }
+}
+
+impl Validate for CreateSplitSettlementRequest {
+    fn check_fields(&self, errors: &mut FieldErrors) {
+        errors.name("friend", &self.friend);
+        errors.positive("amount", self.amount);
+        let code = self.currency.as_deref().unwrap_or(currency::DEFAULT_DISPLAY_CURRENCY);
+        errors.currency("currency", code);
+        errors.fits_currency("amount", self.amount, code);
+        if let Some(date) = self.date {
+            errors.date("date", date);
+        }
+        if let Some(note) = &self.note {
+            errors.text("note", note);
+        }
+    }
+}
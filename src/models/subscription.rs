@@ -0,0 +1,44 @@
+use chrono::{DateTime, Utc};
+use serde::Serialize;
+
+use crate::utils::money::Money;
+
+/// Repeated charges from one merchant on one account at a regular interval.
+#[derive(Debug, Clone, Serialize)]
+pub struct DetectedSubscription {
+    /// Stable across runs for the same account, merchant and currency.
+    pub id: String,
+    /// The most recent charge's description.
+    pub merchant: String,
+    #[serde(rename = "accountId")]
+    pub account_id: String,
+    pub currency: String,
+    pub category: Option<String>,
+    /// `weekly`, `monthly` or `yearly`.
+    pub frequency: &'static str,
+    #[serde(rename = "chargeCount")]
+    pub charge_count: usize,
+    #[serde(rename = "firstAmount")]
+    pub first_amount: Money,
+    #[serde(rename = "lastAmount")]
+    pub last_amount: Money,
+    #[serde(rename = "averageAmount")]
+    pub average_amount: Money,
+    /// Change from the first charge to the last.
+    #[serde(rename = "amountDrift")]
+    pub amount_drift: Money,
+    #[serde(rename = "driftPercent")]
+    pub drift_percent: f64,
+    #[serde(rename = "firstChargeDate")]
+    pub first_charge_date: DateTime<Utc>,
+    #[serde(rename = "lastChargeDate")]
+    pub last_charge_date: DateTime<Utc>,
+    #[serde(rename = "nextExpectedDate")]
+    pub next_expected_date: DateTime<Utc>,
+    /// False once a charge is more than two intervals overdue.
+    #[serde(rename = "isActive")]
+    pub is_active: bool,
+    /// The recurring transaction already tracking it, if any.
+    #[serde(rename = "recurringTransactionId")]
+    pub recurring_transaction_id: Option<String>,
+}
@@ -0,0 +1,153 @@
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use sqlx::FromRow;
+
+use crate::models::{Account, RecurringTransaction, SavingsGoal, UserPreference};
+
+/// The kinds of records an offline client can sync in a batch.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum SyncEntity {
+    Account,
+    Transaction,
+    Budget,
+    #[serde(alias = "savingsGoal")]
+    SavingsGoal,
+    #[serde(alias = "recurringTransaction")]
+    RecurringTransaction,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum SyncOperation {
+    Create,
+    Update,
+    Delete,
+}
+
+/// One change made on the client while offline.
+#[derive(Debug, Deserialize)]
+pub struct SyncChange {
+    pub entity: SyncEntity,
+    pub operation: SyncOperation,
+    /// The client-generated UUID of the record.
+    pub id: String,
+    /// The same body the entity's create or update endpoint takes; unused for deletes.
+    pub data: Option<Value>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct SyncRequest {
+    pub changes: Vec<SyncChange>,
+}
+
+/// How one change went, with the HTTP status its own endpoint would have answered.
+#[derive(Debug, Clone, Serialize)]
+pub struct SyncResult {
+    pub entity: SyncEntity,
+    pub operation: SyncOperation,
+    pub id: String,
+    pub success: bool,
+    pub status: u16,
+    /// The stored record, for changes whose endpoint returns it (creates do). For a 409
+    /// Conflict, the server's current copy to merge into and retry.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub data: Option<Value>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct SyncChangesQuery {
+    /// The `serverTime` of the previous pull; leave out for everything.
+    pub since: Option<DateTime<Utc>>,
+}
+
+/// A record deleted since the last pull.
+#[derive(Debug, Clone, Serialize, FromRow)]
+pub struct SyncTombstone {
+    #[serde(rename = "entityType")]
+    pub entity_type: String,
+    pub id: String,
+    #[serde(rename = "deletedAt")]
+    pub deleted_at: DateTime<Utc>,
+}
+
+/// Records created or changed since the last pull, and the ones deleted. Transactions, which
+/// can run to years of history, are streamed after these as `transactions`.
+#[derive(Debug, Clone, Serialize)]
+pub struct SyncChanges {
+    /// Pass back as `since` on the next pull. It overlaps this pull slightly, so a few
+    /// records may come again; apply them as upserts.
+    #[serde(rename = "serverTime")]
+    pub server_time: DateTime<Utc>,
+    pub accounts: Vec<Account>,
+    /// Budgets with their categories.
+    pub budgets: Vec<Value>,
+    #[serde(rename = "savingsGoals")]
+    pub savings_goals: Vec<SavingsGoal>,
+    #[serde(rename = "recurringTransactions")]
+    pub recurring_transactions: Vec<RecurringTransaction>,
+    /// The whole preferences document when it changed since the last pull; `null` otherwise.
+    pub preferences: Option<UserPreference>,
+    pub deleted: Vec<SyncTombstone>,
+}
+
+/// A client that syncs, identified by the `X-Device-Id` it sends, with where its last pull
+/// left off.
+#[derive(Debug, Clone, Serialize, FromRow)]
+pub struct SyncDevice {
+    pub id: String,
+    #[serde(rename = "userId")]
+    pub user_id: String,
+    #[serde(rename = "deviceId")]
+    pub device_id: String,
+    pub name: String,
+    pub platform: Option<String>,
+    #[serde(rename = "appVersion")]
+    pub app_version: Option<String>,
+    /// The push registration reaching this device, if any.
+    #[serde(rename = "pushDeviceId")]
+    pub push_device_id: Option<String>,
+    /// The `serverTime` of the device's last pull.
+    #[serde(rename = "lastCursor")]
+    pub last_cursor: Option<DateTime<Utc>>,
+    #[serde(rename = "lastPulledAt")]
+    pub last_pulled_at: Option<DateTime<Utc>>,
+    #[serde(rename = "lastPushedAt")]
+    pub last_pushed_at: Option<DateTime<Utc>>,
+    #[serde(rename = "createdAt")]
+    pub created_at: DateTime<Utc>,
+    #[serde(rename = "updatedAt")]
+    pub updated_at: DateTime<Utc>,
+}
+
+/// A device with how far behind the server it is.
+#[derive(Debug, Clone, Serialize)]
+pub struct SyncDeviceStatus {
+    #[serde(flatten)]
+    pub device: SyncDevice,
+    /// Seconds since the device's cursor; `None` until it has pulled.
+    #[serde(rename = "lagSeconds")]
+    pub lag_seconds: Option<i64>,
+    /// Records created, changed or deleted since the cursor.
+    #[serde(rename = "pendingChanges")]
+    pub pending_changes: Option<i64>,
+    /// The cursor is past the change log's retention; the device must download everything.
+    #[serde(rename = "cursorExpired")]
+    pub cursor_expired: bool,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct RegisterSyncDeviceRequest {
+    /// Defaults to the request's `X-Device-Id`.
+    #[serde(alias = "deviceId")]
+    pub device_id: Option<String>,
+    pub name: Option<String>,
+    pub platform: Option<String>,
+    #[serde(alias = "appVersion")]
+    pub app_version: Option<String>,
+    #[serde(alias = "pushDeviceId")]
+    pub push_device_id: Option<String>,
+}
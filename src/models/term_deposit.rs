@@ -0,0 +1,184 @@
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use sqlx::FromRow;
+
+use crate::utils::money::Money;
+use crate::services::currency;
+use crate::utils::validation::{FieldErrors, Validate};
+
+/// `fixed` is a lump sum held for the tenure (FDR); `recurring` is a monthly installment
+/// scheme (DPS) where `principal` is the installment.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, sqlx::Type)]
+#[sqlx(type_name = "TEXT", rename_all = "lowercase")]
+#[serde(rename_all = "lowercase")]
+pub enum DepositType {
+    Fixed,
+    Recurring,
+}
+
+/// How often interest is added to the balance; `simple` never compounds.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, sqlx::Type)]
+#[sqlx(type_name = "TEXT", rename_all = "snake_case")]
+#[serde(rename_all = "snake_case")]
+pub enum Compounding {
+    Simple,
+    Monthly,
+    Quarterly,
+    #[serde(alias = "halfYearly")]
+    HalfYearly,
+    Yearly,
+}
+
+impl Compounding {
+    /// Compounding periods per year; `None` for simple interest.
+    pub fn periods_per_year(&self) -> Option<f64> {
+        match self {
+            Self::Simple => None,
+            Self::Monthly => Some(12.0),
+            Self::Quarterly => Some(4.0),
+            Self::HalfYearly => Some(2.0),
+            Self::Yearly => Some(1.0),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, FromRow)]
+pub struct TermDeposit {
+    pub id: String,
+    #[serde(rename = "userId")]
+    pub user_id: String,
+    /// The account the deposit was funded from and pays out to, if tracked.
+    #[serde(rename = "accountId")]
+    pub account_id: Option<String>,
+    pub name: String,
+    #[serde(rename = "depositType")]
+    pub deposit_type: DepositType,
+    pub principal: Money,
+    pub currency: String,
+    /// Percent per year, e.g. `9.5`.
+    #[serde(rename = "annualRate")]
+    pub annual_rate: f64,
+    #[serde(rename = "tenureMonths")]
+    pub tenure_months: i64,
+    pub compounding: Compounding,
+    #[serde(rename = "startDate")]
+    pub start_date: DateTime<Utc>,
+    #[serde(rename = "maturityDate")]
+    pub maturity_date: DateTime<Utc>,
+    #[serde(rename = "isClosed")]
+    pub is_closed: bool,
+    pub notes: Option<String>,
+    #[serde(rename = "createdAt")]
+    pub created_at: DateTime<Utc>,
+    #[serde(rename = "updatedAt")]
+    pub updated_at: DateTime<Utc>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct CreateTermDepositRequest {
+    #[serde(alias = "accountId")]
+    pub account_id: Option<String>,
+    pub name: String,
+    #[serde(alias = "depositType")]
+    pub deposit_type: Option<DepositType>,
+    pub principal: Money,
+    pub currency: Option<String>,
+    #[serde(alias = "annualRate")]
+    pub annual_rate: f64,
+    #[serde(alias = "tenureMonths")]
+    pub tenure_months: u32,
+    pub compounding: Option<Compounding>,
+    #[serde(alias = "startDate")]
+    pub start_date: Option<DateTime<Utc>>,
+    pub notes: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct UpdateTermDepositRequest {
+    #[serde(alias = "accountId")]
+    pub account_id: Option<String>,
+    pub name: Option<String>,
+    pub principal: Option<Money>,
+    #[serde(alias = "annualRate")]
+    pub annual_rate: Option<f64>,
+    #[serde(alias = "tenureMonths")]
+    pub tenure_months: Option<u32>,
+    pub compounding: Option<Compounding>,
+    #[serde(alias = "startDate")]
+    pub start_date: Option<DateTime<Utc>>,
+    #[serde(alias = "isClosed")]
+    pub is_closed: Option<bool>,
+    pub notes: Option<String>,
+}
+
+/// A deposit with its interest worked out as of today.
+#[derive(Debug, Clone, Serialize)]
+pub struct TermDepositSummary {
+    #[serde(flatten)]
+    pub deposit: TermDeposit,
+    /// Paid in so far: the principal, or the installments due to date for a recurring deposit.
+    #[serde(rename = "amountDeposited")]
+    pub amount_deposited: Money,
+    #[serde(rename = "accruedInterest")]
+    pub accrued_interest: Money,
+    #[serde(rename = "currentValue")]
+    pub current_value: Money,
+    #[serde(rename = "maturityValue")]
+    pub maturity_value: Money,
+    #[serde(rename = "totalInterest")]
+    pub total_interest: Money,
+    #[serde(rename = "daysToMaturity")]
+    pub days_to_maturity: i64,
+}
+
+/// Longest tenure, in months.
+pub const MAX_TENURE_MONTHS: u32 = 600;
+
+fn check_tenure(errors: &mut FieldErrors, tenure_months: u32) {
+    errors.check(
+        (1..=MAX_TENURE_MONTHS).contains(&tenure_months),
+        "tenureMonths",
+        &format!("must be between 1 and {}", MAX_TENURE_MONTHS),
+    );
+}
+
+impl Validate for CreateTermDepositRequest {
+    fn check_fields(&self, errors: &mut FieldErrors) {
+        errors.name("name", &self.name);
+        errors.positive("principal", self.principal);
+        let code = self.currency.as_deref().unwrap_or(currency::DEFAULT_DISPLAY_CURRENCY);
+        errors.currency("currency", code);
+        errors.fits_currency("principal", self.principal, code);
+        errors.percentage("annualRate", self.annual_rate);
+        check_tenure(errors, self.tenure_months);
+        if let Some(start_date) = self.start_date {
+            errors.date("startDate", start_date);
+        }
+        if let Some(notes) = &self.notes {
+            errors.text("notes", notes);
+        }
+    }
+}
+
+impl Validate for UpdateTermDepositRequest {
+    fn check_fields(&self, errors: &mut FieldErrors) {
+        if let Some(name) = &self.name {
+            errors.name("name", name);
+        }
+        if let Some(principal) = self.principal {
+            errors.positive("principal", principal);
+        }
+        if let Some(annual_rate) = self.annual_rate {
+            errors.percentage("annualRate", annual_rate);
+        }
+        if let Some(tenure_months) = self.tenure_months {
+            check_tenure(errors, tenure_months);
+        }
+        if let Some(start_date) = self.start_date {
+            errors.date("startDate", start_date);
+        }
+        if let Some(notes) = &self.notes {
+            errors.text("notes", notes);
+        }
+    }
+}
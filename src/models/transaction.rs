@@ -3,6 +3,12 @@ use sqlx::FromRow;
 use uuid::Uuid;
 use chrono::{DateTime, Utc, NaiveDateTime};
 
+use crate::utils::money::Money;
+use crate::utils::validation::{FieldErrors, Validate};
+
+/// Currency of a transaction created without one.
+const DEFAULT_CURRENCY: &str = "BDT";
+
 #[derive(Debug, Clone, Serialize, Deserialize, FromRow)]
 pub struct Transaction {
     pub id: String,
@@ -12,13 +18,26 @@ pub struct Transaction {
     pub account_id: String,
     #[serde(rename = "type")]
     pub transaction_type: TransactionType,
-    pub amount: f64,
+    pub amount: Money,
     pub currency: String,
     pub category: Option<String>,
     pub description: Option<String>,
     pub date: DateTime<Utc>,
     #[serde(rename = "createdAt")]
     pub created_at: DateTime<Utc>,
+    /// Rate from `currency` into `base_currency` in effect when the transaction was recorded.
+    #[serde(rename = "exchangeRate")]
+    pub exchange_rate: Option<f64>,
+    /// `amount` in `base_currency` at `exchange_rate`; reports sum this rather than reconverting.
+    #[serde(rename = "baseAmount")]
+    pub base_amount: Option<Money>,
+    /// The user's display currency at the time of recording.
+    #[serde(rename = "baseCurrency")]
+    pub base_currency: Option<String>,
+    /// Increases with every change; see `services::concurrency`.
+    #[sqlx(default)]
+    #[serde(default)]
+    pub version: i64,
 }
 
 #[derive(Debug, Clone, Copy, Serialize, Deserialize, sqlx::Type)]
@@ -40,7 +59,7 @@ pub struct CreateTransactionRequest {
     pub account_id: String,
     #[serde(alias = "type")]
     pub transaction_type: TransactionType,
-    pub amount: f64,
+    pub amount: Money,
     pub currency: Option<String>,
     pub category: Option<String>,
     pub description: Option<String>,
@@ -49,6 +68,12 @@ pub struct CreateTransactionRequest {
     // Accept but ignore these fields sent by Flutter
     #[serde(alias = "createdAt")]
     pub created_at: Option<DateTime<Utc>>,
+    /// Rate into the user's display currency; looked up when omitted.
+    #[serde(alias = "exchangeRate")]
+    pub exchange_rate: Option<f64>,
+    /// Amount in the user's display currency; derived from the rate when omitted.
+    #[serde(alias = "baseAmount")]
+    pub base_amount: Option<Money>,
 }
 
 fn deserialize_optional_datetime<'de, D>(deserializer: D) -> Result<Option<DateTime<Utc>>, D::Error>
@@ -107,11 +132,17 @@ where
 pub struct UpdateTransactionRequest {
     pub account_id: Option<String>,
     pub transaction_type: Option<TransactionType>,
-    pub amount: Option<f64>,
+    pub amount: Option<Money>,
     pub currency: Option<String>,
     pub category: Option<String>,
     pub description: Option<String>,
     pub date: Option<DateTime<Utc>>,
+    #[serde(alias = "exchangeRate")]
+    pub exchange_rate: Option<f64>,
+    #[serde(alias = "baseAmount")]
+    pub base_amount: Option<Money>,
+    /// The version the update is based on, unless sent as `If-Match`.
+    pub version: Option<i64>,
 }
 
 impl Transaction {
@@ -123,38 +154,75 @@ impl Transaction {
             account_id: request.account_id,
             transaction_type: request.transaction_type,
             amount: request.amount,
-            currency: request.currency.unwrap_or_else(|| "BDT".to_string()),
+            currency: request.currency.unwrap_or_else(|| DEFAULT_CURRENCY.to_string()),
             category: request.category,
             description: request.description,
             date: request.date.unwrap_or(now),
             created_at: now,
+            exchange_rate: None,
+            base_amount: None,
+            base_currency: None,
+            version: 1,
         }
     }
 }
 
-pub struct TransactionCategories;
+/// A client-supplied rate must be positive.
+fn valid_exchange_rate(exchange_rate: Option<f64>) -> bool {
+    exchange_rate.is_none_or(|rate| rate.is_finite() && rate > 0.0)
+}
 
-impl TransactionCategories {
-    pub fn get_income_categories() -> Vec<&'static str> {
-        vec![
-            "Salary",
-            "Business",
-            "Investment",
-            "Gift",
-            "Other Income",
-        ]
+impl Validate for CreateTransactionRequest {
+    fn check_fields(&self, errors: &mut FieldErrors) {
+        errors.name("accountId", &self.account_id);
+        errors.positive("amount", self.amount);
+        errors.fits_currency("amount", self.amount, self.currency.as_deref().unwrap_or(DEFAULT_CURRENCY));
+        if let Some(code) = &self.currency {
+            errors.currency("currency", code);
+        }
+        if let Some(category) = &self.category {
+            errors.text("category", category);
+        }
+        if let Some(description) = &self.description {
+            errors.text("description", description);
+        }
+        if let Some(date) = self.date {
+            errors.date("date", date);
+        }
+        errors.check(valid_exchange_rate(self.exchange_rate), "exchangeRate", "must be greater than zero");
+        if let Some(base_amount) = self.base_amount {
+            errors.not_negative("baseAmount", base_amount);
+        }
     }
+}
 
-    pub fn get_expense_categories() -> Vec<&'static str> {
-        vec![
-            "Food",
-            "Transportation",
-            "Shopping",
-            "Entertainment",
-            "Bills",
-            "Medical",
-            "Education",
-            "Other Expense",
-        ]
+impl Validate for UpdateTransactionRequest {
+    fn check_fields(&self, errors: &mut FieldErrors) {
+        if let Some(account_id) = &self.account_id {
+            errors.name("accountId", account_id);
+        }
+        if let Some(amount) = self.amount {
+            errors.positive("amount", amount);
+        }
+        if let Some(code) = &self.currency {
+            errors.currency("currency", code);
+        }
+        // Either one alone is checked against the stored value by the handler
+        if let (Some(amount), Some(code)) = (self.amount, &self.currency) {
+            errors.fits_currency("amount", amount, code);
+        }
+        if let Some(category) = &self.category {
+            errors.text("category", category);
+        }
+        if let Some(description) = &self.description {
+            errors.text("description", description);
+        }
+        if let Some(date) = self.date {
+            errors.date("date", date);
+        }
+        errors.check(valid_exchange_rate(self.exchange_rate), "exchangeRate", "must be greater than zero");
+        if let Some(base_amount) = self.base_amount {
+            errors.not_negative("baseAmount", base_amount);
+        }
     }
-}
\ No newline at end of file
+}
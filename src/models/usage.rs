@@ -0,0 +1,33 @@
+use serde::Serialize;
+use sqlx::FromRow;
+
+#[derive(Debug, Clone, Serialize, FromRow)]
+pub struct EndpointUsage {
+    pub method: String,
+    pub route: String,
+    pub requests: i64,
+    #[serde(rename = "requestBytes")]
+    pub request_bytes: i64,
+    #[serde(rename = "responseBytes")]
+    pub response_bytes: i64,
+}
+
+#[derive(Debug, Clone, Serialize, FromRow)]
+pub struct DeviceUsage {
+    pub device: String,
+    pub requests: i64,
+    #[serde(rename = "requestBytes")]
+    pub request_bytes: i64,
+    #[serde(rename = "responseBytes")]
+    pub response_bytes: i64,
+}
+
+#[derive(Debug, Clone, Serialize, FromRow)]
+pub struct DailyUsage {
+    pub day: String,
+    pub requests: i64,
+    #[serde(rename = "requestBytes")]
+    pub request_bytes: i64,
+    #[serde(rename = "responseBytes")]
+    pub response_bytes: i64,
+}
@@ -2,12 +2,18 @@ use serde::{Deserialize, Serialize};
 use chrono::{DateTime, Utc};
 use uuid::Uuid;
 
+use crate::services::avatars;
+use crate::utils::validation::{FieldErrors, Validate};
+
 #[derive(Debug, Serialize, Deserialize, Clone, sqlx::FromRow)]
 pub struct User {
     pub id: String,
     pub name: String,
     pub email: String,
     pub password_hash: String,
+    pub email_verified_at: Option<DateTime<Utc>>,
+    pub is_admin: bool,
+    pub avatar_key: Option<String>,
     pub created_at: DateTime<Utc>,
     pub updated_at: DateTime<Utc>,
 }
@@ -19,16 +25,51 @@ pub struct CreateUserRequest {
     pub password: String,
 }
 
+/// `PUT /api/me`: the fields to change; the rest keep their values. A new email address has to
+/// be verified again, and needs the current password.
+#[derive(Debug, Deserialize)]
+pub struct UpdateProfileRequest {
+    pub name: Option<String>,
+    pub email: Option<String>,
+    #[serde(alias = "currentPassword")]
+    pub current_password: Option<String>,
+}
+
 #[derive(Debug, Deserialize)]
 pub struct LoginRequest {
     pub email: String,
     pub password: String,
 }
 
+#[derive(Debug, Deserialize)]
+pub struct VerifyEmailRequest {
+    pub token: String,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct ForgotPasswordRequest {
+    pub email: String,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct ResetPasswordRequest {
+    pub token: String,
+    pub password: String,
+}
+
 #[derive(Debug, Serialize)]
 pub struct AuthResponse {
     pub token: String,
     pub user: UserResponse,
+    #[serde(rename = "reauthReason", skip_serializing_if = "Option::is_none")]
+    pub reauth_reason: Option<ReauthReason>,
+}
+
+/// Why the user's previous session ended, reported once on the next login.
+#[derive(Debug, Clone, Serialize)]
+pub struct ReauthReason {
+    pub code: String,
+    pub message: String,
 }
 
 #[derive(Debug, Serialize)]
@@ -36,6 +77,13 @@ pub struct UserResponse {
     pub id: String,
     pub name: String,
     pub email: String,
+    #[serde(rename = "emailVerified")]
+    pub email_verified: bool,
+    #[serde(rename = "isAdmin")]
+    pub is_admin: bool,
+    /// Where the avatar is served, if the user uploaded one.
+    #[serde(rename = "avatarUrl")]
+    pub avatar_url: Option<String>,
     #[serde(rename = "createdAt")]
     pub created_at: DateTime<Utc>,
     #[serde(rename = "updatedAt")]
@@ -48,6 +96,9 @@ impl From<User> for UserResponse {
             id: user.id,
             name: user.name,
             email: user.email,
+            email_verified: user.email_verified_at.is_some(),
+            is_admin: user.is_admin,
+            avatar_url: user.avatar_key.as_deref().map(avatars::url),
             created_at: user.created_at,
             updated_at: user.updated_at,
         }
@@ -62,8 +113,56 @@ impl User {
             name,
             email,
             password_hash,
+            email_verified_at: None,
+            is_admin: false,
+            avatar_key: None,
             created_at: now,
             updated_at: now,
         }
     }
-}
\ No newline at end of file
+}
+
+/// Whether `email` looks like an address; delivery is what proves it.
+fn is_email(email: &str) -> bool {
+    email
+        .trim()
+        .split_once('@')
+        .is_some_and(|(local, domain)| !local.is_empty() && domain.contains('.'))
+}
+
+/// Shortest password a new account may use.
+pub const MIN_PASSWORD_LENGTH: usize = 8;
+
+impl Validate for CreateUserRequest {
+    fn check_fields(&self, errors: &mut FieldErrors) {
+        errors.name("name", &self.name);
+        errors.check(is_email(&self.email), "email", "must be an email address");
+        check_new_password(errors, &self.password);
+    }
+}
+
+impl Validate for ResetPasswordRequest {
+    fn check_fields(&self, errors: &mut FieldErrors) {
+        errors.check(!self.token.trim().is_empty(), "token", "must not be empty");
+        check_new_password(errors, &self.password);
+    }
+}
+
+fn check_new_password(errors: &mut FieldErrors, password: &str) {
+    errors.check(
+        password.chars().count() >= MIN_PASSWORD_LENGTH,
+        "password",
+        &format!("must be at least {} characters", MIN_PASSWORD_LENGTH),
+    );
+}
+
+impl Validate for UpdateProfileRequest {
+    fn check_fields(&self, errors: &mut FieldErrors) {
+        if let Some(name) = &self.name {
+            errors.name("name", name);
+        }
+        if let Some(email) = &self.email {
+            errors.check(is_email(email), "email", "must be an email address");
+        }
+    }
+}
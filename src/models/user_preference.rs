@@ -1,17 +1,61 @@
-use chrono::{DateTime, Utc};
 use serde::{Deserialize, Serialize};
+use serde_json::{Map, Value};
 use sqlx::FromRow;
 
-#[derive(Debug, Clone, Serialize, Deserialize, FromRow)]
+use crate::services::credit_utilization::DEFAULT_THRESHOLDS;
+use crate::services::currency::DEFAULT_DISPLAY_CURRENCY;
+use crate::services::user_time::{DEFAULT_FIRST_DAY_OF_WEEK, DEFAULT_LOCALE, DEFAULT_TIMEZONE};
+
+/// One setting a user changed from its default. `value` is JSON.
+#[derive(Debug, Clone, FromRow)]
+pub struct UserSetting {
+    pub key: String,
+    pub value: String,
+}
+
+/// A user's preferences as one document: every setting the server knows, defaulted where the
+/// user hasn't chosen, plus whatever the apps keep under `client`.
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct UserPreference {
+    #[serde(rename = "userId")]
     pub user_id: String,
     #[serde(rename = "displayCurrency")]
     pub display_currency: String,
+    #[serde(rename = "sessionIdleTimeoutMinutes")]
+    pub session_idle_timeout_minutes: Option<i64>,
+    #[serde(rename = "creditUtilizationThresholds")]
+    pub credit_utilization_thresholds: Vec<u32>,
+    /// IANA name, such as `Asia/Dhaka`. Reports start days, weeks and months at its midnight.
+    pub timezone: String,
+    /// BCP 47 tag, such as `bn-BD`, for clients to format dates and numbers with.
+    pub locale: String,
+    /// Lowercase weekday name that weekly reports start on.
+    #[serde(rename = "firstDayOfWeek")]
+    pub first_day_of_week: String,
+    /// Free-form settings of the apps. The server only stores them.
+    pub client: Map<String, Value>,
+    /// Goes up by one with every change; 0 until the user first saves a preference. Send it
+    /// back as `revision` or `If-Match` to update only the copy it came with.
+    pub revision: i64,
+    /// None until the user first saves a preference.
     #[serde(rename = "updatedAt")]
-    pub updated_at: String,
+    pub updated_at: Option<String>,
 }
 
-#[derive(Debug, Deserialize)]
-pub struct UpdatePreferenceRequest {
-    pub display_currency: Option<String>,
+impl UserPreference {
+    /// What a user who never saved a preference gets.
+    pub fn defaults(user_id: &str) -> Self {
+        Self {
+            user_id: user_id.to_string(),
+            display_currency: DEFAULT_DISPLAY_CURRENCY.to_string(),
+            session_idle_timeout_minutes: None,
+            credit_utilization_thresholds: DEFAULT_THRESHOLDS.to_vec(),
+            timezone: DEFAULT_TIMEZONE.to_string(),
+            locale: DEFAULT_LOCALE.to_string(),
+            first_day_of_week: DEFAULT_FIRST_DAY_OF_WEEK.to_string(),
+            client: Map::new(),
+            revision: 0,
+            updated_at: None,
+        }
+    }
 }
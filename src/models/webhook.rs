@@ -0,0 +1,111 @@
+use serde::{Deserialize, Serialize};
+use sqlx::FromRow;
+
+use crate::services::{events, webhooks};
+use crate::utils::validation::{FieldErrors, Validate};
+
+#[derive(Debug, Clone, Serialize, FromRow)]
+pub struct Webhook {
+    pub id: String,
+    #[serde(rename = "userId")]
+    pub user_id: String,
+    pub url: String,
+    /// Only returned when the webhook is created or the secret is rotated.
+    #[serde(skip)]
+    pub secret: String,
+    /// JSON array of subscribed event types; empty means every type.
+    #[serde(skip)]
+    pub event_types: String,
+    #[serde(rename = "schemaVersion")]
+    pub schema_version: i64,
+    #[serde(rename = "isActive")]
+    pub is_active: bool,
+    #[serde(rename = "createdAt")]
+    pub created_at: String,
+    #[serde(rename = "updatedAt")]
+    pub updated_at: String,
+}
+
+impl Webhook {
+    pub fn events(&self) -> Vec<String> {
+        serde_json::from_str(&self.event_types).unwrap_or_default()
+    }
+}
+
+#[derive(Debug, Deserialize)]
+pub struct CreateWebhookRequest {
+    pub url: String,
+    /// Generated when omitted.
+    pub secret: Option<String>,
+    pub events: Option<Vec<String>>,
+    #[serde(alias = "schemaVersion")]
+    pub schema_version: Option<u32>,
+    #[serde(alias = "isActive")]
+    pub is_active: Option<bool>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct UpdateWebhookRequest {
+    pub url: Option<String>,
+    pub secret: Option<String>,
+    pub events: Option<Vec<String>>,
+    #[serde(alias = "schemaVersion")]
+    pub schema_version: Option<u32>,
+    #[serde(alias = "isActive")]
+    pub is_active: Option<bool>,
+}
+
+/// One attempt series to deliver an event to a webhook.
+#[derive(Debug, Clone, Serialize, FromRow)]
+pub struct WebhookDelivery {
+    pub id: String,
+    #[serde(rename = "webhookId")]
+    pub webhook_id: String,
+    #[serde(rename = "eventId")]
+    pub event_id: String,
+    #[serde(rename = "eventType")]
+    pub event_type: String,
+    pub attempts: i64,
+    #[serde(rename = "lastStatus")]
+    pub last_status: Option<i64>,
+    #[serde(rename = "lastError")]
+    pub last_error: Option<String>,
+    #[serde(rename = "nextAttemptAt")]
+    pub next_attempt_at: String,
+    #[serde(rename = "deliveredAt")]
+    pub delivered_at: Option<String>,
+    #[serde(rename = "createdAt")]
+    pub created_at: String,
+}
+
+fn valid_url(url: &str) -> bool {
+    reqwest::Url::parse(url).is_ok_and(|u| matches!(u.scheme(), "http" | "https") && u.host().is_some())
+}
+
+fn check_webhook(errors: &mut FieldErrors, url: Option<&str>, events: Option<&[String]>, schema_version: Option<u32>) {
+    if let Some(url) = url {
+        errors.check(valid_url(url.trim()), "url", "must be an http or https URL");
+    }
+    if let Some(events) = events {
+        errors.check(
+            events.iter().all(|e| webhooks::is_known_event_type(e)),
+            "events",
+            "must only name known event types",
+        );
+    }
+    if let Some(version) = schema_version {
+        errors.check(events::is_supported_version(version), "schemaVersion", "is not a supported schema version");
+    }
+}
+
+impl Validate for CreateWebhookRequest {
+    fn check_fields(&self, errors: &mut FieldErrors) {
+        check_webhook(errors, Some(&self.url), self.events.as_deref(), self.schema_version);
+    }
+}
+
+impl Validate for UpdateWebhookRequest {
+    fn check_fields(&self, errors: &mut FieldErrors) {
+        check_webhook(errors, self.url.as_deref(), self.events.as_deref(), self.schema_version);
+    }
+}
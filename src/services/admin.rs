@@ -0,0 +1,171 @@
+use std::path::Path;
+
+use anyhow::{anyhow, Result};
+use bcrypt::{hash, DEFAULT_COST};
+use chrono::{Duration, Utc};
+
+use crate::models::{
+    ActiveUsers, AdminStats, CreateUserRequest, DailyCount, DatabaseFileStats, TableRows, User, UserCounts,
+    MIN_PASSWORD_LENGTH,
+};
+use crate::services::database::DbPool;
+use crate::services::maintenance;
+use crate::utils::datetime::format_db_datetime;
+use crate::utils::validation::{FieldErrors, Validate};
+
+/// What [`create_admin`] did to the account with the given email.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum AdminAccount {
+    Created(String),
+    Promoted(String),
+}
+
+/// Makes `email` an admin, creating the account if there isn't one. An existing account keeps
+/// its password unless `password` is given; a new one needs it. Admins start verified, since
+/// no one may be around to read the email.
+pub async fn create_admin(pool: &DbPool, name: &str, email: &str, password: Option<&str>) -> Result<AdminAccount> {
+    let email = email.trim().to_lowercase();
+    let now = format_db_datetime(Utc::now());
+    let existing = sqlx::query_as::<_, User>("SELECT * FROM users WHERE lower(email) = ?")
+        .bind(&email)
+        .fetch_optional(pool)
+        .await?;
+
+    if let Some(user) = existing {
+        if let Some(password) = password {
+            check_password(password)?;
+            sqlx::query("UPDATE users SET password_hash = ?, updated_at = ? WHERE id = ?")
+                .bind(hash(password, DEFAULT_COST)?)
+                .bind(&now)
+                .bind(&user.id)
+                .execute(pool)
+                .await?;
+        }
+        sqlx::query("UPDATE users SET is_admin = TRUE, email_verified_at = COALESCE(email_verified_at, ?), updated_at = ? WHERE id = ?")
+            .bind(&now)
+            .bind(&now)
+            .bind(&user.id)
+            .execute(pool)
+            .await?;
+        return Ok(AdminAccount::Promoted(user.id));
+    }
+
+    let password = password.ok_or_else(|| anyhow!("{} has no account yet, so a password is needed", email))?;
+    let request = CreateUserRequest {
+        name: name.to_string(),
+        email: email.clone(),
+        password: password.to_string(),
+    };
+    let mut errors = FieldErrors::default();
+    request.check_fields(&mut errors);
+    if !errors.is_empty() {
+        return Err(anyhow!("{}", errors));
+    }
+
+    let user = User::new(request.name, request.email, hash(password, DEFAULT_COST)?);
+    sqlx::query(
+        "INSERT INTO users (id, name, email, password_hash, is_admin, email_verified_at, created_at, updated_at) VALUES (?, ?, ?, ?, TRUE, ?, ?, ?)"
+    )
+    .bind(&user.id)
+    .bind(&user.name)
+    .bind(&user.email)
+    .bind(&user.password_hash)
+    .bind(&now)
+    .bind(user.created_at)
+    .bind(user.updated_at)
+    .execute(pool)
+    .await?;
+    Ok(AdminAccount::Created(user.id))
+}
+
+fn check_password(password: &str) -> Result<()> {
+    if password.chars().count() < MIN_PASSWORD_LENGTH {
+        return Err(anyhow!("password must be at least {} characters", MIN_PASSWORD_LENGTH));
+    }
+    Ok(())
+}
+
+/// The id of the account with `email`, in any case.
+pub async fn user_id_by_email(pool: &DbPool, email: &str) -> Result<String> {
+    sqlx::query_scalar::<_, String>("SELECT id FROM users WHERE lower(email) = ?")
+        .bind(email.trim().to_lowercase())
+        .fetch_optional(pool)
+        .await?
+        .ok_or_else(|| anyhow!("No user with email {}", email))
+}
+
+/// Writes a consistent copy of the whole database to `path` with `VACUUM INTO`, which is safe
+/// while the server is running. Refuses to overwrite an existing file.
+pub async fn backup(pool: &DbPool, path: &Path) -> Result<()> {
+    if path.exists() {
+        return Err(anyhow!("{} already exists", path.display()));
+    }
+    let target = path.to_str().ok_or_else(|| anyhow!("{} is not a valid UTF-8 path", path.display()))?;
+    sqlx::query("VACUUM INTO ?").bind(target).execute(pool).await?;
+    Ok(())
+}
+
+/// Days of signups [`stats`] reports.
+pub const SIGNUP_WINDOW_DAYS: i64 = 30;
+
+/// User counts, activity from `api_usage`, signups per day, the database file's size and the
+/// rows in every table.
+pub async fn stats(pool: &DbPool) -> Result<AdminStats> {
+    let users = sqlx::query_as::<_, UserCounts>(
+        "SELECT COUNT(*) AS total, COUNT(email_verified_at) AS verified, COALESCE(SUM(is_admin), 0) AS admins FROM users"
+    )
+    .fetch_one(pool)
+    .await?;
+
+    let today = Utc::now().date_naive();
+    let since = |days: i64| (today - Duration::days(days - 1)).format("%Y-%m-%d").to_string();
+    let active_users = sqlx::query_as::<_, ActiveUsers>(
+        r#"
+        SELECT
+            COUNT(DISTINCT CASE WHEN day >= ? THEN user_id END) AS daily,
+            COUNT(DISTINCT CASE WHEN day >= ? THEN user_id END) AS weekly,
+            COUNT(DISTINCT user_id) AS monthly
+        FROM api_usage WHERE day >= ?
+        "#,
+    )
+    .bind(since(1))
+    .bind(since(7))
+    .bind(since(30))
+    .fetch_one(pool)
+    .await?;
+
+    let signups_by_day = sqlx::query_as::<_, DailyCount>(
+        "SELECT date(created_at) AS day, COUNT(*) AS count FROM users WHERE date(created_at) >= ? GROUP BY day ORDER BY day ASC"
+    )
+    .bind(since(SIGNUP_WINDOW_DAYS))
+    .fetch_all(pool)
+    .await?;
+
+    let size = maintenance::size(pool).await?;
+    let database = DatabaseFileStats {
+        size_bytes: size.bytes(),
+        free_bytes: size.free_bytes(),
+        page_size: size.page_size,
+    };
+
+    let names: Vec<String> = sqlx::query_scalar(
+        "SELECT name FROM sqlite_master WHERE type = 'table' AND name NOT LIKE 'sqlite_%' ORDER BY name"
+    )
+    .fetch_all(pool)
+    .await?;
+    let mut tables = Vec::with_capacity(names.len());
+    for table in names {
+        // Names come from sqlite_master, but quote them anyway
+        let sql = format!("SELECT COUNT(*) FROM \"{}\"", table.replace('"', "\"\""));
+        let rows = sqlx::query_scalar(&sql).fetch_one(pool).await?;
+        tables.push(TableRows { table, rows });
+    }
+
+    Ok(AdminStats {
+        users,
+        active_users,
+        signups_by_day,
+        database,
+        tables,
+    })
+}
@@ -0,0 +1,180 @@
+use anyhow::Result;
+use chrono::{DateTime, Months, Utc};
+use rust_decimal::prelude::FromPrimitive;
+use rust_decimal::Decimal;
+use uuid::Uuid;
+
+use crate::models::{AmortizationEntry, AmortizationSchedule, AmortizationTracking};
+use crate::services::database::DbPool;
+use crate::utils::datetime::format_db_datetime;
+use crate::utils::money::Money;
+
+fn round_money(value: Money) -> Money {
+    value.round_dp(2)
+}
+
+/// Computes the fixed monthly payment for a fully amortizing loan. The annuity factor needs a
+/// fractional power, so it is worked out in floating point and applied to the exact principal.
+pub fn monthly_payment(principal: Money, annual_rate: f64, term_months: u32) -> Money {
+    let monthly_rate = annual_rate / 100.0 / 12.0;
+    let n = term_months as f64;
+    let factor = if monthly_rate == 0.0 {
+        1.0 / n
+    } else {
+        monthly_rate / (1.0 - (1.0 + monthly_rate).powf(-n))
+    };
+    principal * factor
+}
+
+/// Builds a full amortization table. The first installment falls one month after
+/// `start_date`, and the final installment absorbs any rounding residue.
+pub fn build_schedule(
+    user_id: &str,
+    principal: Money,
+    annual_rate: f64,
+    term_months: u32,
+    start_date: DateTime<Utc>,
+) -> (AmortizationSchedule, Vec<AmortizationEntry>) {
+    let schedule_id = Uuid::new_v4().to_string();
+    let monthly_rate = Decimal::from_f64(annual_rate).unwrap_or_default() / Decimal::from(1200);
+    let payment = round_money(monthly_payment(principal, annual_rate, term_months));
+
+    let mut entries = Vec::with_capacity(term_months as usize);
+    let mut remaining = principal;
+    let mut total_interest = Money::ZERO;
+
+    for period in 1..=term_months {
+        let interest = round_money(remaining * monthly_rate);
+        let mut principal_part = round_money(payment - interest);
+        if period == term_months || principal_part > remaining {
+            principal_part = round_money(remaining);
+        }
+        remaining = round_money(remaining - principal_part);
+        total_interest += interest;
+
+        entries.push(AmortizationEntry {
+            schedule_id: schedule_id.clone(),
+            period: period as i64,
+            due_date: start_date + Months::new(period),
+            payment: round_money(principal_part + interest),
+            principal: principal_part,
+            interest,
+            remaining,
+            actual_payment: None,
+            paid_date: None,
+        });
+    }
+
+    let schedule = AmortizationSchedule {
+        id: schedule_id,
+        user_id: user_id.to_string(),
+        loan_id: None,
+        liability_id: None,
+        principal: round_money(principal),
+        annual_rate,
+        term_months: term_months as i64,
+        payment,
+        total_interest: round_money(total_interest),
+        start_date,
+        created_at: Utc::now(),
+    };
+
+    (schedule, entries)
+}
+
+/// Persists a schedule, replacing any schedule already attached to the same loan or liability.
+pub async fn save_schedule(
+    pool: &DbPool,
+    schedule: &AmortizationSchedule,
+    entries: &[AmortizationEntry],
+) -> Result<()> {
+    let mut tx = pool.begin().await?;
+
+    sqlx::query(
+        "DELETE FROM amortization_schedules WHERE user_id = ? AND ((loan_id IS NOT NULL AND loan_id = ?) OR (liability_id IS NOT NULL AND liability_id = ?))"
+    )
+    .bind(&schedule.user_id)
+    .bind(&schedule.loan_id)
+    .bind(&schedule.liability_id)
+    .execute(&mut tx)
+    .await?;
+
+    sqlx::query(
+        "INSERT INTO amortization_schedules (id, user_id, loan_id, liability_id, principal, annual_rate, term_months, payment, total_interest, start_date, created_at) VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?)"
+    )
+    .bind(&schedule.id)
+    .bind(&schedule.user_id)
+    .bind(&schedule.loan_id)
+    .bind(&schedule.liability_id)
+    .bind(schedule.principal)
+    .bind(schedule.annual_rate)
+    .bind(schedule.term_months)
+    .bind(schedule.payment)
+    .bind(schedule.total_interest)
+    .bind(format_db_datetime(schedule.start_date))
+    .bind(format_db_datetime(schedule.created_at))
+    .execute(&mut tx)
+    .await?;
+
+    for entry in entries {
+        sqlx::query(
+            "INSERT INTO amortization_entries (schedule_id, period, due_date, payment, principal, interest, remaining, actual_payment, paid_date) VALUES (?, ?, ?, ?, ?, ?, ?, NULL, NULL)"
+        )
+        .bind(&entry.schedule_id)
+        .bind(entry.period)
+        .bind(format_db_datetime(entry.due_date))
+        .bind(entry.payment)
+        .bind(entry.principal)
+        .bind(entry.interest)
+        .bind(entry.remaining)
+        .execute(&mut tx)
+        .await?;
+    }
+
+    tx.commit().await?;
+    Ok(())
+}
+
+/// Loads a stored schedule and its entries if it belongs to the user.
+pub async fn load_schedule(
+    pool: &DbPool,
+    user_id: &str,
+    schedule_id: &str,
+) -> Result<Option<(AmortizationSchedule, Vec<AmortizationEntry>)>> {
+    let schedule = sqlx::query_as::<_, AmortizationSchedule>(
+        "SELECT * FROM amortization_schedules WHERE id = ? AND user_id = ?",
+    )
+    .bind(schedule_id)
+    .bind(user_id)
+    .fetch_optional(pool)
+    .await?;
+
+    let schedule = match schedule {
+        Some(schedule) => schedule,
+        None => return Ok(None),
+    };
+
+    let entries = sqlx::query_as::<_, AmortizationEntry>(
+        "SELECT * FROM amortization_entries WHERE schedule_id = ? ORDER BY period ASC",
+    )
+    .bind(&schedule.id)
+    .fetch_all(pool)
+    .await?;
+
+    Ok(Some((schedule, entries)))
+}
+
+/// Compares planned installments due by `now` against the recorded actual payments.
+pub fn tracking(entries: &[AmortizationEntry], now: DateTime<Utc>) -> AmortizationTracking {
+    let due: Vec<_> = entries.iter().filter(|e| e.due_date <= now).collect();
+    let planned_to_date: Money = due.iter().map(|e| e.payment).sum();
+    let paid_to_date: Money = entries.iter().filter_map(|e| e.actual_payment).sum();
+
+    AmortizationTracking {
+        planned_to_date,
+        paid_to_date,
+        variance: paid_to_date - planned_to_date,
+        periods_due: due.len() as i64,
+        periods_paid: entries.iter().filter(|e| e.actual_payment.is_some()).count() as i64,
+    }
+}
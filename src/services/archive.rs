@@ -0,0 +1,491 @@
+use std::collections::{BTreeMap, HashMap, HashSet};
+
+use anyhow::{anyhow, Result};
+use chrono::Utc;
+use hyper::body::Body;
+use serde_json::{json, Map, Value};
+use sqlx::query::Query;
+use sqlx::sqlite::{SqliteArguments, SqliteRow};
+use sqlx::{Column, Row, Sqlite, TypeInfo, ValueRef};
+use uuid::Uuid;
+
+use crate::models::{
+    ArchiveUser, ConflictPolicy, DataArchive, RestoreRowError, RestoreSummary, ARCHIVE_FORMAT, ARCHIVE_VERSION,
+};
+use crate::services::database::{money_columns, DbPool};
+use crate::services::preferences;
+use crate::utils::datetime::format_db_datetime;
+use crate::utils::money::Money;
+use crate::utils::streaming::ChunkedBody;
+
+/// How rows of an archived table are tied to their owner.
+#[derive(Debug, Clone, Copy)]
+pub enum Scope {
+    /// The table has its own `user_id` column.
+    Owned,
+    /// Rows hang off a parent row through `key`, e.g. `budget_categories.budget_id`.
+    Child { parent: &'static str, key: &'static str },
+}
+
+#[derive(Debug, Clone, Copy)]
+pub struct ArchiveTable {
+    pub name: &'static str,
+    pub scope: Scope,
+    /// Columns holding IDs of rows in other archived tables, as `(column, table)`.
+    pub references: &'static [(&'static str, &'static str)],
+}
+
+impl ArchiveTable {
+    /// Reference columns, including a child table's link to its parent.
+    fn all_references(&self) -> Vec<(&'static str, &'static str)> {
+        let parent = match self.scope {
+            Scope::Owned => None,
+            Scope::Child { parent, key } => Some((key, parent)),
+        };
+        parent.into_iter().chain(self.references.iter().copied()).collect()
+    }
+}
+
+const fn owned(name: &'static str, references: &'static [(&'static str, &'static str)]) -> ArchiveTable {
+    ArchiveTable { name, scope: Scope::Owned, references }
+}
+
+const fn child(name: &'static str, parent: &'static str, key: &'static str) -> ArchiveTable {
+    ArchiveTable { name, scope: Scope::Child { parent, key }, references: &[] }
+}
+
+/// The tables holding user data, parents before children. Sessions, usage, events, version
+/// history and delivery queues are operational and left out, as are webhooks, which carry
+/// signing secrets, and bill reminders, whose targets span several tables.
+pub const ARCHIVE_TABLES: &[ArchiveTable] = &[
+    owned("user_settings", &[]),
+    owned("accounts", &[]),
+    owned("categories", &[]),
+    owned("category_archives", &[("category_id", "categories")]),
+    owned("transactions", &[("account_id", "accounts")]),
+    owned("loans", &[("account_id", "accounts"), ("transaction_id", "transactions")]),
+    owned("liabilities", &[("account_id", "accounts"), ("transaction_id", "transactions")]),
+    owned("savings_goals", &[("account_id", "accounts")]),
+    owned("savings_goal_contributions", &[("goal_id", "savings_goals"), ("transaction_id", "transactions")]),
+    owned("budgets", &[("account_id", "accounts")]),
+    child("budget_categories", "budgets", "budget_id"),
+    owned("budget_periods", &[("budget_id", "budgets")]),
+    owned("recurring_transactions", &[("account_id", "accounts"), ("savings_goal_id", "savings_goals")]),
+    owned("amortization_schedules", &[("loan_id", "loans"), ("liability_id", "liabilities")]),
+    child("amortization_entries", "amortization_schedules", "schedule_id"),
+    owned("cash_counts", &[("account_id", "accounts"), ("adjustment_transaction_id", "transactions")]),
+    child("cash_count_denominations", "cash_counts", "cash_count_id"),
+    owned("category_keywords", &[]),
+    owned("user_exchange_rates", &[]),
+    owned("holdings", &[("account_id", "accounts")]),
+    owned("term_deposits", &[("account_id", "accounts")]),
+    owned("card_statements", &[("account_id", "accounts")]),
+    owned("emi_plans", &[("account_id", "accounts"), ("recurring_transaction_id", "recurring_transactions")]),
+    owned("reconciliations", &[("account_id", "accounts")]),
+    ArchiveTable {
+        name: "reconciliation_transactions",
+        scope: Scope::Child { parent: "reconciliations", key: "reconciliation_id" },
+        references: &[("transaction_id", "transactions")],
+    },
+];
+
+/// Converts a row to a JSON object, keeping SQLite's storage class for each value. Money
+/// columns are written as plain amounts rather than their stored scaled integers.
+fn row_to_json(table: &str, row: &SqliteRow) -> Result<Map<String, Value>> {
+    let money = money_columns(table);
+    let mut object = Map::new();
+    for column in row.columns() {
+        let index = column.ordinal();
+        let raw = row.try_get_raw(index)?;
+        let value = if raw.is_null() {
+            Value::Null
+        } else if money.contains(&column.name()) {
+            json!(row.try_get::<Money, _>(index)?)
+        } else {
+            match raw.type_info().name() {
+                "INTEGER" => json!(row.try_get::<i64, _>(index)?),
+                "REAL" => json!(row.try_get::<f64, _>(index)?),
+                "BLOB" => json!(hex::encode(row.try_get::<Vec<u8>, _>(index)?)),
+                _ => json!(row.try_get::<String, _>(index)?),
+            }
+        };
+        object.insert(column.name().to_string(), value);
+    }
+    Ok(object)
+}
+
+/// Rows of an archived table read per query.
+const ARCHIVE_PAGE_SIZE: i64 = 500;
+
+/// The user's rows in one table after `after_rowid`, a page at a time in rowid order, with the
+/// rowid of the last one.
+async fn table_page(
+    pool: &DbPool,
+    user_id: &str,
+    table: &ArchiveTable,
+    after_rowid: i64,
+) -> Result<(Vec<Map<String, Value>>, Option<i64>)> {
+    // Table and column names come from ARCHIVE_TABLES, never from the request.
+    let sql = match table.scope {
+        Scope::Owned => format!(
+            "SELECT rowid AS archive_rowid, * FROM {} WHERE user_id = ? AND rowid > ? ORDER BY rowid LIMIT ?",
+            table.name
+        ),
+        Scope::Child { parent, key } => format!(
+            "SELECT rowid AS archive_rowid, * FROM {} WHERE {} IN (SELECT id FROM {} WHERE user_id = ?) AND rowid > ? ORDER BY rowid LIMIT ?",
+            table.name, key, parent
+        ),
+    };
+
+    let rows = sqlx::query(&sql)
+        .bind(user_id)
+        .bind(after_rowid)
+        .bind(ARCHIVE_PAGE_SIZE)
+        .fetch_all(pool)
+        .await?;
+    let last = match rows.last() {
+        Some(last) if rows.len() as i64 == ARCHIVE_PAGE_SIZE => Some(last.try_get("archive_rowid")?),
+        _ => None,
+    };
+    let rows = rows
+        .iter()
+        .map(|row| {
+            let mut object = row_to_json(table.name, row)?;
+            object.remove("archive_rowid");
+            Ok(object)
+        })
+        .collect::<Result<_>>()?;
+    Ok((rows, last))
+}
+
+/// Every row the user owns in one table.
+async fn table_rows(pool: &DbPool, user_id: &str, table: &ArchiveTable) -> Result<Vec<Map<String, Value>>> {
+    let mut rows = Vec::new();
+    let mut after_rowid = 0;
+    loop {
+        let (page, last) = table_page(pool, user_id, table, after_rowid).await?;
+        rows.extend(page);
+        match last {
+            Some(last) => after_rowid = last,
+            None => return Ok(rows),
+        }
+    }
+}
+
+async fn archive_user(pool: &DbPool, user_id: &str) -> Result<ArchiveUser> {
+    let user = sqlx::query("SELECT id, name, email, created_at FROM users WHERE id = ?")
+        .bind(user_id)
+        .fetch_optional(pool)
+        .await?
+        .ok_or_else(|| anyhow!("User {} not found", user_id))?;
+
+    Ok(ArchiveUser {
+        id: user.get("id"),
+        name: user.get("name"),
+        email: user.get("email"),
+        created_at: user.get("created_at"),
+    })
+}
+
+/// Collects everything the user owns into a single archive.
+pub async fn export_archive(pool: &DbPool, user_id: &str) -> Result<DataArchive> {
+    let user = archive_user(pool, user_id).await?;
+
+    let mut tables = BTreeMap::new();
+    for table in ARCHIVE_TABLES {
+        tables.insert(table.name.to_string(), table_rows(pool, user_id, table).await?);
+    }
+
+    Ok(DataArchive {
+        format: ARCHIVE_FORMAT.to_string(),
+        version: ARCHIVE_VERSION,
+        exported_at: format_db_datetime(Utc::now()),
+        user,
+        tables,
+    })
+}
+
+/// The same archive as [`export_archive`], written out as JSON while it is read, a page of
+/// rows at a time, so a large one never sits in memory whole.
+pub async fn stream_archive(pool: DbPool, user_id: String) -> Result<Body> {
+    let header = DataArchive {
+        format: ARCHIVE_FORMAT.to_string(),
+        version: ARCHIVE_VERSION,
+        exported_at: format_db_datetime(Utc::now()),
+        user: archive_user(&pool, &user_id).await?,
+        tables: BTreeMap::new(),
+    };
+    // Drop the empty `tables` object's closing braces, to write the tables in their place
+    let mut head = serde_json::to_vec(&header)?;
+    head.truncate(head.len() - 2);
+
+    let (mut writer, body) = ChunkedBody::new();
+    writer.push(&head);
+    tokio::spawn(async move {
+        if let Err(e) = write_tables(&pool, &user_id, &mut writer).await {
+            tracing::error!("Archive export for user {} failed part way: {:#}", user_id, e);
+            return;
+        }
+        writer.push(b"}}");
+        if writer.finish().await.is_err() {
+            tracing::debug!("Archive export for user {} cancelled by client", user_id);
+        }
+    });
+    Ok(body)
+}
+
+async fn write_tables(pool: &DbPool, user_id: &str, writer: &mut ChunkedBody) -> Result<()> {
+    for (index, table) in ARCHIVE_TABLES.iter().enumerate() {
+        if index > 0 {
+            writer.push(b",");
+        }
+        writer.push_json(&table.name)?;
+        writer.push(b":[");
+
+        let mut after_rowid = 0;
+        let mut first = true;
+        loop {
+            let (rows, last) = table_page(pool, user_id, table, after_rowid).await?;
+            for row in &rows {
+                if !first {
+                    writer.push(b",");
+                }
+                writer.push_json(row)?;
+                first = false;
+            }
+            writer.flush().await?;
+            match last {
+                Some(last) => after_rowid = last,
+                None => break,
+            }
+        }
+        writer.push(b"]");
+    }
+    Ok(())
+}
+
+/// Checks the archive header; returns a message suitable for the client when it is unusable.
+pub fn check_header(archive: &DataArchive) -> Result<(), String> {
+    if archive.format != ARCHIVE_FORMAT {
+        return Err(format!("Not a {} file", ARCHIVE_FORMAT));
+    }
+    if archive.version > ARCHIVE_VERSION {
+        return Err(format!(
+            "Archive version {} is newer than the supported version {}",
+            archive.version, ARCHIVE_VERSION
+        ));
+    }
+    Ok(())
+}
+
+fn bind_value<'q>(
+    query: Query<'q, Sqlite, SqliteArguments<'q>>,
+    value: &Value,
+    is_money: bool,
+) -> Query<'q, Sqlite, SqliteArguments<'q>> {
+    if is_money {
+        if let Ok(amount) = serde_json::from_value::<Money>(value.clone()) {
+            return query.bind(amount);
+        }
+    }
+    match value {
+        Value::Null => query.bind(None::<String>),
+        Value::Bool(flag) => query.bind(*flag),
+        Value::Number(number) => match number.as_i64() {
+            Some(integer) => query.bind(integer),
+            None => query.bind(number.as_f64()),
+        },
+        Value::String(text) => query.bind(text.clone()),
+        other => query.bind(other.to_string()),
+    }
+}
+
+/// Writes an archive back for `user_id` inside one database transaction. Archived IDs are kept
+/// when free; taken ones are handled per `policy`, and references follow any remapped ID. Every
+/// row is attempted so all problems are reported at once; if any row fails, or when only
+/// validating, the transaction is rolled back.
+pub async fn restore_archive(
+    pool: &DbPool,
+    user_id: &str,
+    mut archive: DataArchive,
+    policy: ConflictPolicy,
+    validate_only: bool,
+) -> Result<RestoreSummary> {
+    let mut summary = RestoreSummary {
+        validate_only,
+        ..Default::default()
+    };
+
+    // Archives from before version 2 hold preferences as one row of columns
+    if let Some(rows) = archive.tables.remove("user_preferences") {
+        let settings = archive.tables.entry("user_settings".to_string()).or_default();
+        settings.extend(rows.iter().flat_map(preferences::legacy_settings));
+    }
+
+    // Archives from before version 3 mark archived categories on the category row
+    if let Some(rows) = archive.tables.get_mut("categories") {
+        let mut archived = Vec::new();
+        for row in rows.iter_mut() {
+            if matches!(row.remove("is_archived"), Some(flag) if flag == json!(1) || flag == json!(true)) {
+                let at = ["updated_at", "created_at"].iter().find_map(|column| row.get(*column).filter(|at| !at.is_null()));
+                let mut mark = Map::new();
+                mark.insert("category_id".to_string(), row.get("id").cloned().unwrap_or(Value::Null));
+                mark.insert("archived_at".to_string(), at.cloned().unwrap_or(Value::Null));
+                archived.push(mark);
+            }
+        }
+        if !archived.is_empty() {
+            archive.tables.entry("category_archives".to_string()).or_default().extend(archived);
+        }
+    }
+
+    for name in archive.tables.keys() {
+        if !ARCHIVE_TABLES.iter().any(|table| table.name == name) {
+            summary.errors.push(RestoreRowError {
+                table: name.clone(),
+                index: 0,
+                message: "Unknown table".to_string(),
+            });
+        }
+    }
+
+    let mut tx = pool.begin().await?;
+    let mut ids: HashMap<(&'static str, String), String> = HashMap::new();
+
+    for table in ARCHIVE_TABLES {
+        let Some(rows) = archive.tables.remove(table.name) else {
+            continue;
+        };
+        let columns: HashSet<String> = sqlx::query(&format!("PRAGMA table_info({})", table.name))
+            .fetch_all(&mut tx)
+            .await?
+            .iter()
+            .map(|row| row.get::<String, _>("name"))
+            .collect();
+        let has_id = columns.contains("id");
+        let references = table.all_references();
+
+        for (index, mut row) in rows.into_iter().enumerate() {
+            let mut error = |message: String| {
+                summary.errors.push(RestoreRowError {
+                    table: table.name.to_string(),
+                    index,
+                    message,
+                })
+            };
+
+            if let Some(unknown) = row.keys().find(|column| !columns.contains(*column)) {
+                error(format!("Unknown column '{}'", unknown));
+                continue;
+            }
+            if matches!(table.scope, Scope::Owned) {
+                row.insert("user_id".to_string(), json!(user_id));
+            }
+
+            let mut unresolved = None;
+            for (column, target) in &references {
+                let old = match row.get(*column) {
+                    None | Some(Value::Null) => continue,
+                    Some(Value::String(old)) => old.clone(),
+                    Some(_) => {
+                        unresolved = Some(format!("'{}' must be an ID", column));
+                        break;
+                    }
+                };
+                let resolved = match ids.get(&(*target, old.clone())) {
+                    Some(new) => Some(new.clone()),
+                    // Rows outside the archive may only be referenced if the user already owns them,
+                    // or if they're shared, like the built-in categories.
+                    None => sqlx::query_scalar::<_, String>(&format!("SELECT id FROM {} WHERE id = ? AND user_id IN (?, '')", target))
+                        .bind(&old)
+                        .bind(user_id)
+                        .fetch_optional(&mut tx)
+                        .await?,
+                };
+                match resolved {
+                    Some(id) => {
+                        row.insert(column.to_string(), json!(id));
+                    }
+                    None => {
+                        unresolved = Some(format!("'{}' refers to unknown {} {}", column, target, old));
+                        break;
+                    }
+                }
+            }
+            if let Some(message) = unresolved {
+                error(message);
+                continue;
+            }
+
+            if has_id {
+                let Some(Value::String(old_id)) = row.get("id").cloned() else {
+                    error("Missing id".to_string());
+                    continue;
+                };
+                let owner = sqlx::query_scalar::<_, String>(&format!("SELECT user_id FROM {} WHERE id = ?", table.name))
+                    .bind(&old_id)
+                    .fetch_optional(&mut tx)
+                    .await?;
+                let new_id = match owner {
+                    None => old_id.clone(),
+                    Some(owner) if owner == user_id && policy == ConflictPolicy::Skip => {
+                        ids.insert((table.name, old_id.clone()), old_id);
+                        *summary.skipped.entry(table.name.to_string()).or_default() += 1;
+                        continue;
+                    }
+                    Some(_) => {
+                        summary.remapped += 1;
+                        Uuid::new_v4().to_string()
+                    }
+                };
+                ids.insert((table.name, old_id), new_id.clone());
+                row.insert("id".to_string(), json!(new_id));
+            }
+
+            // Settings and manual rates are one row per user and key (or pair), so the archived copy
+            // replaces the current one; key-only rows such as budget categories are left alone
+            // if already present.
+            let verb = match (table.name, has_id) {
+                ("user_settings" | "user_exchange_rates", _) => "INSERT OR REPLACE",
+                (_, true) => "INSERT",
+                (_, false) => "INSERT OR IGNORE",
+            };
+            let names: Vec<&String> = row.keys().collect();
+            let sql = format!(
+                "{} INTO {} ({}) VALUES ({})",
+                verb,
+                table.name,
+                names.iter().map(|name| format!("\"{}\"", name)).collect::<Vec<_>>().join(", "),
+                vec!["?"; names.len()].join(", ")
+            );
+            let mut query = sqlx::query(&sql);
+            for (name, value) in &row {
+                query = bind_value(query, value, money_columns(table.name).contains(&name.as_str()));
+            }
+
+            match query.execute(&mut tx).await {
+                Ok(result) if result.rows_affected() > 0 => {
+                    *summary.inserted.entry(table.name.to_string()).or_default() += 1;
+                }
+                Ok(_) => {
+                    *summary.skipped.entry(table.name.to_string()).or_default() += 1;
+                }
+                Err(e) => error(e.to_string()),
+            }
+        }
+    }
+
+    // Devices holding the old preferences pick up the restored ones on their next pull
+    if summary.inserted.contains_key("user_settings") {
+        preferences::bump_revision(&mut tx, user_id).await?;
+    }
+
+    if validate_only || !summary.errors.is_empty() {
+        tx.rollback().await?;
+    } else {
+        tx.commit().await?;
+        summary.committed = true;
+    }
+
+    Ok(summary)
+}
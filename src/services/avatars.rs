@@ -0,0 +1,86 @@
+use std::io::Cursor;
+
+use anyhow::Result;
+use image::imageops::FilterType;
+use image::io::{Limits, Reader};
+use image::{ImageFormat, ImageOutputFormat};
+use uuid::Uuid;
+
+use crate::services::database::DbPool;
+use crate::services::storage;
+
+/// Largest upload `POST /api/me/avatar` accepts.
+pub const MAX_AVATAR_UPLOAD_BYTES: usize = 5 * 1024 * 1024;
+
+/// Width and height of a stored avatar, in pixels.
+pub const AVATAR_SIZE: u32 = 256;
+
+/// Largest width or height an upload may claim, so a tiny file can't decode into gigabytes.
+const MAX_SOURCE_DIMENSION: u32 = 8192;
+
+/// Where avatars are kept in storage, and served from under `/avatars/`.
+const AVATAR_PREFIX: &str = "avatars";
+
+/// The URL an avatar stored under `key` is served at.
+pub fn url(key: &str) -> String {
+    format!("/{}", key)
+}
+
+/// The storage key of the avatar served as `/avatars/<file>`.
+pub fn key(file: &str) -> String {
+    format!("{}/{}", AVATAR_PREFIX, file)
+}
+
+/// Turns an uploaded PNG, JPEG or WebP into a square avatar: cropped to the middle, scaled to
+/// [`AVATAR_SIZE`] and written as PNG. The format is read from the bytes, not the client's
+/// content type. Errors are messages for the user.
+pub fn process(upload: &[u8]) -> Result<Vec<u8>, String> {
+    let mut reader = Reader::new(Cursor::new(upload))
+        .with_guessed_format()
+        .map_err(|_| "Could not read the image".to_string())?;
+    if !matches!(reader.format(), Some(ImageFormat::Png | ImageFormat::Jpeg | ImageFormat::WebP)) {
+        return Err("Avatar must be a PNG, JPEG or WebP image".to_string());
+    }
+    let mut limits = Limits::default();
+    limits.max_image_width = Some(MAX_SOURCE_DIMENSION);
+    limits.max_image_height = Some(MAX_SOURCE_DIMENSION);
+    reader.limits(limits);
+
+    let image = reader.decode().map_err(|e| format!("Could not read the image: {}", e))?;
+    let avatar = image.resize_to_fill(AVATAR_SIZE, AVATAR_SIZE, FilterType::Lanczos3);
+
+    let mut png = Vec::new();
+    avatar
+        .write_to(&mut Cursor::new(&mut png), ImageOutputFormat::Png)
+        .map_err(|e| format!("Could not write the avatar: {}", e))?;
+    Ok(png)
+}
+
+/// Stores `png` as `user_id`'s avatar and drops the one it replaces. Each upload gets a new
+/// key, so clients and caches can keep an avatar URL forever. Returns the new key.
+pub async fn replace(pool: &DbPool, user_id: &str, png: Vec<u8>) -> Result<String> {
+    let key = key(&format!("{}.png", Uuid::new_v4()));
+    storage::get().put(&key, png).await?;
+
+    let previous: Option<String> = sqlx::query_scalar("SELECT avatar_key FROM users WHERE id = ?")
+        .bind(user_id)
+        .fetch_one(pool)
+        .await?;
+    let updated = sqlx::query("UPDATE users SET avatar_key = ?, updated_at = ? WHERE id = ?")
+        .bind(&key)
+        .bind(chrono::Utc::now())
+        .bind(user_id)
+        .execute(pool)
+        .await;
+    if let Err(e) = updated {
+        storage::get().delete(&key).await.ok();
+        return Err(e.into());
+    }
+
+    if let Some(previous) = previous {
+        if let Err(e) = storage::get().delete(&previous).await {
+            tracing::warn!("Failed to delete replaced avatar {}: {}", previous, e);
+        }
+    }
+    Ok(key)
+}
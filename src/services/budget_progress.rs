@@ -0,0 +1,231 @@
+use std::collections::{BTreeMap, HashMap};
+
+use anyhow::Result;
+use chrono::{DateTime, NaiveDate, Utc};
+use sqlx::Row;
+
+use crate::models::{Budget, BudgetProgress, CategorySpending, CurrencySpending};
+use crate::services::{budget_rollover, currency, events};
+use crate::services::database::DbPool;
+use crate::services::notifications::{self, NewNotification};
+use crate::utils::datetime::format_db_datetime;
+use crate::utils::money::Money;
+
+/// Loads the join-table categories linked to a budget.
+pub async fn linked_categories(pool: &DbPool, budget_id: &str) -> Result<Vec<String>> {
+    let categories = sqlx::query("SELECT category FROM budget_categories WHERE budget_id = ? ORDER BY category")
+        .bind(budget_id)
+        .fetch_all(pool)
+        .await?
+        .into_iter()
+        .map(|row| row.get::<String, _>("category"))
+        .collect();
+
+    Ok(categories)
+}
+
+/// Loads the join-table categories for all of a user's budgets, keyed by budget ID.
+pub async fn linked_categories_by_budget(pool: &DbPool, user_id: &str) -> Result<HashMap<String, Vec<String>>> {
+    let rows = sqlx::query(
+        "SELECT bc.budget_id, bc.category FROM budget_categories bc JOIN budgets b ON b.id = bc.budget_id WHERE b.user_id = ? ORDER BY bc.category"
+    )
+    .bind(user_id)
+    .fetch_all(pool)
+    .await?;
+
+    let mut by_budget: HashMap<String, Vec<String>> = HashMap::new();
+    for row in rows {
+        by_budget
+            .entry(row.get::<String, _>("budget_id"))
+            .or_default()
+            .push(row.get::<String, _>("category"));
+    }
+
+    Ok(by_budget)
+}
+
+/// Replaces the join-table categories linked to a budget.
+pub async fn set_linked_categories(pool: &DbPool, budget_id: &str, categories: &[String]) -> Result<()> {
+    let mut tx = pool.begin().await?;
+
+    sqlx::query("DELETE FROM budget_categories WHERE budget_id = ?")
+        .bind(budget_id)
+        .execute(&mut tx)
+        .await?;
+
+    for category in categories {
+        sqlx::query("INSERT OR IGNORE INTO budget_categories (budget_id, category) VALUES (?, ?)")
+            .bind(budget_id)
+            .bind(category)
+            .execute(&mut tx)
+            .await?;
+    }
+
+    tx.commit().await?;
+    Ok(())
+}
+
+/// Sums a budget's expenses per category between `start` (inclusive) and `end` (exclusive),
+/// honoring the budget's category set and account scope.
+pub async fn spending_by_category(
+    pool: &DbPool,
+    budget: &Budget,
+    start: DateTime<Utc>,
+    end: DateTime<Utc>,
+) -> Result<Vec<CategorySpending>> {
+    Ok(budget_spending(pool, budget, start, end).await?.0)
+}
+
+/// Like [`spending_by_category`], also returning the per-currency detail. Multi-currency
+/// budgets count expenses in any currency, converted at the rate on each transaction's date;
+/// spending with no known rate is reported per currency but left out of the category totals.
+pub async fn budget_spending(
+    pool: &DbPool,
+    budget: &Budget,
+    start: DateTime<Utc>,
+    end: DateTime<Utc>,
+) -> Result<(Vec<CategorySpending>, Vec<CurrencySpending>)> {
+    let categories = budget.target_categories(&linked_categories(pool, &budget.id).await?);
+
+    let category_filter = if categories.is_empty() {
+        String::new()
+    } else {
+        format!(" AND category IN ({})", vec!["?"; categories.len()].join(", "))
+    };
+    let sql = format!(
+        "SELECT COALESCE(category, '') AS category, currency, substr(date, 1, 10) AS day, SUM(amount) AS spent FROM transactions WHERE user_id = ? AND transaction_type = 'expense' AND (? OR currency = ?) AND date >= ? AND date < ? AND (? IS NULL OR account_id = ?){} GROUP BY category, currency, day",
+        category_filter
+    );
+
+    let mut query = sqlx::query(&sql)
+        .bind(&budget.user_id)
+        .bind(budget.multi_currency)
+        .bind(&budget.currency)
+        .bind(format_db_datetime(start))
+        .bind(format_db_datetime(end))
+        .bind(&budget.account_id)
+        .bind(&budget.account_id);
+    for category in &categories {
+        query = query.bind(category);
+    }
+    let rows = query.fetch_all(pool).await?;
+
+    let mut converter = currency::Converter::new(pool, &budget.user_id, &budget.currency);
+    let mut by_category: BTreeMap<String, Money> = BTreeMap::new();
+    let mut by_currency: BTreeMap<String, CurrencySpending> = BTreeMap::new();
+    for row in rows {
+        let category = row.get::<String, _>("category");
+        let currency = row.get::<String, _>("currency");
+        let spent = row.get::<Money, _>("spent");
+        let converted = match NaiveDate::parse_from_str(&row.get::<String, _>("day"), "%Y-%m-%d") {
+            Ok(day) => converter.convert(spent, &currency, day).await?,
+            Err(_) => None,
+        };
+
+        let detail = by_currency.entry(currency.clone()).or_insert_with(|| CurrencySpending {
+            currency,
+            spent: Money::ZERO,
+            converted: Money::ZERO,
+            unconverted: Money::ZERO,
+        });
+        detail.spent += spent;
+        match converted {
+            Some(amount) => {
+                detail.converted += amount;
+                *by_category.entry(category).or_default() += amount;
+            }
+            None => detail.unconverted += spent,
+        }
+    }
+
+    let mut by_category: Vec<CategorySpending> = by_category
+        .into_iter()
+        .map(|(category, spent)| CategorySpending { category, spent })
+        .collect();
+    by_category.sort_by_key(|c| std::cmp::Reverse(c.spent));
+
+    Ok((by_category, by_currency.into_values().collect()))
+}
+
+/// Sums the expenses that count against a budget between `start` (inclusive) and `end` (exclusive).
+pub async fn budget_spent(
+    pool: &DbPool,
+    budget: &Budget,
+    start: DateTime<Utc>,
+    end: DateTime<Utc>,
+) -> Result<Money> {
+    let spending = spending_by_category(pool, budget, start, end).await?;
+    Ok(spending.iter().map(|s| s.spent).sum())
+}
+
+/// Computes progress for the budget's current period, using the rollover-adjusted limit.
+pub async fn budget_progress(pool: &DbPool, budget: &Budget) -> Result<BudgetProgress> {
+    budget_rollover::sync_budget_periods(pool, budget).await?;
+
+    let (period_start, period_end) = budget.period_bounds(Utc::now());
+    let limit = sqlx::query("SELECT adjusted_amount FROM budget_periods WHERE budget_id = ? AND period_start = ?")
+        .bind(&budget.id)
+        .bind(format_db_datetime(period_start))
+        .fetch_optional(pool)
+        .await?
+        .map(|row| row.get::<Money, _>("adjusted_amount"))
+        .unwrap_or(budget.amount);
+
+    let (by_category, by_currency) = budget_spending(pool, budget, period_start, period_end).await?;
+    let spent: Money = by_category.iter().map(|s| s.spent).sum();
+    let percent_used = if limit.is_positive() { spent.ratio(limit) * 100.0 } else { 0.0 };
+
+    Ok(BudgetProgress {
+        budget_id: budget.id.clone(),
+        categories: budget.target_categories(&linked_categories(pool, &budget.id).await?),
+        account_id: budget.account_id.clone(),
+        currency: budget.currency.clone(),
+        period_start,
+        period_end,
+        limit,
+        spent,
+        remaining: limit - spent,
+        percent_used: (percent_used * 100.0).round() / 100.0,
+        is_exceeded: spent > limit,
+        by_category,
+        multi_currency: budget.multi_currency,
+        by_currency,
+    })
+}
+
+/// Queues a `budget_exceeded` notification for each of the user's budgets that is over its limit
+/// in the current period. Each budget alerts at most once per period.
+pub async fn notify_exceeded_budgets(pool: &DbPool, user_id: &str) -> Result<()> {
+    let budgets = sqlx::query_as::<_, Budget>("SELECT * FROM budgets WHERE user_id = ?")
+        .bind(user_id)
+        .fetch_all(pool)
+        .await?;
+
+    for budget in &budgets {
+        let progress = budget_progress(pool, budget).await?;
+        if !progress.is_exceeded {
+            continue;
+        }
+        let queued = notifications::enqueue(pool, NewNotification {
+            user_id,
+            kind: "budget_exceeded",
+            title: format!("Budget exceeded: {}", budget.category),
+            body: format!(
+                "You've spent {:.2} of your {:.2} {} budget this period.",
+                progress.spent, progress.limit, budget.currency
+            ),
+            entity: Some(("budget", &budget.id)),
+            dedupe_key: Some(format!("budget_exceeded:{}:{}", budget.id, format_db_datetime(progress.period_start))),
+        })
+        .await;
+
+        match queued {
+            // The dedupe key also keeps the event to once per period
+            Ok(true) => events::emit(pool, user_id, "budget.exceeded", "budget", &budget.id, &progress).await,
+            Ok(false) => {}
+            Err(e) => tracing::error!("Failed to queue budget_exceeded notification for user {}: {}", user_id, e),
+        }
+    }
+
+    Ok(())
+}
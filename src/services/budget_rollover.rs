@@ -0,0 +1,140 @@
+use std::time::Duration;
+
+use anyhow::Result;
+use chrono::{DateTime, Utc};
+use uuid::Uuid;
+
+use crate::models::{Budget, BudgetPeriod};
+use crate::services::budget_progress::budget_spent;
+use crate::services::database::DbPool;
+use crate::services::scheduler::Scheduler;
+use crate::utils::datetime::format_db_datetime;
+use crate::utils::money::Money;
+
+/// How often the background job closes elapsed budget periods.
+const PERIOD_CLOSE_INTERVAL: Duration = Duration::from_secs(60 * 60);
+
+async fn latest_period(pool: &DbPool, budget_id: &str) -> Result<Option<BudgetPeriod>> {
+    let period = sqlx::query_as::<_, BudgetPeriod>(
+        "SELECT * FROM budget_periods WHERE budget_id = ? ORDER BY period_start DESC LIMIT 1",
+    )
+    .bind(budget_id)
+    .fetch_optional(pool)
+    .await?;
+
+    Ok(period)
+}
+
+async fn insert_period(
+    pool: &DbPool,
+    budget: &Budget,
+    start: DateTime<Utc>,
+    end: DateTime<Utc>,
+    carried_over: Money,
+) -> Result<()> {
+    let now = format_db_datetime(Utc::now());
+    let spent = budget_spent(pool, budget, start, end).await?;
+
+    sqlx::query(
+        "INSERT OR IGNORE INTO budget_periods (id, budget_id, user_id, period_start, period_end, base_amount, carried_over, adjusted_amount, spent, is_closed, closed_at, created_at, updated_at) VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?, FALSE, NULL, ?, ?)"
+    )
+    .bind(Uuid::new_v4().to_string())
+    .bind(&budget.id)
+    .bind(&budget.user_id)
+    .bind(format_db_datetime(start))
+    .bind(format_db_datetime(end))
+    .bind(budget.amount)
+    .bind(carried_over)
+    .bind(budget.amount + carried_over)
+    .bind(spent)
+    .bind(&now)
+    .bind(&now)
+    .execute(pool)
+    .await?;
+
+    Ok(())
+}
+
+/// Brings a budget's period records up to date: closes every elapsed period, opens the
+/// following one with the rolled-over remainder, and refreshes the open period's figures.
+pub async fn sync_budget_periods(pool: &DbPool, budget: &Budget) -> Result<()> {
+    let now = Utc::now();
+
+    let mut current = match latest_period(pool, &budget.id).await? {
+        Some(period) => period,
+        None => {
+            let (start, end) = budget.period_bounds(budget.created_at);
+            insert_period(pool, budget, start, end, Money::ZERO).await?;
+            match latest_period(pool, &budget.id).await? {
+                Some(period) => period,
+                None => return Ok(()),
+            }
+        }
+    };
+
+    while current.period_end <= now {
+        let spent = budget_spent(pool, budget, current.period_start, current.period_end).await?;
+        let remaining = current.adjusted_amount - spent;
+        let closed_at = format_db_datetime(now);
+
+        sqlx::query(
+            "UPDATE budget_periods SET spent = ?, is_closed = TRUE, closed_at = ?, updated_at = ? WHERE id = ?"
+        )
+        .bind(spent)
+        .bind(&closed_at)
+        .bind(&closed_at)
+        .bind(&current.id)
+        .execute(pool)
+        .await?;
+
+        let carried_over = if budget.rollover { remaining } else { Money::ZERO };
+        let (start, end) = budget.period_bounds(current.period_end);
+        insert_period(pool, budget, start, end, carried_over).await?;
+
+        current = match latest_period(pool, &budget.id).await? {
+            Some(period) if period.period_start > current.period_start => period,
+            _ => return Ok(()),
+        };
+    }
+
+    // The open period tracks the budget's current limit and spending
+    let spent = budget_spent(pool, budget, current.period_start, current.period_end).await?;
+    let carried_over = if budget.rollover { current.carried_over } else { Money::ZERO };
+    sqlx::query(
+        "UPDATE budget_periods SET base_amount = ?, carried_over = ?, adjusted_amount = ?, spent = ?, updated_at = ? WHERE id = ?"
+    )
+    .bind(budget.amount)
+    .bind(carried_over)
+    .bind(budget.amount + carried_over)
+    .bind(spent)
+    .bind(format_db_datetime(now))
+    .bind(&current.id)
+    .execute(pool)
+    .await?;
+
+    Ok(())
+}
+
+/// Closes elapsed periods for every budget.
+pub async fn close_due_periods(pool: &DbPool) -> Result<()> {
+    let budgets = sqlx::query_as::<_, Budget>("SELECT * FROM budgets")
+        .fetch_all(pool)
+        .await?;
+
+    for budget in &budgets {
+        if let Err(e) = sync_budget_periods(pool, budget).await {
+            tracing::error!("Failed to close periods for budget {}: {}", budget.id, e);
+        }
+    }
+
+    tracing::info!("Budget periods synced for {} budgets", budgets.len());
+    Ok(())
+}
+
+/// Schedules the background job that periodically closes elapsed budget periods.
+pub fn schedule_period_close_job(scheduler: &mut Scheduler) {
+    scheduler.every("budget_period_close", PERIOD_CLOSE_INTERVAL, |pool| async move {
+        close_due_periods(&pool).await?;
+        Ok(0)
+    });
+}
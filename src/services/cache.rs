@@ -0,0 +1,264 @@
+use std::collections::HashMap;
+use std::future::Future;
+use std::sync::{Mutex, OnceLock};
+use std::time::{Duration, Instant};
+
+use anyhow::Result;
+use serde::{de::DeserializeOwned, Serialize};
+
+use crate::config::{self, CacheConfig};
+
+/// Prefix of every key the cache writes to Redis, so it can share a server with other apps.
+#[cfg(feature = "redis")]
+const REDIS_PREFIX: &str = "pm:cache";
+
+/// Hot reads kept between requests: dashboards, preferences and exchange rates. Entries are
+/// kept per user and expire after `cache.ttl_secs`. A user's entries are dropped when they or
+/// someone sharing a household with them writes anything (`middleware::cache`), and all of them
+/// when a background job changes data (`services::scheduler`).
+///
+/// Dropping works by generation: keys carry the user's and the cache's generation number, and
+/// invalidating bumps it. A read that started before a write can then only store its result
+/// under the old generation, where nobody looks any more.
+pub struct Cache {
+    ttl: Duration,
+    backend: Backend,
+}
+
+enum Backend {
+    Disabled,
+    Memory(Mutex<MemoryStore>),
+    #[cfg(feature = "redis")]
+    Redis(redis::aio::ConnectionManager),
+}
+
+struct MemoryStore {
+    max_entries: usize,
+    generation: u64,
+    user_generations: HashMap<String, u64>,
+    entries: HashMap<String, (Instant, String)>,
+}
+
+impl Cache {
+    /// The cache `config` describes: Redis when `redis_url` is set, otherwise in memory.
+    pub async fn connect(config: &CacheConfig) -> Result<Self> {
+        #[cfg(feature = "redis")]
+        if let (true, Some(url)) = (config.enabled, &config.redis_url) {
+            let client = redis::Client::open(url.as_str())?;
+            let connection = redis::aio::ConnectionManager::new(client).await?;
+            return Ok(Self {
+                ttl: Duration::from_secs(config.ttl_secs),
+                backend: Backend::Redis(connection),
+            });
+        }
+        Ok(Self::in_memory(config))
+    }
+
+    pub fn in_memory(config: &CacheConfig) -> Self {
+        let backend = if config.enabled {
+            Backend::Memory(Mutex::new(MemoryStore {
+                max_entries: config.max_entries,
+                generation: 0,
+                user_generations: HashMap::new(),
+                entries: HashMap::new(),
+            }))
+        } else {
+            Backend::Disabled
+        };
+        Self {
+            ttl: Duration::from_secs(config.ttl_secs),
+            backend,
+        }
+    }
+
+    pub fn backend_name(&self) -> &'static str {
+        match self.backend {
+            Backend::Disabled => "disabled",
+            Backend::Memory(_) => "memory",
+            #[cfg(feature = "redis")]
+            Backend::Redis(_) => "redis",
+        }
+    }
+
+    /// `user_id`'s cached `name`, or what `load` returns, which is then cached. Cache failures
+    /// are logged and fall back to `load`; the cache never fails a read.
+    pub async fn cached<T, F>(&self, user_id: &str, name: &str, load: F) -> Result<T>
+    where
+        T: Serialize + DeserializeOwned,
+        F: Future<Output = Result<T>>,
+    {
+        let key = match self.key(user_id, name).await {
+            Ok(Some(key)) => key,
+            Ok(None) => return load.await,
+            Err(e) => {
+                tracing::warn!("Cache unavailable, reading {} directly: {}", name, e);
+                return load.await;
+            }
+        };
+
+        match self.read(&key).await {
+            Ok(Some(json)) => match serde_json::from_str(&json) {
+                Ok(value) => return Ok(value),
+                Err(e) => tracing::warn!("Ignoring unreadable cache entry {}: {}", key, e),
+            },
+            Ok(None) => {}
+            Err(e) => tracing::warn!("Failed to read cache entry {}: {}", key, e),
+        }
+
+        let value = load.await?;
+        match serde_json::to_string(&value) {
+            Ok(json) => {
+                if let Err(e) = self.write(&key, json).await {
+                    tracing::warn!("Failed to write cache entry {}: {}", key, e);
+                }
+            }
+            Err(e) => tracing::warn!("Failed to serialize cache entry {}: {}", key, e),
+        }
+        Ok(value)
+    }
+
+    /// Drops everything cached for `user_id`.
+    pub async fn invalidate_user(&self, user_id: &str) {
+        let result: Result<()> = match &self.backend {
+            Backend::Disabled => Ok(()),
+            Backend::Memory(store) => {
+                let mut store = lock(store);
+                *store.user_generations.entry(user_id.to_string()).or_default() += 1;
+                store.entries.retain(|key, _| key.split(':').nth(1) != Some(user_id));
+                Ok(())
+            }
+            #[cfg(feature = "redis")]
+            Backend::Redis(connection) => redis::cmd("INCR")
+                .arg(format!("{}:generation:{}", REDIS_PREFIX, user_id))
+                .query_async::<_, u64>(&mut connection.clone())
+                .await
+                .map(|_| ())
+                .map_err(Into::into),
+        };
+        if let Err(e) = result {
+            tracing::error!("Failed to invalidate the cache of user {}: {}", user_id, e);
+        }
+    }
+
+    /// Drops everything cached, for changes that can touch any user such as new exchange rates.
+    pub async fn invalidate_all(&self) {
+        let result: Result<()> = match &self.backend {
+            Backend::Disabled => Ok(()),
+            Backend::Memory(store) => {
+                let mut store = lock(store);
+                store.generation += 1;
+                store.user_generations.clear();
+                store.entries.clear();
+                Ok(())
+            }
+            #[cfg(feature = "redis")]
+            Backend::Redis(connection) => redis::cmd("INCR")
+                .arg(format!("{}:generation", REDIS_PREFIX))
+                .query_async::<_, u64>(&mut connection.clone())
+                .await
+                .map(|_| ())
+                .map_err(Into::into),
+        };
+        if let Err(e) = result {
+            tracing::error!("Failed to invalidate the cache: {}", e);
+        }
+    }
+
+    /// The key `name` is cached under for `user_id` at the current generations; `None` when
+    /// caching is off.
+    async fn key(&self, user_id: &str, name: &str) -> Result<Option<String>> {
+        match &self.backend {
+            Backend::Disabled => Ok(None),
+            Backend::Memory(store) => {
+                let store = lock(store);
+                let user_generation = store.user_generations.get(user_id).copied().unwrap_or(0);
+                Ok(Some(format!("{}:{}:{}:{}", store.generation, user_id, user_generation, name)))
+            }
+            #[cfg(feature = "redis")]
+            Backend::Redis(connection) => {
+                let (generation, user_generation): (Option<u64>, Option<u64>) = redis::cmd("MGET")
+                    .arg(format!("{}:generation", REDIS_PREFIX))
+                    .arg(format!("{}:generation:{}", REDIS_PREFIX, user_id))
+                    .query_async(&mut connection.clone())
+                    .await?;
+                Ok(Some(format!(
+                    "{}:{}:{}:{}:{}",
+                    REDIS_PREFIX,
+                    generation.unwrap_or(0),
+                    user_id,
+                    user_generation.unwrap_or(0),
+                    name
+                )))
+            }
+        }
+    }
+
+    async fn read(&self, key: &str) -> Result<Option<String>> {
+        match &self.backend {
+            Backend::Disabled => Ok(None),
+            Backend::Memory(store) => Ok(lock(store)
+                .entries
+                .get(key)
+                .filter(|(expires, _)| *expires > Instant::now())
+                .map(|(_, json)| json.clone())),
+            #[cfg(feature = "redis")]
+            Backend::Redis(connection) => Ok(redis::cmd("GET").arg(key).query_async(&mut connection.clone()).await?),
+        }
+    }
+
+    async fn write(&self, key: &str, json: String) -> Result<()> {
+        match &self.backend {
+            Backend::Disabled => Ok(()),
+            Backend::Memory(store) => {
+                lock(store).insert(key.to_string(), Instant::now() + self.ttl, json);
+                Ok(())
+            }
+            #[cfg(feature = "redis")]
+            Backend::Redis(connection) => Ok(redis::cmd("SET")
+                .arg(key)
+                .arg(json)
+                .arg("EX")
+                .arg(self.ttl.as_secs())
+                .query_async(&mut connection.clone())
+                .await?),
+        }
+    }
+}
+
+impl MemoryStore {
+    /// Makes room by dropping expired entries, then the ones closest to expiring.
+    fn insert(&mut self, key: String, expires: Instant, json: String) {
+        if self.entries.len() >= self.max_entries && !self.entries.contains_key(&key) {
+            let now = Instant::now();
+            self.entries.retain(|_, (expires, _)| *expires > now);
+            while self.entries.len() >= self.max_entries {
+                let Some(oldest) = self.entries.iter().min_by_key(|(_, (expires, _))| *expires).map(|(key, _)| key.clone()) else {
+                    break;
+                };
+                self.entries.remove(&oldest);
+            }
+        }
+        self.entries.insert(key, (expires, json));
+    }
+}
+
+fn lock(store: &Mutex<MemoryStore>) -> std::sync::MutexGuard<'_, MemoryStore> {
+    store.lock().unwrap_or_else(|e| e.into_inner())
+}
+
+static CACHE: OnceLock<Cache> = OnceLock::new();
+
+/// Connects the cache [`get`] returns. Call once at startup, before anything reads it.
+pub async fn init(config: &CacheConfig) -> Result<&'static Cache> {
+    let cache = Cache::connect(config).await?;
+    if CACHE.set(cache).is_err() {
+        tracing::warn!("Cache was already set up; keeping the first one");
+    }
+    Ok(get())
+}
+
+/// The server's cache, or an in-memory one from the configuration when [`init`] hasn't been
+/// called, as in tests.
+pub fn get() -> &'static Cache {
+    CACHE.get_or_init(|| Cache::in_memory(&config::get().cache))
+}
@@ -0,0 +1,206 @@
+use std::collections::BTreeMap;
+
+use anyhow::Result;
+use chrono::{DateTime, Datelike, Months, NaiveDate, Utc};
+
+use crate::models::{CalendarDay, CalendarItem, CalendarTotals, Liability, Loan, RecurringTransaction, SavingsGoal, TermDeposit};
+use crate::services::database::DbPool;
+use crate::services::term_deposits;
+use crate::utils::datetime::format_db_datetime;
+use crate::utils::money::Money;
+
+/// Upper bound on occurrences listed per schedule (a daily rule gives 31 in a month).
+const MAX_OCCURRENCES_PER_SCHEDULE: usize = 31;
+
+/// Parses `YYYY-MM` into the first day of that month.
+pub fn parse_month(value: &str) -> Option<NaiveDate> {
+    NaiveDate::parse_from_str(&format!("{}-01", value.trim()), "%Y-%m-%d").ok()
+}
+
+fn day_key(date: DateTime<Utc>) -> String {
+    date.format("%Y-%m-%d").to_string()
+}
+
+/// Everything falling due in `[start, end)`: unpaid liabilities, loans expected back, upcoming
+/// recurring occurrences, savings-goal deadlines and maturing term deposits, in no particular
+/// order.
+pub async fn items_between(
+    pool: &DbPool,
+    user_id: &str,
+    start: DateTime<Utc>,
+    end: DateTime<Utc>,
+) -> Result<Vec<(DateTime<Utc>, CalendarItem)>> {
+    let (start_str, end_str) = (format_db_datetime(start), format_db_datetime(end));
+    let mut items = Vec::new();
+    let mut add = |at: DateTime<Utc>, item: CalendarItem| items.push((at, item));
+
+    let liabilities = sqlx::query_as::<_, Liability>(
+        "SELECT * FROM liabilities WHERE user_id = ? AND is_paid = FALSE AND due_date >= ? AND due_date < ? ORDER BY due_date"
+    )
+    .bind(user_id)
+    .bind(&start_str)
+    .bind(&end_str)
+    .fetch_all(pool)
+    .await?;
+    for liability in liabilities {
+        add(liability.due_date, CalendarItem {
+            kind: "liability_due",
+            id: liability.id,
+            title: format!("Pay {}", liability.person_name),
+            amount: liability.amount,
+            currency: liability.currency,
+            direction: Some("out"),
+        });
+    }
+
+    let loans = sqlx::query_as::<_, Loan>(
+        "SELECT * FROM loans WHERE user_id = ? AND is_returned = FALSE AND return_date >= ? AND return_date < ? ORDER BY return_date"
+    )
+    .bind(user_id)
+    .bind(&start_str)
+    .bind(&end_str)
+    .fetch_all(pool)
+    .await?;
+    for loan in loans {
+        let Some(return_date) = loan.return_date else {
+            continue;
+        };
+        add(return_date, CalendarItem {
+            kind: "loan_expected",
+            id: loan.id,
+            title: format!("Repayment from {}", loan.person_name),
+            amount: loan.amount,
+            currency: loan.currency,
+            direction: Some("in"),
+        });
+    }
+
+    let schedules = sqlx::query_as::<_, RecurringTransaction>(
+        "SELECT * FROM recurring_transactions WHERE user_id = ? AND is_active = TRUE AND next_due_date < ?"
+    )
+    .bind(user_id)
+    .bind(&end_str)
+    .fetch_all(pool)
+    .await?;
+    for rt in schedules {
+        let rule = match rt.rule() {
+            Ok(rule) => rule,
+            Err(e) => {
+                tracing::warn!("Recurring transaction {} has an invalid schedule ({}); not on calendar", rt.id, e);
+                continue;
+            }
+        };
+        let title = rt
+            .description
+            .clone()
+            .or_else(|| rt.category.clone())
+            .unwrap_or_else(|| format!("Recurring {}", rt.transaction_type));
+        let direction = if rt.transaction_type == "income" { "in" } else { "out" };
+
+        // Occurrences before next_due_date have already been posted or skipped.
+        let mut next = rule.next_on_or_after(rt.start_date, rt.next_due_date.max(start));
+        for _ in 0..MAX_OCCURRENCES_PER_SCHEDULE {
+            let Some(at) = next else {
+                break;
+            };
+            if at >= end || rt.end_date.is_some_and(|end_date| at > end_date) {
+                break;
+            }
+            add(at, CalendarItem {
+                kind: "recurring",
+                id: rt.id.clone(),
+                title: title.clone(),
+                amount: rt.amount,
+                currency: rt.currency.clone(),
+                direction: Some(direction),
+            });
+            next = rule.next_after(rt.start_date, at);
+        }
+    }
+
+    let goals = sqlx::query_as::<_, SavingsGoal>(
+        "SELECT * FROM savings_goals WHERE user_id = ? AND is_completed = FALSE AND target_date >= ? AND target_date < ? ORDER BY target_date"
+    )
+    .bind(user_id)
+    .bind(&start_str)
+    .bind(&end_str)
+    .fetch_all(pool)
+    .await?;
+    for goal in goals {
+        add(goal.target_date, CalendarItem {
+            kind: "goal_deadline",
+            id: goal.id,
+            title: format!("{} deadline", goal.name),
+            amount: (goal.target_amount - goal.current_amount).max(Money::ZERO),
+            currency: goal.currency,
+            direction: None,
+        });
+    }
+
+    let deposits = sqlx::query_as::<_, TermDeposit>(
+        "SELECT * FROM term_deposits WHERE user_id = ? AND is_closed = FALSE AND maturity_date >= ? AND maturity_date < ? ORDER BY maturity_date"
+    )
+    .bind(user_id)
+    .bind(&start_str)
+    .bind(&end_str)
+    .fetch_all(pool)
+    .await?;
+    for deposit in deposits {
+        let (_, maturity_value) = term_deposits::value_on(&deposit, deposit.maturity_date);
+        add(deposit.maturity_date, CalendarItem {
+            kind: "deposit_maturity",
+            id: deposit.id,
+            title: format!("{} matures", deposit.name),
+            amount: maturity_value,
+            currency: deposit.currency,
+            direction: Some("in"),
+        });
+    }
+
+    Ok(items)
+}
+
+/// Everything falling due in the month starting at `first_day`. Every day of the month is
+/// present, with an empty item list if nothing is due.
+pub async fn month_calendar(pool: &DbPool, user_id: &str, first_day: NaiveDate) -> Result<(Vec<CalendarDay>, Vec<CalendarTotals>)> {
+    let start = first_day.and_hms_opt(0, 0, 0).unwrap_or_default().and_utc();
+    let end = start.checked_add_months(Months::new(1)).unwrap_or(start);
+
+    let mut days: BTreeMap<String, Vec<CalendarItem>> = BTreeMap::new();
+    let mut date = first_day;
+    while date < end.date_naive() {
+        days.insert(date.format("%Y-%m-%d").to_string(), Vec::new());
+        date = date.succ_opt().unwrap_or(end.date_naive());
+    }
+    for (at, item) in items_between(pool, user_id, start, end).await? {
+        if let Some(day) = days.get_mut(&day_key(at)) {
+            day.push(item);
+        }
+    }
+
+    let mut totals: BTreeMap<String, CalendarTotals> = BTreeMap::new();
+    for item in days.values().flatten() {
+        let entry = totals.entry(item.currency.clone()).or_insert_with(|| CalendarTotals {
+            currency: item.currency.clone(),
+            inflow: Money::ZERO,
+            outflow: Money::ZERO,
+        });
+        match item.direction {
+            Some("in") => entry.inflow += item.amount,
+            Some("out") => entry.outflow += item.amount,
+            _ => {}
+        }
+    }
+
+    let days = days
+        .into_iter()
+        .map(|(date, items)| CalendarDay { date, items })
+        .collect();
+    Ok((days, totals.into_values().collect()))
+}
+
+/// First day of the current month.
+pub fn current_month() -> NaiveDate {
+    let today = Utc::now().date_naive();
+    today.with_day(1).unwrap_or(today)
+}
@@ -0,0 +1,237 @@
+use std::time::Duration;
+
+use anyhow::Result;
+use chrono::{DateTime, Datelike, Duration as ChronoDuration, Months, NaiveDate, Utc};
+use sqlx::Row;
+use uuid::Uuid;
+
+use crate::models::{CardBillingCycle, CardStatement, CardStatementStatus};
+use crate::services::currency;
+use crate::services::database::DbPool;
+use crate::services::scheduler::Scheduler;
+use crate::utils::datetime::{days_in_month, format_db_datetime};
+use crate::utils::money::Money;
+
+/// How often the background job closes elapsed billing cycles.
+const STATEMENT_INTERVAL: Duration = Duration::from_secs(60 * 60);
+
+/// Cards with statement and due days set.
+const BILLING_CYCLE_SELECT: &str = "SELECT id AS account_id, user_id, currency, statement_day, payment_due_day, created_at FROM accounts WHERE account_type = 'credit_card' AND statement_day IS NOT NULL AND payment_due_day IS NOT NULL";
+
+/// Share of the closing balance asked for as the minimum payment.
+const MINIMUM_DUE_PERCENT: f64 = 5.0;
+
+/// `day` of the given month, or the month's last day if it is shorter.
+fn day_of_month(year: i32, month: u32, day: u32) -> NaiveDate {
+    NaiveDate::from_ymd_opt(year, month, day.min(days_in_month(year, month))).unwrap_or_default()
+}
+
+fn start_of_day(date: NaiveDate) -> DateTime<Utc> {
+    date.and_hms_opt(0, 0, 0).unwrap_or_default().and_utc()
+}
+
+fn next_month(date: NaiveDate) -> NaiveDate {
+    date.with_day(1).unwrap_or(date) + Months::new(1)
+}
+
+/// End of the billing cycle running at `at`: the start of the day after the next statement day.
+pub fn cycle_end_after(at: DateTime<Utc>, statement_day: u32) -> DateTime<Utc> {
+    let date = at.date_naive();
+    let end = start_of_day(day_of_month(date.year(), date.month(), statement_day)) + ChronoDuration::days(1);
+    if end > at {
+        return end;
+    }
+    let next = next_month(date);
+    start_of_day(day_of_month(next.year(), next.month(), statement_day)) + ChronoDuration::days(1)
+}
+
+/// Payment due date for the cycle ending at `period_end`: the first due day after the
+/// statement day.
+pub fn due_date_for(period_end: DateTime<Utc>, payment_due_day: u32) -> DateTime<Utc> {
+    let statement_date = period_end.date_naive().pred_opt().unwrap_or_default();
+    let due = day_of_month(statement_date.year(), statement_date.month(), payment_due_day);
+    if due > statement_date {
+        return start_of_day(due);
+    }
+    let next = next_month(statement_date);
+    start_of_day(day_of_month(next.year(), next.month(), payment_due_day))
+}
+
+fn minimum_due(closing_balance: Money, code: &str) -> Money {
+    let minimum = currency::round(closing_balance * (MINIMUM_DUE_PERCENT / 100.0), code);
+    if minimum.is_zero() || minimum > closing_balance {
+        closing_balance
+    } else {
+        minimum
+    }
+}
+
+async fn latest_statement(pool: &DbPool, account_id: &str) -> Result<Option<CardStatement>> {
+    Ok(sqlx::query_as::<_, CardStatement>(
+        "SELECT * FROM card_statements WHERE account_id = ? ORDER BY period_end DESC LIMIT 1",
+    )
+    .bind(account_id)
+    .fetch_optional(pool)
+    .await?)
+}
+
+/// Expense and income totals on the card between `start` and `end`.
+async fn cycle_totals(pool: &DbPool, account_id: &str, start: DateTime<Utc>, end: DateTime<Utc>) -> Result<(Money, Money)> {
+    let rows = sqlx::query(
+        "SELECT transaction_type, SUM(amount) AS total FROM transactions WHERE account_id = ? AND date >= ? AND date < ? AND transaction_type IN ('expense', 'income') GROUP BY transaction_type"
+    )
+    .bind(account_id)
+    .bind(format_db_datetime(start))
+    .bind(format_db_datetime(end))
+    .fetch_all(pool)
+    .await?;
+
+    let (mut spend, mut credits) = (Money::ZERO, Money::ZERO);
+    for row in rows {
+        let total: Money = row.get("total");
+        match row.get::<String, _>("transaction_type").as_str() {
+            "expense" => spend = total.abs(),
+            _ => credits = total.abs(),
+        }
+    }
+    Ok((spend, credits))
+}
+
+/// Writes a statement for every billing cycle of the card that has ended since the last one.
+/// The first cycle runs from when the card was added; each later one opens with the previous
+/// closing balance. Returns the number of statements written.
+pub async fn sync_statements(pool: &DbPool, card: &CardBillingCycle) -> Result<usize> {
+    let (mut start, mut opening_balance) = match latest_statement(pool, &card.account_id).await? {
+        Some(statement) => (statement.period_end, statement.closing_balance),
+        None => (card.created_at, Money::ZERO),
+    };
+    let now = Utc::now();
+    let mut written = 0;
+    loop {
+        let end = cycle_end_after(start, card.statement_day);
+        if end > now {
+            break;
+        }
+
+        let (spend, credits) = cycle_totals(pool, &card.account_id, start, end).await?;
+        let closing_balance = (opening_balance + spend - credits).max(Money::ZERO);
+        sqlx::query(
+            "INSERT OR IGNORE INTO card_statements (id, account_id, user_id, period_start, period_end, due_date, currency, opening_balance, spend, credits, closing_balance, minimum_due, created_at) VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?)"
+        )
+        .bind(Uuid::new_v4().to_string())
+        .bind(&card.account_id)
+        .bind(&card.user_id)
+        .bind(format_db_datetime(start))
+        .bind(format_db_datetime(end))
+        .bind(format_db_datetime(due_date_for(end, card.payment_due_day)))
+        .bind(&card.currency)
+        .bind(opening_balance)
+        .bind(spend)
+        .bind(credits)
+        .bind(closing_balance)
+        .bind(minimum_due(closing_balance, &card.currency))
+        .bind(format_db_datetime(now))
+        .execute(pool)
+        .await?;
+
+        start = end;
+        opening_balance = closing_balance;
+        written += 1;
+    }
+    Ok(written)
+}
+
+/// The statement with the income booked on the card from its close through the due date.
+pub async fn status(pool: &DbPool, statement: CardStatement) -> Result<CardStatementStatus> {
+    let amount_paid: Money = sqlx::query(
+        "SELECT COALESCE(SUM(amount), 0) AS paid FROM transactions WHERE account_id = ? AND transaction_type = 'income' AND date >= ? AND date < ?"
+    )
+    .bind(&statement.account_id)
+    .bind(format_db_datetime(statement.period_end))
+    .bind(format_db_datetime(statement.due_date + ChronoDuration::days(1)))
+    .fetch_one(pool)
+    .await?
+    .get("paid");
+
+    Ok(CardStatementStatus {
+        is_paid: amount_paid >= statement.closing_balance,
+        amount_paid,
+        statement,
+    })
+}
+
+/// [`status`] for statements of one card, reading its payments in one query.
+pub async fn statuses(pool: &DbPool, account_id: &str, statements: Vec<CardStatement>) -> Result<Vec<CardStatementStatus>> {
+    let windows: Vec<(String, String)> = statements
+        .iter()
+        .map(|s| (format_db_datetime(s.period_end), format_db_datetime(s.due_date + ChronoDuration::days(1))))
+        .collect();
+    let (Some(from), Some(to)) = (windows.iter().map(|w| &w.0).min(), windows.iter().map(|w| &w.1).max()) else {
+        return Ok(Vec::new());
+    };
+
+    let payments = sqlx::query(
+        "SELECT date, amount FROM transactions WHERE account_id = ? AND transaction_type = 'income' AND date >= ? AND date < ?"
+    )
+    .bind(account_id)
+    .bind(from)
+    .bind(to)
+    .fetch_all(pool)
+    .await?
+    .into_iter()
+    .map(|row| (row.get::<String, _>("date"), row.get::<Money, _>("amount")))
+    .collect::<Vec<_>>();
+
+    Ok(statements
+        .into_iter()
+        .zip(&windows)
+        .map(|(statement, (from, to))| {
+            let amount_paid = payments
+                .iter()
+                .filter(|(date, _)| date >= from && date < to)
+                .map(|(_, amount)| *amount)
+                .sum();
+            CardStatementStatus {
+                is_paid: amount_paid >= statement.closing_balance,
+                amount_paid,
+                statement,
+            }
+        })
+        .collect())
+}
+
+/// The user's card's billing settings; `None` if the account isn't a credit card with both
+/// days set.
+pub async fn billing_cycle(pool: &DbPool, account_id: &str, user_id: &str) -> Result<Option<CardBillingCycle>> {
+    Ok(sqlx::query_as::<_, CardBillingCycle>(&format!("{} AND id = ? AND user_id = ?", BILLING_CYCLE_SELECT))
+        .bind(account_id)
+        .bind(user_id)
+        .fetch_optional(pool)
+        .await?)
+}
+
+/// Closes elapsed billing cycles for every credit card with statement and due days set.
+pub async fn close_due_cycles(pool: &DbPool) -> Result<usize> {
+    let cards = sqlx::query_as::<_, CardBillingCycle>(BILLING_CYCLE_SELECT)
+        .fetch_all(pool)
+        .await?;
+
+    let mut written = 0;
+    for card in &cards {
+        match sync_statements(pool, card).await {
+            Ok(count) => written += count,
+            Err(e) => tracing::error!("Failed to write statements for account {}: {}", card.account_id, e),
+        }
+    }
+    Ok(written)
+}
+
+pub fn schedule_statement_job(scheduler: &mut Scheduler) {
+    scheduler.every("card_statements", STATEMENT_INTERVAL, |pool| async move {
+        let written = close_due_cycles(&pool).await?;
+        if written > 0 {
+            tracing::info!("Wrote {} credit card statements", written);
+        }
+        Ok(written as u64)
+    });
+}
@@ -0,0 +1,195 @@
+use std::collections::{HashMap, HashSet};
+use std::time::Duration;
+
+use anyhow::Result;
+use chrono::Utc;
+use sqlx::Row;
+
+use crate::models::{CategorySuggestion, TransactionType};
+use crate::services::database::DbPool;
+use crate::services::scheduler::Scheduler;
+use crate::utils::datetime::format_db_datetime;
+
+/// How often the background job retrains every user's keyword model.
+const TRAINING_INTERVAL: Duration = Duration::from_secs(60 * 60);
+
+const STOP_WORDS: &[&str] = &[
+    "the", "and", "for", "from", "with", "to", "of", "at", "in", "on", "by", "a", "an", "my", "via", "payment",
+];
+
+/// Splits a free-text description into normalized keywords.
+pub fn tokenize(description: &str) -> Vec<String> {
+    let mut seen = HashSet::new();
+    description
+        .split(|c: char| !c.is_alphanumeric())
+        .map(|token| token.to_lowercase())
+        .filter(|token| token.chars().count() >= 2)
+        .filter(|token| !token.chars().all(|c| c.is_ascii_digit()))
+        .filter(|token| !STOP_WORDS.contains(&token.as_str()))
+        .filter(|token| seen.insert(token.clone()))
+        .collect()
+}
+
+/// Rebuilds the keyword frequency table for one user from their categorized transactions.
+pub async fn train_user_model(pool: &DbPool, user_id: &str) -> Result<usize> {
+    let rows = sqlx::query(
+        "SELECT description, category, transaction_type FROM transactions WHERE user_id = ? AND category IS NOT NULL AND category != '' AND description IS NOT NULL AND description != ''"
+    )
+    .bind(user_id)
+    .fetch_all(pool)
+    .await?;
+
+    let mut counts: HashMap<(String, String, String), i64> = HashMap::new();
+    for row in rows {
+        let description = row.get::<String, _>("description");
+        let category = row.get::<String, _>("category");
+        let transaction_type = row.get::<String, _>("transaction_type");
+        for keyword in tokenize(&description) {
+            *counts
+                .entry((keyword, category.clone(), transaction_type.clone()))
+                .or_insert(0) += 1;
+        }
+    }
+
+    let now = format_db_datetime(Utc::now());
+    let mut tx = pool.begin().await?;
+
+    sqlx::query("DELETE FROM category_keywords WHERE user_id = ?")
+        .bind(user_id)
+        .execute(&mut tx)
+        .await?;
+
+    for ((keyword, category, transaction_type), occurrences) in &counts {
+        sqlx::query(
+            "INSERT INTO category_keywords (user_id, keyword, category, transaction_type, occurrences, updated_at) VALUES (?, ?, ?, ?, ?, ?)"
+        )
+        .bind(user_id)
+        .bind(keyword)
+        .bind(category)
+        .bind(transaction_type)
+        .bind(occurrences)
+        .bind(&now)
+        .execute(&mut tx)
+        .await?;
+    }
+
+    tx.commit().await?;
+    Ok(counts.len())
+}
+
+/// Retrains the model for every user that has at least one transaction.
+pub async fn train_all_users(pool: &DbPool) -> Result<()> {
+    let user_ids: Vec<String> = sqlx::query("SELECT DISTINCT user_id FROM transactions")
+        .fetch_all(pool)
+        .await?
+        .into_iter()
+        .map(|row| row.get::<String, _>("user_id"))
+        .collect();
+
+    for user_id in &user_ids {
+        if let Err(e) = train_user_model(pool, user_id).await {
+            tracing::error!("Failed to train category model for user {}: {}", user_id, e);
+        }
+    }
+
+    tracing::info!("Category keyword model trained for {} users", user_ids.len());
+    Ok(())
+}
+
+/// Schedules the background job that periodically retrains the keyword models.
+pub fn schedule_training_job(scheduler: &mut Scheduler) {
+    scheduler.every("category_model_training", TRAINING_INTERVAL, |pool| async move {
+        train_all_users(&pool).await?;
+        Ok(0)
+    });
+}
+
+/// Ranks the user's categories for a new description.
+///
+/// Each matched keyword votes for the categories it was seen with, weighted by how
+/// often it co-occurred with that category relative to all its categories. The summed
+/// votes are divided by the number of keywords so the score stays within `0.0..=1.0`.
+pub async fn suggest(
+    pool: &DbPool,
+    user_id: &str,
+    description: &str,
+    transaction_type: Option<TransactionType>,
+    limit: usize,
+) -> Result<Vec<CategorySuggestion>> {
+    let keywords = tokenize(description);
+    if keywords.is_empty() {
+        return Ok(Vec::new());
+    }
+
+    // Users created since the last scheduled run have no model yet
+    let trained_keywords: i64 = sqlx::query("SELECT COUNT(*) AS count FROM category_keywords WHERE user_id = ?")
+        .bind(user_id)
+        .fetch_one(pool)
+        .await?
+        .get("count");
+    if trained_keywords == 0 {
+        train_user_model(pool, user_id).await?;
+    }
+
+    let placeholders = vec!["?"; keywords.len()].join(", ");
+    let sql = format!(
+        // Archived categories still name old transactions but aren't offered for new ones
+        "SELECT keyword, category, SUM(occurrences) AS occurrences FROM category_keywords ck
+         WHERE user_id = ? AND (? IS NULL OR transaction_type = ?) AND keyword IN ({})
+           AND NOT EXISTS (
+               SELECT 1 FROM categories c JOIN category_archives a ON a.category_id = c.id
+               WHERE c.name = ck.category COLLATE NOCASE AND a.user_id = ck.user_id
+           )
+         GROUP BY keyword, category",
+        placeholders
+    );
+
+    let mut query = sqlx::query(&sql)
+        .bind(user_id)
+        .bind(transaction_type)
+        .bind(transaction_type);
+    for keyword in &keywords {
+        query = query.bind(keyword);
+    }
+    let rows = query.fetch_all(pool).await?;
+
+    let mut keyword_totals: HashMap<String, i64> = HashMap::new();
+    let mut pairs = Vec::with_capacity(rows.len());
+    for row in rows {
+        let keyword = row.get::<String, _>("keyword");
+        let category = row.get::<String, _>("category");
+        let occurrences = row.get::<i64, _>("occurrences");
+        *keyword_totals.entry(keyword.clone()).or_insert(0) += occurrences;
+        pairs.push((keyword, category, occurrences));
+    }
+
+    let mut scores: HashMap<String, (f64, Vec<String>)> = HashMap::new();
+    for (keyword, category, occurrences) in pairs {
+        let total = keyword_totals[&keyword] as f64;
+        let entry = scores.entry(category).or_insert((0.0, Vec::new()));
+        entry.0 += occurrences as f64 / total;
+        entry.1.push(keyword);
+    }
+
+    let keyword_count = keywords.len() as f64;
+    let mut suggestions: Vec<CategorySuggestion> = scores
+        .into_iter()
+        .map(|(category, (score, mut matched_keywords))| {
+            matched_keywords.sort();
+            CategorySuggestion {
+                category,
+                score: (score / keyword_count * 1000.0).round() / 1000.0,
+                matched_keywords,
+            }
+        })
+        .collect();
+
+    suggestions.sort_by(|a, b| {
+        b.score
+            .partial_cmp(&a.score)
+            .unwrap_or(std::cmp::Ordering::Equal)
+            .then_with(|| a.category.cmp(&b.category))
+    });
+    suggestions.truncate(limit);
+    Ok(suggestions)
+}
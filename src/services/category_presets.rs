@@ -0,0 +1,229 @@
+use std::collections::HashMap;
+
+use anyhow::Result;
+use chrono::Utc;
+use serde::Serialize;
+use uuid::Uuid;
+
+use crate::models::{Category, CategoryImportSummary, CategoryTemplate, CategoryType};
+use crate::services::database::DbPool;
+use crate::utils::datetime::format_db_datetime;
+
+/// Most categories one import may add.
+pub const MAX_IMPORTED_CATEGORIES: usize = 200;
+
+/// Format name written into category exports.
+pub const CATEGORY_SET_FORMAT: &str = "personal-manager-categories";
+
+pub const CATEGORY_SET_VERSION: u32 = 1;
+
+/// A curated set of categories for one way of life, added in one go.
+pub struct PresetPack {
+    pub id: &'static str,
+    pub name: &'static str,
+    pub description: &'static str,
+    /// `(name, category_type, icon, color)`.
+    pub categories: &'static [(&'static str, CategoryType, &'static str, &'static str)],
+}
+
+pub const PRESET_PACKS: &[PresetPack] = &[
+    PresetPack {
+        id: "student",
+        name: "Student",
+        description: "Tuition, books, hostel life and part-time income",
+        categories: &[
+            ("Allowance", CategoryType::Income, "👛", "#4CAF50"),
+            ("Scholarship", CategoryType::Income, "🎓", "#8BC34A"),
+            ("Part-time Job", CategoryType::Income, "🧑‍💻", "#CDDC39"),
+            ("Tuition", CategoryType::Expense, "🏫", "#3F51B5"),
+            ("Books & Supplies", CategoryType::Expense, "📚", "#673AB7"),
+            ("Hostel & Rent", CategoryType::Expense, "🏠", "#795548"),
+            ("Canteen", CategoryType::Expense, "🍜", "#FF5722"),
+            ("Transport", CategoryType::Expense, "🚌", "#2196F3"),
+            ("Mobile & Internet", CategoryType::Expense, "📱", "#00BCD4"),
+            ("Hangouts", CategoryType::Expense, "🎉", "#E91E63"),
+        ],
+    },
+    PresetPack {
+        id: "family",
+        name: "Family",
+        description: "Running a household with children",
+        categories: &[
+            ("Salary", CategoryType::Income, "💼", "#4CAF50"),
+            ("Rental Income", CategoryType::Income, "🏘️", "#009688"),
+            ("Groceries", CategoryType::Expense, "🛒", "#FF9800"),
+            ("Rent", CategoryType::Expense, "🏠", "#795548"),
+            ("Utilities", CategoryType::Expense, "💡", "#FFC107"),
+            ("School Fees", CategoryType::Expense, "🎒", "#3F51B5"),
+            ("Childcare", CategoryType::Expense, "🧸", "#F48FB1"),
+            ("Healthcare", CategoryType::Expense, "💊", "#F44336"),
+            ("Household Help", CategoryType::Expense, "🧹", "#9E9E9E"),
+            ("Family Outings", CategoryType::Expense, "🎡", "#E91E63"),
+            ("Gifts & Festivals", CategoryType::Expense, "🎁", "#9C27B0"),
+        ],
+    },
+    PresetPack {
+        id: "freelancer",
+        name: "Freelancer",
+        description: "Client income and the costs of working for yourself",
+        categories: &[
+            ("Client Payments", CategoryType::Income, "💻", "#4CAF50"),
+            ("Retainers", CategoryType::Income, "🤝", "#8BC34A"),
+            ("Platform Payouts", CategoryType::Income, "🌐", "#00BCD4"),
+            ("Software & Subscriptions", CategoryType::Expense, "🧩", "#3F51B5"),
+            ("Equipment", CategoryType::Expense, "🖥️", "#607D8B"),
+            ("Coworking", CategoryType::Expense, "🏢", "#795548"),
+            ("Platform Fees", CategoryType::Expense, "🧾", "#FF9800"),
+            ("Taxes", CategoryType::Expense, "🏛️", "#F44336"),
+            ("Professional Development", CategoryType::Expense, "📈", "#673AB7"),
+            ("Internet", CategoryType::Expense, "📶", "#2196F3"),
+        ],
+    },
+];
+
+/// A pack as the API lists it.
+#[derive(Debug, Clone, Serialize)]
+pub struct PresetPackSummary {
+    pub id: &'static str,
+    pub name: &'static str,
+    pub description: &'static str,
+    pub categories: Vec<CategoryTemplate>,
+}
+
+impl PresetPack {
+    pub fn find(id: &str) -> Option<&'static PresetPack> {
+        PRESET_PACKS.iter().find(|pack| pack.id.eq_ignore_ascii_case(id))
+    }
+
+    pub fn templates(&self) -> Vec<CategoryTemplate> {
+        self.categories
+            .iter()
+            .map(|(name, category_type, icon, color)| CategoryTemplate {
+                name: name.to_string(),
+                category_type: *category_type,
+                icon: icon.to_string(),
+                color: color.to_string(),
+            })
+            .collect()
+    }
+
+    pub fn summary(&self) -> PresetPackSummary {
+        PresetPackSummary {
+            id: self.id,
+            name: self.name,
+            description: self.description,
+            categories: self.templates(),
+        }
+    }
+}
+
+fn type_name(category_type: CategoryType) -> &'static str {
+    match category_type {
+        CategoryType::Income => "income",
+        CategoryType::Expense => "expense",
+    }
+}
+
+/// The user's own categories, oldest first, without their IDs.
+pub async fn export(pool: &DbPool, user_id: &str) -> Result<Vec<CategoryTemplate>> {
+    let categories = sqlx::query_as::<_, Category>("SELECT * FROM categories WHERE user_id = ? ORDER BY created_at, name")
+        .bind(user_id)
+        .fetch_all(pool)
+        .await?;
+    Ok(categories
+        .into_iter()
+        .map(|category| CategoryTemplate {
+            name: category.name,
+            category_type: category.category_type,
+            icon: category.icon,
+            color: category.color,
+        })
+        .collect())
+}
+
+fn category_key(name: &str, category_type: CategoryType) -> (String, &'static str) {
+    (name.trim().to_lowercase(), type_name(category_type))
+}
+
+/// Creates `templates` as the user's categories in one transaction. One whose name and type
+/// match a category the user can already pick, or an earlier template, is skipped; one
+/// matching only a category the user archived brings that category back instead. Names
+/// compare without case or surrounding spaces.
+pub async fn add_categories(pool: &DbPool, user_id: &str, templates: &[CategoryTemplate]) -> Result<CategoryImportSummary> {
+    let mut tx = pool.begin().await?;
+    // Each name and type the user can reach, with the archived category holding it if no
+    // active one does
+    let mut taken: HashMap<(String, &'static str), Option<Category>> = HashMap::new();
+    let existing = sqlx::query_as::<_, Category>(
+        "SELECT *, EXISTS (SELECT 1 FROM category_archives a WHERE a.user_id = ? AND a.category_id = categories.id) AS is_archived
+         FROM categories WHERE user_id = ? OR user_id = ''",
+    )
+    .bind(user_id)
+    .bind(user_id)
+    .fetch_all(&mut tx)
+    .await?;
+    for category in existing {
+        let key = category_key(&category.name, category.category_type);
+        let archived = category.is_archived.then_some(category);
+        match taken.get_mut(&key) {
+            Some(slot) if archived.is_none() => *slot = None,
+            Some(_) => {}
+            None => {
+                taken.insert(key, archived);
+            }
+        }
+    }
+
+    let now = Utc::now();
+    let mut summary = CategoryImportSummary::default();
+    for template in templates {
+        let name = template.name.trim();
+        let key = category_key(name, template.category_type);
+        if let Some(slot) = taken.get_mut(&key) {
+            match slot.take() {
+                Some(mut category) => {
+                    sqlx::query("DELETE FROM category_archives WHERE user_id = ? AND category_id = ?")
+                        .bind(user_id)
+                        .bind(&category.id)
+                        .execute(&mut tx)
+                        .await?;
+                    category.is_archived = false;
+                    summary.unarchived.push(category);
+                }
+                None => summary.skipped.push(name.to_string()),
+            }
+            continue;
+        }
+        taken.insert(key, None);
+        let category = Category {
+            id: Uuid::new_v4().to_string(),
+            name: name.to_string(),
+            category_type: template.category_type,
+            icon: template.icon.clone(),
+            color: template.color.clone(),
+            is_default: false,
+            is_archived: false,
+            created_at: now,
+            user_id: user_id.to_string(),
+            updated_at: Some(now),
+        };
+        sqlx::query(
+            "INSERT INTO categories (id, name, category_type, icon, color, is_default, created_at, user_id, updated_at) VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?)"
+        )
+        .bind(&category.id)
+        .bind(&category.name)
+        .bind(type_name(category.category_type))
+        .bind(&category.icon)
+        .bind(&category.color)
+        .bind(false)
+        .bind(format_db_datetime(now))
+        .bind(user_id)
+        .bind(format_db_datetime(now))
+        .execute(&mut tx)
+        .await?;
+        summary.created.push(category);
+    }
+
+    tx.commit().await?;
+    Ok(summary)
+}
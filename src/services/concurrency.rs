@@ -0,0 +1,114 @@
+use anyhow::Result;
+use axum::{
+    http::{header, HeaderMap, HeaderValue},
+    response::{IntoResponse, Response},
+};
+use serde_json::{json, Value};
+
+use crate::models::{Account, Budget, RecurringTransaction, SavingsGoal, Transaction};
+use crate::services::database::DbPool;
+use crate::services::history::EntityKind;
+use crate::utils::response::ApiResponse;
+use crate::utils::error::AppError;
+
+/// The version an update was based on: `If-Match` when sent (`"3"`, `W/"3"` or `3`),
+/// otherwise the body's `version`. Updates without either get 428 Precondition Required.
+pub fn expected_version(headers: &HeaderMap, body_version: Option<i64>) -> Result<i64, AppError> {
+    let Some(if_match) = headers.get(header::IF_MATCH) else {
+        return body_version.ok_or_else(|| {
+            tracing::warn!("Update sent without If-Match or version");
+            AppError::PreconditionRequired("Send If-Match or the version your update is based on".into())
+        });
+    };
+
+    if_match
+        .to_str()
+        .ok()
+        .map(|value| value.trim().trim_start_matches("W/").trim_matches('"'))
+        .and_then(|value| value.parse().ok())
+        .ok_or_else(|| {
+            tracing::warn!("Invalid If-Match header: {:?}", if_match);
+            AppError::BadRequest("Invalid If-Match header".into())
+        })
+}
+
+/// The stored record as its own endpoints return it, or `None` if the user has no such record.
+pub async fn current_copy(pool: &DbPool, kind: EntityKind, id: &str, user_id: &str) -> Result<Option<Value>> {
+    let copy = match kind {
+        EntityKind::Account => sqlx::query_as::<_, Account>("SELECT * FROM accounts WHERE id = ? AND user_id = ?")
+            .bind(id)
+            .bind(user_id)
+            .fetch_optional(pool)
+            .await?
+            .map(|account| json!(account)),
+        EntityKind::Transaction => sqlx::query_as::<_, Transaction>("SELECT * FROM transactions WHERE id = ? AND user_id = ?")
+            .bind(id)
+            .bind(user_id)
+            .fetch_optional(pool)
+            .await?
+            .map(|transaction| json!(transaction)),
+        EntityKind::Budget => sqlx::query_as::<_, Budget>("SELECT * FROM budgets WHERE id = ? AND user_id = ?")
+            .bind(id)
+            .bind(user_id)
+            .fetch_optional(pool)
+            .await?
+            .map(|budget| json!(budget)),
+        EntityKind::SavingsGoal => sqlx::query_as::<_, SavingsGoal>("SELECT * FROM savings_goals WHERE id = ? AND user_id = ?")
+            .bind(id)
+            .bind(user_id)
+            .fetch_optional(pool)
+            .await?
+            .map(|goal| json!(goal)),
+        EntityKind::RecurringTransaction => {
+            sqlx::query_as::<_, RecurringTransaction>("SELECT * FROM recurring_transactions WHERE id = ? AND user_id = ?")
+                .bind(id)
+                .bind(user_id)
+                .fetch_optional(pool)
+                .await?
+                .map(|recurring| json!(recurring))
+        }
+        EntityKind::Loan | EntityKind::Liability => anyhow::bail!("{} records are not versioned", kind.name()),
+    };
+
+    Ok(copy)
+}
+
+/// The error for an update whose `WHERE ... AND version = ?` matched nothing: 409 Conflict with
+/// the server's copy when the record was changed in the meantime, or 404 when it doesn't exist.
+pub async fn rejected_update(pool: &DbPool, kind: EntityKind, id: &str, user_id: &str, expected: i64) -> Result<Response, AppError> {
+    let label = kind.name().replace('_', " ");
+    let current = current_copy(pool, kind, id, user_id).await.map_err(|e| {
+        tracing::error!("Failed to load {} {}: {}", kind.name(), id, e);
+        AppError::Internal(format!("Failed to load the {}", label))
+    })?;
+
+    let Some(current) = current else {
+        tracing::warn!("{} not found for update: {}", kind.name(), id);
+        return Err(AppError::NotFound(format!("The {} was not found", label)));
+    };
+
+    tracing::warn!("Stale update of {} {}: based on version {}, server has {}", kind.name(), id, expected, current["version"]);
+    let message = format!("The {} was changed by someone else; apply your changes to the current copy and retry", label);
+    Err(AppError::Stale { message, current })
+}
+
+/// A successful update's answer, with the new version in the body and as the `ETag` to send
+/// back as `If-Match` next time.
+pub async fn updated(pool: &DbPool, kind: EntityKind, id: &str, message: &str) -> Response {
+    let version = sqlx::query_scalar::<_, i64>(&format!("SELECT version FROM {} WHERE id = ?", kind.table()))
+        .bind(id)
+        .fetch_optional(pool)
+        .await
+        .unwrap_or_else(|e| {
+            tracing::error!("Failed to read the version of {} {}: {}", kind.name(), id, e);
+            None
+        });
+
+    let mut response = ApiResponse::ok(json!({ "id": id, "version": version }))
+        .with_message(message)
+        .into_response();
+    if let Some(etag) = version.and_then(|version| HeaderValue::from_str(&format!("\"{}\"", version)).ok()) {
+        response.headers_mut().insert(header::ETAG, etag);
+    }
+    response
+}
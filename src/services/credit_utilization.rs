@@ -0,0 +1,92 @@
+use anyhow::Result;
+use sqlx::Row;
+
+use crate::models::CreditUtilization;
+use crate::services::database::DbPool;
+use crate::services::notifications::{self, NewNotification};
+use crate::services::preferences;
+use crate::utils::money::Money;
+
+/// Utilization percentages that alert when the user hasn't chosen their own.
+pub const DEFAULT_THRESHOLDS: &[u32] = &[30, 80];
+
+/// Most thresholds a user can set.
+pub const MAX_THRESHOLDS: usize = 5;
+
+/// Credit cards with a limit to measure against.
+const CARDS_SELECT: &str = "SELECT id, name, currency, balance, credit_limit, utilization_alert_level FROM accounts WHERE user_id = ? AND account_type = 'credit_card' AND credit_limit > 0";
+
+pub async fn thresholds(pool: &DbPool, user_id: &str) -> Result<Vec<u32>> {
+    Ok(preferences::load(pool, user_id).await?.credit_utilization_thresholds)
+}
+
+fn utilization(row: &sqlx::sqlite::SqliteRow, thresholds: &[u32]) -> CreditUtilization {
+    let credit_limit: Money = row.get("credit_limit");
+    let used = (-row.get::<Money, _>("balance")).max(Money::ZERO);
+    let percent = used.ratio(credit_limit) * 100.0;
+    CreditUtilization {
+        account_id: row.get("id"),
+        name: row.get("name"),
+        currency: row.get("currency"),
+        credit_limit,
+        used,
+        utilization_percent: (percent * 100.0).round() / 100.0,
+        threshold: thresholds.iter().copied().filter(|&t| percent >= t as f64).max(),
+    }
+}
+
+/// Utilization of each of the user's credit cards that has a limit.
+pub async fn card_utilizations(pool: &DbPool, user_id: &str) -> Result<Vec<CreditUtilization>> {
+    let thresholds = thresholds(pool, user_id).await?;
+    let rows = sqlx::query(&format!("{} ORDER BY name", CARDS_SELECT))
+        .bind(user_id)
+        .fetch_all(pool)
+        .await?;
+    Ok(rows.iter().map(|row| utilization(row, &thresholds)).collect())
+}
+
+/// Queues a `credit_utilization` notification if the card has crossed a higher threshold than
+/// it last alerted at. Falling back below a threshold re-arms it.
+pub async fn notify_utilization(pool: &DbPool, user_id: &str, account_id: &str) -> Result<()> {
+    let thresholds = thresholds(pool, user_id).await?;
+    let Some(row) = sqlx::query(&format!("{} AND id = ?", CARDS_SELECT))
+        .bind(user_id)
+        .bind(account_id)
+        .fetch_optional(pool)
+        .await?
+    else {
+        return Ok(());
+    };
+
+    let card = utilization(&row, &thresholds);
+    let level = card.threshold.unwrap_or(0);
+    let alerted_level: i64 = row.get("utilization_alert_level");
+    if i64::from(level) == alerted_level {
+        return Ok(());
+    }
+
+    if i64::from(level) > alerted_level {
+        let queued = notifications::enqueue(pool, NewNotification {
+            user_id,
+            kind: "credit_utilization",
+            title: format!("{} is over {}% of its limit", card.name, level),
+            body: format!(
+                "{:.2} of your {:.2} {} limit is in use ({:.0}%).",
+                card.used, card.credit_limit, card.currency, card.utilization_percent
+            ),
+            entity: Some(("account", account_id)),
+            dedupe_key: None,
+        })
+        .await;
+        if let Err(e) = queued {
+            tracing::error!("Failed to queue credit_utilization notification for user {}: {}", user_id, e);
+        }
+    }
+
+    sqlx::query("UPDATE accounts SET utilization_alert_level = ? WHERE id = ?")
+        .bind(i64::from(level))
+        .bind(account_id)
+        .execute(pool)
+        .await?;
+    Ok(())
+}
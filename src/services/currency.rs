@@ -0,0 +1,273 @@
+use std::collections::HashMap;
+use std::time::Duration;
+
+use anyhow::{anyhow, Result};
+use chrono::{NaiveDate, Utc};
+use serde::Deserialize;
+
+use crate::config::MarketDataConfig;
+use crate::models::CurrencyInfo;
+use crate::services::cache;
+use crate::services::database::DbPool;
+use crate::services::preferences;
+use crate::services::scheduler::Scheduler;
+use crate::utils::datetime::format_db_datetime;
+use crate::utils::money::Money;
+
+/// Currency used to bridge pairs with no stored rate between them, e.g. EUR -> USD -> BDT.
+pub const PIVOT_CURRENCY: &str = "USD";
+
+/// Used when a user has not picked a display currency.
+pub const DEFAULT_DISPLAY_CURRENCY: &str = "BDT";
+
+const RATE_REFRESH_INTERVAL: Duration = Duration::from_secs(12 * 60 * 60);
+const RATE_FETCH_TIMEOUT: Duration = Duration::from_secs(30);
+
+/// Whether `code` looks like an ISO 4217 code. Any such code is accepted, listed or not.
+pub fn is_currency_code(code: &str) -> bool {
+    code.len() == 3 && code.chars().all(|c| c.is_ascii_alphabetic())
+}
+
+/// Rounds `amount` half away from zero to the minor unit of `currency`. Every amount derived
+/// by conversion or aggregation goes through here so it can be stored and shown as-is.
+pub fn round(amount: Money, currency: &str) -> Money {
+    amount.round_dp(CurrencyInfo::decimals_for(currency))
+}
+
+/// Whether `amount` can be written in `currency` without going below its minor unit, e.g.
+/// `10.5` is fine in USD but not in JPY.
+pub fn fits_minor_unit(amount: Money, currency: &str) -> bool {
+    amount.decimal_places() <= CurrencyInfo::decimals_for(currency)
+}
+
+/// Rate for `user_id` to convert one unit of `from` into `to` on `date`: the user's own rate
+/// for the pair if set, otherwise the latest provider rate on or before that day. Either is
+/// read directly, inverted from the opposite pair, or crossed through [`PIVOT_CURRENCY`].
+/// Cached, as totals look up the same few pairs on every request.
+pub async fn rate_on(pool: &DbPool, user_id: &str, from: &str, to: &str, date: NaiveDate) -> Result<Option<f64>> {
+    if from.eq_ignore_ascii_case(to) {
+        return Ok(Some(1.0));
+    }
+    let name = format!("rate:{}:{}:{}", from.to_uppercase(), to.to_uppercase(), date);
+    cache::get().cached(user_id, &name, find_rate(pool, user_id, from, to, date)).await
+}
+
+async fn find_rate(pool: &DbPool, user_id: &str, from: &str, to: &str, date: NaiveDate) -> Result<Option<f64>> {
+    if let Some(rate) = pair_rate(pool, user_id, from, to, date).await? {
+        return Ok(Some(rate));
+    }
+    if from.eq_ignore_ascii_case(PIVOT_CURRENCY) || to.eq_ignore_ascii_case(PIVOT_CURRENCY) {
+        return Ok(None);
+    }
+
+    let to_pivot = pair_rate(pool, user_id, from, PIVOT_CURRENCY, date).await?;
+    let from_pivot = pair_rate(pool, user_id, PIVOT_CURRENCY, to, date).await?;
+    Ok(to_pivot.zip(from_pivot).map(|(a, b)| a * b))
+}
+
+/// A rate for exactly this pair, the user's own before the provider's.
+async fn pair_rate(pool: &DbPool, user_id: &str, from: &str, to: &str, date: NaiveDate) -> Result<Option<f64>> {
+    if let Some(rate) = user_rate(pool, user_id, from, to).await? {
+        return Ok(Some(rate));
+    }
+    stored_rate(pool, from, to, date).await
+}
+
+/// The user's own rate for this pair, set either way round.
+async fn user_rate(pool: &DbPool, user_id: &str, from: &str, to: &str) -> Result<Option<f64>> {
+    let row = sqlx::query_as::<_, (String, f64)>(
+        r#"
+        SELECT base_currency, rate FROM user_exchange_rates
+        WHERE user_id = ? AND ((base_currency = ? AND quote_currency = ?) OR (base_currency = ? AND quote_currency = ?))
+        "#,
+    )
+    .bind(user_id)
+    .bind(from.to_uppercase())
+    .bind(to.to_uppercase())
+    .bind(to.to_uppercase())
+    .bind(from.to_uppercase())
+    .fetch_optional(pool)
+    .await?;
+
+    Ok(row.filter(|(_, rate)| *rate > 0.0).map(|(base, rate)| {
+        if base.eq_ignore_ascii_case(from) { rate } else { 1.0 / rate }
+    }))
+}
+
+/// A provider rate for exactly this pair, stored either way round.
+async fn stored_rate(pool: &DbPool, from: &str, to: &str, date: NaiveDate) -> Result<Option<f64>> {
+    let day = date.format("%Y-%m-%d").to_string();
+
+    let direct = sqlx::query_scalar::<_, f64>(
+        "SELECT rate FROM exchange_rates WHERE base_currency = ? AND quote_currency = ? AND rate_date <= ? ORDER BY rate_date DESC LIMIT 1",
+    )
+    .bind(from.to_uppercase())
+    .bind(to.to_uppercase())
+    .bind(&day)
+    .fetch_optional(pool)
+    .await?;
+    if let Some(rate) = direct.filter(|rate| *rate > 0.0) {
+        return Ok(Some(rate));
+    }
+
+    let inverse = sqlx::query_scalar::<_, f64>(
+        "SELECT rate FROM exchange_rates WHERE base_currency = ? AND quote_currency = ? AND rate_date <= ? ORDER BY rate_date DESC LIMIT 1",
+    )
+    .bind(to.to_uppercase())
+    .bind(from.to_uppercase())
+    .bind(&day)
+    .fetch_optional(pool)
+    .await?;
+
+    Ok(inverse.filter(|rate| *rate > 0.0).map(|rate| 1.0 / rate))
+}
+
+/// Converts a user's amounts into a single target currency, remembering each (currency, day)
+/// lookup so a batch of transactions costs one query per distinct pair.
+pub struct Converter<'a> {
+    pool: &'a DbPool,
+    user_id: String,
+    target: String,
+    rates: HashMap<(String, NaiveDate), Option<f64>>,
+}
+
+impl<'a> Converter<'a> {
+    pub fn new(pool: &'a DbPool, user_id: &str, target: &str) -> Self {
+        Self {
+            pool,
+            user_id: user_id.to_string(),
+            target: target.to_string(),
+            rates: HashMap::new(),
+        }
+    }
+
+    pub async fn rate(&mut self, from: &str, date: NaiveDate) -> Result<Option<f64>> {
+        let key = (from.to_uppercase(), date);
+        if let Some(rate) = self.rates.get(&key) {
+            return Ok(*rate);
+        }
+        let rate = rate_on(self.pool, &self.user_id, from, &self.target, date).await?;
+        self.rates.insert(key, rate);
+        Ok(rate)
+    }
+
+    /// Converts `amount` of `from` at the rate for `date`, rounded to the target currency, or
+    /// `None` if no rate is known.
+    pub async fn convert(&mut self, amount: Money, from: &str, date: NaiveDate) -> Result<Option<Money>> {
+        Ok(self.rate(from, date).await?.map(|rate| round(amount * rate, &self.target)))
+    }
+}
+
+/// A transaction amount fixed in the user's display currency when it is recorded.
+#[derive(Debug, Clone)]
+pub struct BaseAmount {
+    pub currency: String,
+    pub exchange_rate: f64,
+    pub amount: Money,
+}
+
+/// Converts `amount` of `currency` into the user's display currency at the rate in effect on
+/// `date`, rounded to that currency. A rate or base amount given by the client wins over a
+/// looked-up rate; `None` when neither is given and no rate is known.
+pub async fn capture_base_amount(
+    pool: &DbPool,
+    user_id: &str,
+    amount: Money,
+    currency: &str,
+    date: NaiveDate,
+    exchange_rate: Option<f64>,
+    base_amount: Option<Money>,
+) -> Result<Option<BaseAmount>> {
+    let target = display_currency(pool, user_id).await?;
+    let rate = match exchange_rate.or_else(|| base_amount.filter(|_| !amount.is_zero()).map(|base| base.ratio(amount))) {
+        Some(rate) => Some(rate),
+        None => rate_on(pool, user_id, currency, &target, date).await?,
+    };
+
+    Ok(rate.map(|rate| BaseAmount {
+        currency: target.clone(),
+        exchange_rate: rate,
+        amount: round(base_amount.unwrap_or(amount * rate), &target),
+    }))
+}
+
+/// The user's preferred currency for consolidated totals.
+pub async fn display_currency(pool: &DbPool, user_id: &str) -> Result<String> {
+    Ok(preferences::load(pool, user_id).await?.display_currency)
+}
+
+/// Stores `1 base = rate quote` for each quote currency on `date`, replacing that day's rates.
+pub async fn store_rates(pool: &DbPool, base: &str, date: NaiveDate, rates: &HashMap<String, f64>) -> Result<usize> {
+    let day = date.format("%Y-%m-%d").to_string();
+    let now = format_db_datetime(Utc::now());
+    let mut tx = pool.begin().await?;
+    let mut stored = 0;
+    for (quote, rate) in rates {
+        if quote.eq_ignore_ascii_case(base) || !rate.is_finite() || *rate <= 0.0 {
+            continue;
+        }
+        sqlx::query(
+            r#"
+            INSERT INTO exchange_rates (base_currency, quote_currency, rate_date, rate, created_at)
+            VALUES (?, ?, ?, ?, ?)
+            ON CONFLICT (base_currency, quote_currency, rate_date) DO UPDATE SET rate = excluded.rate, created_at = excluded.created_at
+            "#,
+        )
+        .bind(base.to_uppercase())
+        .bind(quote.to_uppercase())
+        .bind(&day)
+        .bind(rate)
+        .bind(&now)
+        .execute(&mut tx)
+        .await?;
+        stored += 1;
+    }
+    tx.commit().await?;
+    Ok(stored)
+}
+
+/// Response of the rates provider. Both the `open.er-api.com` (`base_code`) and the
+/// exchangerate.host / Frankfurter (`base`) layouts are accepted.
+#[derive(Debug, Deserialize)]
+struct ProviderRates {
+    #[serde(alias = "base_code")]
+    base: String,
+    rates: HashMap<String, f64>,
+}
+
+/// Fetches today's rates from `EXCHANGE_RATES_URL` and stores them.
+pub async fn refresh_rates(pool: &DbPool, client: &reqwest::Client, url: &str) -> Result<usize> {
+    let response = client.get(url).send().await?;
+    if !response.status().is_success() {
+        return Err(anyhow!("rates provider returned {}", response.status()));
+    }
+    let body: ProviderRates = response.json().await?;
+    store_rates(pool, &body.base, Utc::now().date_naive(), &body.rates).await
+}
+
+/// Schedules the daily rate refresh if `market_data.exchange_rates_url` is set, e.g.
+/// `https://open.er-api.com/v6/latest/USD`. Without it only rates entered by hand are used.
+pub fn schedule_exchange_rate_job(scheduler: &mut Scheduler, config: &MarketDataConfig) {
+    let Some(url) = config.exchange_rates_url.clone() else {
+        tracing::info!("Exchange rate refresh disabled: market_data.exchange_rates_url (EXCHANGE_RATES_URL) is not set");
+        return;
+    };
+    let client = match reqwest::Client::builder().timeout(RATE_FETCH_TIMEOUT).build() {
+        Ok(client) => client,
+        Err(e) => {
+            tracing::error!("Exchange rate refresh disabled: {}", e);
+            return;
+        }
+    };
+
+    scheduler.every("exchange_rates", RATE_REFRESH_INTERVAL, move |pool| {
+        let client = client.clone();
+        let url = url.clone();
+        async move {
+            let stored = refresh_rates(&pool, &client, &url).await?;
+            tracing::info!("Stored {} exchange rates", stored);
+            Ok(stored as u64)
+        }
+    })
+    .only_when(|features| features.enabled(features.exchange_rates));
+}
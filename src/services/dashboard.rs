@@ -0,0 +1,94 @@
+use anyhow::Result;
+use chrono::{Duration, Utc};
+use sqlx::Row;
+
+use crate::models::{AccountBalanceTotal, Budget, Dashboard, GoalProgress, SavingsGoal, UpcomingBill};
+use crate::services::database::DbPool;
+use crate::services::{budget_progress, calendar, credit_utilization, net_worth, notifications, reports, user_time};
+
+const RECENT_TRANSACTIONS_LIMIT: i64 = 10;
+
+/// How far ahead upcoming bills are listed.
+const UPCOMING_BILLS_DAYS: i64 = 30;
+
+pub async fn dashboard(pool: &DbPool, user_id: &str) -> Result<Dashboard> {
+    let account_totals = sqlx::query(
+        "SELECT currency, SUM(balance) AS balance, COUNT(*) AS account_count FROM accounts WHERE user_id = ? AND exclude_from_totals = FALSE GROUP BY currency ORDER BY currency"
+    )
+    .bind(user_id)
+    .fetch_all(pool)
+    .await?
+    .into_iter()
+    .map(|row| AccountBalanceTotal {
+        currency: row.get("currency"),
+        balance: row.get("balance"),
+        account_count: row.get("account_count"),
+    })
+    .collect();
+
+    let (net_worth, excluded_accounts) = net_worth::net_worth(pool, user_id).await?;
+
+    let time = user_time::for_user(pool, user_id).await?;
+
+    let recent_transactions = sqlx::query(
+        "SELECT id, account_id, transaction_type, amount, currency, category, description, date FROM transactions WHERE user_id = ? ORDER BY date DESC, created_at DESC LIMIT ?"
+    )
+    .bind(user_id)
+    .bind(RECENT_TRANSACTIONS_LIMIT)
+    .fetch_all(pool)
+    .await?
+    .into_iter()
+    .map(|row| reports::report_transaction(row, &time))
+    .collect();
+
+    let budgets = sqlx::query_as::<_, Budget>("SELECT * FROM budgets WHERE user_id = ? ORDER BY created_at")
+        .bind(user_id)
+        .fetch_all(pool)
+        .await?;
+    let mut budget_statuses = Vec::with_capacity(budgets.len());
+    for budget in &budgets {
+        budget_statuses.push(budget_progress::budget_progress(pool, budget).await?);
+    }
+
+    let now = Utc::now();
+    let mut upcoming_bills: Vec<UpcomingBill> = calendar::items_between(pool, user_id, now, now + Duration::days(UPCOMING_BILLS_DAYS))
+        .await?
+        .into_iter()
+        .filter(|(_, item)| item.direction == Some("out"))
+        .map(|(due_date, item)| UpcomingBill { due_date, item })
+        .collect();
+    upcoming_bills.sort_by_key(|bill| bill.due_date);
+
+    let goals = sqlx::query_as::<_, SavingsGoal>(
+        "SELECT * FROM savings_goals WHERE user_id = ? AND is_completed = FALSE ORDER BY target_date"
+    )
+    .bind(user_id)
+    .fetch_all(pool)
+    .await?
+    .into_iter()
+    .map(|goal| {
+        let percent = if goal.target_amount.is_positive() { goal.current_amount.ratio(goal.target_amount) * 100.0 } else { 0.0 };
+        GoalProgress {
+            id: goal.id,
+            name: goal.name,
+            target_amount: goal.target_amount,
+            current_amount: goal.current_amount,
+            currency: goal.currency,
+            target_date: goal.target_date,
+            percent_complete: (percent.min(100.0) * 100.0).round() / 100.0,
+        }
+    })
+    .collect();
+
+    Ok(Dashboard {
+        account_totals,
+        excluded_accounts,
+        net_worth,
+        recent_transactions,
+        budgets: budget_statuses,
+        upcoming_bills,
+        goals,
+        credit_utilization: credit_utilization::card_utilizations(pool, user_id).await?,
+        unread_notifications: notifications::unread_count(pool, user_id).await?,
+    })
+}
@@ -1,248 +1,495 @@
-use sqlx::{sqlite::{SqlitePool, SqliteConnectOptions, SqliteJournalMode}, Pool, Sqlite};
+use sqlx::{
+    migrate::Migrator,
+    sqlite::{
+        SqliteAutoVacuum, SqliteConnectOptions, SqliteJournalMode, SqlitePoolOptions, SqliteQueryResult, SqliteRow,
+        SqliteStatement, SqliteSynchronous, SqliteTypeInfo,
+    },
+    Describe, Either, Execute, Executor, Pool, Sqlite,
+};
 use anyhow::Result;
+use chrono::Utc;
+use futures_util::{future::BoxFuture, stream::BoxStream, FutureExt, StreamExt};
+use std::ops::Deref;
 use std::str::FromStr;
+use std::time::{Duration, Instant};
 
-pub type DbPool = Pool<Sqlite>;
+use crate::config::{self, DatabaseConfig};
+use crate::middleware::access_log;
+use crate::utils::datetime::format_db_datetime;
+use crate::utils::money::MONEY_SCALE;
 
-pub async fn init_db(database_url: &str) -> Result<DbPool> {
-    // Create database connection pool with create_if_missing
-    let options = SqliteConnectOptions::from_str(database_url)?
+/// The connection pool. Queries run on it directly are timed, and ones slower than
+/// `database.slow_query_ms` are logged with their SQL and the signed-in user, to point at
+/// missing indexes. Queries inside a transaction or on an acquired connection aren't timed.
+#[derive(Debug, Clone)]
+pub struct DbPool {
+    pool: Pool<Sqlite>,
+    slow_query: Option<Duration>,
+}
+
+impl DbPool {
+    /// `slow_query` of zero turns slow query logging off.
+    pub fn new(pool: Pool<Sqlite>, slow_query: Duration) -> Self {
+        Self {
+            pool,
+            slow_query: (!slow_query.is_zero()).then_some(slow_query),
+        }
+    }
+
+    /// Times a query from when it is sent until its results have been read or dropped.
+    fn timer<'q>(&self, sql: &'q str) -> Option<QueryTimer<'q>> {
+        self.slow_query.map(|threshold| QueryTimer {
+            sql,
+            threshold,
+            started: Instant::now(),
+        })
+    }
+}
+
+impl Deref for DbPool {
+    type Target = Pool<Sqlite>;
+
+    fn deref(&self) -> &Pool<Sqlite> {
+        &self.pool
+    }
+}
+
+struct QueryTimer<'q> {
+    sql: &'q str,
+    threshold: Duration,
+    started: Instant,
+}
+
+impl Drop for QueryTimer<'_> {
+    fn drop(&mut self) {
+        let elapsed = self.started.elapsed();
+        if elapsed < self.threshold {
+            return;
+        }
+        let user_id = access_log::current_user();
+        let elapsed_ms = (elapsed.as_secs_f64() * 10_000.0).round() / 10.0;
+        let sql = self.sql.split_whitespace().collect::<Vec<_>>().join(" ");
+        tracing::warn!(
+            target: "slow_query",
+            elapsed_ms, user_id = user_id.as_deref().unwrap_or("-"), sql,
+            "🐢 Slow query ({:.1}ms): {}", elapsed_ms, sql,
+        );
+    }
+}
+
+impl<'p> Executor<'p> for &'p DbPool {
+    type Database = Sqlite;
+
+    fn fetch_many<'e, 'q: 'e, E>(self, query: E) -> BoxStream<'e, Result<Either<SqliteQueryResult, SqliteRow>, sqlx::Error>>
+    where
+        'p: 'e,
+        E: 'q + Execute<'q, Sqlite>,
+    {
+        match self.timer(query.sql()) {
+            Some(timer) => self.pool.fetch_many(query).map(move |step| {
+                let _ = &timer;
+                step
+            }).boxed(),
+            None => self.pool.fetch_many(query),
+        }
+    }
+
+    fn fetch_optional<'e, 'q: 'e, E>(self, query: E) -> BoxFuture<'e, Result<Option<SqliteRow>, sqlx::Error>>
+    where
+        'p: 'e,
+        E: 'q + Execute<'q, Sqlite>,
+    {
+        let timer = self.timer(query.sql());
+        let fetch = self.pool.fetch_optional(query);
+        async move {
+            let row = fetch.await;
+            drop(timer);
+            row
+        }
+        .boxed()
+    }
+
+    fn prepare_with<'e, 'q: 'e>(self, sql: &'q str, parameters: &'e [SqliteTypeInfo]) -> BoxFuture<'e, Result<SqliteStatement<'q>, sqlx::Error>>
+    where
+        'p: 'e,
+    {
+        self.pool.prepare_with(sql, parameters)
+    }
+
+    fn describe<'e, 'q: 'e>(self, sql: &'q str) -> BoxFuture<'e, Result<Describe<Sqlite>, sqlx::Error>>
+    where
+        'p: 'e,
+    {
+        self.pool.describe(sql)
+    }
+}
+
+/// Money columns by table. They hold integers scaled by `10^MONEY_SCALE`; see `utils::money`.
+pub const MONEY_COLUMNS: &[(&str, &[&str])] = &[
+    ("accounts", &["balance", "credit_limit"]),
+    ("transactions", &["amount", "base_amount"]),
+    ("liabilities", &["amount"]),
+    ("loans", &["amount"]),
+    ("savings_goals", &["target_amount", "current_amount"]),
+    ("savings_goal_contributions", &["amount"]),
+    ("budgets", &["amount"]),
+    ("budget_periods", &["base_amount", "carried_over", "adjusted_amount", "spent"]),
+    ("recurring_transactions", &["amount"]),
+    ("amortization_schedules", &["principal", "payment", "total_interest"]),
+    ("amortization_entries", &["payment", "principal", "interest", "remaining", "actual_payment"]),
+    ("cash_counts", &["total", "previous_balance", "difference"]),
+    ("cash_count_denominations", &["denomination", "subtotal"]),
+    ("holdings", &["cost_basis"]),
+    ("term_deposits", &["principal"]),
+    ("card_statements", &["opening_balance", "spend", "credits", "closing_balance", "minimum_due"]),
+    ("emi_plans", &["purchase_amount", "installment_amount", "total_interest"]),
+    ("split_expenses", &["amount"]),
+    ("split_shares", &["amount"]),
+    ("split_settlements", &["amount"]),
+    ("reconciliations", &["statement_balance"]),
+];
+
+/// Tables whose rows carry a `version` for optimistic concurrency; see `services::concurrency`.
+pub const VERSIONED_TABLES: &[&str] = &["accounts", "transactions", "budgets", "savings_goals", "recurring_transactions"];
+
+/// Tables whose writes are logged in `change_log` for delta sync, as `(table, entity type,
+/// account column)`. The account column lets household members see changes on shared accounts.
+pub const CHANGE_LOGGED_TABLES: &[(&str, &str, Option<&str>)] = &[
+    ("accounts", "account", Some("id")),
+    ("transactions", "transaction", Some("account_id")),
+    ("budgets", "budget", None),
+    ("savings_goals", "savings_goal", None),
+    ("recurring_transactions", "recurring_transaction", None),
+];
+
+/// The versioned schema in `migrations/`, embedded at build time.
+pub static MIGRATOR: Migrator = sqlx::migrate!();
+
+/// Columns added with `ALTER TABLE` before the schema moved to `migrations/`. Databases from
+/// then may lack some of them; the initial migration already has them all.
+const LEGACY_COLUMNS: &[(&str, &str)] = &[
+    ("users", "email_verified_at DATETIME"),
+    ("transactions", "exchange_rate REAL"),
+    ("transactions", "base_amount REAL"),
+    ("transactions", "base_currency TEXT"),
+    ("accounts", "exclude_from_totals BOOLEAN NOT NULL DEFAULT FALSE"),
+    ("accounts", "statement_day INTEGER"),
+    ("accounts", "payment_due_day INTEGER"),
+    ("accounts", "utilization_alert_level INTEGER NOT NULL DEFAULT 0"),
+    ("loans", "is_historical_entry BOOLEAN NOT NULL DEFAULT FALSE"),
+    ("loans", "account_id TEXT"),
+    ("loans", "transaction_id TEXT"),
+    ("liabilities", "is_historical_entry BOOLEAN NOT NULL DEFAULT FALSE"),
+    ("liabilities", "account_id TEXT"),
+    ("liabilities", "transaction_id TEXT"),
+    ("categories", "user_id TEXT NOT NULL DEFAULT ''"),
+    ("categories", "updated_at DATETIME"),
+    ("budgets", "rollover BOOLEAN NOT NULL DEFAULT FALSE"),
+    ("budgets", "account_id TEXT"),
+    ("budgets", "multi_currency BOOLEAN NOT NULL DEFAULT FALSE"),
+    ("recurring_transactions", "recurrence_interval INTEGER NOT NULL DEFAULT 1"),
+    ("recurring_transactions", "day_of_month INTEGER"),
+    ("recurring_transactions", "weekday TEXT"),
+    ("user_preferences", "session_idle_timeout_minutes INTEGER"),
+    ("user_preferences", "credit_utilization_thresholds TEXT"),
+    ("notifications", "pushed_at DATETIME"),
+];
+
+/// The money columns of `table`, if any.
+pub fn money_columns(table: &str) -> &'static [&'static str] {
+    MONEY_COLUMNS
+        .iter()
+        .find(|(name, _)| *name == table)
+        .map_or(&[], |(_, columns)| columns)
+}
+
+/// How long a write waits for another connection's lock before failing with "database is
+/// locked". Background jobs and requests write concurrently, and WAL still allows one writer.
+const BUSY_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// First wait between attempts to reach the database at startup; it doubles each time, up to
+/// [`MAX_CONNECT_RETRY_DELAY`].
+const CONNECT_RETRY_DELAY: Duration = Duration::from_secs(1);
+const MAX_CONNECT_RETRY_DELAY: Duration = Duration::from_secs(30);
+
+/// How long a request waits for a free connection before failing.
+const ACQUIRE_TIMEOUT: Duration = Duration::from_secs(30);
+
+/// Connections are closed after sitting unused this long, and replaced after this age, so ones
+/// left broken by a storage hiccup don't linger in the pool.
+const CONNECTION_IDLE_TIMEOUT: Duration = Duration::from_secs(10 * 60);
+const CONNECTION_MAX_LIFETIME: Duration = Duration::from_secs(30 * 60);
+
+/// Opens the pool. Every connection gets the same settings: WAL so reads never wait for a
+/// writer, a busy timeout so writers queue instead of failing, and foreign keys enforced. Each
+/// is checked before it is handed out, and a broken one is replaced by a fresh connection.
+///
+/// A database that can't be reached yet, e.g. a volume still being mounted, is retried with
+/// backoff up to `database.connect_attempts` times; a malformed URL fails at once.
+pub async fn init_db(config: &DatabaseConfig) -> Result<DbPool> {
+    let options = SqliteConnectOptions::from_str(&config.url)?
         .create_if_missing(true)
-        .journal_mode(SqliteJournalMode::Wal);
-    let pool = SqlitePool::connect_with(options).await?;
+        .journal_mode(SqliteJournalMode::Wal)
+        .synchronous(SqliteSynchronous::Normal)
+        .busy_timeout(BUSY_TIMEOUT)
+        .foreign_keys(true)
+        // Only takes effect on new databases; the maintenance job's VACUUM converts older ones
+        .auto_vacuum(SqliteAutoVacuum::Incremental);
 
-    // Enable foreign key constraints for SQLite
-    sqlx::query("PRAGMA foreign_keys = ON")
-        .execute(&pool)
-        .await?;
+    let mut attempt = 1;
+    let pool = loop {
+        let connected = SqlitePoolOptions::new()
+            .max_connections(config.max_connections)
+            .min_connections(config.min_connections)
+            .acquire_timeout(ACQUIRE_TIMEOUT)
+            .idle_timeout(CONNECTION_IDLE_TIMEOUT)
+            .max_lifetime(CONNECTION_MAX_LIFETIME)
+            .test_before_acquire(true)
+            .connect_with(options.clone())
+            .await;
+        match connected {
+            Ok(pool) => break pool,
+            Err(e) if attempt < config.connect_attempts => {
+                let delay = CONNECT_RETRY_DELAY.saturating_mul(2u32.saturating_pow(attempt - 1)).min(MAX_CONNECT_RETRY_DELAY);
+                tracing::warn!(
+                    "Database unavailable (attempt {}/{}), retrying in {:?}: {}",
+                    attempt, config.connect_attempts, delay, e
+                );
+                tokio::time::sleep(delay).await;
+                attempt += 1;
+            }
+            Err(e) => return Err(e.into()),
+        }
+    };
+    let pool = DbPool::new(pool, Duration::from_millis(config.slow_query_ms));
 
-    log::info!("✅ Database connected successfully");
+    tracing::info!("✅ Database connected successfully ({}-{} connections)", config.min_connections, config.max_connections);
     Ok(pool)
 }
 
-pub async fn create_tables(pool: &DbPool) -> Result<()> {
-    // Create users table first (referenced by other tables)
-    sqlx::query(
-        r#"
-        CREATE TABLE IF NOT EXISTS users (
-            id TEXT PRIMARY KEY,
-            name TEXT NOT NULL,
-            email TEXT NOT NULL UNIQUE,
-            password_hash TEXT NOT NULL,
-            created_at DATETIME NOT NULL,
-            updated_at DATETIME NOT NULL
-        )
-        "#,
-    )
-    .execute(pool)
-    .await?;
-
-    // Create accounts table
-    sqlx::query(
-        r#"
-        CREATE TABLE IF NOT EXISTS accounts (
-            id TEXT PRIMARY KEY,
-            user_id TEXT NOT NULL,
-            name TEXT NOT NULL,
-            account_type TEXT NOT NULL,
-            balance REAL NOT NULL,
-            currency TEXT NOT NULL DEFAULT 'BDT',
-            credit_limit REAL,
-            created_at DATETIME NOT NULL,
-            updated_at DATETIME NOT NULL,
-            FOREIGN KEY (user_id) REFERENCES users(id) ON DELETE CASCADE
-        )
-        "#,
-    )
-    .execute(pool)
-    .await?;
-
-    // Create categories table
-    sqlx::query(
-        r#"
-        CREATE TABLE IF NOT EXISTS categories (
-            id TEXT PRIMARY KEY,
-            name TEXT NOT NULL,
-            category_type TEXT NOT NULL,
-            icon TEXT NOT NULL,
-            color TEXT NOT NULL,
-            is_default BOOLEAN NOT NULL DEFAULT FALSE,
-            created_at DATETIME NOT NULL
-        )
-        "#,
-    )
-    .execute(pool)
-    .await?;
-
-    // Create transactions table
-    sqlx::query(
-        r#"
-        CREATE TABLE IF NOT EXISTS transactions (
-            id TEXT PRIMARY KEY,
-            user_id TEXT NOT NULL,
-            account_id TEXT NOT NULL,
-            transaction_type TEXT NOT NULL,
-            amount REAL NOT NULL,
-            currency TEXT NOT NULL DEFAULT 'BDT',
-            category TEXT,
-            description TEXT,
-            date DATETIME NOT NULL,
-            created_at DATETIME NOT NULL,
-            FOREIGN KEY (user_id) REFERENCES users(id) ON DELETE CASCADE,
-            FOREIGN KEY (account_id) REFERENCES accounts(id) ON DELETE CASCADE
-        )
-        "#,
-    )
-    .execute(pool)
-    .await?;
-
-    // Create liabilities table
-    sqlx::query(
-        r#"
-        CREATE TABLE IF NOT EXISTS liabilities (
-            id TEXT PRIMARY KEY,
-            user_id TEXT NOT NULL,
-            person_name TEXT NOT NULL,
-            amount REAL NOT NULL,
-            currency TEXT NOT NULL DEFAULT 'BDT',
-            due_date DATETIME NOT NULL,
-            is_paid BOOLEAN NOT NULL DEFAULT FALSE,
-            description TEXT,
-            created_at DATETIME NOT NULL,
-            updated_at DATETIME NOT NULL,
-            is_historical_entry BOOLEAN NOT NULL DEFAULT FALSE,
-            account_id TEXT,
-            transaction_id TEXT,
-            FOREIGN KEY (user_id) REFERENCES users(id) ON DELETE CASCADE
-        )
-        "#,
-    )
-    .execute(pool)
-    .await?;
-
-    // Create loans table
-    sqlx::query(
-        r#"
-        CREATE TABLE IF NOT EXISTS loans (
-            id TEXT PRIMARY KEY,
-            user_id TEXT NOT NULL,
-            person_name TEXT NOT NULL,
-            amount REAL NOT NULL,
-            currency TEXT NOT NULL DEFAULT 'BDT',
-            loan_date DATETIME NOT NULL,
-            return_date DATETIME,
-            is_returned BOOLEAN NOT NULL DEFAULT FALSE,
-            description TEXT,
-            created_at DATETIME NOT NULL,
-            updated_at DATETIME NOT NULL,
-            is_historical_entry BOOLEAN NOT NULL DEFAULT FALSE,
-            account_id TEXT,
-            transaction_id TEXT,
-            FOREIGN KEY (user_id) REFERENCES users(id) ON DELETE CASCADE
-        )
-        "#,
-    )
-    .execute(pool)
-    .await?;
-
-    // Create savings_goals table
-    sqlx::query(
-        r#"
-        CREATE TABLE IF NOT EXISTS savings_goals (
-            id TEXT PRIMARY KEY,
-            user_id TEXT NOT NULL,
-            name TEXT NOT NULL,
-            target_amount REAL NOT NULL,
-            current_amount REAL NOT NULL DEFAULT 0.0,
-            currency TEXT NOT NULL DEFAULT 'BDT',
-            target_date DATETIME NOT NULL,
-            description TEXT,
-            account_id TEXT,
-            priority TEXT NOT NULL DEFAULT 'medium',
-            is_completed BOOLEAN NOT NULL DEFAULT FALSE,
-            created_at DATETIME NOT NULL,
-            updated_at DATETIME NOT NULL,
-            FOREIGN KEY (user_id) REFERENCES users(id) ON DELETE CASCADE
-        )
-        "#,
-    )
-    .execute(pool)
-    .await?;
-
-    // Create budgets table
-    sqlx::query(
-        r#"
-        CREATE TABLE IF NOT EXISTS budgets (
-            id TEXT PRIMARY KEY,
-            user_id TEXT NOT NULL,
-            category TEXT NOT NULL,
-            amount REAL NOT NULL,
-            currency TEXT NOT NULL DEFAULT 'BDT',
-            period TEXT NOT NULL DEFAULT 'monthly',
-            created_at DATETIME NOT NULL,
-            updated_at DATETIME NOT NULL,
-            FOREIGN KEY (user_id) REFERENCES users(id) ON DELETE CASCADE
-        )
-        "#,
-    )
-    .execute(pool)
-    .await?;
-
-    // Create recurring_transactions table
-    sqlx::query(
-        r#"
-        CREATE TABLE IF NOT EXISTS recurring_transactions (
-            id TEXT PRIMARY KEY,
-            user_id TEXT NOT NULL,
-            account_id TEXT NOT NULL,
-            transaction_type TEXT NOT NULL,
-            amount REAL NOT NULL,
-            currency TEXT NOT NULL DEFAULT 'BDT',
-            category TEXT,
-            description TEXT,
-            frequency TEXT NOT NULL DEFAULT 'monthly',
-            start_date DATETIME NOT NULL,
-            end_date DATETIME,
-            next_due_date DATETIME NOT NULL,
-            is_active BOOLEAN NOT NULL DEFAULT TRUE,
-            savings_goal_id TEXT,
-            created_at DATETIME NOT NULL,
-            updated_at DATETIME NOT NULL,
-            FOREIGN KEY (user_id) REFERENCES users(id) ON DELETE CASCADE,
-            FOREIGN KEY (account_id) REFERENCES accounts(id) ON DELETE CASCADE
-        )
-        "#,
-    )
-    .execute(pool)
-    .await?;
-
-    // Migrations for existing databases: add new columns if they don't exist
-    // .ok() ignores "duplicate column" errors for databases that already have these columns
-    sqlx::query("ALTER TABLE loans ADD COLUMN is_historical_entry BOOLEAN NOT NULL DEFAULT FALSE").execute(pool).await.ok();
-    sqlx::query("ALTER TABLE loans ADD COLUMN account_id TEXT").execute(pool).await.ok();
-    sqlx::query("ALTER TABLE loans ADD COLUMN transaction_id TEXT").execute(pool).await.ok();
-
-    sqlx::query("ALTER TABLE liabilities ADD COLUMN is_historical_entry BOOLEAN NOT NULL DEFAULT FALSE").execute(pool).await.ok();
-    sqlx::query("ALTER TABLE liabilities ADD COLUMN account_id TEXT").execute(pool).await.ok();
-    sqlx::query("ALTER TABLE liabilities ADD COLUMN transaction_id TEXT").execute(pool).await.ok();
-
-    sqlx::query("ALTER TABLE categories ADD COLUMN user_id TEXT NOT NULL DEFAULT ''").execute(pool).await.ok();
-    sqlx::query("ALTER TABLE categories ADD COLUMN updated_at DATETIME").execute(pool).await.ok();
-
-    // Create user_preferences table
-    sqlx::query(
-        r#"
-        CREATE TABLE IF NOT EXISTS user_preferences (
-            user_id TEXT PRIMARY KEY,
-            display_currency TEXT NOT NULL DEFAULT 'BDT',
-            updated_at DATETIME NOT NULL,
-            FOREIGN KEY (user_id) REFERENCES users(id) ON DELETE CASCADE
+/// A throwaway database in memory, for tests. Every connection to `sqlite::memory:` opens a
+/// database of its own, so the pool holds exactly one and never closes it.
+pub async fn init_memory_db() -> Result<DbPool> {
+    let options = SqliteConnectOptions::from_str("sqlite::memory:")?.foreign_keys(true);
+    let pool = SqlitePoolOptions::new()
+        .max_connections(1)
+        .min_connections(1)
+        .idle_timeout(None)
+        .max_lifetime(None)
+        .acquire_timeout(Duration::from_secs(30))
+        .connect_with(options)
+        .await?;
+    Ok(DbPool::new(pool, Duration::from_millis(config::get().database.slow_query_ms)))
+}
+
+/// Migrations in `migrations/` the database hasn't applied, by description.
+pub async fn pending_migrations(pool: &DbPool) -> Result<Vec<String>> {
+    let applied: Vec<i64> = if table_exists(pool, "_sqlx_migrations").await? {
+        sqlx::query_scalar("SELECT version FROM _sqlx_migrations WHERE success = TRUE")
+            .fetch_all(pool)
+            .await?
+    } else {
+        Vec::new()
+    };
+    Ok(MIGRATOR
+        .iter()
+        .filter(|migration| !applied.contains(&migration.version))
+        .map(|migration| migration.description.to_string())
+        .collect())
+}
+
+/// The newest migration that has run, or `None` on a database that has never been migrated.
+pub async fn migration_level(pool: &DbPool) -> Result<Option<i64>> {
+    if !table_exists(pool, "_sqlx_migrations").await? {
+        return Ok(None);
+    }
+    Ok(sqlx::query_scalar("SELECT MAX(version) FROM _sqlx_migrations WHERE success = TRUE")
+        .fetch_one(pool)
+        .await?)
+}
+
+async fn table_exists(pool: &DbPool, name: &str) -> Result<bool> {
+    let count: i64 = sqlx::query_scalar("SELECT COUNT(*) FROM sqlite_master WHERE type = 'table' AND name = ?")
+        .bind(name)
+        .fetch_one(pool)
+        .await?;
+    Ok(count > 0)
+}
+
+/// Brings the schema up to date: runs pending migrations, then recreates the triggers, which
+/// are generated from [`VERSIONED_TABLES`] and [`CHANGE_LOGGED_TABLES`].
+pub async fn migrate(pool: &DbPool) -> Result<()> {
+    // A database with tables but no migration history predates `migrations/`
+    let legacy = table_exists(pool, "users").await? && !table_exists(pool, "_sqlx_migrations").await?;
+    if legacy {
+        tracing::info!("Upgrading a database created before versioned migrations");
+        add_legacy_columns(pool).await?;
+    }
+
+    MIGRATOR.run(&pool.pool).await?;
+
+    if legacy {
+        upgrade_legacy_data(pool).await?;
+    }
+    create_version_triggers(pool).await?;
+    create_change_log_triggers(pool).await?;
+
+    tracing::info!("✅ Database schema is up to date");
+    Ok(())
+}
+
+/// Adds whatever [`LEGACY_COLUMNS`] a pre-migrations database is missing, so the initial
+/// migration, which only creates what doesn't exist, leaves it in the same shape as a new one.
+async fn add_legacy_columns(pool: &DbPool) -> Result<()> {
+    let mut columns: Vec<(&str, String)> = LEGACY_COLUMNS
+        .iter()
+        .map(|(table, column)| (*table, column.to_string()))
+        .collect();
+    for table in VERSIONED_TABLES {
+        columns.push((table, "version INTEGER NOT NULL DEFAULT 1".to_string()));
+    }
+
+    for (table, column) in columns {
+        let name = column.split_whitespace().next().unwrap_or_default();
+        let has_column: i64 = sqlx::query_scalar("SELECT COUNT(*) FROM pragma_table_info(?) WHERE name = ?")
+            .bind(table)
+            .bind(name)
+            .fetch_one(pool)
+            .await?;
+        // A table that doesn't exist yet is created whole by the initial migration
+        if has_column == 0 && table_exists(pool, table).await? {
+            sqlx::query(&format!("ALTER TABLE {} ADD COLUMN {}", table, column)).execute(pool).await?;
+        }
+    }
+    Ok(())
+}
+
+/// One-off data fixes that `create_tables` used to repeat on every start, for databases that
+/// predate `migrations/`.
+async fn upgrade_legacy_data(pool: &DbPool) -> Result<()> {
+    // Fold legacy free-text frequencies into frequency + interval
+    sqlx::query("UPDATE recurring_transactions SET frequency = 'weekly', recurrence_interval = recurrence_interval * 2 WHERE frequency = 'biweekly'").execute(pool).await?;
+    sqlx::query("UPDATE recurring_transactions SET frequency = 'monthly', recurrence_interval = recurrence_interval * 3 WHERE frequency = 'quarterly'").execute(pool).await?;
+    sqlx::query("UPDATE recurring_transactions SET frequency = 'yearly' WHERE frequency = 'annually'").execute(pool).await?;
+
+    // Tombstones used to be change_log rows flagged as deleted
+    let has_deleted_flag: i64 = sqlx::query_scalar("SELECT COUNT(*) FROM pragma_table_info('change_log') WHERE name = 'deleted'")
+        .fetch_one(pool)
+        .await?;
+    if has_deleted_flag > 0 {
+        sqlx::query(
+            "INSERT OR IGNORE INTO deleted_records (entity_type, entity_id, user_id, account_id, deleted_at) SELECT entity_type, entity_id, user_id, account_id, changed_at FROM change_log WHERE deleted = TRUE"
         )
-        "#,
-    )
-    .execute(pool)
-    .await?;
+        .execute(pool)
+        .await?;
+        sqlx::query("DELETE FROM change_log WHERE deleted = TRUE").execute(pool).await?;
+    }
+
+    // One-off data migrations were tracked here before `_sqlx_migrations`
+    sqlx::query("CREATE TABLE IF NOT EXISTS schema_migrations (name TEXT PRIMARY KEY, applied_at DATETIME NOT NULL)")
+        .execute(pool)
+        .await?;
+    migrate_money_to_minor_units(pool).await
+}
+
+/// Bumps `version` on every update that doesn't set it itself, whoever makes the change.
+async fn create_version_triggers(pool: &DbPool) -> Result<()> {
+    for table in VERSIONED_TABLES {
+        sqlx::query(&format!(
+            r#"
+            CREATE TRIGGER IF NOT EXISTS {table}_bump_version AFTER UPDATE ON {table}
+            WHEN NEW.version = OLD.version
+            BEGIN
+                UPDATE {table} SET version = OLD.version + 1 WHERE id = NEW.id;
+            END
+            "#,
+        ))
+        .execute(pool)
+        .await?;
+    }
+    Ok(())
+}
+
+/// Keeps `change_log` and `deleted_records` up to date on every write to the synced tables,
+/// however it is made: handlers, background jobs and cascading deletes alike. The triggers are
+/// recreated on every start so existing databases pick up changes to them.
+async fn create_change_log_triggers(pool: &DbPool) -> Result<()> {
+    // One connection, so the CREATE never sees a schema cached from before the DROP
+    let mut conn = pool.acquire().await?;
+    for (table, entity, account_column) in CHANGE_LOGGED_TABLES {
+        let account_id = |row: &str| account_column.map_or("NULL".to_string(), |column| format!("{}.{}", row, column));
+        let log_change = format!(
+            r#"
+            INSERT INTO change_log (entity_type, entity_id, user_id, account_id, changed_at)
+            VALUES ('{entity}', NEW.id, NEW.user_id, {account_id}, strftime('%Y-%m-%d %H:%M:%S', 'now'))
+            ON CONFLICT (entity_type, entity_id) DO UPDATE SET
+                user_id = excluded.user_id,
+                account_id = excluded.account_id,
+                changed_at = excluded.changed_at;
+            "#,
+            account_id = account_id("NEW"),
+        );
+        let triggers = [
+            (
+                "change_log_insert",
+                "INSERT",
+                // A record restored under its old ID is no longer deleted
+                format!("{log_change} DELETE FROM deleted_records WHERE entity_type = '{entity}' AND entity_id = NEW.id;"),
+            ),
+            ("change_log_update", "UPDATE", log_change.clone()),
+            (
+                "change_log_delete",
+                "DELETE",
+                format!(
+                    r#"
+                    DELETE FROM change_log WHERE entity_type = '{entity}' AND entity_id = OLD.id;
+                    INSERT INTO deleted_records (entity_type, entity_id, user_id, account_id, deleted_at)
+                    VALUES ('{entity}', OLD.id, OLD.user_id, {account_id}, strftime('%Y-%m-%d %H:%M:%S', 'now'))
+                    ON CONFLICT (entity_type, entity_id) DO UPDATE SET
+                        user_id = excluded.user_id,
+                        account_id = excluded.account_id,
+                        deleted_at = excluded.deleted_at;
+                    "#,
+                    account_id = account_id("OLD"),
+                ),
+            ),
+        ];
+
+        for (name, event, body) in triggers {
+            sqlx::query(&format!("DROP TRIGGER IF EXISTS {}_{}", table, name)).execute(&mut conn).await?;
+            sqlx::query(&format!("CREATE TRIGGER {table}_{name} AFTER {event} ON {table} BEGIN {body} END"))
+                .execute(&mut conn)
+                .await?;
+        }
+    }
+    Ok(())
+}
+
+/// Rescales money written as plain REAL values, before amounts were stored as scaled integers.
+/// Runs once per database; new databases never held unscaled amounts.
+async fn migrate_money_to_minor_units(pool: &DbPool) -> Result<()> {
+    let mut tx = pool.begin().await?;
+    let pending = sqlx::query("INSERT OR IGNORE INTO schema_migrations (name, applied_at) VALUES ('money_minor_units', ?)")
+        .bind(format_db_datetime(Utc::now()))
+        .execute(&mut tx)
+        .await?
+        .rows_affected()
+        > 0;
+    if !pending {
+        return Ok(());
+    }
 
-    log::info!("✅ All database tables created successfully");
+    let factor = 10i64.pow(MONEY_SCALE);
+    for (table, columns) in MONEY_COLUMNS {
+        let assignments: Vec<String> = columns
+            .iter()
+            .map(|column| format!("{} = ROUND({} * {})", column, column, factor))
+            .collect();
+        sqlx::query(&format!("UPDATE {} SET {}", table, assignments.join(", ")))
+            .execute(&mut tx)
+            .await?;
+    }
+    tx.commit().await?;
+    tracing::info!("Moved money columns to minor units");
     Ok(())
 }
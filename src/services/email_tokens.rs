@@ -0,0 +1,79 @@
+use anyhow::Result;
+use chrono::{Duration, Utc};
+use uuid::Uuid;
+
+use crate::services::database::DbPool;
+use crate::utils::datetime::format_db_datetime;
+
+/// What a single-use emailed token lets its holder do.
+#[derive(Debug, Clone, Copy)]
+pub enum TokenPurpose {
+    VerifyEmail,
+    ResetPassword,
+}
+
+impl TokenPurpose {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            TokenPurpose::VerifyEmail => "verify_email",
+            TokenPurpose::ResetPassword => "reset_password",
+        }
+    }
+
+    pub fn lifetime(&self) -> Duration {
+        match self {
+            TokenPurpose::VerifyEmail => Duration::hours(48),
+            TokenPurpose::ResetPassword => Duration::minutes(60),
+        }
+    }
+}
+
+/// Creates a token for the user, invalidating any earlier unused one with the same purpose.
+pub async fn issue(pool: &DbPool, user_id: &str, purpose: TokenPurpose) -> Result<String> {
+    let now = Utc::now();
+    let token = format!("{}{}", Uuid::new_v4().simple(), Uuid::new_v4().simple());
+
+    let mut tx = pool.begin().await?;
+    sqlx::query("DELETE FROM email_tokens WHERE user_id = ? AND purpose = ? AND used_at IS NULL")
+        .bind(user_id)
+        .bind(purpose.as_str())
+        .execute(&mut tx)
+        .await?;
+    sqlx::query("INSERT INTO email_tokens (token, user_id, purpose, expires_at, created_at) VALUES (?, ?, ?, ?, ?)")
+        .bind(&token)
+        .bind(user_id)
+        .bind(purpose.as_str())
+        .bind(format_db_datetime(now + purpose.lifetime()))
+        .bind(format_db_datetime(now))
+        .execute(&mut tx)
+        .await?;
+    tx.commit().await?;
+
+    Ok(token)
+}
+
+/// Marks a token used and returns its user, or `None` if it is unknown, expired, already used
+/// or meant for something else.
+pub async fn consume(pool: &DbPool, token: &str, purpose: TokenPurpose) -> Result<Option<String>> {
+    let now = format_db_datetime(Utc::now());
+    let user_id = sqlx::query_scalar::<_, String>(
+        "UPDATE email_tokens SET used_at = ? WHERE token = ? AND purpose = ? AND used_at IS NULL AND expires_at > ? RETURNING user_id",
+    )
+    .bind(&now)
+    .bind(token)
+    .bind(purpose.as_str())
+    .bind(&now)
+    .fetch_optional(pool)
+    .await?;
+
+    Ok(user_id)
+}
+
+/// Deletes tokens that can no longer be consumed: used or past their expiry.
+pub async fn prune(pool: &DbPool) -> Result<u64> {
+    let result = sqlx::query("DELETE FROM email_tokens WHERE used_at IS NOT NULL OR expires_at < ?")
+        .bind(format_db_datetime(Utc::now()))
+        .execute(pool)
+        .await?;
+    Ok(result.rows_affected())
+}
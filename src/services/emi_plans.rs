@@ -0,0 +1,131 @@
+use anyhow::{anyhow, Result};
+use chrono::{DateTime, Datelike, Utc};
+use rust_decimal::Decimal;
+
+use crate::models::{
+    CreateRecurringTransactionRequest, EmiPlan, EmiPlanStatus, RecurrenceRule, RecurringTransaction,
+};
+use crate::services::database::DbPool;
+use crate::services::{amortization, currency, recurring};
+use crate::utils::datetime::format_db_datetime;
+use crate::utils::money::Money;
+
+/// The equal monthly installment and the interest it adds over the whole plan.
+pub fn installment_terms(purchase_amount: Money, annual_rate: f64, installments: u32, code: &str) -> (Money, Money) {
+    let installment = currency::round(amortization::monthly_payment(purchase_amount, annual_rate, installments), code);
+    let total_interest = (installment * Decimal::from(installments) - purchase_amount).max(Money::ZERO);
+    (installment, total_interest)
+}
+
+fn rule(plan: &EmiPlan) -> Result<RecurrenceRule> {
+    RecurrenceRule::parse("monthly", Some(1), Some(plan.first_due_date.day()), None).map_err(|e| anyhow!(e.0))
+}
+
+/// Every installment's due date, first to last.
+pub fn due_dates(plan: &EmiPlan) -> Result<Vec<DateTime<Utc>>> {
+    let rule = rule(plan)?;
+    let mut dates = vec![plan.first_due_date];
+    while dates.len() < plan.installments.max(1) as usize {
+        let previous = dates[dates.len() - 1];
+        let next = rule
+            .next_after(plan.first_due_date, previous)
+            .ok_or_else(|| anyhow!("Could not compute the installment after {}", previous))?;
+        dates.push(next);
+    }
+    Ok(dates)
+}
+
+/// The recurring transaction that posts the plan's installments as expenses.
+fn installment_schedule(plan: &EmiPlan, last_due_date: DateTime<Utc>) -> Result<RecurringTransaction> {
+    RecurringTransaction::new(
+        CreateRecurringTransactionRequest {
+            id: None,
+            account_id: plan.account_id.clone(),
+            transaction_type: "expense".to_string(),
+            amount: plan.installment_amount,
+            currency: Some(plan.currency.clone()),
+            category: plan.category.clone(),
+            description: Some(installment_description(&plan.name)),
+            frequency: Some("monthly".to_string()),
+            interval: Some(1),
+            day_of_month: Some(plan.first_due_date.day()),
+            weekday: None,
+            start_date: plan.first_due_date,
+            end_date: Some(last_due_date),
+            next_due_date: None,
+            is_active: Some(true),
+            savings_goal_id: None,
+        },
+        plan.user_id.clone(),
+    )
+    .map_err(|e| anyhow!(e.0))
+}
+
+pub fn installment_description(name: &str) -> String {
+    format!("{} (EMI installment)", name)
+}
+
+/// Stores the plan together with the recurring transaction that posts its installments.
+pub async fn create_plan(pool: &DbPool, mut plan: EmiPlan) -> Result<EmiPlan> {
+    let last_due_date = due_dates(&plan)?.pop().unwrap_or(plan.first_due_date);
+    let schedule = installment_schedule(&plan, last_due_date)?;
+    plan.recurring_transaction_id = Some(schedule.id.clone());
+
+    let mut tx = pool.begin().await?;
+    recurring::insert_recurring(&mut tx, &schedule).await?;
+    sqlx::query(
+        r#"
+        INSERT INTO emi_plans (id, user_id, account_id, name, purchase_amount, currency, annual_rate, installments, installment_amount,
+            total_interest, purchase_date, first_due_date, recurring_transaction_id, category, notes, created_at, updated_at)
+        VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?)
+        "#,
+    )
+    .bind(&plan.id)
+    .bind(&plan.user_id)
+    .bind(&plan.account_id)
+    .bind(&plan.name)
+    .bind(plan.purchase_amount)
+    .bind(&plan.currency)
+    .bind(plan.annual_rate)
+    .bind(plan.installments)
+    .bind(plan.installment_amount)
+    .bind(plan.total_interest)
+    .bind(format_db_datetime(plan.purchase_date))
+    .bind(format_db_datetime(plan.first_due_date))
+    .bind(&plan.recurring_transaction_id)
+    .bind(&plan.category)
+    .bind(&plan.notes)
+    .bind(format_db_datetime(plan.created_at))
+    .bind(format_db_datetime(plan.updated_at))
+    .execute(&mut tx)
+    .await?;
+    tx.commit().await?;
+
+    Ok(plan)
+}
+
+/// How many installments have posted, judged by where the recurring transaction has got to.
+/// Once it is deleted, installments due by now count as posted.
+pub async fn status(pool: &DbPool, plan: EmiPlan) -> Result<EmiPlanStatus> {
+    let dates = due_dates(&plan)?;
+    let posted_before = match &plan.recurring_transaction_id {
+        Some(id) => sqlx::query_scalar::<_, DateTime<Utc>>("SELECT next_due_date FROM recurring_transactions WHERE id = ?")
+            .bind(id)
+            .fetch_optional(pool)
+            .await?,
+        None => None,
+    };
+    let installments_posted = match posted_before {
+        Some(next_due_date) => dates.iter().filter(|&&date| date < next_due_date).count(),
+        None => dates.iter().filter(|&&date| date <= Utc::now()).count(),
+    };
+
+    let remaining = dates.len() - installments_posted;
+    Ok(EmiPlanStatus {
+        installments_posted: installments_posted as i64,
+        remaining_amount: plan.installment_amount * Decimal::from(remaining as u64),
+        next_due_date: if posted_before.is_some() { dates.get(installments_posted).copied() } else { None },
+        last_due_date: dates.last().copied().unwrap_or(plan.first_due_date),
+        plan,
+    })
+}
@@ -0,0 +1,87 @@
+use anyhow::{bail, Result};
+use axum::{
+    http::{header, HeaderMap, HeaderValue, StatusCode},
+    response::{IntoResponse, Response},
+};
+use sha2::{Digest, Sha256};
+
+use crate::services::database::{DbPool, VERSIONED_TABLES};
+use crate::services::history::EntityKind;
+use crate::services::households::{self, SHARED_ACCOUNT_IDS};
+use crate::utils::pagination::PageQuery;
+
+/// A weak ETag for the user's list of `kind`, cheap enough to check on every poll. It changes
+/// whenever a visible row is added, changed or deleted: row versions count every change and
+/// `deleted_records` keeps deletions. Compute it before reading the list, so a write in between
+/// only costs the client one extra download.
+pub async fn list_etag(pool: &DbPool, kind: EntityKind, user_id: &str) -> Result<String> {
+    if !VERSIONED_TABLES.contains(&kind.table()) {
+        bail!("{} lists have no ETag", kind.name());
+    }
+    let (filter, user_binds) = match kind {
+        EntityKind::Account => (households::visible_accounts_filter(), 2),
+        EntityKind::Transaction => (households::visible_rows_filter(), 2),
+        _ => ("user_id = ?".to_string(), 1),
+    };
+
+    let query = format!("SELECT COUNT(*), COALESCE(SUM(version), 0) FROM {} WHERE {}", kind.table(), filter);
+    let mut rows = sqlx::query_as::<_, (i64, i64)>(&query);
+    for _ in 0..user_binds {
+        rows = rows.bind(user_id);
+    }
+    let (count, versions) = rows.fetch_one(pool).await?;
+
+    let (deleted, last_deletion) = sqlx::query_as::<_, (i64, Option<String>)>(&format!(
+        "SELECT COUNT(*), MAX(deleted_at) FROM deleted_records WHERE entity_type = ? AND (user_id = ? OR account_id IN ({}))",
+        SHARED_ACCOUNT_IDS
+    ))
+    .bind(kind.name())
+    .bind(user_id)
+    .bind(user_id)
+    .fetch_one(pool)
+    .await?;
+
+    let fingerprint = format!(
+        "{}|{}|{}|{}|{}|{}",
+        kind.name(),
+        user_id,
+        count,
+        versions,
+        deleted,
+        last_deletion.unwrap_or_default()
+    );
+    Ok(format!("W/\"{}\"", &hex::encode(Sha256::digest(fingerprint.as_bytes()))[..32]))
+}
+
+/// `etag` narrowed to one page of the list, so a client holding several pages can't have one
+/// page's copy confirmed for another.
+pub fn for_page(etag: &str, page: &PageQuery) -> String {
+    format!("{}-{}-{}\"", etag.trim_end_matches('"'), page.offset(), page.limit())
+}
+
+/// Whether the request's `If-None-Match` already names `etag`. Compared weakly, as lists are
+/// tagged by content rather than bytes.
+pub fn is_fresh(headers: &HeaderMap, etag: &str) -> bool {
+    let opaque = |tag: &str| tag.trim().trim_start_matches("W/").to_string();
+    let etag = opaque(etag);
+    headers
+        .get_all(header::IF_NONE_MATCH)
+        .iter()
+        .filter_map(|value| value.to_str().ok())
+        .flat_map(|value| value.split(','))
+        .any(|tag| tag.trim() == "*" || opaque(tag) == etag)
+}
+
+/// Adds `etag` to a response.
+pub fn tagged(etag: &str, response: impl IntoResponse) -> Response {
+    let mut response = response.into_response();
+    if let Ok(value) = HeaderValue::from_str(etag) {
+        response.headers_mut().insert(header::ETAG, value);
+    }
+    response
+}
+
+/// 304 Not Modified, for a client whose copy of the list is current.
+pub fn not_modified(etag: &str) -> Response {
+    tagged(etag, StatusCode::NOT_MODIFIED)
+}
@@ -0,0 +1,120 @@
+use anyhow::Result;
+use chrono::Utc;
+use serde::Serialize;
+use serde_json::{json, Value};
+use uuid::Uuid;
+
+use crate::models::Event;
+use crate::services::database::DbPool;
+use crate::services::webhooks;
+use crate::utils::datetime::format_db_datetime;
+
+/// Schema version used for payloads unless a consumer pins an older one.
+pub const CURRENT_SCHEMA_VERSION: u32 = 2;
+
+/// Every payload schema version that can still be rendered.
+pub const SUPPORTED_SCHEMA_VERSIONS: &[u32] = &[1, 2];
+
+pub fn is_supported_version(version: u32) -> bool {
+    SUPPORTED_SCHEMA_VERSIONS.contains(&version)
+}
+
+/// Describes how each schema version lays out an event payload.
+pub fn schema_descriptions() -> Value {
+    json!([
+        {
+            "version": 1,
+            "fields": ["schemaVersion", "id", "type", "createdAt", "data"],
+            "description": "Flat payload; `data` is the entity snapshot."
+        },
+        {
+            "version": 2,
+            "fields": ["schemaVersion", "id", "type", "occurredAt", "sequence", "entity", "data.object"],
+            "description": "Adds `entity` {type, id} and the delivery `sequence`; the snapshot moves to `data.object`."
+        }
+    ])
+}
+
+/// Appends an event to the log and queues it for the user's webhooks. The snapshot is stored
+/// unversioned and rendered per consumer.
+pub async fn record_event<T: Serialize>(
+    pool: &DbPool,
+    user_id: &str,
+    event_type: &str,
+    entity_type: &str,
+    entity_id: &str,
+    data: &T,
+) -> Result<()> {
+    let event_id = Uuid::new_v4().to_string();
+    sqlx::query(
+        "INSERT INTO events (id, user_id, event_type, entity_type, entity_id, data, created_at) VALUES (?, ?, ?, ?, ?, ?, ?)"
+    )
+    .bind(&event_id)
+    .bind(user_id)
+    .bind(event_type)
+    .bind(entity_type)
+    .bind(entity_id)
+    .bind(serde_json::to_string(data)?)
+    .bind(format_db_datetime(Utc::now()))
+    .execute(pool)
+    .await?;
+
+    webhooks::enqueue_deliveries(pool, user_id, &event_id, event_type).await?;
+
+    Ok(())
+}
+
+/// Records an event without failing the caller; event delivery must never break a write.
+pub async fn emit<T: Serialize>(
+    pool: &DbPool,
+    user_id: &str,
+    event_type: &str,
+    entity_type: &str,
+    entity_id: &str,
+    data: &T,
+) {
+    if let Err(e) = record_event(pool, user_id, event_type, entity_type, entity_id, data).await {
+        tracing::error!("Failed to record {} event for {}: {}", event_type, entity_id, e);
+    }
+}
+
+/// Renders a logged event in the requested payload schema version.
+pub fn render_payload(event: &Event, version: u32) -> Value {
+    let data: Value = serde_json::from_str(&event.data).unwrap_or(Value::Null);
+    match version {
+        1 => json!({
+            "schemaVersion": 1,
+            "id": event.id,
+            "type": event.event_type,
+            "createdAt": event.created_at,
+            "data": data
+        }),
+        _ => json!({
+            "schemaVersion": 2,
+            "id": event.id,
+            "type": event.event_type,
+            "occurredAt": event.created_at,
+            "sequence": event.sequence,
+            "entity": {
+                "type": event.entity_type,
+                "id": event.entity_id
+            },
+            "data": {
+                "object": data
+            }
+        }),
+    }
+}
+
+/// Loads a user's most recent events, newest first.
+pub async fn recent_events(pool: &DbPool, user_id: &str, limit: i64) -> Result<Vec<Event>> {
+    let events = sqlx::query_as::<_, Event>(
+        "SELECT * FROM events WHERE user_id = ? ORDER BY sequence DESC LIMIT ?",
+    )
+    .bind(user_id)
+    .bind(limit)
+    .fetch_all(pool)
+    .await?;
+
+    Ok(events)
+}
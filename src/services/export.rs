@@ -0,0 +1,678 @@
+use std::collections::{BTreeMap, BTreeSet};
+
+use anyhow::Result;
+use chrono::{DateTime, Duration, NaiveDate, Utc};
+use hyper::body::{Body, Bytes};
+use sqlx::sqlite::SqliteRow;
+use sqlx::Row;
+
+use crate::models::{Transaction, TransactionType};
+use crate::services::database::DbPool;
+use crate::utils::money::Money;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum ExportFormat {
+    Ledger,
+    Beancount,
+    Ofx,
+    Qif,
+}
+
+impl ExportFormat {
+    pub fn parse(value: &str) -> Option<Self> {
+        match value.to_lowercase().as_str() {
+            "ledger" | "hledger" => Some(Self::Ledger),
+            "beancount" => Some(Self::Beancount),
+            "ofx" | "qfx" => Some(Self::Ofx),
+            "qif" => Some(Self::Qif),
+            _ => None,
+        }
+    }
+
+    pub fn file_extension(&self) -> &'static str {
+        match self {
+            Self::Ledger => "ledger",
+            Self::Beancount => "beancount",
+            Self::Ofx => "ofx",
+            Self::Qif => "qif",
+        }
+    }
+
+    pub fn content_type(&self) -> &'static str {
+        match self {
+            Self::Ledger | Self::Beancount => "text/plain; charset=utf-8",
+            Self::Ofx => "application/x-ofx",
+            Self::Qif => "application/qif",
+        }
+    }
+
+    /// Statement formats describe one account at a time and can be limited to a single account.
+    pub fn is_statement(&self) -> bool {
+        matches!(self, Self::Ofx | Self::Qif)
+    }
+}
+
+struct LedgerAccount {
+    name: String,
+    balance: Money,
+    currency: String,
+    created_at: DateTime<Utc>,
+}
+
+const OPENING_BALANCES: &str = "Equity:Opening-Balances";
+const TRANSFERS: &str = "Equity:Transfers";
+
+/// Turns a free-form name into an account path component valid in both ledger and beancount.
+fn account_component(name: &str) -> String {
+    let words: Vec<String> = name
+        .split(|c: char| !c.is_alphanumeric())
+        .filter(|w| !w.is_empty())
+        .map(|w| {
+            let mut chars = w.chars();
+            match chars.next() {
+                Some(first) => first.to_uppercase().chain(chars).collect(),
+                None => String::new(),
+            }
+        })
+        .collect();
+
+    if words.is_empty() {
+        "Unnamed".to_string()
+    } else {
+        let joined = words.join("-");
+        if joined.starts_with(|c: char| c.is_ascii_uppercase() || c.is_ascii_digit()) {
+            joined
+        } else {
+            format!("X-{}", joined)
+        }
+    }
+}
+
+fn asset_account_path(name: &str, account_type: &str) -> String {
+    let (root, group) = match account_type.replace('_', "").as_str() {
+        "creditcard" => ("Liabilities", "Credit-Card"),
+        "bank" => ("Assets", "Bank"),
+        "mobilebanking" => ("Assets", "Mobile-Banking"),
+        "investment" => ("Assets", "Investment"),
+        "savings" => ("Assets", "Savings"),
+        "wallet" => ("Assets", "Wallet"),
+        _ => ("Assets", "Cash"),
+    };
+    format!("{}:{}:{}", root, group, account_component(name))
+}
+
+fn category_account_path(transaction: &Transaction) -> String {
+    let category = account_component(transaction.category.as_deref().unwrap_or("Uncategorized"));
+    match transaction.transaction_type {
+        TransactionType::Income => format!("Income:{}", category),
+        TransactionType::Expense => format!("Expenses:{}", category),
+        TransactionType::Transfer => TRANSFERS.to_string(),
+    }
+}
+
+fn quote(text: &str) -> String {
+    text.replace('\\', "\\\\").replace('"', "\\\"")
+}
+
+/// Signed effect of a transaction on its account; transfers are treated as outgoing.
+pub fn account_delta(transaction: &Transaction) -> Money {
+    match transaction.transaction_type {
+        TransactionType::Income => transaction.amount,
+        TransactionType::Expense | TransactionType::Transfer => -transaction.amount,
+    }
+}
+
+struct Posting {
+    account: String,
+    amount: Money,
+    currency: String,
+}
+
+struct Entry {
+    date: NaiveDate,
+    payee: String,
+    postings: Vec<Posting>,
+}
+
+/// Builds a double-entry plain-text accounting file from a user's accounts and transactions.
+/// Each account gets an opening balance so the closing balance matches the stored one.
+pub async fn export_user(pool: &DbPool, user_id: &str, format: ExportFormat) -> Result<String> {
+    if format.is_statement() {
+        return export_statements(pool, user_id, format, None)
+            .await
+            .map(Option::unwrap_or_default);
+    }
+
+    let account_rows = sqlx::query(
+        "SELECT id, name, account_type, balance, currency, created_at FROM accounts WHERE user_id = ? ORDER BY created_at ASC"
+    )
+    .bind(user_id)
+    .fetch_all(pool)
+    .await?;
+
+    let mut accounts = BTreeMap::new();
+    for row in account_rows {
+        let name = row.get::<String, _>("name");
+        let account_type = row.get::<String, _>("account_type");
+        accounts.insert(
+            row.get::<String, _>("id"),
+            LedgerAccount {
+                name: asset_account_path(&name, &account_type),
+                balance: row.get::<Money, _>("balance"),
+                currency: row.get::<String, _>("currency"),
+                created_at: row.get::<DateTime<Utc>, _>("created_at"),
+            },
+        );
+    }
+
+    let transactions = sqlx::query_as::<_, Transaction>(
+        "SELECT * FROM transactions WHERE user_id = ? ORDER BY date ASC, created_at ASC"
+    )
+    .bind(user_id)
+    .fetch_all(pool)
+    .await?;
+
+    let mut opened: BTreeMap<String, (NaiveDate, BTreeSet<String>)> = BTreeMap::new();
+    let mut open_account = |name: &str, date: NaiveDate, currency: &str| {
+        let entry = opened.entry(name.to_string()).or_insert_with(|| (date, BTreeSet::new()));
+        entry.0 = entry.0.min(date);
+        entry.1.insert(currency.to_string());
+    };
+
+    let mut entries = Vec::new();
+    for (account_id, account) in &accounts {
+        let account_transactions: Vec<&Transaction> = transactions
+            .iter()
+            .filter(|t| &t.account_id == account_id)
+            .collect();
+        let net = account_transactions
+            .iter()
+            .map(|t| account_delta(t))
+            .sum::<Money>();
+        let first_date = account_transactions
+            .iter()
+            .map(|t| t.date)
+            .chain(std::iter::once(account.created_at))
+            .min()
+            .unwrap_or(account.created_at)
+            .date_naive();
+
+        open_account(&account.name, first_date, &account.currency);
+
+        let opening = account.balance - net;
+        if !opening.round_dp(2).is_zero() {
+            open_account(OPENING_BALANCES, first_date, &account.currency);
+            entries.push(Entry {
+                date: first_date,
+                payee: "Opening balance".to_string(),
+                postings: vec![
+                    Posting { account: account.name.clone(), amount: opening, currency: account.currency.clone() },
+                    Posting { account: OPENING_BALANCES.to_string(), amount: -opening, currency: account.currency.clone() },
+                ],
+            });
+        }
+    }
+
+    for transaction in &transactions {
+        let Some(account) = accounts.get(&transaction.account_id) else {
+            continue;
+        };
+        let date = transaction.date.date_naive();
+        let counter = category_account_path(transaction);
+        open_account(&counter, date, &transaction.currency);
+
+        let delta = account_delta(transaction);
+        let payee = transaction
+            .description
+            .clone()
+            .or_else(|| transaction.category.clone())
+            .unwrap_or_else(|| "Transaction".to_string());
+        entries.push(Entry {
+            date,
+            payee,
+            postings: vec![
+                Posting { account: counter, amount: -delta, currency: transaction.currency.clone() },
+                Posting { account: account.name.clone(), amount: delta, currency: transaction.currency.clone() },
+            ],
+        });
+    }
+
+    entries.sort_by_key(|e| e.date);
+
+    let mut output = String::new();
+    match format {
+        ExportFormat::Beancount => {
+            output.push_str(";; Exported from Personal Manager\n\n");
+            for (name, (date, currencies)) in &opened {
+                let currencies: Vec<&str> = currencies.iter().map(|c| c.as_str()).collect();
+                output.push_str(&format!("{} open {} {}\n", date.format("%Y-%m-%d"), name, currencies.join(",")));
+            }
+            output.push('\n');
+            for entry in &entries {
+                output.push_str(&format!("{} * \"{}\"\n", entry.date.format("%Y-%m-%d"), quote(&entry.payee)));
+                for posting in &entry.postings {
+                    output.push_str(&format!("  {:<48} {:>12.2} {}\n", posting.account, posting.amount, posting.currency));
+                }
+                output.push('\n');
+            }
+        }
+        ExportFormat::Ofx | ExportFormat::Qif => {}
+        ExportFormat::Ledger => {
+            output.push_str("; Exported from Personal Manager\n\n");
+            for name in opened.keys() {
+                output.push_str(&format!("account {}\n", name));
+            }
+            output.push('\n');
+            for entry in &entries {
+                output.push_str(&format!("{} {}\n", entry.date.format("%Y/%m/%d"), entry.payee));
+                for posting in &entry.postings {
+                    output.push_str(&format!("    {:<48} {:>12.2} {}\n", posting.account, posting.amount, posting.currency));
+                }
+                output.push('\n');
+            }
+        }
+    }
+
+    Ok(output)
+}
+
+struct StatementAccount {
+    id: String,
+    name: String,
+    account_type: String,
+    balance: Money,
+    currency: String,
+    transactions: Vec<Transaction>,
+}
+
+/// Keeps free text on one line, as QIF fields are line-delimited.
+fn single_line(text: &str) -> String {
+    text.split(['\r', '\n']).filter(|part| !part.is_empty()).collect::<Vec<_>>().join(" ")
+}
+
+fn ofx_text(text: &str, max_len: usize) -> String {
+    single_line(text)
+        .chars()
+        .take(max_len)
+        .collect::<String>()
+        .replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+}
+
+fn ofx_date(date: DateTime<Utc>) -> String {
+    date.format("%Y%m%d%H%M%S").to_string()
+}
+
+fn qif_account_type(account_type: &str) -> &'static str {
+    match account_type.replace('_', "").as_str() {
+        "creditcard" => "CCard",
+        "cash" | "wallet" => "Cash",
+        "investment" => "Oth A",
+        _ => "Bank",
+    }
+}
+
+fn write_qif(accounts: &[StatementAccount]) -> String {
+    let mut output = String::new();
+    for account in accounts {
+        let account_type = qif_account_type(&account.account_type);
+        output.push_str(&format!("!Account\nN{}\nT{}\n^\n", single_line(&account.name), account_type));
+        output.push_str(&format!("!Type:{}\n", account_type));
+        for transaction in &account.transactions {
+            output.push_str(&format!("D{}\n", transaction.date.format("%m/%d/%Y")));
+            output.push_str(&format!("T{:.2}\n", account_delta(transaction)));
+            if let Some(description) = &transaction.description {
+                output.push_str(&format!("P{}\n", single_line(description)));
+            }
+            match transaction.transaction_type {
+                // Brackets mark a transfer; the other side is not tracked, so name the generic account.
+                TransactionType::Transfer => output.push_str("L[Transfers]\n"),
+                _ => {
+                    if let Some(category) = &transaction.category {
+                        output.push_str(&format!("L{}\n", single_line(category)));
+                    }
+                }
+            }
+            output.push_str("^\n");
+        }
+    }
+    output
+}
+
+fn write_ofx(accounts: &[StatementAccount]) -> String {
+    let now = ofx_date(Utc::now());
+    let status = "<STATUS><CODE>0<SEVERITY>INFO</STATUS>";
+    let (mut bank, mut cards) = (String::new(), String::new());
+
+    for account in accounts {
+        let start = account.transactions.first().map(|t| ofx_date(t.date)).unwrap_or_else(|| now.clone());
+        let end = account.transactions.last().map(|t| ofx_date(t.date)).unwrap_or_else(|| now.clone());
+
+        let mut list = format!("<BANKTRANLIST><DTSTART>{}<DTEND>{}\n", start, end);
+        for transaction in &account.transactions {
+            let trntype = match transaction.transaction_type {
+                TransactionType::Income => "CREDIT",
+                TransactionType::Expense => "DEBIT",
+                TransactionType::Transfer => "XFER",
+            };
+            list.push_str(&format!(
+                "<STMTTRN><TRNTYPE>{}<DTPOSTED>{}<TRNAMT>{:.2}<FITID>{}",
+                trntype,
+                ofx_date(transaction.date),
+                account_delta(transaction),
+                transaction.id
+            ));
+            if let Some(description) = transaction.description.as_deref().or(transaction.category.as_deref()) {
+                list.push_str(&format!("<NAME>{}", ofx_text(description, 32)));
+            }
+            if let Some(category) = &transaction.category {
+                list.push_str(&format!("<MEMO>{}", ofx_text(category, 255)));
+            }
+            list.push_str("</STMTTRN>\n");
+        }
+        list.push_str("</BANKTRANLIST>\n");
+        let balance = format!("<LEDGERBAL><BALAMT>{:.2}<DTASOF>{}</LEDGERBAL>\n", account.balance, now);
+
+        if account.account_type.replace('_', "") == "creditcard" {
+            cards.push_str(&format!(
+                "<CCSTMTTRNRS><TRNUID>{id}{status}\n<CCSTMTRS><CURDEF>{currency}<CCACCTFROM><ACCTID>{id}</CCACCTFROM>\n{list}{balance}</CCSTMTRS></CCSTMTTRNRS>\n",
+                id = account.id,
+                status = status,
+                currency = account.currency,
+                list = list,
+                balance = balance,
+            ));
+        } else {
+            let acct_type = if account.account_type == "savings" { "SAVINGS" } else { "CHECKING" };
+            bank.push_str(&format!(
+                "<STMTTRNRS><TRNUID>{id}{status}\n<STMTRS><CURDEF>{currency}<BANKACCTFROM><BANKID>PERSONALMANAGER<ACCTID>{id}<ACCTTYPE>{acct_type}</BANKACCTFROM>\n{list}{balance}</STMTRS></STMTTRNRS>\n",
+                id = account.id,
+                status = status,
+                currency = account.currency,
+                acct_type = acct_type,
+                list = list,
+                balance = balance,
+            ));
+        }
+    }
+
+    let mut output = String::from(
+        "OFXHEADER:100\nDATA:OFXSGML\nVERSION:102\nSECURITY:NONE\nENCODING:UTF-8\nCHARSET:NONE\nCOMPRESSION:NONE\nOLDFILEUID:NONE\nNEWFILEUID:NONE\n\n",
+    );
+    output.push_str("<OFX>\n");
+    output.push_str(&format!(
+        "<SIGNONMSGSRSV1><SONRS>{}<DTSERVER>{}<LANGUAGE>ENG</SONRS></SIGNONMSGSRSV1>\n",
+        status, now
+    ));
+    if !bank.is_empty() {
+        output.push_str(&format!("<BANKMSGSRSV1>\n{}</BANKMSGSRSV1>\n", bank));
+    }
+    if !cards.is_empty() {
+        output.push_str(&format!("<CREDITCARDMSGSRSV1>\n{}</CREDITCARDMSGSRSV1>\n", cards));
+    }
+    output.push_str("</OFX>\n");
+    output
+}
+
+/// Writes an OFX or QIF file with one statement per account, or only `account_id` when given.
+/// Returns `None` if that account does not belong to the user.
+pub async fn export_statements(
+    pool: &DbPool,
+    user_id: &str,
+    format: ExportFormat,
+    account_id: Option<&str>,
+) -> Result<Option<String>> {
+    let account_rows = sqlx::query(
+        "SELECT id, name, account_type, balance, currency FROM accounts WHERE user_id = ? AND (? IS NULL OR id = ?) ORDER BY created_at ASC"
+    )
+    .bind(user_id)
+    .bind(account_id)
+    .bind(account_id)
+    .fetch_all(pool)
+    .await?;
+    if account_id.is_some() && account_rows.is_empty() {
+        return Ok(None);
+    }
+
+    let mut accounts = Vec::new();
+    for row in account_rows {
+        let id = row.get::<String, _>("id");
+        let transactions = sqlx::query_as::<_, Transaction>(
+            "SELECT * FROM transactions WHERE user_id = ? AND account_id = ? ORDER BY date ASC, created_at ASC"
+        )
+        .bind(user_id)
+        .bind(&id)
+        .fetch_all(pool)
+        .await?;
+        accounts.push(StatementAccount {
+            id,
+            name: row.get("name"),
+            account_type: row.get("account_type"),
+            balance: row.get("balance"),
+            currency: row.get("currency"),
+            transactions,
+        });
+    }
+
+    Ok(Some(match format {
+        ExportFormat::Qif => write_qif(&accounts),
+        _ => write_ofx(&accounts),
+    }))
+}
+
+/// A column of the transactions CSV export.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum CsvColumn {
+    Id,
+    Date,
+    Type,
+    Amount,
+    Currency,
+    Category,
+    Description,
+    Account,
+    AccountId,
+    CreatedAt,
+}
+
+impl CsvColumn {
+    pub const DEFAULT: [CsvColumn; 7] = [
+        Self::Date,
+        Self::Type,
+        Self::Amount,
+        Self::Currency,
+        Self::Category,
+        Self::Description,
+        Self::Account,
+    ];
+
+    pub fn parse(value: &str) -> Option<Self> {
+        match value.trim().to_lowercase().as_str() {
+            "id" => Some(Self::Id),
+            "date" => Some(Self::Date),
+            "type" | "transaction_type" => Some(Self::Type),
+            "amount" => Some(Self::Amount),
+            "currency" => Some(Self::Currency),
+            "category" => Some(Self::Category),
+            "description" => Some(Self::Description),
+            "account" | "account_name" => Some(Self::Account),
+            "account_id" => Some(Self::AccountId),
+            "created_at" => Some(Self::CreatedAt),
+            _ => None,
+        }
+    }
+
+    pub fn header(&self) -> &'static str {
+        match self {
+            Self::Id => "id",
+            Self::Date => "date",
+            Self::Type => "type",
+            Self::Amount => "amount",
+            Self::Currency => "currency",
+            Self::Category => "category",
+            Self::Description => "description",
+            Self::Account => "account",
+            Self::AccountId => "account_id",
+            Self::CreatedAt => "created_at",
+        }
+    }
+
+    /// Whether the column holds free text a user typed, which spreadsheets might read as a formula.
+    fn is_free_text(&self) -> bool {
+        matches!(self, Self::Category | Self::Description | Self::Account)
+    }
+
+    fn value(&self, row: &SqliteRow) -> String {
+        let text = |name: &str| row.get::<Option<String>, _>(name).unwrap_or_default();
+        match self {
+            Self::Id => text("id"),
+            Self::Date => text("date"),
+            Self::Type => text("transaction_type"),
+            Self::Amount => row.get::<Money, _>("amount").to_string(),
+            Self::Currency => text("currency"),
+            Self::Category => text("category"),
+            Self::Description => text("description"),
+            Self::Account => text("account_name"),
+            Self::AccountId => text("account_id"),
+            Self::CreatedAt => text("created_at"),
+        }
+    }
+}
+
+/// Parses a comma-separated column list; on failure returns the first unknown name.
+pub fn parse_columns(spec: &str) -> Result<Vec<CsvColumn>, String> {
+    let columns = spec
+        .split(',')
+        .filter(|name| !name.trim().is_empty())
+        .map(|name| CsvColumn::parse(name).ok_or_else(|| name.trim().to_string()))
+        .collect::<Result<Vec<_>, _>>()?;
+
+    if columns.is_empty() {
+        Ok(CsvColumn::DEFAULT.to_vec())
+    } else {
+        Ok(columns)
+    }
+}
+
+/// Prefixes text a spreadsheet would evaluate as a formula with a quote so it stays literal.
+fn neutralize_formula(value: String) -> String {
+    if value.starts_with(['=', '+', '-', '@', '\t', '\r']) {
+        format!("'{}", value)
+    } else {
+        value
+    }
+}
+
+const CSV_PAGE_SIZE: i64 = 500;
+
+fn csv_chunk<I>(records: I) -> Result<Bytes>
+where
+    I: IntoIterator<Item = Vec<String>>,
+{
+    let mut writer = csv::Writer::from_writer(Vec::new());
+    for record in records {
+        writer.write_record(&record)?;
+    }
+    Ok(Bytes::from(writer.into_inner().map_err(|e| e.into_error())?))
+}
+
+/// Streams a user's transactions as CSV, oldest first, optionally limited to `from..=to`.
+/// Rows are read a page at a time so large histories are never held in memory.
+pub fn transactions_csv(
+    pool: DbPool,
+    user_id: String,
+    from: Option<NaiveDate>,
+    to: Option<NaiveDate>,
+    columns: Vec<CsvColumn>,
+) -> Body {
+    let (mut sender, body) = Body::channel();
+    let from = from.map(|d| d.format("%Y-%m-%d 00:00:00").to_string());
+    let to = to.map(|d| (d + Duration::days(1)).format("%Y-%m-%d 00:00:00").to_string());
+
+    tokio::spawn(async move {
+        let header = columns.iter().map(|c| c.header().to_string()).collect();
+        let Ok(chunk) = csv_chunk([header]) else {
+            sender.abort();
+            return;
+        };
+        if sender.send_data(chunk).await.is_err() {
+            return;
+        }
+
+        // Keyset pagination on (date, id); an empty cursor sorts before every row.
+        let (mut last_date, mut last_id) = (String::new(), String::new());
+        loop {
+            let page = sqlx::query(
+                r#"
+                SELECT t.id, t.date, t.transaction_type, t.amount, t.currency, t.category, t.description,
+                       t.account_id, t.created_at, a.name AS account_name
+                FROM transactions t
+                LEFT JOIN accounts a ON a.id = t.account_id
+                WHERE t.user_id = ?
+                  AND (? IS NULL OR t.date >= ?) AND (? IS NULL OR t.date < ?)
+                  AND (t.date > ? OR (t.date = ? AND t.id > ?))
+                ORDER BY t.date, t.id
+                LIMIT ?
+                "#,
+            )
+            .bind(&user_id)
+            .bind(&from)
+            .bind(&from)
+            .bind(&to)
+            .bind(&to)
+            .bind(&last_date)
+            .bind(&last_date)
+            .bind(&last_id)
+            .bind(CSV_PAGE_SIZE)
+            .fetch_all(&pool)
+            .await;
+
+            let rows = match page {
+                Ok(rows) => rows,
+                Err(e) => {
+                    // Aborting makes the client see a failed download rather than a silently truncated file.
+                    tracing::error!("Failed to read transactions for CSV export: {}", e);
+                    sender.abort();
+                    return;
+                }
+            };
+            let Some(last) = rows.last() else {
+                return;
+            };
+            last_date = last.get("date");
+            last_id = last.get("id");
+
+            let records = rows.iter().map(|row| {
+                columns
+                    .iter()
+                    .map(|column| {
+                        let value = column.value(row);
+                        if column.is_free_text() {
+                            neutralize_formula(value)
+                        } else {
+                            value
+                        }
+                    })
+                    .collect()
+            });
+            let chunk = match csv_chunk(records) {
+                Ok(chunk) => chunk,
+                Err(e) => {
+                    tracing::error!("Failed to write CSV export: {}", e);
+                    sender.abort();
+                    return;
+                }
+            };
+            if sender.send_data(chunk).await.is_err() {
+                tracing::debug!("CSV export for user {} cancelled by client", user_id);
+                return;
+            }
+            if (rows.len() as i64) < CSV_PAGE_SIZE {
+                return;
+            }
+        }
+    });
+
+    body
+}
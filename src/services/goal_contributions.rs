@@ -0,0 +1,73 @@
+use anyhow::Result;
+use sqlx::{Row, SqliteConnection};
+
+use crate::models::SavingsGoalContribution;
+use crate::utils::datetime::format_db_datetime;
+use crate::utils::money::Money;
+
+/// Outcome of applying a contribution to a goal.
+pub enum ContributionOutcome {
+    Applied { current_amount: Money, is_completed: bool },
+    GoalNotFound,
+    /// A withdrawal larger than the saved amount.
+    InsufficientSavings,
+}
+
+/// Stores a contribution and bumps the goal's `current_amount` in the caller's transaction,
+/// marking the goal completed once the target is reached.
+pub async fn apply_contribution(
+    conn: &mut SqliteConnection,
+    contribution: &SavingsGoalContribution,
+) -> Result<ContributionOutcome> {
+    let goal = sqlx::query("SELECT current_amount FROM savings_goals WHERE id = ? AND user_id = ?")
+        .bind(&contribution.goal_id)
+        .bind(&contribution.user_id)
+        .fetch_optional(&mut *conn)
+        .await?;
+
+    let current_amount = match goal {
+        Some(row) => row.get::<Money, _>("current_amount"),
+        None => return Ok(ContributionOutcome::GoalNotFound),
+    };
+    if (current_amount + contribution.amount).is_negative() {
+        return Ok(ContributionOutcome::InsufficientSavings);
+    }
+
+    let now = format_db_datetime(contribution.created_at);
+
+    sqlx::query(
+        "UPDATE savings_goals SET current_amount = current_amount + ?, is_completed = CASE WHEN current_amount + ? >= target_amount THEN TRUE ELSE is_completed END, updated_at = ? WHERE id = ? AND user_id = ?"
+    )
+    .bind(contribution.amount)
+    .bind(contribution.amount)
+    .bind(&now)
+    .bind(&contribution.goal_id)
+    .bind(&contribution.user_id)
+    .execute(&mut *conn)
+    .await?;
+
+    sqlx::query(
+        "INSERT INTO savings_goal_contributions (id, user_id, goal_id, amount, contribution_date, note, transaction_id, source, created_at) VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?)"
+    )
+    .bind(&contribution.id)
+    .bind(&contribution.user_id)
+    .bind(&contribution.goal_id)
+    .bind(contribution.amount)
+    .bind(format_db_datetime(contribution.contribution_date))
+    .bind(&contribution.note)
+    .bind(&contribution.transaction_id)
+    .bind(&contribution.source)
+    .bind(&now)
+    .execute(&mut *conn)
+    .await?;
+
+    let goal = sqlx::query("SELECT current_amount, is_completed FROM savings_goals WHERE id = ?")
+        .bind(&contribution.goal_id)
+        .fetch_one(&mut *conn)
+        .await?;
+
+    Ok(ContributionOutcome::Applied {
+        current_amount: goal.get::<Money, _>("current_amount"),
+        is_completed: goal.get::<bool, _>("is_completed"),
+    })
+}
@@ -0,0 +1,391 @@
+use anyhow::{anyhow, bail, Result};
+use chrono::Utc;
+use serde::Serialize;
+use serde_json::{Map, Value};
+use sqlx::sqlite::SqliteRow;
+use sqlx::{Column, Row};
+use uuid::Uuid;
+
+use crate::middleware::device::ClientDevice;
+use crate::services::database::{money_columns, DbPool};
+use crate::services::live;
+use crate::utils::datetime::format_db_datetime;
+use crate::utils::money::Money;
+
+/// Entities whose changes are versioned.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum EntityKind {
+    Transaction,
+    Account,
+    Budget,
+    SavingsGoal,
+    Loan,
+    Liability,
+    RecurringTransaction,
+}
+
+impl EntityKind {
+    /// Maps the collection segment of an `/api/{entity}/...` path.
+    pub fn from_path_segment(segment: &str) -> Option<Self> {
+        match segment {
+            "transactions" => Some(Self::Transaction),
+            "accounts" => Some(Self::Account),
+            "budgets" => Some(Self::Budget),
+            "savings-goals" => Some(Self::SavingsGoal),
+            "loans" => Some(Self::Loan),
+            "liabilities" => Some(Self::Liability),
+            "recurring-transactions" => Some(Self::RecurringTransaction),
+            _ => None,
+        }
+    }
+
+    pub fn table(&self) -> &'static str {
+        match self {
+            Self::Transaction => "transactions",
+            Self::Account => "accounts",
+            Self::Budget => "budgets",
+            Self::SavingsGoal => "savings_goals",
+            Self::Loan => "loans",
+            Self::Liability => "liabilities",
+            Self::RecurringTransaction => "recurring_transactions",
+        }
+    }
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            Self::Transaction => "transaction",
+            Self::Account => "account",
+            Self::Budget => "budget",
+            Self::SavingsGoal => "savings_goal",
+            Self::Loan => "loan",
+            Self::Liability => "liability",
+            Self::RecurringTransaction => "recurring_transaction",
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct FieldChange {
+    pub field: String,
+    pub from: Value,
+    pub to: Value,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct EntityVersion {
+    pub version: i64,
+    pub action: String,
+    #[serde(rename = "userId")]
+    pub user_id: String,
+    pub device: String,
+    #[serde(rename = "createdAt")]
+    pub created_at: String,
+    pub changes: Vec<FieldChange>,
+    pub snapshot: Value,
+}
+
+/// Fields that change on every write and would only add noise to diffs.
+const IGNORED_FIELDS: &[&str] = &["updated_at"];
+
+/// Converts a row into a JSON object keyed by column name, whatever the table. Money columns
+/// hold scaled integers, so they are read back as amounts.
+fn row_to_json(table: &str, row: &SqliteRow) -> Value {
+    let money = money_columns(table);
+    let mut object = Map::new();
+    for (index, column) in row.columns().iter().enumerate() {
+        let value = if money.contains(&column.name()) {
+            row.try_get::<Option<Money>, _>(index)
+                .ok()
+                .flatten()
+                .map(|amount| serde_json::json!(amount))
+                .unwrap_or(Value::Null)
+        } else if let Ok(value) = row.try_get::<Option<i64>, _>(index) {
+            value.map(Value::from).unwrap_or(Value::Null)
+        } else if let Ok(value) = row.try_get::<Option<f64>, _>(index) {
+            value.map(Value::from).unwrap_or(Value::Null)
+        } else if let Ok(value) = row.try_get::<Option<String>, _>(index) {
+            value.map(Value::from).unwrap_or(Value::Null)
+        } else {
+            Value::Null
+        };
+        object.insert(column.name().to_string(), value);
+    }
+    Value::Object(object)
+}
+
+/// Current state of an entity as stored, or `None` if it doesn't exist for this user.
+pub async fn snapshot(pool: &DbPool, kind: EntityKind, id: &str, user_id: &str) -> Option<Value> {
+    let query = format!("SELECT * FROM {} WHERE id = ? AND user_id = ?", kind.table());
+    match sqlx::query(&query).bind(id).bind(user_id).fetch_optional(pool).await {
+        Ok(row) => row.map(|row| row_to_json(kind.table(), &row)),
+        Err(e) => {
+            tracing::error!("Failed to snapshot {} {}: {}", kind.name(), id, e);
+            None
+        }
+    }
+}
+
+async fn insert_version(
+    pool: &DbPool,
+    kind: EntityKind,
+    id: &str,
+    user_id: &str,
+    action: &str,
+    snapshot: Option<&Value>,
+    device: &str,
+) -> Result<()> {
+    sqlx::query(
+        r#"
+        INSERT INTO entity_versions (id, user_id, entity_type, entity_id, version, action, snapshot, device, created_at)
+        SELECT ?, ?, ?, ?, COALESCE(MAX(version), 0) + 1, ?, ?, ?, ?
+        FROM entity_versions WHERE entity_type = ? AND entity_id = ?
+        "#,
+    )
+    .bind(Uuid::new_v4().to_string())
+    .bind(user_id)
+    .bind(kind.name())
+    .bind(id)
+    .bind(action)
+    .bind(snapshot.map(|s| s.to_string()))
+    .bind(device)
+    .bind(format_db_datetime(Utc::now()))
+    .bind(kind.name())
+    .bind(id)
+    .execute(pool)
+    .await?;
+
+    Ok(())
+}
+
+async fn try_record(
+    pool: &DbPool,
+    kind: EntityKind,
+    id: &str,
+    user_id: &str,
+    action: &str,
+    before: Option<Value>,
+    device: &str,
+) -> Result<()> {
+    // Entities written before history existed get their prior state as a baseline version.
+    if let Some(before) = &before {
+        let has_history = sqlx::query_scalar::<_, i64>(
+            "SELECT COUNT(*) FROM entity_versions WHERE entity_type = ? AND entity_id = ?",
+        )
+        .bind(kind.name())
+        .bind(id)
+        .fetch_one(pool)
+        .await?
+            > 0;
+        if !has_history {
+            insert_version(pool, kind, id, user_id, "baseline", Some(before), device).await?;
+        }
+    }
+
+    let after = match action {
+        "deleted" => None,
+        _ => snapshot(pool, kind, id, user_id).await,
+    };
+    insert_version(pool, kind, id, user_id, action, after.as_ref(), device).await
+}
+
+/// Records a new version after a write and pushes it to connected clients. `before` is the
+/// state read prior to an update or delete. Failures are logged; history must never break the
+/// write itself.
+pub async fn record(
+    pool: &DbPool,
+    kind: EntityKind,
+    id: &str,
+    user_id: &str,
+    action: &str,
+    before: Option<Value>,
+    device: &ClientDevice,
+) {
+    live::publish(pool, kind, id, user_id, action, before.as_ref(), &device.0).await;
+    if let Err(e) = try_record(pool, kind, id, user_id, action, before, &device.0).await {
+        tracing::error!("Failed to record history for {} {}: {}", kind.name(), id, e);
+    }
+}
+
+/// Field-level differences between two snapshots.
+pub fn diff(before: &Value, after: &Value) -> Vec<FieldChange> {
+    let empty = Map::new();
+    let before = before.as_object().unwrap_or(&empty);
+    let after = after.as_object().unwrap_or(&empty);
+
+    let mut fields: Vec<&String> = before.keys().chain(after.keys()).collect();
+    fields.sort();
+    fields.dedup();
+
+    fields
+        .into_iter()
+        .filter(|field| !IGNORED_FIELDS.contains(&field.as_str()))
+        .filter_map(|field| {
+            let from = before.get(field).cloned().unwrap_or(Value::Null);
+            let to = after.get(field).cloned().unwrap_or(Value::Null);
+            (from != to).then(|| FieldChange {
+                field: field.clone(),
+                from,
+                to,
+            })
+        })
+        .collect()
+}
+
+/// `snake_case` column name as the API spells it, e.g. `targetAmount`.
+fn camel_case(column: &str) -> String {
+    let mut name = String::with_capacity(column.len());
+    let mut upper = false;
+    for c in column.chars() {
+        if c == '_' {
+            upper = true;
+        } else if upper {
+            name.push(c.to_ascii_uppercase());
+            upper = false;
+        } else {
+            name.push(c);
+        }
+    }
+    name
+}
+
+/// A snapshot with its columns renamed to the API's field names. Stored snapshots keep column
+/// names, since restores write them back as-is.
+fn camel_case_keys(snapshot: &Value) -> Value {
+    match snapshot {
+        Value::Object(columns) => Value::Object(columns.iter().map(|(column, value)| (camel_case(column), value.clone())).collect()),
+        other => other.clone(),
+    }
+}
+
+/// Every recorded version, oldest first, each with its diff against the previous state.
+pub async fn versions(pool: &DbPool, kind: EntityKind, id: &str, user_id: &str) -> Result<Vec<EntityVersion>> {
+    let rows = sqlx::query(
+        "SELECT version, action, user_id, device, snapshot, created_at FROM entity_versions WHERE entity_type = ? AND entity_id = ? AND user_id = ? ORDER BY version ASC",
+    )
+    .bind(kind.name())
+    .bind(id)
+    .bind(user_id)
+    .fetch_all(pool)
+    .await?;
+
+    let mut previous = Value::Null;
+    let mut versions = Vec::with_capacity(rows.len());
+    for row in rows {
+        let snapshot: Value = row
+            .get::<Option<String>, _>("snapshot")
+            .and_then(|s| serde_json::from_str(&s).ok())
+            .unwrap_or(Value::Null);
+        let changes = diff(&previous, &snapshot)
+            .into_iter()
+            .map(|change| FieldChange {
+                field: camel_case(&change.field),
+                ..change
+            })
+            .collect();
+        versions.push(EntityVersion {
+            version: row.get("version"),
+            action: row.get("action"),
+            user_id: row.get("user_id"),
+            device: row.get("device"),
+            created_at: row.get("created_at"),
+            changes,
+            snapshot: camel_case_keys(&snapshot),
+        });
+        previous = snapshot;
+    }
+
+    Ok(versions)
+}
+
+fn bind_value<'q>(
+    query: sqlx::query::Query<'q, sqlx::Sqlite, sqlx::sqlite::SqliteArguments<'q>>,
+    value: &Value,
+    is_money: bool,
+) -> sqlx::query::Query<'q, sqlx::Sqlite, sqlx::sqlite::SqliteArguments<'q>> {
+    if is_money {
+        if let Ok(amount) = serde_json::from_value::<Money>(value.clone()) {
+            return query.bind(amount);
+        }
+    }
+    match value {
+        Value::Null => query.bind(None::<String>),
+        Value::Bool(b) => query.bind(*b),
+        Value::Number(n) => match n.as_i64() {
+            Some(i) => query.bind(i),
+            None => query.bind(n.as_f64()),
+        },
+        Value::String(s) => query.bind(s.clone()),
+        other => query.bind(other.to_string()),
+    }
+}
+
+/// Puts an entity back to the state captured in `version`, re-creating it if it was deleted.
+pub async fn restore(
+    pool: &DbPool,
+    kind: EntityKind,
+    id: &str,
+    user_id: &str,
+    version: i64,
+    device: &ClientDevice,
+) -> Result<Option<Value>> {
+    let stored = sqlx::query_scalar::<_, Option<String>>(
+        "SELECT snapshot FROM entity_versions WHERE entity_type = ? AND entity_id = ? AND user_id = ? AND version = ?",
+    )
+    .bind(kind.name())
+    .bind(id)
+    .bind(user_id)
+    .bind(version)
+    .fetch_optional(pool)
+    .await?;
+
+    let Some(stored) = stored else {
+        return Ok(None);
+    };
+    let Some(stored) = stored else {
+        bail!("Version {} has no state to restore (it records a deletion)", version);
+    };
+    let mut target: Map<String, Value> = serde_json::from_str(&stored)?;
+    if target.contains_key("updated_at") {
+        target.insert("updated_at".to_string(), Value::from(format_db_datetime(Utc::now())));
+    }
+    let columns: Vec<&String> = target
+        .keys()
+        // The row's own version keeps counting up from where it is
+        .filter(|column| !["id", "user_id", "version"].contains(&column.as_str()))
+        .collect();
+    if columns
+        .iter()
+        .any(|column| !column.chars().all(|c| c.is_ascii_lowercase() || c.is_ascii_digit() || c == '_'))
+    {
+        return Err(anyhow!("Snapshot has an unexpected column name"));
+    }
+
+    let money = money_columns(kind.table());
+    let before = snapshot(pool, kind, id, user_id).await;
+    if before.is_some() {
+        let assignments: Vec<String> = columns.iter().map(|c| format!("{} = ?", c)).collect();
+        let sql = format!("UPDATE {} SET {} WHERE id = ? AND user_id = ?", kind.table(), assignments.join(", "));
+        let mut query = sqlx::query(&sql);
+        for column in &columns {
+            query = bind_value(query, &target[column.as_str()], money.contains(&column.as_str()));
+        }
+        query.bind(id).bind(user_id).execute(pool).await?;
+    } else {
+        let names: Vec<&str> = columns.iter().map(|c| c.as_str()).collect();
+        let placeholders = vec!["?"; names.len() + 2].join(", ");
+        let sql = format!(
+            "INSERT INTO {} (id, user_id, {}) VALUES ({})",
+            kind.table(),
+            names.join(", "),
+            placeholders
+        );
+        let mut query = sqlx::query(&sql).bind(id).bind(user_id);
+        for column in &columns {
+            query = bind_value(query, &target[column.as_str()], money.contains(&column.as_str()));
+        }
+        query.execute(pool).await?;
+    }
+
+    record(pool, kind, id, user_id, "restored", before, device).await;
+    Ok(snapshot(pool, kind, id, user_id).await)
+}
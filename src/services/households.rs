@@ -0,0 +1,274 @@
+use anyhow::Result;
+use chrono::{Duration, Utc};
+use sqlx::Row;
+use uuid::Uuid;
+
+use crate::models::{Household, HouseholdDetail, HouseholdInvite, HouseholdMember, HouseholdRole, SharedAccount};
+use crate::services::database::DbPool;
+use crate::utils::datetime::format_db_datetime;
+use crate::utils::pagination::{PageQuery, Paginated};
+
+pub const INVITE_LIFETIME_DAYS: i64 = 7;
+
+/// IDs of accounts shared with the user (`?`) through their households.
+pub const SHARED_ACCOUNT_IDS: &str = "SELECT ha.account_id FROM household_accounts ha JOIN household_members hm ON hm.household_id = ha.household_id WHERE hm.user_id = ?";
+
+/// Accounts the user (`?` twice) owns or sees through a household, for `WHERE` clauses on
+/// `accounts`.
+pub fn visible_accounts_filter() -> String {
+    format!("(user_id = ? OR id IN ({}))", SHARED_ACCOUNT_IDS)
+}
+
+/// Rows on accounts the user (`?` twice) can see, for `WHERE` clauses on tables with an
+/// `account_id`. Covers rows the user wrote as well as other members' rows on shared accounts.
+pub fn visible_rows_filter() -> String {
+    format!("(user_id = ? OR account_id IN ({}))", SHARED_ACCOUNT_IDS)
+}
+
+/// How a user reaches an account: as its owner, or through a household sharing it.
+#[derive(Debug, Clone)]
+pub struct AccountAccess {
+    pub owner_id: String,
+    /// The best role among households sharing the account; `None` for the owner.
+    pub shared_role: Option<HouseholdRole>,
+}
+
+impl AccountAccess {
+    pub fn is_owner(&self) -> bool {
+        self.shared_role.is_none()
+    }
+
+    /// Whether the user may add transactions and similar records to the account.
+    pub fn can_write(&self) -> bool {
+        self.shared_role.is_none_or(|role| role.can_write())
+    }
+}
+
+/// The user's access to an account; `None` when they can't see it.
+pub async fn account_access(pool: &DbPool, account_id: &str, user_id: &str) -> Result<Option<AccountAccess>> {
+    let Some(owner_id) = sqlx::query_scalar::<_, String>("SELECT user_id FROM accounts WHERE id = ?")
+        .bind(account_id)
+        .fetch_optional(pool)
+        .await?
+    else {
+        return Ok(None);
+    };
+    if owner_id == user_id {
+        return Ok(Some(AccountAccess { owner_id, shared_role: None }));
+    }
+
+    let roles = sqlx::query_scalar::<_, HouseholdRole>(
+        "SELECT hm.role FROM household_accounts ha JOIN household_members hm ON hm.household_id = ha.household_id WHERE ha.account_id = ? AND hm.user_id = ?"
+    )
+    .bind(account_id)
+    .bind(user_id)
+    .fetch_all(pool)
+    .await?;
+    let best = roles
+        .into_iter()
+        .max_by_key(|role| (role.can_manage(), role.can_write()));
+    Ok(best.map(|role| AccountAccess { owner_id, shared_role: Some(role) }))
+}
+
+/// Whether the user may add records to the account, whether it is theirs or shared with them.
+pub async fn can_write_account(pool: &DbPool, account_id: &str, user_id: &str) -> Result<bool> {
+    Ok(account_access(pool, account_id, user_id).await?.is_some_and(|access| access.can_write()))
+}
+
+/// The user's role in the household; `None` when they are not a member.
+pub async fn membership(pool: &DbPool, household_id: &str, user_id: &str) -> Result<Option<HouseholdRole>> {
+    Ok(sqlx::query_scalar::<_, HouseholdRole>("SELECT role FROM household_members WHERE household_id = ? AND user_id = ?")
+        .bind(household_id)
+        .bind(user_id)
+        .fetch_optional(pool)
+        .await?)
+}
+
+/// The household with its members and shared accounts.
+pub async fn detail(pool: &DbPool, household: Household, role: HouseholdRole) -> Result<HouseholdDetail> {
+    let members = sqlx::query_as::<_, HouseholdMember>(
+        "SELECT hm.user_id, u.name, u.email, hm.role, hm.joined_at FROM household_members hm JOIN users u ON u.id = hm.user_id WHERE hm.household_id = ? ORDER BY hm.joined_at"
+    )
+    .bind(&household.id)
+    .fetch_all(pool)
+    .await?;
+
+    let accounts = sqlx::query_as::<_, SharedAccount>(
+        "SELECT ha.account_id, a.name, ha.shared_by, ha.shared_at FROM household_accounts ha JOIN accounts a ON a.id = ha.account_id WHERE ha.household_id = ? ORDER BY ha.shared_at"
+    )
+    .bind(&household.id)
+    .fetch_all(pool)
+    .await?;
+
+    Ok(HouseholdDetail { household, role, members, accounts })
+}
+
+/// Everyone who shares a household with the user, not counting the user.
+pub async fn co_member_ids(pool: &DbPool, user_id: &str) -> Result<Vec<String>> {
+    Ok(sqlx::query_scalar(
+        "SELECT DISTINCT other.user_id FROM household_members mine JOIN household_members other ON other.household_id = mine.household_id WHERE mine.user_id = ? AND other.user_id != ?"
+    )
+    .bind(user_id)
+    .bind(user_id)
+    .fetch_all(pool)
+    .await?)
+}
+
+/// One page of the households the user belongs to, with their role in each.
+pub async fn for_user(pool: &DbPool, user_id: &str, page: &PageQuery) -> Result<Paginated<(Household, HouseholdRole)>> {
+    let rows = sqlx::query(
+        "SELECT h.id, h.name, h.created_by, h.created_at, h.updated_at, hm.role FROM households h JOIN household_members hm ON hm.household_id = h.id WHERE hm.user_id = ? ORDER BY h.created_at, h.id LIMIT ? OFFSET ?"
+    )
+    .bind(user_id)
+    .bind(page.limit() as i64)
+    .bind(page.offset() as i64)
+    .fetch_all(pool)
+    .await?;
+
+    let total = sqlx::query_scalar::<_, i64>("SELECT COUNT(*) FROM household_members WHERE user_id = ?")
+        .bind(user_id)
+        .fetch_one(pool)
+        .await?;
+
+    let memberships = rows
+        .into_iter()
+        .map(|row| {
+            let household = Household {
+                id: row.get("id"),
+                name: row.get("name"),
+                created_by: row.get("created_by"),
+                created_at: row.get("created_at"),
+                updated_at: row.get("updated_at"),
+            };
+            (household, row.get("role"))
+        })
+        .collect();
+    Ok(Paginated::new(memberships, total as usize, page))
+}
+
+/// Invites `email` to the household, replacing any earlier pending invite for it.
+pub async fn create_invite(
+    pool: &DbPool,
+    household_id: &str,
+    email: &str,
+    role: HouseholdRole,
+    invited_by: &str,
+) -> Result<HouseholdInvite> {
+    let now = Utc::now();
+    let invite = HouseholdInvite {
+        id: Uuid::new_v4().to_string(),
+        household_id: household_id.to_string(),
+        email: email.to_string(),
+        role,
+        token: format!("{}{}", Uuid::new_v4().simple(), Uuid::new_v4().simple()),
+        invited_by: invited_by.to_string(),
+        expires_at: now + Duration::days(INVITE_LIFETIME_DAYS),
+        accepted_at: None,
+        created_at: now,
+    };
+
+    let mut tx = pool.begin().await?;
+    sqlx::query("DELETE FROM household_invites WHERE household_id = ? AND email = ? AND accepted_at IS NULL")
+        .bind(household_id)
+        .bind(email)
+        .execute(&mut tx)
+        .await?;
+    sqlx::query(
+        "INSERT INTO household_invites (id, household_id, email, role, token, invited_by, expires_at, created_at) VALUES (?, ?, ?, ?, ?, ?, ?, ?)"
+    )
+    .bind(&invite.id)
+    .bind(&invite.household_id)
+    .bind(&invite.email)
+    .bind(invite.role)
+    .bind(&invite.token)
+    .bind(&invite.invited_by)
+    .bind(format_db_datetime(invite.expires_at))
+    .bind(format_db_datetime(invite.created_at))
+    .execute(&mut tx)
+    .await?;
+    tx.commit().await?;
+
+    Ok(invite)
+}
+
+pub enum AcceptOutcome {
+    Joined(String),
+    /// Unknown, expired or already accepted.
+    NotFound,
+    /// Addressed to a different email than the user's.
+    WrongEmail,
+    AlreadyMember,
+}
+
+/// Adds the user to the invite's household with the invited role.
+pub async fn accept_invite(pool: &DbPool, token: &str, user_id: &str, email: &str) -> Result<AcceptOutcome> {
+    let now = format_db_datetime(Utc::now());
+    let mut tx = pool.begin().await?;
+    let Some(invite) = sqlx::query_as::<_, HouseholdInvite>(
+        "SELECT * FROM household_invites WHERE token = ? AND accepted_at IS NULL AND expires_at > ?"
+    )
+    .bind(token)
+    .bind(&now)
+    .fetch_optional(&mut tx)
+    .await?
+    else {
+        return Ok(AcceptOutcome::NotFound);
+    };
+    if !invite.email.eq_ignore_ascii_case(email) {
+        return Ok(AcceptOutcome::WrongEmail);
+    }
+
+    let joined = sqlx::query("INSERT OR IGNORE INTO household_members (household_id, user_id, role, joined_at) VALUES (?, ?, ?, ?)")
+        .bind(&invite.household_id)
+        .bind(user_id)
+        .bind(invite.role)
+        .bind(&now)
+        .execute(&mut tx)
+        .await?
+        .rows_affected();
+    if joined == 0 {
+        return Ok(AcceptOutcome::AlreadyMember);
+    }
+    sqlx::query("UPDATE household_invites SET accepted_at = ? WHERE id = ?")
+        .bind(&now)
+        .bind(&invite.id)
+        .execute(&mut tx)
+        .await?;
+    tx.commit().await?;
+
+    Ok(AcceptOutcome::Joined(invite.household_id))
+}
+
+/// Deletes invites that expired without being accepted.
+pub async fn prune_expired_invites(pool: &DbPool) -> Result<u64> {
+    let result = sqlx::query("DELETE FROM household_invites WHERE accepted_at IS NULL AND expires_at < ?")
+        .bind(format_db_datetime(Utc::now()))
+        .execute(pool)
+        .await?;
+    Ok(result.rows_affected())
+}
+
+pub async fn owner_count(pool: &DbPool, household_id: &str) -> Result<i64> {
+    Ok(sqlx::query_scalar("SELECT COUNT(*) FROM household_members WHERE household_id = ? AND role = ?")
+        .bind(household_id)
+        .bind(HouseholdRole::Owner)
+        .fetch_one(pool)
+        .await?)
+}
+
+/// Takes the user out of the household along with the accounts they shared there.
+pub async fn remove_member(pool: &DbPool, household_id: &str, user_id: &str) -> Result<()> {
+    let mut tx = pool.begin().await?;
+    sqlx::query("DELETE FROM household_accounts WHERE household_id = ? AND shared_by = ?")
+        .bind(household_id)
+        .bind(user_id)
+        .execute(&mut tx)
+        .await?;
+    sqlx::query("DELETE FROM household_members WHERE household_id = ? AND user_id = ?")
+        .bind(household_id)
+        .bind(user_id)
+        .execute(&mut tx)
+        .await?;
+    tx.commit().await?;
+    Ok(())
+}
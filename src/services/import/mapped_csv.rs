@@ -0,0 +1,126 @@
+use anyhow::{anyhow, bail, Result};
+use chrono::{DateTime, NaiveDate, NaiveDateTime, Utc};
+
+use super::{field, parse_amount, parse_date, CsvTable, ParsedImport};
+use crate::models::{CsvColumnMapping, ImportRowError, ImportedTransaction, TransactionType};
+
+use crate::utils::money::Money;
+
+/// Picks `;` or tab when the header line uses them, otherwise `,`.
+pub fn detect_delimiter(content: &str) -> u8 {
+    let first_line = content.lines().next().unwrap_or_default();
+    if first_line.contains(';') {
+        b';'
+    } else if first_line.contains('\t') {
+        b'\t'
+    } else {
+        b','
+    }
+}
+
+fn parse_mapped_date(value: &str, format: Option<&str>) -> Option<DateTime<Utc>> {
+    let Some(format) = format else {
+        return parse_date(value);
+    };
+    NaiveDateTime::parse_from_str(value, format)
+        .map(|date| date.and_utc())
+        .ok()
+        .or_else(|| {
+            NaiveDate::parse_from_str(value, format)
+                .ok()
+                .and_then(|date| date.and_hms_opt(0, 0, 0))
+                .map(|date| date.and_utc())
+        })
+}
+
+/// Adapter for arbitrary CSV files, driven by a caller-supplied column mapping.
+///
+/// Rows go to the mapped account column, or to `account` when given, which takes precedence.
+/// Amounts come from a signed `amount` column or from separate `debit` / `credit` columns.
+pub fn parse(content: &str, delimiter: u8, mapping: &CsvColumnMapping, account: Option<&str>) -> Result<ParsedImport> {
+    let table = CsvTable::read(content, delimiter)?;
+    let column = |name: Option<&str>| -> Result<Option<usize>> {
+        match name {
+            Some(name) => table
+                .column(&[name.trim().to_lowercase().as_str()])
+                .map(Some)
+                .ok_or_else(|| anyhow!("Column '{}' not found in file", name)),
+            None => Ok(None),
+        }
+    };
+
+    let date_col = column(Some(&mapping.date))?;
+    let amount_col = column(mapping.amount.as_deref())?;
+    let debit_col = column(mapping.debit.as_deref())?;
+    let credit_col = column(mapping.credit.as_deref())?;
+    let type_col = column(mapping.transaction_type.as_deref())?;
+    let account_col = column(mapping.account.as_deref())?;
+    let category_col = column(mapping.category.as_deref())?;
+    let description_col = column(mapping.description.as_deref())?;
+    let currency_col = column(mapping.currency.as_deref())?;
+
+    if amount_col.is_none() && debit_col.is_none() && credit_col.is_none() {
+        bail!("Mapping needs an amount column, or debit and credit columns");
+    }
+    if account_col.is_none() && account.is_none() {
+        bail!("Mapping needs an account column when no account is chosen");
+    }
+
+    let mut parsed = ParsedImport::default();
+    for (index, record) in table.rows.iter().enumerate() {
+        let row = index + 2;
+        let error = |message: &str| ImportRowError { row, message: message.to_string() };
+
+        let Some(date) = field(record, date_col).and_then(|value| parse_mapped_date(value, mapping.date_format.as_deref())) else {
+            parsed.errors.push(error("Invalid date"));
+            continue;
+        };
+
+        // Debits are outflows; a row with neither side filled in is rejected below.
+        let signed_amount = match field(record, amount_col) {
+            Some(value) => parse_amount(value),
+            None => match (field(record, debit_col), field(record, credit_col)) {
+                (Some(debit), _) => parse_amount(debit).map(|amount| -amount.abs()),
+                (None, Some(credit)) => parse_amount(credit).map(Money::abs),
+                (None, None) => None,
+            },
+        };
+        let Some(amount) = signed_amount else {
+            parsed.errors.push(error("Invalid amount"));
+            continue;
+        };
+
+        let Some(account) = account.or_else(|| field(record, account_col)) else {
+            parsed.errors.push(error("Missing account"));
+            continue;
+        };
+
+        let transaction_type = match field(record, type_col).map(str::to_lowercase).as_deref() {
+            Some("income") | Some("credit") | Some("in") => TransactionType::Income,
+            Some("expense") | Some("expenses") | Some("debit") | Some("out") => TransactionType::Expense,
+            Some("transfer") => TransactionType::Transfer,
+            Some(other) => {
+                parsed.errors.push(error(&format!("Unknown transaction type '{}'", other)));
+                continue;
+            }
+            None if !amount.is_negative() => TransactionType::Income,
+            None => TransactionType::Expense,
+        };
+
+        parsed.transactions.push(ImportedTransaction {
+            row,
+            account: account.to_string(),
+            currency: field(record, currency_col).map(str::to_uppercase),
+            transaction_type,
+            amount: amount.abs(),
+            category: match transaction_type {
+                TransactionType::Transfer => None,
+                _ => field(record, category_col).map(str::to_string),
+            },
+            description: field(record, description_col).map(str::to_string),
+            date,
+        });
+    }
+
+    Ok(parsed)
+}
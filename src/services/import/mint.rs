@@ -0,0 +1,85 @@
+use anyhow::Result;
+
+use super::{field, parse_amount, parse_date, CsvTable, ParsedImport};
+use crate::models::{ImportRowError, ImportedTransaction, TransactionType};
+
+/// Mint categories that move money between the user's own accounts.
+const TRANSFER_CATEGORIES: &[&str] = &["transfer", "credit card payment", "transfer for cash spending"];
+
+/// Adapter for the transactions CSV export of Mint.
+///
+/// Columns: `Date, Description, Original Description, Amount, Transaction Type, Category,
+/// Account Name, Labels, Notes`. Amounts are unsigned with a `debit`/`credit` type. Transfers
+/// appear on both accounts; only the debit side is imported.
+pub fn parse(content: &str) -> Result<ParsedImport> {
+    let table = CsvTable::read(content, b',')?;
+    let date_col = table.require(&["date"])?;
+    let amount_col = table.require(&["amount"])?;
+    let type_col = table.require(&["transaction type"])?;
+    let account_col = table.require(&["account name", "account"])?;
+    let description_col = table.column(&["description"]);
+    let original_col = table.column(&["original description"]);
+    let category_col = table.column(&["category"]);
+    let notes_col = table.column(&["notes"]);
+
+    let mut parsed = ParsedImport::default();
+    for (index, record) in table.rows.iter().enumerate() {
+        let row = index + 2;
+        let error = |message: &str| ImportRowError { row, message: message.to_string() };
+
+        let Some(date) = field(record, Some(date_col)).and_then(parse_date) else {
+            parsed.errors.push(error("Invalid date"));
+            continue;
+        };
+        let Some(amount) = field(record, Some(amount_col)).and_then(parse_amount) else {
+            parsed.errors.push(error("Invalid amount"));
+            continue;
+        };
+        let Some(account) = field(record, Some(account_col)) else {
+            parsed.errors.push(error("Missing account"));
+            continue;
+        };
+
+        let category = field(record, category_col);
+        let is_transfer = category.is_some_and(|c| TRANSFER_CATEGORIES.contains(&c.to_lowercase().as_str()));
+        let is_debit = match field(record, Some(type_col)).map(str::to_lowercase).as_deref() {
+            Some("debit") => true,
+            Some("credit") => false,
+            _ => {
+                parsed.errors.push(error("Unknown Transaction Type value"));
+                continue;
+            }
+        };
+        let transaction_type = match (is_transfer, is_debit) {
+            (true, false) => {
+                parsed.skipped += 1;
+                continue;
+            }
+            (true, true) => TransactionType::Transfer,
+            (false, true) => TransactionType::Expense,
+            (false, false) => TransactionType::Income,
+        };
+
+        let description = field(record, description_col).or_else(|| field(record, original_col));
+        let description = match (description, field(record, notes_col)) {
+            (Some(description), Some(notes)) => Some(format!("{} - {}", description, notes)),
+            (description, notes) => description.or(notes).map(str::to_string),
+        };
+
+        parsed.transactions.push(ImportedTransaction {
+            row,
+            account: account.to_string(),
+            currency: None,
+            transaction_type,
+            amount: amount.abs(),
+            category: match transaction_type {
+                TransactionType::Transfer => None,
+                _ => category.map(str::to_string),
+            },
+            description,
+            date,
+        });
+    }
+
+    Ok(parsed)
+}
@@ -0,0 +1,402 @@
+pub mod mapped_csv;
+pub mod mint;
+pub mod money_manager;
+pub mod ofx;
+pub mod qif;
+pub mod wallet;
+pub mod ynab;
+
+use std::collections::{HashMap, HashSet};
+
+use anyhow::{anyhow, Result};
+use chrono::{DateTime, NaiveDate, NaiveDateTime, Utc};
+use rust_decimal::Decimal;
+use sqlx::Row;
+use uuid::Uuid;
+
+use crate::models::{
+    ImportPreview, ImportRowError, ImportSummary, ImportedCategory, ImportedTransaction, TransactionType,
+};
+use crate::services::database::DbPool;
+use crate::utils::datetime::format_db_datetime;
+use crate::utils::money::Money;
+
+/// Rows parsed from an export file by one of the app adapters.
+#[derive(Debug, Default)]
+pub struct ParsedImport {
+    pub transactions: Vec<ImportedTransaction>,
+    pub skipped: usize,
+    pub errors: Vec<ImportRowError>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum ImportSource {
+    MoneyManager,
+    Wallet,
+    Ynab,
+    Mint,
+    Qif,
+    Ofx,
+}
+
+impl ImportSource {
+    pub fn parse(value: &str) -> Option<Self> {
+        match value.to_lowercase().replace(['-', ' '], "_").as_str() {
+            "money_manager" | "moneymanager" | "realbyte" => Some(Self::MoneyManager),
+            "wallet" | "budgetbakers" | "wallet_budgetbakers" => Some(Self::Wallet),
+            "ynab" | "ynab4" | "you_need_a_budget" => Some(Self::Ynab),
+            "mint" | "intuit_mint" => Some(Self::Mint),
+            "qif" => Some(Self::Qif),
+            "ofx" | "qfx" => Some(Self::Ofx),
+            _ => None,
+        }
+    }
+
+    /// Guesses the statement format of an uploaded bank file.
+    pub fn detect_statement(content: &str) -> Option<Self> {
+        let head = content.trim_start().get(..200).unwrap_or(content.trim_start()).to_uppercase();
+        if head.contains("OFXHEADER") || head.contains("<OFX>") {
+            Some(Self::Ofx)
+        } else if head.starts_with('!') {
+            Some(Self::Qif)
+        } else {
+            None
+        }
+    }
+
+    pub fn parse_content(&self, content: &str) -> Result<ParsedImport> {
+        match self {
+            Self::MoneyManager => money_manager::parse(content),
+            Self::Wallet => wallet::parse(content),
+            Self::Ynab => ynab::parse(content),
+            Self::Mint => mint::parse(content),
+            Self::Qif => qif::parse(content),
+            Self::Ofx => ofx::parse(content),
+        }
+    }
+}
+
+/// A CSV file with case-insensitive header lookup, shared by the adapters.
+pub struct CsvTable {
+    headers: Vec<String>,
+    pub rows: Vec<csv::StringRecord>,
+}
+
+impl CsvTable {
+    pub fn read(content: &str, delimiter: u8) -> Result<Self> {
+        let mut reader = csv::ReaderBuilder::new()
+            .delimiter(delimiter)
+            .flexible(true)
+            .trim(csv::Trim::All)
+            .from_reader(content.trim_start_matches('\u{feff}').as_bytes());
+
+        let headers = reader
+            .headers()?
+            .iter()
+            .map(|h| h.trim().to_lowercase())
+            .collect();
+        let rows = reader.records().collect::<Result<Vec<_>, _>>()?;
+
+        Ok(Self { headers, rows })
+    }
+
+    /// Index of the first header matching any of the given names.
+    pub fn column(&self, names: &[&str]) -> Option<usize> {
+        names
+            .iter()
+            .find_map(|name| self.headers.iter().position(|h| h == name))
+    }
+
+    pub fn require(&self, names: &[&str]) -> Result<usize> {
+        self.column(names)
+            .ok_or_else(|| anyhow!("Missing required column '{}'", names[0]))
+    }
+}
+
+pub fn field(record: &csv::StringRecord, index: Option<usize>) -> Option<&str> {
+    index
+        .and_then(|i| record.get(i))
+        .map(str::trim)
+        .filter(|value| !value.is_empty())
+}
+
+/// Parses amounts such as `1,234.50`, `-12` or `৳ 300`.
+pub fn parse_amount(value: &str) -> Option<Money> {
+    let cleaned: String = value
+        .chars()
+        .filter(|c| c.is_ascii_digit() || *c == '.' || *c == '-')
+        .collect();
+    cleaned.parse::<Money>().ok()
+}
+
+/// Accepts the date layouts the supported apps export.
+pub fn parse_date(value: &str) -> Option<DateTime<Utc>> {
+    if let Ok(date) = DateTime::parse_from_rfc3339(value) {
+        return Some(date.with_timezone(&Utc));
+    }
+
+    const DATETIME_FORMATS: &[&str] = &[
+        "%Y-%m-%d %H:%M:%S",
+        "%Y-%m-%d %H:%M",
+        "%Y-%m-%dT%H:%M:%S%.f",
+        "%m/%d/%Y %H:%M:%S",
+        "%m/%d/%Y %H:%M",
+    ];
+    for format in DATETIME_FORMATS {
+        if let Ok(date) = NaiveDateTime::parse_from_str(value, format) {
+            return Some(date.and_utc());
+        }
+    }
+
+    const DATE_FORMATS: &[&str] = &["%Y-%m-%d", "%m/%d/%Y", "%d.%m.%Y"];
+    for format in DATE_FORMATS {
+        if let Ok(date) = NaiveDate::parse_from_str(value, format) {
+            return date.and_hms_opt(0, 0, 0).map(|d| d.and_utc());
+        }
+    }
+
+    None
+}
+
+fn category_type(transaction_type: TransactionType) -> Option<&'static str> {
+    match transaction_type {
+        TransactionType::Income => Some("income"),
+        TransactionType::Expense => Some("expense"),
+        TransactionType::Transfer => None,
+    }
+}
+
+fn duplicate_key(account: &str, transaction_type: TransactionType, amount: Money, date: DateTime<Utc>) -> String {
+    format!(
+        "{}|{:?}|{}|{}",
+        account.to_lowercase(),
+        transaction_type,
+        (amount * Decimal::ONE_HUNDRED).round_dp(0),
+        date.format("%Y-%m-%d")
+    )
+}
+
+/// Points statement rows at an existing account: either the one named in the file, or one whose
+/// name carries the same last four digits of the account number.
+fn match_account(name: &str, existing: &[String]) -> Option<String> {
+    if let Some(found) = existing.iter().find(|a| a.eq_ignore_ascii_case(name)) {
+        return Some(found.clone());
+    }
+
+    let digits: String = name.chars().filter(|c| c.is_ascii_digit()).collect();
+    if digits.len() < 4 {
+        return None;
+    }
+    let last_four = &digits[digits.len() - 4..];
+    existing.iter().find(|a| a.contains(last_four)).cloned()
+}
+
+/// Works out which accounts and categories the import would create for this user and sets aside
+/// rows that duplicate stored transactions.
+pub async fn preview(pool: &DbPool, user_id: &str, mut parsed: ParsedImport) -> Result<ImportPreview> {
+    let existing_accounts: Vec<String> = sqlx::query("SELECT name FROM accounts WHERE user_id = ?")
+        .bind(user_id)
+        .fetch_all(pool)
+        .await?
+        .into_iter()
+        .map(|row| row.get::<String, _>("name"))
+        .collect();
+
+    let existing_categories: HashSet<String> =
+        sqlx::query("SELECT name FROM categories WHERE user_id = ? OR user_id = ''")
+            .bind(user_id)
+            .fetch_all(pool)
+            .await?
+            .into_iter()
+            .map(|row| row.get::<String, _>("name").to_lowercase())
+            .collect();
+
+    // Counted so two identical purchases on one day still import once each.
+    let mut existing_keys: HashMap<String, usize> = HashMap::new();
+    let stored = sqlx::query(
+        "SELECT a.name, t.transaction_type, t.amount, t.date FROM transactions t JOIN accounts a ON a.id = t.account_id WHERE t.user_id = ?"
+    )
+    .bind(user_id)
+    .fetch_all(pool)
+    .await?
+    .into_iter()
+    .filter_map(|row| {
+        let transaction_type: TransactionType =
+            serde_json::from_value(serde_json::json!(row.get::<String, _>("transaction_type"))).ok()?;
+        Some(duplicate_key(
+            &row.get::<String, _>("name"),
+            transaction_type,
+            row.get::<Money, _>("amount"),
+            row.get::<DateTime<Utc>, _>("date"),
+        ))
+    });
+    for key in stored {
+        *existing_keys.entry(key).or_default() += 1;
+    }
+
+    let mut transactions = Vec::new();
+    let mut duplicates = Vec::new();
+    for mut transaction in parsed.transactions.drain(..) {
+        if let Some(name) = match_account(&transaction.account, &existing_accounts) {
+            transaction.account = name;
+        }
+        let key = duplicate_key(&transaction.account, transaction.transaction_type, transaction.amount, transaction.date);
+        match existing_keys.get_mut(&key) {
+            Some(count) if *count > 0 => {
+                *count -= 1;
+                duplicates.push(transaction);
+            }
+            _ => transactions.push(transaction),
+        }
+    }
+
+    let mut accounts_to_create: Vec<String> = Vec::new();
+    let mut categories_to_create: Vec<ImportedCategory> = Vec::new();
+    for transaction in &transactions {
+        let account_key = transaction.account.to_lowercase();
+        if !existing_accounts.iter().any(|a| a.to_lowercase() == account_key)
+            && !accounts_to_create.iter().any(|a| a.to_lowercase() == account_key)
+        {
+            accounts_to_create.push(transaction.account.clone());
+        }
+
+        if let (Some(category), Some(category_type)) =
+            (&transaction.category, category_type(transaction.transaction_type))
+        {
+            let category_key = category.to_lowercase();
+            if !existing_categories.contains(&category_key)
+                && !categories_to_create.iter().any(|c| c.name.to_lowercase() == category_key)
+            {
+                categories_to_create.push(ImportedCategory {
+                    name: category.clone(),
+                    category_type: category_type.to_string(),
+                });
+            }
+        }
+    }
+
+    Ok(ImportPreview {
+        accounts_to_create,
+        categories_to_create,
+        transactions,
+        duplicates,
+        skipped: parsed.skipped,
+        errors: parsed.errors,
+    })
+}
+
+fn guess_account_type(name: &str) -> &'static str {
+    let name = name.to_lowercase();
+    if name.contains("cash") {
+        "cash"
+    } else if name.contains("card") || name.contains("credit") {
+        "credit_card"
+    } else if name.contains("bkash") || name.contains("nagad") || name.contains("rocket") {
+        "mobile_banking"
+    } else if name.contains("wallet") {
+        "wallet"
+    } else if name.contains("saving") {
+        "savings"
+    } else {
+        "bank"
+    }
+}
+
+/// Creates the missing accounts and categories and inserts every transaction in one database transaction.
+pub async fn apply(
+    pool: &DbPool,
+    user_id: &str,
+    preview: ImportPreview,
+    default_currency: &str,
+) -> Result<ImportSummary> {
+    let now = format_db_datetime(Utc::now());
+    let mut tx = pool.begin().await?;
+
+    for name in &preview.accounts_to_create {
+        let currency = preview
+            .transactions
+            .iter()
+            .find(|t| &t.account == name)
+            .and_then(|t| t.currency.clone())
+            .unwrap_or_else(|| default_currency.to_string());
+        sqlx::query(
+            "INSERT INTO accounts (id, user_id, name, account_type, balance, currency, credit_limit, created_at, updated_at) VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?)"
+        )
+        .bind(Uuid::new_v4().to_string())
+        .bind(user_id)
+        .bind(name)
+        .bind(guess_account_type(name))
+        .bind(Money::ZERO)
+        .bind(currency)
+        .bind(None::<Money>)
+        .bind(&now)
+        .bind(&now)
+        .execute(&mut tx)
+        .await?;
+    }
+
+    for category in &preview.categories_to_create {
+        sqlx::query(
+            "INSERT INTO categories (id, name, category_type, icon, color, is_default, created_at, user_id, updated_at) VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?)"
+        )
+        .bind(Uuid::new_v4().to_string())
+        .bind(&category.name)
+        .bind(&category.category_type)
+        .bind("📁")
+        .bind("#9E9E9E")
+        .bind(false)
+        .bind(&now)
+        .bind(user_id)
+        .bind(&now)
+        .execute(&mut tx)
+        .await?;
+    }
+
+    let accounts: HashMap<String, (String, String)> =
+        sqlx::query("SELECT id, name, currency FROM accounts WHERE user_id = ?")
+            .bind(user_id)
+            .fetch_all(&mut tx)
+            .await?
+            .into_iter()
+            .map(|row| {
+                (
+                    row.get::<String, _>("name").to_lowercase(),
+                    (row.get::<String, _>("id"), row.get::<String, _>("currency")),
+                )
+            })
+            .collect();
+
+    let mut imported = 0;
+    for transaction in &preview.transactions {
+        let Some((account_id, account_currency)) = accounts.get(&transaction.account.to_lowercase()) else {
+            continue;
+        };
+        sqlx::query(
+            "INSERT INTO transactions (id, user_id, account_id, transaction_type, amount, currency, category, description, date, created_at) VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?, ?)"
+        )
+        .bind(Uuid::new_v4().to_string())
+        .bind(user_id)
+        .bind(account_id)
+        .bind(format!("{:?}", transaction.transaction_type).to_lowercase())
+        .bind(transaction.amount)
+        .bind(transaction.currency.as_deref().unwrap_or(account_currency))
+        .bind(&transaction.category)
+        .bind(&transaction.description)
+        .bind(format_db_datetime(transaction.date))
+        .bind(&now)
+        .execute(&mut tx)
+        .await?;
+        imported += 1;
+    }
+
+    tx.commit().await?;
+
+    Ok(ImportSummary {
+        accounts_created: preview.accounts_to_create.len(),
+        categories_created: preview.categories_to_create.len(),
+        transactions_imported: imported,
+        duplicates: preview.duplicates.len(),
+        skipped: preview.skipped,
+        errors: preview.errors,
+    })
+}
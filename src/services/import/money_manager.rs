@@ -0,0 +1,76 @@
+use anyhow::Result;
+
+use super::{field, parse_amount, parse_date, CsvTable, ParsedImport};
+use crate::models::{ImportRowError, ImportedTransaction, TransactionType};
+
+/// Adapter for the CSV export of Money Manager (Realbyte).
+///
+/// Columns: `Date, Accounts, Category, Subcategory, Note, Amount, Income/Expense, Description, Currency`.
+/// Transfers appear as a `Transfer-Out` and a `Transfer-In` row; only the outgoing side is imported.
+pub fn parse(content: &str) -> Result<ParsedImport> {
+    let table = CsvTable::read(content, b',')?;
+    let date_col = table.require(&["date", "period"])?;
+    let account_col = table.require(&["accounts", "account"])?;
+    let amount_col = table.require(&["amount"])?;
+    let type_col = table.require(&["income/expense", "type"])?;
+    let category_col = table.column(&["category"]);
+    let note_col = table.column(&["note"]);
+    let description_col = table.column(&["description"]);
+    let currency_col = table.column(&["currency"]);
+
+    let mut parsed = ParsedImport::default();
+    for (index, record) in table.rows.iter().enumerate() {
+        // Header is line 1.
+        let row = index + 2;
+        let error = |message: &str| ImportRowError { row, message: message.to_string() };
+
+        let kind = field(record, Some(type_col)).unwrap_or_default().to_lowercase();
+        let transaction_type = if kind.starts_with("transfer-in") {
+            parsed.skipped += 1;
+            continue;
+        } else if kind.starts_with("transfer") {
+            TransactionType::Transfer
+        } else if kind.starts_with("income") {
+            TransactionType::Income
+        } else if kind.starts_with("exp") {
+            TransactionType::Expense
+        } else {
+            parsed.errors.push(error("Unknown Income/Expense value"));
+            continue;
+        };
+
+        let Some(date) = field(record, Some(date_col)).and_then(parse_date) else {
+            parsed.errors.push(error("Invalid date"));
+            continue;
+        };
+        let Some(amount) = field(record, Some(amount_col)).and_then(parse_amount) else {
+            parsed.errors.push(error("Invalid amount"));
+            continue;
+        };
+        let Some(account) = field(record, Some(account_col)) else {
+            parsed.errors.push(error("Missing account"));
+            continue;
+        };
+
+        // For transfers Money Manager puts the destination account in the category column.
+        let category = match transaction_type {
+            TransactionType::Transfer => None,
+            _ => field(record, category_col).map(str::to_string),
+        };
+
+        parsed.transactions.push(ImportedTransaction {
+            row,
+            account: account.to_string(),
+            currency: field(record, currency_col).map(str::to_uppercase),
+            transaction_type,
+            amount: amount.abs(),
+            category,
+            description: field(record, note_col)
+                .or_else(|| field(record, description_col))
+                .map(str::to_string),
+            date,
+        });
+    }
+
+    Ok(parsed)
+}
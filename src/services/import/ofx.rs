@@ -0,0 +1,117 @@
+use anyhow::{bail, Result};
+use chrono::{DateTime, NaiveDate, NaiveDateTime, Utc};
+
+use super::{parse_amount, ParsedImport};
+use crate::models::{ImportRowError, ImportedTransaction, TransactionType};
+
+/// Reads `<TAG>value` from OFX content. Handles both SGML (unclosed tags) and XML flavours.
+fn tag_value<'a>(block: &'a str, tag: &str) -> Option<&'a str> {
+    let open = format!("<{}>", tag);
+    let start = block.find(&open)? + open.len();
+    let rest = &block[start..];
+    let end = rest.find(['<', '\n', '\r']).unwrap_or(rest.len());
+    let value = rest[..end].trim();
+    (!value.is_empty()).then_some(value)
+}
+
+/// Decodes the character entities OFX requires for `&`, `<` and `>` in text.
+fn unescape(text: &str) -> String {
+    text.replace("&lt;", "<").replace("&gt;", ">").replace("&amp;", "&")
+}
+
+/// Splits out the bodies of every `<TAG>...</TAG>` block.
+fn blocks<'a>(content: &'a str, tag: &str) -> Vec<(usize, &'a str)> {
+    let open = format!("<{}>", tag);
+    let close = format!("</{}>", tag);
+    let mut found = Vec::new();
+    let mut offset = 0;
+    while let Some(start) = content[offset..].find(&open) {
+        let body_start = offset + start + open.len();
+        let body_end = content[body_start..]
+            .find(&close)
+            .map(|end| body_start + end)
+            .unwrap_or(content.len());
+        found.push((body_start, &content[body_start..body_end]));
+        offset = body_end;
+    }
+    found
+}
+
+/// OFX dates are `YYYYMMDD[HHMMSS[.XXX]][[+-offset:TZ]]`.
+fn parse_ofx_date(value: &str) -> Option<DateTime<Utc>> {
+    let digits: String = value.chars().take_while(|c| c.is_ascii_digit()).collect();
+    if digits.len() >= 14 {
+        NaiveDateTime::parse_from_str(&digits[..14], "%Y%m%d%H%M%S").ok().map(|d| d.and_utc())
+    } else if digits.len() >= 8 {
+        NaiveDate::parse_from_str(&digits[..8], "%Y%m%d")
+            .ok()
+            .and_then(|d| d.and_hms_opt(0, 0, 0))
+            .map(|d| d.and_utc())
+    } else {
+        None
+    }
+}
+
+/// Parses an OFX/QFX bank or credit card statement. Rows are attributed to the statement's
+/// account number so they can be matched against an existing account by its last four digits.
+pub fn parse(content: &str) -> Result<ParsedImport> {
+    let upper = content.to_ascii_uppercase();
+    let account = tag_value(&upper, "ACCTID")
+        .map(|id| {
+            let digits: String = id.chars().filter(|c| c.is_ascii_alphanumeric()).collect();
+            let last_four = &digits[digits.len().saturating_sub(4)..];
+            format!("Account {}", last_four)
+        })
+        .unwrap_or_else(|| "OFX Import".to_string());
+    let currency = tag_value(&upper, "CURDEF").map(str::to_string);
+
+    let mut parsed = ParsedImport::default();
+    for (offset, block) in blocks(&upper, "STMTTRN") {
+        let row = upper[..offset].lines().count();
+        let error = |message: &str| ImportRowError { row, message: message.to_string() };
+
+        let Some(date) = tag_value(block, "DTPOSTED").and_then(parse_ofx_date) else {
+            parsed.errors.push(error("Invalid DTPOSTED"));
+            continue;
+        };
+        let Some(amount) = tag_value(block, "TRNAMT").and_then(parse_amount) else {
+            parsed.errors.push(error("Invalid TRNAMT"));
+            continue;
+        };
+
+        let transaction_type = match tag_value(block, "TRNTYPE") {
+            Some("XFER") if amount.is_positive() => {
+                parsed.skipped += 1;
+                continue;
+            }
+            Some("XFER") => TransactionType::Transfer,
+            _ if !amount.is_negative() => TransactionType::Income,
+            _ => TransactionType::Expense,
+        };
+
+        // Tags were upper-cased for matching; take the original text for names and memos.
+        let original = &content[offset..offset + block.len()];
+        let description = match (tag_value(original, "NAME"), tag_value(original, "MEMO")) {
+            (Some(name), Some(memo)) if name != memo => Some(format!("{} - {}", name, memo)),
+            (name, memo) => name.or(memo).map(str::to_string),
+        }
+        .map(|text| unescape(&text));
+
+        parsed.transactions.push(ImportedTransaction {
+            row,
+            account: account.clone(),
+            currency: currency.clone(),
+            transaction_type,
+            amount: amount.abs(),
+            category: None,
+            description,
+            date,
+        });
+    }
+
+    if parsed.transactions.is_empty() && parsed.errors.is_empty() {
+        bail!("No transactions found in OFX file");
+    }
+
+    Ok(parsed)
+}
@@ -0,0 +1,151 @@
+use anyhow::{bail, Result};
+use chrono::{DateTime, NaiveDate, Utc};
+
+use super::{parse_amount, ParsedImport};
+use crate::models::{ImportRowError, ImportedTransaction, TransactionType};
+
+/// QIF dates come as `MM/DD/YYYY`, `MM/DD'YY`, `M/ D/YY` or `YYYY-MM-DD` depending on the bank.
+fn parse_qif_date(value: &str) -> Option<DateTime<Utc>> {
+    let normalized: String = value.chars().filter(|c| !c.is_whitespace()).collect::<String>().replace('\'', "/");
+    let parts: Vec<&str> = normalized.split(['/', '-', '.']).collect();
+    if parts.len() != 3 {
+        return None;
+    }
+
+    let numbers: Vec<i32> = parts.iter().map(|p| p.parse().ok()).collect::<Option<_>>()?;
+    let (year, month, day) = if parts[0].len() == 4 {
+        (numbers[0], numbers[1], numbers[2])
+    } else {
+        let year = if parts[2].len() <= 2 { 2000 + numbers[2] } else { numbers[2] };
+        (year, numbers[0], numbers[1])
+    };
+
+    NaiveDate::from_ymd_opt(year, month as u32, day as u32)
+        .and_then(|d| d.and_hms_opt(0, 0, 0))
+        .map(|d| d.and_utc())
+}
+
+#[derive(Default)]
+struct Record {
+    line: usize,
+    date: Option<String>,
+    amount: Option<String>,
+    payee: Option<String>,
+    memo: Option<String>,
+    category: Option<String>,
+}
+
+/// Parses a QIF bank statement. The account name comes from an `!Account` block when present;
+/// `L[Account]` categories mark transfers.
+pub fn parse(content: &str) -> Result<ParsedImport> {
+    let mut parsed = ParsedImport::default();
+    let mut account = "QIF Import".to_string();
+    let mut in_account_block = false;
+    let mut in_transactions = false;
+    let mut record = Record::default();
+
+    for (index, raw) in content.lines().enumerate() {
+        let line = raw.trim_end_matches('\r');
+        let trimmed = line.trim();
+        if trimmed.is_empty() {
+            continue;
+        }
+
+        if let Some(header) = trimmed.strip_prefix('!') {
+            let header = header.to_lowercase();
+            in_account_block = header == "account";
+            in_transactions = header.starts_with("type:")
+                && !matches!(header.as_str(), "type:cat" | "type:class" | "type:memorized");
+            continue;
+        }
+
+        let (code, value) = trimmed.split_at(1);
+        let value = value.trim();
+
+        if in_account_block {
+            match code {
+                "N" => account = value.to_string(),
+                "^" => in_account_block = false,
+                _ => {}
+            }
+            continue;
+        }
+        if !in_transactions {
+            continue;
+        }
+
+        if record.line == 0 {
+            record.line = index + 1;
+        }
+        match code {
+            "D" => record.date = Some(value.to_string()),
+            "T" | "U" => record.amount = Some(value.to_string()),
+            "P" => record.payee = Some(value.to_string()),
+            "M" => record.memo = Some(value.to_string()),
+            "L" => record.category = Some(value.to_string()),
+            "^" => {
+                let finished = std::mem::take(&mut record);
+                push_record(&mut parsed, &account, finished);
+            }
+            _ => {}
+        }
+    }
+
+    if record.line != 0 {
+        push_record(&mut parsed, &account, record);
+    }
+    if parsed.transactions.is_empty() && parsed.errors.is_empty() {
+        bail!("No transactions found in QIF file");
+    }
+
+    Ok(parsed)
+}
+
+fn push_record(parsed: &mut ParsedImport, account: &str, record: Record) {
+    let row = record.line;
+    let error = |message: &str| ImportRowError { row, message: message.to_string() };
+
+    let Some(date) = record.date.as_deref().and_then(parse_qif_date) else {
+        parsed.errors.push(error("Invalid date"));
+        return;
+    };
+    let Some(amount) = record.amount.as_deref().and_then(parse_amount) else {
+        parsed.errors.push(error("Invalid amount"));
+        return;
+    };
+
+    let is_transfer = record.category.as_deref().is_some_and(|c| c.starts_with('['));
+    let transaction_type = if is_transfer {
+        if amount.is_positive() {
+            // The outgoing side of the transfer is imported from the other account's statement.
+            parsed.skipped += 1;
+            return;
+        }
+        TransactionType::Transfer
+    } else if !amount.is_negative() {
+        TransactionType::Income
+    } else {
+        TransactionType::Expense
+    };
+
+    let category = match transaction_type {
+        TransactionType::Transfer => None,
+        // Subcategories are written as `Parent:Child`; keep the parent.
+        _ => record.category.and_then(|c| c.split(':').next().map(|s| s.trim().to_string())).filter(|c| !c.is_empty()),
+    };
+    let description = match (record.payee, record.memo) {
+        (Some(payee), Some(memo)) => Some(format!("{} - {}", payee, memo)),
+        (payee, memo) => payee.or(memo),
+    };
+
+    parsed.transactions.push(ImportedTransaction {
+        row,
+        account: account.to_string(),
+        currency: None,
+        transaction_type,
+        amount: amount.abs(),
+        category,
+        description,
+        date,
+    });
+}
@@ -0,0 +1,82 @@
+use anyhow::Result;
+
+use super::{field, parse_amount, parse_date, CsvTable, ParsedImport};
+use crate::models::{ImportRowError, ImportedTransaction, TransactionType};
+
+/// Adapter for the CSV export of Wallet by BudgetBakers.
+///
+/// Semicolon separated with columns such as `account;category;currency;amount;type;note;date;transfer;payee`.
+/// Amounts are signed; transfers appear once per side and only the outgoing side is imported.
+pub fn parse(content: &str) -> Result<ParsedImport> {
+    let first_line = content.lines().next().unwrap_or_default();
+    let delimiter = if first_line.contains(';') { b';' } else { b',' };
+
+    let table = CsvTable::read(content, delimiter)?;
+    let account_col = table.require(&["account"])?;
+    let amount_col = table.require(&["amount"])?;
+    let date_col = table.require(&["date"])?;
+    let type_col = table.column(&["type"]);
+    let category_col = table.column(&["category", "custom_category"]);
+    let currency_col = table.column(&["currency"]);
+    let note_col = table.column(&["note"]);
+    let payee_col = table.column(&["payee"]);
+    let transfer_col = table.column(&["transfer"]);
+
+    let mut parsed = ParsedImport::default();
+    for (index, record) in table.rows.iter().enumerate() {
+        let row = index + 2;
+        let error = |message: &str| ImportRowError { row, message: message.to_string() };
+
+        let Some(amount) = field(record, Some(amount_col)).and_then(parse_amount) else {
+            parsed.errors.push(error("Invalid amount"));
+            continue;
+        };
+        let Some(date) = field(record, Some(date_col)).and_then(parse_date) else {
+            parsed.errors.push(error("Invalid date"));
+            continue;
+        };
+        let Some(account) = field(record, Some(account_col)) else {
+            parsed.errors.push(error("Missing account"));
+            continue;
+        };
+
+        let is_transfer = field(record, transfer_col)
+            .map(|value| value.eq_ignore_ascii_case("true"))
+            .unwrap_or(false);
+        let transaction_type = if is_transfer {
+            if amount.is_positive() {
+                parsed.skipped += 1;
+                continue;
+            }
+            TransactionType::Transfer
+        } else {
+            match field(record, type_col).map(str::to_lowercase).as_deref() {
+                Some("income") => TransactionType::Income,
+                Some("expense") | Some("expenses") => TransactionType::Expense,
+                _ if !amount.is_negative() => TransactionType::Income,
+                _ => TransactionType::Expense,
+            }
+        };
+
+        let description = match (field(record, note_col), field(record, payee_col)) {
+            (Some(note), Some(payee)) => Some(format!("{} - {}", payee, note)),
+            (note, payee) => note.or(payee).map(str::to_string),
+        };
+
+        parsed.transactions.push(ImportedTransaction {
+            row,
+            account: account.to_string(),
+            currency: field(record, currency_col).map(str::to_uppercase),
+            transaction_type,
+            amount: amount.abs(),
+            category: match transaction_type {
+                TransactionType::Transfer => None,
+                _ => field(record, category_col).map(str::to_string),
+            },
+            description,
+            date,
+        });
+    }
+
+    Ok(parsed)
+}
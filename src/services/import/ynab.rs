@@ -0,0 +1,82 @@
+use anyhow::Result;
+
+use super::{field, parse_amount, parse_date, CsvTable, ParsedImport};
+use crate::models::{ImportRowError, ImportedTransaction, TransactionType};
+
+/// Categories YNAB uses for unassigned income rather than a real category.
+const INCOME_CATEGORIES: &[&str] = &["ready to assign", "to be budgeted", "inflow: ready to assign", "inflow: to be budgeted"];
+
+/// Adapter for the register export of YNAB, both the current web app and classic YNAB 4.
+///
+/// Columns: `Account, Flag, Date, Payee, Category Group/Category, Category Group, Category, Memo,
+/// Outflow, Inflow, Cleared` (YNAB 4 has `Master Category, Sub Category` instead). Transfers have a
+/// `Transfer : <account>` payee and appear once per account; only the outflow side is imported.
+pub fn parse(content: &str) -> Result<ParsedImport> {
+    let table = CsvTable::read(content, b',')?;
+    let account_col = table.require(&["account"])?;
+    let date_col = table.require(&["date"])?;
+    let outflow_col = table.require(&["outflow"])?;
+    let inflow_col = table.require(&["inflow"])?;
+    let payee_col = table.column(&["payee"]);
+    let category_col = table.column(&["category", "sub category"]);
+    let memo_col = table.column(&["memo"]);
+
+    let mut parsed = ParsedImport::default();
+    for (index, record) in table.rows.iter().enumerate() {
+        let row = index + 2;
+        let error = |message: &str| ImportRowError { row, message: message.to_string() };
+
+        let Some(date) = field(record, Some(date_col)).and_then(parse_date) else {
+            parsed.errors.push(error("Invalid date"));
+            continue;
+        };
+        let Some(account) = field(record, Some(account_col)) else {
+            parsed.errors.push(error("Missing account"));
+            continue;
+        };
+
+        let outflow = field(record, Some(outflow_col)).and_then(parse_amount).unwrap_or_default();
+        let inflow = field(record, Some(inflow_col)).and_then(parse_amount).unwrap_or_default();
+        let amount = inflow - outflow;
+        if amount.is_zero() {
+            parsed.skipped += 1;
+            continue;
+        }
+
+        let payee = field(record, payee_col);
+        let is_transfer = payee.is_some_and(|p| p.to_lowercase().starts_with("transfer :"));
+        let transaction_type = match (is_transfer, amount.is_positive()) {
+            (true, true) => {
+                parsed.skipped += 1;
+                continue;
+            }
+            (true, false) => TransactionType::Transfer,
+            (false, true) => TransactionType::Income,
+            (false, false) => TransactionType::Expense,
+        };
+
+        let category = match transaction_type {
+            TransactionType::Transfer => None,
+            _ => field(record, category_col)
+                .filter(|c| !INCOME_CATEGORIES.contains(&c.to_lowercase().as_str()))
+                .map(str::to_string),
+        };
+        let description = match (payee, field(record, memo_col)) {
+            (Some(payee), Some(memo)) => Some(format!("{} - {}", payee, memo)),
+            (payee, memo) => payee.or(memo).map(str::to_string),
+        };
+
+        parsed.transactions.push(ImportedTransaction {
+            row,
+            account: account.to_string(),
+            currency: None,
+            transaction_type,
+            amount: amount.abs(),
+            category,
+            description,
+            date,
+        });
+    }
+
+    Ok(parsed)
+}
@@ -0,0 +1,64 @@
+use std::collections::BTreeMap;
+
+use anyhow::Result;
+use chrono::Utc;
+
+use crate::middleware::owned;
+use crate::models::{Holding, HoldingValuation};
+use crate::services::currency;
+use crate::services::database::DbPool;
+use crate::services::market_prices;
+use crate::utils::money::Money;
+use crate::utils::pagination::{PageQuery, Paginated};
+
+/// Values `holding` at its cached quote, converted into the holding's currency at today's rate.
+pub async fn value(pool: &DbPool, holding: Holding) -> Result<HoldingValuation> {
+    let quote = market_prices::latest_quote(pool, holding.asset_type, &holding.symbol).await?;
+    let market_value = match &quote {
+        Some(quote) => currency::rate_on(pool, &holding.user_id, &quote.currency, &holding.currency, Utc::now().date_naive())
+            .await?
+            .and_then(|rate| Money::from_f64(holding.quantity * quote.price * rate))
+            .map(|value| currency::round(value, &holding.currency)),
+        None => None,
+    };
+
+    Ok(HoldingValuation {
+        gain: market_value.map(|value| value - holding.cost_basis),
+        holding,
+        quote,
+        market_value,
+    })
+}
+
+/// One page of the user's holdings, valued.
+pub async fn valuations(pool: &DbPool, user_id: &str, page: &PageQuery) -> Result<Paginated<HoldingValuation>> {
+    let holdings = owned::page::<Holding>(pool, user_id, "asset_type, symbol", page).await?;
+
+    let mut valued = Vec::with_capacity(holdings.items.len());
+    for holding in holdings.items {
+        valued.push(value(pool, holding).await?);
+    }
+    Ok(Paginated::new(valued, holdings.total, page))
+}
+
+/// Value of the user's holdings per currency, for net worth. Holdings in accounts flagged
+/// `exclude_from_totals` are left out; those without a usable quote count at cost.
+pub async fn totals_by_currency(pool: &DbPool, user_id: &str) -> Result<BTreeMap<String, Money>> {
+    let holdings = sqlx::query_as::<_, Holding>(
+        r#"
+        SELECT h.* FROM holdings h
+        LEFT JOIN accounts a ON a.id = h.account_id
+        WHERE h.user_id = ? AND COALESCE(a.exclude_from_totals, FALSE) = FALSE
+        "#,
+    )
+    .bind(user_id)
+    .fetch_all(pool)
+    .await?;
+
+    let mut totals: BTreeMap<String, Money> = BTreeMap::new();
+    for holding in holdings {
+        let valued = value(pool, holding).await?;
+        *totals.entry(valued.holding.currency.clone()).or_default() += valued.value_or_cost();
+    }
+    Ok(totals)
+}
@@ -0,0 +1,184 @@
+use std::collections::VecDeque;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, Mutex, OnceLock};
+
+use anyhow::Result;
+use chrono::Utc;
+use serde_json::{json, Value};
+use tokio::sync::{broadcast, watch};
+
+use crate::services::concurrency;
+use crate::services::database::DbPool;
+use crate::services::history::EntityKind;
+
+/// Changes held for each connected client before it is considered lagging and told to resync.
+const LIVE_CHANNEL_CAPACITY: usize = 1024;
+
+/// Recent changes kept for clients resuming a stream with `Last-Event-ID`.
+const REPLAY_CAPACITY: usize = 1000;
+
+/// An entity change on its way to the live connections of the users who can see it.
+#[derive(Debug)]
+pub struct LiveChange {
+    /// Counts up from 1 for each server start; see `event_id`.
+    pub sequence: u64,
+    pub recipients: Vec<String>,
+    pub payload: Value,
+}
+
+impl LiveChange {
+    /// The change's ID on the wire. It names the server run, so IDs from before a restart
+    /// aren't mistaken for current ones.
+    pub fn event_id(&self) -> String {
+        format!("{}-{}", bus().started, self.sequence)
+    }
+}
+
+struct LiveBus {
+    sender: broadcast::Sender<Arc<LiveChange>>,
+    recent: Mutex<VecDeque<Arc<LiveChange>>>,
+    next_sequence: AtomicU64,
+    started: i64,
+    closing: watch::Sender<bool>,
+}
+
+fn bus() -> &'static LiveBus {
+    static BUS: OnceLock<LiveBus> = OnceLock::new();
+    BUS.get_or_init(|| LiveBus {
+        sender: broadcast::channel(LIVE_CHANNEL_CAPACITY).0,
+        recent: Mutex::new(VecDeque::with_capacity(REPLAY_CAPACITY)),
+        next_sequence: AtomicU64::new(1),
+        started: Utc::now().timestamp_millis(),
+        closing: watch::channel(false).0,
+    })
+}
+
+pub fn subscribe() -> broadcast::Receiver<Arc<LiveChange>> {
+    bus().sender.subscribe()
+}
+
+/// Ends every open stream, for shutdown; clients reconnect to another server or after restart.
+pub fn close() {
+    bus().closing.send_replace(true);
+}
+
+/// Turns true once the server is closing streams; see [`close`].
+pub fn closing() -> watch::Receiver<bool> {
+    bus().closing.subscribe()
+}
+
+/// The changes after `last_event_id`, oldest first, or `None` when they can't all be replayed
+/// because the ID is from before a restart or older than the replay buffer.
+pub fn changes_after(last_event_id: &str) -> Option<Vec<Arc<LiveChange>>> {
+    let bus = bus();
+    let (started, sequence) = last_event_id.split_once('-')?;
+    let sequence: u64 = sequence.parse().ok()?;
+    if started.parse::<i64>().ok()? != bus.started {
+        return None;
+    }
+
+    let recent = bus.recent.lock().unwrap_or_else(|e| e.into_inner());
+    let oldest = recent.front().map(|change| change.sequence).unwrap_or(bus.next_sequence.load(Ordering::SeqCst));
+    if sequence + 1 < oldest {
+        return None;
+    }
+    Some(recent.iter().filter(|change| change.sequence > sequence).cloned().collect())
+}
+
+fn send(recipients: Vec<String>, payload: Value) {
+    let bus = bus();
+    // Numbered under the lock so the replay buffer stays in order
+    let mut recent = bus.recent.lock().unwrap_or_else(|e| e.into_inner());
+    let change = Arc::new(LiveChange {
+        sequence: bus.next_sequence.fetch_add(1, Ordering::SeqCst),
+        recipients,
+        payload,
+    });
+    if recent.len() == REPLAY_CAPACITY {
+        recent.pop_front();
+    }
+    recent.push_back(change.clone());
+    // Only fails when nobody is connected
+    let _ = bus.sender.send(change);
+}
+
+/// The account an account-scoped change belongs to, read from the stored copy or, for a
+/// delete, from the snapshot taken before it.
+fn account_of(kind: EntityKind, id: &str, data: Option<&Value>, before: Option<&Value>) -> Option<String> {
+    match kind {
+        EntityKind::Account => Some(id.to_string()),
+        EntityKind::Transaction => data
+            .and_then(|data| data.get("accountId"))
+            .or_else(|| before.and_then(|before| before.get("account_id")))
+            .and_then(Value::as_str)
+            .map(str::to_string),
+        _ => None,
+    }
+}
+
+/// The acting user, plus the owner and household members of the account a change belongs to.
+async fn recipients(pool: &DbPool, user_id: &str, account_id: Option<&str>) -> Result<Vec<String>> {
+    let mut recipients = vec![user_id.to_string()];
+    if let Some(account_id) = account_id {
+        let shared = sqlx::query_scalar::<_, String>(
+            "SELECT user_id FROM accounts WHERE id = ? UNION SELECT hm.user_id FROM household_accounts ha JOIN household_members hm ON hm.household_id = ha.household_id WHERE ha.account_id = ?"
+        )
+        .bind(account_id)
+        .bind(account_id)
+        .fetch_all(pool)
+        .await?;
+        recipients.extend(shared);
+        recipients.sort();
+        recipients.dedup();
+    }
+    Ok(recipients)
+}
+
+async fn try_publish(
+    pool: &DbPool,
+    kind: EntityKind,
+    id: &str,
+    user_id: &str,
+    action: &str,
+    before: Option<&Value>,
+    device: &str,
+) -> Result<()> {
+    let data = match (kind, action) {
+        (_, "deleted") | (EntityKind::Loan | EntityKind::Liability, _) => None,
+        _ => concurrency::current_copy(pool, kind, id, user_id).await?,
+    };
+    let account_id = account_of(kind, id, data.as_ref(), before);
+    let recipients = recipients(pool, user_id, account_id.as_deref()).await?;
+
+    let payload = json!({
+        "type": format!("{}.{}", kind.name(), action),
+        "entity": {
+            "type": kind.name(),
+            "id": id
+        },
+        "action": action,
+        "device": device,
+        "occurredAt": Utc::now(),
+        "data": data
+    });
+
+    send(recipients, payload);
+    Ok(())
+}
+
+/// Pushes a change to every connected client that can see it, the device that made it
+/// included; clients can skip their own by `device`. Kept for replay even when nobody is
+/// connected. Failures are logged, never returned.
+pub async fn publish(
+    pool: &DbPool,
+    kind: EntityKind,
+    id: &str,
+    user_id: &str,
+    action: &str,
+    before: Option<&Value>,
+    device: &str,
+) {
+    if let Err(e) = try_publish(pool, kind, id, user_id, action, before, device).await {
+        tracing::error!("Failed to publish live {} change for {}: {}", kind.name(), id, e);
+    }
+}
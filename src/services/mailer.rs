@@ -0,0 +1,265 @@
+use std::sync::Arc;
+use std::time::Duration;
+
+use anyhow::{anyhow, Result};
+use chrono::{Duration as ChronoDuration, Utc};
+use lettre::message::{header::ContentType, Mailbox};
+use lettre::transport::smtp::authentication::Credentials;
+use lettre::{AsyncSmtpTransport, AsyncTransport, Message, Tokio1Executor};
+use sqlx::Row;
+use uuid::Uuid;
+
+use crate::config::{self, MailConfig};
+use crate::services::database::DbPool;
+use crate::services::scheduler::Scheduler;
+use crate::services::email_tokens::TokenPurpose;
+use crate::services::households;
+use crate::utils::datetime::format_db_datetime;
+
+const MAIL_DELIVERY_INTERVAL: Duration = Duration::from_secs(15);
+
+const MAIL_BATCH_SIZE: i64 = 50;
+
+/// A message is given up on after this many failed sends.
+const MAX_SEND_ATTEMPTS: i64 = 6;
+
+/// Delay before the first retry; it doubles with every further failure.
+const RETRY_BASE_MINUTES: i64 = 1;
+
+/// Sent and abandoned messages are deleted after this many days.
+const OUTBOX_RETENTION_DAYS: i64 = 30;
+
+/// The emails the app sends. Rendered to plain text when queued.
+pub enum EmailTemplate<'a> {
+    Verification { name: &'a str, token: &'a str },
+    PasswordReset { name: &'a str, token: &'a str },
+    Reminder { name: &'a str, title: &'a str, body: &'a str },
+    HouseholdInvite { inviter: &'a str, household: &'a str, token: &'a str },
+}
+
+/// Where the user acts on a token: a link when `mail.app_url` is set, otherwise the bare code
+/// to paste into the app.
+fn token_instructions(path: &str, token: &str) -> String {
+    match &config::get().mail.app_url {
+        Some(base) => format!("{}/{}?token={}", base.trim_end_matches('/'), path, token),
+        None => format!("Code: {}", token),
+    }
+}
+
+impl EmailTemplate<'_> {
+    /// Returns the subject and plain-text body.
+    pub fn render(&self) -> (String, String) {
+        match self {
+            EmailTemplate::Verification { name, token } => (
+                "Verify your email address".to_string(),
+                format!(
+                    "Hi {},\n\nPlease confirm this is your email address:\n\n{}\n\nThis expires in {} hours. If you didn't create an account, you can ignore this email.\n",
+                    name,
+                    token_instructions("verify-email", token),
+                    TokenPurpose::VerifyEmail.lifetime().num_hours()
+                ),
+            ),
+            EmailTemplate::PasswordReset { name, token } => (
+                "Reset your password".to_string(),
+                format!(
+                    "Hi {},\n\nSomeone asked to reset the password for your account. To choose a new one:\n\n{}\n\nThis expires in {} minutes. If it wasn't you, ignore this email and your password stays the same.\n",
+                    name,
+                    token_instructions("reset-password", token),
+                    TokenPurpose::ResetPassword.lifetime().num_minutes()
+                ),
+            ),
+            EmailTemplate::Reminder { name, title, body } => (
+                title.to_string(),
+                format!("Hi {},\n\n{}\n", name, body),
+            ),
+            EmailTemplate::HouseholdInvite { inviter, household, token } => (
+                format!("{} invited you to {}", inviter, household),
+                format!(
+                    "Hi,\n\n{} invited you to join the household \"{}\" and see the accounts shared there. To accept, sign in with this email address and open:\n\n{}\n\nThis expires in {} days. If you weren't expecting it, you can ignore this email.\n",
+                    inviter,
+                    household,
+                    token_instructions("household-invite", token),
+                    households::INVITE_LIFETIME_DAYS
+                ),
+            ),
+        }
+    }
+}
+
+/// Queues an email for the delivery worker. Returns as soon as it is stored.
+pub async fn enqueue(pool: &DbPool, user_id: Option<&str>, to: &str, template: EmailTemplate<'_>) -> Result<()> {
+    let (subject, body) = template.render();
+    let now = format_db_datetime(Utc::now());
+    sqlx::query(
+        r#"
+        INSERT INTO email_outbox (id, user_id, to_address, subject, body, attempts, next_attempt_at, created_at)
+        VALUES (?, ?, ?, ?, ?, 0, ?, ?)
+        "#,
+    )
+    .bind(Uuid::new_v4().to_string())
+    .bind(user_id)
+    .bind(to)
+    .bind(&subject)
+    .bind(&body)
+    .bind(&now)
+    .bind(&now)
+    .execute(pool)
+    .await?;
+
+    Ok(())
+}
+
+/// Queues an email, logging instead of failing; email must never break the caller.
+pub async fn send(pool: &DbPool, user_id: Option<&str>, to: &str, template: EmailTemplate<'_>) {
+    if let Err(e) = enqueue(pool, user_id, to, template).await {
+        tracing::error!("Failed to queue email to {}: {}", to, e);
+    }
+}
+
+/// SMTP transport configured by `mail.smtp_host`, `mail.smtp_from` and optionally the port,
+/// credentials and `smtp_security` (`starttls`, the default, `tls` or `none`).
+pub struct SmtpMailer {
+    transport: AsyncSmtpTransport<Tokio1Executor>,
+    from: Mailbox,
+}
+
+impl SmtpMailer {
+    pub fn from_config(config: &MailConfig) -> Result<Option<Self>> {
+        let Some(host) = &config.smtp_host else {
+            return Ok(None);
+        };
+        let from: Mailbox = config
+            .smtp_from
+            .as_deref()
+            .ok_or_else(|| anyhow!("mail.smtp_from is not set"))?
+            .parse()?;
+
+        let mut builder = match config.smtp_security.as_str() {
+            "starttls" => AsyncSmtpTransport::<Tokio1Executor>::starttls_relay(host)?,
+            "tls" => AsyncSmtpTransport::<Tokio1Executor>::relay(host)?,
+            "none" => AsyncSmtpTransport::<Tokio1Executor>::builder_dangerous(host),
+            other => return Err(anyhow!("unknown mail.smtp_security '{}'", other)),
+        };
+        if let Some(port) = config.smtp_port {
+            builder = builder.port(port);
+        }
+        if let Some(username) = &config.smtp_username {
+            let password = config.smtp_password.clone().unwrap_or_default();
+            builder = builder.credentials(Credentials::new(username.clone(), password));
+        }
+
+        Ok(Some(Self {
+            transport: builder.build(),
+            from,
+        }))
+    }
+
+    async fn send(&self, to: &str, subject: &str, body: &str) -> Result<()> {
+        let message = Message::builder()
+            .from(self.from.clone())
+            .to(to.parse()?)
+            .subject(subject)
+            .header(ContentType::TEXT_PLAIN)
+            .body(body.to_string())?;
+        self.transport.send(message).await?;
+        Ok(())
+    }
+}
+
+/// Sends queued emails that are due. Failures are retried with exponential backoff until
+/// `MAX_SEND_ATTEMPTS` is reached.
+pub async fn deliver_pending(pool: &DbPool, mailer: &SmtpMailer) -> Result<usize> {
+    let pending = sqlx::query(
+        r#"
+        SELECT id, to_address, subject, body, attempts FROM email_outbox
+        WHERE sent_at IS NULL AND attempts < ? AND next_attempt_at <= ?
+        ORDER BY created_at
+        LIMIT ?
+        "#,
+    )
+    .bind(MAX_SEND_ATTEMPTS)
+    .bind(format_db_datetime(Utc::now()))
+    .bind(MAIL_BATCH_SIZE)
+    .fetch_all(pool)
+    .await?;
+
+    let mut sent = 0;
+    for row in &pending {
+        let id: String = row.get("id");
+        let to: String = row.get("to_address");
+        let attempts = row.get::<i64, _>("attempts") + 1;
+
+        match mailer.send(&to, row.get("subject"), row.get("body")).await {
+            Ok(()) => {
+                sent += 1;
+                sqlx::query("UPDATE email_outbox SET sent_at = ?, attempts = ?, last_error = NULL WHERE id = ?")
+                    .bind(format_db_datetime(Utc::now()))
+                    .bind(attempts)
+                    .bind(&id)
+                    .execute(pool)
+                    .await?;
+            }
+            Err(e) => {
+                if attempts >= MAX_SEND_ATTEMPTS {
+                    tracing::warn!("Giving up on email {} to {} after {} attempts: {}", id, to, attempts, e);
+                } else {
+                    tracing::warn!("Email {} to {} failed (attempt {}): {}", id, to, attempts, e);
+                }
+                let retry_at = Utc::now() + ChronoDuration::minutes(RETRY_BASE_MINUTES << (attempts - 1));
+                sqlx::query("UPDATE email_outbox SET attempts = ?, last_error = ?, next_attempt_at = ? WHERE id = ?")
+                    .bind(attempts)
+                    .bind(e.to_string())
+                    .bind(format_db_datetime(retry_at))
+                    .bind(&id)
+                    .execute(pool)
+                    .await?;
+            }
+        }
+    }
+
+    Ok(sent)
+}
+
+pub async fn prune_outbox(pool: &DbPool) -> Result<u64> {
+    let cutoff = format_db_datetime(Utc::now() - ChronoDuration::days(OUTBOX_RETENTION_DAYS));
+    let result = sqlx::query("DELETE FROM email_outbox WHERE created_at < ? AND (sent_at IS NOT NULL OR attempts >= ?)")
+        .bind(cutoff)
+        .bind(MAX_SEND_ATTEMPTS)
+        .execute(pool)
+        .await?;
+
+    Ok(result.rows_affected())
+}
+
+/// Schedules the email delivery worker if SMTP is configured. Emails queued meanwhile are
+/// kept and go out once it is.
+pub fn schedule_mail_delivery_job(scheduler: &mut Scheduler, config: &MailConfig) {
+    let mailer = match SmtpMailer::from_config(config) {
+        Ok(Some(mailer)) => Arc::new(mailer),
+        Ok(None) => {
+            tracing::info!("Email delivery disabled: mail.smtp_host (SMTP_HOST) is not set");
+            return;
+        }
+        Err(e) => {
+            tracing::error!("Email delivery disabled: {}", e);
+            return;
+        }
+    };
+
+    // Queued emails keep their own retry schedule, so a failed run just waits for the next one
+    scheduler
+        .every("mail_delivery", MAIL_DELIVERY_INTERVAL, move |pool| {
+            let mailer = mailer.clone();
+            async move {
+                let sent = deliver_pending(&pool, &mailer).await?;
+                if sent > 0 {
+                    tracing::info!("Sent {} emails", sent);
+                }
+                prune_outbox(&pool).await?;
+                Ok(sent as u64)
+            }
+        })
+        .retries(0)
+        .keeps_cache()
+        .only_when(|features| features.enabled(features.email));
+}
@@ -0,0 +1,179 @@
+use std::time::{Duration, Instant};
+
+use anyhow::Result;
+use chrono::Utc;
+
+use crate::services::database::DbPool;
+use crate::services::scheduler::Scheduler;
+use crate::services::{email_tokens, households, sync};
+use crate::utils::datetime::format_db_datetime;
+
+const PRUNE_INTERVAL: Duration = Duration::from_secs(24 * 60 * 60);
+const OPTIMIZE_INTERVAL: Duration = Duration::from_secs(24 * 60 * 60);
+const VACUUM_INTERVAL: Duration = Duration::from_secs(7 * 24 * 60 * 60);
+
+/// A full `VACUUM` rewrites the whole file, so it only runs once free pages make up this share
+/// of it; the daily incremental vacuum handles the rest.
+const VACUUM_FREE_RATIO: f64 = 0.2;
+
+/// Runs kept in `db_maintenance_runs` per task.
+const KEPT_RUNS: i64 = 200;
+
+/// `PRAGMA auto_vacuum` value for incremental mode.
+const AUTO_VACUUM_INCREMENTAL: i64 = 2;
+
+/// How big the database file is and how much of it is free pages.
+#[derive(Debug, Clone, Copy)]
+pub struct DatabaseSize {
+    pub page_size: i64,
+    pub page_count: i64,
+    pub free_pages: i64,
+}
+
+impl DatabaseSize {
+    pub fn bytes(&self) -> i64 {
+        self.page_size * self.page_count
+    }
+
+    pub fn free_bytes(&self) -> i64 {
+        self.page_size * self.free_pages
+    }
+
+    fn free_ratio(&self) -> f64 {
+        if self.page_count == 0 { 0.0 } else { self.free_pages as f64 / self.page_count as f64 }
+    }
+}
+
+pub async fn size(pool: &DbPool) -> Result<DatabaseSize> {
+    Ok(DatabaseSize {
+        page_size: sqlx::query_scalar("PRAGMA page_size").fetch_one(pool).await?,
+        page_count: sqlx::query_scalar("PRAGMA page_count").fetch_one(pool).await?,
+        free_pages: sqlx::query_scalar("PRAGMA freelist_count").fetch_one(pool).await?,
+    })
+}
+
+/// Deletes rows nothing reads any more: change log entries and tombstones past the sync
+/// window, spent or expired email tokens, and household invites that expired unanswered.
+pub async fn prune(pool: &DbPool) -> Result<u64> {
+    let changes = sync::prune_change_log(pool).await?;
+    let tokens = email_tokens::prune(pool).await?;
+    let invites = households::prune_expired_invites(pool).await?;
+    Ok(changes + tokens + invites)
+}
+
+/// Refreshes the query planner's statistics, then returns free pages to the file system when
+/// the database is in incremental auto-vacuum mode.
+pub async fn optimize(pool: &DbPool) -> Result<()> {
+    sqlx::query("ANALYZE").execute(pool).await?;
+
+    let mode: i64 = sqlx::query_scalar("PRAGMA auto_vacuum").fetch_one(pool).await?;
+    if mode == AUTO_VACUUM_INCREMENTAL {
+        sqlx::query("PRAGMA incremental_vacuum").execute(pool).await?;
+    }
+    Ok(())
+}
+
+/// Rewrites the database with a full `VACUUM` when free pages make up [`VACUUM_FREE_RATIO`] of
+/// it, or when it isn't in incremental auto-vacuum mode yet, which a `VACUUM` switches it to.
+/// Returns false when it wasn't needed.
+pub async fn vacuum(pool: &DbPool) -> Result<bool> {
+    let mode: i64 = sqlx::query_scalar("PRAGMA auto_vacuum").fetch_one(pool).await?;
+    if mode == AUTO_VACUUM_INCREMENTAL && size(pool).await?.free_ratio() < VACUUM_FREE_RATIO {
+        return Ok(false);
+    }
+
+    // The mode change only sticks when the VACUUM runs on the same connection
+    let mut connection = pool.acquire().await?;
+    if mode != AUTO_VACUUM_INCREMENTAL {
+        tracing::info!("Switching the database to incremental auto-vacuum");
+        sqlx::query("PRAGMA auto_vacuum = INCREMENTAL").execute(&mut connection).await?;
+    }
+    sqlx::query("VACUUM").execute(&mut connection).await?;
+    Ok(true)
+}
+
+/// One maintenance task's outcome, as stored in `db_maintenance_runs`.
+struct MaintenanceRun {
+    task: &'static str,
+    duration: Duration,
+    rows_pruned: u64,
+    before: DatabaseSize,
+    after: DatabaseSize,
+}
+
+async fn record(pool: &DbPool, run: &MaintenanceRun) -> Result<()> {
+    // Other writes can grow the file meanwhile; that isn't negative reclaiming
+    let reclaimed = (run.before.bytes() - run.after.bytes()).max(0);
+    tracing::info!(
+        task = run.task,
+        rows_pruned = run.rows_pruned,
+        size_bytes = run.after.bytes(),
+        free_bytes = run.after.free_bytes(),
+        reclaimed_bytes = reclaimed,
+        "🧹 Database {} finished in {:?}: {} rows pruned, {} bytes reclaimed",
+        run.task, run.duration, run.rows_pruned, reclaimed,
+    );
+
+    sqlx::query(
+        "INSERT INTO db_maintenance_runs (task, ran_at, duration_ms, rows_pruned, size_before_bytes, size_after_bytes, reclaimed_bytes) VALUES (?, ?, ?, ?, ?, ?, ?)"
+    )
+    .bind(run.task)
+    .bind(format_db_datetime(Utc::now()))
+    .bind(run.duration.as_millis() as i64)
+    .bind(run.rows_pruned as i64)
+    .bind(run.before.bytes())
+    .bind(run.after.bytes())
+    .bind(reclaimed)
+    .execute(pool)
+    .await?;
+
+    sqlx::query("DELETE FROM db_maintenance_runs WHERE task = ? AND id <= (SELECT id FROM db_maintenance_runs WHERE task = ? ORDER BY id DESC LIMIT 1 OFFSET ?)")
+        .bind(run.task)
+        .bind(run.task)
+        .bind(KEPT_RUNS)
+        .execute(pool)
+        .await?;
+    Ok(())
+}
+
+/// Runs `task` and records its effect on the database size. `task` returns the rows it
+/// pruned, or `None` when it found nothing to do, which isn't recorded.
+async fn measured<Fut>(pool: &DbPool, name: &'static str, task: Fut) -> Result<u64>
+where
+    Fut: std::future::Future<Output = Result<Option<u64>>>,
+{
+    let started = Instant::now();
+    let before = size(pool).await?;
+    let Some(rows_pruned) = task.await? else {
+        return Ok(0);
+    };
+    let run = MaintenanceRun {
+        task: name,
+        duration: started.elapsed(),
+        rows_pruned,
+        before,
+        after: size(pool).await?,
+    };
+    record(pool, &run).await?;
+    Ok(rows_pruned)
+}
+
+/// Schedules pruning and `ANALYZE` with incremental vacuum daily, and a full `VACUUM` check
+/// weekly. None of them change what users see, so they leave the cache alone.
+pub fn schedule_maintenance_jobs(scheduler: &mut Scheduler) {
+    scheduler
+        .every("db_prune", PRUNE_INTERVAL, |pool| async move {
+            measured(&pool, "prune", async { prune(&pool).await.map(Some) }).await
+        })
+        .keeps_cache();
+    scheduler
+        .every("db_optimize", OPTIMIZE_INTERVAL, |pool| async move {
+            measured(&pool, "optimize", async { optimize(&pool).await.map(|_| Some(0)) }).await
+        })
+        .keeps_cache();
+    scheduler
+        .every("db_vacuum", VACUUM_INTERVAL, |pool| async move {
+            measured(&pool, "vacuum", async { Ok(vacuum(&pool).await?.then_some(0)) }).await
+        })
+        .keeps_cache();
+}
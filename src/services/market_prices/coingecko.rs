@@ -0,0 +1,54 @@
+use std::collections::HashMap;
+use std::time::Duration;
+
+use anyhow::{anyhow, Result};
+
+use super::Quote;
+use crate::config::MarketDataConfig;
+use crate::services::currency::PIVOT_CURRENCY;
+
+const QUOTE_TIMEOUT: Duration = Duration::from_secs(20);
+
+/// Crypto quotes from a CoinGecko style simple-price endpoint, configured with
+/// `market_data.crypto_quotes_url`, e.g.
+/// `https://api.coingecko.com/api/v3/simple/price?ids={symbol}&vs_currencies={currency}`.
+/// Prices are asked for in [`PIVOT_CURRENCY`] and converted like any other amount.
+pub struct CryptoQuotes {
+    client: reqwest::Client,
+    url: String,
+}
+
+impl CryptoQuotes {
+    pub fn from_config(config: &MarketDataConfig) -> Result<Option<Self>> {
+        let Some(url) = config.crypto_quotes_url.clone() else {
+            return Ok(None);
+        };
+        if !url.contains("{symbol}") {
+            return Err(anyhow!("market_data.crypto_quotes_url must contain {{symbol}}"));
+        }
+        let client = reqwest::Client::builder().timeout(QUOTE_TIMEOUT).build()?;
+        Ok(Some(Self { client, url }))
+    }
+
+    pub async fn quote(&self, symbol: &str) -> Result<Quote> {
+        let currency = PIVOT_CURRENCY.to_lowercase();
+        let url = self.url.replace("{symbol}", symbol).replace("{currency}", &currency);
+        let response = self.client.get(url).send().await?;
+        if !response.status().is_success() {
+            return Err(anyhow!("quote provider returned {} for {}", response.status(), symbol));
+        }
+        // {"bitcoin": {"usd": 67000.12}}
+        let body: HashMap<String, HashMap<String, f64>> = response.json().await?;
+        let price = body
+            .iter()
+            .find(|(id, _)| id.eq_ignore_ascii_case(symbol))
+            .and_then(|(_, prices)| prices.get(&currency))
+            .copied()
+            .ok_or_else(|| anyhow!("no quote for {}", symbol))?;
+
+        Ok(Quote {
+            price,
+            currency: PIVOT_CURRENCY.to_string(),
+        })
+    }
+}
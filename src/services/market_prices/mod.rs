@@ -0,0 +1,159 @@
+mod coingecko;
+mod yahoo;
+
+use std::sync::Arc;
+use std::time::Duration;
+
+use anyhow::Result;
+use chrono::{Duration as ChronoDuration, Utc};
+
+use crate::config::MarketDataConfig;
+use crate::models::{AssetType, MarketQuote};
+use crate::services::database::DbPool;
+use crate::services::scheduler::Scheduler;
+use crate::utils::datetime::format_db_datetime;
+
+use coingecko::CryptoQuotes;
+use yahoo::EquityQuotes;
+
+const PRICE_REFRESH_INTERVAL: Duration = Duration::from_secs(60 * 60);
+
+/// Quotes fetched more recently than this are reused rather than asked for again.
+const QUOTE_FRESH_MINUTES: i64 = 5;
+
+/// A provider's price for one unit of a symbol.
+pub struct Quote {
+    pub price: f64,
+    pub currency: String,
+}
+
+/// Price providers from `market_data` settings; either may be absent.
+pub struct PriceProviders {
+    equities: Option<EquityQuotes>,
+    crypto: Option<CryptoQuotes>,
+}
+
+impl PriceProviders {
+    pub fn from_config(config: &MarketDataConfig) -> Self {
+        let equities = match EquityQuotes::from_config(config) {
+            Ok(provider) => provider,
+            Err(e) => {
+                tracing::error!("Stock and fund prices disabled: {}", e);
+                None
+            }
+        };
+        let crypto = match CryptoQuotes::from_config(config) {
+            Ok(provider) => provider,
+            Err(e) => {
+                tracing::error!("Crypto prices disabled: {}", e);
+                None
+            }
+        };
+        Self { equities, crypto }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.equities.is_none() && self.crypto.is_none()
+    }
+
+    /// `None` if no provider is configured for `asset_type`.
+    async fn quote(&self, asset_type: AssetType, symbol: &str) -> Option<Result<Quote>> {
+        match asset_type {
+            AssetType::Stock | AssetType::MutualFund => match &self.equities {
+                Some(equities) => Some(equities.quote(symbol).await),
+                None => None,
+            },
+            AssetType::Crypto => match &self.crypto {
+                Some(crypto) => Some(crypto.quote(symbol).await),
+                None => None,
+            },
+        }
+    }
+}
+
+/// The cached quote for a symbol, if one has been fetched.
+pub async fn latest_quote(pool: &DbPool, asset_type: AssetType, symbol: &str) -> Result<Option<MarketQuote>> {
+    Ok(sqlx::query_as::<_, MarketQuote>("SELECT * FROM market_prices WHERE asset_type = ? AND symbol = ?")
+        .bind(asset_type)
+        .bind(symbol)
+        .fetch_optional(pool)
+        .await?)
+}
+
+/// Fetches a fresh quote for every held symbol (only `user_id`'s, when given) and caches it.
+/// Symbols quoted in the last few minutes are skipped; a failed symbol keeps its old quote.
+/// Returns the number of quotes stored.
+pub async fn refresh_prices(pool: &DbPool, providers: &PriceProviders, user_id: Option<&str>) -> Result<usize> {
+    let fresh_since = format_db_datetime(Utc::now() - ChronoDuration::minutes(QUOTE_FRESH_MINUTES));
+    let symbols = sqlx::query_as::<_, (AssetType, String)>(
+        r#"
+        SELECT DISTINCT h.asset_type, h.symbol FROM holdings h
+        WHERE (? IS NULL OR h.user_id = ?)
+          AND NOT EXISTS (
+              SELECT 1 FROM market_prices p
+              WHERE p.asset_type = h.asset_type AND p.symbol = h.symbol AND p.fetched_at >= ?
+          )
+        "#,
+    )
+    .bind(user_id)
+    .bind(user_id)
+    .bind(&fresh_since)
+    .fetch_all(pool)
+    .await?;
+
+    let mut stored = 0;
+    for (asset_type, symbol) in symbols {
+        let quote = match providers.quote(asset_type, &symbol).await {
+            Some(Ok(quote)) if quote.price.is_finite() && quote.price >= 0.0 => quote,
+            Some(Ok(quote)) => {
+                tracing::warn!("Ignoring price {} for {} {}", quote.price, asset_type.as_str(), symbol);
+                continue;
+            }
+            Some(Err(e)) => {
+                tracing::warn!("Price fetch for {} {} failed: {}", asset_type.as_str(), symbol, e);
+                continue;
+            }
+            None => continue,
+        };
+
+        sqlx::query(
+            r#"
+            INSERT INTO market_prices (asset_type, symbol, price, currency, fetched_at)
+            VALUES (?, ?, ?, ?, ?)
+            ON CONFLICT (asset_type, symbol) DO UPDATE SET price = excluded.price, currency = excluded.currency, fetched_at = excluded.fetched_at
+            "#,
+        )
+        .bind(asset_type)
+        .bind(&symbol)
+        .bind(quote.price)
+        .bind(&quote.currency)
+        .bind(format_db_datetime(Utc::now()))
+        .execute(pool)
+        .await?;
+        stored += 1;
+    }
+    Ok(stored)
+}
+
+/// Schedules the hourly price refresh if at least one provider is configured. Without one,
+/// holdings are valued at cost.
+pub fn schedule_price_refresh_job(scheduler: &mut Scheduler, config: &MarketDataConfig) {
+    let providers = PriceProviders::from_config(config);
+    if providers.is_empty() {
+        tracing::info!("Market price refresh disabled: neither market_data.equity_quotes_url nor market_data.crypto_quotes_url is set");
+        return;
+    }
+
+    let providers = Arc::new(providers);
+    scheduler.every("market_prices", PRICE_REFRESH_INTERVAL, move |pool| {
+        let providers = providers.clone();
+        async move {
+            let stored = refresh_prices(&pool, &providers, None).await?;
+            if stored > 0 {
+                tracing::info!("Stored {} market prices", stored);
+            }
+            Ok(stored as u64)
+        }
+    })
+    .only_when(|features| features.enabled(features.market_prices));
+}
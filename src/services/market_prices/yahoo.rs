@@ -0,0 +1,70 @@
+use std::time::Duration;
+
+use anyhow::{anyhow, Result};
+use serde::Deserialize;
+
+use super::Quote;
+use crate::config::MarketDataConfig;
+
+const QUOTE_TIMEOUT: Duration = Duration::from_secs(20);
+
+#[derive(Deserialize)]
+struct ChartResponse {
+    chart: Chart,
+}
+
+#[derive(Deserialize)]
+struct Chart {
+    result: Option<Vec<ChartResult>>,
+}
+
+#[derive(Deserialize)]
+struct ChartResult {
+    meta: ChartMeta,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct ChartMeta {
+    regular_market_price: f64,
+    currency: String,
+}
+
+/// Stock and mutual fund quotes from a Yahoo Finance style chart endpoint, configured with
+/// `market_data.equity_quotes_url`, e.g. `https://query1.finance.yahoo.com/v8/finance/chart/{symbol}`.
+pub struct EquityQuotes {
+    client: reqwest::Client,
+    url: String,
+}
+
+impl EquityQuotes {
+    pub fn from_config(config: &MarketDataConfig) -> Result<Option<Self>> {
+        let Some(url) = config.equity_quotes_url.clone() else {
+            return Ok(None);
+        };
+        if !url.contains("{symbol}") {
+            return Err(anyhow!("market_data.equity_quotes_url must contain {{symbol}}"));
+        }
+        let client = reqwest::Client::builder().timeout(QUOTE_TIMEOUT).build()?;
+        Ok(Some(Self { client, url }))
+    }
+
+    pub async fn quote(&self, symbol: &str) -> Result<Quote> {
+        let response = self.client.get(self.url.replace("{symbol}", symbol)).send().await?;
+        if !response.status().is_success() {
+            return Err(anyhow!("quote provider returned {} for {}", response.status(), symbol));
+        }
+        let body: ChartResponse = response.json().await?;
+        let meta = body
+            .chart
+            .result
+            .and_then(|results| results.into_iter().next())
+            .map(|result| result.meta)
+            .ok_or_else(|| anyhow!("no quote for {}", symbol))?;
+
+        Ok(Quote {
+            price: meta.regular_market_price,
+            currency: meta.currency.to_uppercase(),
+        })
+    }
+}
@@ -1,3 +1,56 @@
 pub mod database;
+pub mod category_model;
+pub mod budget_rollover;
+pub mod budget_progress;
+pub mod amortization;
+pub mod goal_contributions;
+pub mod events;
+pub mod recurring;
+pub mod export;
+pub mod import;
+pub mod usage;
+pub mod history;
+pub mod sessions;
+pub mod calendar;
+pub mod net_worth;
+pub mod notifications;
+pub mod reminders;
+pub mod push;
+pub mod currency;
+pub mod email_tokens;
+pub mod mailer;
+pub mod webhooks;
+pub mod reports;
+pub mod dashboard;
+pub mod stats;
+pub mod statement;
+pub mod archive;
+pub mod market_prices;
+pub mod investments;
+pub mod term_deposits;
+pub mod card_statements;
+pub mod credit_utilization;
+pub mod emi_plans;
+pub mod subscriptions;
+pub mod households;
+pub mod splits;
+pub mod sync;
+pub mod concurrency;
+pub mod live;
+pub mod etags;
+pub mod seed;
+pub mod shutdown;
+pub mod admin;
+pub mod scheduler;
+pub mod cache;
+pub mod maintenance;
+pub mod quotas;
+pub mod reload;
+pub mod storage;
+pub mod avatars;
+pub mod user_time;
+pub mod preferences;
+pub mod category_presets;
+pub mod reconciliations;
 
 pub use database::*;
\ No newline at end of file
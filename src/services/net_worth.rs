@@ -0,0 +1,104 @@
+use std::collections::BTreeMap;
+
+use anyhow::Result;
+use chrono::Utc;
+use sqlx::Row;
+
+use crate::models::{ConsolidatedNetWorth, NetWorthTotals};
+use crate::services::currency::{self, Converter};
+use crate::services::database::DbPool;
+use crate::services::investments;
+use crate::utils::money::Money;
+
+/// Net worth per currency. Accounts flagged `exclude_from_totals` are left out; the number of
+/// such accounts is returned alongside so clients can say so.
+pub async fn net_worth(pool: &DbPool, user_id: &str) -> Result<(Vec<NetWorthTotals>, i64)> {
+    let mut totals: BTreeMap<String, (Money, Money)> = BTreeMap::new();
+
+    let accounts = sqlx::query(
+        "SELECT balance, currency FROM accounts WHERE user_id = ? AND exclude_from_totals = FALSE"
+    )
+    .bind(user_id)
+    .fetch_all(pool)
+    .await?;
+    for row in accounts {
+        let balance: Money = row.get("balance");
+        let entry = totals.entry(row.get("currency")).or_default();
+        if !balance.is_negative() {
+            entry.0 += balance;
+        } else {
+            entry.1 += -balance;
+        }
+    }
+
+    let loans = sqlx::query("SELECT amount, currency FROM loans WHERE user_id = ? AND is_returned = FALSE")
+        .bind(user_id)
+        .fetch_all(pool)
+        .await?;
+    for row in loans {
+        totals.entry(row.get("currency")).or_default().0 += row.get::<Money, _>("amount");
+    }
+
+    let liabilities = sqlx::query("SELECT amount, currency FROM liabilities WHERE user_id = ? AND is_paid = FALSE")
+        .bind(user_id)
+        .fetch_all(pool)
+        .await?;
+    for row in liabilities {
+        totals.entry(row.get("currency")).or_default().1 += row.get::<Money, _>("amount");
+    }
+
+    let investments = investments::totals_by_currency(pool, user_id).await?;
+    for (currency, value) in &investments {
+        totals.entry(currency.clone()).or_default().0 += *value;
+    }
+
+    let excluded = sqlx::query_scalar::<_, i64>(
+        "SELECT COUNT(*) FROM accounts WHERE user_id = ? AND exclude_from_totals = TRUE"
+    )
+    .bind(user_id)
+    .fetch_one(pool)
+    .await?;
+
+    let totals = totals
+        .into_iter()
+        .map(|(currency, (assets, debts))| NetWorthTotals {
+            investments: investments.get(&currency).copied().unwrap_or_default(),
+            currency,
+            assets,
+            debts,
+            net_worth: assets - debts,
+        })
+        .collect();
+    Ok((totals, excluded))
+}
+
+/// Sums per-currency `totals` into `currency` at today's rates. Currencies with no known rate
+/// are listed rather than guessed at.
+pub async fn consolidate(
+    pool: &DbPool,
+    user_id: &str,
+    totals: &[NetWorthTotals],
+    currency: &str,
+) -> Result<ConsolidatedNetWorth> {
+    let today = Utc::now().date_naive();
+    let mut converter = Converter::new(pool, user_id, currency);
+    let (mut assets, mut debts) = (Money::ZERO, Money::ZERO);
+    let mut unconverted_currencies = Vec::new();
+    for total in totals {
+        match converter.rate(&total.currency, today).await? {
+            Some(rate) => {
+                assets += currency::round(total.assets * rate, currency);
+                debts += currency::round(total.debts * rate, currency);
+            }
+            None => unconverted_currencies.push(total.currency.clone()),
+        }
+    }
+
+    Ok(ConsolidatedNetWorth {
+        currency: currency.to_string(),
+        assets,
+        debts,
+        net_worth: assets - debts,
+        unconverted_currencies,
+    })
+}
@@ -0,0 +1,151 @@
+use std::time::Duration;
+
+use anyhow::Result;
+use chrono::{Duration as ChronoDuration, Utc};
+use uuid::Uuid;
+
+use crate::models::Notification;
+use crate::services::database::DbPool;
+use crate::services::scheduler::Scheduler;
+use crate::utils::datetime::format_db_datetime;
+use crate::utils::pagination::{PageQuery, Paginated};
+
+/// Read notifications are deleted after this many days; unread ones are kept.
+const READ_RETENTION_DAYS: i64 = 90;
+
+const NOTIFICATION_PRUNE_INTERVAL: Duration = Duration::from_secs(24 * 60 * 60);
+
+/// A notification to queue for a user.
+pub struct NewNotification<'a> {
+    pub user_id: &'a str,
+    pub kind: &'a str,
+    pub title: String,
+    pub body: String,
+    /// The entity the notification is about, so clients can link to it.
+    pub entity: Option<(&'a str, &'a str)>,
+    /// Notifications sharing a key are only ever queued once per user, which lets periodic
+    /// checks re-run without repeating themselves.
+    pub dedupe_key: Option<String>,
+}
+
+/// Queues a notification. Returns `false` if one with the same dedupe key already exists.
+pub async fn enqueue(pool: &DbPool, notification: NewNotification<'_>) -> Result<bool> {
+    let (entity_type, entity_id) = notification.entity.unzip();
+    let result = sqlx::query(
+        r#"
+        INSERT INTO notifications (id, user_id, kind, title, body, entity_type, entity_id, dedupe_key, created_at)
+        VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?)
+        ON CONFLICT (user_id, dedupe_key) DO NOTHING
+        "#,
+    )
+    .bind(Uuid::new_v4().to_string())
+    .bind(notification.user_id)
+    .bind(notification.kind)
+    .bind(&notification.title)
+    .bind(&notification.body)
+    .bind(entity_type)
+    .bind(entity_id)
+    .bind(&notification.dedupe_key)
+    .bind(format_db_datetime(Utc::now()))
+    .execute(pool)
+    .await?;
+
+    Ok(result.rows_affected() > 0)
+}
+
+/// Queues a notification, logging instead of failing; alerts must never break the caller.
+pub async fn notify(pool: &DbPool, notification: NewNotification<'_>) {
+    let kind = notification.kind.to_string();
+    let user_id = notification.user_id.to_string();
+    if let Err(e) = enqueue(pool, notification).await {
+        tracing::error!("Failed to queue {} notification for user {}: {}", kind, user_id, e);
+    }
+}
+
+pub async fn list(pool: &DbPool, user_id: &str, unread_only: bool, page: &PageQuery) -> Result<Paginated<Notification>> {
+    let notifications = sqlx::query_as::<_, Notification>(
+        r#"
+        SELECT id, user_id, kind, title, body, entity_type, entity_id, read_at, created_at
+        FROM notifications
+        WHERE user_id = ? AND (? = FALSE OR read_at IS NULL)
+        ORDER BY created_at DESC, rowid DESC
+        LIMIT ? OFFSET ?
+        "#,
+    )
+    .bind(user_id)
+    .bind(unread_only)
+    .bind(page.limit() as i64)
+    .bind(page.offset() as i64)
+    .fetch_all(pool)
+    .await?;
+
+    let total = sqlx::query_scalar::<_, i64>(
+        "SELECT COUNT(*) FROM notifications WHERE user_id = ? AND (? = FALSE OR read_at IS NULL)",
+    )
+    .bind(user_id)
+    .bind(unread_only)
+    .fetch_one(pool)
+    .await?;
+
+    Ok(Paginated::new(notifications, total as usize, page))
+}
+
+pub async fn unread_count(pool: &DbPool, user_id: &str) -> Result<i64> {
+    let count = sqlx::query_scalar::<_, i64>(
+        "SELECT COUNT(*) FROM notifications WHERE user_id = ? AND read_at IS NULL",
+    )
+    .bind(user_id)
+    .fetch_one(pool)
+    .await?;
+
+    Ok(count)
+}
+
+/// Marks one notification read. Returns `false` if it doesn't exist for this user.
+pub async fn mark_read(pool: &DbPool, user_id: &str, id: &str) -> Result<bool> {
+    let exists = sqlx::query_scalar::<_, i64>("SELECT COUNT(*) FROM notifications WHERE id = ? AND user_id = ?")
+        .bind(id)
+        .bind(user_id)
+        .fetch_one(pool)
+        .await?
+        > 0;
+
+    sqlx::query("UPDATE notifications SET read_at = ? WHERE id = ? AND user_id = ? AND read_at IS NULL")
+        .bind(format_db_datetime(Utc::now()))
+        .bind(id)
+        .bind(user_id)
+        .execute(pool)
+        .await?;
+
+    Ok(exists)
+}
+
+pub async fn mark_all_read(pool: &DbPool, user_id: &str) -> Result<u64> {
+    let result = sqlx::query("UPDATE notifications SET read_at = ? WHERE user_id = ? AND read_at IS NULL")
+        .bind(format_db_datetime(Utc::now()))
+        .bind(user_id)
+        .execute(pool)
+        .await?;
+
+    Ok(result.rows_affected())
+}
+
+pub async fn prune_read(pool: &DbPool) -> Result<u64> {
+    let cutoff = format_db_datetime(Utc::now() - ChronoDuration::days(READ_RETENTION_DAYS));
+    let result = sqlx::query("DELETE FROM notifications WHERE read_at IS NOT NULL AND read_at < ?")
+        .bind(cutoff)
+        .execute(pool)
+        .await?;
+
+    Ok(result.rows_affected())
+}
+
+pub fn schedule_notification_prune_job(scheduler: &mut Scheduler) {
+    scheduler.every("notification_prune", NOTIFICATION_PRUNE_INTERVAL, |pool| async move {
+        let removed = prune_read(&pool).await?;
+        if removed > 0 {
+            tracing::info!("Pruned {} old read notifications", removed);
+        }
+        Ok(removed)
+    });
+}
@@ -0,0 +1,331 @@
+use anyhow::Result;
+use axum::http::{header, HeaderMap};
+use chrono::Utc;
+use serde_json::{json, Map, Value};
+use sqlx::SqliteConnection;
+
+use crate::models::{CurrencyInfo, UserPreference, UserSetting};
+use crate::services::cache;
+use crate::services::credit_utilization::MAX_THRESHOLDS;
+use crate::services::database::DbPool;
+use crate::services::sessions::{MAX_IDLE_TIMEOUT_MINUTES, MIN_IDLE_TIMEOUT_MINUTES};
+use crate::services::user_time;
+use crate::utils::datetime::format_db_datetime;
+use crate::utils::error::AppError;
+
+pub const DISPLAY_CURRENCY: &str = "displayCurrency";
+pub const SESSION_IDLE_TIMEOUT_MINUTES: &str = "sessionIdleTimeoutMinutes";
+pub const CREDIT_UTILIZATION_THRESHOLDS: &str = "creditUtilizationThresholds";
+pub const TIMEZONE: &str = "timezone";
+pub const LOCALE: &str = "locale";
+pub const FIRST_DAY_OF_WEEK: &str = "firstDayOfWeek";
+
+/// The apps' own settings, kept as one JSON object.
+pub const CLIENT: &str = "client";
+
+/// Settings the server validates and reads, with the snake_case names older clients send.
+const KNOWN_KEYS: &[(&str, &str)] = &[
+    (DISPLAY_CURRENCY, "display_currency"),
+    (SESSION_IDLE_TIMEOUT_MINUTES, "session_idle_timeout_minutes"),
+    (CREDIT_UTILIZATION_THRESHOLDS, "credit_utilization_thresholds"),
+    (TIMEZONE, "timezone"),
+    (LOCALE, "locale"),
+    (FIRST_DAY_OF_WEEK, "first_day_of_week"),
+    (CLIENT, "client"),
+];
+
+/// Fields of the document that can't be set.
+const READ_ONLY_KEYS: &[&str] = &["userId", "user_id", "updatedAt", "updated_at"];
+
+/// The body field naming the revision an update is based on.
+const REVISION: &str = "revision";
+
+/// Largest the `client` object may grow to, serialized.
+pub const MAX_CLIENT_SETTINGS_BYTES: usize = 16 * 1024;
+
+/// A change to one setting: the value to store, or `None` to go back to the default. For
+/// [`CLIENT`] the value is a merge patch (RFC 7396) over the stored object.
+pub type Change = (&'static str, Option<Value>);
+
+/// The value `key` is stored as, or `None` for null, which resets it. Errors are messages for
+/// the user.
+fn normalize(key: &str, value: &Value) -> Result<Option<Value>, String> {
+    if value.is_null() {
+        return Ok(None);
+    }
+    let normalized = match key {
+        // Only currencies listed by GET /api/currencies have formatting rules to display in
+        DISPLAY_CURRENCY => value
+            .as_str()
+            .and_then(CurrencyInfo::find)
+            .map(|info| json!(info.code))
+            .ok_or("Unsupported display currency")?,
+        SESSION_IDLE_TIMEOUT_MINUTES => value
+            .as_i64()
+            .filter(|minutes| (MIN_IDLE_TIMEOUT_MINUTES..=MAX_IDLE_TIMEOUT_MINUTES).contains(minutes))
+            .map(|minutes| json!(minutes))
+            .ok_or("Session idle timeout is out of range")?,
+        CREDIT_UTILIZATION_THRESHOLDS => {
+            let percents: Option<Vec<u32>> = value.as_array().and_then(|values| {
+                values
+                    .iter()
+                    .map(|v| v.as_u64().and_then(|p| u32::try_from(p).ok()).filter(|p| (1..=100).contains(p)))
+                    .collect()
+            });
+            match percents {
+                Some(mut percents) if !percents.is_empty() && percents.len() <= MAX_THRESHOLDS => {
+                    percents.sort_unstable();
+                    percents.dedup();
+                    json!(percents)
+                }
+                _ => return Err("Invalid credit utilization thresholds".to_string()),
+            }
+        }
+        TIMEZONE => value
+            .as_str()
+            .and_then(user_time::parse_timezone)
+            .map(|timezone| json!(timezone.name()))
+            .ok_or("Unknown timezone")?,
+        LOCALE => value
+            .as_str()
+            .filter(|tag| user_time::is_locale(tag))
+            .map(|tag| json!(tag))
+            .ok_or("Invalid locale")?,
+        FIRST_DAY_OF_WEEK => value
+            .as_str()
+            .and_then(user_time::parse_weekday)
+            .map(|day| json!(user_time::weekday_name(day)))
+            .ok_or("Invalid first day of week")?,
+        CLIENT => match value {
+            Value::Object(_) if value.to_string().len() <= MAX_CLIENT_SETTINGS_BYTES => value.clone(),
+            Value::Object(_) => return Err("Client settings are too large".to_string()),
+            _ => return Err("Client settings must be an object".to_string()),
+        },
+        _ => return Err(format!("Unknown preference '{}'", key)),
+    };
+    Ok(Some(normalized))
+}
+
+/// The revision a `PUT /api/preferences` is based on: `If-Match` when sent (`"3"`, `W/"3"` or
+/// `3`), otherwise the body's `revision`. Without either, the update applies to whatever is
+/// current. Errors are messages for the user.
+pub fn expected_revision(headers: &HeaderMap, body: &Value) -> Result<Option<i64>, String> {
+    if let Some(if_match) = headers.get(header::IF_MATCH) {
+        return if_match
+            .to_str()
+            .ok()
+            .map(|value| value.trim().trim_start_matches("W/").trim_matches('"'))
+            .and_then(|value| value.parse().ok())
+            .map(Some)
+            .ok_or_else(|| "Invalid If-Match header".to_string());
+    }
+    match body.get(REVISION) {
+        None | Some(Value::Null) => Ok(None),
+        Some(revision) => revision.as_i64().map(Some).ok_or_else(|| "Invalid revision".to_string()),
+    }
+}
+
+/// Reads a `PUT /api/preferences` body. Settings left out stay as they are. Errors are messages
+/// for the user.
+pub fn parse_update(body: &Value) -> Result<Vec<Change>, String> {
+    let fields = body.as_object().ok_or("Preferences must be an object")?;
+    let mut changes = Vec::new();
+    for (name, value) in fields {
+        if name == REVISION || READ_ONLY_KEYS.contains(&name.as_str()) {
+            continue;
+        }
+        let key = KNOWN_KEYS
+            .iter()
+            .find(|(key, alias)| key == name || alias == name)
+            .map(|(key, _)| *key)
+            .ok_or_else(|| format!("Unknown preference '{}'; app settings belong under '{}'", name, CLIENT))?;
+        changes.push((key, normalize(key, value)?));
+    }
+    Ok(changes)
+}
+
+/// Puts a stored setting into `preferences`. Keys this server doesn't know are skipped.
+fn apply(preferences: &mut UserPreference, key: &str, value: Value) -> serde_json::Result<()> {
+    match key {
+        DISPLAY_CURRENCY => preferences.display_currency = serde_json::from_value(value)?,
+        SESSION_IDLE_TIMEOUT_MINUTES => preferences.session_idle_timeout_minutes = serde_json::from_value(value)?,
+        CREDIT_UTILIZATION_THRESHOLDS => preferences.credit_utilization_thresholds = serde_json::from_value(value)?,
+        TIMEZONE => preferences.timezone = serde_json::from_value(value)?,
+        LOCALE => preferences.locale = serde_json::from_value(value)?,
+        FIRST_DAY_OF_WEEK => preferences.first_day_of_week = serde_json::from_value(value)?,
+        CLIENT => preferences.client = serde_json::from_value(value)?,
+        _ => {}
+    }
+    Ok(())
+}
+
+async fn read(pool: &DbPool, user_id: &str) -> Result<UserPreference> {
+    let settings = sqlx::query_as::<_, UserSetting>("SELECT key, value FROM user_settings WHERE user_id = ?")
+        .bind(user_id)
+        .fetch_all(pool)
+        .await?;
+
+    let mut preferences = UserPreference::defaults(user_id);
+    for setting in settings {
+        let applied = serde_json::from_str(&setting.value).and_then(|value| apply(&mut preferences, &setting.key, value));
+        if let Err(e) = applied {
+            tracing::warn!("Ignoring unreadable preference {} of user {}: {}", setting.key, user_id, e);
+        }
+    }
+
+    let revision = sqlx::query_as::<_, (i64, String)>("SELECT revision, updated_at FROM preference_revisions WHERE user_id = ?")
+        .bind(user_id)
+        .fetch_optional(pool)
+        .await?;
+    if let Some((revision, updated_at)) = revision {
+        preferences.revision = revision;
+        preferences.updated_at = Some(updated_at);
+    }
+    Ok(preferences)
+}
+
+/// Records a change to `user_id`'s preferences and returns the new revision.
+pub async fn bump_revision(conn: &mut SqliteConnection, user_id: &str) -> sqlx::Result<i64> {
+    sqlx::query_scalar(
+        r#"
+        INSERT INTO preference_revisions (user_id, revision, updated_at) VALUES (?, 1, ?)
+        ON CONFLICT(user_id) DO UPDATE SET revision = revision + 1, updated_at = excluded.updated_at
+        RETURNING revision
+        "#,
+    )
+    .bind(user_id)
+    .bind(format_db_datetime(Utc::now()))
+    .fetch_one(conn)
+    .await
+}
+
+/// The merged preferences of `user_id`.
+pub async fn load(pool: &DbPool, user_id: &str) -> Result<UserPreference> {
+    cache::get().cached(user_id, "preferences", read(pool, user_id)).await
+}
+
+/// Applies `changes` in one transaction and returns the merged preferences. With `expected`,
+/// nothing changes unless the preferences are still at that revision; otherwise the answer is
+/// 409 Conflict with the current copy.
+pub async fn update(
+    pool: &DbPool,
+    user_id: &str,
+    changes: &[Change],
+    expected: Option<i64>,
+) -> Result<UserPreference, AppError> {
+    let internal_error = |e: sqlx::Error| {
+        tracing::error!("Failed to update preferences of user {}: {}", user_id, e);
+        AppError::Internal("Failed to update preferences".into())
+    };
+    let load_error = |e: anyhow::Error| {
+        tracing::error!("Failed to load preferences of user {}: {}", user_id, e);
+        AppError::Internal("Failed to update preferences".into())
+    };
+    let now = format_db_datetime(Utc::now());
+    let mut tx = pool.begin().await.map_err(internal_error)?;
+
+    if let Some(expected) = expected {
+        let revision: i64 = sqlx::query_scalar("SELECT COALESCE(MAX(revision), 0) FROM preference_revisions WHERE user_id = ?")
+            .bind(user_id)
+            .fetch_one(&mut tx)
+            .await
+            .map_err(internal_error)?;
+        if revision != expected {
+            tx.rollback().await.map_err(internal_error)?;
+            tracing::warn!("Stale preferences update of user {}: based on revision {}, server has {}", user_id, expected, revision);
+            let current = read(pool, user_id).await.map_err(load_error)?;
+            return Err(AppError::Stale {
+                message: "Your preferences were changed on another device; apply your changes to the current copy and retry".into(),
+                current: json!(current),
+            });
+        }
+    }
+
+    for (key, value) in changes {
+        let Some(value) = value else {
+            sqlx::query("DELETE FROM user_settings WHERE user_id = ? AND key = ?")
+                .bind(user_id)
+                .bind(key)
+                .execute(&mut tx)
+                .await
+                .map_err(internal_error)?;
+            continue;
+        };
+        // Client settings are patched in place; json_patch drops the members set to null
+        let sql = if *key == CLIENT {
+            r#"
+            INSERT INTO user_settings (user_id, key, value, updated_at) VALUES (?1, ?2, json_patch('{}', ?3), ?4)
+            ON CONFLICT(user_id, key) DO UPDATE SET value = json_patch(value, ?3), updated_at = ?4
+            RETURNING value
+            "#
+        } else {
+            r#"
+            INSERT INTO user_settings (user_id, key, value, updated_at) VALUES (?1, ?2, ?3, ?4)
+            ON CONFLICT(user_id, key) DO UPDATE SET value = excluded.value, updated_at = excluded.updated_at
+            RETURNING value
+            "#
+        };
+        let stored: String = sqlx::query_scalar(sql)
+            .bind(user_id)
+            .bind(key)
+            .bind(value.to_string())
+            .bind(&now)
+            .fetch_one(&mut tx)
+            .await
+            .map_err(internal_error)?;
+        if *key == CLIENT && stored.len() > MAX_CLIENT_SETTINGS_BYTES {
+            tracing::warn!("Rejected client settings of user {}: {} bytes", user_id, stored.len());
+            return Err(AppError::BadRequest("Client settings are too large".into()));
+        }
+    }
+
+    if !changes.is_empty() {
+        bump_revision(&mut tx, user_id).await.map_err(internal_error)?;
+    }
+    tx.commit().await.map_err(internal_error)?;
+    read(pool, user_id).await.map_err(load_error)
+}
+
+/// The user's preferences if they changed at or after `since`, for a sync pull; always for a
+/// first pull.
+pub async fn changed_since(pool: &DbPool, user_id: &str, since: Option<&str>) -> Result<Option<UserPreference>> {
+    if let Some(since) = since {
+        let changed: bool = sqlx::query_scalar("SELECT EXISTS (SELECT 1 FROM preference_revisions WHERE user_id = ? AND updated_at >= ?)")
+            .bind(user_id)
+            .bind(since)
+            .fetch_one(pool)
+            .await?;
+        if !changed {
+            return Ok(None);
+        }
+    }
+    Ok(Some(read(pool, user_id).await?))
+}
+
+/// The `user_settings` rows of a `user_preferences` row from an archive made before settings
+/// were stored by key. Empty or invalid columns are skipped.
+pub fn legacy_settings(row: &Map<String, Value>) -> Vec<Map<String, Value>> {
+    let updated_at = row.get("updated_at").cloned().unwrap_or(Value::Null);
+    let thresholds = row.get("credit_utilization_thresholds").and_then(Value::as_str).map(|stored| {
+        json!(stored.split(',').filter_map(|part| part.trim().parse::<u32>().ok()).collect::<Vec<_>>())
+    });
+    let values = [
+        (DISPLAY_CURRENCY, row.get("display_currency").cloned()),
+        (SESSION_IDLE_TIMEOUT_MINUTES, row.get("session_idle_timeout_minutes").cloned()),
+        (CREDIT_UTILIZATION_THRESHOLDS, thresholds),
+        (TIMEZONE, row.get("timezone").cloned()),
+        (LOCALE, row.get("locale").cloned()),
+        (FIRST_DAY_OF_WEEK, row.get("first_day_of_week").cloned()),
+    ];
+    values
+        .into_iter()
+        .filter_map(|(key, value)| {
+            let value = normalize(key, &value?).ok()??;
+            let mut setting = Map::new();
+            setting.insert("key".to_string(), json!(key));
+            setting.insert("value".to_string(), json!(value.to_string()));
+            setting.insert("updated_at".to_string(), updated_at.clone());
+            Some(setting)
+        })
+        .collect()
+}
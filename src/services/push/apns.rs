@@ -0,0 +1,109 @@
+use anyhow::{anyhow, Result};
+use chrono::{DateTime, Duration, Utc};
+use jsonwebtoken::{encode, Algorithm, EncodingKey, Header};
+use serde::Serialize;
+use serde_json::{json, Value};
+
+use super::{PushMessage, SendOutcome};
+use crate::config::PushConfig;
+
+/// Apple rejects provider tokens older than an hour and throttles ones refreshed more often
+/// than every 20 minutes.
+const PROVIDER_TOKEN_LIFETIME_MINUTES: i64 = 40;
+
+#[derive(Serialize)]
+struct ProviderClaims<'a> {
+    iss: &'a str,
+    iat: i64,
+}
+
+/// Sends through APNs with token-based auth. Configured by `push.apns_key_path` (the .p8 key),
+/// `apns_key_id`, `apns_team_id`, `apns_topic` (the app bundle id) and optionally
+/// `apns_sandbox`.
+pub struct ApnsSender {
+    client: reqwest::Client,
+    key: EncodingKey,
+    key_id: String,
+    team_id: String,
+    topic: String,
+    base_url: &'static str,
+    provider_token: Option<(String, DateTime<Utc>)>,
+}
+
+fn required(value: &Option<String>, name: &str) -> Result<String> {
+    value.clone().ok_or_else(|| anyhow!("push.{} is not set", name))
+}
+
+impl ApnsSender {
+    pub fn from_config(config: &PushConfig) -> Result<Option<Self>> {
+        let Some(key_path) = &config.apns_key_path else {
+            return Ok(None);
+        };
+        let key = EncodingKey::from_ec_pem(&std::fs::read(key_path)?)?;
+
+        Ok(Some(Self {
+            client: reqwest::Client::builder().http2_prior_knowledge().build()?,
+            key,
+            key_id: required(&config.apns_key_id, "apns_key_id")?,
+            team_id: required(&config.apns_team_id, "apns_team_id")?,
+            topic: required(&config.apns_topic, "apns_topic")?,
+            base_url: if config.apns_sandbox {
+                "https://api.sandbox.push.apple.com"
+            } else {
+                "https://api.push.apple.com"
+            },
+            provider_token: None,
+        }))
+    }
+
+    fn provider_token(&mut self) -> Result<String> {
+        if let Some((token, issued_at)) = &self.provider_token {
+            if Utc::now() - *issued_at < Duration::minutes(PROVIDER_TOKEN_LIFETIME_MINUTES) {
+                return Ok(token.clone());
+            }
+        }
+
+        let mut header = Header::new(Algorithm::ES256);
+        header.kid = Some(self.key_id.clone());
+        let now = Utc::now();
+        let token = encode(&header, &ProviderClaims { iss: &self.team_id, iat: now.timestamp() }, &self.key)?;
+        self.provider_token = Some((token.clone(), now));
+        Ok(token)
+    }
+
+    pub async fn send(&mut self, token: &str, message: &PushMessage<'_>) -> SendOutcome {
+        let provider_token = match self.provider_token() {
+            Ok(provider_token) => provider_token,
+            Err(e) => return SendOutcome::Failed(format!("APNs authentication failed: {}", e)),
+        };
+
+        let body = json!({
+            "aps": { "alert": { "title": message.title, "body": message.body }, "sound": "default" },
+            "notificationId": message.notification_id,
+            "kind": message.kind
+        });
+        let response = self
+            .client
+            .post(format!("{}/3/device/{}", self.base_url, token))
+            .header("authorization", format!("bearer {}", provider_token))
+            .header("apns-topic", &self.topic)
+            .header("apns-push-type", "alert")
+            .json(&body)
+            .send()
+            .await;
+        let response = match response {
+            Ok(response) => response,
+            Err(e) => return SendOutcome::Failed(e.to_string()),
+        };
+
+        let status = response.status();
+        if status.is_success() {
+            return SendOutcome::Delivered;
+        }
+        let error: Value = response.json().await.unwrap_or(Value::Null);
+        match (status.as_u16(), error["reason"].as_str().unwrap_or_default()) {
+            (410, _) | (400, "BadDeviceToken") | (400, "DeviceTokenNotForTopic") => SendOutcome::InvalidToken,
+            _ => SendOutcome::Failed(format!("APNs returned {}: {}", status, error)),
+        }
+    }
+}
@@ -0,0 +1,141 @@
+use anyhow::{anyhow, Result};
+use chrono::{DateTime, Duration, Utc};
+use jsonwebtoken::{encode, Algorithm, EncodingKey, Header};
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+
+use super::{PushMessage, SendOutcome};
+use crate::config::PushConfig;
+
+const FCM_SCOPE: &str = "https://www.googleapis.com/auth/firebase.messaging";
+
+/// The fields of a Google service-account key file needed to mint access tokens.
+#[derive(Deserialize)]
+struct ServiceAccount {
+    project_id: String,
+    client_email: String,
+    private_key: String,
+    token_uri: String,
+}
+
+#[derive(Serialize)]
+struct AssertionClaims<'a> {
+    iss: &'a str,
+    scope: &'a str,
+    aud: &'a str,
+    iat: i64,
+    exp: i64,
+}
+
+#[derive(Deserialize)]
+struct AccessToken {
+    access_token: String,
+    expires_in: i64,
+}
+
+/// Sends through the FCM HTTP v1 API, authenticating with the service account named by
+/// `push.fcm_service_account_path`.
+pub struct FcmSender {
+    client: reqwest::Client,
+    account: ServiceAccount,
+    key: EncodingKey,
+    access_token: Option<(String, DateTime<Utc>)>,
+}
+
+impl FcmSender {
+    pub fn from_config(config: &PushConfig) -> Result<Option<Self>> {
+        let Some(path) = &config.fcm_service_account_path else {
+            return Ok(None);
+        };
+        let account: ServiceAccount = serde_json::from_str(&std::fs::read_to_string(path)?)
+            .map_err(|e| anyhow!("invalid service account file {}: {}", path.display(), e))?;
+        let key = EncodingKey::from_rsa_pem(account.private_key.as_bytes())?;
+
+        Ok(Some(Self {
+            client: reqwest::Client::new(),
+            account,
+            key,
+            access_token: None,
+        }))
+    }
+
+    /// OAuth access token for the service account, refreshed a minute before it expires.
+    async fn access_token(&mut self) -> Result<String> {
+        if let Some((token, expires_at)) = &self.access_token {
+            if *expires_at > Utc::now() + Duration::minutes(1) {
+                return Ok(token.clone());
+            }
+        }
+
+        let now = Utc::now().timestamp();
+        let assertion = encode(
+            &Header::new(Algorithm::RS256),
+            &AssertionClaims {
+                iss: &self.account.client_email,
+                scope: FCM_SCOPE,
+                aud: &self.account.token_uri,
+                iat: now,
+                exp: now + 3600,
+            },
+            &self.key,
+        )?;
+
+        let response = self
+            .client
+            .post(&self.account.token_uri)
+            .form(&[
+                ("grant_type", "urn:ietf:params:oauth:grant-type:jwt-bearer"),
+                ("assertion", assertion.as_str()),
+            ])
+            .send()
+            .await?
+            .error_for_status()?
+            .json::<AccessToken>()
+            .await?;
+
+        let expires_at = Utc::now() + Duration::seconds(response.expires_in);
+        self.access_token = Some((response.access_token.clone(), expires_at));
+        Ok(response.access_token)
+    }
+
+    pub async fn send(&mut self, token: &str, message: &PushMessage<'_>) -> SendOutcome {
+        let access_token = match self.access_token().await {
+            Ok(access_token) => access_token,
+            Err(e) => return SendOutcome::Failed(format!("FCM authentication failed: {}", e)),
+        };
+
+        let url = format!(
+            "https://fcm.googleapis.com/v1/projects/{}/messages:send",
+            self.account.project_id
+        );
+        let body = json!({
+            "message": {
+                "token": token,
+                "notification": { "title": message.title, "body": message.body },
+                "data": { "notificationId": message.notification_id, "kind": message.kind }
+            }
+        });
+
+        let response = match self.client.post(url).bearer_auth(access_token).json(&body).send().await {
+            Ok(response) => response,
+            Err(e) => return SendOutcome::Failed(e.to_string()),
+        };
+        let status = response.status();
+        if status.is_success() {
+            return SendOutcome::Delivered;
+        }
+
+        let error: Value = response.json().await.unwrap_or(Value::Null);
+        let error_code = error["error"]["details"]
+            .as_array()
+            .into_iter()
+            .flatten()
+            .find_map(|detail| detail["errorCode"].as_str())
+            .unwrap_or_default();
+        match (status.as_u16(), error_code) {
+            (404, _) | (_, "UNREGISTERED") => SendOutcome::InvalidToken,
+            (400, "INVALID_ARGUMENT") if error.to_string().contains("registration token") => SendOutcome::InvalidToken,
+            _ => SendOutcome::Failed(format!("FCM returned {}: {}", status, error)),
+        }
+    }
+}
@@ -0,0 +1,179 @@
+mod apns;
+mod fcm;
+
+use std::sync::Arc;
+use std::time::Duration;
+
+use anyhow::Result;
+use chrono::{Duration as ChronoDuration, Utc};
+use sqlx::Row;
+use tokio::sync::Mutex;
+
+use crate::config::PushConfig;
+use crate::models::{PushDevice, PushPlatform};
+use crate::services::database::DbPool;
+use crate::services::scheduler::Scheduler;
+use crate::utils::datetime::format_db_datetime;
+
+use apns::ApnsSender;
+use fcm::FcmSender;
+
+const PUSH_DELIVERY_INTERVAL: Duration = Duration::from_secs(30);
+
+/// Notifications older than this when first seen are not pushed (e.g. right after push is
+/// configured), so devices aren't flooded with stale alerts.
+const MAX_PUSH_AGE_HOURS: i64 = 24;
+
+const PUSH_BATCH_SIZE: i64 = 100;
+
+pub struct PushMessage<'a> {
+    pub notification_id: &'a str,
+    pub kind: &'a str,
+    pub title: &'a str,
+    pub body: &'a str,
+}
+
+pub enum SendOutcome {
+    Delivered,
+    /// The provider says the token is no longer valid; the device is dropped.
+    InvalidToken,
+    Failed(String),
+}
+
+/// Push providers from `push` settings; either may be absent.
+pub struct PushSenders {
+    fcm: Option<FcmSender>,
+    apns: Option<ApnsSender>,
+}
+
+impl PushSenders {
+    pub fn from_config(config: &PushConfig) -> Self {
+        let fcm = match FcmSender::from_config(config) {
+            Ok(sender) => sender,
+            Err(e) => {
+                tracing::error!("FCM push disabled: {}", e);
+                None
+            }
+        };
+        let apns = match ApnsSender::from_config(config) {
+            Ok(sender) => sender,
+            Err(e) => {
+                tracing::error!("APNs push disabled: {}", e);
+                None
+            }
+        };
+        Self { fcm, apns }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.fcm.is_none() && self.apns.is_none()
+    }
+
+    async fn send(&mut self, device: &PushDevice, message: &PushMessage<'_>) -> Option<SendOutcome> {
+        match device.platform.as_str() {
+            p if p == PushPlatform::Fcm.as_str() => match &mut self.fcm {
+                Some(fcm) => Some(fcm.send(&device.token, message).await),
+                None => None,
+            },
+            p if p == PushPlatform::Apns.as_str() => match &mut self.apns {
+                Some(apns) => Some(apns.send(&device.token, message).await),
+                None => None,
+            },
+            _ => None,
+        }
+    }
+}
+
+/// Pushes every notification not yet pushed to all of its user's registered devices, dropping
+/// devices whose token the provider rejects. Each notification is attempted once.
+pub async fn deliver_pending(pool: &DbPool, senders: &mut PushSenders) -> Result<usize> {
+    let now = Utc::now();
+    let pending = sqlx::query(
+        r#"
+        SELECT id, user_id, kind, title, body FROM notifications
+        WHERE pushed_at IS NULL AND read_at IS NULL AND created_at >= ?
+        ORDER BY created_at
+        LIMIT ?
+        "#,
+    )
+    .bind(format_db_datetime(now - ChronoDuration::hours(MAX_PUSH_AGE_HOURS)))
+    .bind(PUSH_BATCH_SIZE)
+    .fetch_all(pool)
+    .await?;
+
+    let mut delivered = 0;
+    for row in &pending {
+        let notification_id: String = row.get("id");
+        let user_id: String = row.get("user_id");
+        let (kind, title, body): (String, String, String) = (row.get("kind"), row.get("title"), row.get("body"));
+        let message = PushMessage {
+            notification_id: &notification_id,
+            kind: &kind,
+            title: &title,
+            body: &body,
+        };
+
+        let devices = sqlx::query_as::<_, PushDevice>("SELECT * FROM push_devices WHERE user_id = ?")
+            .bind(&user_id)
+            .fetch_all(pool)
+            .await?;
+
+        for device in &devices {
+            match senders.send(device, &message).await {
+                Some(SendOutcome::Delivered) => {
+                    delivered += 1;
+                    sqlx::query("UPDATE push_devices SET last_used_at = ? WHERE id = ?")
+                        .bind(format_db_datetime(Utc::now()))
+                        .bind(&device.id)
+                        .execute(pool)
+                        .await?;
+                }
+                Some(SendOutcome::InvalidToken) => {
+                    tracing::info!("Push token for device {} ({}) is no longer valid; removing it", device.id, device.platform);
+                    sqlx::query("DELETE FROM push_devices WHERE id = ?")
+                        .bind(&device.id)
+                        .execute(pool)
+                        .await?;
+                }
+                Some(SendOutcome::Failed(reason)) => {
+                    tracing::warn!("Push to device {} failed: {}", device.id, reason);
+                }
+                None => {}
+            }
+        }
+
+        sqlx::query("UPDATE notifications SET pushed_at = ? WHERE id = ?")
+            .bind(format_db_datetime(Utc::now()))
+            .bind(&notification_id)
+            .execute(pool)
+            .await?;
+    }
+
+    Ok(delivered)
+}
+
+/// Schedules the push delivery worker if at least one provider is configured.
+pub fn schedule_push_delivery_job(scheduler: &mut Scheduler, config: &PushConfig) {
+    let senders = PushSenders::from_config(config);
+    if senders.is_empty() {
+        tracing::info!("Push delivery disabled: neither FCM nor APNs is configured");
+        return;
+    }
+
+    // Senders cache provider tokens between runs
+    let senders = Arc::new(Mutex::new(senders));
+    scheduler
+        .every("push_delivery", PUSH_DELIVERY_INTERVAL, move |pool| {
+            let senders = senders.clone();
+            async move {
+                let delivered = deliver_pending(&pool, &mut *senders.lock().await).await?;
+                if delivered > 0 {
+                    tracing::info!("Delivered {} push notifications", delivered);
+                }
+                Ok(delivered as u64)
+            }
+        })
+        .retries(0)
+        .keeps_cache()
+        .only_when(|features| features.enabled(features.push));
+}
@@ -0,0 +1,161 @@
+use std::collections::HashMap;
+use std::sync::{Mutex, OnceLock};
+
+use anyhow::Result;
+use chrono::{DateTime, Duration, DurationRound, TimeZone, Utc};
+
+use crate::config;
+use crate::models::{QuotaStatus, QuotaUsage, Quotas, UpdateUserQuotaRequest, UserQuotaOverride};
+use crate::services::database::DbPool;
+use crate::utils::datetime::format_db_datetime;
+
+/// Users tracked by the request counter before windows from earlier minutes are dropped.
+const MAX_TRACKED_USERS: usize = 10_000;
+
+/// Requests a user made in one clock minute.
+struct Window {
+    minute: i64,
+    count: u64,
+}
+
+/// Request counts per user for the current minute. Kept in memory, so each server process
+/// counts on its own.
+fn windows() -> &'static Mutex<HashMap<String, Window>> {
+    static WINDOWS: OnceLock<Mutex<HashMap<String, Window>>> = OnceLock::new();
+    WINDOWS.get_or_init(|| Mutex::new(HashMap::new()))
+}
+
+fn limit(value: u64) -> Option<u64> {
+    (value > 0).then_some(value)
+}
+
+/// A column of `user_quotas` over the configured `default`.
+fn overridden(column: Option<i64>, default: u64) -> Option<u64> {
+    match column {
+        Some(value) => limit(value.max(0) as u64),
+        None => limit(default),
+    }
+}
+
+/// `user_id`'s quotas: the configured defaults with their overrides on top.
+pub async fn for_user(pool: &DbPool, user_id: &str) -> Result<Quotas> {
+    let defaults = &config::current().quotas;
+    let row = sqlx::query_as::<_, UserQuotaOverride>(
+        "SELECT requests_per_minute, sync_batch_size, daily_upload_bytes FROM user_quotas WHERE user_id = ?"
+    )
+    .bind(user_id)
+    .fetch_optional(pool)
+    .await?
+    .unwrap_or(UserQuotaOverride {
+        requests_per_minute: None,
+        sync_batch_size: None,
+        daily_upload_bytes: None,
+    });
+
+    Ok(Quotas {
+        requests_per_minute: overridden(row.requests_per_minute, defaults.requests_per_minute),
+        sync_batch_size: overridden(row.sync_batch_size, defaults.sync_batch_size),
+        daily_upload_bytes: overridden(row.daily_upload_bytes, defaults.daily_upload_bytes),
+    })
+}
+
+/// Replaces `user_id`'s overrides; fields left out go back to the defaults.
+pub async fn set_for_user(pool: &DbPool, user_id: &str, request: &UpdateUserQuotaRequest) -> Result<()> {
+    let column = |value: Option<u64>| value.map(|value| value.min(i64::MAX as u64) as i64);
+    sqlx::query(
+        r#"
+        INSERT INTO user_quotas (user_id, requests_per_minute, sync_batch_size, daily_upload_bytes, updated_at)
+        VALUES (?, ?, ?, ?, ?)
+        ON CONFLICT (user_id) DO UPDATE SET
+            requests_per_minute = excluded.requests_per_minute,
+            sync_batch_size = excluded.sync_batch_size,
+            daily_upload_bytes = excluded.daily_upload_bytes,
+            updated_at = excluded.updated_at
+        "#,
+    )
+    .bind(user_id)
+    .bind(column(request.requests_per_minute))
+    .bind(column(request.sync_batch_size))
+    .bind(column(request.daily_upload_bytes))
+    .bind(format_db_datetime(Utc::now()))
+    .execute(pool)
+    .await?;
+    Ok(())
+}
+
+fn minute_of(now: DateTime<Utc>) -> i64 {
+    now.timestamp().div_euclid(60)
+}
+
+fn next_minute(now: DateTime<Utc>) -> DateTime<Utc> {
+    Utc.timestamp_opt((minute_of(now) + 1) * 60, 0).single().unwrap_or(now)
+}
+
+fn next_day(now: DateTime<Utc>) -> DateTime<Utc> {
+    now.duration_trunc(Duration::days(1)).unwrap_or(now) + Duration::days(1)
+}
+
+/// Counts a request by `user_id` against `limit` per minute. Returns the requests used this
+/// minute, or the seconds until the next minute when the limit is already reached.
+pub fn take_request(user_id: &str, limit: Option<u64>, now: DateTime<Utc>) -> Result<u64, u64> {
+    let minute = minute_of(now);
+    let mut windows = windows().lock().unwrap_or_else(|e| e.into_inner());
+    if windows.len() >= MAX_TRACKED_USERS && !windows.contains_key(user_id) {
+        windows.retain(|_, window| window.minute == minute);
+    }
+
+    let window = windows.entry(user_id.to_string()).or_insert(Window { minute, count: 0 });
+    if window.minute != minute {
+        *window = Window { minute, count: 0 };
+    }
+    if limit.is_some_and(|limit| window.count >= limit) {
+        return Err((next_minute(now) - now).num_seconds().max(1) as u64);
+    }
+    window.count += 1;
+    Ok(window.count)
+}
+
+fn requests_this_minute(user_id: &str, now: DateTime<Utc>) -> u64 {
+    let windows = windows().lock().unwrap_or_else(|e| e.into_inner());
+    windows
+        .get(user_id)
+        .filter(|window| window.minute == minute_of(now))
+        .map_or(0, |window| window.count)
+}
+
+/// Request body bytes `user_id` sent today, from `api_usage`.
+pub async fn uploaded_today(pool: &DbPool, user_id: &str, now: DateTime<Utc>) -> Result<u64> {
+    let bytes: i64 = sqlx::query_scalar("SELECT COALESCE(SUM(request_bytes), 0) FROM api_usage WHERE user_id = ? AND day = ?")
+        .bind(user_id)
+        .bind(now.format("%Y-%m-%d").to_string())
+        .fetch_one(pool)
+        .await?;
+    Ok(bytes.max(0) as u64)
+}
+
+/// Seconds until `user_id` can upload again, once today's uploads and `incoming` bytes would
+/// go over `limit`.
+pub async fn check_upload(pool: &DbPool, user_id: &str, limit: Option<u64>, incoming: u64, now: DateTime<Utc>) -> Result<Option<u64>> {
+    let Some(limit) = limit else {
+        return Ok(None);
+    };
+    if incoming == 0 {
+        return Ok(None);
+    }
+    let used = uploaded_today(pool, user_id, now).await?;
+    if used.saturating_add(incoming) <= limit {
+        return Ok(None);
+    }
+    Ok(Some((next_day(now) - now).num_seconds().max(1) as u64))
+}
+
+/// Where `user_id` stands against each quota.
+pub async fn status(pool: &DbPool, user_id: &str) -> Result<QuotaStatus> {
+    let quotas = for_user(pool, user_id).await?;
+    let now = Utc::now();
+    Ok(QuotaStatus {
+        requests_per_minute: QuotaUsage::new(quotas.requests_per_minute, requests_this_minute(user_id, now), next_minute(now)),
+        daily_upload_bytes: QuotaUsage::new(quotas.daily_upload_bytes, uploaded_today(pool, user_id, now).await?, next_day(now)),
+        sync_batch_size: quotas.sync_batch_size,
+    })
+}
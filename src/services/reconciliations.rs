@@ -0,0 +1,128 @@
+use std::collections::HashSet;
+
+use anyhow::Result;
+use chrono::{DateTime, Duration, Utc};
+
+use crate::models::{Reconciliation, ReconciliationSummary, Transaction};
+use crate::services::database::DbPool;
+use crate::services::user_time::UserTime;
+use crate::utils::datetime::format_db_datetime;
+use crate::utils::money::Money;
+
+/// The first moment after the statement day, on the user's clock. Transactions before it may
+/// appear on the statement.
+pub fn statement_cutoff(reconciliation: &Reconciliation, time: &UserTime) -> DateTime<Utc> {
+    let day = time.localize(reconciliation.statement_date).date_naive();
+    time.start_of_day(day + Duration::days(1))
+}
+
+pub async fn find(pool: &DbPool, account_id: &str, id: &str) -> Result<Option<Reconciliation>> {
+    Ok(sqlx::query_as::<_, Reconciliation>("SELECT * FROM reconciliations WHERE id = ? AND account_id = ?")
+        .bind(id)
+        .bind(account_id)
+        .fetch_optional(pool)
+        .await?)
+}
+
+/// The account's reconciliation in progress, if any.
+pub async fn open_for_account(pool: &DbPool, account_id: &str) -> Result<Option<Reconciliation>> {
+    Ok(sqlx::query_as::<_, Reconciliation>("SELECT * FROM reconciliations WHERE account_id = ? AND status = 'open'")
+        .bind(account_id)
+        .fetch_optional(pool)
+        .await?)
+}
+
+/// Of `transaction_ids`, those that aren't the account's or fall after the statement day.
+pub async fn not_clearable(
+    pool: &DbPool,
+    reconciliation: &Reconciliation,
+    transaction_ids: &[String],
+    time: &UserTime,
+) -> Result<Vec<String>> {
+    let placeholders = vec!["?"; transaction_ids.len()].join(", ");
+    let sql = format!("SELECT id FROM transactions WHERE account_id = ? AND date < ? AND id IN ({})", placeholders);
+    let mut query = sqlx::query_scalar::<_, String>(&sql)
+        .bind(&reconciliation.account_id)
+        .bind(format_db_datetime(statement_cutoff(reconciliation, time)));
+    for id in transaction_ids {
+        query = query.bind(id);
+    }
+    let clearable: HashSet<String> = query.fetch_all(pool).await?.into_iter().collect();
+    Ok(transaction_ids.iter().filter(|id| !clearable.contains(*id)).cloned().collect())
+}
+
+/// Marks transactions as cleared against `reconciliation_id`. Ones another reconciliation
+/// already cleared stay with it.
+pub async fn clear(pool: &DbPool, reconciliation_id: &str, transaction_ids: &[String]) -> Result<()> {
+    let now = format_db_datetime(Utc::now());
+    let mut tx = pool.begin().await?;
+    for id in transaction_ids {
+        sqlx::query("INSERT OR IGNORE INTO reconciliation_transactions (transaction_id, reconciliation_id, cleared_at) VALUES (?, ?, ?)")
+            .bind(id)
+            .bind(reconciliation_id)
+            .bind(&now)
+            .execute(&mut tx)
+            .await?;
+    }
+    tx.commit().await?;
+    Ok(())
+}
+
+/// Takes back transactions this reconciliation cleared.
+pub async fn unclear(pool: &DbPool, reconciliation_id: &str, transaction_ids: &[String]) -> Result<()> {
+    let mut tx = pool.begin().await?;
+    for id in transaction_ids {
+        sqlx::query("DELETE FROM reconciliation_transactions WHERE transaction_id = ? AND reconciliation_id = ?")
+            .bind(id)
+            .bind(reconciliation_id)
+            .execute(&mut tx)
+            .await?;
+    }
+    tx.commit().await?;
+    Ok(())
+}
+
+/// The reconciliation with its cleared balance and what remains to explain.
+pub async fn summary(pool: &DbPool, reconciliation: Reconciliation, time: &UserTime) -> Result<ReconciliationSummary> {
+    let (balance, currency): (Money, String) = sqlx::query_as("SELECT balance, currency FROM accounts WHERE id = ?")
+        .bind(&reconciliation.account_id)
+        .fetch_one(pool)
+        .await?;
+
+    // Mirrors account_delta: only income adds to the account.
+    let uncleared_total: Money = sqlx::query_scalar(
+        "SELECT COALESCE(SUM(CASE WHEN transaction_type = 'income' THEN amount ELSE -amount END), 0) FROM transactions
+         WHERE account_id = ? AND id NOT IN (SELECT transaction_id FROM reconciliation_transactions)"
+    )
+    .bind(&reconciliation.account_id)
+    .fetch_one(pool)
+    .await?;
+
+    let cleared_transaction_ids = sqlx::query_scalar::<_, String>(
+        "SELECT rt.transaction_id FROM reconciliation_transactions rt JOIN transactions t ON t.id = rt.transaction_id
+         WHERE rt.reconciliation_id = ? ORDER BY t.date, t.created_at"
+    )
+    .bind(&reconciliation.id)
+    .fetch_all(pool)
+    .await?;
+
+    let uncleared = sqlx::query_as::<_, Transaction>(
+        "SELECT * FROM transactions
+         WHERE account_id = ? AND date < ? AND id NOT IN (SELECT transaction_id FROM reconciliation_transactions)
+         ORDER BY date, created_at"
+    )
+    .bind(&reconciliation.account_id)
+    .bind(format_db_datetime(statement_cutoff(&reconciliation, time)))
+    .fetch_all(pool)
+    .await?;
+
+    let cleared_balance = balance - uncleared_total;
+    Ok(ReconciliationSummary {
+        currency,
+        cleared_balance,
+        difference: reconciliation.statement_balance - cleared_balance,
+        cleared_transaction_ids,
+        uncleared,
+        reconciliation,
+    })
+}
@@ -0,0 +1,280 @@
+use std::collections::HashSet;
+use std::time::Duration;
+
+use anyhow::{anyhow, Result};
+use chrono::{DateTime, Utc};
+use serde::Serialize;
+use serde_json::json;
+use sqlx::{Row, SqliteConnection};
+
+use crate::models::{
+    CreateTransactionRequest, RecurringTransaction, SavingsGoalContribution, Transaction, TransactionType,
+};
+use crate::services::budget_progress;
+use crate::services::database::DbPool;
+use crate::services::scheduler::Scheduler;
+use crate::services::events;
+use crate::services::notifications::{self, NewNotification};
+use crate::services::goal_contributions::{self, ContributionOutcome};
+use crate::utils::datetime::format_db_datetime;
+use crate::utils::money::Money;
+
+/// Result of posting one occurrence of a recurring transaction.
+#[derive(Debug, Serialize)]
+pub struct PostedOccurrence {
+    pub transaction: Transaction,
+    pub contribution: Option<SavingsGoalContribution>,
+    #[serde(rename = "goalCompleted")]
+    pub goal_completed: bool,
+}
+
+const RECURRING_RUN_INTERVAL: Duration = Duration::from_secs(5 * 60);
+
+/// Upper bound on occurrences caught up for one recurring transaction in a single run.
+const MAX_CATCH_UP: usize = 400;
+
+/// Stores a new recurring transaction.
+pub async fn insert_recurring(conn: &mut SqliteConnection, rt: &RecurringTransaction) -> sqlx::Result<()> {
+    sqlx::query(
+        "INSERT INTO recurring_transactions (id, user_id, account_id, transaction_type, amount, currency, category, description, frequency, recurrence_interval, day_of_month, weekday, start_date, end_date, next_due_date, is_active, savings_goal_id, created_at, updated_at) VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?)"
+    )
+    .bind(&rt.id)
+    .bind(&rt.user_id)
+    .bind(&rt.account_id)
+    .bind(&rt.transaction_type)
+    .bind(rt.amount)
+    .bind(&rt.currency)
+    .bind(&rt.category)
+    .bind(&rt.description)
+    .bind(&rt.frequency)
+    .bind(rt.recurrence_interval)
+    .bind(rt.day_of_month)
+    .bind(&rt.weekday)
+    .bind(format_db_datetime(rt.start_date))
+    .bind(rt.end_date.map(format_db_datetime))
+    .bind(format_db_datetime(rt.next_due_date))
+    .bind(rt.is_active)
+    .bind(&rt.savings_goal_id)
+    .bind(format_db_datetime(rt.created_at))
+    .bind(format_db_datetime(rt.updated_at))
+    .execute(conn)
+    .await?;
+    Ok(())
+}
+
+/// Inserts the real transaction for an occurrence and, when the recurring transaction is
+/// linked to a savings goal, records a matching contribution in the same database transaction.
+pub async fn post_occurrence(
+    pool: &DbPool,
+    rt: &RecurringTransaction,
+    date: DateTime<Utc>,
+) -> Result<PostedOccurrence> {
+    let mut tx = pool.begin().await?;
+    let posted = insert_occurrence(&mut tx, rt, date).await?;
+    tx.commit().await?;
+
+    emit_occurrence_events(pool, rt, &posted).await?;
+    Ok(posted)
+}
+
+async fn insert_occurrence(
+    conn: &mut SqliteConnection,
+    rt: &RecurringTransaction,
+    date: DateTime<Utc>,
+) -> Result<PostedOccurrence> {
+    let transaction_type: TransactionType = serde_json::from_value(json!(rt.transaction_type.to_lowercase()))
+        .map_err(|_| anyhow!("Unknown transaction type '{}'", rt.transaction_type))?;
+
+    let transaction = Transaction::new(
+        CreateTransactionRequest {
+            id: None,
+            account_id: rt.account_id.clone(),
+            transaction_type,
+            amount: rt.amount,
+            currency: Some(rt.currency.clone()),
+            category: rt.category.clone(),
+            description: rt.description.clone(),
+            date: Some(date),
+            created_at: None,
+            exchange_rate: None,
+            base_amount: None,
+        },
+        rt.user_id.clone(),
+    );
+
+    sqlx::query(
+        "INSERT INTO transactions (id, user_id, account_id, transaction_type, amount, currency, category, description, date, created_at) VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?, ?)"
+    )
+    .bind(&transaction.id)
+    .bind(&transaction.user_id)
+    .bind(&transaction.account_id)
+    .bind(rt.transaction_type.to_lowercase())
+    .bind(transaction.amount)
+    .bind(&transaction.currency)
+    .bind(&transaction.category)
+    .bind(&transaction.description)
+    .bind(format_db_datetime(transaction.date))
+    .bind(format_db_datetime(transaction.created_at))
+    .execute(&mut *conn)
+    .await?;
+
+    let mut contribution = None;
+    let mut goal_completed = false;
+
+    if let Some(goal_id) = &rt.savings_goal_id {
+        let was_completed = sqlx::query("SELECT is_completed FROM savings_goals WHERE id = ? AND user_id = ?")
+            .bind(goal_id)
+            .bind(&rt.user_id)
+            .fetch_optional(&mut *conn)
+            .await?
+            .map(|row| row.get::<bool, _>("is_completed"));
+
+        let linked = SavingsGoalContribution::new(
+            rt.user_id.clone(),
+            goal_id.clone(),
+            rt.amount.abs(),
+            date,
+            rt.description.clone(),
+            Some(transaction.id.clone()),
+            "recurring",
+        );
+
+        match goal_contributions::apply_contribution(&mut *conn, &linked).await? {
+            ContributionOutcome::Applied { is_completed, .. } => {
+                goal_completed = is_completed && was_completed == Some(false);
+                contribution = Some(linked);
+            }
+            ContributionOutcome::GoalNotFound => {
+                tracing::warn!("Recurring transaction {} links to missing savings goal {}", rt.id, goal_id);
+            }
+            ContributionOutcome::InsufficientSavings => {
+                tracing::warn!("Skipping contribution for recurring transaction {}: insufficient savings", rt.id);
+            }
+        }
+    }
+
+    Ok(PostedOccurrence {
+        transaction,
+        contribution,
+        goal_completed,
+    })
+}
+
+async fn emit_occurrence_events(pool: &DbPool, rt: &RecurringTransaction, posted: &PostedOccurrence) -> Result<()> {
+    events::emit(pool, &rt.user_id, "transaction.created", "transaction", &posted.transaction.id, &posted.transaction).await;
+
+    if let (true, Some(contribution)) = (posted.goal_completed, &posted.contribution) {
+        let goal = sqlx::query("SELECT name, current_amount, target_amount FROM savings_goals WHERE id = ?")
+            .bind(&contribution.goal_id)
+            .fetch_one(pool)
+            .await?;
+        events::emit(pool, &rt.user_id, "goal.completed", "savings_goal", &contribution.goal_id, &json!({
+            "id": contribution.goal_id,
+            "name": goal.get::<String, _>("name"),
+            "currentAmount": goal.get::<Money, _>("current_amount"),
+            "targetAmount": goal.get::<Money, _>("target_amount")
+        })).await;
+    }
+
+    Ok(())
+}
+
+/// Posts every due occurrence of active recurring transactions, advancing `next_due_date`
+/// after each one and deactivating schedules that have passed their `end_date`.
+pub async fn run_due_recurring(pool: &DbPool) -> Result<usize> {
+    let now = Utc::now();
+    let due = sqlx::query_as::<_, RecurringTransaction>(
+        "SELECT * FROM recurring_transactions WHERE is_active = TRUE AND next_due_date <= ?"
+    )
+    .bind(format_db_datetime(now))
+    .fetch_all(pool)
+    .await?;
+
+    let mut posted_count = 0;
+    let mut users_posted_for = HashSet::new();
+    for mut rt in due {
+        for _ in 0..MAX_CATCH_UP {
+            if rt.next_due_date > now {
+                break;
+            }
+            if rt.end_date.is_some_and(|end| rt.next_due_date > end) {
+                break;
+            }
+            let next_due_date = match rt.rule() {
+                Ok(rule) => rule.next_after(rt.start_date, rt.next_due_date),
+                Err(e) => {
+                    tracing::warn!("Recurring transaction {} has an invalid schedule ({}); skipping", rt.id, e);
+                    break;
+                }
+            };
+            let Some(next_due_date) = next_due_date else {
+                break;
+            };
+
+            let mut tx = pool.begin().await?;
+            // Guard on the old due date so overlapping runs cannot post the same occurrence twice.
+            let advanced = sqlx::query(
+                "UPDATE recurring_transactions SET next_due_date = ?, updated_at = ? WHERE id = ? AND next_due_date = ? AND is_active = TRUE"
+            )
+            .bind(format_db_datetime(next_due_date))
+            .bind(format_db_datetime(now))
+            .bind(&rt.id)
+            .bind(format_db_datetime(rt.next_due_date))
+            .execute(&mut tx)
+            .await?;
+            if advanced.rows_affected() == 0 {
+                break;
+            }
+
+            let posted = insert_occurrence(&mut tx, &rt, rt.next_due_date).await?;
+            tx.commit().await?;
+            emit_occurrence_events(pool, &rt, &posted).await?;
+            notifications::notify(pool, NewNotification {
+                user_id: &rt.user_id,
+                kind: "recurring_posted",
+                title: format!("Posted {}", rt.description.as_deref().or(rt.category.as_deref()).unwrap_or("recurring transaction")),
+                body: format!(
+                    "{:.2} {} {} recorded for {}.",
+                    rt.amount, rt.currency, rt.transaction_type, posted.transaction.date.format("%Y-%m-%d")
+                ),
+                entity: Some(("transaction", &posted.transaction.id)),
+                dedupe_key: None,
+            })
+            .await;
+            users_posted_for.insert(rt.user_id.clone());
+
+            posted_count += 1;
+            rt.next_due_date = next_due_date;
+        }
+
+        if rt.end_date.is_some_and(|end| rt.next_due_date > end) {
+            deactivate(pool, &rt.id).await?;
+            tracing::info!("Recurring transaction {} reached its end date; deactivated", rt.id);
+        }
+    }
+
+    for user_id in &users_posted_for {
+        if let Err(e) = budget_progress::notify_exceeded_budgets(pool, user_id).await {
+            tracing::error!("Failed to check budgets for user {}: {}", user_id, e);
+        }
+    }
+
+    Ok(posted_count)
+}
+
+async fn deactivate(pool: &DbPool, id: &str) -> Result<()> {
+    sqlx::query("UPDATE recurring_transactions SET is_active = FALSE, updated_at = ? WHERE id = ?")
+        .bind(format_db_datetime(Utc::now()))
+        .bind(id)
+        .execute(pool)
+        .await?;
+    Ok(())
+}
+
+pub fn schedule_recurring_job(scheduler: &mut Scheduler) {
+    scheduler.every("recurring_transactions", RECURRING_RUN_INTERVAL, |pool| async move {
+        let posted = run_due_recurring(&pool).await?;
+        tracing::info!("Recurring run finished: {} occurrence(s) posted", posted);
+        Ok(posted as u64)
+    });
+}
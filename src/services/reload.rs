@@ -0,0 +1,45 @@
+use anyhow::Result;
+
+use crate::config::{self, Reloaded};
+use crate::utils::logging;
+
+/// Reloads the configuration and applies what changed: a new log level swaps the log filter,
+/// while quotas, CORS origins and features are read from [`config::current`] as they are used.
+pub fn reload() -> Result<Reloaded> {
+    let reloaded = config::reload()?;
+
+    if reloaded.applied.contains(&"log.level") {
+        let level = &config::current().log.level;
+        match logging::set_level(level) {
+            Ok(()) => tracing::info!("📊 Log level: {}", level),
+            Err(e) => tracing::warn!("Log level {} not applied: {}", level, e),
+        }
+    }
+    if reloaded.applied.is_empty() {
+        tracing::info!("🔄 Configuration reloaded, nothing to apply");
+    } else {
+        tracing::info!("🔄 Configuration reloaded: {} applied", reloaded.applied.join(", "));
+    }
+    if !reloaded.needs_restart.is_empty() {
+        tracing::warn!("🔄 Changes to {} take effect after a restart", reloaded.needs_restart.join(", "));
+    }
+    Ok(reloaded)
+}
+
+/// Reloads the configuration on every SIGHUP, keeping the running one when the new one is
+/// invalid. Never resolves; where there is no SIGHUP, it just waits.
+pub async fn reload_on_hangup() {
+    #[cfg(unix)]
+    match tokio::signal::unix::signal(tokio::signal::unix::SignalKind::hangup()) {
+        Ok(mut sighup) => {
+            while sighup.recv().await.is_some() {
+                tracing::info!("🔄 Received SIGHUP, reloading the configuration");
+                if let Err(e) = reload() {
+                    tracing::error!("Configuration not reloaded: {:#}", e);
+                }
+            }
+        }
+        Err(e) => tracing::error!("Failed to listen for SIGHUP: {}", e),
+    }
+    std::future::pending::<()>().await;
+}
@@ -0,0 +1,339 @@
+use std::time::Duration;
+
+use anyhow::Result;
+use chrono::{DateTime, Duration as ChronoDuration, Utc};
+use sqlx::Row;
+
+use crate::models::{BillReminder, BillReminderSchedule, CardStatement, Liability, RecurringTransaction, ReminderTarget, TermDeposit};
+use crate::services::card_statements;
+use crate::services::database::DbPool;
+use crate::services::scheduler::Scheduler;
+use crate::services::mailer::{self, EmailTemplate};
+use crate::services::notifications::{self, NewNotification};
+use crate::services::term_deposits;
+use crate::utils::datetime::format_db_datetime;
+
+/// Unpaid liabilities due within this many days get a reminder.
+const LIABILITY_REMINDER_DAYS: i64 = 3;
+
+/// Open term deposits maturing within this many days get a reminder.
+const DEPOSIT_MATURITY_REMINDER_DAYS: i64 = 7;
+
+/// Credit card statements due within this many days and not yet paid off get a reminder.
+const CARD_PAYMENT_REMINDER_DAYS: i64 = 3;
+
+/// Longest lead time a bill reminder may be given.
+pub const MAX_REMINDER_DAYS_BEFORE: i64 = 60;
+
+const REMINDER_INTERVAL: Duration = Duration::from_secs(60 * 60);
+
+/// Leaves out bills whose owner set their own reminders for them, for `WHERE` clauses on
+/// `table`, whose `column` holds the reminder target's ID.
+fn without_configured_reminders(target: ReminderTarget, table: &str, column: &str) -> String {
+    format!(
+        "NOT EXISTS (SELECT 1 FROM bill_reminders r WHERE r.target_type = '{}' AND r.target_id = {}.{} AND r.user_id = {}.user_id AND r.is_active = TRUE)",
+        target.as_str(),
+        table,
+        column,
+        table
+    )
+}
+
+/// Queues a `liability_due` reminder for every unpaid liability falling due soon, unless its
+/// owner configured bill reminders for it. Reminders are keyed on the due date, so moving the
+/// due date produces a fresh reminder.
+pub async fn remind_due_liabilities(pool: &DbPool) -> Result<usize> {
+    let now = Utc::now();
+    let liabilities = sqlx::query_as::<_, Liability>(&format!(
+        "SELECT * FROM liabilities WHERE is_paid = FALSE AND due_date >= ? AND due_date < ? AND {}",
+        without_configured_reminders(ReminderTarget::Liability, "liabilities", "id")
+    ))
+    .bind(format_db_datetime(now))
+    .bind(format_db_datetime(now + ChronoDuration::days(LIABILITY_REMINDER_DAYS)))
+    .fetch_all(pool)
+    .await?;
+
+    for liability in &liabilities {
+        let due = liability.due_date.format("%Y-%m-%d").to_string();
+        let title = format!("Payment to {} due soon", liability.person_name);
+        let body = format!("{:.2} {} is due on {}.", liability.amount, liability.currency, due);
+        let queued = notifications::enqueue(pool, NewNotification {
+            user_id: &liability.user_id,
+            kind: "liability_due",
+            title: title.clone(),
+            body: body.clone(),
+            entity: Some(("liability", &liability.id)),
+            dedupe_key: Some(format!("liability_due:{}:{}", liability.id, due)),
+        })
+        .await;
+
+        match queued {
+            // Only email the first time; later runs hit the dedupe key
+            Ok(true) => email_reminder(pool, &liability.user_id, &title, &body).await?,
+            Ok(false) => {}
+            Err(e) => tracing::error!("Failed to queue liability_due notification for user {}: {}", liability.user_id, e),
+        }
+    }
+
+    Ok(liabilities.len())
+}
+
+/// Queues a `deposit_maturity` reminder for every open term deposit maturing soon, keyed on the
+/// maturity date like liability reminders.
+pub async fn remind_maturing_deposits(pool: &DbPool) -> Result<usize> {
+    let now = Utc::now();
+    let deposits = sqlx::query_as::<_, TermDeposit>(
+        "SELECT * FROM term_deposits WHERE is_closed = FALSE AND maturity_date >= ? AND maturity_date < ?"
+    )
+    .bind(format_db_datetime(now))
+    .bind(format_db_datetime(now + ChronoDuration::days(DEPOSIT_MATURITY_REMINDER_DAYS)))
+    .fetch_all(pool)
+    .await?;
+
+    for deposit in &deposits {
+        let matures = deposit.maturity_date.format("%Y-%m-%d").to_string();
+        let (_, maturity_value) = term_deposits::value_on(deposit, deposit.maturity_date);
+        let title = format!("{} matures soon", deposit.name);
+        let body = format!("Matures on {} with {:.2} {} due.", matures, maturity_value, deposit.currency);
+        let queued = notifications::enqueue(pool, NewNotification {
+            user_id: &deposit.user_id,
+            kind: "deposit_maturity",
+            title: title.clone(),
+            body: body.clone(),
+            entity: Some(("term_deposit", &deposit.id)),
+            dedupe_key: Some(format!("deposit_maturity:{}:{}", deposit.id, matures)),
+        })
+        .await;
+
+        match queued {
+            Ok(true) => email_reminder(pool, &deposit.user_id, &title, &body).await?,
+            Ok(false) => {}
+            Err(e) => tracing::error!("Failed to queue deposit_maturity notification for user {}: {}", deposit.user_id, e),
+        }
+    }
+
+    Ok(deposits.len())
+}
+
+/// Queues a `card_payment_due` reminder for every credit card statement falling due soon
+/// that hasn't been paid off, unless the card's owner configured bill reminders for it. One
+/// reminder per statement.
+pub async fn remind_card_payments(pool: &DbPool) -> Result<usize> {
+    let now = Utc::now();
+    let statements = sqlx::query_as::<_, CardStatement>(&format!(
+        "SELECT * FROM card_statements WHERE closing_balance > 0 AND due_date >= ? AND due_date < ? AND {}",
+        without_configured_reminders(ReminderTarget::CreditCard, "card_statements", "account_id")
+    ))
+    .bind(format_db_datetime(now))
+    .bind(format_db_datetime(now + ChronoDuration::days(CARD_PAYMENT_REMINDER_DAYS)))
+    .fetch_all(pool)
+    .await?;
+
+    let mut reminded = 0;
+    for statement in statements {
+        let status = card_statements::status(pool, statement).await?;
+        if status.is_paid {
+            continue;
+        }
+        let statement = &status.statement;
+        let card_name: String = sqlx::query_scalar("SELECT name FROM accounts WHERE id = ?")
+            .bind(&statement.account_id)
+            .fetch_one(pool)
+            .await?;
+        let title = format!("{} payment due soon", card_name);
+        let body = format!(
+            "{:.2} {} is due on {} (minimum {:.2}).",
+            statement.closing_balance - status.amount_paid,
+            statement.currency,
+            statement.due_date.format("%Y-%m-%d"),
+            statement.minimum_due
+        );
+        let queued = notifications::enqueue(pool, NewNotification {
+            user_id: &statement.user_id,
+            kind: "card_payment_due",
+            title: title.clone(),
+            body: body.clone(),
+            entity: Some(("card_statement", &statement.id)),
+            dedupe_key: Some(format!("card_payment_due:{}", statement.id)),
+        })
+        .await;
+
+        match queued {
+            Ok(true) => email_reminder(pool, &statement.user_id, &title, &body).await?,
+            Ok(false) => {}
+            Err(e) => tracing::error!("Failed to queue card_payment_due notification for user {}: {}", statement.user_id, e),
+        }
+        reminded += 1;
+    }
+
+    Ok(reminded)
+}
+
+/// The next due date of a bill, with the reminder to send about it.
+pub struct UpcomingBill {
+    pub due_date: DateTime<Utc>,
+    pub title: String,
+    pub body: String,
+}
+
+/// The next time the reminder's bill falls due: an unpaid liability's due date, a card's
+/// earliest unpaid statement that isn't past due, or a recurring transaction's next occurrence.
+/// `None` when nothing is coming up or the target is gone.
+pub async fn upcoming_bill(pool: &DbPool, reminder: &BillReminder) -> Result<Option<UpcomingBill>> {
+    match reminder.target_type {
+        ReminderTarget::Liability => {
+            let liability = sqlx::query_as::<_, Liability>("SELECT * FROM liabilities WHERE id = ? AND user_id = ? AND is_paid = FALSE")
+                .bind(&reminder.target_id)
+                .bind(&reminder.user_id)
+                .fetch_optional(pool)
+                .await?;
+            Ok(liability.map(|liability| UpcomingBill {
+                due_date: liability.due_date,
+                title: format!("Payment to {} due soon", liability.person_name),
+                body: format!(
+                    "{:.2} {} is due on {}.",
+                    liability.amount,
+                    liability.currency,
+                    liability.due_date.format("%Y-%m-%d")
+                ),
+            }))
+        }
+        ReminderTarget::CreditCard => {
+            let today = Utc::now().date_naive().and_hms_opt(0, 0, 0).unwrap_or_default().and_utc();
+            let statements = sqlx::query_as::<_, CardStatement>(
+                "SELECT * FROM card_statements WHERE account_id = ? AND closing_balance > 0 AND due_date >= ? ORDER BY due_date"
+            )
+            .bind(&reminder.target_id)
+            .bind(format_db_datetime(today))
+            .fetch_all(pool)
+            .await?;
+            for statement in statements {
+                let status = card_statements::status(pool, statement).await?;
+                if status.is_paid {
+                    continue;
+                }
+                let statement = &status.statement;
+                let card_name: String = sqlx::query_scalar("SELECT name FROM accounts WHERE id = ?")
+                    .bind(&statement.account_id)
+                    .fetch_one(pool)
+                    .await?;
+                return Ok(Some(UpcomingBill {
+                    due_date: statement.due_date,
+                    title: format!("{} payment due soon", card_name),
+                    body: format!(
+                        "{:.2} {} is due on {} (minimum {:.2}).",
+                        statement.closing_balance - status.amount_paid,
+                        statement.currency,
+                        statement.due_date.format("%Y-%m-%d"),
+                        statement.minimum_due
+                    ),
+                }));
+            }
+            Ok(None)
+        }
+        ReminderTarget::RecurringTransaction => {
+            let recurring = sqlx::query_as::<_, RecurringTransaction>(
+                "SELECT * FROM recurring_transactions WHERE id = ? AND user_id = ? AND is_active = TRUE AND (end_date IS NULL OR end_date >= next_due_date)"
+            )
+            .bind(&reminder.target_id)
+            .bind(&reminder.user_id)
+            .fetch_optional(pool)
+            .await?;
+            Ok(recurring.map(|recurring| {
+                let name = recurring
+                    .description
+                    .clone()
+                    .or_else(|| recurring.category.clone())
+                    .unwrap_or_else(|| "Recurring transaction".to_string());
+                UpcomingBill {
+                    due_date: recurring.next_due_date,
+                    title: format!("{} due soon", name),
+                    body: format!(
+                        "{:.2} {} is due on {}.",
+                        recurring.amount,
+                        recurring.currency,
+                        recurring.next_due_date.format("%Y-%m-%d")
+                    ),
+                }
+            }))
+        }
+    }
+}
+
+/// The reminder with its bill's next due date and when it goes out.
+pub async fn schedule(pool: &DbPool, reminder: BillReminder) -> Result<BillReminderSchedule> {
+    let next_due_date = upcoming_bill(pool, &reminder).await?.map(|bill| bill.due_date);
+    let remind_at = next_due_date.map(|due| due - ChronoDuration::days(reminder.days_before));
+    Ok(BillReminderSchedule { reminder, next_due_date, remind_at })
+}
+
+/// Queues a `bill_reminder` for every active bill reminder whose lead time has been reached,
+/// through the end of the due date. Each reminder goes out once per due date.
+pub async fn remind_scheduled_bills(pool: &DbPool) -> Result<usize> {
+    let now = Utc::now();
+    let reminders = sqlx::query_as::<_, BillReminder>("SELECT * FROM bill_reminders WHERE is_active = TRUE")
+        .fetch_all(pool)
+        .await?;
+
+    let mut reminded = 0;
+    for reminder in &reminders {
+        let Some(bill) = upcoming_bill(pool, reminder).await? else {
+            continue;
+        };
+        let remind_at = bill.due_date - ChronoDuration::days(reminder.days_before);
+        if now < remind_at || now >= bill.due_date + ChronoDuration::days(1) {
+            continue;
+        }
+        let queued = notifications::enqueue(pool, NewNotification {
+            user_id: &reminder.user_id,
+            kind: "bill_reminder",
+            title: bill.title.clone(),
+            body: bill.body.clone(),
+            entity: Some((reminder.target_type.as_str(), &reminder.target_id)),
+            dedupe_key: Some(format!("bill_reminder:{}:{}", reminder.id, bill.due_date.format("%Y-%m-%d"))),
+        })
+        .await;
+
+        match queued {
+            Ok(true) if reminder.send_email => email_reminder(pool, &reminder.user_id, &bill.title, &bill.body).await?,
+            Ok(_) => {}
+            Err(e) => tracing::error!("Failed to queue bill_reminder notification for user {}: {}", reminder.user_id, e),
+        }
+        reminded += 1;
+    }
+
+    Ok(reminded)
+}
+
+async fn email_reminder(pool: &DbPool, user_id: &str, title: &str, body: &str) -> Result<()> {
+    let recipient = sqlx::query("SELECT name, email FROM users WHERE id = ?")
+        .bind(user_id)
+        .fetch_optional(pool)
+        .await?;
+    if let Some(row) = recipient {
+        let (name, email): (String, String) = (row.get("name"), row.get("email"));
+        mailer::send(pool, Some(user_id), &email, EmailTemplate::Reminder {
+            name: &name,
+            title,
+            body,
+        })
+        .await;
+    }
+    Ok(())
+}
+
+/// Each kind of reminder is its own job, so one failing doesn't hold up the others.
+pub fn schedule_reminder_jobs(scheduler: &mut Scheduler) {
+    scheduler.every("liability_reminders", REMINDER_INTERVAL, |pool| async move {
+        Ok(remind_due_liabilities(&pool).await? as u64)
+    });
+    scheduler.every("deposit_maturity_reminders", REMINDER_INTERVAL, |pool| async move {
+        Ok(remind_maturing_deposits(&pool).await? as u64)
+    });
+    scheduler.every("card_payment_reminders", REMINDER_INTERVAL, |pool| async move {
+        Ok(remind_card_payments(&pool).await? as u64)
+    });
+    scheduler.every("bill_reminders", REMINDER_INTERVAL, |pool| async move {
+        Ok(remind_scheduled_bills(&pool).await? as u64)
+    });
+}
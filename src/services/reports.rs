@@ -0,0 +1,526 @@
+use std::collections::BTreeMap;
+
+use anyhow::Result;
+use chrono::{DateTime, Datelike, Duration, Months, NaiveDate, Utc};
+use rust_decimal::Decimal;
+use sqlx::sqlite::SqliteRow;
+use sqlx::Row;
+
+use crate::models::{
+    CashflowBucket, CashflowSeries, CategoryForecast, CategoryTotal, ConsolidatedTotals, CurrencyTotals,
+    ForecastTotals, Granularity, MonthAmount, MonthlySummary, ReportTransaction,
+};
+use crate::services::currency;
+use crate::services::database::DbPool;
+use crate::services::user_time::UserTime;
+use crate::utils::datetime::format_db_datetime;
+use crate::utils::money::Money;
+
+/// How many expense categories the monthly summary lists.
+const TOP_CATEGORIES_LIMIT: i64 = 5;
+
+/// How many of the month's biggest transactions the monthly summary lists.
+const LARGEST_TRANSACTIONS_LIMIT: i64 = 5;
+
+/// Leaves out transactions on accounts flagged `exclude_from_totals`. Expects the user ID bound
+/// where it appears.
+pub const INCLUDED_ACCOUNTS_FILTER: &str =
+    "account_id NOT IN (SELECT id FROM accounts WHERE user_id = ? AND exclude_from_totals = TRUE)";
+
+/// Buckets a cash-flow report covers when no start date is given.
+pub const DEFAULT_CASHFLOW_BUCKETS: u32 = 12;
+
+/// Most buckets one cash-flow report may span (five years of weeks).
+pub const MAX_CASHFLOW_BUCKETS: usize = 260;
+
+/// Complete months of history a forecast is fitted on.
+const FORECAST_HISTORY_MONTHS: u32 = 6;
+
+/// Months averaged for the moving average; the trend compares this window to the one before.
+const MOVING_AVERAGE_MONTHS: usize = 3;
+
+/// Projecting further out than this is mostly noise.
+pub const MAX_FORECAST_MONTHS: u32 = 3;
+
+/// A spending category is flagged when its recent average rose by at least this much.
+const TRENDING_UP_PERCENT: f64 = 25.0;
+
+/// ...and by at least this amount, so tiny categories don't get flagged over pocket change.
+const TRENDING_UP_MIN_INCREASE: Money = Money::new(Decimal::ONE);
+
+/// The first day of `year`-`month`, if that is a real month.
+pub fn month_start(year: i32, month: u32) -> Option<NaiveDate> {
+    NaiveDate::from_ymd_opt(year, month, 1)
+}
+
+/// A row of `id, account_id, transaction_type, amount, currency, category, description, date`,
+/// dated on the user's clock.
+pub fn report_transaction(row: SqliteRow, time: &UserTime) -> ReportTransaction {
+    ReportTransaction {
+        id: row.get("id"),
+        account_id: row.get("account_id"),
+        transaction_type: row.get("transaction_type"),
+        amount: row.get("amount"),
+        currency: row.get("currency"),
+        category: row.get("category"),
+        description: row.get("description"),
+        date: time.localize(row.get::<DateTime<Utc>, _>("date")),
+    }
+}
+
+/// Opens a query with `buckets(bucket, start, finish)`: one row per period beginning on each of
+/// `starts` in the user's timezone, the last ending where `end` begins. SQLite can't shift
+/// dates into a named timezone, so transactions are joined against these UTC bounds instead.
+/// Bind the values of [`bucket_bounds`] first.
+fn buckets_cte(count: usize) -> String {
+    format!("WITH buckets(bucket, start, finish) AS (VALUES {})", vec!["(?, ?, ?)"; count].join(", "))
+}
+
+/// `(bucket, start, finish)` for [`buckets_cte`]; `bucket` is the local start date, `YYYY-MM-DD`.
+fn bucket_bounds(time: &UserTime, starts: &[NaiveDate], end: NaiveDate) -> Vec<(String, String, String)> {
+    starts
+        .iter()
+        .enumerate()
+        .map(|(i, start)| {
+            let finish = starts.get(i + 1).copied().unwrap_or(end);
+            (
+                start.format("%Y-%m-%d").to_string(),
+                format_db_datetime(time.start_of_day(*start)),
+                format_db_datetime(time.start_of_day(finish)),
+            )
+        })
+        .collect()
+}
+
+pub async fn excluded_account_count(pool: &DbPool, user_id: &str) -> Result<i64> {
+    let count = sqlx::query_scalar::<_, i64>(
+        "SELECT COUNT(*) FROM accounts WHERE user_id = ? AND exclude_from_totals = TRUE"
+    )
+    .bind(user_id)
+    .fetch_one(pool)
+    .await?;
+
+    Ok(count)
+}
+
+/// Income, expenses, top expense categories and largest transactions for the month starting at
+/// `first_day`, per currency. The month runs from midnight to midnight in the user's timezone.
+/// Everything is aggregated in SQL.
+pub async fn monthly_summary(pool: &DbPool, user_id: &str, first_day: NaiveDate, time: &UserTime) -> Result<MonthlySummary> {
+    let start = time.start_of_day(first_day);
+    let end = time.start_of_day(first_day + Months::new(1));
+    let (start_str, end_str) = (format_db_datetime(start), format_db_datetime(end));
+
+    let totals_sql = format!(
+        r#"
+        SELECT currency,
+               COALESCE(SUM(CASE WHEN transaction_type = 'income' THEN amount END), 0.0) AS income,
+               COALESCE(SUM(CASE WHEN transaction_type = 'expense' THEN amount END), 0.0) AS expenses,
+               COUNT(*) AS transaction_count
+        FROM transactions
+        WHERE user_id = ? AND date >= ? AND date < ? AND transaction_type IN ('income', 'expense') AND {}
+        GROUP BY currency
+        ORDER BY currency
+        "#,
+        INCLUDED_ACCOUNTS_FILTER
+    );
+    let totals: Vec<CurrencyTotals> = sqlx::query(&totals_sql)
+        .bind(user_id)
+        .bind(&start_str)
+        .bind(&end_str)
+        .bind(user_id)
+        .fetch_all(pool)
+        .await?
+        .into_iter()
+        .map(|row| {
+            let (income, expenses): (Money, Money) = (row.get("income"), row.get("expenses"));
+            CurrencyTotals {
+                currency: row.get("currency"),
+                income,
+                expenses,
+                net: income - expenses,
+                transaction_count: row.get("transaction_count"),
+            }
+        })
+        .collect();
+
+    let categories_sql = format!(
+        r#"
+        SELECT COALESCE(category, 'Uncategorized') AS category, currency, SUM(amount) AS total, COUNT(*) AS transaction_count
+        FROM transactions
+        WHERE user_id = ? AND date >= ? AND date < ? AND transaction_type = 'expense' AND {}
+        GROUP BY COALESCE(category, 'Uncategorized'), currency
+        ORDER BY total DESC
+        LIMIT ?
+        "#,
+        INCLUDED_ACCOUNTS_FILTER
+    );
+    let top_categories = sqlx::query(&categories_sql)
+        .bind(user_id)
+        .bind(&start_str)
+        .bind(&end_str)
+        .bind(user_id)
+        .bind(TOP_CATEGORIES_LIMIT)
+        .fetch_all(pool)
+        .await?
+        .into_iter()
+        .map(|row| {
+            let currency: String = row.get("currency");
+            let total: Money = row.get("total");
+            let expenses = totals
+                .iter()
+                .find(|t| t.currency == currency)
+                .map_or(Money::ZERO, |t| t.expenses);
+            let percent = if expenses.is_positive() { total.ratio(expenses) * 100.0 } else { 0.0 };
+            CategoryTotal {
+                category: row.get("category"),
+                currency,
+                total,
+                transaction_count: row.get("transaction_count"),
+                percent_of_expenses: (percent * 100.0).round() / 100.0,
+            }
+        })
+        .collect();
+
+    let largest_sql = format!(
+        r#"
+        SELECT id, account_id, transaction_type, amount, currency, category, description, date
+        FROM transactions
+        WHERE user_id = ? AND date >= ? AND date < ? AND transaction_type IN ('income', 'expense') AND {}
+        ORDER BY amount DESC, date DESC
+        LIMIT ?
+        "#,
+        INCLUDED_ACCOUNTS_FILTER
+    );
+    let largest_transactions = sqlx::query(&largest_sql)
+        .bind(user_id)
+        .bind(&start_str)
+        .bind(&end_str)
+        .bind(user_id)
+        .bind(LARGEST_TRANSACTIONS_LIMIT)
+        .fetch_all(pool)
+        .await?
+        .into_iter()
+        .map(|row| report_transaction(row, time))
+        .collect();
+
+    let consolidated = consolidated_totals(pool, user_id, &start_str, &end_str).await?;
+
+    Ok(MonthlySummary {
+        month: first_day.format("%Y-%m").to_string(),
+        totals,
+        consolidated,
+        top_categories,
+        largest_transactions,
+        excluded_accounts: excluded_account_count(pool, user_id).await?,
+    })
+}
+
+/// Income and expenses between `start` and `end` in the user's display currency. Transactions
+/// with a base amount captured in that currency use it; the rest are converted at their
+/// day's rate.
+async fn consolidated_totals(pool: &DbPool, user_id: &str, start: &str, end: &str) -> Result<ConsolidatedTotals> {
+    let target = currency::display_currency(pool, user_id).await?;
+    let sql = format!(
+        r#"
+        SELECT currency, transaction_type, substr(date, 1, 10) AS day,
+               COALESCE(SUM(CASE WHEN base_currency = ? THEN base_amount END), 0.0) AS captured,
+               COALESCE(SUM(CASE WHEN base_currency = ? AND base_amount IS NOT NULL THEN NULL ELSE amount END), 0.0) AS uncaptured,
+               COUNT(CASE WHEN base_currency = ? AND base_amount IS NOT NULL THEN NULL ELSE 1 END) AS uncaptured_count
+        FROM transactions
+        WHERE user_id = ? AND date >= ? AND date < ? AND transaction_type IN ('income', 'expense') AND {}
+        GROUP BY currency, transaction_type, substr(date, 1, 10)
+        "#,
+        INCLUDED_ACCOUNTS_FILTER
+    );
+    let rows = sqlx::query(&sql)
+        .bind(&target)
+        .bind(&target)
+        .bind(&target)
+        .bind(user_id)
+        .bind(start)
+        .bind(end)
+        .bind(user_id)
+        .fetch_all(pool)
+        .await?;
+
+    let mut converter = currency::Converter::new(pool, user_id, &target);
+    let (mut income, mut expenses) = (Money::ZERO, Money::ZERO);
+    let mut unconverted_currencies: Vec<String> = Vec::new();
+    for row in rows {
+        let currency: String = row.get("currency");
+        let mut total: Money = row.get("captured");
+        if row.get::<i64, _>("uncaptured_count") > 0 {
+            let uncaptured: Money = row.get("uncaptured");
+            let converted = match NaiveDate::parse_from_str(&row.get::<String, _>("day"), "%Y-%m-%d") {
+                Ok(day) => converter.convert(uncaptured, &currency, day).await?,
+                Err(_) => None,
+            };
+            match converted {
+                Some(amount) => total += amount,
+                None => {
+                    if !unconverted_currencies.contains(&currency) {
+                        unconverted_currencies.push(currency);
+                    }
+                }
+            }
+        }
+        if row.get::<String, _>("transaction_type") == "income" {
+            income += total;
+        } else {
+            expenses += total;
+        }
+    }
+    unconverted_currencies.sort();
+
+    Ok(ConsolidatedTotals {
+        currency: target,
+        income,
+        expenses,
+        net: income - expenses,
+        unconverted_currencies,
+    })
+}
+
+/// The start of `date`'s week, on the user's first day of the week, or the first of its month.
+pub fn bucket_start(granularity: Granularity, date: NaiveDate, time: &UserTime) -> NaiveDate {
+    match granularity {
+        Granularity::Week => time.week_start(date),
+        Granularity::Month => date.with_day(1).unwrap_or(date),
+    }
+}
+
+fn next_bucket(granularity: Granularity, start: NaiveDate) -> NaiveDate {
+    match granularity {
+        Granularity::Week => start + Duration::days(7),
+        Granularity::Month => start + Months::new(1),
+    }
+}
+
+/// Start of the default range: `DEFAULT_CASHFLOW_BUCKETS` buckets ending with the one holding `to`.
+pub fn default_cashflow_start(granularity: Granularity, to: NaiveDate, time: &UserTime) -> NaiveDate {
+    let last = bucket_start(granularity, to, time);
+    match granularity {
+        Granularity::Week => last - Duration::weeks(DEFAULT_CASHFLOW_BUCKETS as i64 - 1),
+        Granularity::Month => last - Months::new(DEFAULT_CASHFLOW_BUCKETS - 1),
+    }
+}
+
+/// Start dates of every bucket overlapping `from..=to`, stopping once past `MAX_CASHFLOW_BUCKETS`.
+pub fn bucket_starts(granularity: Granularity, from: NaiveDate, to: NaiveDate, time: &UserTime) -> Vec<NaiveDate> {
+    let mut starts = Vec::new();
+    let mut start = bucket_start(granularity, from, time);
+    while start <= to && starts.len() <= MAX_CASHFLOW_BUCKETS {
+        starts.push(start);
+        start = next_bucket(granularity, start);
+    }
+    starts
+}
+
+/// Income vs expenses per bucket and currency, grouped in SQL. `starts` comes from
+/// [`bucket_starts`]; the range ends after `to`, in the user's timezone.
+pub async fn cashflow(
+    pool: &DbPool,
+    user_id: &str,
+    starts: &[NaiveDate],
+    to: NaiveDate,
+    time: &UserTime,
+) -> Result<Vec<CashflowSeries>> {
+    let Some(first) = starts.first() else {
+        return Ok(Vec::new());
+    };
+    let sql = format!(
+        r#"
+        {}
+        SELECT b.bucket AS bucket, t.currency AS currency,
+               COALESCE(SUM(CASE WHEN t.transaction_type = 'income' THEN t.amount END), 0.0) AS income,
+               COALESCE(SUM(CASE WHEN t.transaction_type = 'expense' THEN t.amount END), 0.0) AS expenses
+        FROM transactions t
+        JOIN buckets b ON t.date >= b.start AND t.date < b.finish
+        WHERE t.user_id = ? AND t.date >= ? AND t.date < ? AND t.transaction_type IN ('income', 'expense') AND {}
+        GROUP BY b.bucket, t.currency
+        "#,
+        buckets_cte(starts.len()),
+        INCLUDED_ACCOUNTS_FILTER
+    );
+    let end = to + Duration::days(1);
+    let bounds = bucket_bounds(time, starts, end);
+    let mut query = sqlx::query(&sql);
+    for (bucket, start, finish) in &bounds {
+        query = query.bind(bucket).bind(start).bind(finish);
+    }
+    let rows = query
+        .bind(user_id)
+        .bind(format_db_datetime(time.start_of_day(*first)))
+        .bind(format_db_datetime(time.start_of_day(end)))
+        .bind(user_id)
+        .fetch_all(pool)
+        .await?;
+
+    let mut by_currency: BTreeMap<String, BTreeMap<NaiveDate, (Money, Money)>> = BTreeMap::new();
+    for row in rows {
+        let Ok(bucket) = NaiveDate::parse_from_str(&row.get::<String, _>("bucket"), "%Y-%m-%d") else {
+            continue;
+        };
+        by_currency
+            .entry(row.get("currency"))
+            .or_default()
+            .insert(bucket, (row.get("income"), row.get("expenses")));
+    }
+
+    let series = by_currency
+        .into_iter()
+        .map(|(currency, amounts)| CashflowSeries {
+            currency,
+            buckets: starts
+                .iter()
+                .map(|start| {
+                    let (income, expenses) = amounts.get(start).copied().unwrap_or_default();
+                    CashflowBucket {
+                        start: *start,
+                        income,
+                        expenses,
+                        net: income - expenses,
+                    }
+                })
+                .collect(),
+        })
+        .collect();
+    Ok(series)
+}
+
+fn round2(value: f64) -> f64 {
+    (value * 100.0).round() / 100.0
+}
+
+fn month_key(date: NaiveDate) -> String {
+    date.format("%Y-%m").to_string()
+}
+
+/// Fits each category's last `FORECAST_HISTORY_MONTHS` complete months before `this_month` and
+/// projects the next `months` months, starting with `this_month`. Months follow the user's
+/// timezone.
+///
+/// The projection is the recent moving average plus its monthly drift: the difference between
+/// the last two averaging windows spread over the months separating them. It never goes below zero.
+pub async fn forecast(
+    pool: &DbPool,
+    user_id: &str,
+    this_month: NaiveDate,
+    months: u32,
+    time: &UserTime,
+) -> Result<(Vec<CategoryForecast>, Vec<ForecastTotals>)> {
+    let history_start = this_month - Months::new(FORECAST_HISTORY_MONTHS);
+    let history_months: Vec<NaiveDate> = (0..FORECAST_HISTORY_MONTHS).map(|i| history_start + Months::new(i)).collect();
+    let future_months: Vec<NaiveDate> = (0..months).map(|i| this_month + Months::new(i)).collect();
+
+    let sql = format!(
+        r#"
+        {}
+        SELECT t.transaction_type AS transaction_type, COALESCE(t.category, 'Uncategorized') AS category,
+               t.currency AS currency, substr(b.bucket, 1, 7) AS month, SUM(t.amount) AS total
+        FROM transactions t
+        JOIN buckets b ON t.date >= b.start AND t.date < b.finish
+        WHERE t.user_id = ? AND t.date >= ? AND t.date < ? AND t.transaction_type IN ('income', 'expense') AND {}
+        GROUP BY t.transaction_type, COALESCE(t.category, 'Uncategorized'), t.currency, month
+        "#,
+        buckets_cte(history_months.len()),
+        INCLUDED_ACCOUNTS_FILTER
+    );
+    let bounds = bucket_bounds(time, &history_months, this_month);
+    let mut query = sqlx::query(&sql);
+    for (bucket, start, finish) in &bounds {
+        query = query.bind(bucket).bind(start).bind(finish);
+    }
+    let rows = query
+        .bind(user_id)
+        .bind(format_db_datetime(time.start_of_day(history_start)))
+        .bind(format_db_datetime(time.start_of_day(this_month)))
+        .bind(user_id)
+        .fetch_all(pool)
+        .await?;
+
+    let mut series: BTreeMap<(String, String, String), BTreeMap<String, Money>> = BTreeMap::new();
+    for row in rows {
+        series
+            .entry((row.get("transaction_type"), row.get("category"), row.get("currency")))
+            .or_default()
+            .insert(row.get("month"), row.get("total"));
+    }
+
+    let mut forecasts = Vec::new();
+    let mut totals: BTreeMap<(String, String), (Money, Money)> = BTreeMap::new();
+    for ((transaction_type, category, currency), by_month) in series {
+        let amounts: Vec<Money> = history_months
+            .iter()
+            .map(|m| by_month.get(&month_key(*m)).copied().unwrap_or_default())
+            .collect();
+        let recent = &amounts[amounts.len() - MOVING_AVERAGE_MONTHS..];
+        let earlier = &amounts[amounts.len() - 2 * MOVING_AVERAGE_MONTHS..amounts.len() - MOVING_AVERAGE_MONTHS];
+        let window = Decimal::from(MOVING_AVERAGE_MONTHS);
+        let recent_avg = recent.iter().sum::<Money>() / window;
+        let earlier_avg = earlier.iter().sum::<Money>() / window;
+        let drift = (recent_avg - earlier_avg) / window;
+
+        let change_percent = earlier_avg
+            .is_positive()
+            .then(|| round2((recent_avg - earlier_avg).ratio(earlier_avg) * 100.0));
+        let trending_up = transaction_type == "expense"
+            && recent_avg - earlier_avg >= TRENDING_UP_MIN_INCREASE
+            && change_percent.is_none_or(|change| change >= TRENDING_UP_PERCENT);
+
+        // The recent average sits mid-window, (n + 1) / 2 months before this month.
+        let projections: Vec<MonthAmount> = future_months
+            .iter()
+            .enumerate()
+            .map(|(i, month)| {
+                let steps = Decimal::from(MOVING_AVERAGE_MONTHS + 1) / Decimal::TWO + Decimal::from(i);
+                let amount = currency::round((recent_avg + drift * steps).max(Money::ZERO), &currency);
+                let entry = totals.entry((month_key(*month), currency.clone())).or_default();
+                if transaction_type == "income" {
+                    entry.0 += amount;
+                } else {
+                    entry.1 += amount;
+                }
+                MonthAmount { month: month_key(*month), amount }
+            })
+            .collect();
+
+        let moving_average = currency::round(recent_avg, &currency);
+        forecasts.push(CategoryForecast {
+            transaction_type,
+            category,
+            currency,
+            history: history_months
+                .iter()
+                .zip(&amounts)
+                .map(|(m, amount)| MonthAmount { month: month_key(*m), amount: *amount })
+                .collect(),
+            moving_average,
+            change_percent,
+            trending_up,
+            projections,
+        });
+    }
+
+    // Flagged categories first, then by projected size
+    forecasts.sort_by(|a, b| {
+        let projected = |f: &CategoryForecast| f.projections.first().map_or(Money::ZERO, |p| p.amount);
+        b.trending_up
+            .cmp(&a.trending_up)
+            .then(a.transaction_type.cmp(&b.transaction_type))
+            .then(projected(b).cmp(&projected(a)))
+    });
+
+    let totals = totals
+        .into_iter()
+        .map(|((month, currency), (income, expenses))| ForecastTotals {
+            month,
+            currency,
+            income,
+            expenses,
+            net: income - expenses,
+        })
+        .collect();
+    Ok((forecasts, totals))
+}
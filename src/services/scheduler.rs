@@ -0,0 +1,212 @@
+use std::collections::hash_map::RandomState;
+use std::future::Future;
+use std::hash::{BuildHasher, Hasher};
+use std::pin::Pin;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+use anyhow::Result;
+use chrono::{DateTime, Utc};
+use tracing::Instrument;
+
+use crate::config::{self, Features};
+use crate::services::cache;
+use crate::services::database::DbPool;
+use crate::services::shutdown::{Coordinator, Shutdown};
+use crate::utils::datetime::format_db_datetime;
+
+/// Runs kept in `job_runs` per job; older ones are pruned as new ones finish.
+const KEPT_RUNS: i64 = 200;
+
+/// Attempts a run gets before it is recorded as failed, unless the job sets its own.
+const DEFAULT_ATTEMPTS: u32 = 3;
+
+/// First retry delay; each later one doubles, plus up to this much random jitter so jobs that
+/// failed together don't retry in lockstep.
+const DEFAULT_RETRY_DELAY: Duration = Duration::from_secs(5);
+
+type RunFuture = Pin<Box<dyn Future<Output = Result<u64>> + Send>>;
+type RunFn = Arc<dyn Fn(DbPool) -> RunFuture + Send + Sync>;
+
+/// A registered job. A run returns how many items it handled, e.g. emails sent.
+pub struct Job {
+    name: &'static str,
+    interval: Duration,
+    attempts: u32,
+    invalidates_cache: bool,
+    enabled: fn(&Features) -> bool,
+    run: RunFn,
+}
+
+impl Job {
+    /// Retries a failed run up to `retries` times before recording it as failed. Jobs that run
+    /// every few seconds anyway, or retry per item, are better off with none.
+    pub fn retries(&mut self, retries: u32) -> &mut Self {
+        self.attempts = retries + 1;
+        self
+    }
+
+    /// Leaves the cache alone after runs that handled something. By default such a run drops
+    /// every cached read, as it may have changed data behind them; jobs that only deliver or
+    /// prune bookkeeping don't need to.
+    pub fn keeps_cache(&mut self) -> &mut Self {
+        self.invalidates_cache = false;
+        self
+    }
+
+    /// Skips runs while `enabled` says no for the features in effect, checked on every tick
+    /// so a config reload can switch the job on or off. Jobs otherwise run while
+    /// `features.background_jobs` is on.
+    pub fn only_when(&mut self, enabled: fn(&Features) -> bool) -> &mut Self {
+        self.enabled = enabled;
+        self
+    }
+}
+
+/// Hosts the periodic background jobs: each runs on its own interval, failed runs are retried
+/// with jittered backoff, every run is recorded in `job_runs`, and all of them stop through
+/// the shutdown [`Coordinator`].
+pub struct Scheduler {
+    pool: DbPool,
+    jobs: Vec<Job>,
+    retry_delay: Duration,
+}
+
+impl Scheduler {
+    pub fn new(pool: DbPool) -> Self {
+        Self {
+            pool,
+            jobs: Vec::new(),
+            retry_delay: DEFAULT_RETRY_DELAY,
+        }
+    }
+
+    /// Sets the first retry delay, which is otherwise [`DEFAULT_RETRY_DELAY`].
+    pub fn with_retry_delay(mut self, delay: Duration) -> Self {
+        self.retry_delay = delay;
+        self
+    }
+
+    /// Runs `run` every `interval`, the first time as soon as the scheduler starts.
+    pub fn every<F, Fut>(&mut self, name: &'static str, interval: Duration, run: F) -> &mut Job
+    where
+        F: Fn(DbPool) -> Fut + Send + Sync + 'static,
+        Fut: Future<Output = Result<u64>> + Send + 'static,
+    {
+        self.jobs.push(Job {
+            name,
+            interval,
+            attempts: DEFAULT_ATTEMPTS,
+            invalidates_cache: true,
+            enabled: |features| features.background_jobs,
+            run: Arc::new(move |pool| Box::pin(run(pool))),
+        });
+        self.jobs.last_mut().expect("job was just added")
+    }
+
+    pub fn job_names(&self) -> Vec<&'static str> {
+        self.jobs.iter().map(|job| job.name).collect()
+    }
+
+    /// Starts every job; each stops after its current run once shutdown begins.
+    pub fn start(self, coordinator: &Coordinator) {
+        tracing::info!("⏰ Scheduling {} background jobs: {}", self.jobs.len(), self.job_names().join(", "));
+        for job in self.jobs {
+            let pool = self.pool.clone();
+            let retry_delay = self.retry_delay;
+            let mut shutdown = coordinator.handle();
+            let span = tracing::info_span!("job", name = job.name);
+            tokio::spawn(
+                async move {
+                    let mut interval = tokio::time::interval(job.interval);
+                    while shutdown.tick(&mut interval).await {
+                        if (job.enabled)(&config::current().features) {
+                            run_job(&pool, &job, retry_delay, &mut shutdown).await;
+                        }
+                    }
+                }
+                .instrument(span),
+            );
+        }
+    }
+}
+
+/// One scheduled run, retried while attempts remain, then recorded.
+async fn run_job(pool: &DbPool, job: &Job, retry_delay: Duration, shutdown: &mut Shutdown) {
+    let started_at = Utc::now();
+    let started = Instant::now();
+    let mut attempts = 0;
+    let result = loop {
+        attempts += 1;
+        match (job.run)(pool.clone()).await {
+            Ok(processed) => break Ok(processed),
+            Err(e) if attempts < job.attempts => {
+                let delay = retry_delay * 2u32.pow(attempts - 1) + jitter(retry_delay);
+                tracing::warn!("Job {} failed (attempt {}/{}), retrying in {:?}: {:#}", job.name, attempts, job.attempts, delay, e);
+                if !shutdown.sleep(delay).await {
+                    break Err(e);
+                }
+            }
+            Err(e) => break Err(e),
+        }
+    };
+
+    let run = JobRun {
+        job: job.name,
+        started_at,
+        duration: started.elapsed(),
+        attempts,
+        outcome: result,
+    };
+    match &run.outcome {
+        Ok(processed) if *processed > 0 && job.invalidates_cache => cache::get().invalidate_all().await,
+        Ok(_) => {}
+        Err(e) => tracing::error!("Job {} failed after {} attempt(s): {:#}", job.name, attempts, e),
+    }
+    if let Err(e) = record_run(pool, &run).await {
+        tracing::error!("Failed to record the run of job {}: {}", job.name, e);
+    }
+}
+
+/// A finished run, as stored in `job_runs`.
+struct JobRun {
+    job: &'static str,
+    started_at: DateTime<Utc>,
+    duration: Duration,
+    attempts: u32,
+    outcome: Result<u64>,
+}
+
+async fn record_run(pool: &DbPool, run: &JobRun) -> Result<()> {
+    let (status, processed, error) = match &run.outcome {
+        Ok(processed) => ("succeeded", *processed, None),
+        Err(e) => ("failed", 0, Some(format!("{:#}", e))),
+    };
+    sqlx::query(
+        "INSERT INTO job_runs (job, started_at, finished_at, duration_ms, attempts, status, processed, error) VALUES (?, ?, ?, ?, ?, ?, ?, ?)"
+    )
+    .bind(run.job)
+    .bind(format_db_datetime(run.started_at))
+    .bind(format_db_datetime(Utc::now()))
+    .bind(run.duration.as_millis() as i64)
+    .bind(run.attempts as i64)
+    .bind(status)
+    .bind(processed as i64)
+    .bind(error)
+    .execute(pool)
+    .await?;
+
+    sqlx::query("DELETE FROM job_runs WHERE job = ? AND id <= (SELECT id FROM job_runs WHERE job = ? ORDER BY id DESC LIMIT 1 OFFSET ?)")
+        .bind(run.job)
+        .bind(run.job)
+        .bind(KEPT_RUNS)
+        .execute(pool)
+        .await?;
+    Ok(())
+}
+
+/// A random delay up to `max`.
+fn jitter(max: Duration) -> Duration {
+    let random = RandomState::new().build_hasher().finish();
+    max.mul_f64((random % 1000) as f64 / 1000.0)
+}
@@ -0,0 +1,207 @@
+use anyhow::Result;
+use bcrypt::{hash, DEFAULT_COST};
+use chrono::{Datelike, Duration, TimeZone, Utc};
+use uuid::Uuid;
+
+use crate::services::database::DbPool;
+use crate::utils::datetime::format_db_datetime;
+use crate::utils::money::Money;
+
+pub const DEMO_EMAIL: &str = "demo@example.com";
+pub const DEMO_PASSWORD: &str = "demo-password";
+
+/// Months of transaction history the demo user gets, counting the current one.
+const HISTORY_MONTHS: i64 = 3;
+
+/// `(name, account_type, balance, credit_limit)`, all in BDT.
+const ACCOUNTS: &[(&str, &str, i64, Option<i64>)] = &[
+    ("City Bank Salary", "bank", 84_250, None),
+    ("Cash Wallet", "cash", 3_420, None),
+    ("bKash", "mobile_banking", 2_150, None),
+    ("Visa Platinum", "credit_card", 18_640, Some(150_000)),
+    ("DBBL Savings", "savings", 152_000, None),
+];
+
+/// `(name, category_type, icon, color)`.
+const CATEGORIES: &[(&str, &str, &str, &str)] = &[
+    ("Salary", "income", "💼", "#4CAF50"),
+    ("Freelance", "income", "💻", "#8BC34A"),
+    ("Rent", "expense", "🏠", "#795548"),
+    ("Groceries", "expense", "🛒", "#FF9800"),
+    ("Dining Out", "expense", "🍽️", "#F44336"),
+    ("Transport", "expense", "🚌", "#2196F3"),
+    ("Utilities", "expense", "💡", "#FFC107"),
+    ("Mobile & Internet", "expense", "📱", "#00BCD4"),
+    ("Shopping", "expense", "🛍️", "#E91E63"),
+    ("Health", "expense", "💊", "#009688"),
+];
+
+/// `(category, monthly amount)`.
+const BUDGETS: &[(&str, i64)] = &[("Groceries", 14_000), ("Dining Out", 5_000), ("Transport", 4_000), ("Shopping", 8_000)];
+
+/// Creates `demo@example.com` with a few months of realistic data: accounts of each common
+/// type, categories, transactions, budgets and savings goals. Returns false without touching
+/// anything when the demo user already exists.
+pub async fn seed_demo(pool: &DbPool) -> Result<bool> {
+    let existing: i64 = sqlx::query_scalar("SELECT COUNT(*) FROM users WHERE email = ?")
+        .bind(DEMO_EMAIL)
+        .fetch_one(pool)
+        .await?;
+    if existing > 0 {
+        return Ok(false);
+    }
+
+    let now = Utc::now();
+    let now_str = format_db_datetime(now);
+    let user_id = Uuid::new_v4().to_string();
+    let password_hash = hash(DEMO_PASSWORD, DEFAULT_COST)?;
+    let mut tx = pool.begin().await?;
+
+    sqlx::query(
+        "INSERT INTO users (id, name, email, password_hash, created_at, updated_at, email_verified_at) VALUES (?, ?, ?, ?, ?, ?, ?)"
+    )
+    .bind(&user_id)
+    .bind("Demo User")
+    .bind(DEMO_EMAIL)
+    .bind(&password_hash)
+    .bind(&now_str)
+    .bind(&now_str)
+    .bind(&now_str)
+    .execute(&mut tx)
+    .await?;
+
+    let mut account_ids = Vec::new();
+    for (name, account_type, balance, credit_limit) in ACCOUNTS {
+        let id = Uuid::new_v4().to_string();
+        let (statement_day, payment_due_day) = if *account_type == "credit_card" { (Some(20), Some(5)) } else { (None, None) };
+        sqlx::query(
+            "INSERT INTO accounts (id, user_id, name, account_type, balance, currency, credit_limit, statement_day, payment_due_day, created_at, updated_at) VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?)"
+        )
+        .bind(&id)
+        .bind(&user_id)
+        .bind(name)
+        .bind(account_type)
+        .bind(Money::from(*balance))
+        .bind("BDT")
+        .bind(credit_limit.map(Money::from))
+        .bind(statement_day)
+        .bind(payment_due_day)
+        .bind(&now_str)
+        .bind(&now_str)
+        .execute(&mut tx)
+        .await?;
+        account_ids.push(id);
+    }
+    let [bank, cash, bkash, card, savings] = <[String; 5]>::try_from(account_ids).expect("one id per account");
+
+    for (name, category_type, icon, color) in CATEGORIES {
+        sqlx::query(
+            "INSERT INTO categories (id, name, category_type, icon, color, is_default, created_at, user_id, updated_at) VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?)"
+        )
+        .bind(Uuid::new_v4().to_string())
+        .bind(name)
+        .bind(category_type)
+        .bind(icon)
+        .bind(color)
+        .bind(false)
+        .bind(&now_str)
+        .bind(&user_id)
+        .bind(&now_str)
+        .execute(&mut tx)
+        .await?;
+    }
+
+    let this_month = Utc.with_ymd_and_hms(now.year(), now.month(), 1, 9, 0, 0).unwrap();
+    for months_back in (0..HISTORY_MONTHS).rev() {
+        let month_start = (0..months_back).fold(this_month, |start, _| {
+            let previous = start - Duration::days(1);
+            Utc.with_ymd_and_hms(previous.year(), previous.month(), 1, 9, 0, 0).unwrap()
+        });
+        let on = |day: i64| month_start + Duration::days(day - 1);
+        let step = months_back * 3;
+
+        let mut entries = vec![
+            (on(1), &bank, "income", 85_000, "Salary", "Monthly salary"),
+            (on(3), &bank, "expense", 25_000, "Rent", "Apartment rent"),
+            (on(6), &bkash, "expense", 1_200 + step * 10, "Utilities", "Electricity bill"),
+            (on(7), &bkash, "expense", 799, "Mobile & Internet", "Home internet"),
+            (on(14), &cash, "expense", 650 + step * 25, "Health", "Pharmacy"),
+            (on(18), &bank, "income", 12_000 + step * 500, "Freelance", "Website project"),
+            (on(22), &card, "expense", 4_350 + step * 120, "Shopping", "Clothing"),
+            (on(25), &bank, "transfer", 10_000, "Savings", "Monthly savings"),
+        ];
+        for week in 0..4 {
+            let day = 2 + week * 7;
+            entries.push((on(day), &cash, "expense", 2_800 + (week + step) % 4 * 350, "Groceries", "Weekly groceries"));
+            entries.push((on(day + 1), &bkash, "expense", 180 + (week + step) % 3 * 40, "Transport", "Ride share"));
+            entries.push((on(day + 4), &card, "expense", 950 + (week + step) % 5 * 150, "Dining Out", "Dinner out"));
+        }
+
+        for (date, account_id, transaction_type, amount, category, description) in entries {
+            if date > now {
+                continue;
+            }
+            sqlx::query(
+                "INSERT INTO transactions (id, user_id, account_id, transaction_type, amount, currency, category, description, date, created_at) VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?, ?)"
+            )
+            .bind(Uuid::new_v4().to_string())
+            .bind(&user_id)
+            .bind(account_id)
+            .bind(transaction_type)
+            .bind(Money::from(amount))
+            .bind("BDT")
+            .bind(category)
+            .bind(description)
+            .bind(format_db_datetime(date))
+            .bind(&now_str)
+            .execute(&mut tx)
+            .await?;
+        }
+    }
+
+    for (category, amount) in BUDGETS {
+        sqlx::query(
+            "INSERT INTO budgets (id, user_id, category, amount, currency, period, rollover, created_at, updated_at) VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?)"
+        )
+        .bind(Uuid::new_v4().to_string())
+        .bind(&user_id)
+        .bind(category)
+        .bind(Money::from(*amount))
+        .bind("BDT")
+        .bind("monthly")
+        .bind(false)
+        .bind(&now_str)
+        .bind(&now_str)
+        .execute(&mut tx)
+        .await?;
+    }
+
+    let goals = [
+        ("Emergency Fund", 300_000, 152_000, 365, Some(&savings), "high", "Six months of expenses"),
+        ("New Laptop", 120_000, 35_000, 180, None, "medium", "For freelance work"),
+        ("Cox's Bazar Trip", 40_000, 12_500, 90, None, "low", "Family holiday"),
+    ];
+    for (name, target, current, days, account_id, priority, description) in goals {
+        sqlx::query(
+            "INSERT INTO savings_goals (id, user_id, name, target_amount, current_amount, currency, target_date, description, account_id, priority, is_completed, created_at, updated_at) VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?)"
+        )
+        .bind(Uuid::new_v4().to_string())
+        .bind(&user_id)
+        .bind(name)
+        .bind(Money::from(target))
+        .bind(Money::from(current))
+        .bind("BDT")
+        .bind(format_db_datetime(now + Duration::days(days)))
+        .bind(description)
+        .bind(account_id)
+        .bind(priority)
+        .bind(false)
+        .bind(&now_str)
+        .bind(&now_str)
+        .execute(&mut tx)
+        .await?;
+    }
+
+    tx.commit().await?;
+    Ok(true)
+}
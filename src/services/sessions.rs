@@ -0,0 +1,205 @@
+use std::time::Duration;
+
+use anyhow::Result;
+use chrono::{Duration as ChronoDuration, NaiveDateTime, Utc};
+use sqlx::Row;
+use uuid::Uuid;
+
+use crate::models::ReauthReason;
+use crate::services::database::DbPool;
+use crate::services::preferences;
+use crate::services::scheduler::Scheduler;
+use crate::utils::datetime::format_db_datetime;
+use crate::utils::jwt::create_jwt;
+
+pub const IDLE_TIMEOUT_REASON: &str = "session_idle_timeout";
+pub const PASSWORD_RESET_REASON: &str = "password_reset";
+
+/// Accepted range for the idle-timeout preference (5 minutes to 30 days).
+pub const MIN_IDLE_TIMEOUT_MINUTES: i64 = 5;
+pub const MAX_IDLE_TIMEOUT_MINUTES: i64 = 30 * 24 * 60;
+
+/// `last_seen_at` is only rewritten when older than this, so busy clients don't write on every request.
+const TOUCH_THRESHOLD_SECS: i64 = 60;
+
+/// Sessions older than this can't back a live token any more and are deleted.
+const SESSION_RETENTION_DAYS: i64 = 30;
+
+const SESSION_SWEEP_INTERVAL: Duration = Duration::from_secs(5 * 60);
+
+pub enum SessionStatus {
+    Active,
+    Ended(String),
+}
+
+pub async fn idle_timeout_minutes(pool: &DbPool, user_id: &str) -> Result<Option<i64>> {
+    Ok(preferences::load(pool, user_id).await?.session_idle_timeout_minutes)
+}
+
+fn reauth_message(reason: &str, idle_minutes: Option<i64>) -> String {
+    match (reason, idle_minutes) {
+        (IDLE_TIMEOUT_REASON, Some(minutes)) => format!(
+            "You were signed out after {} minutes of inactivity, as set in your security preferences.",
+            minutes
+        ),
+        (IDLE_TIMEOUT_REASON, None) => "You were signed out after a period of inactivity.".to_string(),
+        (PASSWORD_RESET_REASON, _) => "Your password was reset, so you were signed out everywhere.".to_string(),
+        _ => "Your previous session was ended. Please sign in again.".to_string(),
+    }
+}
+
+/// Opens a session for a successful login and returns its token, plus the reason the previous
+/// session ended if the user hasn't been told yet.
+pub async fn start_session(pool: &DbPool, user_id: &str, device: &str) -> Result<(String, Option<ReauthReason>)> {
+    let now = format_db_datetime(Utc::now());
+
+    let unreported = sqlx::query_scalar::<_, String>(
+        "SELECT revoked_reason FROM sessions WHERE user_id = ? AND revoked_reason IS NOT NULL AND reason_reported = FALSE ORDER BY revoked_at DESC LIMIT 1",
+    )
+    .bind(user_id)
+    .fetch_optional(pool)
+    .await?;
+
+    let reauth_reason = match unreported {
+        Some(code) => {
+            sqlx::query("UPDATE sessions SET reason_reported = TRUE WHERE user_id = ? AND revoked_reason IS NOT NULL")
+                .bind(user_id)
+                .execute(pool)
+                .await?;
+            let idle_minutes = idle_timeout_minutes(pool, user_id).await?;
+            Some(ReauthReason {
+                message: reauth_message(&code, idle_minutes),
+                code,
+            })
+        }
+        None => None,
+    };
+
+    let session_id = Uuid::new_v4().to_string();
+    sqlx::query("INSERT INTO sessions (id, user_id, device, created_at, last_seen_at) VALUES (?, ?, ?, ?, ?)")
+        .bind(&session_id)
+        .bind(user_id)
+        .bind(device)
+        .bind(&now)
+        .bind(&now)
+        .execute(pool)
+        .await?;
+
+    let token = create_jwt(user_id, &session_id)?;
+    Ok((token, reauth_reason))
+}
+
+async fn revoke(pool: &DbPool, session_id: &str, reason: &str) -> Result<()> {
+    sqlx::query("UPDATE sessions SET revoked_at = ?, revoked_reason = ? WHERE id = ? AND revoked_at IS NULL")
+        .bind(format_db_datetime(Utc::now()))
+        .bind(reason)
+        .bind(session_id)
+        .execute(pool)
+        .await?;
+    Ok(())
+}
+
+/// Ends all of a user's live sessions, e.g. after their password changes.
+pub async fn revoke_user_sessions(pool: &DbPool, user_id: &str, reason: &str) -> Result<u64> {
+    let result = sqlx::query("UPDATE sessions SET revoked_at = ?, revoked_reason = ? WHERE user_id = ? AND revoked_at IS NULL")
+        .bind(format_db_datetime(Utc::now()))
+        .bind(reason)
+        .bind(user_id)
+        .execute(pool)
+        .await?;
+    Ok(result.rows_affected())
+}
+
+/// Checks a session on use: ends it if it has sat idle past the user's limit, otherwise
+/// marks it as seen.
+pub async fn touch_session(pool: &DbPool, session_id: &str, user_id: &str) -> Result<SessionStatus> {
+    let row = sqlx::query(
+        r#"
+        SELECT s.last_seen_at, s.revoked_reason, CAST(p.value AS INTEGER) AS session_idle_timeout_minutes
+        FROM sessions s
+        LEFT JOIN user_settings p ON p.user_id = s.user_id AND p.key = ?
+        WHERE s.id = ? AND s.user_id = ?
+        "#,
+    )
+    .bind(preferences::SESSION_IDLE_TIMEOUT_MINUTES)
+    .bind(session_id)
+    .bind(user_id)
+    .fetch_optional(pool)
+    .await?;
+
+    let Some(row) = row else {
+        return Ok(SessionStatus::Ended("session_revoked".to_string()));
+    };
+    if let Some(reason) = row.get::<Option<String>, _>("revoked_reason") {
+        return Ok(SessionStatus::Ended(reason));
+    }
+
+    let now = Utc::now();
+    let last_seen = NaiveDateTime::parse_from_str(&row.get::<String, _>("last_seen_at"), "%Y-%m-%d %H:%M:%S")
+        .map(|dt| dt.and_utc())
+        .unwrap_or(now);
+    let idle = now - last_seen;
+
+    if let Some(minutes) = row.get::<Option<i64>, _>("session_idle_timeout_minutes") {
+        if idle > ChronoDuration::minutes(minutes) {
+            revoke(pool, session_id, IDLE_TIMEOUT_REASON).await?;
+            return Ok(SessionStatus::Ended(IDLE_TIMEOUT_REASON.to_string()));
+        }
+    }
+
+    if idle > ChronoDuration::seconds(TOUCH_THRESHOLD_SECS) {
+        sqlx::query("UPDATE sessions SET last_seen_at = ? WHERE id = ?")
+            .bind(format_db_datetime(now))
+            .bind(session_id)
+            .execute(pool)
+            .await?;
+    }
+
+    Ok(SessionStatus::Active)
+}
+
+/// Ends every live session that has been idle longer than its owner's limit, and drops sessions
+/// too old to matter.
+pub async fn revoke_idle_sessions(pool: &DbPool) -> Result<u64> {
+    let now = format_db_datetime(Utc::now());
+
+    let revoked = sqlx::query(
+        r#"
+        UPDATE sessions SET revoked_at = ?, revoked_reason = ?
+        WHERE revoked_at IS NULL
+          AND EXISTS (
+              SELECT 1 FROM user_settings p
+              WHERE p.user_id = sessions.user_id
+                AND p.key = ?
+                AND sessions.last_seen_at < datetime(?, '-' || CAST(p.value AS INTEGER) || ' minutes')
+          )
+        "#,
+    )
+    .bind(&now)
+    .bind(IDLE_TIMEOUT_REASON)
+    .bind(preferences::SESSION_IDLE_TIMEOUT_MINUTES)
+    .bind(&now)
+    .execute(pool)
+    .await?
+    .rows_affected();
+
+    let cutoff = format_db_datetime(Utc::now() - ChronoDuration::days(SESSION_RETENTION_DAYS));
+    sqlx::query("DELETE FROM sessions WHERE last_seen_at < ?")
+        .bind(cutoff)
+        .execute(pool)
+        .await?;
+
+    Ok(revoked)
+}
+
+pub fn schedule_session_sweep_job(scheduler: &mut Scheduler) {
+    scheduler
+        .every("session_sweep", SESSION_SWEEP_INTERVAL, |pool| async move {
+            let revoked = revoke_idle_sessions(&pool).await?;
+            if revoked > 0 {
+                tracing::info!("Signed out {} idle sessions", revoked);
+            }
+            Ok(revoked)
+        })
+        .keeps_cache();
+}
@@ -0,0 +1,111 @@
+use std::time::Duration;
+
+use tokio::sync::{mpsc, watch};
+use tokio::time::Interval;
+
+/// Starts and tracks a graceful shutdown. Background jobs hold a [`Shutdown`] handle, stop
+/// once the run they are in finishes, and [`Coordinator::drain`] waits for all of them.
+pub struct Coordinator {
+    stop: watch::Sender<bool>,
+    running: mpsc::Sender<()>,
+    finished: mpsc::Receiver<()>,
+}
+
+/// A background job's view of shutdown. Dropping the last handle tells the coordinator the
+/// job has stopped.
+#[derive(Clone)]
+pub struct Shutdown {
+    stopping: watch::Receiver<bool>,
+    _running: mpsc::Sender<()>,
+}
+
+impl Coordinator {
+    pub fn new() -> Self {
+        let (stop, _) = watch::channel(false);
+        let (running, finished) = mpsc::channel(1);
+        Self { stop, running, finished }
+    }
+
+    pub fn handle(&self) -> Shutdown {
+        Shutdown {
+            stopping: self.stop.subscribe(),
+            _running: self.running.clone(),
+        }
+    }
+
+    /// Tells every job to stop after its current run.
+    pub fn begin(&self) {
+        self.stop.send_replace(true);
+    }
+
+    /// Resolves once [`Coordinator::begin`] has been called.
+    pub async fn stopping(&self) {
+        let mut stopping = self.stop.subscribe();
+        let _ = stopping.wait_for(|stopping| *stopping).await;
+    }
+
+    /// Waits up to `timeout` for every job to stop; false if some were still running.
+    pub async fn drain(self, timeout: Duration) -> bool {
+        self.begin();
+        let Self { running, mut finished, .. } = self;
+        drop(running);
+        tokio::time::timeout(timeout, finished.recv()).await.is_ok()
+    }
+}
+
+impl Default for Coordinator {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Shutdown {
+    /// Waits for the job's next run: true when `interval` ticks, false once shutdown has
+    /// begun, which ends the job's loop.
+    pub async fn tick(&mut self, interval: &mut Interval) -> bool {
+        tokio::select! {
+            biased;
+            _ = self.stopping.wait_for(|stopping| *stopping) => false,
+            _ = interval.tick() => true,
+        }
+    }
+
+    /// Waits `duration`: true when it has passed, false if shutdown began first.
+    pub async fn sleep(&mut self, duration: Duration) -> bool {
+        tokio::select! {
+            biased;
+            _ = self.stopping.wait_for(|stopping| *stopping) => false,
+            _ = tokio::time::sleep(duration) => true,
+        }
+    }
+}
+
+/// Resolves on Ctrl+C, or on SIGTERM where there is one, which is what `docker stop` sends.
+pub async fn signal() {
+    let ctrl_c = async {
+        if let Err(e) = tokio::signal::ctrl_c().await {
+            tracing::error!("Failed to listen for Ctrl+C: {}", e);
+            std::future::pending::<()>().await;
+        }
+    };
+
+    #[cfg(unix)]
+    let terminate = async {
+        match tokio::signal::unix::signal(tokio::signal::unix::SignalKind::terminate()) {
+            Ok(mut sigterm) => {
+                sigterm.recv().await;
+            }
+            Err(e) => {
+                tracing::error!("Failed to listen for SIGTERM: {}", e);
+                std::future::pending::<()>().await;
+            }
+        }
+    };
+    #[cfg(not(unix))]
+    let terminate = std::future::pending::<()>();
+
+    tokio::select! {
+        _ = ctrl_c => tracing::info!("🛑 Received Ctrl+C"),
+        _ = terminate => tracing::info!("🛑 Received SIGTERM"),
+    }
+}
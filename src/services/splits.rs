@@ -0,0 +1,237 @@
+use std::collections::HashMap;
+
+use anyhow::Result;
+use sqlx::{Row, SqliteConnection};
+
+use crate::models::{CurrencyInfo, FriendBalance, SplitExpense, SplitExpenseDetail, SplitSettlement, SplitShare, Transaction};
+use crate::services::database::DbPool;
+use crate::utils::datetime::format_db_datetime;
+use crate::utils::money::{Money, MONEY_SCALE};
+
+/// Split expenses the user paid for or has a share in.
+pub const INVOLVED_SPLITS: &str = "(paid_by = ? OR id IN (SELECT split_id FROM split_shares WHERE user_id = ?))";
+
+/// The user (`?1`) and everyone sharing a household with them.
+const HOUSEHOLD_PEOPLE: &str = "SELECT ?1 UNION SELECT other.user_id FROM household_members mine JOIN household_members other ON other.household_id = mine.household_id WHERE mine.user_id = ?1";
+
+/// Everyone on a split expense with the user (`?1`), as payer or sharer.
+const SPLIT_PEOPLE: &str = "SELECT paid_by FROM split_expenses WHERE id IN (SELECT split_id FROM split_shares WHERE user_id = ?1) UNION SELECT s.user_id FROM split_shares s JOIN split_expenses e ON e.id = s.split_id WHERE e.paid_by = ?1 OR e.id IN (SELECT split_id FROM split_shares WHERE user_id = ?1)";
+
+async fn resolve_among(pool: &DbPool, people: &str, user_id: &str, reference: &str) -> Result<Option<String>> {
+    let sql = format!("SELECT id FROM users WHERE (id = ?2 OR lower(email) = lower(?2)) AND id IN ({})", people);
+    Ok(sqlx::query_scalar::<_, String>(&sql)
+        .bind(user_id)
+        .bind(reference.trim())
+        .fetch_optional(pool)
+        .await?)
+}
+
+/// Finds, by ID or email address, the user or someone in one of their households: the people
+/// a split expense can name. Anyone else is `None`, the same as an unknown user.
+pub async fn resolve_member(pool: &DbPool, user_id: &str, reference: &str) -> Result<Option<String>> {
+    resolve_among(pool, HOUSEHOLD_PEOPLE, user_id, reference).await
+}
+
+/// Like [`resolve_member`], but also finds anyone already on a split expense with the user, so
+/// balances can be settled after someone leaves the household.
+pub async fn resolve_friend(pool: &DbPool, user_id: &str, reference: &str) -> Result<Option<String>> {
+    resolve_among(pool, &format!("{} UNION {}", HOUSEHOLD_PEOPLE, SPLIT_PEOPLE), user_id, reference).await
+}
+
+/// Splits `amount` into `count` shares in the currency's minor unit. Each gets the same whole
+/// number of units and the first `amount % count` get one unit more, so the shares add up
+/// exactly and none is negative.
+pub fn even_shares(amount: Money, count: usize, code: &str) -> Vec<Money> {
+    if count == 0 {
+        return Vec::new();
+    }
+    // Stored units in one of the currency's minor units
+    let per_unit = 10i64.pow(MONEY_SCALE - CurrencyInfo::decimals_for(code).min(MONEY_SCALE));
+    let units = amount.to_minor_units() / per_unit;
+    let count = count as i64;
+    (0..count)
+        .map(|i| Money::from_minor_units((units / count + i64::from(i < units % count)) * per_unit))
+        .collect()
+}
+
+/// Stores a real income or expense in one of the user's accounts, inside a database
+/// transaction.
+pub async fn insert_transaction(conn: &mut SqliteConnection, transaction: &Transaction) -> sqlx::Result<()> {
+    sqlx::query(
+        "INSERT INTO transactions (id, user_id, account_id, transaction_type, amount, currency, category, description, date, created_at, exchange_rate, base_amount, base_currency) VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?)"
+    )
+    .bind(&transaction.id)
+    .bind(&transaction.user_id)
+    .bind(&transaction.account_id)
+    .bind(transaction.transaction_type)
+    .bind(transaction.amount)
+    .bind(&transaction.currency)
+    .bind(&transaction.category)
+    .bind(&transaction.description)
+    .bind(format_db_datetime(transaction.date))
+    .bind(format_db_datetime(transaction.created_at))
+    .bind(transaction.exchange_rate)
+    .bind(transaction.base_amount)
+    .bind(&transaction.base_currency)
+    .execute(&mut *conn)
+    .await?;
+    Ok(())
+}
+
+/// Stores a split expense with its shares and, if given, the payer's expense transaction.
+pub async fn create_split(
+    pool: &DbPool,
+    split: &SplitExpense,
+    shares: &[(String, Money)],
+    transaction: Option<&Transaction>,
+) -> Result<()> {
+    let mut tx = pool.begin().await?;
+    if let Some(transaction) = transaction {
+        insert_transaction(&mut tx, transaction).await?;
+    }
+    sqlx::query(
+        "INSERT INTO split_expenses (id, created_by, paid_by, description, amount, currency, category, date, transaction_id, created_at) VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?, ?)"
+    )
+    .bind(&split.id)
+    .bind(&split.created_by)
+    .bind(&split.paid_by)
+    .bind(&split.description)
+    .bind(split.amount)
+    .bind(&split.currency)
+    .bind(&split.category)
+    .bind(format_db_datetime(split.date))
+    .bind(&split.transaction_id)
+    .bind(format_db_datetime(split.created_at))
+    .execute(&mut tx)
+    .await?;
+    for (user_id, amount) in shares {
+        sqlx::query("INSERT INTO split_shares (split_id, user_id, amount) VALUES (?, ?, ?)")
+            .bind(&split.id)
+            .bind(user_id)
+            .bind(*amount)
+            .execute(&mut tx)
+            .await?;
+    }
+    tx.commit().await?;
+    Ok(())
+}
+
+/// Stores a settlement and, if given, the creator's side of the payment.
+pub async fn create_settlement(pool: &DbPool, settlement: &SplitSettlement, transaction: Option<&Transaction>) -> Result<()> {
+    let mut tx = pool.begin().await?;
+    if let Some(transaction) = transaction {
+        insert_transaction(&mut tx, transaction).await?;
+    }
+    sqlx::query(
+        "INSERT INTO split_settlements (id, from_user_id, to_user_id, amount, currency, date, note, transaction_id, created_by, created_at) VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?, ?)"
+    )
+    .bind(&settlement.id)
+    .bind(&settlement.from_user_id)
+    .bind(&settlement.to_user_id)
+    .bind(settlement.amount)
+    .bind(&settlement.currency)
+    .bind(format_db_datetime(settlement.date))
+    .bind(&settlement.note)
+    .bind(&settlement.transaction_id)
+    .bind(&settlement.created_by)
+    .bind(format_db_datetime(settlement.created_at))
+    .execute(&mut tx)
+    .await?;
+    tx.commit().await?;
+    Ok(())
+}
+
+pub async fn detail(pool: &DbPool, split: SplitExpense) -> Result<SplitExpenseDetail> {
+    let mut details = details(pool, vec![split]).await?;
+    Ok(details.remove(0))
+}
+
+/// The splits with their shares, read in one query.
+pub async fn details(pool: &DbPool, splits: Vec<SplitExpense>) -> Result<Vec<SplitExpenseDetail>> {
+    if splits.is_empty() {
+        return Ok(Vec::new());
+    }
+    let placeholders = vec!["?"; splits.len()].join(", ");
+    let sql = format!(
+        "SELECT s.split_id, s.user_id, u.name, u.email, s.amount FROM split_shares s JOIN users u ON u.id = s.user_id WHERE s.split_id IN ({}) ORDER BY u.name",
+        placeholders
+    );
+    let mut query = sqlx::query(&sql);
+    for split in &splits {
+        query = query.bind(&split.id);
+    }
+    let mut shares: HashMap<String, Vec<SplitShare>> = HashMap::new();
+    for row in query.fetch_all(pool).await? {
+        shares.entry(row.get("split_id")).or_default().push(SplitShare {
+            user_id: row.get("user_id"),
+            name: row.get("name"),
+            email: row.get("email"),
+            amount: row.get("amount"),
+        });
+    }
+
+    Ok(splits
+        .into_iter()
+        .map(|split| {
+            let shares = shares.remove(&split.id).unwrap_or_default();
+            SplitExpenseDetail { split, shares }
+        })
+        .collect())
+}
+
+/// What each friend owes the user (positive) or is owed by them (negative), by currency.
+/// Only friends the user isn't settled up with are listed; `friend_id` narrows it to one.
+pub async fn balances(pool: &DbPool, user_id: &str, friend_id: Option<&str>) -> Result<Vec<FriendBalance>> {
+    let mut totals: HashMap<(String, String), Money> = HashMap::new();
+
+    // Every share of someone other than the payer is owed to the payer.
+    let shares = sqlx::query(
+        "SELECT e.paid_by, e.currency, s.user_id, s.amount FROM split_shares s JOIN split_expenses e ON e.id = s.split_id WHERE s.user_id != e.paid_by AND (e.paid_by = ? OR s.user_id = ?)"
+    )
+    .bind(user_id)
+    .bind(user_id)
+    .fetch_all(pool)
+    .await?;
+    for row in shares {
+        let (payer, debtor): (String, String) = (row.get("paid_by"), row.get("user_id"));
+        let amount: Money = row.get("amount");
+        let (friend, owed) = if payer == user_id { (debtor, amount) } else { (payer, -amount) };
+        *totals.entry((friend, row.get("currency"))).or_default() += owed;
+    }
+
+    // A settlement pays down what its sender owes its recipient.
+    let settlements = sqlx::query(
+        "SELECT from_user_id, to_user_id, amount, currency FROM split_settlements WHERE from_user_id = ? OR to_user_id = ?"
+    )
+    .bind(user_id)
+    .bind(user_id)
+    .fetch_all(pool)
+    .await?;
+    for row in settlements {
+        let (from, to): (String, String) = (row.get("from_user_id"), row.get("to_user_id"));
+        let amount: Money = row.get("amount");
+        let (friend, owed) = if to == user_id { (from, -amount) } else { (to, amount) };
+        *totals.entry((friend, row.get("currency"))).or_default() += owed;
+    }
+
+    let mut balances = Vec::new();
+    for ((friend, code), balance) in totals {
+        if balance.is_zero() || friend_id.is_some_and(|id| id != friend) {
+            continue;
+        }
+        let user = sqlx::query("SELECT name, email FROM users WHERE id = ?")
+            .bind(&friend)
+            .fetch_one(pool)
+            .await?;
+        balances.push(FriendBalance {
+            user_id: friend,
+            name: user.get("name"),
+            email: user.get("email"),
+            currency: code,
+            balance,
+        });
+    }
+    balances.sort_by(|a, b| a.name.cmp(&b.name).then_with(|| a.currency.cmp(&b.currency)));
+    Ok(balances)
+}
+
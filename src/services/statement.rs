@@ -0,0 +1,323 @@
+use anyhow::Result;
+use chrono::{Duration, NaiveDate, Utc};
+use pdf_writer::{Content, Finish, Name, Pdf, Rect, Ref, Str};
+use sqlx::Row;
+
+use crate::models::Transaction;
+use crate::services::database::DbPool;
+use crate::services::export::account_delta;
+use crate::services::households;
+use crate::utils::money::Money;
+
+const PAGE_WIDTH: f32 = 595.0;
+const PAGE_HEIGHT: f32 = 842.0;
+const MARGIN: f32 = 50.0;
+const ROW_HEIGHT: f32 = 14.0;
+const FONT_SIZE: f32 = 9.0;
+
+const REGULAR: Name = Name(b"F1");
+const BOLD: Name = Name(b"F2");
+
+/// Right edges of the amount and balance columns.
+const AMOUNT_RIGHT: f32 = 470.0;
+const BALANCE_RIGHT: f32 = PAGE_WIDTH - MARGIN;
+
+/// An account's transactions over a period, with the balances either side of it.
+pub struct Statement {
+    pub account_name: String,
+    pub account_type: String,
+    pub currency: String,
+    pub from: NaiveDate,
+    pub to: NaiveDate,
+    pub opening_balance: Money,
+    pub transactions: Vec<Transaction>,
+}
+
+impl Statement {
+    pub fn money_in(&self) -> Money {
+        self.transactions.iter().map(account_delta).filter(|delta| delta.is_positive()).sum()
+    }
+
+    pub fn money_out(&self) -> Money {
+        -self.transactions.iter().map(account_delta).filter(|delta| delta.is_negative()).sum::<Money>()
+    }
+
+    pub fn closing_balance(&self) -> Money {
+        self.opening_balance + self.transactions.iter().map(account_delta).sum::<Money>()
+    }
+}
+
+/// Loads the statement for `from..=to`. The stored balance is taken as current, so the opening
+/// balance is worked back from it through every transaction dated on or after `from`.
+/// Every household member's transactions on a shared account are included.
+/// Returns `None` if the user can't see the account.
+pub async fn load(
+    pool: &DbPool,
+    user_id: &str,
+    account_id: &str,
+    from: NaiveDate,
+    to: NaiveDate,
+) -> Result<Option<Statement>> {
+    let Some(account) = sqlx::query(&format!(
+        "SELECT name, account_type, balance, currency FROM accounts WHERE id = ? AND {}",
+        households::visible_accounts_filter()
+    ))
+    .bind(account_id)
+    .bind(user_id)
+    .bind(user_id)
+    .fetch_optional(pool)
+    .await?
+    else {
+        return Ok(None);
+    };
+
+    let start = from.format("%Y-%m-%d 00:00:00").to_string();
+    let end = (to + Duration::days(1)).format("%Y-%m-%d 00:00:00").to_string();
+
+    // Mirrors account_delta: only income adds to the account.
+    let since_start: Money = sqlx::query_scalar(
+        "SELECT COALESCE(SUM(CASE WHEN transaction_type = 'income' THEN amount ELSE -amount END), 0) FROM transactions WHERE account_id = ? AND date >= ?"
+    )
+    .bind(account_id)
+    .bind(&start)
+    .fetch_one(pool)
+    .await?;
+
+    let transactions = sqlx::query_as::<_, Transaction>(
+        "SELECT * FROM transactions WHERE account_id = ? AND date >= ? AND date < ? ORDER BY date ASC, created_at ASC"
+    )
+    .bind(account_id)
+    .bind(&start)
+    .bind(&end)
+    .fetch_all(pool)
+    .await?;
+
+    let balance: Money = account.get("balance");
+    Ok(Some(Statement {
+        account_name: account.get("name"),
+        account_type: account.get("account_type"),
+        currency: account.get("currency"),
+        from,
+        to,
+        opening_balance: balance - since_start,
+        transactions,
+    }))
+}
+
+/// Encodes text for the standard PDF fonts (WinAnsiEncoding); other characters become `?`.
+fn win_ansi(text: &str) -> Vec<u8> {
+    text.chars()
+        .map(|c| match c {
+            ' '..='~' => c as u8,
+            '\u{a0}'..='\u{ff}' => c as u32 as u8,
+            '€' => 0x80,
+            '‘' => 0x91,
+            '’' => 0x92,
+            '“' => 0x93,
+            '”' => 0x94,
+            '•' => 0x95,
+            '–' => 0x96,
+            '—' => 0x97,
+            _ => b'?',
+        })
+        .collect()
+}
+
+/// `1234.5` as `1,234.50`.
+fn format_amount(amount: Money) -> String {
+    let fixed = format!("{:.2}", amount.abs());
+    let (whole, cents) = fixed.split_once('.').unwrap_or((&fixed, "00"));
+    let mut grouped = String::new();
+    for (i, digit) in whole.chars().enumerate() {
+        if i > 0 && (whole.len() - i) % 3 == 0 {
+            grouped.push(',');
+        }
+        grouped.push(digit);
+    }
+    let sign = if amount.round_dp(2).is_negative() { "-" } else { "" };
+    format!("{}{}.{}", sign, grouped, cents)
+}
+
+/// Width of a formatted amount in Helvetica, whose digits all share one advance width.
+fn amount_width(text: &str, size: f32) -> f32 {
+    let units: f32 = text
+        .chars()
+        .map(|c| match c {
+            ',' | '.' | ' ' => 278.0,
+            '-' => 333.0,
+            _ => 556.0,
+        })
+        .sum();
+    units * size / 1000.0
+}
+
+fn truncate(text: &str, max_chars: usize) -> String {
+    if text.chars().count() <= max_chars {
+        text.to_string()
+    } else {
+        format!("{}...", text.chars().take(max_chars.saturating_sub(3)).collect::<String>())
+    }
+}
+
+fn show(content: &mut Content, font: Name, size: f32, x: f32, y: f32, text: &str) {
+    content.begin_text();
+    content.set_font(font, size);
+    content.next_line(x, y);
+    content.show(Str(&win_ansi(text)));
+    content.end_text();
+}
+
+fn show_amount(content: &mut Content, font: Name, size: f32, right: f32, y: f32, amount: Money) {
+    let text = format_amount(amount);
+    show(content, font, size, right - amount_width(&text, size), y, &text);
+}
+
+fn rule(content: &mut Content, y: f32) {
+    content.set_line_width(0.5);
+    content.move_to(MARGIN, y);
+    content.line_to(PAGE_WIDTH - MARGIN, y);
+    content.stroke();
+}
+
+fn table_header(content: &mut Content, y: f32) {
+    show(content, BOLD, FONT_SIZE, MARGIN, y, "Date");
+    show(content, BOLD, FONT_SIZE, 115.0, y, "Description");
+    show(content, BOLD, FONT_SIZE, 305.0, y, "Category");
+    show(content, BOLD, FONT_SIZE, AMOUNT_RIGHT - amount_width("0000000", FONT_SIZE), y, "Amount");
+    show(content, BOLD, FONT_SIZE, BALANCE_RIGHT - amount_width("0000000", FONT_SIZE), y, "Balance");
+    rule(content, y - 4.0);
+}
+
+/// Lays the statement out on A4 pages using the built-in Helvetica fonts.
+pub fn render_pdf(statement: &Statement) -> Vec<u8> {
+    let mut pages: Vec<Content> = Vec::new();
+    let mut content = Content::new();
+
+    let mut y = PAGE_HEIGHT - MARGIN - 10.0;
+    show(&mut content, BOLD, 18.0, MARGIN, y, "Account Statement");
+    y -= 24.0;
+    show(&mut content, BOLD, 12.0, MARGIN, y, &statement.account_name);
+    y -= 15.0;
+    show(
+        &mut content,
+        REGULAR,
+        10.0,
+        MARGIN,
+        y,
+        &format!("{} account, {}", statement.account_type.replace('_', " "), statement.currency),
+    );
+    y -= 14.0;
+    show(
+        &mut content,
+        REGULAR,
+        10.0,
+        MARGIN,
+        y,
+        &format!("Period: {} to {}", statement.from.format("%Y-%m-%d"), statement.to.format("%Y-%m-%d")),
+    );
+    y -= 14.0;
+    show(
+        &mut content,
+        REGULAR,
+        10.0,
+        MARGIN,
+        y,
+        &format!("Generated: {}", Utc::now().format("%Y-%m-%d %H:%M UTC")),
+    );
+
+    y -= 28.0;
+    let summary = [
+        ("Opening balance", statement.opening_balance),
+        ("Money in", statement.money_in()),
+        ("Money out", -statement.money_out()),
+        ("Closing balance", statement.closing_balance()),
+    ];
+    for (label, amount) in summary {
+        show(&mut content, BOLD, 10.0, MARGIN, y, label);
+        show_amount(&mut content, REGULAR, 10.0, 260.0, y, amount);
+        y -= 15.0;
+    }
+
+    y -= 20.0;
+    table_header(&mut content, y);
+    y -= ROW_HEIGHT + 4.0;
+
+    let mut balance = statement.opening_balance;
+    show(&mut content, REGULAR, FONT_SIZE, MARGIN, y, &statement.from.format("%Y-%m-%d").to_string());
+    show(&mut content, REGULAR, FONT_SIZE, 115.0, y, "Opening balance");
+    show_amount(&mut content, REGULAR, FONT_SIZE, BALANCE_RIGHT, y, balance);
+    y -= ROW_HEIGHT;
+
+    for transaction in &statement.transactions {
+        if y < MARGIN + 20.0 {
+            pages.push(std::mem::replace(&mut content, Content::new()));
+            y = PAGE_HEIGHT - MARGIN - 10.0;
+            table_header(&mut content, y);
+            y -= ROW_HEIGHT + 4.0;
+        }
+
+        let delta = account_delta(transaction);
+        balance += delta;
+        let description = transaction
+            .description
+            .as_deref()
+            .map(|text| text.split_whitespace().collect::<Vec<_>>().join(" "))
+            .unwrap_or_else(|| format!("{:?}", transaction.transaction_type));
+        show(&mut content, REGULAR, FONT_SIZE, MARGIN, y, &transaction.date.format("%Y-%m-%d").to_string());
+        show(&mut content, REGULAR, FONT_SIZE, 115.0, y, &truncate(&description, 40));
+        show(&mut content, REGULAR, FONT_SIZE, 305.0, y, &truncate(transaction.category.as_deref().unwrap_or(""), 18));
+        show_amount(&mut content, REGULAR, FONT_SIZE, AMOUNT_RIGHT, y, delta);
+        show_amount(&mut content, REGULAR, FONT_SIZE, BALANCE_RIGHT, y, balance);
+        y -= ROW_HEIGHT;
+    }
+
+    if statement.transactions.is_empty() {
+        show(&mut content, REGULAR, FONT_SIZE, 115.0, y, "No transactions in this period.");
+        y -= ROW_HEIGHT;
+    }
+    rule(&mut content, y + ROW_HEIGHT - 4.0);
+    show(&mut content, BOLD, FONT_SIZE, 115.0, y, "Closing balance");
+    show_amount(&mut content, BOLD, FONT_SIZE, BALANCE_RIGHT, y, balance);
+    pages.push(content);
+
+    let catalog_id = Ref::new(1);
+    let page_tree_id = Ref::new(2);
+    let regular_id = Ref::new(3);
+    let bold_id = Ref::new(4);
+    let page_ids: Vec<Ref> = (0..pages.len() as i32).map(|i| Ref::new(5 + i * 2)).collect();
+
+    let mut pdf = Pdf::new();
+    pdf.catalog(catalog_id).pages(page_tree_id);
+    pdf.pages(page_tree_id).kids(page_ids.iter().copied()).count(pages.len() as i32);
+    pdf.type1_font(regular_id)
+        .base_font(Name(b"Helvetica"))
+        .encoding_predefined(Name(b"WinAnsiEncoding"));
+    pdf.type1_font(bold_id)
+        .base_font(Name(b"Helvetica-Bold"))
+        .encoding_predefined(Name(b"WinAnsiEncoding"));
+
+    let page_count = pages.len();
+    for (index, (mut content, page_id)) in pages.into_iter().zip(page_ids).enumerate() {
+        show(
+            &mut content,
+            REGULAR,
+            8.0,
+            MARGIN,
+            MARGIN - 20.0,
+            &format!("{} - page {} of {}", statement.account_name, index + 1, page_count),
+        );
+
+        let content_id = Ref::new(page_id.get() + 1);
+        let mut page = pdf.page(page_id);
+        page.media_box(Rect::new(0.0, 0.0, PAGE_WIDTH, PAGE_HEIGHT));
+        page.parent(page_tree_id);
+        page.contents(content_id);
+        let mut resources = page.resources();
+        resources.fonts().pair(REGULAR, regular_id).pair(BOLD, bold_id);
+        resources.finish();
+        page.finish();
+        pdf.stream(content_id, &content.finish());
+    }
+
+    pdf.finish()
+}
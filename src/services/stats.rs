@@ -0,0 +1,151 @@
+use anyhow::Result;
+use chrono::{DateTime, Duration, NaiveDate, Utc};
+use sqlx::query::Query;
+use sqlx::sqlite::SqliteArguments;
+use sqlx::{Row, Sqlite};
+
+use crate::models::{BusiestCategory, EntityCounts, Stats, TransactionAggregate};
+use crate::services::currency;
+use crate::services::database::DbPool;
+use crate::services::reports::{excluded_account_count, report_transaction, INCLUDED_ACCOUNTS_FILTER};
+use crate::services::user_time::UserTime;
+use crate::utils::datetime::format_db_datetime;
+
+/// Binds the parameters of the transaction scope built in [`stats`].
+fn bind_scope<'q>(
+    query: Query<'q, Sqlite, SqliteArguments<'q>>,
+    user_id: &'q str,
+    from: &'q Option<String>,
+    to: &'q Option<String>,
+) -> Query<'q, Sqlite, SqliteArguments<'q>> {
+    query
+        .bind(user_id)
+        .bind(from)
+        .bind(from)
+        .bind(to)
+        .bind(to)
+        .bind(user_id)
+}
+
+/// Account-wide statistics, computed with aggregate queries. Transaction figures cover
+/// `from..=to` in the user's timezone when given and leave out accounts flagged
+/// `exclude_from_totals`.
+pub async fn stats(
+    pool: &DbPool,
+    user_id: &str,
+    from: Option<NaiveDate>,
+    to: Option<NaiveDate>,
+    time: &UserTime,
+) -> Result<Stats> {
+    let from = from.map(|d| format_db_datetime(time.start_of_day(d)));
+    let to = to.map(|d| format_db_datetime(time.start_of_day(d + Duration::days(1))));
+    let scope = format!(
+        "user_id = ? AND (? IS NULL OR date >= ?) AND (? IS NULL OR date < ?) AND {}",
+        INCLUDED_ACCOUNTS_FILTER
+    );
+
+    let counts = sqlx::query(
+        r#"
+        SELECT
+            (SELECT COUNT(*) FROM accounts WHERE user_id = ?1) AS accounts,
+            (SELECT COUNT(*) FROM budgets WHERE user_id = ?1) AS budgets,
+            (SELECT COUNT(*) FROM savings_goals WHERE user_id = ?1) AS savings_goals,
+            (SELECT COUNT(*) FROM recurring_transactions WHERE user_id = ?1 AND is_active = TRUE) AS active_recurring,
+            (SELECT COUNT(*) FROM liabilities WHERE user_id = ?1 AND is_paid = FALSE) AS unpaid_liabilities,
+            (SELECT COUNT(*) FROM loans WHERE user_id = ?1 AND is_returned = FALSE) AS outstanding_loans
+        "#,
+    )
+    .bind(user_id)
+    .fetch_one(pool)
+    .await?;
+
+    let span_sql = format!(
+        "SELECT COUNT(*) AS transactions, MIN(date) AS first_date, MAX(date) AS last_date FROM transactions WHERE {}",
+        scope
+    );
+    let span = bind_scope(sqlx::query(&span_sql), user_id, &from, &to)
+        .fetch_one(pool)
+    .await?;
+
+    let counts = EntityCounts {
+        transactions: span.get("transactions"),
+        accounts: counts.get("accounts"),
+        budgets: counts.get("budgets"),
+        savings_goals: counts.get("savings_goals"),
+        active_recurring: counts.get("active_recurring"),
+        unpaid_liabilities: counts.get("unpaid_liabilities"),
+        outstanding_loans: counts.get("outstanding_loans"),
+    };
+
+    let by_currency_sql = format!(
+        r#"
+        SELECT currency, transaction_type, COUNT(*) AS count, SUM(amount) AS total, AVG(amount) AS average,
+               MIN(amount) AS min, MAX(amount) AS max
+        FROM transactions
+        WHERE {}
+        GROUP BY currency, transaction_type
+        ORDER BY currency, transaction_type
+        "#,
+        scope
+    );
+    let by_currency = bind_scope(sqlx::query(&by_currency_sql), user_id, &from, &to)
+        .fetch_all(pool)
+        .await?
+        .into_iter()
+        .map(|row| TransactionAggregate {
+            currency: row.get("currency"),
+            transaction_type: row.get("transaction_type"),
+            count: row.get("count"),
+            total: row.get("total"),
+            average: currency::round(row.get("average"), &row.get::<String, _>("currency")),
+            min: row.get("min"),
+            max: row.get("max"),
+        })
+        .collect();
+
+    let extreme = |order: &str| {
+        format!(
+            "SELECT id, account_id, transaction_type, amount, currency, category, description, date FROM transactions WHERE {} AND transaction_type IN ('income', 'expense') ORDER BY amount {}, date DESC LIMIT 1",
+            scope, order
+        )
+    };
+    let (largest_sql, smallest_sql) = (extreme("DESC"), extreme("ASC"));
+    let largest_transaction = bind_scope(sqlx::query(&largest_sql), user_id, &from, &to)
+        .fetch_optional(pool)
+        .await?
+        .map(|row| report_transaction(row, time));
+    let smallest_transaction = bind_scope(sqlx::query(&smallest_sql), user_id, &from, &to)
+        .fetch_optional(pool)
+        .await?
+        .map(|row| report_transaction(row, time));
+
+    let busiest_sql = format!(
+        r#"
+        SELECT category, COUNT(*) AS transaction_count
+        FROM transactions
+        WHERE {} AND transaction_type = 'expense' AND category IS NOT NULL AND category != ''
+        GROUP BY category
+        ORDER BY transaction_count DESC, category
+        LIMIT 1
+        "#,
+        scope
+    );
+    let busiest_category = bind_scope(sqlx::query(&busiest_sql), user_id, &from, &to)
+        .fetch_optional(pool)
+        .await?
+        .map(|row| BusiestCategory {
+            category: row.get("category"),
+            transaction_count: row.get("transaction_count"),
+        });
+
+    Ok(Stats {
+        counts,
+        by_currency,
+        largest_transaction,
+        smallest_transaction,
+        busiest_category,
+        first_transaction_date: span.get::<Option<DateTime<Utc>>, _>("first_date").map(|date| time.localize(date)),
+        last_transaction_date: span.get::<Option<DateTime<Utc>>, _>("last_date").map(|date| time.localize(date)),
+        excluded_accounts: excluded_account_count(pool, user_id).await?,
+    })
+}
@@ -0,0 +1,123 @@
+use std::collections::HashMap;
+use std::path::PathBuf;
+use std::sync::{Mutex, OnceLock};
+
+use anyhow::{anyhow, Result};
+
+use crate::config::StorageConfig;
+
+/// Uploaded files, such as avatars, by key. Keys are relative paths like `avatars/<id>.png`,
+/// made by the server and never taken from a request as is.
+pub struct Storage {
+    backend: Backend,
+}
+
+enum Backend {
+    Local(PathBuf),
+    Memory(Mutex<HashMap<String, Vec<u8>>>),
+}
+
+/// Keys are made of plain path segments, so none can reach outside the storage directory.
+fn check_key(key: &str) -> Result<()> {
+    let valid = !key.is_empty()
+        && key.split('/').all(|segment| {
+            !segment.is_empty()
+                && !segment.starts_with('.')
+                && segment.chars().all(|c| c.is_ascii_alphanumeric() || matches!(c, '-' | '_' | '.'))
+        });
+    if valid {
+        Ok(())
+    } else {
+        Err(anyhow!("invalid storage key '{}'", key))
+    }
+}
+
+impl Storage {
+    /// Files kept under `config.dir`.
+    pub fn local(config: &StorageConfig) -> Self {
+        Self {
+            backend: Backend::Local(config.dir.clone()),
+        }
+    }
+
+    /// Files kept in memory until the process exits.
+    pub fn in_memory() -> Self {
+        Self {
+            backend: Backend::Memory(Mutex::new(HashMap::new())),
+        }
+    }
+
+    pub fn backend_name(&self) -> &'static str {
+        match self.backend {
+            Backend::Local(_) => "local",
+            Backend::Memory(_) => "memory",
+        }
+    }
+
+    /// Stores `bytes` under `key`, replacing what was there. Readers never see a half-written
+    /// file: it is written aside and renamed into place.
+    pub async fn put(&self, key: &str, bytes: Vec<u8>) -> Result<()> {
+        check_key(key)?;
+        match &self.backend {
+            Backend::Local(dir) => {
+                let path = dir.join(key);
+                if let Some(parent) = path.parent() {
+                    tokio::fs::create_dir_all(parent).await?;
+                }
+                let partial = path.with_extension("partial");
+                tokio::fs::write(&partial, bytes).await?;
+                tokio::fs::rename(&partial, &path).await?;
+            }
+            Backend::Memory(files) => {
+                lock(files).insert(key.to_string(), bytes);
+            }
+        }
+        Ok(())
+    }
+
+    pub async fn get(&self, key: &str) -> Result<Option<Vec<u8>>> {
+        check_key(key)?;
+        match &self.backend {
+            Backend::Local(dir) => match tokio::fs::read(dir.join(key)).await {
+                Ok(bytes) => Ok(Some(bytes)),
+                Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(None),
+                Err(e) => Err(e.into()),
+            },
+            Backend::Memory(files) => Ok(lock(files).get(key).cloned()),
+        }
+    }
+
+    /// Removes `key`; one that isn't there is already gone.
+    pub async fn delete(&self, key: &str) -> Result<()> {
+        check_key(key)?;
+        match &self.backend {
+            Backend::Local(dir) => match tokio::fs::remove_file(dir.join(key)).await {
+                Err(e) if e.kind() != std::io::ErrorKind::NotFound => Err(e.into()),
+                _ => Ok(()),
+            },
+            Backend::Memory(files) => {
+                lock(files).remove(key);
+                Ok(())
+            }
+        }
+    }
+}
+
+fn lock(files: &Mutex<HashMap<String, Vec<u8>>>) -> std::sync::MutexGuard<'_, HashMap<String, Vec<u8>>> {
+    files.lock().unwrap_or_else(|e| e.into_inner())
+}
+
+static STORAGE: OnceLock<Storage> = OnceLock::new();
+
+/// Sets up the storage [`get`] returns. Call once at startup, before anything reads it.
+pub fn init(config: &StorageConfig) -> &'static Storage {
+    if STORAGE.set(Storage::local(config)).is_err() {
+        tracing::warn!("Storage was already set up; keeping the first one");
+    }
+    get()
+}
+
+/// The server's storage, or one in memory when [`init`] hasn't been called, as in tests.
+pub fn get() -> &'static Storage {
+    STORAGE.get_or_init(Storage::in_memory)
+}
@@ -0,0 +1,216 @@
+use std::collections::HashMap;
+
+use anyhow::{anyhow, Result};
+use chrono::{DateTime, Datelike, Duration, Months, Utc};
+use rust_decimal::Decimal;
+use sha2::{Digest, Sha256};
+use sqlx::Row;
+
+use crate::models::{CreateRecurringTransactionRequest, DetectedSubscription, RecurringTransaction};
+use crate::services::category_model::tokenize;
+use crate::services::database::DbPool;
+use crate::services::{currency, recurring};
+use crate::utils::datetime::format_db_datetime;
+use crate::utils::money::Money;
+
+/// How far back charges are looked at; long enough to see a yearly charge repeat.
+const HISTORY_DAYS: i64 = 3 * 365;
+
+/// Share of the gaps between charges that must fit the cadence.
+const REGULAR_SHARE: f64 = 0.75;
+
+struct Cadence {
+    frequency: &'static str,
+    min_days: f64,
+    max_days: f64,
+    min_charges: usize,
+}
+
+impl Cadence {
+    fn fits(&self, days: f64) -> bool {
+        (self.min_days..=self.max_days).contains(&days)
+    }
+
+    fn after(&self, date: DateTime<Utc>) -> DateTime<Utc> {
+        match self.frequency {
+            "weekly" => date + Duration::days(7),
+            "yearly" => date + Months::new(12),
+            _ => date + Months::new(1),
+        }
+    }
+}
+
+const CADENCES: &[Cadence] = &[
+    Cadence { frequency: "weekly", min_days: 5.0, max_days: 9.0, min_charges: 3 },
+    Cadence { frequency: "monthly", min_days: 26.0, max_days: 35.0, min_charges: 3 },
+    Cadence { frequency: "yearly", min_days: 350.0, max_days: 380.0, min_charges: 2 },
+];
+
+struct Charge {
+    amount: Money,
+    category: Option<String>,
+    description: String,
+    date: DateTime<Utc>,
+}
+
+/// The merchant a description names: its keywords, without reference numbers.
+fn merchant_key(description: &str) -> String {
+    tokenize(description).join(" ")
+}
+
+fn subscription_id(account_id: &str, merchant: &str, code: &str) -> String {
+    let digest = Sha256::digest(format!("{}|{}|{}", account_id, merchant, code).as_bytes());
+    hex::encode(&digest[..8])
+}
+
+fn median(mut values: Vec<f64>) -> f64 {
+    values.sort_by(f64::total_cmp);
+    let middle = values.len() / 2;
+    if values.len().is_multiple_of(2) {
+        (values[middle - 1] + values[middle]) / 2.0
+    } else {
+        values[middle]
+    }
+}
+
+/// Matches charges (oldest first) to a cadence when their gaps are regular and their amounts
+/// stay within a factor of two of the typical one.
+fn cadence_of(charges: &[Charge]) -> Option<&'static Cadence> {
+    let gaps: Vec<f64> = charges
+        .windows(2)
+        .map(|pair| (pair[1].date - pair[0].date).num_hours() as f64 / 24.0)
+        .collect();
+    if gaps.is_empty() {
+        return None;
+    }
+    let cadence = CADENCES.iter().find(|cadence| cadence.fits(median(gaps.clone())))?;
+    let regular = gaps.iter().filter(|&&days| cadence.fits(days)).count();
+    if charges.len() < cadence.min_charges || (regular as f64) < gaps.len() as f64 * REGULAR_SHARE {
+        return None;
+    }
+
+    let typical = median(charges.iter().map(|charge| charge.amount.to_f64()).collect());
+    let steady = charges
+        .iter()
+        .all(|charge| (typical / 2.0..=typical * 2.0).contains(&charge.amount.to_f64()));
+    steady.then_some(cadence)
+}
+
+/// Finds the user's subscriptions among expenses from the last few years, active ones first.
+pub async fn detect(pool: &DbPool, user_id: &str) -> Result<Vec<DetectedSubscription>> {
+    let now = Utc::now();
+    let rows = sqlx::query(
+        "SELECT account_id, amount, currency, category, description, date FROM transactions WHERE user_id = ? AND transaction_type = 'expense' AND description IS NOT NULL AND date >= ? ORDER BY date"
+    )
+    .bind(user_id)
+    .bind(format_db_datetime(now - Duration::days(HISTORY_DAYS)))
+    .fetch_all(pool)
+    .await?;
+
+    let mut groups: HashMap<(String, String, String), Vec<Charge>> = HashMap::new();
+    for row in rows {
+        let description: String = row.get("description");
+        let merchant = merchant_key(&description);
+        if merchant.is_empty() {
+            continue;
+        }
+        groups
+            .entry((row.get("account_id"), merchant, row.get("currency")))
+            .or_default()
+            .push(Charge {
+                amount: row.get::<Money, _>("amount").abs(),
+                category: row.get("category"),
+                description,
+                date: row.get("date"),
+            });
+    }
+
+    let tracked = tracked_schedules(pool, user_id).await?;
+    let mut subscriptions = Vec::new();
+    for ((account_id, merchant, code), charges) in groups {
+        let Some(cadence) = cadence_of(&charges) else {
+            continue;
+        };
+        let (first, last) = (&charges[0], &charges[charges.len() - 1]);
+        let total: Money = charges.iter().map(|charge| charge.amount).sum();
+        let drift = last.amount - first.amount;
+        let drift_percent = if first.amount.is_positive() { drift.ratio(first.amount) * 100.0 } else { 0.0 };
+        let next_expected_date = cadence.after(last.date);
+
+        subscriptions.push(DetectedSubscription {
+            id: subscription_id(&account_id, &merchant, &code),
+            merchant: last.description.clone(),
+            recurring_transaction_id: tracked.get(&(account_id.clone(), merchant, code.clone())).cloned(),
+            account_id,
+            category: last.category.clone(),
+            frequency: cadence.frequency,
+            charge_count: charges.len(),
+            first_amount: first.amount,
+            last_amount: last.amount,
+            average_amount: currency::round(total / Decimal::from(charges.len() as u64), &code),
+            amount_drift: drift,
+            drift_percent: (drift_percent * 100.0).round() / 100.0,
+            first_charge_date: first.date,
+            last_charge_date: last.date,
+            is_active: cadence.after(next_expected_date) > now,
+            next_expected_date,
+            currency: code,
+        });
+    }
+
+    subscriptions.sort_by_key(|subscription| (!subscription.is_active, subscription.next_expected_date));
+    Ok(subscriptions)
+}
+
+/// Active recurring expenses keyed like detected subscriptions.
+async fn tracked_schedules(pool: &DbPool, user_id: &str) -> Result<HashMap<(String, String, String), String>> {
+    let rows = sqlx::query(
+        "SELECT id, account_id, currency, description FROM recurring_transactions WHERE user_id = ? AND is_active = TRUE AND transaction_type = 'expense' AND description IS NOT NULL"
+    )
+    .bind(user_id)
+    .fetch_all(pool)
+    .await?;
+
+    Ok(rows
+        .into_iter()
+        .map(|row| {
+            let key = (row.get("account_id"), merchant_key(&row.get::<String, _>("description")), row.get("currency"));
+            (key, row.get("id"))
+        })
+        .collect())
+}
+
+/// Starts tracking a detected subscription as a recurring expense at its latest amount, due
+/// from the next expected charge.
+pub async fn track(pool: &DbPool, user_id: &str, subscription: &DetectedSubscription) -> Result<RecurringTransaction> {
+    let day_of_month = match subscription.frequency {
+        "weekly" => None,
+        _ => Some(subscription.last_charge_date.day()),
+    };
+    let rt = RecurringTransaction::new(
+        CreateRecurringTransactionRequest {
+            id: None,
+            account_id: subscription.account_id.clone(),
+            transaction_type: "expense".to_string(),
+            amount: subscription.last_amount,
+            currency: Some(subscription.currency.clone()),
+            category: subscription.category.clone(),
+            description: Some(subscription.merchant.clone()),
+            frequency: Some(subscription.frequency.to_string()),
+            interval: Some(1),
+            day_of_month,
+            weekday: None,
+            start_date: subscription.next_expected_date,
+            end_date: None,
+            next_due_date: None,
+            is_active: Some(true),
+            savings_goal_id: None,
+        },
+        user_id.to_string(),
+    )
+    .map_err(|e| anyhow!(e.0))?;
+
+    let mut conn = pool.acquire().await?;
+    recurring::insert_recurring(&mut conn, &rt).await?;
+    Ok(rt)
+}
@@ -0,0 +1,378 @@
+use anyhow::Result;
+use chrono::{DateTime, Duration as ChronoDuration, Utc};
+use hyper::body::Body;
+use serde_json::json;
+use sqlx::{FromRow, Row};
+use uuid::Uuid;
+
+use crate::models::{
+    Account, Budget, RecurringTransaction, RegisterSyncDeviceRequest, SavingsGoal, SyncChanges, SyncDevice, SyncDeviceStatus,
+    SyncTombstone, Transaction,
+};
+use crate::services::budget_progress;
+use crate::services::database::DbPool;
+use crate::services::households::{self, SHARED_ACCOUNT_IDS};
+use crate::services::preferences;
+use crate::utils::datetime::format_db_datetime;
+use crate::utils::pagination::{PageQuery, Paginated};
+use crate::utils::streaming::ChunkedBody;
+
+/// Change log entries and tombstones in `deleted_records` are kept this long. Clients that last
+/// pulled before then must download everything again.
+pub const CHANGE_RETENTION_DAYS: i64 = 90;
+
+/// How far each pull's `serverTime` reaches back, so writes still being committed while a
+/// pull runs are picked up by the next one.
+const SYNC_CURSOR_OVERLAP_SECS: i64 = 60;
+
+/// Transactions read per query while a pull streams them.
+const TRANSACTION_PAGE_SIZE: i64 = 500;
+
+/// Whether a pull from `since` can still be answered from the change log.
+pub fn within_retention(since: DateTime<Utc>) -> bool {
+    since >= Utc::now() - ChronoDuration::days(CHANGE_RETENTION_DAYS)
+}
+
+/// Rows of `entity` changed since the cursor (`?` twice; `NULL` for all rows), for `WHERE`
+/// clauses on its table.
+fn changed_since(entity: &str) -> String {
+    format!(
+        "(? IS NULL OR id IN (SELECT entity_id FROM change_log WHERE entity_type = '{}' AND changed_at >= ?))",
+        entity
+    )
+}
+
+/// Everything the user can see that changed at or after `since`, or everything when `since`
+/// is `None`, except transactions, which [`pull_body`] streams. Accounts include those shared
+/// through households.
+pub async fn changes_since(pool: &DbPool, user_id: &str, since: Option<DateTime<Utc>>) -> Result<SyncChanges> {
+    let server_time = Utc::now() - ChronoDuration::seconds(SYNC_CURSOR_OVERLAP_SECS);
+    let since = since.map(format_db_datetime);
+
+    let accounts = sqlx::query_as::<_, Account>(&format!(
+        "SELECT * FROM accounts WHERE {} AND {} ORDER BY created_at",
+        households::visible_accounts_filter(),
+        changed_since("account")
+    ))
+    .bind(user_id)
+    .bind(user_id)
+    .bind(&since)
+    .bind(&since)
+    .fetch_all(pool)
+    .await?;
+
+    let budgets = sqlx::query_as::<_, Budget>(&format!(
+        "SELECT * FROM budgets WHERE user_id = ? AND {} ORDER BY created_at",
+        changed_since("budget")
+    ))
+    .bind(user_id)
+    .bind(&since)
+    .bind(&since)
+    .fetch_all(pool)
+    .await?;
+    let linked = budget_progress::linked_categories_by_budget(pool, user_id).await?;
+    let budgets = budgets
+        .into_iter()
+        .map(|budget| {
+            let categories = budget.target_categories(linked.get(&budget.id).map(Vec::as_slice).unwrap_or_default());
+            let mut value = json!(budget);
+            value["categories"] = json!(categories);
+            value
+        })
+        .collect();
+
+    let savings_goals = sqlx::query_as::<_, SavingsGoal>(&format!(
+        "SELECT * FROM savings_goals WHERE user_id = ? AND {} ORDER BY created_at",
+        changed_since("savings_goal")
+    ))
+    .bind(user_id)
+    .bind(&since)
+    .bind(&since)
+    .fetch_all(pool)
+    .await?;
+
+    let recurring_transactions = sqlx::query_as::<_, RecurringTransaction>(&format!(
+        "SELECT * FROM recurring_transactions WHERE user_id = ? AND {} ORDER BY created_at",
+        changed_since("recurring_transaction")
+    ))
+    .bind(user_id)
+    .bind(&since)
+    .bind(&since)
+    .fetch_all(pool)
+    .await?;
+
+    let preferences = preferences::changed_since(pool, user_id, since.as_deref()).await?;
+
+    // A first pull has nothing to delete
+    let deleted = match &since {
+        Some(since) => {
+            sqlx::query_as::<_, SyncTombstone>(&format!(
+                "SELECT entity_type, entity_id AS id, deleted_at FROM deleted_records WHERE deleted_at >= ? AND (user_id = ? OR account_id IN ({})) ORDER BY deleted_at",
+                SHARED_ACCOUNT_IDS
+            ))
+            .bind(since)
+            .bind(user_id)
+            .bind(user_id)
+            .fetch_all(pool)
+            .await?
+        }
+        None => Vec::new(),
+    };
+
+    Ok(SyncChanges {
+        server_time,
+        accounts,
+        budgets,
+        savings_goals,
+        recurring_transactions,
+        preferences,
+        deleted,
+    })
+}
+
+/// A pull's response: `changes` in the usual envelope, with the transactions changed since
+/// `since` streamed in after them. Those are read a page at a time, keyset-paginated on
+/// `(date, id)`, so a years-long history is never held in memory. Transactions on accounts
+/// shared through households are included.
+pub fn pull_body(pool: DbPool, user_id: String, since: Option<DateTime<Utc>>, changes: SyncChanges) -> Result<Body> {
+    // `data` is left open for the transactions to follow
+    let mut data = serde_json::to_vec(&changes)?;
+    data.pop();
+
+    let (mut writer, body) = ChunkedBody::new();
+    writer.push(br#"{"success":true,"data":"#);
+    writer.push(&data);
+    writer.push(br#","transactions":["#);
+
+    tokio::spawn(async move {
+        let since = since.map(format_db_datetime);
+        match stream_transactions(&pool, &user_id, since.as_deref(), &mut writer).await {
+            Ok(count) => {
+                writer.push(b"]}}");
+                if writer.finish().await.is_ok() {
+                    tracing::info!(
+                        "Sync pull for user {}: {} accounts, {} transactions, {} deletions",
+                        user_id,
+                        changes.accounts.len(),
+                        count,
+                        changes.deleted.len()
+                    );
+                }
+            }
+            Err(e) => tracing::error!("Sync pull for user {} failed part way: {:#}", user_id, e),
+        }
+    });
+    Ok(body)
+}
+
+/// Writes the transactions of a pull, comma-separated, and returns how many there were.
+async fn stream_transactions(pool: &DbPool, user_id: &str, since: Option<&str>, writer: &mut ChunkedBody) -> Result<usize> {
+    let sql = format!(
+        "SELECT * FROM transactions WHERE {} AND {} AND (date > ? OR (date = ? AND id > ?)) ORDER BY date, id LIMIT ?",
+        households::visible_rows_filter(),
+        changed_since("transaction")
+    );
+
+    // An empty cursor sorts before every row
+    let (mut last_date, mut last_id) = (String::new(), String::new());
+    let mut count = 0;
+    loop {
+        let rows = sqlx::query(&sql)
+            .bind(user_id)
+            .bind(user_id)
+            .bind(since)
+            .bind(since)
+            .bind(&last_date)
+            .bind(&last_date)
+            .bind(&last_id)
+            .bind(TRANSACTION_PAGE_SIZE)
+            .fetch_all(pool)
+            .await?;
+
+        for row in &rows {
+            if count > 0 {
+                writer.push(b",");
+            }
+            writer.push_json(&Transaction::from_row(row)?)?;
+            count += 1;
+        }
+        writer.flush().await?;
+
+        match rows.last() {
+            Some(last) if rows.len() as i64 == TRANSACTION_PAGE_SIZE => {
+                last_date = last.get("date");
+                last_id = last.get("id");
+            }
+            _ => return Ok(count),
+        }
+    }
+}
+
+async fn find_device(pool: &DbPool, user_id: &str, device_id: &str) -> Result<Option<SyncDevice>> {
+    Ok(sqlx::query_as::<_, SyncDevice>("SELECT * FROM sync_devices WHERE user_id = ? AND device_id = ?")
+        .bind(user_id)
+        .bind(device_id)
+        .fetch_optional(pool)
+        .await?)
+}
+
+/// Adds the user's device `device_id`, or updates the metadata of one already known. Fields
+/// left out keep their stored values.
+pub async fn register_device(
+    pool: &DbPool,
+    user_id: &str,
+    device_id: &str,
+    name: &str,
+    request: &RegisterSyncDeviceRequest,
+) -> Result<SyncDevice> {
+    let now = format_db_datetime(Utc::now());
+    sqlx::query(
+        r#"
+        INSERT INTO sync_devices (id, user_id, device_id, name, platform, app_version, push_device_id, created_at, updated_at)
+        VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?)
+        ON CONFLICT (user_id, device_id) DO UPDATE SET
+            name = excluded.name,
+            platform = COALESCE(excluded.platform, platform),
+            app_version = COALESCE(excluded.app_version, app_version),
+            push_device_id = COALESCE(excluded.push_device_id, push_device_id),
+            updated_at = excluded.updated_at
+        "#,
+    )
+    .bind(Uuid::new_v4().to_string())
+    .bind(user_id)
+    .bind(device_id)
+    .bind(name)
+    .bind(&request.platform)
+    .bind(&request.app_version)
+    .bind(&request.push_device_id)
+    .bind(&now)
+    .bind(&now)
+    .execute(pool)
+    .await?;
+
+    find_device(pool, user_id, device_id)
+        .await?
+        .ok_or_else(|| anyhow::anyhow!("sync device {} vanished after registering", device_id))
+}
+
+/// Records a device's pull and the cursor it was given, registering the device if new.
+pub async fn record_pull(pool: &DbPool, user_id: &str, device_id: &str, cursor: DateTime<Utc>) -> Result<()> {
+    let now = format_db_datetime(Utc::now());
+    sqlx::query(
+        r#"
+        INSERT INTO sync_devices (id, user_id, device_id, name, last_cursor, last_pulled_at, created_at, updated_at)
+        VALUES (?, ?, ?, ?, ?, ?, ?, ?)
+        ON CONFLICT (user_id, device_id) DO UPDATE SET
+            last_cursor = excluded.last_cursor,
+            last_pulled_at = excluded.last_pulled_at,
+            updated_at = excluded.updated_at
+        "#,
+    )
+    .bind(Uuid::new_v4().to_string())
+    .bind(user_id)
+    .bind(device_id)
+    .bind(device_id)
+    .bind(format_db_datetime(cursor))
+    .bind(&now)
+    .bind(&now)
+    .bind(&now)
+    .execute(pool)
+    .await?;
+    Ok(())
+}
+
+/// Records a device's batch of offline changes, registering the device if new.
+pub async fn record_push(pool: &DbPool, user_id: &str, device_id: &str) -> Result<()> {
+    let now = format_db_datetime(Utc::now());
+    sqlx::query(
+        r#"
+        INSERT INTO sync_devices (id, user_id, device_id, name, last_pushed_at, created_at, updated_at)
+        VALUES (?, ?, ?, ?, ?, ?, ?)
+        ON CONFLICT (user_id, device_id) DO UPDATE SET
+            last_pushed_at = excluded.last_pushed_at,
+            updated_at = excluded.updated_at
+        "#,
+    )
+    .bind(Uuid::new_v4().to_string())
+    .bind(user_id)
+    .bind(device_id)
+    .bind(device_id)
+    .bind(&now)
+    .bind(&now)
+    .bind(&now)
+    .execute(pool)
+    .await?;
+    Ok(())
+}
+
+/// How many changes the user can see at or after `since`, deletions and changed preferences
+/// included.
+async fn pending_changes(pool: &DbPool, user_id: &str, since: DateTime<Utc>) -> Result<i64> {
+    let since = format_db_datetime(since);
+    let count = sqlx::query_scalar::<_, i64>(&format!(
+        "SELECT (SELECT COUNT(*) FROM change_log WHERE changed_at >= ? AND (user_id = ? OR account_id IN ({shared}))) + (SELECT COUNT(*) FROM deleted_records WHERE deleted_at >= ? AND (user_id = ? OR account_id IN ({shared}))) + (SELECT COUNT(*) FROM preference_revisions WHERE updated_at >= ? AND user_id = ?)",
+        shared = SHARED_ACCOUNT_IDS
+    ))
+    .bind(&since)
+    .bind(user_id)
+    .bind(user_id)
+    .bind(&since)
+    .bind(user_id)
+    .bind(user_id)
+    .bind(&since)
+    .bind(user_id)
+    .fetch_one(pool)
+    .await?;
+    Ok(count)
+}
+
+/// One page of the user's devices, most recently synced first, with how far each is behind.
+pub async fn device_statuses(pool: &DbPool, user_id: &str, page: &PageQuery) -> Result<Paginated<SyncDeviceStatus>> {
+    let devices = sqlx::query_as::<_, SyncDevice>(
+        "SELECT * FROM sync_devices WHERE user_id = ? ORDER BY COALESCE(last_pulled_at, created_at) DESC, id LIMIT ? OFFSET ?",
+    )
+    .bind(user_id)
+    .bind(page.limit() as i64)
+    .bind(page.offset() as i64)
+    .fetch_all(pool)
+    .await?;
+    let total = sqlx::query_scalar::<_, i64>("SELECT COUNT(*) FROM sync_devices WHERE user_id = ?")
+        .bind(user_id)
+        .fetch_one(pool)
+        .await?;
+
+    let now = Utc::now();
+    let mut statuses = Vec::with_capacity(devices.len());
+    for device in devices {
+        let (lag_seconds, pending_changes, cursor_expired) = match device.last_cursor {
+            Some(cursor) => (
+                Some((now - cursor).num_seconds().max(0)),
+                Some(pending_changes(pool, user_id, cursor).await?),
+                !within_retention(cursor),
+            ),
+            None => (None, None, false),
+        };
+        statuses.push(SyncDeviceStatus {
+            device,
+            lag_seconds,
+            pending_changes,
+            cursor_expired,
+        });
+    }
+    Ok(Paginated::new(statuses, total as usize, page))
+}
+
+/// Drops change log entries and tombstones past the retention window.
+pub async fn prune_change_log(pool: &DbPool) -> Result<u64> {
+    let cutoff = format_db_datetime(Utc::now() - ChronoDuration::days(CHANGE_RETENTION_DAYS));
+    let changes = sqlx::query("DELETE FROM change_log WHERE changed_at < ?")
+        .bind(&cutoff)
+        .execute(pool)
+        .await?;
+    let tombstones = sqlx::query("DELETE FROM deleted_records WHERE deleted_at < ?")
+        .bind(&cutoff)
+        .execute(pool)
+        .await?;
+
+    Ok(changes.rows_affected() + tombstones.rows_affected())
+}
@@ -0,0 +1,75 @@
+use chrono::{DateTime, Months, Utc};
+
+use crate::models::{Compounding, DepositType, TermDeposit, TermDepositSummary};
+use crate::services::currency;
+use crate::utils::money::Money;
+
+pub fn maturity_date(start_date: DateTime<Utc>, tenure_months: u32) -> DateTime<Utc> {
+    start_date + Months::new(tenure_months)
+}
+
+/// Years from `from` to `to`: whole months count as twelfths, so a tenure comes out exact,
+/// and the days left over as 365ths.
+fn years_between(from: DateTime<Utc>, to: DateTime<Utc>) -> f64 {
+    if to <= from {
+        return 0.0;
+    }
+    let mut months = 0;
+    while from + Months::new(months + 1) <= to {
+        months += 1;
+    }
+    let rest_days = (to - (from + Months::new(months))).num_days();
+    months as f64 / 12.0 + rest_days as f64 / 365.0
+}
+
+/// What one unit grows to over `years`. Worked out in floating point as the compound factor
+/// needs a fractional power; it is applied to the exact principal.
+fn growth(annual_rate: f64, compounding: Compounding, years: f64) -> f64 {
+    let rate = annual_rate / 100.0;
+    match compounding.periods_per_year() {
+        None => 1.0 + rate * years,
+        Some(periods) => (1.0 + rate / periods).powf(periods * years),
+    }
+}
+
+/// Amount paid in and value of the deposit on `as_of`, which is held between the start and
+/// maturity dates. Recurring installments fall on the start date and monthly after it.
+pub fn value_on(deposit: &TermDeposit, as_of: DateTime<Utc>) -> (Money, Money) {
+    let as_of = as_of.clamp(deposit.start_date, deposit.maturity_date);
+    let (deposited, value) = match deposit.deposit_type {
+        DepositType::Fixed => {
+            let years = years_between(deposit.start_date, as_of);
+            (deposit.principal, deposit.principal * growth(deposit.annual_rate, deposit.compounding, years))
+        }
+        DepositType::Recurring => {
+            let (mut deposited, mut value) = (Money::ZERO, Money::ZERO);
+            for installment in 0..deposit.tenure_months.max(0) as u32 {
+                let paid_on = deposit.start_date + Months::new(installment);
+                if paid_on > as_of {
+                    break;
+                }
+                let years = years_between(paid_on, as_of);
+                deposited += deposit.principal;
+                value += deposit.principal * growth(deposit.annual_rate, deposit.compounding, years);
+            }
+            (deposited, value)
+        }
+    };
+    (deposited, currency::round(value, &deposit.currency))
+}
+
+/// The deposit with its interest to date and at maturity.
+pub fn summarize(deposit: TermDeposit) -> TermDepositSummary {
+    let now = Utc::now();
+    let (amount_deposited, current_value) = value_on(&deposit, now);
+    let (total_deposited, maturity_value) = value_on(&deposit, deposit.maturity_date);
+    TermDepositSummary {
+        amount_deposited,
+        accrued_interest: current_value - amount_deposited,
+        current_value,
+        maturity_value,
+        total_interest: maturity_value - total_deposited,
+        days_to_maturity: (deposit.maturity_date - now).num_days().max(0),
+        deposit,
+    }
+}
@@ -0,0 +1,197 @@
+use std::collections::HashMap;
+use std::sync::{Mutex, OnceLock};
+use std::time::Duration;
+
+use anyhow::Result;
+use chrono::Utc;
+
+use crate::models::{DailyUsage, DeviceUsage, EndpointUsage};
+use crate::services::database::DbPool;
+use crate::services::scheduler::Scheduler;
+
+/// Usage is reported over, and kept for, this many days.
+pub const USAGE_WINDOW_DAYS: i64 = 30;
+
+const USAGE_PRUNE_INTERVAL: Duration = Duration::from_secs(24 * 60 * 60);
+
+/// How often counted requests are written to `api_usage`.
+const USAGE_FLUSH_INTERVAL: Duration = Duration::from_secs(30);
+
+pub struct UsageRecord {
+    pub user_id: String,
+    pub method: String,
+    pub route: String,
+    pub device: String,
+    pub request_bytes: i64,
+    pub response_bytes: i64,
+}
+
+/// One `api_usage` row: a user's requests to an endpoint from a device on a day.
+#[derive(PartialEq, Eq, Hash)]
+struct UsageKey {
+    user_id: String,
+    day: String,
+    method: String,
+    route: String,
+    device: String,
+}
+
+#[derive(Default)]
+struct UsageCounts {
+    requests: i64,
+    request_bytes: i64,
+    response_bytes: i64,
+}
+
+impl UsageCounts {
+    fn add(&mut self, other: &UsageCounts) {
+        self.requests += other.requests;
+        self.request_bytes += other.request_bytes;
+        self.response_bytes += other.response_bytes;
+    }
+}
+
+/// Requests counted since the last flush. Kept in memory, so each server process counts on
+/// its own and adds its share when it flushes.
+fn pending() -> &'static Mutex<HashMap<UsageKey, UsageCounts>> {
+    static PENDING: OnceLock<Mutex<HashMap<UsageKey, UsageCounts>>> = OnceLock::new();
+    PENDING.get_or_init(|| Mutex::new(HashMap::new()))
+}
+
+/// Adds one request to today's counters for the user/endpoint/device, written out by the
+/// next [`flush`].
+pub fn record(usage: UsageRecord) {
+    let key = UsageKey {
+        user_id: usage.user_id,
+        day: Utc::now().format("%Y-%m-%d").to_string(),
+        method: usage.method,
+        route: usage.route,
+        device: usage.device,
+    };
+    let mut pending = pending().lock().unwrap_or_else(|e| e.into_inner());
+    pending.entry(key).or_default().add(&UsageCounts {
+        requests: 1,
+        request_bytes: usage.request_bytes,
+        response_bytes: usage.response_bytes,
+    });
+}
+
+/// Adds the counted requests to `api_usage` in one transaction and returns how many there
+/// were. If the write fails they are kept for the next flush.
+pub async fn flush(pool: &DbPool) -> Result<u64> {
+    let counts = std::mem::take(&mut *pending().lock().unwrap_or_else(|e| e.into_inner()));
+    if counts.is_empty() {
+        return Ok(0);
+    }
+
+    match write_counts(pool, &counts).await {
+        Ok(()) => Ok(counts.values().map(|c| c.requests as u64).sum()),
+        Err(e) => {
+            let mut pending = pending().lock().unwrap_or_else(|e| e.into_inner());
+            for (key, count) in counts {
+                pending.entry(key).or_default().add(&count);
+            }
+            Err(e)
+        }
+    }
+}
+
+async fn write_counts(pool: &DbPool, counts: &HashMap<UsageKey, UsageCounts>) -> Result<()> {
+    let mut tx = pool.begin().await?;
+    for (key, count) in counts {
+        // Users deleted since their requests were counted are skipped
+        sqlx::query(
+            r#"
+            INSERT INTO api_usage (user_id, day, method, route, device, request_count, request_bytes, response_bytes)
+            SELECT ?, ?, ?, ?, ?, ?, ?, ? WHERE EXISTS (SELECT 1 FROM users WHERE id = ?)
+            ON CONFLICT (user_id, day, method, route, device) DO UPDATE SET
+                request_count = request_count + excluded.request_count,
+                request_bytes = request_bytes + excluded.request_bytes,
+                response_bytes = response_bytes + excluded.response_bytes
+            "#,
+        )
+        .bind(&key.user_id)
+        .bind(&key.day)
+        .bind(&key.method)
+        .bind(&key.route)
+        .bind(&key.device)
+        .bind(count.requests)
+        .bind(count.request_bytes)
+        .bind(count.response_bytes)
+        .bind(&key.user_id)
+        .execute(&mut tx)
+        .await?;
+    }
+    tx.commit().await?;
+    Ok(())
+}
+
+fn window_start() -> String {
+    (Utc::now() - chrono::Duration::days(USAGE_WINDOW_DAYS - 1))
+        .format("%Y-%m-%d")
+        .to_string()
+}
+
+pub async fn usage_by_endpoint(pool: &DbPool, user_id: &str) -> Result<Vec<EndpointUsage>> {
+    let rows = sqlx::query_as::<_, EndpointUsage>(
+        "SELECT method, route, SUM(request_count) AS requests, SUM(request_bytes) AS request_bytes, SUM(response_bytes) AS response_bytes FROM api_usage WHERE user_id = ? AND day >= ? GROUP BY method, route ORDER BY requests DESC"
+    )
+    .bind(user_id)
+    .bind(window_start())
+    .fetch_all(pool)
+    .await?;
+
+    Ok(rows)
+}
+
+pub async fn usage_by_device(pool: &DbPool, user_id: &str) -> Result<Vec<DeviceUsage>> {
+    let rows = sqlx::query_as::<_, DeviceUsage>(
+        "SELECT device, SUM(request_count) AS requests, SUM(request_bytes) AS request_bytes, SUM(response_bytes) AS response_bytes FROM api_usage WHERE user_id = ? AND day >= ? GROUP BY device ORDER BY requests DESC"
+    )
+    .bind(user_id)
+    .bind(window_start())
+    .fetch_all(pool)
+    .await?;
+
+    Ok(rows)
+}
+
+pub async fn usage_by_day(pool: &DbPool, user_id: &str) -> Result<Vec<DailyUsage>> {
+    let rows = sqlx::query_as::<_, DailyUsage>(
+        "SELECT day, SUM(request_count) AS requests, SUM(request_bytes) AS request_bytes, SUM(response_bytes) AS response_bytes FROM api_usage WHERE user_id = ? AND day >= ? GROUP BY day ORDER BY day ASC"
+    )
+    .bind(user_id)
+    .bind(window_start())
+    .fetch_all(pool)
+    .await?;
+
+    Ok(rows)
+}
+
+pub async fn prune_usage(pool: &DbPool) -> Result<u64> {
+    let result = sqlx::query("DELETE FROM api_usage WHERE day < ?")
+        .bind(window_start())
+        .execute(pool)
+        .await?;
+
+    Ok(result.rows_affected())
+}
+
+pub fn schedule_usage_flush_job(scheduler: &mut Scheduler) {
+    scheduler
+        .every("usage_flush", USAGE_FLUSH_INTERVAL, |pool| async move { flush(&pool).await })
+        .retries(0)
+        .keeps_cache();
+}
+
+pub fn schedule_usage_prune_job(scheduler: &mut Scheduler) {
+    scheduler
+        .every("usage_prune", USAGE_PRUNE_INTERVAL, |pool| async move {
+            let removed = prune_usage(&pool).await?;
+            if removed > 0 {
+                tracing::info!("Pruned {} expired API usage rows", removed);
+            }
+            Ok(removed)
+        })
+        .keeps_cache();
+}
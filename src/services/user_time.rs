@@ -0,0 +1,103 @@
+use anyhow::Result;
+use chrono::{DateTime, Datelike, Duration, FixedOffset, LocalResult, NaiveDate, TimeZone, Utc, Weekday};
+use chrono_tz::Tz;
+
+use crate::services::database::DbPool;
+use crate::services::preferences;
+
+/// Timezone of users who haven't chosen one.
+pub const DEFAULT_TIMEZONE: &str = "UTC";
+
+pub const DEFAULT_LOCALE: &str = "en-US";
+
+pub const DEFAULT_FIRST_DAY_OF_WEEK: &str = "monday";
+
+/// An IANA timezone name such as `Asia/Dhaka`.
+pub fn parse_timezone(name: &str) -> Option<Tz> {
+    name.parse().ok()
+}
+
+/// A weekday by name, such as `sunday` or `Sun`.
+pub fn parse_weekday(name: &str) -> Option<Weekday> {
+    name.parse().ok()
+}
+
+/// How preferences store a weekday: its full name in lowercase.
+pub fn weekday_name(day: Weekday) -> &'static str {
+    match day {
+        Weekday::Mon => "monday",
+        Weekday::Tue => "tuesday",
+        Weekday::Wed => "wednesday",
+        Weekday::Thu => "thursday",
+        Weekday::Fri => "friday",
+        Weekday::Sat => "saturday",
+        Weekday::Sun => "sunday",
+    }
+}
+
+/// Whether `tag` looks like a BCP 47 language tag, such as `en`, `bn-BD` or `zh-Hant-TW`.
+pub fn is_locale(tag: &str) -> bool {
+    let mut parts = tag.split('-');
+    let language = parts.next().unwrap_or_default();
+    tag.len() <= 35
+        && (2..=3).contains(&language.len())
+        && language.chars().all(|c| c.is_ascii_alphabetic())
+        && parts.all(|part| (2..=8).contains(&part.len()) && part.chars().all(|c| c.is_ascii_alphanumeric()))
+}
+
+/// A user's calendar: where their days begin and which day starts their week. Reports bucket
+/// by it, so a late-evening expense counts toward the day the user spent it on.
+#[derive(Debug, Clone, Copy)]
+pub struct UserTime {
+    pub timezone: Tz,
+    pub first_day_of_week: Weekday,
+}
+
+impl Default for UserTime {
+    fn default() -> Self {
+        Self {
+            timezone: Tz::UTC,
+            first_day_of_week: Weekday::Mon,
+        }
+    }
+}
+
+impl UserTime {
+    /// The user's date right now.
+    pub fn today(&self) -> NaiveDate {
+        Utc::now().with_timezone(&self.timezone).date_naive()
+    }
+
+    /// When `date` begins for the user. Where a DST change skips midnight, the day begins at the
+    /// first moment that exists.
+    pub fn start_of_day(&self, date: NaiveDate) -> DateTime<Utc> {
+        let midnight = date.and_hms_opt(0, 0, 0).unwrap_or_default();
+        (0..=12)
+            .find_map(|quarter| match self.timezone.from_local_datetime(&(midnight + Duration::minutes(15 * quarter))) {
+                LocalResult::Single(start) | LocalResult::Ambiguous(start, _) => Some(start.with_timezone(&Utc)),
+                LocalResult::None => None,
+            })
+            .unwrap_or_else(|| midnight.and_utc())
+    }
+
+    /// `moment` on the user's clock, with its UTC offset.
+    pub fn localize(&self, moment: DateTime<Utc>) -> DateTime<FixedOffset> {
+        moment.with_timezone(&self.timezone).fixed_offset()
+    }
+
+    /// The first day of the week holding `date`.
+    pub fn week_start(&self, date: NaiveDate) -> NaiveDate {
+        let offset = (date.weekday().num_days_from_monday() + 7 - self.first_day_of_week.num_days_from_monday()) % 7;
+        date - Duration::days(offset as i64)
+    }
+}
+
+/// The calendar `user_id` chose in their preferences.
+pub async fn for_user(pool: &DbPool, user_id: &str) -> Result<UserTime> {
+    let preferences = preferences::load(pool, user_id).await?;
+    let defaults = UserTime::default();
+    Ok(UserTime {
+        timezone: parse_timezone(&preferences.timezone).unwrap_or(defaults.timezone),
+        first_day_of_week: parse_weekday(&preferences.first_day_of_week).unwrap_or(defaults.first_day_of_week),
+    })
+}
@@ -0,0 +1,265 @@
+use std::net::IpAddr;
+use std::time::Duration;
+
+use anyhow::Result;
+use chrono::{Duration as ChronoDuration, Utc};
+use futures_util::{stream, StreamExt, TryStreamExt};
+use hmac::{Hmac, Mac};
+use sha2::Sha256;
+use sqlx::sqlite::SqliteRow;
+use sqlx::Row;
+
+use crate::models::Event;
+use crate::services::database::DbPool;
+use crate::services::scheduler::Scheduler;
+use crate::services::events;
+use crate::utils::datetime::format_db_datetime;
+
+/// Event types a webhook can subscribe to.
+pub const WEBHOOK_EVENT_TYPES: &[&str] = &[
+    "transaction.created",
+    "transaction.updated",
+    "transaction.deleted",
+    "budget.exceeded",
+    "goal.completed",
+];
+
+const WEBHOOK_DELIVERY_INTERVAL: Duration = Duration::from_secs(10);
+
+const WEBHOOK_BATCH_SIZE: i64 = 50;
+
+/// Kept short so a slow receiver can't hold up the rest of the batch for long.
+const WEBHOOK_TIMEOUT: Duration = Duration::from_secs(3);
+
+/// How many deliveries are in flight at once.
+const WEBHOOK_CONCURRENCY: usize = 8;
+
+/// A delivery is given up on after this many failed attempts.
+const MAX_DELIVERY_ATTEMPTS: i64 = 8;
+
+/// Delay before the first retry; it doubles with every further failure.
+const RETRY_BASE_MINUTES: i64 = 1;
+
+/// Finished deliveries are deleted after this many days.
+const DELIVERY_RETENTION_DAYS: i64 = 30;
+
+pub fn is_known_event_type(event_type: &str) -> bool {
+    WEBHOOK_EVENT_TYPES.contains(&event_type)
+}
+
+/// Whether a webhook may be sent to `ip`: not loopback, private, link-local (which covers
+/// the 169.254.169.254 cloud metadata address) or otherwise not routable.
+fn is_public_ip(ip: IpAddr) -> bool {
+    match ip {
+        IpAddr::V4(ip) => !(ip.is_loopback() || ip.is_private() || ip.is_link_local() || ip.is_unspecified() || ip.is_broadcast() || ip.is_multicast()),
+        IpAddr::V6(ip) => match ip.to_ipv4_mapped() {
+            Some(ip) => is_public_ip(IpAddr::V4(ip)),
+            None => !(ip.is_loopback() || ip.is_unique_local() || ip.is_unicast_link_local() || ip.is_unspecified() || ip.is_multicast()),
+        },
+    }
+}
+
+/// Whether `url` is http(s) and its host resolves only to public addresses. Checked when a
+/// webhook is saved and again before every delivery.
+pub async fn is_public_url(url: &str) -> bool {
+    let Ok(url) = reqwest::Url::parse(url) else {
+        return false;
+    };
+    if !matches!(url.scheme(), "http" | "https") {
+        return false;
+    }
+    let (Some(host), Some(port)) = (url.host_str(), url.port_or_known_default()) else {
+        return false;
+    };
+    // IPv6 literals come bracketed; lookup_host takes them bare and hands IPs straight back
+    let host = host.trim_start_matches('[').trim_end_matches(']');
+    let resolved = tokio::net::lookup_host((host.to_string(), port)).await;
+    match resolved {
+        Ok(addrs) => {
+            let addrs: Vec<_> = addrs.collect();
+            !addrs.is_empty() && addrs.iter().all(|addr| is_public_ip(addr.ip()))
+        }
+        Err(e) => {
+            tracing::warn!("Failed to resolve webhook host {}: {}", host, e);
+            false
+        }
+    }
+}
+
+/// Signature header value: `t=<unix seconds>,v1=<hex HMAC-SHA256 of "<t>.<body>">`.
+/// Receivers recompute it with their secret and should reject stale timestamps.
+pub fn sign(secret: &str, timestamp: i64, body: &str) -> String {
+    let mut mac = Hmac::<Sha256>::new_from_slice(secret.as_bytes()).expect("HMAC accepts any key length");
+    mac.update(format!("{}.{}", timestamp, body).as_bytes());
+    format!("t={},v1={}", timestamp, hex::encode(mac.finalize().into_bytes()))
+}
+
+/// Queues a delivery of the event to each of the user's active webhooks subscribed to its type.
+pub async fn enqueue_deliveries(pool: &DbPool, user_id: &str, event_id: &str, event_type: &str) -> Result<u64> {
+    let now = format_db_datetime(Utc::now());
+    let result = sqlx::query(
+        r#"
+        INSERT INTO webhook_deliveries (id, webhook_id, event_id, event_type, attempts, next_attempt_at, created_at)
+        SELECT lower(hex(randomblob(16))), w.id, ?, ?, 0, ?, ?
+        FROM webhooks w
+        WHERE w.user_id = ? AND w.is_active = TRUE
+          AND (json_array_length(w.event_types) = 0
+               OR EXISTS (SELECT 1 FROM json_each(w.event_types) WHERE json_each.value = ?))
+        "#,
+    )
+    .bind(event_id)
+    .bind(event_type)
+    .bind(&now)
+    .bind(&now)
+    .bind(user_id)
+    .bind(event_type)
+    .execute(pool)
+    .await?;
+
+    Ok(result.rows_affected())
+}
+
+/// Posts due deliveries, rendering each event in its webhook's schema version. Anything but a
+/// 2xx response is retried with exponential backoff up to `MAX_DELIVERY_ATTEMPTS`.
+pub async fn deliver_pending(pool: &DbPool, client: &reqwest::Client) -> Result<usize> {
+    let pending = sqlx::query(
+        r#"
+        SELECT d.id, d.event_id, d.attempts, w.id AS webhook_id, w.url, w.secret, w.schema_version
+        FROM webhook_deliveries d
+        JOIN webhooks w ON w.id = d.webhook_id
+        WHERE d.delivered_at IS NULL AND d.attempts < ? AND d.next_attempt_at <= ? AND w.is_active = TRUE
+        ORDER BY d.created_at
+        LIMIT ?
+        "#,
+    )
+    .bind(MAX_DELIVERY_ATTEMPTS)
+    .bind(format_db_datetime(Utc::now()))
+    .bind(WEBHOOK_BATCH_SIZE)
+    .fetch_all(pool)
+    .await?;
+
+    let delivered = stream::iter(pending)
+        .map(|row| async move { deliver(pool, client, &row).await })
+        .buffer_unordered(WEBHOOK_CONCURRENCY)
+        .try_fold(0, |delivered, ok| async move { Ok(delivered + usize::from(ok)) })
+        .await?;
+
+    Ok(delivered)
+}
+
+/// Posts one delivery and records the outcome; `true` if the receiver took it.
+async fn deliver(pool: &DbPool, client: &reqwest::Client, row: &SqliteRow) -> Result<bool> {
+    let delivery_id: String = row.get("id");
+    let webhook_id: String = row.get("webhook_id");
+    let url: String = row.get("url");
+    let attempts = row.get::<i64, _>("attempts") + 1;
+
+    let event = sqlx::query_as::<_, Event>("SELECT * FROM events WHERE id = ?")
+        .bind(row.get::<String, _>("event_id"))
+        .fetch_optional(pool)
+        .await?;
+    let Some(event) = event else {
+        sqlx::query("DELETE FROM webhook_deliveries WHERE id = ?")
+            .bind(&delivery_id)
+            .execute(pool)
+            .await?;
+        return Ok(false);
+    };
+
+    // The host may have been pointed somewhere internal since the webhook was saved
+    let (status, error) = if !is_public_url(&url).await {
+        (None, Some("URL resolves to a private or local address".to_string()))
+    } else {
+        let body = events::render_payload(&event, row.get::<i64, _>("schema_version") as u32).to_string();
+        let signature = sign(&row.get::<String, _>("secret"), Utc::now().timestamp(), &body);
+        let response = client
+            .post(&url)
+            .header("Content-Type", "application/json")
+            .header("X-Webhook-Id", &webhook_id)
+            .header("X-Webhook-Delivery", &delivery_id)
+            .header("X-Webhook-Event", &event.event_type)
+            .header("X-Webhook-Signature", signature)
+            .body(body)
+            .send()
+            .await;
+
+        match response {
+            Ok(response) if response.status().is_success() => (Some(response.status().as_u16() as i64), None),
+            Ok(response) => (Some(response.status().as_u16() as i64), Some(format!("HTTP {}", response.status()))),
+            Err(e) => (None, Some(e.to_string())),
+        }
+    };
+
+    match error {
+        None => {
+            sqlx::query("UPDATE webhook_deliveries SET attempts = ?, last_status = ?, last_error = NULL, delivered_at = ? WHERE id = ?")
+                .bind(attempts)
+                .bind(status)
+                .bind(format_db_datetime(Utc::now()))
+                .bind(&delivery_id)
+                .execute(pool)
+                .await?;
+            Ok(true)
+        }
+        Some(error) => {
+            if attempts >= MAX_DELIVERY_ATTEMPTS {
+                tracing::warn!("Giving up on webhook delivery {} to {} after {} attempts: {}", delivery_id, webhook_id, attempts, error);
+            } else {
+                tracing::warn!("Webhook delivery {} to {} failed (attempt {}): {}", delivery_id, webhook_id, attempts, error);
+            }
+            let retry_at = Utc::now() + ChronoDuration::minutes(RETRY_BASE_MINUTES << (attempts - 1));
+            sqlx::query("UPDATE webhook_deliveries SET attempts = ?, last_status = ?, last_error = ?, next_attempt_at = ? WHERE id = ?")
+                .bind(attempts)
+                .bind(status)
+                .bind(&error)
+                .bind(format_db_datetime(retry_at))
+                .bind(&delivery_id)
+                .execute(pool)
+                .await?;
+            Ok(false)
+        }
+    }
+}
+
+pub async fn prune_deliveries(pool: &DbPool) -> Result<u64> {
+    let cutoff = format_db_datetime(Utc::now() - ChronoDuration::days(DELIVERY_RETENTION_DAYS));
+    let result = sqlx::query("DELETE FROM webhook_deliveries WHERE created_at < ? AND (delivered_at IS NOT NULL OR attempts >= ?)")
+        .bind(cutoff)
+        .bind(MAX_DELIVERY_ATTEMPTS)
+        .execute(pool)
+        .await?;
+
+    Ok(result.rows_affected())
+}
+
+pub fn schedule_webhook_delivery_job(scheduler: &mut Scheduler) {
+    // A redirect could lead anywhere, including addresses the URL check turned away
+    let client = match reqwest::Client::builder()
+        .timeout(WEBHOOK_TIMEOUT)
+        .redirect(reqwest::redirect::Policy::none())
+        .build()
+    {
+        Ok(client) => client,
+        Err(e) => {
+            tracing::error!("Webhook delivery disabled: {}", e);
+            return;
+        }
+    };
+
+    // Deliveries keep their own retry schedule, so a failed run just waits for the next one
+    scheduler
+        .every("webhook_delivery", WEBHOOK_DELIVERY_INTERVAL, move |pool| {
+            let client = client.clone();
+            async move {
+                let delivered = deliver_pending(&pool, &client).await?;
+                if delivered > 0 {
+                    tracing::info!("Delivered {} webhook events", delivered);
+                }
+                prune_deliveries(&pool).await?;
+                Ok(delivered as u64)
+            }
+        })
+        .retries(0)
+        .keeps_cache()
+        .only_when(|features| features.enabled(features.webhooks));
+}
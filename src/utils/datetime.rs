@@ -0,0 +1,14 @@
+use chrono::{DateTime, Datelike, NaiveDate, Utc};
+
+/// Formats a timestamp the way every table stores its DATETIME columns.
+pub fn format_db_datetime(value: DateTime<Utc>) -> String {
+    value.format("%Y-%m-%d %H:%M:%S").to_string()
+}
+
+pub fn days_in_month(year: i32, month: u32) -> u32 {
+    let (next_year, next_month) = if month == 12 { (year + 1, 1) } else { (year, month + 1) };
+    NaiveDate::from_ymd_opt(next_year, next_month, 1)
+        .and_then(|d| d.pred_opt())
+        .map(|d| d.day())
+        .unwrap_or(28)
+}
@@ -0,0 +1,116 @@
+use axum::{
+    http::{header, StatusCode},
+    response::{IntoResponse, Response},
+};
+use serde_json::{json, Value};
+
+use crate::utils::response::ApiResponse;
+use crate::utils::validation::FieldErrors;
+
+/// Everything a request can fail with. Each variant answers with one status and a stable,
+/// machine-readable `code`; messages are meant for users, so error details stay in the logs.
+#[derive(Debug)]
+pub enum AppError {
+    BadRequest(String),
+    Unauthorized(String),
+    /// The token's session was ended; its code is the reason, e.g. `idle_timeout`.
+    SessionEnded { reason: String, message: String },
+    Forbidden(String),
+    NotFound(String),
+    Conflict(String),
+    /// An update based on an outdated version, with the record as it is now.
+    Stale { message: String, current: Value },
+    Gone(String),
+    PayloadTooLarge(String),
+    Unprocessable(String),
+    /// Input that failed validation, with a report of what was wrong.
+    Invalid { message: String, details: Value },
+    /// A request body with fields that failed their checks, listed field by field.
+    Validation(FieldErrors),
+    PreconditionRequired(String),
+    /// A user went over one of their quotas, which frees up after `retry_after_secs`.
+    QuotaExceeded { quota: String, message: String, retry_after_secs: u64 },
+    Unavailable(String),
+    Internal(String),
+}
+
+impl AppError {
+    pub fn status(&self) -> StatusCode {
+        match self {
+            AppError::BadRequest(_) => StatusCode::BAD_REQUEST,
+            AppError::Unauthorized(_) | AppError::SessionEnded { .. } => StatusCode::UNAUTHORIZED,
+            AppError::Forbidden(_) => StatusCode::FORBIDDEN,
+            AppError::NotFound(_) => StatusCode::NOT_FOUND,
+            AppError::Conflict(_) | AppError::Stale { .. } => StatusCode::CONFLICT,
+            AppError::Gone(_) => StatusCode::GONE,
+            AppError::PayloadTooLarge(_) => StatusCode::PAYLOAD_TOO_LARGE,
+            AppError::Unprocessable(_) | AppError::Invalid { .. } | AppError::Validation(_) => {
+                StatusCode::UNPROCESSABLE_ENTITY
+            },
+            AppError::PreconditionRequired(_) => StatusCode::PRECONDITION_REQUIRED,
+            AppError::QuotaExceeded { .. } => StatusCode::TOO_MANY_REQUESTS,
+            AppError::Unavailable(_) => StatusCode::SERVICE_UNAVAILABLE,
+            AppError::Internal(_) => StatusCode::INTERNAL_SERVER_ERROR,
+        }
+    }
+
+    pub fn code(&self) -> &str {
+        match self {
+            AppError::BadRequest(_) => "bad_request",
+            AppError::Unauthorized(_) => "unauthorized",
+            AppError::SessionEnded { reason, .. } => reason,
+            AppError::Forbidden(_) => "forbidden",
+            AppError::NotFound(_) => "not_found",
+            AppError::Conflict(_) => "conflict",
+            AppError::Stale { .. } => "stale_version",
+            AppError::Gone(_) => "gone",
+            AppError::PayloadTooLarge(_) => "payload_too_large",
+            AppError::Unprocessable(_) => "unprocessable",
+            AppError::Invalid { .. } | AppError::Validation(_) => "validation_failed",
+            AppError::PreconditionRequired(_) => "precondition_required",
+            AppError::QuotaExceeded { .. } => "quota_exceeded",
+            AppError::Unavailable(_) => "service_unavailable",
+            AppError::Internal(_) => "internal_error",
+        }
+    }
+
+    pub fn message(&self) -> String {
+        match self {
+            AppError::Validation(errors) => {
+                format!("Invalid {}", errors.fields().collect::<Vec<_>>().join(", "))
+            }
+            AppError::BadRequest(message)
+            | AppError::Unauthorized(message)
+            | AppError::SessionEnded { message, .. }
+            | AppError::Forbidden(message)
+            | AppError::NotFound(message)
+            | AppError::Conflict(message)
+            | AppError::Stale { message, .. }
+            | AppError::Gone(message)
+            | AppError::PayloadTooLarge(message)
+            | AppError::Unprocessable(message)
+            | AppError::Invalid { message, .. }
+            | AppError::PreconditionRequired(message)
+            | AppError::QuotaExceeded { message, .. }
+            | AppError::Unavailable(message)
+            | AppError::Internal(message) => message.clone(),
+        }
+    }
+}
+
+impl IntoResponse for AppError {
+    fn into_response(self) -> Response {
+        let response = ApiResponse::error(self.status(), self.message()).with_code(self.code());
+        match self {
+            AppError::Stale { current, .. } => response.with_data(current).into_response(),
+            AppError::Invalid { details, .. } => response.with_data(details).into_response(),
+            AppError::Validation(errors) => response.with_fields(errors).into_response(),
+            AppError::QuotaExceeded { quota, retry_after_secs, .. } => (
+                [(header::RETRY_AFTER, retry_after_secs.to_string())],
+                response.with_data(json!({ "quota": quota, "retryAfterSecs": retry_after_secs })),
+            )
+                .into_response(),
+            _ => response.into_response(),
+        }
+    }
+}
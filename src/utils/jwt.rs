@@ -3,29 +3,33 @@ use serde::{Deserialize, Serialize};
 use chrono::{Duration, Utc};
 use anyhow::Result;
 
+use crate::config;
+
 #[derive(Debug, Serialize, Deserialize)]
 pub struct Claims {
     pub sub: String, // user id
     pub exp: usize,  // expiration time
     pub iat: usize,  // issued at
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub sid: Option<String>, // session id (absent on tokens issued before sessions were tracked)
 }
 
-const JWT_SECRET: &str = "your-secret-key-here-change-in-production";
-
-pub fn create_jwt(user_id: &str) -> Result<String> {
+pub fn create_jwt(user_id: &str, session_id: &str) -> Result<String> {
+    let jwt = &config::get().jwt;
     let now = Utc::now();
-    let expires_at = now + Duration::hours(24); // Token expires in 24 hours
+    let expires_at = now + Duration::hours(jwt.expiry_hours);
     
     let claims = Claims {
         sub: user_id.to_string(),
         exp: expires_at.timestamp() as usize,
         iat: now.timestamp() as usize,
+        sid: Some(session_id.to_string()),
     };
     
     let token = encode(
         &Header::default(),
         &claims,
-        &EncodingKey::from_secret(JWT_SECRET.as_ref()),
+        &EncodingKey::from_secret(jwt.secret.as_ref()),
     )?;
     
     Ok(token)
@@ -34,7 +38,7 @@ pub fn create_jwt(user_id: &str) -> Result<String> {
 pub fn verify_jwt(token: &str) -> Result<Claims> {
     let token_data = decode::<Claims>(
         token,
-        &DecodingKey::from_secret(JWT_SECRET.as_ref()),
+        &DecodingKey::from_secret(config::get().jwt.secret.as_ref()),
         &Validation::new(Algorithm::HS256),
     )?;
     
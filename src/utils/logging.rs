@@ -0,0 +1,38 @@
+use std::io::IsTerminal;
+use std::sync::OnceLock;
+
+use anyhow::{anyhow, Result};
+use tracing_subscriber::{fmt, layer::SubscriberExt, reload, util::SubscriberInitExt, EnvFilter, Registry};
+
+use crate::config::LogConfig;
+
+/// Swaps the level filter of the running subscriber, set by [`init`].
+static FILTER: OnceLock<reload::Handle<EnvFilter, Registry>> = OnceLock::new();
+
+/// Sends `tracing` events, and `log` records from dependencies, to stdout: readable text, or
+/// one JSON object per line carrying the request span's fields for log collectors to query.
+/// `RUST_LOG` overrides the configured level.
+pub fn init(config: &LogConfig) {
+    let filter = EnvFilter::try_from_default_env().unwrap_or_else(|_| EnvFilter::new(&config.level));
+    let (filter, handle) = reload::Layer::new(filter);
+
+    let (json, text) = if config.format == "json" {
+        let json = fmt::layer().json().with_current_span(true).with_span_list(false).flatten_event(true);
+        (Some(json), None)
+    } else {
+        (None, Some(fmt::layer().with_ansi(std::io::stdout().is_terminal())))
+    };
+    tracing_subscriber::registry().with(filter).with(json).with(text).init();
+    FILTER.set(handle).ok();
+}
+
+/// Logs at `level` from now on, unless `RUST_LOG` pins it. Does nothing before [`init`].
+pub fn set_level(level: &str) -> Result<()> {
+    if std::env::var_os("RUST_LOG").is_some() {
+        return Err(anyhow!("RUST_LOG is set, which wins over log.level"));
+    }
+    if let Some(handle) = FILTER.get() {
+        handle.reload(EnvFilter::new(level))?;
+    }
+    Ok(())
+}
@@ -1,3 +1,10 @@
 pub mod jwt;
+pub mod datetime;
 
-pub use jwt::*;
\ No newline at end of file
+pub mod money;
+pub mod response;
+pub mod error;
+pub mod validation;
+pub mod pagination;
+pub mod streaming;
+pub mod logging;
@@ -0,0 +1,255 @@
+use std::fmt;
+use std::iter::Sum;
+use std::ops::{Add, AddAssign, Div, Mul, Neg, Sub, SubAssign};
+use std::str::FromStr;
+
+use rust_decimal::prelude::{FromPrimitive, ToPrimitive};
+use rust_decimal::{Decimal, RoundingStrategy};
+use serde::de::{self, Visitor};
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+use sqlx::encode::IsNull;
+use sqlx::error::BoxDynError;
+use sqlx::sqlite::{Sqlite, SqliteArgumentValue, SqliteTypeInfo, SqliteValueRef};
+use sqlx::{Decode, Encode, Type};
+
+/// Decimal places kept in storage. Money columns hold integers scaled by `10^MONEY_SCALE`,
+/// so SQL sums and comparisons are exact and three-decimal currencies still fit.
+pub const MONEY_SCALE: u32 = 4;
+
+/// An exact money amount. Stored as a scaled integer and sent over JSON as a plain number;
+/// requests may also send it as a string to avoid float parsing on the client.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct Money(Decimal);
+
+impl Money {
+    pub const ZERO: Money = Money(Decimal::ZERO);
+
+    pub const fn new(value: Decimal) -> Self {
+        Self(value)
+    }
+
+    /// For amounts worked out in floating point, such as a quantity times a market price.
+    pub fn from_f64(value: f64) -> Option<Self> {
+        Decimal::from_f64(value).map(Self)
+    }
+
+    /// Reads a scaled integer as stored in a money column.
+    pub fn from_minor_units(units: i64) -> Self {
+        Self(Decimal::new(units, MONEY_SCALE).normalize())
+    }
+
+    /// The scaled integer stored for this amount, rounding half away from zero past the
+    /// stored scale.
+    pub fn to_minor_units(self) -> i64 {
+        let rounded = self.0.round_dp_with_strategy(MONEY_SCALE, RoundingStrategy::MidpointAwayFromZero);
+        (rounded * Decimal::from(10i64.pow(MONEY_SCALE))).to_i64().unwrap_or(i64::MAX)
+    }
+
+    pub fn to_f64(self) -> f64 {
+        self.0.to_f64().unwrap_or_default()
+    }
+
+    pub fn abs(self) -> Self {
+        Self(self.0.abs())
+    }
+
+    pub fn is_zero(self) -> bool {
+        self.0.is_zero()
+    }
+
+    pub fn is_negative(self) -> bool {
+        self.0.is_sign_negative() && !self.0.is_zero()
+    }
+
+    pub fn is_positive(self) -> bool {
+        self.0.is_sign_positive() && !self.0.is_zero()
+    }
+
+    /// Digits after the decimal point, ignoring trailing zeros.
+    pub fn decimal_places(self) -> u32 {
+        self.0.normalize().scale()
+    }
+
+    pub fn round_dp(self, dp: u32) -> Self {
+        Self(self.0.round_dp_with_strategy(dp, RoundingStrategy::MidpointAwayFromZero))
+    }
+
+    /// `self` as a share of `total`, or 0 when `total` is zero.
+    pub fn ratio(self, total: Money) -> f64 {
+        if total.is_zero() {
+            0.0
+        } else {
+            (self.0 / total.0).to_f64().unwrap_or_default()
+        }
+    }
+
+    pub fn max(self, other: Money) -> Self {
+        Self(self.0.max(other.0))
+    }
+}
+
+impl From<Decimal> for Money {
+    fn from(value: Decimal) -> Self {
+        Self(value)
+    }
+}
+
+impl From<i64> for Money {
+    fn from(value: i64) -> Self {
+        Self(Decimal::from(value))
+    }
+}
+
+impl FromStr for Money {
+    type Err = rust_decimal::Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Decimal::from_str(s.trim()).map(Self)
+    }
+}
+
+/// Supports precision, e.g. `{:.2}`, rounding half away from zero; otherwise prints without
+/// trailing zeros.
+impl fmt::Display for Money {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let value = match f.precision() {
+            Some(dp) => self.round_dp(dp as u32).0,
+            None => self.0,
+        };
+        // Keep amounts that round to zero from printing as "-0.00".
+        let value = if value.is_zero() { Decimal::ZERO } else { value.normalize() };
+        fmt::Display::fmt(&value, f)
+    }
+}
+
+impl Add for Money {
+    type Output = Money;
+    fn add(self, rhs: Money) -> Money {
+        Money(self.0 + rhs.0)
+    }
+}
+
+impl Sub for Money {
+    type Output = Money;
+    fn sub(self, rhs: Money) -> Money {
+        Money(self.0 - rhs.0)
+    }
+}
+
+impl Neg for Money {
+    type Output = Money;
+    fn neg(self) -> Money {
+        Money(-self.0)
+    }
+}
+
+impl AddAssign for Money {
+    fn add_assign(&mut self, rhs: Money) {
+        self.0 += rhs.0;
+    }
+}
+
+impl SubAssign for Money {
+    fn sub_assign(&mut self, rhs: Money) {
+        self.0 -= rhs.0;
+    }
+}
+
+impl Mul<Decimal> for Money {
+    type Output = Money;
+    fn mul(self, rhs: Decimal) -> Money {
+        Money(self.0 * rhs)
+    }
+}
+
+/// Applies an exchange rate or other floating-point factor.
+impl Mul<f64> for Money {
+    type Output = Money;
+    fn mul(self, rhs: f64) -> Money {
+        Money(self.0 * Decimal::from_f64(rhs).unwrap_or_default())
+    }
+}
+
+impl Div<Decimal> for Money {
+    type Output = Money;
+    fn div(self, rhs: Decimal) -> Money {
+        Money(self.0 / rhs)
+    }
+}
+
+impl Sum for Money {
+    fn sum<I: Iterator<Item = Money>>(iter: I) -> Money {
+        iter.fold(Money::ZERO, Add::add)
+    }
+}
+
+impl<'a> Sum<&'a Money> for Money {
+    fn sum<I: Iterator<Item = &'a Money>>(iter: I) -> Money {
+        iter.copied().sum()
+    }
+}
+
+impl Serialize for Money {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_f64(self.to_f64())
+    }
+}
+
+struct MoneyVisitor;
+
+impl Visitor<'_> for MoneyVisitor {
+    type Value = Money;
+
+    fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+        formatter.write_str("an amount as a number or decimal string")
+    }
+
+    fn visit_i64<E: de::Error>(self, value: i64) -> Result<Money, E> {
+        Ok(Money::from(value))
+    }
+
+    fn visit_u64<E: de::Error>(self, value: u64) -> Result<Money, E> {
+        Ok(Money(Decimal::from(value)))
+    }
+
+    fn visit_f64<E: de::Error>(self, value: f64) -> Result<Money, E> {
+        Decimal::from_f64(value)
+            .map(Money)
+            .ok_or_else(|| E::invalid_value(de::Unexpected::Float(value), &self))
+    }
+
+    fn visit_str<E: de::Error>(self, value: &str) -> Result<Money, E> {
+        Money::from_str(value).map_err(|_| E::invalid_value(de::Unexpected::Str(value), &self))
+    }
+}
+
+impl<'de> Deserialize<'de> for Money {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Money, D::Error> {
+        deserializer.deserialize_any(MoneyVisitor)
+    }
+}
+
+impl Type<Sqlite> for Money {
+    fn type_info() -> SqliteTypeInfo {
+        <i64 as Type<Sqlite>>::type_info()
+    }
+
+    // Money columns are declared REAL, and SUM/AVG may come back as either storage class.
+    fn compatible(ty: &SqliteTypeInfo) -> bool {
+        <i64 as Type<Sqlite>>::compatible(ty) || <f64 as Type<Sqlite>>::compatible(ty)
+    }
+}
+
+impl<'q> Encode<'q, Sqlite> for Money {
+    fn encode_by_ref(&self, args: &mut Vec<SqliteArgumentValue<'q>>) -> IsNull {
+        args.push(SqliteArgumentValue::Int64(self.to_minor_units()));
+        IsNull::No
+    }
+}
+
+impl<'r> Decode<'r, Sqlite> for Money {
+    fn decode(value: SqliteValueRef<'r>) -> Result<Money, BoxDynError> {
+        let units = <f64 as Decode<Sqlite>>::decode(value)?;
+        Ok(Money::from_minor_units(units.round() as i64))
+    }
+}
@@ -0,0 +1,81 @@
+use serde::{Deserialize, Deserializer, Serialize};
+
+/// Page size when the client doesn't ask for one.
+pub const DEFAULT_PAGE_SIZE: usize = 50;
+
+/// Largest page a client may ask for; bigger requests are cut down to it.
+pub const MAX_PAGE_SIZE: usize = 500;
+
+/// `?limit=&cursor=` on a list endpoint. The cursor is whatever the previous page returned as
+/// `nextCursor`; clients should pass it back untouched rather than build one.
+#[derive(Debug, Clone, Default, Deserialize)]
+pub struct PageQuery {
+    pub limit: Option<usize>,
+    #[serde(default, deserialize_with = "cursor_offset")]
+    pub cursor: Option<usize>,
+}
+
+/// Reads a cursor, so a mangled one is a 400 from the query extractor rather than page one.
+fn cursor_offset<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Option<usize>, D::Error> {
+    match Option::<String>::deserialize(deserializer)? {
+        None => Ok(None),
+        Some(cursor) if cursor.is_empty() => Ok(None),
+        Some(cursor) => cursor
+            .parse()
+            .map(Some)
+            .map_err(|_| serde::de::Error::custom("invalid page cursor")),
+    }
+}
+
+impl PageQuery {
+    pub fn limit(&self) -> usize {
+        self.limit.unwrap_or(DEFAULT_PAGE_SIZE).clamp(1, MAX_PAGE_SIZE)
+    }
+
+    pub fn offset(&self) -> usize {
+        self.cursor.unwrap_or(0)
+    }
+}
+
+/// One page of a list, the same shape on every list endpoint: the `items`, how many there are
+/// in all, and the cursor for the next page while `hasMore`.
+#[derive(Debug, Clone, Serialize)]
+pub struct Paginated<T> {
+    pub items: Vec<T>,
+    pub total: usize,
+    #[serde(rename = "nextCursor")]
+    pub next_cursor: Option<String>,
+    #[serde(rename = "hasMore")]
+    pub has_more: bool,
+}
+
+impl<T> Paginated<T> {
+    /// A page already cut to `page` by the query, e.g. with `LIMIT`/`OFFSET`, out of `total`.
+    pub fn new(items: Vec<T>, total: usize, page: &PageQuery) -> Self {
+        let end = page.offset() + items.len();
+        let has_more = end < total;
+        Self {
+            items,
+            total,
+            next_cursor: has_more.then(|| end.to_string()),
+            has_more,
+        }
+    }
+
+    /// The same page with each item turned into another.
+    pub fn map<U>(self, f: impl FnMut(T) -> U) -> Paginated<U> {
+        Paginated {
+            items: self.items.into_iter().map(f).collect(),
+            total: self.total,
+            next_cursor: self.next_cursor,
+            has_more: self.has_more,
+        }
+    }
+
+    /// Cuts `page` out of the whole list.
+    pub fn slice(items: Vec<T>, page: &PageQuery) -> Self {
+        let total = items.len();
+        let items = items.into_iter().skip(page.offset()).take(page.limit()).collect();
+        Self::new(items, total, page)
+    }
+}
@@ -0,0 +1,123 @@
+use axum::{
+    http::StatusCode,
+    response::{IntoResponse, Json, Response},
+};
+use serde::Serialize;
+use serde_json::Value;
+
+use crate::utils::validation::FieldErrors;
+
+/// What went wrong, for clients: a stable snake_case `code` to branch on and a `message` to show.
+#[derive(Debug, Clone, Serialize)]
+pub struct ApiError {
+    pub code: String,
+    pub message: String,
+    /// Per-field problems, for a request that failed validation.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub fields: Option<FieldErrors>,
+}
+
+/// The envelope every endpoint answers with. `data` holds the result, `error` is set when
+/// `success` is false, and `meta` carries anything about the response rather than the data,
+/// such as whether an import was a dry run. Fields with nothing in them are left out.
+#[derive(Debug, Clone, Serialize)]
+pub struct ApiResponse<T = Value> {
+    pub success: bool,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub data: Option<T>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub message: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error: Option<ApiError>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub meta: Option<Value>,
+    #[serde(skip)]
+    pub status: StatusCode,
+}
+
+/// The default error code for a status: its reason phrase in snake_case, e.g. `not_found`.
+pub fn status_code_name(status: StatusCode) -> String {
+    status
+        .canonical_reason()
+        .unwrap_or("error")
+        .to_lowercase()
+        .replace(|c: char| !c.is_ascii_alphanumeric(), "_")
+}
+
+impl<T> ApiResponse<T> {
+    pub fn ok(data: T) -> Self {
+        Self {
+            success: true,
+            data: Some(data),
+            message: None,
+            error: None,
+            meta: None,
+            status: StatusCode::OK,
+        }
+    }
+
+    pub fn with_message(mut self, message: impl Into<String>) -> Self {
+        self.message = Some(message.into());
+        self
+    }
+
+    pub fn with_meta(mut self, meta: Value) -> Self {
+        self.meta = Some(meta);
+        self
+    }
+
+    /// Replaces the status-derived error code, e.g. with why a session ended.
+    pub fn with_code(mut self, code: impl Into<String>) -> Self {
+        if let Some(error) = self.error.as_mut() {
+            error.code = code.into();
+        }
+        self
+    }
+
+    pub fn with_fields(mut self, fields: FieldErrors) -> Self {
+        if let Some(error) = self.error.as_mut() {
+            error.fields = Some(fields);
+        }
+        self
+    }
+
+    pub fn with_data(mut self, data: T) -> Self {
+        self.data = Some(data);
+        self
+    }
+}
+
+impl ApiResponse {
+    /// Success with nothing to return but a note, e.g. after a delete.
+    pub fn message(message: impl Into<String>) -> Self {
+        Self {
+            success: true,
+            data: None,
+            message: Some(message.into()),
+            error: None,
+            meta: None,
+            status: StatusCode::OK,
+        }
+    }
+
+    pub fn error(status: StatusCode, message: impl Into<String>) -> Self {
+        Self {
+            success: false,
+            data: None,
+            message: None,
+            error: Some(ApiError {
+                code: status_code_name(status),
+                message: message.into(),
+                fields: None,
+            }),
+            meta: None,
+            status,
+        }
+    }
+}
+
+impl<T: Serialize> IntoResponse for ApiResponse<T> {
+    fn into_response(self) -> Response {
+        (self.status, Json(self)).into_response()
+    }
+}
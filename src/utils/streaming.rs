@@ -0,0 +1,60 @@
+use anyhow::{anyhow, Result};
+use hyper::body::{Body, Bytes, Sender};
+use serde::Serialize;
+
+/// Bytes gathered before they are sent on as one chunk.
+const CHUNK_BYTES: usize = 64 * 1024;
+
+/// A response body written a piece at a time, for results too large to build in memory.
+/// Dropping it before [`ChunkedBody::finish`], e.g. when a query fails part way, aborts the
+/// body so the client sees a failed download rather than a silently truncated file.
+pub struct ChunkedBody {
+    sender: Option<Sender>,
+    buffer: Vec<u8>,
+}
+
+impl ChunkedBody {
+    /// The writer, and the body to answer with.
+    pub fn new() -> (Self, Body) {
+        let (sender, body) = Body::channel();
+        (Self { sender: Some(sender), buffer: Vec::with_capacity(CHUNK_BYTES) }, body)
+    }
+
+    pub fn push(&mut self, bytes: &[u8]) {
+        self.buffer.extend_from_slice(bytes);
+    }
+
+    pub fn push_json<T: Serialize>(&mut self, value: &T) -> Result<()> {
+        serde_json::to_writer(&mut self.buffer, value)?;
+        Ok(())
+    }
+
+    /// Sends what has gathered once it makes a full chunk. Fails when the client went away.
+    pub async fn flush(&mut self) -> Result<()> {
+        if self.buffer.len() >= CHUNK_BYTES {
+            self.send().await?;
+        }
+        Ok(())
+    }
+
+    /// Sends the rest and ends the body.
+    pub async fn finish(mut self) -> Result<()> {
+        self.send().await?;
+        self.sender.take();
+        Ok(())
+    }
+
+    async fn send(&mut self) -> Result<()> {
+        let chunk = Bytes::from(std::mem::replace(&mut self.buffer, Vec::with_capacity(CHUNK_BYTES)));
+        let sender = self.sender.as_mut().ok_or_else(|| anyhow!("body already finished"))?;
+        sender.send_data(chunk).await.map_err(|_| anyhow!("client disconnected"))
+    }
+}
+
+impl Drop for ChunkedBody {
+    fn drop(&mut self) {
+        if let Some(sender) = self.sender.take() {
+            sender.abort();
+        }
+    }
+}
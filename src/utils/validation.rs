@@ -0,0 +1,136 @@
+use std::collections::BTreeMap;
+use std::fmt;
+
+use chrono::{DateTime, Datelike, Utc};
+use serde::Serialize;
+
+use crate::services::currency;
+use crate::utils::error::AppError;
+use crate::utils::money::Money;
+
+/// Longest name, title or description a request may set.
+pub const MAX_TEXT_LENGTH: usize = 500;
+
+/// Years a date may fall in; anything outside is a client bug, not a real record.
+const SANE_YEARS: std::ops::RangeInclusive<i32> = 1900..=2200;
+
+/// What is wrong with a request, by field as clients name it, e.g. `statementDay`.
+#[derive(Debug, Clone, Default, Serialize)]
+#[serde(transparent)]
+pub struct FieldErrors(BTreeMap<String, Vec<String>>);
+
+impl FieldErrors {
+    pub fn add(&mut self, field: &str, message: impl Into<String>) {
+        self.0.entry(field.to_string()).or_default().push(message.into());
+    }
+
+    /// Records `message` against `field` unless `valid`.
+    pub fn check(&mut self, valid: bool, field: &str, message: &str) {
+        if !valid {
+            self.add(field, message);
+        }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.0.is_empty()
+    }
+
+    pub fn fields(&self) -> impl Iterator<Item = &str> {
+        self.0.keys().map(String::as_str)
+    }
+
+    /// A name or label: something other than whitespace, and not too long.
+    pub fn name(&mut self, field: &str, value: &str) {
+        self.check(!value.trim().is_empty(), field, "must not be empty");
+        self.text(field, value);
+    }
+
+    /// Free text, such as a description or note, within [`MAX_TEXT_LENGTH`].
+    pub fn text(&mut self, field: &str, value: &str) {
+        self.check(
+            value.chars().count() <= MAX_TEXT_LENGTH,
+            field,
+            &format!("must be at most {} characters", MAX_TEXT_LENGTH),
+        );
+    }
+
+    /// A three-letter ISO 4217 code, in any case.
+    pub fn currency(&mut self, field: &str, code: &str) {
+        self.check(currency::is_currency_code(code), field, "must be a three-letter currency code");
+    }
+
+    pub fn positive(&mut self, field: &str, amount: Money) {
+        self.check(amount.is_positive(), field, "must be greater than zero");
+    }
+
+    pub fn not_negative(&mut self, field: &str, amount: Money) {
+        self.check(!amount.is_negative(), field, "must not be negative");
+    }
+
+    /// An amount written no finer than `code`'s minor unit. Skipped while the code itself is
+    /// wrong, which is reported on its own.
+    pub fn fits_currency(&mut self, field: &str, amount: Money, code: &str) {
+        if currency::is_currency_code(code) {
+            self.check(
+                currency::fits_minor_unit(amount, code),
+                field,
+                "has more decimal places than the currency allows",
+            );
+        }
+    }
+
+    /// An annual rate in percent, 0 to 100.
+    pub fn percentage(&mut self, field: &str, rate: f64) {
+        self.check(rate.is_finite() && (0.0..=100.0).contains(&rate), field, "must be between 0 and 100");
+    }
+
+    /// A date within a plausible range of years.
+    pub fn date(&mut self, field: &str, date: DateTime<Utc>) {
+        self.check(SANE_YEARS.contains(&date.year()), field, "must be a realistic date");
+    }
+
+    /// `end` on or after `start`, reported against the `end` field.
+    pub fn not_before(&mut self, field: &str, end: DateTime<Utc>, start: DateTime<Utc>, start_field: &str) {
+        self.check(end >= start, field, &format!("must not be before {}", start_field));
+    }
+
+    /// One of a fixed set of values.
+    pub fn one_of(&mut self, field: &str, value: &str, allowed: &[&str]) {
+        self.check(allowed.contains(&value), field, &format!("must be one of: {}", allowed.join(", ")));
+    }
+
+    /// Ok when nothing was recorded, otherwise a 422 listing every problem.
+    pub fn into_result(self) -> Result<(), AppError> {
+        if self.is_empty() {
+            Ok(())
+        } else {
+            Err(AppError::Validation(self))
+        }
+    }
+}
+
+/// One `field message` line per problem, for places without a client to return them to, like
+/// the command line.
+impl fmt::Display for FieldErrors {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let lines: Vec<String> = self
+            .0
+            .iter()
+            .flat_map(|(field, messages)| messages.iter().map(move |message| format!("{} {}", field, message)))
+            .collect();
+        f.write_str(&lines.join("\n"))
+    }
+}
+
+/// A request body that can say what is wrong with it before anything is written. Checks only
+/// look at the request itself; whatever needs the database (ownership, the account's currency)
+/// stays in the handler.
+pub trait Validate {
+    fn check_fields(&self, errors: &mut FieldErrors);
+
+    fn validate(&self) -> Result<(), AppError> {
+        let mut errors = FieldErrors::default();
+        self.check_fields(&mut errors);
+        errors.into_result()
+    }
+}
@@ -0,0 +1,149 @@
+mod common;
+
+use axum::http::StatusCode;
+use serde_json::json;
+
+use common::TestApp;
+
+#[tokio::test]
+async fn created_account_is_listed() {
+    let app = TestApp::new().await;
+    let token = app.signup("owner@example.com").await;
+
+    let id = app.create_account(&token, "Checking", "BDT").await;
+
+    let response = app.get("/accounts", &token).await;
+    assert_eq!(response.status, StatusCode::OK);
+    assert_eq!(response.data()["total"], 1);
+    assert_eq!(response.data()["items"][0]["id"], id.as_str());
+    assert_eq!(response.data()["items"][0]["name"], "Checking");
+}
+
+#[tokio::test]
+async fn accounts_are_scoped_to_their_owner() {
+    let app = TestApp::new().await;
+    let owner = app.signup("owner@example.com").await;
+    let other = app.signup("other@example.com").await;
+    let id = app.create_account(&owner, "Checking", "BDT").await;
+
+    assert_eq!(app.get("/accounts", &other).await.data()["total"], 0);
+    assert_eq!(app.get("/api/accounts", &other).await.data()["total"], 0);
+    assert_eq!(app.get(&format!("/accounts/{}", id), &other).await.status, StatusCode::NOT_FOUND);
+
+    let update = app.put(&format!("/accounts/{}", id), &other, json!({ "name": "Mine now", "version": 1 })).await;
+    assert_eq!(update.status, StatusCode::NOT_FOUND);
+    assert_eq!(app.delete(&format!("/accounts/{}", id), &other).await.status, StatusCode::NOT_FOUND);
+
+    let response = app.get(&format!("/accounts/{}", id), &owner).await;
+    assert_eq!(response.status, StatusCode::OK);
+    assert_eq!(response.data()["name"], "Checking");
+}
+
+#[tokio::test]
+async fn invalid_account_is_rejected_by_field() {
+    let app = TestApp::new().await;
+    let token = app.signup("owner@example.com").await;
+
+    let response = app
+        .post("/accounts", &token, json!({ "name": " ", "account_type": "bank", "balance": 0, "currency": "dollars" }))
+        .await;
+
+    assert_eq!(response.status, StatusCode::UNPROCESSABLE_ENTITY);
+    assert_eq!(response.body["error"]["code"], "validation_failed");
+    assert!(response.body["error"]["fields"]["name"].is_array());
+    assert!(response.body["error"]["fields"]["currency"].is_array());
+}
+
+#[tokio::test]
+async fn accounts_are_listed_in_the_order_the_user_arranged() {
+    let app = TestApp::new().await;
+    let token = app.signup("owner@example.com").await;
+    let cash = app.create_account(&token, "Cash", "BDT").await;
+    let bank = app.create_account(&token, "Bank", "BDT").await;
+    let bkash = app.create_account(&token, "bKash", "BDT").await;
+
+    let names = |response: &common::TestResponse| -> Vec<String> {
+        response.data()["items"].as_array().unwrap().iter().map(|a| a["name"].as_str().unwrap().to_string()).collect()
+    };
+    assert_eq!(names(&app.get("/accounts", &token).await), ["Cash", "Bank", "bKash"]);
+
+    let reordered = app.post("/accounts/reorder", &token, json!({ "accountIds": [bkash, cash] })).await;
+    assert_eq!(reordered.status, StatusCode::OK, "{}", reordered.body);
+    assert_eq!(names(&app.get("/accounts", &token).await), ["bKash", "Cash", "Bank"]);
+    assert_eq!(app.get(&format!("/accounts/{}", bank), &token).await.data()["sortOrder"], 2);
+
+    let favorite = app.put(&format!("/accounts/{}", bank), &token, json!({ "isFavorite": true, "version": 2 })).await;
+    assert_eq!(favorite.status, StatusCode::OK, "{}", favorite.body);
+    assert_eq!(app.get(&format!("/accounts/{}", bank), &token).await.data()["isFavorite"], true);
+
+    let other = app.signup("other@example.com").await;
+    let foreign = app.post("/accounts/reorder", &other, json!({ "accountIds": [cash] })).await;
+    assert_eq!(foreign.status, StatusCode::NOT_FOUND);
+    let repeated = app.post("/accounts/reorder", &token, json!({ "accountIds": [cash, cash] })).await;
+    assert_eq!(repeated.status, StatusCode::UNPROCESSABLE_ENTITY);
+}
+
+#[tokio::test]
+async fn account_icon_and_color_are_kept() {
+    let app = TestApp::new().await;
+    let token = app.signup("owner@example.com").await;
+
+    let created = app
+        .post("/accounts", &token, json!({ "name": "bKash", "account_type": "mobile_banking", "balance": 0, "icon": "📱", "color": "#E2136E" }))
+        .await;
+    assert_eq!(created.status, StatusCode::OK, "{}", created.body);
+    let id = created.data()["id"].as_str().unwrap().to_string();
+
+    let updated = app.put(&format!("/accounts/{}", id), &token, json!({ "color": "#000000", "version": 1 })).await;
+    assert_eq!(updated.status, StatusCode::OK, "{}", updated.body);
+
+    let account = app.get(&format!("/accounts/{}", id), &token).await;
+    assert_eq!(account.data()["icon"], "📱");
+    assert_eq!(account.data()["color"], "#000000");
+
+    let cleared = app.put(&format!("/accounts/{}", id), &token, json!({ "icon": null, "version": 2 })).await;
+    assert_eq!(cleared.status, StatusCode::OK, "{}", cleared.body);
+    let account = app.get(&format!("/accounts/{}", id), &token).await;
+    assert!(account.data()["icon"].is_null());
+    assert_eq!(account.data()["color"], "#000000");
+
+    let named = app.put(&format!("/accounts/{}", id), &token, json!({ "color": "pink", "version": 3 })).await;
+    assert_eq!(named.status, StatusCode::UNPROCESSABLE_ENTITY);
+    assert!(named.body["error"]["fields"]["color"].is_array());
+}
+
+#[tokio::test]
+async fn institution_details_are_kept_but_full_numbers_are_refused() {
+    let app = TestApp::new().await;
+    let token = app.signup("owner@example.com").await;
+
+    let created = app
+        .post(
+            "/accounts",
+            &token,
+            json!({ "name": "Savings", "account_type": "bank", "balance": 0, "institutionName": "BRAC Bank, Gulshan", "accountNumberMasked": "****4821" }),
+        )
+        .await;
+    assert_eq!(created.status, StatusCode::OK, "{}", created.body);
+    let id = created.data()["id"].as_str().unwrap().to_string();
+
+    let noted = app.put(&format!("/accounts/{}", id), &token, json!({ "notes": "Joint with Rafi", "version": 1 })).await;
+    assert_eq!(noted.status, StatusCode::OK, "{}", noted.body);
+    let account = app.get(&format!("/accounts/{}", id), &token).await;
+    assert_eq!(account.data()["institutionName"], "BRAC Bank, Gulshan");
+    assert_eq!(account.data()["accountNumberMasked"], "****4821");
+    assert_eq!(account.data()["notes"], "Joint with Rafi");
+
+    let unmasked = app.put(&format!("/accounts/{}", id), &token, json!({ "accountNumberMasked": "1501204821", "version": 2 })).await;
+    assert_eq!(unmasked.status, StatusCode::UNPROCESSABLE_ENTITY);
+    assert!(unmasked.body["error"]["fields"]["accountNumberMasked"].is_array());
+
+    let cleared = app
+        .put(&format!("/accounts/{}", id), &token, json!({ "accountNumberMasked": null, "notes": null, "version": 2 }))
+        .await;
+    assert_eq!(cleared.status, StatusCode::OK, "{}", cleared.body);
+    let account = app.get(&format!("/accounts/{}", id), &token).await;
+    assert_eq!(account.data()["institutionName"], "BRAC Bank, Gulshan");
+    assert!(account.data()["accountNumberMasked"].is_null());
+    assert!(account.data()["notes"].is_null());
+}
@@ -0,0 +1,106 @@
+mod common;
+
+use axum::http::{Method, StatusCode};
+use serde_json::json;
+
+use common::{TestApp, PASSWORD};
+use personal_manager_backend::config::DatabaseConfig;
+use personal_manager_backend::services::admin::{self, AdminAccount};
+use personal_manager_backend::services::database;
+
+#[tokio::test]
+async fn new_admin_can_sign_in() {
+    let app = TestApp::new().await;
+
+    let created = admin::create_admin(&app.pool, "Ops", "Ops@Example.com", Some("admin-password")).await.unwrap();
+    assert!(matches!(created, AdminAccount::Created(_)));
+
+    let login = app
+        .request(Method::POST, "/auth/login", None, Some(json!({ "email": "ops@example.com", "password": "admin-password" })))
+        .await;
+    assert_eq!(login.status, StatusCode::OK, "{}", login.body);
+    assert_eq!(login.data()["user"]["isAdmin"], true);
+    assert_eq!(login.data()["user"]["emailVerified"], true);
+}
+
+#[tokio::test]
+async fn existing_user_is_promoted_and_keeps_their_password() {
+    let app = TestApp::new().await;
+    app.signup("member@example.com").await;
+
+    let promoted = admin::create_admin(&app.pool, "Admin", "member@example.com", None).await.unwrap();
+    assert!(matches!(promoted, AdminAccount::Promoted(_)));
+
+    let login = app
+        .request(Method::POST, "/auth/login", None, Some(json!({ "email": "member@example.com", "password": PASSWORD })))
+        .await;
+    assert_eq!(login.status, StatusCode::OK, "{}", login.body);
+    assert_eq!(login.data()["user"]["isAdmin"], true);
+}
+
+#[tokio::test]
+async fn new_admin_needs_a_valid_password() {
+    let app = TestApp::new().await;
+
+    assert!(admin::create_admin(&app.pool, "Ops", "ops@example.com", None).await.is_err());
+    assert!(admin::create_admin(&app.pool, "Ops", "ops@example.com", Some("short")).await.is_err());
+}
+
+#[tokio::test]
+async fn backup_copies_the_database() {
+    // In-memory databases back up into memory too, so this one needs a file
+    let dir = std::env::temp_dir().join(format!("pm-backup-{}", std::process::id()));
+    std::fs::create_dir_all(&dir).unwrap();
+    let database_config = |name: &str| DatabaseConfig {
+        url: format!("sqlite:{}?mode=rwc", dir.join(name).display()),
+        ..DatabaseConfig::default()
+    };
+    let pool = database::init_db(&database_config("live.sqlite")).await.unwrap();
+    database::migrate(&pool).await.unwrap();
+    admin::create_admin(&pool, "Ops", "ops@example.com", Some("admin-password")).await.unwrap();
+
+    let path = dir.join("backup.sqlite");
+    admin::backup(&pool, &path).await.unwrap();
+    let again = admin::backup(&pool, &path).await;
+    let backup = database::init_db(&database_config("backup.sqlite")).await.unwrap();
+    let admins: i64 = sqlx::query_scalar("SELECT COUNT(*) FROM users WHERE is_admin").fetch_one(&backup).await.unwrap();
+    pool.close().await;
+    backup.close().await;
+    std::fs::remove_dir_all(&dir).ok();
+
+    assert_eq!(admins, 1);
+    assert!(again.is_err(), "an existing file must not be overwritten");
+}
+
+#[tokio::test]
+async fn stats_are_for_admins_only() {
+    let app = TestApp::new().await;
+    let member = app.signup("member@example.com").await;
+
+    let response = app.get("/admin/stats", &member).await;
+    assert_eq!(response.status, StatusCode::FORBIDDEN);
+    assert_eq!(app.request(Method::GET, "/admin/stats", None, None).await.status, StatusCode::UNAUTHORIZED);
+}
+
+#[tokio::test]
+async fn stats_count_users_signups_and_rows() {
+    let app = TestApp::new().await;
+    let token = app.signup("ops@example.com").await;
+    app.signup("member@example.com").await;
+    admin::create_admin(&app.pool, "Ops", "ops@example.com", None).await.unwrap();
+    app.create_account(&token, "Checking", "BDT").await;
+
+    let response = app.get("/admin/stats", &token).await;
+    assert_eq!(response.status, StatusCode::OK, "{}", response.body);
+    let data = response.data();
+    assert_eq!(data["users"]["total"], 2);
+    assert_eq!(data["users"]["admins"], 1);
+    assert!(data["activeUsers"]["monthly"].is_i64());
+
+    let signups: i64 = data["signupsByDay"].as_array().unwrap().iter().map(|day| day["count"].as_i64().unwrap()).sum();
+    assert_eq!(signups, 2);
+
+    let accounts = data["tables"].as_array().unwrap().iter().find(|table| table["table"] == "accounts").unwrap();
+    assert_eq!(accounts["rows"], 1);
+    assert!(data["database"]["sizeBytes"].as_i64().unwrap() > 0);
+}
@@ -0,0 +1,34 @@
+mod common;
+
+use axum::http::{Method, StatusCode};
+use serde_json::json;
+
+use common::TestApp;
+
+#[tokio::test]
+async fn signin_validates_new_accounts_like_signup() {
+    let app = TestApp::new().await;
+
+    let response = app
+        .request(Method::POST, "/auth/signin", None, Some(json!({ "name": "New User", "email": "not-an-email", "password": "x" })))
+        .await;
+
+    assert_eq!(response.status, StatusCode::UNPROCESSABLE_ENTITY);
+    assert!(response.body["error"]["fields"]["email"].is_array());
+    assert!(response.body["error"]["fields"]["password"].is_array());
+    let created: i64 = sqlx::query_scalar("SELECT COUNT(*) FROM users").fetch_one(&app.pool).await.unwrap();
+    assert_eq!(created, 0);
+}
+
+#[tokio::test]
+async fn reset_password_enforces_the_minimum_length() {
+    let app = TestApp::new().await;
+
+    let response = app
+        .request(Method::POST, "/auth/reset-password", None, Some(json!({ "token": "", "password": "short" })))
+        .await;
+
+    assert_eq!(response.status, StatusCode::UNPROCESSABLE_ENTITY);
+    assert!(response.body["error"]["fields"]["password"].is_array());
+    assert!(response.body["error"]["fields"]["token"].is_array());
+}
@@ -0,0 +1,89 @@
+mod common;
+
+use std::sync::atomic::{AtomicU32, Ordering};
+
+use axum::http::StatusCode;
+use serde_json::json;
+
+use common::TestApp;
+use personal_manager_backend::config::CacheConfig;
+use personal_manager_backend::services::cache::Cache;
+
+/// Reads `name` through the cache, counting how often it had to be loaded.
+async fn read(cache: &Cache, user_id: &str, name: &str, loads: &AtomicU32) -> u32 {
+    cache
+        .cached(user_id, name, async { Ok(loads.fetch_add(1, Ordering::SeqCst) + 1) })
+        .await
+        .unwrap()
+}
+
+#[tokio::test]
+async fn reads_are_served_until_invalidated() {
+    let cache = Cache::in_memory(&CacheConfig::default());
+    let loads = AtomicU32::new(0);
+
+    assert_eq!(read(&cache, "alice", "dashboard", &loads).await, 1);
+    assert_eq!(read(&cache, "alice", "dashboard", &loads).await, 1);
+
+    cache.invalidate_user("bob").await;
+    assert_eq!(read(&cache, "alice", "dashboard", &loads).await, 1);
+
+    cache.invalidate_user("alice").await;
+    assert_eq!(read(&cache, "alice", "dashboard", &loads).await, 2);
+
+    cache.invalidate_all().await;
+    assert_eq!(read(&cache, "alice", "dashboard", &loads).await, 3);
+}
+
+#[tokio::test]
+async fn disabled_cache_always_loads() {
+    let cache = Cache::in_memory(&CacheConfig { enabled: false, ..CacheConfig::default() });
+    let loads = AtomicU32::new(0);
+
+    assert_eq!(read(&cache, "alice", "dashboard", &loads).await, 1);
+    assert_eq!(read(&cache, "alice", "dashboard", &loads).await, 2);
+}
+
+#[tokio::test]
+async fn full_cache_makes_room() {
+    let cache = Cache::in_memory(&CacheConfig { max_entries: 2, ..CacheConfig::default() });
+    let loads = AtomicU32::new(0);
+
+    read(&cache, "alice", "first", &loads).await;
+    read(&cache, "alice", "second", &loads).await;
+    read(&cache, "alice", "third", &loads).await;
+    assert_eq!(loads.load(Ordering::SeqCst), 3);
+
+    // The entry closest to expiring made room for the third
+    assert_eq!(read(&cache, "alice", "third", &loads).await, 3);
+    assert_eq!(read(&cache, "alice", "first", &loads).await, 4);
+}
+
+#[tokio::test]
+async fn dashboard_reflects_writes() {
+    let app = TestApp::new().await;
+    let token = app.signup("owner@example.com").await;
+
+    let before = app.get("/api/dashboard", &token).await;
+    assert_eq!(before.status, StatusCode::OK);
+    assert_eq!(before.data()["accountTotals"], json!([]));
+
+    app.create_account(&token, "Checking", "BDT").await;
+
+    let after = app.get("/api/dashboard", &token).await;
+    assert_eq!(after.data()["accountTotals"][0]["currency"], "BDT");
+    assert_eq!(after.data()["accountTotals"][0]["accountCount"], 1);
+}
+
+#[tokio::test]
+async fn preferences_reflect_updates() {
+    let app = TestApp::new().await;
+    let token = app.signup("owner@example.com").await;
+
+    assert_eq!(app.get("/api/preferences", &token).await.data()["displayCurrency"], "BDT");
+
+    let update = app.put("/api/preferences", &token, json!({ "displayCurrency": "USD" })).await;
+    assert_eq!(update.status, StatusCode::OK);
+
+    assert_eq!(app.get("/api/preferences", &token).await.data()["displayCurrency"], "USD");
+}
@@ -0,0 +1,183 @@
+mod common;
+
+use axum::http::StatusCode;
+use serde_json::{json, Value};
+
+use common::TestApp;
+
+fn names(categories: &Value) -> Vec<String> {
+    categories
+        .as_array()
+        .unwrap()
+        .iter()
+        .map(|category| category["name"].as_str().unwrap().to_string())
+        .collect()
+}
+
+#[tokio::test]
+async fn preset_packs_are_added_once() {
+    let app = TestApp::new().await;
+    let token = app.signup("student@example.com").await;
+
+    let packs = app.get("/api/categories/presets", &token).await;
+    assert_eq!(packs.status, StatusCode::OK);
+    let ids: Vec<_> = packs.data().as_array().unwrap().iter().map(|pack| pack["id"].clone()).collect();
+    assert_eq!(ids, vec![json!("student"), json!("family"), json!("freelancer")]);
+
+    let first = app.post("/api/categories/presets/student", &token, json!({})).await;
+    assert_eq!(first.status, StatusCode::CREATED, "{}", first.body);
+    let created = names(&first.data()["created"]);
+    assert!(created.contains(&"Tuition".to_string()));
+    assert!(first.data()["skipped"].as_array().unwrap().is_empty());
+
+    let again = app.post("/api/categories/presets/student", &token, json!({})).await;
+    assert_eq!(again.status, StatusCode::OK);
+    assert!(again.data()["created"].as_array().unwrap().is_empty());
+    assert_eq!(again.data()["skipped"], json!(created));
+
+    let missing = app.post("/api/categories/presets/retiree", &token, json!({})).await;
+    assert_eq!(missing.status, StatusCode::NOT_FOUND);
+}
+
+#[tokio::test]
+async fn exported_categories_import_into_another_account() {
+    let app = TestApp::new().await;
+    let owner = app.signup("owner@example.com").await;
+    let friend = app.signup("friend@example.com").await;
+    app.post("/api/categories/presets/freelancer", &owner, json!({})).await;
+
+    let export = app.get("/api/categories/export", &owner).await;
+    assert_eq!(export.status, StatusCode::OK);
+    assert_eq!(export.data()["format"], "personal-manager-categories");
+    let exported = names(&export.data()["categories"]);
+    assert!(exported.contains(&"Coworking".to_string()));
+
+    let imported = app.post("/api/categories/import", &friend, export.data().clone()).await;
+    assert_eq!(imported.status, StatusCode::CREATED, "{}", imported.body);
+    assert_eq!(names(&imported.data()["created"]), exported);
+
+    let categories = app.get("/api/categories", &friend).await;
+    assert!(names(&categories.data()["items"]).contains(&"Coworking".to_string()));
+
+    let invalid = app
+        .post("/api/categories/import", &friend, json!({ "categories": [{ "name": "", "categoryType": "expense", "icon": "x", "color": "#000" }] }))
+        .await;
+    assert_eq!(invalid.status, StatusCode::UNPROCESSABLE_ENTITY);
+}
+
+#[tokio::test]
+async fn archived_categories_leave_the_picker_but_keep_their_transactions() {
+    let app = TestApp::new().await;
+    let token = app.signup("owner@example.com").await;
+    let pack = app.post("/api/categories/presets/freelancer", &token, json!({})).await;
+    let coworking = pack.data()["created"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .find(|category| category["name"] == "Coworking")
+        .unwrap()["id"]
+        .as_str()
+        .unwrap()
+        .to_string();
+    let account = app.create_account(&token, "Wallet", "BDT").await;
+    let spent = app
+        .post("/transactions", &token, json!({ "account_id": account, "transaction_type": "expense", "amount": 1500, "category": "Coworking", "description": "Hub desk pass" }))
+        .await;
+    assert_eq!(spent.status, StatusCode::OK, "{}", spent.body);
+    let suggest = json!({ "description": "desk pass", "type": "expense" });
+    let suggested = app.post("/api/suggest-category", &token, suggest.clone()).await;
+    assert_eq!(suggested.data()[0]["category"], "Coworking");
+
+    let archived = app.post(&format!("/api/categories/{}/archive", coworking), &token, json!({})).await;
+    assert_eq!(archived.status, StatusCode::OK, "{}", archived.body);
+    assert_eq!(archived.data()["isArchived"], true);
+
+    let picker = app.get("/api/categories", &token).await;
+    assert!(!names(&picker.data()["items"]).contains(&"Coworking".to_string()));
+    let everything = app.get("/api/categories?includeArchived=true", &token).await;
+    assert!(names(&everything.data()["items"]).contains(&"Coworking".to_string()));
+    let transactions = app.get("/transactions", &token).await;
+    assert_eq!(transactions.data()["items"][0]["category"], "Coworking");
+    let suggested = app.post("/api/suggest-category", &token, suggest).await;
+    assert_eq!(suggested.data(), &json!([]));
+
+    let reapplied = app.post("/api/categories/presets/freelancer", &token, json!({})).await;
+    assert_eq!(reapplied.status, StatusCode::OK, "{}", reapplied.body);
+    assert_eq!(names(&reapplied.data()["unarchived"]), ["Coworking"]);
+    assert!(names(&app.get("/api/categories", &token).await.data()["items"]).contains(&"Coworking".to_string()));
+    app.post(&format!("/api/categories/{}/archive", coworking), &token, json!({})).await;
+
+    let restored = app.post(&format!("/api/categories/{}/unarchive", coworking), &token, json!({})).await;
+    assert_eq!(restored.data()["isArchived"], false);
+    assert!(names(&app.get("/api/categories", &token).await.data()["items"]).contains(&"Coworking".to_string()));
+
+    let stranger = app.signup("stranger@example.com").await;
+    let forbidden = app.post(&format!("/api/categories/{}/archive", coworking), &stranger, json!({})).await;
+    assert_eq!(forbidden.status, StatusCode::NOT_FOUND);
+}
+
+/// Adds a built-in category, as shared by every user.
+async fn built_in(app: &TestApp, name: &str) -> String {
+    let id = format!("built-in-{}", name.to_lowercase());
+    sqlx::query("INSERT INTO categories (id, name, category_type, icon, color, is_default, created_at, user_id) VALUES (?, ?, 'expense', 'x', '#000', FALSE, '2024-01-01 00:00:00', '')")
+        .bind(&id)
+        .bind(name)
+        .execute(&app.pool)
+        .await
+        .unwrap();
+    id
+}
+
+#[tokio::test]
+async fn built_in_categories_are_archived_per_user() {
+    let app = TestApp::new().await;
+    let owner = app.signup("owner@example.com").await;
+    let friend = app.signup("friend@example.com").await;
+    let groceries = built_in(&app, "Groceries").await;
+
+    let archived = app.post(&format!("/api/categories/{}/archive", groceries), &owner, json!({})).await;
+    assert_eq!(archived.status, StatusCode::OK, "{}", archived.body);
+    assert_eq!(archived.data()["isArchived"], true);
+    assert!(!names(&app.get("/api/categories", &owner).await.data()["items"]).contains(&"Groceries".to_string()));
+
+    let theirs = app.get("/api/categories?includeArchived=true", &friend).await;
+    let shared = theirs.data()["items"].as_array().unwrap().iter().find(|category| category["id"] == groceries.as_str()).unwrap();
+    assert_eq!(shared["isArchived"], false);
+    assert_eq!(app.get("/api/categories", &friend).await.data()["total"], 1);
+
+    let restored = app.post(&format!("/api/categories/{}/unarchive", groceries), &owner, json!({})).await;
+    assert_eq!(restored.data()["isArchived"], false);
+    assert!(names(&app.get("/api/categories", &owner).await.data()["items"]).contains(&"Groceries".to_string()));
+}
+
+#[tokio::test]
+async fn archive_marks_survive_a_backup() {
+    let app = TestApp::new().await;
+    let owner = app.signup("owner@example.com").await;
+    let groceries = built_in(&app, "Groceries").await;
+    let pack = app.post("/api/categories/presets/freelancer", &owner, json!({})).await;
+    let coworking = pack.data()["created"].as_array().unwrap().iter().find(|category| category["name"] == "Coworking").unwrap()["id"].clone();
+    app.post(&format!("/api/categories/{}/archive", groceries), &owner, json!({})).await;
+    app.post(&format!("/api/categories/{}/archive", coworking.as_str().unwrap()), &owner, json!({})).await;
+    let backup = app.get("/api/export/all", &owner).await.body;
+    assert_eq!(backup["tables"]["category_archives"].as_array().map(Vec::len), Some(2));
+
+    let copy = app.signup("copy@example.com").await;
+    let restored = app.post("/api/import/all", &copy, backup.clone()).await;
+    assert_eq!(restored.status, StatusCode::OK, "{}", restored.body);
+    let picker = names(&app.get("/api/categories", &copy).await.data()["items"]);
+    assert!(!picker.contains(&"Groceries".to_string()) && !picker.contains(&"Coworking".to_string()), "{:?}", picker);
+
+    // Older archives mark only the user's own categories, on the category row
+    let mut legacy = backup;
+    legacy["version"] = json!(2);
+    legacy["tables"].as_object_mut().unwrap().remove("category_archives");
+    for category in legacy["tables"]["categories"].as_array_mut().unwrap() {
+        category["is_archived"] = json!(category["id"] == coworking);
+    }
+    let old = app.signup("old@example.com").await;
+    let restored = app.post("/api/import/all", &old, legacy).await;
+    assert_eq!(restored.status, StatusCode::OK, "{}", restored.body);
+    let picker = names(&app.get("/api/categories", &old).await.data()["items"]);
+    assert!(picker.contains(&"Groceries".to_string()) && !picker.contains(&"Coworking".to_string()), "{:?}", picker);
+}
@@ -0,0 +1,95 @@
+//! Runs the whole router against a fresh in-memory database, so tests go through routing,
+//! extractors and middleware the way a client would.
+
+#![allow(dead_code)]
+
+use axum::{
+    body::Body,
+    http::{header, Method, Request, StatusCode},
+    Router,
+};
+use serde_json::Value;
+use tower::ServiceExt;
+
+use personal_manager_backend::{app, services::database::{self, DbPool}};
+
+pub const PASSWORD: &str = "password1";
+
+pub struct TestApp {
+    pub router: Router,
+    pub pool: DbPool,
+}
+
+/// A response with its body parsed as JSON, or `Value::Null` when there is none.
+pub struct TestResponse {
+    pub status: StatusCode,
+    pub body: Value,
+}
+
+impl TestResponse {
+    /// The envelope's `data`.
+    pub fn data(&self) -> &Value {
+        &self.body["data"]
+    }
+}
+
+impl TestApp {
+    /// A migrated, empty database with no background jobs running.
+    pub async fn new() -> Self {
+        let pool = database::init_memory_db().await.expect("open in-memory database");
+        database::migrate(&pool).await.expect("migrate in-memory database");
+        Self { router: app::router(pool.clone()), pool }
+    }
+
+    pub async fn request(&self, method: Method, path: &str, token: Option<&str>, body: Option<Value>) -> TestResponse {
+        let mut request = Request::builder().method(method).uri(path);
+        if let Some(token) = token {
+            request = request.header(header::AUTHORIZATION, format!("Bearer {}", token));
+        }
+        let request = match body {
+            Some(body) => request
+                .header(header::CONTENT_TYPE, "application/json")
+                .body(Body::from(body.to_string())),
+            None => request.body(Body::empty()),
+        }
+        .expect("build request");
+
+        let response = self.router.clone().oneshot(request).await.expect("router never fails");
+        let status = response.status();
+        let bytes = hyper::body::to_bytes(response.into_body()).await.expect("read response body");
+        let body = serde_json::from_slice(&bytes).unwrap_or(Value::Null);
+        TestResponse { status, body }
+    }
+
+    pub async fn get(&self, path: &str, token: &str) -> TestResponse {
+        self.request(Method::GET, path, Some(token), None).await
+    }
+
+    pub async fn post(&self, path: &str, token: &str, body: Value) -> TestResponse {
+        self.request(Method::POST, path, Some(token), Some(body)).await
+    }
+
+    pub async fn put(&self, path: &str, token: &str, body: Value) -> TestResponse {
+        self.request(Method::PUT, path, Some(token), Some(body)).await
+    }
+
+    pub async fn delete(&self, path: &str, token: &str) -> TestResponse {
+        self.request(Method::DELETE, path, Some(token), None).await
+    }
+
+    /// Signs up a user with `email` and [`PASSWORD`], returning their token.
+    pub async fn signup(&self, email: &str) -> String {
+        let body = serde_json::json!({ "name": "Test User", "email": email, "password": PASSWORD });
+        let response = self.request(Method::POST, "/auth/signup", None, Some(body)).await;
+        assert_eq!(response.status, StatusCode::OK, "signup failed: {}", response.body);
+        response.data()["token"].as_str().expect("signup returns a token").to_string()
+    }
+
+    /// Creates an account for the token's user, returning its id.
+    pub async fn create_account(&self, token: &str, name: &str, currency: &str) -> String {
+        let body = serde_json::json!({ "name": name, "account_type": "bank", "balance": 0, "currency": currency });
+        let response = self.post("/accounts", token, body).await;
+        assert_eq!(response.status, StatusCode::OK, "account create failed: {}", response.body);
+        response.data()["id"].as_str().expect("account has an id").to_string()
+    }
+}
@@ -0,0 +1,86 @@
+use std::path::{Path, PathBuf};
+
+use personal_manager_backend::config::{self, Config};
+
+fn write_config(name: &str, text: &str) -> PathBuf {
+    let path = std::env::temp_dir().join(format!("pm-config-{}-{}.toml", name, std::process::id()));
+    std::fs::write(&path, text).expect("write config file");
+    path
+}
+
+#[test]
+fn defaults_are_valid() {
+    assert_eq!(Config::default().problems(), Vec::<String>::new());
+}
+
+#[test]
+fn example_file_is_valid() {
+    let config = Config::from_file(Path::new("config.example.toml")).expect("example parses");
+
+    assert_eq!(config.problems(), Vec::<String>::new());
+}
+
+#[test]
+fn file_overrides_only_what_it_sets() {
+    let path = write_config("partial", "[server]\nport = 8080\n\n[features]\nemail = false\n");
+
+    let config = Config::from_file(&path).expect("config parses");
+    std::fs::remove_file(&path).ok();
+
+    assert_eq!(config.server.port, 8080);
+    assert_eq!(config.server.host, "0.0.0.0");
+    assert!(!config.features.enabled(config.features.email));
+    assert!(config.features.enabled(config.features.push));
+}
+
+#[test]
+fn unknown_keys_are_rejected() {
+    let path = write_config("unknown", "[server]\nprot = 8080\n");
+
+    let error = Config::from_file(&path).unwrap_err().to_string();
+    std::fs::remove_file(&path).ok();
+
+    assert!(error.contains("prot"), "{}", error);
+}
+
+#[test]
+fn problems_are_all_listed() {
+    let mut config = Config::default();
+    config.cors.allowed_origins = vec!["example.com".to_string()];
+    config.log.level = "loud".to_string();
+    config.log.format = "xml".to_string();
+    config.mail.smtp_host = Some("smtp.example.com".to_string());
+
+    let problems = config.problems();
+
+    assert_eq!(problems.len(), 4, "{:?}", problems);
+    assert!(problems.iter().any(|problem| problem.contains("cors.allowed_origins")));
+    assert!(problems.iter().any(|problem| problem.contains("log.level")));
+    assert!(problems.iter().any(|problem| problem.contains("log.format")));
+    assert!(problems.iter().any(|problem| problem.contains("mail.smtp_from")));
+}
+
+#[test]
+fn log_level_takes_per_module_filters() {
+    let mut config = Config::default();
+    config.log.level = "info,sqlx=warn".to_string();
+
+    assert_eq!(config.problems(), Vec::<String>::new());
+}
+
+#[test]
+fn reload_applies_only_reloadable_sections() {
+    let mut loaded = Config::default();
+    loaded.quotas.requests_per_minute = 5;
+    loaded.features.email = false;
+    loaded.server.port = 8080;
+
+    let reloaded = config::apply(loaded);
+
+    assert_eq!(reloaded.applied, vec!["quotas", "features"]);
+    assert_eq!(reloaded.needs_restart, vec!["server"]);
+    let current = config::current();
+    assert_eq!(current.quotas.requests_per_minute, 5);
+    assert!(!current.features.enabled(current.features.email));
+    assert_eq!(current.server.port, config::get().server.port);
+}
@@ -0,0 +1,46 @@
+use std::time::{Duration, Instant};
+
+use personal_manager_backend::config::DatabaseConfig;
+use personal_manager_backend::services::database;
+
+#[tokio::test]
+async fn unreachable_database_is_retried_then_reported() {
+    let config = DatabaseConfig {
+        url: "sqlite:/nonexistent-directory/personal_manager.db".to_string(),
+        connect_attempts: 2,
+        ..DatabaseConfig::default()
+    };
+
+    let started = Instant::now();
+    assert!(database::init_db(&config).await.is_err());
+    assert!(started.elapsed() >= Duration::from_secs(1), "gave up without waiting to retry");
+}
+
+#[tokio::test]
+async fn malformed_url_fails_without_retrying() {
+    let config = DatabaseConfig {
+        url: "sqlite:/tmp/personal_manager.db?mode=sideways".to_string(),
+        connect_attempts: 5,
+        ..DatabaseConfig::default()
+    };
+
+    let started = Instant::now();
+    assert!(database::init_db(&config).await.is_err());
+    assert!(started.elapsed() < Duration::from_secs(1));
+}
+
+#[tokio::test]
+async fn file_database_opens_with_connections_checked() {
+    let dir = std::env::temp_dir().join(format!("pm-database-{}", std::process::id()));
+    std::fs::create_dir_all(&dir).unwrap();
+    let config = DatabaseConfig {
+        url: format!("sqlite:{}", dir.join("db.sqlite").display()),
+        ..DatabaseConfig::default()
+    };
+
+    let pool = database::init_db(&config).await.unwrap();
+    let one: i64 = sqlx::query_scalar("SELECT 1").fetch_one(&pool).await.unwrap();
+    assert_eq!(one, 1);
+    pool.close().await;
+    std::fs::remove_dir_all(&dir).unwrap();
+}
@@ -0,0 +1,64 @@
+mod common;
+
+use axum::http::StatusCode;
+use serde_json::json;
+
+use common::TestApp;
+
+/// One transaction through the API, then enough copies to span several pages of a stream.
+async fn many_transactions(app: &TestApp, token: &str) -> usize {
+    let account = app.create_account(token, "Wallet", "BDT").await;
+    let response = app
+        .post("/transactions", token, json!({ "account_id": account, "transaction_type": "expense", "amount": 3, "category": "Food" }))
+        .await;
+    assert_eq!(response.status, StatusCode::OK, "{}", response.body);
+
+    sqlx::query(
+        r#"
+        WITH RECURSIVE n(i) AS (SELECT 1 UNION ALL SELECT i + 1 FROM n WHERE i < 1200)
+        INSERT INTO transactions (id, user_id, account_id, transaction_type, amount, currency, category, description, date, created_at)
+        SELECT lower(hex(randomblob(16))), t.user_id, t.account_id, t.transaction_type, t.amount, t.currency, t.category,
+               t.description, t.date, t.created_at
+        FROM transactions t, n
+        "#,
+    )
+    .execute(&app.pool)
+    .await
+    .expect("copy transactions");
+    1201
+}
+
+#[tokio::test]
+async fn archive_export_streams_every_row() {
+    let app = TestApp::new().await;
+    let token = app.signup("owner@example.com").await;
+    let count = many_transactions(&app, &token).await;
+
+    let response = app.get("/api/export/all", &token).await;
+
+    assert_eq!(response.status, StatusCode::OK);
+    assert_eq!(response.body["format"], "personal-manager-archive");
+    assert_eq!(response.body["user"]["email"], "owner@example.com");
+    assert_eq!(response.body["tables"]["transactions"].as_array().map(Vec::len), Some(count));
+    assert_eq!(response.body["tables"]["accounts"].as_array().map(Vec::len), Some(1));
+    assert!(response.body["tables"]["transactions"][0].get("archive_rowid").is_none());
+}
+
+#[tokio::test]
+async fn sync_pull_streams_every_transaction() {
+    let app = TestApp::new().await;
+    let token = app.signup("owner@example.com").await;
+    let count = many_transactions(&app, &token).await;
+
+    let response = app.get("/api/sync/changes", &token).await;
+
+    assert_eq!(response.status, StatusCode::OK);
+    assert_eq!(response.body["success"], true);
+    assert_eq!(response.data()["accounts"].as_array().map(Vec::len), Some(1));
+    let transactions = response.data()["transactions"].as_array().expect("transactions");
+    assert_eq!(transactions.len(), count);
+    let mut ids: Vec<&str> = transactions.iter().filter_map(|t| t["id"].as_str()).collect();
+    ids.sort_unstable();
+    ids.dedup();
+    assert_eq!(ids.len(), count);
+}
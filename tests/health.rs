@@ -0,0 +1,41 @@
+mod common;
+
+use axum::http::{Method, StatusCode};
+
+use common::TestApp;
+
+#[tokio::test]
+async fn ready_once_migrated() {
+    let app = TestApp::new().await;
+
+    let response = app.request(Method::GET, "/health/ready", None, None).await;
+
+    assert_eq!(response.status, StatusCode::OK, "{}", response.body);
+    assert_eq!(response.data()["migrations"]["pending"].as_array().map(Vec::len), Some(0));
+}
+
+#[tokio::test]
+async fn api_needs_a_token() {
+    let app = TestApp::new().await;
+
+    let response = app.request(Method::GET, "/accounts", None, None).await;
+
+    assert_eq!(response.status, StatusCode::UNAUTHORIZED);
+    assert_eq!(response.body["success"], false);
+}
+
+#[tokio::test]
+async fn version_reports_build_and_migration_level() {
+    let app = TestApp::new().await;
+
+    let response = app.request(Method::GET, "/version", None, None).await;
+
+    assert_eq!(response.status, StatusCode::OK, "{}", response.body);
+    let data = response.data();
+    assert_eq!(data["version"], env!("CARGO_PKG_VERSION"));
+    assert!(data["major"].is_u64());
+    assert!(data["builtAt"].is_string());
+    assert!(data["features"]["enabled"].is_array());
+    assert_eq!(data["migrations"]["applied"], data["migrations"]["latest"]);
+    assert_eq!(data["migrations"]["pending"].as_array().map(Vec::len), Some(0));
+}
@@ -0,0 +1,77 @@
+mod common;
+
+use std::sync::Arc;
+use std::time::Duration;
+
+use axum::{
+    body::Body,
+    http::{header, Method, Request, StatusCode},
+    routing::post,
+    Router,
+};
+use serde_json::json;
+use tokio::sync::{mpsc, Notify};
+use tower::ServiceExt;
+
+use common::TestApp;
+use personal_manager_backend::config;
+use personal_manager_backend::middleware::load_shed::LoadShedLayer;
+
+#[tokio::test]
+async fn oversized_body_is_rejected() {
+    let app = TestApp::new().await;
+    let token = app.signup("owner@example.com").await;
+    let padding = "x".repeat(config::get().limits.max_body_bytes);
+
+    let response = app
+        .post("/accounts", &token, json!({ "name": "Checking", "account_type": "bank", "currency": "BDT", "description": padding }))
+        .await;
+
+    assert_eq!(response.status, StatusCode::PAYLOAD_TOO_LARGE, "{}", response.body);
+    assert_eq!(response.body["success"], false);
+}
+
+#[tokio::test]
+async fn archive_import_takes_larger_bodies() {
+    let app = TestApp::new().await;
+    let token = app.signup("owner@example.com").await;
+    let padding = "x".repeat(config::get().limits.max_body_bytes);
+
+    let response = app
+        .post("/api/import/all?validateOnly=true", &token, json!({ "format": "not-an-archive", "padding": padding }))
+        .await;
+
+    assert_ne!(response.status, StatusCode::PAYLOAD_TOO_LARGE, "{}", response.body);
+}
+
+#[tokio::test]
+async fn writes_over_the_cap_are_shed_with_retry_after() {
+    let (entered, mut inside) = mpsc::channel::<()>(1);
+    let release = Arc::new(Notify::new());
+    let held = release.clone();
+    let router = Router::new()
+        .route(
+            "/slow",
+            post(move || async move {
+                entered.send(()).await.ok();
+                held.notified().await;
+                "done"
+            })
+            .get(|| async { "read" }),
+        )
+        .layer(LoadShedLayer::new(4, 1, Duration::from_millis(50)));
+    let request = |method: Method| Request::builder().method(method).uri("/slow").body(Body::empty()).unwrap();
+
+    let first = tokio::spawn(router.clone().oneshot(request(Method::POST)));
+    inside.recv().await.unwrap();
+
+    let shed = router.clone().oneshot(request(Method::POST)).await.unwrap();
+    assert_eq!(shed.status(), StatusCode::SERVICE_UNAVAILABLE);
+    assert!(shed.headers().contains_key(header::RETRY_AFTER));
+
+    // Reads don't wait on the writer
+    assert_eq!(router.clone().oneshot(request(Method::GET)).await.unwrap().status(), StatusCode::OK);
+
+    release.notify_one();
+    assert_eq!(first.await.unwrap().unwrap().status(), StatusCode::OK);
+}
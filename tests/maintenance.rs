@@ -0,0 +1,52 @@
+mod common;
+
+use chrono::{Duration, Utc};
+
+use common::TestApp;
+use personal_manager_backend::services::maintenance;
+use personal_manager_backend::utils::datetime::format_db_datetime;
+
+async fn insert_token(app: &TestApp, token: &str, user_id: &str, expires_in: Duration, used: bool) {
+    let now = Utc::now();
+    sqlx::query("INSERT INTO email_tokens (token, user_id, purpose, expires_at, used_at, created_at) VALUES (?, ?, 'verify_email', ?, ?, ?)")
+        .bind(token)
+        .bind(user_id)
+        .bind(format_db_datetime(now + expires_in))
+        .bind(used.then(|| format_db_datetime(now)))
+        .bind(format_db_datetime(now))
+        .execute(&app.pool)
+        .await
+        .unwrap();
+}
+
+#[tokio::test]
+async fn prune_keeps_only_usable_tokens() {
+    let app = TestApp::new().await;
+    app.signup("owner@example.com").await;
+    let user_id: String = sqlx::query_scalar("SELECT id FROM users WHERE email = 'owner@example.com'")
+        .fetch_one(&app.pool)
+        .await
+        .unwrap();
+    sqlx::query("DELETE FROM email_tokens").execute(&app.pool).await.unwrap();
+
+    insert_token(&app, "usable", &user_id, Duration::hours(1), false).await;
+    insert_token(&app, "expired", &user_id, Duration::hours(-1), false).await;
+    insert_token(&app, "used", &user_id, Duration::hours(1), true).await;
+
+    assert_eq!(maintenance::prune(&app.pool).await.unwrap(), 2);
+    let left: Vec<String> = sqlx::query_scalar("SELECT token FROM email_tokens").fetch_all(&app.pool).await.unwrap();
+    assert_eq!(left, vec!["usable".to_string()]);
+}
+
+#[tokio::test]
+async fn vacuum_switches_to_incremental_mode_once() {
+    let app = TestApp::new().await;
+
+    assert!(maintenance::vacuum(&app.pool).await.unwrap());
+    let mode: i64 = sqlx::query_scalar("PRAGMA auto_vacuum").fetch_one(&app.pool).await.unwrap();
+    assert_eq!(mode, 2);
+
+    // Nothing to reclaim in a fresh database
+    assert!(!maintenance::vacuum(&app.pool).await.unwrap());
+    maintenance::optimize(&app.pool).await.unwrap();
+}
@@ -0,0 +1,73 @@
+mod common;
+
+use axum::http::{Method, StatusCode};
+use serde_json::json;
+
+use common::TestApp;
+
+async fn create_loan(app: &TestApp, token: &str) -> String {
+    let response = app
+        .post("/loans", token, json!({ "person_name": "Rahim", "amount": 500, "loan_date": "2024-01-15T00:00:00Z" }))
+        .await;
+    assert_eq!(response.status, StatusCode::OK, "{}", response.body);
+    response.data()["id"].as_str().expect("loan id").to_string()
+}
+
+#[tokio::test]
+async fn owner_gets_their_loan() {
+    let app = TestApp::new().await;
+    let token = app.signup("owner@example.com").await;
+    let id = create_loan(&app, &token).await;
+
+    let response = app.get(&format!("/loans/{}", id), &token).await;
+    assert_eq!(response.status, StatusCode::OK);
+    assert_eq!(response.data()["personName"], "Rahim");
+}
+
+#[tokio::test]
+async fn someone_elses_loan_looks_missing() {
+    let app = TestApp::new().await;
+    let owner = app.signup("owner@example.com").await;
+    let other = app.signup("other@example.com").await;
+    let id = create_loan(&app, &owner).await;
+
+    let response = app.get(&format!("/loans/{}", id), &other).await;
+    assert_eq!(response.status, StatusCode::NOT_FOUND);
+    assert_eq!(response.body["error"]["message"], "Loan not found");
+
+    let missing = app.get("/loans/no-such-loan", &owner).await;
+    assert_eq!(missing.status, StatusCode::NOT_FOUND);
+}
+
+#[tokio::test]
+async fn owned_lookup_still_needs_a_token() {
+    let app = TestApp::new().await;
+    let owner = app.signup("owner@example.com").await;
+    let id = create_loan(&app, &owner).await;
+
+    let response = app.request(Method::GET, &format!("/loans/{}", id), None, None).await;
+    assert_eq!(response.status, StatusCode::UNAUTHORIZED);
+}
+
+#[tokio::test]
+async fn loan_lists_are_paged_by_the_database() {
+    let app = TestApp::new().await;
+    let owner = app.signup("owner@example.com").await;
+    let other = app.signup("other@example.com").await;
+    for _ in 0..3 {
+        create_loan(&app, &owner).await;
+    }
+    create_loan(&app, &other).await;
+
+    let first = app.get("/loans?limit=2", &owner).await;
+    assert_eq!(first.status, StatusCode::OK, "{}", first.body);
+    assert_eq!(first.data()["items"].as_array().unwrap().len(), 2);
+    assert_eq!(first.data()["total"], 3);
+    assert_eq!(first.data()["hasMore"], true);
+
+    let cursor = first.data()["nextCursor"].as_str().unwrap();
+    let rest = app.get(&format!("/loans?limit=2&cursor={}", cursor), &owner).await;
+    assert_eq!(rest.data()["items"].as_array().unwrap().len(), 1);
+    assert_eq!(rest.data()["hasMore"], false);
+    assert!(rest.data()["nextCursor"].is_null());
+}
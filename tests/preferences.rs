@@ -0,0 +1,102 @@
+mod common;
+
+use axum::http::StatusCode;
+use serde_json::json;
+
+use common::TestApp;
+
+#[tokio::test]
+async fn updates_are_partial_and_merged_with_defaults() {
+    let app = TestApp::new().await;
+    let token = app.signup("owner@example.com").await;
+
+    let response = app
+        .put(
+            "/api/preferences",
+            &token,
+            json!({ "displayCurrency": "USD", "client": { "theme": "dark", "home": { "cards": ["budgets"] } } }),
+        )
+        .await;
+    assert_eq!(response.status, StatusCode::OK, "{}", response.body);
+
+    let response = app
+        .put("/api/preferences", &token, json!({ "session_idle_timeout_minutes": 30, "client": { "theme": null, "compact": true } }))
+        .await;
+    assert_eq!(response.status, StatusCode::OK, "{}", response.body);
+
+    let preferences = app.get("/api/preferences", &token).await;
+    let data = preferences.data();
+    assert_eq!(data["displayCurrency"], "USD");
+    assert_eq!(data["sessionIdleTimeoutMinutes"], 30);
+    assert_eq!(data["timezone"], "UTC");
+    assert_eq!(data["creditUtilizationThresholds"], json!([30, 80]));
+    assert_eq!(data["client"], json!({ "home": { "cards": ["budgets"] }, "compact": true }));
+    assert!(data["updatedAt"].is_string());
+}
+
+#[tokio::test]
+async fn null_restores_the_default() {
+    let app = TestApp::new().await;
+    let token = app.signup("owner@example.com").await;
+    app.put("/api/preferences", &token, json!({ "displayCurrency": "EUR", "creditUtilizationThresholds": [90, 50, 90] }))
+        .await;
+    assert_eq!(app.get("/api/preferences", &token).await.data()["creditUtilizationThresholds"], json!([50, 90]));
+
+    let response = app
+        .put("/api/preferences", &token, json!({ "displayCurrency": null, "creditUtilizationThresholds": null }))
+        .await;
+
+    assert_eq!(response.status, StatusCode::OK, "{}", response.body);
+    assert_eq!(response.data()["displayCurrency"], "BDT");
+    assert_eq!(response.data()["creditUtilizationThresholds"], json!([30, 80]));
+}
+
+#[tokio::test]
+async fn unknown_or_invalid_settings_change_nothing() {
+    let app = TestApp::new().await;
+    let token = app.signup("owner@example.com").await;
+
+    let unknown = app.put("/api/preferences", &token, json!({ "displayCurrency": "USD", "theme": "dark" })).await;
+    assert_eq!(unknown.status, StatusCode::BAD_REQUEST);
+    let invalid = app.put("/api/preferences", &token, json!({ "locale": "en-US", "client": ["dark"] })).await;
+    assert_eq!(invalid.status, StatusCode::BAD_REQUEST);
+
+    let preferences = app.get("/api/preferences", &token).await;
+    assert_eq!(preferences.data()["displayCurrency"], "BDT");
+    assert!(preferences.data()["updatedAt"].is_null());
+}
+
+#[tokio::test]
+async fn updates_based_on_an_old_revision_are_refused() {
+    let app = TestApp::new().await;
+    let token = app.signup("owner@example.com").await;
+    assert_eq!(app.get("/api/preferences", &token).await.data()["revision"], 0);
+
+    let phone = app.put("/api/preferences", &token, json!({ "revision": 0, "locale": "bn-BD" })).await;
+    assert_eq!(phone.status, StatusCode::OK, "{}", phone.body);
+    assert_eq!(phone.data()["revision"], 1);
+
+    let tablet = app.put("/api/preferences", &token, json!({ "revision": 0, "locale": "en-GB" })).await;
+    assert_eq!(tablet.status, StatusCode::CONFLICT);
+    assert_eq!(tablet.data()["locale"], "bn-BD");
+    assert_eq!(tablet.data()["revision"], 1);
+
+    let retried = app.put("/api/preferences", &token, json!({ "revision": 1, "locale": "en-GB" })).await;
+    assert_eq!(retried.data()["revision"], 2);
+    assert_eq!(retried.data()["locale"], "en-GB");
+}
+
+#[tokio::test]
+async fn sync_pulls_carry_preferences_only_when_changed() {
+    let app = TestApp::new().await;
+    let token = app.signup("owner@example.com").await;
+    app.put("/api/preferences", &token, json!({ "timezone": "Asia/Dhaka" })).await;
+
+    let first = app.get("/api/sync/changes", &token).await;
+    assert_eq!(first.data()["preferences"]["timezone"], "Asia/Dhaka");
+    assert_eq!(first.data()["preferences"]["revision"], 1);
+
+    let later = app.get("/api/sync/changes?since=2999-01-01T00:00:00Z", &token).await;
+    assert_eq!(later.status, StatusCode::OK, "{}", later.body);
+    assert!(later.data()["preferences"].is_null());
+}
@@ -0,0 +1,108 @@
+mod common;
+
+use std::io::Cursor;
+
+use axum::{
+    body::Body,
+    http::{header, Method, Request, StatusCode},
+};
+use serde_json::json;
+use tower::ServiceExt;
+
+use common::{TestApp, PASSWORD};
+
+#[tokio::test]
+async fn profile_is_read_and_updated() {
+    let app = TestApp::new().await;
+    let token = app.signup("owner@example.com").await;
+
+    let profile = app.get("/api/me", &token).await;
+    assert_eq!(profile.status, StatusCode::OK, "{}", profile.body);
+    assert_eq!(profile.data()["email"], "owner@example.com");
+
+    let updated = app.put("/api/me", &token, json!({ "name": "  Renamed  " })).await;
+    assert_eq!(updated.status, StatusCode::OK, "{}", updated.body);
+    assert_eq!(updated.data()["name"], "Renamed");
+    assert_eq!(updated.data()["email"], "owner@example.com");
+
+    let moved = app.put("/api/me", &token, json!({ "email": " New@Example.com ", "currentPassword": PASSWORD })).await;
+    assert_eq!(moved.status, StatusCode::OK, "{}", moved.body);
+    assert_eq!(moved.data()["email"], "new@example.com");
+    assert_eq!(moved.data()["emailVerified"], false);
+    assert_eq!(app.get("/api/me", &token).await.data()["name"], "Renamed");
+}
+
+#[tokio::test]
+async fn profile_rejects_taken_or_invalid_email() {
+    let app = TestApp::new().await;
+    let token = app.signup("owner@example.com").await;
+    app.signup("other@example.com").await;
+
+    let taken = app.put("/api/me", &token, json!({ "email": "Other@Example.com", "currentPassword": PASSWORD })).await;
+    assert_eq!(taken.status, StatusCode::CONFLICT);
+
+    let unconfirmed = app.put("/api/me", &token, json!({ "email": "new@example.com" })).await;
+    assert_eq!(unconfirmed.status, StatusCode::UNPROCESSABLE_ENTITY);
+    assert!(unconfirmed.body["error"]["fields"]["currentPassword"].is_array());
+    let wrong = app.put("/api/me", &token, json!({ "email": "new@example.com", "currentPassword": "not-my-password" })).await;
+    assert_eq!(wrong.status, StatusCode::FORBIDDEN);
+    let unchanged = app.put("/api/me", &token, json!({ "email": "OWNER@example.com" })).await;
+    assert_eq!(unchanged.status, StatusCode::OK, "{}", unchanged.body);
+
+    let invalid = app.put("/api/me", &token, json!({ "email": "nope", "name": "" })).await;
+    assert_eq!(invalid.status, StatusCode::UNPROCESSABLE_ENTITY);
+    assert_eq!(app.get("/api/me", &token).await.data()["email"], "owner@example.com");
+}
+
+fn multipart(field: &str, bytes: &[u8]) -> Request<Body> {
+    let mut body = format!(
+        "--{b}\r\nContent-Disposition: form-data; name=\"{field}\"; filename=\"me.png\"\r\nContent-Type: image/png\r\n\r\n",
+        b = BOUNDARY,
+        field = field
+    )
+    .into_bytes();
+    body.extend_from_slice(bytes);
+    body.extend_from_slice(format!("\r\n--{}--\r\n", BOUNDARY).as_bytes());
+    Request::builder()
+        .method(Method::POST)
+        .uri("/api/me/avatar")
+        .header(header::CONTENT_TYPE, format!("multipart/form-data; boundary={}", BOUNDARY))
+        .body(Body::from(body))
+        .unwrap()
+}
+
+const BOUNDARY: &str = "avatar-boundary";
+
+#[tokio::test]
+async fn avatar_is_resized_and_served() {
+    let app = TestApp::new().await;
+    let token = app.signup("owner@example.com").await;
+    let mut png = Vec::new();
+    image::DynamicImage::new_rgb8(640, 480)
+        .write_to(&mut Cursor::new(&mut png), image::ImageOutputFormat::Png)
+        .unwrap();
+
+    let mut request = multipart("avatar", &png);
+    request.headers_mut().insert(header::AUTHORIZATION, format!("Bearer {}", token).parse().unwrap());
+    let response = app.router.clone().oneshot(request).await.unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+
+    let profile = app.get("/api/me", &token).await;
+    let url = profile.data()["avatarUrl"].as_str().expect("avatar url").to_string();
+    let served = app
+        .router
+        .clone()
+        .oneshot(Request::builder().uri(&url).body(Body::empty()).unwrap())
+        .await
+        .unwrap();
+    assert_eq!(served.status(), StatusCode::OK);
+    let bytes = hyper::body::to_bytes(served.into_body()).await.unwrap();
+    let avatar = image::load_from_memory(&bytes).unwrap();
+    assert_eq!((avatar.width(), avatar.height()), (256, 256));
+
+    let mut request = multipart("avatar", b"not an image");
+    request.headers_mut().insert(header::AUTHORIZATION, format!("Bearer {}", token).parse().unwrap());
+    let rejected = app.router.clone().oneshot(request).await.unwrap();
+    assert_eq!(rejected.status(), StatusCode::UNPROCESSABLE_ENTITY);
+    assert_eq!(app.get("/api/me", &token).await.data()["avatarUrl"], url.as_str());
+}
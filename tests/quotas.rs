@@ -0,0 +1,68 @@
+mod common;
+
+use axum::http::StatusCode;
+use serde_json::json;
+
+use common::TestApp;
+use personal_manager_backend::models::UpdateUserQuotaRequest;
+use personal_manager_backend::services::{admin, quotas};
+
+async fn user_id(app: &TestApp, email: &str) -> String {
+    admin::user_id_by_email(&app.pool, email).await.unwrap()
+}
+
+#[tokio::test]
+async fn quota_status_starts_at_the_defaults() {
+    let app = TestApp::new().await;
+    let token = app.signup("owner@example.com").await;
+
+    let response = app.get("/api/me/quota", &token).await;
+    assert_eq!(response.status, StatusCode::OK, "{}", response.body);
+    let data = response.data();
+    assert_eq!(data["requestsPerMinute"]["limit"], 600);
+    assert_eq!(data["requestsPerMinute"]["used"], 1);
+    assert_eq!(data["syncBatchSize"], 500);
+    assert_eq!(data["dailyUploadBytes"]["used"], 0);
+}
+
+#[tokio::test]
+async fn requests_over_the_per_minute_quota_are_refused() {
+    let app = TestApp::new().await;
+    let token = app.signup("owner@example.com").await;
+    let id = user_id(&app, "owner@example.com").await;
+    let request = UpdateUserQuotaRequest { requests_per_minute: Some(2), ..Default::default() };
+    quotas::set_for_user(&app.pool, &id, &request).await.unwrap();
+
+    assert_eq!(app.get("/accounts", &token).await.status, StatusCode::OK);
+    assert_eq!(app.get("/accounts", &token).await.status, StatusCode::OK);
+    let refused = app.get("/accounts", &token).await;
+    assert_eq!(refused.status, StatusCode::TOO_MANY_REQUESTS);
+    assert_eq!(refused.body["error"]["code"], "quota_exceeded");
+    assert_eq!(refused.data()["quota"], "requestsPerMinute");
+
+    // Someone else isn't held to it
+    let other = app.signup("other@example.com").await;
+    assert_eq!(app.get("/accounts", &other).await.status, StatusCode::OK);
+}
+
+#[tokio::test]
+async fn admin_override_limits_the_sync_batch() {
+    let app = TestApp::new().await;
+    let admin_token = app.signup("ops@example.com").await;
+    admin::create_admin(&app.pool, "Ops", "ops@example.com", None).await.unwrap();
+    let token = app.signup("owner@example.com").await;
+    let id = user_id(&app, "owner@example.com").await;
+
+    let forbidden = app.put(&format!("/admin/users/{}/quota", id), &token, json!({ "sync_batch_size": 1 })).await;
+    assert_eq!(forbidden.status, StatusCode::FORBIDDEN);
+
+    let updated = app.put(&format!("/admin/users/{}/quota", id), &admin_token, json!({ "sync_batch_size": 1 })).await;
+    assert_eq!(updated.status, StatusCode::OK, "{}", updated.body);
+    assert_eq!(updated.data()["syncBatchSize"], 1);
+    assert_eq!(updated.data()["requestsPerMinute"], 600);
+
+    let change = |n: u32| json!({ "entity": "account", "operation": "delete", "id": format!("missing-{}", n) });
+    let response = app.post("/api/sync", &token, json!({ "changes": [change(1), change(2)] })).await;
+    assert_eq!(response.status, StatusCode::PAYLOAD_TOO_LARGE);
+    assert_eq!(app.post("/api/sync", &token, json!({ "changes": [change(1)] })).await.status, StatusCode::OK);
+}
@@ -0,0 +1,91 @@
+mod common;
+
+use axum::http::StatusCode;
+use serde_json::{json, Value};
+
+use common::TestApp;
+
+async fn add_transaction(app: &TestApp, token: &str, account: &str, kind: &str, amount: i64, date: &str) -> String {
+    let response = app
+        .post("/transactions", token, json!({ "account_id": account, "transaction_type": kind, "amount": amount, "date": date }))
+        .await;
+    assert_eq!(response.status, StatusCode::OK, "{}", response.body);
+    response.data()["id"].as_str().unwrap().to_string()
+}
+
+fn ids(transactions: &Value) -> Vec<&str> {
+    transactions.as_array().unwrap().iter().map(|t| t["id"].as_str().unwrap()).collect()
+}
+
+#[tokio::test]
+async fn clearing_the_statement_transactions_explains_the_difference() {
+    let app = TestApp::new().await;
+    let token = app.signup("owner@example.com").await;
+    // The balance clients keep after the four transactions below
+    let account = app
+        .post("/accounts", &token, json!({ "name": "City Bank", "account_type": "bank", "balance": 30_100, "currency": "BDT" }))
+        .await
+        .data()["id"]
+        .as_str()
+        .unwrap()
+        .to_string();
+    let salary = add_transaction(&app, &token, &account, "income", 50_000, "2026-01-01T09:00:00Z").await;
+    let rent = add_transaction(&app, &token, &account, "expense", 18_000, "2026-01-05T09:00:00Z").await;
+    let late = add_transaction(&app, &token, &account, "expense", 700, "2026-01-31T20:00:00Z").await;
+    let february = add_transaction(&app, &token, &account, "expense", 1_200, "2026-02-02T09:00:00Z").await;
+
+    let started = app
+        .post(
+            &format!("/accounts/{}/reconciliations", account),
+            &token,
+            json!({ "statementDate": "2026-01-31T00:00:00Z", "statementBalance": 32_000 }),
+        )
+        .await;
+    assert_eq!(started.status, StatusCode::CREATED, "{}", started.body);
+    let data = started.data();
+    assert_eq!(data["status"], "open");
+    assert_eq!(data["clearedBalance"], 0.0);
+    assert_eq!(data["difference"], 32_000.0);
+    assert_eq!(ids(&data["uncleared"]), [salary.as_str(), rent.as_str(), late.as_str()]);
+    let path = format!("/accounts/{}/reconciliations/{}", account, data["id"].as_str().unwrap());
+
+    let again = app
+        .post(&format!("/accounts/{}/reconciliations", account), &token, json!({ "statementDate": "2026-01-31T00:00:00Z", "statementBalance": 0 }))
+        .await;
+    assert_eq!(again.status, StatusCode::CONFLICT);
+    let too_fine = app
+        .post(&format!("/accounts/{}/reconciliations", account), &token, json!({ "statementDate": "2026-01-31T00:00:00Z", "statementBalance": 0.001 }))
+        .await;
+    assert_eq!(too_fine.status, StatusCode::UNPROCESSABLE_ENTITY);
+    assert!(too_fine.body["error"]["fields"]["statementBalance"].is_array(), "{}", too_fine.body);
+
+    let too_late = app.post(&format!("{}/clear", path), &token, json!({ "transactionIds": [salary, february] })).await;
+    assert_eq!(too_late.status, StatusCode::UNPROCESSABLE_ENTITY);
+    let message = too_late.body["error"]["message"].as_str().unwrap();
+    assert!(message.contains(&february) && !message.contains(&salary), "{}", message);
+
+    let cleared = app.post(&format!("{}/clear", path), &token, json!({ "transactionIds": [salary, rent] })).await;
+    assert_eq!(cleared.status, StatusCode::OK, "{}", cleared.body);
+    assert_eq!(cleared.data()["clearedBalance"], 32_000.0);
+    assert_eq!(cleared.data()["difference"], 0.0);
+    assert_eq!(ids(&cleared.data()["uncleared"]), [late.as_str()]);
+
+    let mistaken = app.post(&format!("{}/clear", path), &token, json!({ "transactionIds": [late] })).await;
+    assert_eq!(mistaken.data()["difference"], 700.0);
+    let premature = app.post(&format!("{}/complete", path), &token, json!({})).await;
+    assert_eq!(premature.status, StatusCode::UNPROCESSABLE_ENTITY);
+
+    let undone = app.post(&format!("{}/unclear", path), &token, json!({ "transactionIds": [late] })).await;
+    assert_eq!(undone.data()["clearedTransactionIds"], json!([salary, rent]));
+    let completed = app.post(&format!("{}/complete", path), &token, json!({})).await;
+    assert_eq!(completed.status, StatusCode::OK, "{}", completed.body);
+    assert_eq!(completed.data()["status"], "completed");
+    assert!(completed.data()["completedAt"].is_string());
+    let twice = app.post(&format!("{}/complete", path), &token, json!({})).await;
+    assert_eq!(twice.status, StatusCode::CONFLICT);
+
+    let list = app.get(&format!("/accounts/{}/reconciliations", account), &token).await;
+    assert_eq!(list.data()["total"], 1);
+    let stranger = app.signup("stranger@example.com").await;
+    assert_eq!(app.get(&path, &stranger).await.status, StatusCode::NOT_FOUND);
+}
@@ -0,0 +1,69 @@
+mod common;
+
+use axum::http::StatusCode;
+use serde_json::json;
+
+use common::TestApp;
+
+/// A user in Dhaka (UTC+6) whose weeks start on Sunday, with one expense spent just after
+/// midnight on 1 February there, which is still 31 January in UTC.
+async fn dhaka_user(app: &TestApp) -> String {
+    let token = app.signup("owner@example.com").await;
+    let response = app
+        .put("/api/preferences", &token, json!({ "timezone": "Asia/Dhaka", "firstDayOfWeek": "sunday", "locale": "bn-BD" }))
+        .await;
+    assert_eq!(response.status, StatusCode::OK, "{}", response.body);
+    assert_eq!(response.data()["timezone"], "Asia/Dhaka");
+    assert_eq!(response.data()["firstDayOfWeek"], "sunday");
+
+    let account = app.create_account(&token, "Wallet", "BDT").await;
+    let response = app
+        .post(
+            "/transactions",
+            &token,
+            json!({ "account_id": account, "transaction_type": "expense", "amount": 40, "date": "2024-01-31T18:30:00Z" }),
+        )
+        .await;
+    assert_eq!(response.status, StatusCode::OK, "{}", response.body);
+    token
+}
+
+#[tokio::test]
+async fn monthly_report_follows_the_users_timezone() {
+    let app = TestApp::new().await;
+    let token = dhaka_user(&app).await;
+
+    let january = app.get("/api/reports/monthly?year=2024&month=1", &token).await;
+    assert_eq!(january.data()["totals"].as_array().map(Vec::len), Some(0));
+
+    let february = app.get("/api/reports/monthly?year=2024&month=2", &token).await;
+    assert_eq!(february.status, StatusCode::OK, "{}", february.body);
+    assert_eq!(february.data()["totals"][0]["expenses"], 40.0);
+    assert_eq!(february.data()["largestTransactions"][0]["date"], "2024-02-01T00:30:00+06:00");
+}
+
+#[tokio::test]
+async fn weekly_cashflow_starts_on_the_users_first_day_of_week() {
+    let app = TestApp::new().await;
+    let token = dhaka_user(&app).await;
+
+    let response = app.get("/api/reports/cashflow?granularity=week&from=2024-01-29&to=2024-02-10", &token).await;
+
+    assert_eq!(response.status, StatusCode::OK, "{}", response.body);
+    assert_eq!(response.data()["from"], "2024-01-28");
+    let buckets = &response.data()["series"][0]["buckets"];
+    assert_eq!(buckets[0]["start"], "2024-01-28");
+    assert_eq!(buckets[0]["expenses"], 40.0);
+    assert_eq!(buckets[1]["start"], "2024-02-04");
+}
+
+#[tokio::test]
+async fn unknown_timezone_is_rejected() {
+    let app = TestApp::new().await;
+    let token = app.signup("owner@example.com").await;
+
+    let response = app.put("/api/preferences", &token, json!({ "timezone": "Mars/Olympus_Mons" })).await;
+
+    assert_eq!(response.status, StatusCode::BAD_REQUEST);
+    assert_eq!(app.get("/api/preferences", &token).await.data()["timezone"], "UTC");
+}
@@ -0,0 +1,73 @@
+use std::sync::atomic::{AtomicU32, Ordering};
+use std::sync::Arc;
+use std::time::Duration;
+
+use anyhow::anyhow;
+
+use personal_manager_backend::services::database;
+use personal_manager_backend::services::scheduler::Scheduler;
+use personal_manager_backend::services::shutdown::Coordinator;
+
+async fn migrated_pool() -> database::DbPool {
+    let pool = database::init_memory_db().await.expect("open in-memory database");
+    database::migrate(&pool).await.expect("migrate in-memory database");
+    pool
+}
+
+/// `(status, attempts, processed, error)` of the job's first recorded run.
+async fn first_run(pool: &database::DbPool, job: &str) -> (String, i64, i64, Option<String>) {
+    for _ in 0..100 {
+        let run = sqlx::query_as("SELECT status, attempts, processed, error FROM job_runs WHERE job = ? ORDER BY id LIMIT 1")
+            .bind(job)
+            .fetch_optional(pool)
+            .await
+            .unwrap();
+        if let Some(run) = run {
+            return run;
+        }
+        tokio::time::sleep(Duration::from_millis(20)).await;
+    }
+    panic!("job {} never recorded a run", job);
+}
+
+#[tokio::test]
+async fn failed_run_is_retried() {
+    let pool = migrated_pool().await;
+    let calls = Arc::new(AtomicU32::new(0));
+    let mut scheduler = Scheduler::new(pool.clone()).with_retry_delay(Duration::from_millis(10));
+    let job_calls = calls.clone();
+    scheduler.every("flaky", Duration::from_secs(3600), move |_pool| {
+        let calls = job_calls.clone();
+        async move {
+            if calls.fetch_add(1, Ordering::SeqCst) == 0 {
+                Err(anyhow!("first attempt fails"))
+            } else {
+                Ok(7)
+            }
+        }
+    });
+
+    let coordinator = Coordinator::new();
+    scheduler.start(&coordinator);
+
+    assert_eq!(first_run(&pool, "flaky").await, ("succeeded".to_string(), 2, 7, None));
+    assert!(coordinator.drain(Duration::from_secs(1)).await);
+}
+
+#[tokio::test]
+async fn run_fails_once_retries_are_used_up() {
+    let pool = migrated_pool().await;
+    let mut scheduler = Scheduler::new(pool.clone()).with_retry_delay(Duration::from_millis(10));
+    scheduler
+        .every("broken", Duration::from_secs(3600), |_pool| async { Err(anyhow!("provider is down")) })
+        .retries(1);
+
+    let coordinator = Coordinator::new();
+    scheduler.start(&coordinator);
+
+    let (status, attempts, _, error) = first_run(&pool, "broken").await;
+    assert_eq!(status, "failed");
+    assert_eq!(attempts, 2);
+    assert_eq!(error.as_deref(), Some("provider is down"));
+    assert!(coordinator.drain(Duration::from_secs(1)).await);
+}
@@ -0,0 +1,35 @@
+mod common;
+
+use axum::http::{Method, StatusCode};
+use serde_json::json;
+
+use common::TestApp;
+use personal_manager_backend::services::seed::{self, DEMO_EMAIL, DEMO_PASSWORD};
+
+#[tokio::test]
+async fn demo_user_can_sign_in_to_seeded_data() {
+    let app = TestApp::new().await;
+    assert!(seed::seed_demo(&app.pool).await.unwrap());
+
+    let login = app
+        .request(Method::POST, "/auth/login", None, Some(json!({ "email": DEMO_EMAIL, "password": DEMO_PASSWORD })))
+        .await;
+    assert_eq!(login.status, StatusCode::OK, "{}", login.body);
+    let token = login.data()["token"].as_str().unwrap();
+
+    assert_eq!(app.get("/accounts", token).await.data()["total"], 5);
+    assert!(app.get("/transactions", token).await.data()["total"].as_u64().unwrap() > 0);
+    assert!(app.get("/budgets", token).await.data()["total"].as_u64().unwrap() > 0);
+    assert!(app.get("/savings-goals", token).await.data()["total"].as_u64().unwrap() > 0);
+}
+
+#[tokio::test]
+async fn seeding_twice_leaves_the_demo_user_alone() {
+    let app = TestApp::new().await;
+    assert!(seed::seed_demo(&app.pool).await.unwrap());
+
+    assert!(!seed::seed_demo(&app.pool).await.unwrap());
+
+    let accounts: i64 = sqlx::query_scalar("SELECT COUNT(*) FROM accounts").fetch_one(&app.pool).await.unwrap();
+    assert_eq!(accounts, 5);
+}
@@ -0,0 +1,41 @@
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Arc;
+use std::time::Duration;
+
+use personal_manager_backend::services::shutdown::Coordinator;
+
+#[tokio::test]
+async fn drain_waits_for_jobs_to_finish_their_run() {
+    let coordinator = Coordinator::new();
+    let runs = Arc::new(AtomicUsize::new(0));
+
+    let mut shutdown = coordinator.handle();
+    let job_runs = runs.clone();
+    tokio::spawn(async move {
+        let mut interval = tokio::time::interval(Duration::from_millis(10));
+        while shutdown.tick(&mut interval).await {
+            tokio::time::sleep(Duration::from_millis(50)).await;
+            job_runs.fetch_add(1, Ordering::SeqCst);
+        }
+    });
+    tokio::time::sleep(Duration::from_millis(20)).await;
+
+    assert!(coordinator.drain(Duration::from_secs(5)).await);
+    let finished = runs.load(Ordering::SeqCst);
+    assert!(finished >= 1);
+
+    tokio::time::sleep(Duration::from_millis(100)).await;
+    assert_eq!(runs.load(Ordering::SeqCst), finished, "job ran again after shutdown");
+}
+
+#[tokio::test]
+async fn drain_gives_up_on_a_stuck_job() {
+    let coordinator = Coordinator::new();
+    let shutdown = coordinator.handle();
+    tokio::spawn(async move {
+        let _shutdown = shutdown;
+        tokio::time::sleep(Duration::from_secs(60)).await;
+    });
+
+    assert!(!coordinator.drain(Duration::from_millis(50)).await);
+}
@@ -0,0 +1,67 @@
+use std::io::Write;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+use personal_manager_backend::services::database::{self, DbPool};
+
+/// Log output collected for the test's assertions.
+#[derive(Clone, Default)]
+struct Captured(Arc<Mutex<Vec<u8>>>);
+
+impl Write for Captured {
+    fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+        self.0.lock().unwrap().extend_from_slice(buf);
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> std::io::Result<()> {
+        Ok(())
+    }
+}
+
+impl Captured {
+    /// Sends this thread's log output here until the guard is dropped.
+    fn install(&self) -> tracing::subscriber::DefaultGuard {
+        let writer = self.clone();
+        let subscriber = tracing_subscriber::fmt().with_ansi(false).with_writer(move || writer.clone()).finish();
+        tracing::subscriber::set_default(subscriber)
+    }
+
+    fn text(&self) -> String {
+        String::from_utf8(self.0.lock().unwrap().clone()).unwrap()
+    }
+}
+
+async fn pool_with_threshold(threshold: Duration) -> DbPool {
+    let pool = database::init_memory_db().await.expect("open in-memory database");
+    DbPool::new((*pool).clone(), threshold)
+}
+
+#[tokio::test]
+async fn queries_over_the_threshold_are_logged() {
+    let logs = Captured::default();
+    let _guard = logs.install();
+
+    let pool = pool_with_threshold(Duration::from_nanos(1)).await;
+    let rows: Vec<i64> = sqlx::query_scalar("SELECT 1 UNION ALL SELECT 2").fetch_all(&pool).await.unwrap();
+    assert_eq!(rows, vec![1, 2]);
+    let one: i64 = sqlx::query_scalar("SELECT 42\n    AS answer").fetch_one(&pool).await.unwrap();
+    assert_eq!(one, 42);
+
+    let text = logs.text();
+    assert!(text.contains("Slow query") && text.contains("SELECT 1 UNION ALL SELECT 2"), "{}", text);
+    assert!(text.contains("SELECT 42 AS answer"), "{}", text);
+}
+
+#[tokio::test]
+async fn fast_queries_and_disabled_logging_stay_quiet() {
+    let logs = Captured::default();
+    let _guard = logs.install();
+
+    let fast = pool_with_threshold(Duration::from_secs(60)).await;
+    sqlx::query("SELECT 1").execute(&fast).await.unwrap();
+    let disabled = pool_with_threshold(Duration::ZERO).await;
+    sqlx::query("SELECT 1").execute(&disabled).await.unwrap();
+
+    assert!(!logs.text().contains("Slow query"), "{}", logs.text());
+}
@@ -0,0 +1,224 @@
+mod common;
+
+use axum::http::StatusCode;
+use serde_json::{json, Value};
+
+use personal_manager_backend::services::splits;
+use personal_manager_backend::utils::money::Money;
+
+use common::TestApp;
+
+/// Puts everyone in a household the owner creates.
+async fn household(app: &TestApp, owner: &str, members: &[(&str, &str)]) {
+    let created = app.post("/api/households", owner, json!({ "name": "Flat" })).await;
+    assert_eq!(created.status, StatusCode::OK, "{}", created.body);
+    let id = created.data()["id"].as_str().unwrap().to_string();
+    for (token, email) in members {
+        let invited = app.post(&format!("/api/households/{}/invites", id), owner, json!({ "email": email })).await;
+        assert_eq!(invited.status, StatusCode::OK, "{}", invited.body);
+        let invite: String = sqlx::query_scalar("SELECT token FROM household_invites WHERE household_id = ? AND email = ?")
+            .bind(&id)
+            .bind(email)
+            .fetch_one(&app.pool)
+            .await
+            .unwrap();
+        let accepted = app.post("/api/household-invites/accept", token, json!({ "token": invite })).await;
+        assert_eq!(accepted.status, StatusCode::OK, "{}", accepted.body);
+    }
+}
+
+/// The user's balance with each friend, by email.
+async fn balances(app: &TestApp, token: &str) -> Vec<(String, f64)> {
+    let response = app.get("/api/splits/balances", token).await;
+    assert_eq!(response.status, StatusCode::OK, "{}", response.body);
+    let mut balances: Vec<_> = response
+        .data()
+        .as_array()
+        .unwrap()
+        .iter()
+        .map(|balance| (balance["email"].as_str().unwrap().to_string(), balance["balance"].as_f64().unwrap()))
+        .collect();
+    balances.sort_by(|a, b| a.0.cmp(&b.0));
+    balances
+}
+
+fn owed(email: &str, amount: f64) -> (String, f64) {
+    (email.to_string(), amount)
+}
+
+#[tokio::test]
+async fn split_shares_must_add_up_to_the_total() {
+    let app = TestApp::new().await;
+    let owner = app.signup("owner@example.com").await;
+    let friend = app.signup("friend@example.com").await;
+    household(&app, &owner, &[(&friend, "friend@example.com")]).await;
+
+    let short = app
+        .post(
+            "/api/splits",
+            &owner,
+            json!({
+                "description": "Dinner",
+                "amount": 1000,
+                "participants": [{ "user": "owner@example.com", "amount": 600 }, { "user": "friend@example.com", "amount": 300 }]
+            }),
+        )
+        .await;
+    assert_eq!(short.status, StatusCode::BAD_REQUEST, "{}", short.body);
+
+    let partial = app
+        .post(
+            "/api/splits",
+            &owner,
+            json!({
+                "description": "Dinner",
+                "amount": 1000,
+                "participants": [{ "user": "owner@example.com", "amount": 1000 }, { "user": "friend@example.com" }]
+            }),
+        )
+        .await;
+    assert_eq!(partial.status, StatusCode::UNPROCESSABLE_ENTITY);
+
+    assert_eq!(app.get("/api/splits", &owner).await.data()["total"], 0);
+    assert!(balances(&app, &owner).await.is_empty());
+}
+
+#[tokio::test]
+async fn each_split_and_settlement_moves_the_friend_balances() {
+    let app = TestApp::new().await;
+    let owner = app.signup("owner@example.com").await;
+    let anika = app.signup("anika@example.com").await;
+    let babul = app.signup("babul@example.com").await;
+    household(&app, &owner, &[(&anika, "anika@example.com"), (&babul, "babul@example.com")]).await;
+    let wallet = app.create_account(&owner, "Wallet", "BDT").await;
+
+    // Paid by the owner, split evenly three ways
+    let groceries = app
+        .post(
+            "/api/splits",
+            &owner,
+            json!({
+                "description": "Groceries",
+                "amount": 900,
+                "accountId": wallet,
+                "participants": [{ "user": "owner@example.com" }, { "user": "anika@example.com" }, { "user": "babul@example.com" }]
+            }),
+        )
+        .await;
+    assert_eq!(groceries.status, StatusCode::OK, "{}", groceries.body);
+    let shares: Vec<f64> = groceries.data()["shares"].as_array().unwrap().iter().map(|s| s["amount"].as_f64().unwrap()).collect();
+    assert_eq!(shares, [300.0, 300.0, 300.0]);
+    assert_eq!(balances(&app, &owner).await, [owed("anika@example.com", 300.0), owed("babul@example.com", 300.0)]);
+    assert_eq!(balances(&app, &anika).await, [owed("owner@example.com", -300.0)]);
+
+    // The payer's expense covers the whole bill
+    let transactions = app.get("/transactions", &owner).await;
+    let expense: &Value = &transactions.data()["items"][0];
+    assert_eq!(expense["amount"], 900.0);
+    assert_eq!(expense["category"], "Split");
+    assert_eq!(expense["id"], groceries.data()["transactionId"]);
+    assert_eq!(expense["baseAmount"], 900.0);
+    assert_eq!(expense["baseCurrency"], "BDT");
+
+    // Paid by Anika with uneven shares: only the owner's share comes off what Anika owes
+    let taxi = app
+        .post(
+            "/api/splits",
+            &anika,
+            json!({
+                "description": "Taxi",
+                "amount": 500,
+                "participants": [{ "user": "anika@example.com", "amount": 300 }, { "user": "owner@example.com", "amount": 200 }]
+            }),
+        )
+        .await;
+    assert_eq!(taxi.status, StatusCode::OK, "{}", taxi.body);
+    assert_eq!(balances(&app, &owner).await, [owed("anika@example.com", 100.0), owed("babul@example.com", 300.0)]);
+
+    let settled = app
+        .post("/api/splits/settlements", &anika, json!({ "friend": "owner@example.com", "direction": "paid", "amount": 100 }))
+        .await;
+    assert_eq!(settled.status, StatusCode::OK, "{}", settled.body);
+    assert_eq!(balances(&app, &owner).await, [owed("babul@example.com", 300.0)]);
+    assert!(balances(&app, &anika).await.is_empty());
+
+    // Deleting a split takes back only its own shares
+    let taxi_id = taxi.data()["id"].as_str().unwrap();
+    let deleted = app.delete(&format!("/api/splits/{}", taxi_id), &anika).await;
+    assert_eq!(deleted.status, StatusCode::OK, "{}", deleted.body);
+    assert_eq!(balances(&app, &owner).await, [owed("anika@example.com", 200.0), owed("babul@example.com", 300.0)]);
+}
+
+#[tokio::test]
+async fn split_lists_are_paged_with_their_shares() {
+    let app = TestApp::new().await;
+    let owner = app.signup("owner@example.com").await;
+    let friend = app.signup("friend@example.com").await;
+    household(&app, &owner, &[(&friend, "friend@example.com")]).await;
+    for amount in [100, 200, 300] {
+        let split = app
+            .post(
+                "/api/splits",
+                &owner,
+                json!({
+                    "description": "Lunch",
+                    "amount": amount,
+                    "participants": [{ "user": "owner@example.com" }, { "user": "friend@example.com" }]
+                }),
+            )
+            .await;
+        assert_eq!(split.status, StatusCode::OK, "{}", split.body);
+    }
+
+    let first = app.get("/api/splits?limit=2", &owner).await;
+    assert_eq!(first.status, StatusCode::OK, "{}", first.body);
+    assert_eq!(first.data()["total"], 3);
+    assert_eq!(first.data()["hasMore"], true);
+    for split in first.data()["items"].as_array().unwrap() {
+        let shares = split["shares"].as_array().unwrap();
+        assert_eq!(shares.len(), 2, "{}", split);
+        assert_eq!(shares[0]["amount"].as_f64().unwrap() * 2.0, split["amount"].as_f64().unwrap());
+    }
+
+    let cursor = first.data()["nextCursor"].as_str().unwrap();
+    let rest = app.get(&format!("/api/splits?limit=2&cursor={}", cursor), &owner).await;
+    assert_eq!(rest.data()["items"].as_array().unwrap().len(), 1);
+    assert_eq!(rest.data()["items"][0]["shares"].as_array().unwrap().len(), 2);
+    assert_eq!(rest.data()["hasMore"], false);
+}
+
+#[tokio::test]
+async fn splits_only_name_household_members() {
+    let app = TestApp::new().await;
+    let owner = app.signup("owner@example.com").await;
+    app.signup("stranger@example.com").await;
+
+    let body = |email: &str| {
+        json!({
+            "description": "Dinner",
+            "amount": 1000,
+            "participants": [{ "user": "owner@example.com" }, { "user": email }]
+        })
+    };
+    let stranger = app.post("/api/splits", &owner, body("stranger@example.com")).await;
+    let unknown = app.post("/api/splits", &owner, body("nobody@example.com")).await;
+    assert_eq!(stranger.status, StatusCode::NOT_FOUND, "{}", stranger.body);
+    assert_eq!(unknown.status, StatusCode::NOT_FOUND, "{}", unknown.body);
+    assert_eq!(stranger.body, unknown.body);
+
+    let settled = app
+        .post("/api/splits/settlements", &owner, json!({ "friend": "stranger@example.com", "direction": "paid", "amount": 100 }))
+        .await;
+    assert_eq!(settled.status, StatusCode::NOT_FOUND, "{}", settled.body);
+}
+
+#[test]
+fn even_shares_hand_out_the_leftover_minor_units() {
+    let shares = splits::even_shares("0.05".parse().unwrap(), 7, "USD");
+    let cents: Vec<f64> = shares.iter().map(|share| share.to_f64()).collect();
+    assert_eq!(cents, [0.01, 0.01, 0.01, 0.01, 0.01, 0.0, 0.0]);
+    assert_eq!(shares.iter().sum::<Money>(), "0.05".parse().unwrap());
+
+    let shares = splits::even_shares(Money::from(100), 3, "BDT");
+    assert_eq!(shares.iter().map(|share| share.to_f64()).collect::<Vec<_>>(), [33.34, 33.33, 33.33]);
+}
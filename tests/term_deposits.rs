@@ -0,0 +1,103 @@
+mod common;
+
+use axum::http::StatusCode;
+use chrono::{Duration, Months, Utc};
+use serde_json::{json, Value};
+
+use personal_manager_backend::services::reminders;
+
+use common::TestApp;
+
+async fn create_deposit(app: &TestApp, token: &str, body: Value) -> Value {
+    let response = app.post("/api/term-deposits", token, body).await;
+    assert_eq!(response.status, StatusCode::OK, "{}", response.body);
+    response.data().clone()
+}
+
+#[tokio::test]
+async fn matured_deposits_have_earned_all_their_interest() {
+    let app = TestApp::new().await;
+    let token = app.signup("saver@example.com").await;
+
+    let simple = create_deposit(
+        &app,
+        &token,
+        json!({ "name": "FDR", "principal": 100_000, "annualRate": 12.0, "tenureMonths": 12, "compounding": "simple", "startDate": "2024-01-01T00:00:00Z" }),
+    )
+    .await;
+    assert_eq!(simple["maturityDate"], "2025-01-01T00:00:00Z");
+    assert_eq!(simple["maturityValue"], 112_000.0);
+    assert_eq!(simple["totalInterest"], 12_000.0);
+    assert_eq!(simple["accruedInterest"], 12_000.0);
+    assert_eq!(simple["currentValue"], 112_000.0);
+    assert_eq!(simple["daysToMaturity"], 0);
+
+    let compounded = create_deposit(
+        &app,
+        &token,
+        json!({ "name": "FDR yearly", "principal": 100_000, "annualRate": 10.0, "tenureMonths": 24, "compounding": "yearly", "startDate": "2023-03-15T00:00:00Z" }),
+    )
+    .await;
+    assert_eq!(compounded["maturityDate"], "2025-03-15T00:00:00Z");
+    assert_eq!(compounded["maturityValue"], 121_000.0);
+    assert_eq!(compounded["totalInterest"], 21_000.0);
+
+    // Each monthly installment earns simple interest for the months it is held
+    let recurring = create_deposit(
+        &app,
+        &token,
+        json!({ "name": "DPS", "depositType": "recurring", "principal": 1_000, "annualRate": 12.0, "tenureMonths": 12, "compounding": "simple", "startDate": "2024-01-01T00:00:00Z" }),
+    )
+    .await;
+    assert_eq!(recurring["amountDeposited"], 12_000.0);
+    assert_eq!(recurring["maturityValue"], 12_780.0);
+    assert_eq!(recurring["totalInterest"], 780.0);
+}
+
+#[tokio::test]
+async fn open_deposits_accrue_part_of_their_interest() {
+    let app = TestApp::new().await;
+    let token = app.signup("saver@example.com").await;
+    let start = Utc::now() - Months::new(6);
+
+    let deposit = create_deposit(
+        &app,
+        &token,
+        json!({ "name": "FDR", "principal": 100_000, "annualRate": 12.0, "tenureMonths": 12, "compounding": "simple", "startDate": start }),
+    )
+    .await;
+    assert_eq!(deposit["maturityValue"], 112_000.0);
+    let accrued = deposit["accruedInterest"].as_f64().unwrap();
+    assert!((5_900.0..=6_100.0).contains(&accrued), "accrued {}", accrued);
+    assert!(deposit["daysToMaturity"].as_i64().unwrap() > 170);
+}
+
+#[tokio::test]
+async fn maturing_deposits_are_announced_once() {
+    let app = TestApp::new().await;
+    let token = app.signup("saver@example.com").await;
+    let start = Utc::now() - Months::new(12) + Duration::days(3);
+    let deposit = create_deposit(
+        &app,
+        &token,
+        json!({ "name": "FDR", "principal": 100_000, "annualRate": 12.0, "tenureMonths": 12, "compounding": "simple", "startDate": start }),
+    )
+    .await;
+    create_deposit(
+        &app,
+        &token,
+        json!({ "name": "Later FDR", "principal": 50_000, "annualRate": 9.0, "tenureMonths": 12, "compounding": "simple" }),
+    )
+    .await;
+
+    reminders::remind_maturing_deposits(&app.pool).await.unwrap();
+    reminders::remind_maturing_deposits(&app.pool).await.unwrap();
+
+    let notifications = app.get("/api/notifications", &token).await;
+    assert_eq!(notifications.status, StatusCode::OK, "{}", notifications.body);
+    let items = notifications.data()["items"].as_array().unwrap();
+    assert_eq!(items.len(), 1, "{:?}", items);
+    assert_eq!(items[0]["kind"], "deposit_maturity");
+    assert_eq!(items[0]["entityId"], deposit["id"]);
+    assert!(items[0]["body"].as_str().unwrap().contains("112000.00 BDT"), "{}", items[0]["body"]);
+}
@@ -0,0 +1,71 @@
+mod common;
+
+use axum::http::StatusCode;
+use serde_json::json;
+
+use common::TestApp;
+
+#[tokio::test]
+async fn created_transaction_is_listed() {
+    let app = TestApp::new().await;
+    let token = app.signup("owner@example.com").await;
+    let account = app.create_account(&token, "Wallet", "BDT").await;
+
+    let response = app
+        .post(
+            "/transactions",
+            &token,
+            json!({ "account_id": account, "transaction_type": "expense", "amount": 12.5, "category": "Food" }),
+        )
+        .await;
+    assert_eq!(response.status, StatusCode::OK, "{}", response.body);
+
+    let list = app.get("/transactions", &token).await;
+    assert_eq!(list.data()["total"], 1);
+    assert_eq!(list.data()["items"][0]["amount"], 12.5);
+    assert_eq!(list.data()["items"][0]["accountId"], account.as_str());
+}
+
+#[tokio::test]
+async fn transactions_cannot_target_another_users_account() {
+    let app = TestApp::new().await;
+    let owner = app.signup("owner@example.com").await;
+    let other = app.signup("other@example.com").await;
+    let account = app.create_account(&owner, "Wallet", "BDT").await;
+
+    let response = app
+        .post("/transactions", &other, json!({ "account_id": account, "transaction_type": "expense", "amount": 5 }))
+        .await;
+
+    assert!(response.status.is_client_error(), "{}: {}", response.status, response.body);
+    assert_eq!(app.get("/transactions", &owner).await.data()["total"], 0);
+    assert_eq!(app.get("/transactions", &other).await.data()["total"], 0);
+}
+
+#[tokio::test]
+async fn amounts_finer_than_the_currency_are_field_errors() {
+    let app = TestApp::new().await;
+    let token = app.signup("owner@example.com").await;
+    let account = app.create_account(&token, "Wallet", "BDT").await;
+
+    let yen = app
+        .post(
+            "/transactions",
+            &token,
+            json!({ "account_id": account, "transaction_type": "expense", "amount": 10.5, "currency": "JPY" }),
+        )
+        .await;
+    assert_eq!(yen.status, StatusCode::UNPROCESSABLE_ENTITY, "{}", yen.body);
+    assert!(yen.body["error"]["fields"]["amount"].is_array(), "{}", yen.body);
+
+    let created = app
+        .post("/transactions", &token, json!({ "account_id": account, "transaction_type": "expense", "amount": 10 }))
+        .await;
+    assert_eq!(created.status, StatusCode::OK, "{}", created.body);
+    let id = created.data()["id"].as_str().unwrap();
+
+    // Checked against the stored currency when only the amount changes
+    let updated = app.put(&format!("/transactions/{}", id), &token, json!({ "amount": 10.001, "version": 1 })).await;
+    assert_eq!(updated.status, StatusCode::UNPROCESSABLE_ENTITY, "{}", updated.body);
+    assert!(updated.body["error"]["fields"]["amount"].is_array(), "{}", updated.body);
+}
@@ -0,0 +1,28 @@
+mod common;
+
+use axum::http::StatusCode;
+
+use personal_manager_backend::services::usage;
+
+use common::TestApp;
+
+#[tokio::test]
+async fn requests_are_counted_in_memory_until_flushed() {
+    let app = TestApp::new().await;
+    let token = app.signup("owner@example.com").await;
+    app.get("/accounts", &token).await;
+    app.get("/accounts", &token).await;
+
+    let count: i64 = sqlx::query_scalar("SELECT COUNT(*) FROM api_usage").fetch_one(&app.pool).await.unwrap();
+    assert_eq!(count, 0);
+
+    assert_eq!(usage::flush(&app.pool).await.unwrap(), 2);
+    assert_eq!(usage::flush(&app.pool).await.unwrap(), 0);
+
+    let response = app.get("/api/me/usage", &token).await;
+    assert_eq!(response.status, StatusCode::OK, "{}", response.body);
+    let by_endpoint = response.data()["byEndpoint"].as_array().unwrap();
+    assert_eq!(by_endpoint.len(), 1, "{}", response.body);
+    assert_eq!(by_endpoint[0]["route"], "/accounts");
+    assert_eq!(by_endpoint[0]["requests"], 2);
+}
@@ -0,0 +1,63 @@
+mod common;
+
+use axum::http::StatusCode;
+use serde_json::json;
+
+use personal_manager_backend::services::webhooks;
+
+use common::TestApp;
+
+#[tokio::test]
+async fn webhooks_must_point_at_public_addresses() {
+    let app = TestApp::new().await;
+    let token = app.signup("owner@example.com").await;
+
+    for url in [
+        "http://127.0.0.1/hook",
+        "http://localhost:8080/hook",
+        "http://10.0.0.5/hook",
+        "http://192.168.1.1/hook",
+        "http://169.254.169.254/latest/meta-data",
+        "http://[::1]/hook",
+        "http://[::ffff:127.0.0.1]/hook",
+    ] {
+        let response = app.post("/api/webhooks", &token, json!({ "url": url })).await;
+        assert_eq!(response.status, StatusCode::UNPROCESSABLE_ENTITY, "{}: {}", url, response.body);
+    }
+
+    let created = app.post("/api/webhooks", &token, json!({ "url": "https://93.184.216.34/hook" })).await;
+    assert_eq!(created.status, StatusCode::OK, "{}", created.body);
+    let id = created.data()["id"].as_str().unwrap();
+
+    let moved = app.put(&format!("/api/webhooks/{}", id), &token, json!({ "url": "http://127.0.0.1/hook" })).await;
+    assert_eq!(moved.status, StatusCode::UNPROCESSABLE_ENTITY, "{}", moved.body);
+}
+
+#[tokio::test]
+async fn deliveries_recheck_the_address_before_sending() {
+    let app = TestApp::new().await;
+    let token = app.signup("owner@example.com").await;
+    let created = app.post("/api/webhooks", &token, json!({ "url": "https://93.184.216.34/hook" })).await;
+    assert_eq!(created.status, StatusCode::OK, "{}", created.body);
+    let id = created.data()["id"].as_str().unwrap();
+
+    // As if the host had been repointed after the webhook was saved
+    sqlx::query("UPDATE webhooks SET url = 'http://127.0.0.1:9/hook' WHERE id = ?")
+        .bind(id)
+        .execute(&app.pool)
+        .await
+        .unwrap();
+    let account = app.create_account(&token, "Wallet", "BDT").await;
+    let transaction = app
+        .post("/transactions", &token, json!({ "account_id": account, "transaction_type": "expense", "amount": 100 }))
+        .await;
+    assert_eq!(transaction.status, StatusCode::OK, "{}", transaction.body);
+
+    let delivered = webhooks::deliver_pending(&app.pool, &reqwest::Client::new()).await.unwrap();
+    assert_eq!(delivered, 0);
+    let deliveries = app.get(&format!("/api/webhooks/{}/deliveries", id), &token).await;
+    let items = deliveries.data()["items"].as_array().unwrap();
+    assert_eq!(items.len(), 1, "{}", deliveries.body);
+    assert_eq!(items[0]["attempts"], 1);
+    assert!(items[0]["lastError"].as_str().unwrap().contains("private"), "{}", items[0]);
+}